@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use serde_json::Value;
+
+// Below this share of declared entry points found in the selected contract,
+// we consider that the wrong contract was most likely picked.
+const MIN_MATCHING_RATIO: f64 = 0.5;
+const FUNCTION_PATTERN: &str = r"\bfn\s+([A-Za-z_][A-Za-z0-9_]*)";
+
+#[derive(Debug)]
+pub struct AbiComparison {
+    pub declared: BTreeSet<String>,
+    pub local: BTreeSet<String>,
+    pub matching: BTreeSet<String>,
+}
+
+impl AbiComparison {
+    pub fn new(declared: BTreeSet<String>, local: BTreeSet<String>) -> Self {
+        let matching = declared.intersection(&local).cloned().collect();
+        Self {
+            declared,
+            local,
+            matching,
+        }
+    }
+
+    pub fn missing(&self) -> BTreeSet<String> {
+        self.declared.difference(&self.local).cloned().collect()
+    }
+
+    /**
+     * Entry point selectors are derived from the function names, so comparing
+     * the names is equivalent to comparing the selectors.
+     */
+    pub fn is_likely_wrong_contract(&self) -> bool {
+        if self.declared.is_empty() {
+            return false;
+        }
+        (self.matching.len() as f64 / self.declared.len() as f64) < MIN_MATCHING_RATIO
+    }
+}
+
+/**
+ * Extract the external entry point names out of a declared class ABI.
+ * The ABI can either be the JSON array itself, or the JSON string
+ * representation of it as returned by the RPC.
+ */
+pub fn declared_entry_points(abi: &Value) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let items = match abi {
+        Value::String(raw) => match serde_json::from_str::<Value>(raw) {
+            Ok(parsed) => return declared_entry_points(&parsed),
+            Err(_) => return names,
+        },
+        Value::Array(items) => items,
+        _ => return names,
+    };
+
+    for item in items {
+        match item.get("type").and_then(Value::as_str) {
+            Some("function") | Some("l1_handler") => {
+                if let Some(name) = item.get("name").and_then(Value::as_str) {
+                    names.insert(name.to_owned());
+                }
+            }
+            Some("interface") => {
+                names.extend(declared_entry_points(
+                    item.get("items").unwrap_or(&Value::Null),
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    names
+}
+
+/**
+ * Best-effort extraction of the function names defined in a Cairo source file.
+ * This does not try to understand the module structure, it only needs to be
+ * good enough to tell two unrelated contracts apart.
+ */
+pub fn local_function_names(source: &str) -> BTreeSet<String> {
+    let re = Regex::new(FUNCTION_PATTERN).unwrap();
+
+    source
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .flat_map(|line| {
+            re.captures_iter(line)
+                .map(|c| c[1].to_owned())
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+pub fn compare_abi_with_source(abi: &Value, source: &str) -> AbiComparison {
+    AbiComparison::new(declared_entry_points(abi), local_function_names(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERC20_ABI: &str = include_str!("../tests/test_data/abi_check/erc20_abi.json");
+    const ERC20_SOURCE: &str = include_str!("../tests/test_data/abi_check/erc20.cairo");
+    const COUNTER_SOURCE: &str = include_str!("../tests/test_data/abi_check/counter.cairo");
+
+    #[test]
+    fn test_declared_entry_points_from_interface() {
+        let abi: Value = serde_json::from_str(ERC20_ABI).unwrap();
+        let names = declared_entry_points(&abi);
+
+        assert_eq!(
+            names.into_iter().collect::<Vec<String>>(),
+            vec!["balance_of", "get_name", "get_symbol", "transfer"]
+        );
+    }
+
+    #[test]
+    fn test_declared_entry_points_from_string_abi() {
+        let abi = Value::String(ERC20_ABI.to_owned());
+        assert_eq!(declared_entry_points(&abi).len(), 4);
+    }
+
+    #[test]
+    fn test_correct_contract_matches() {
+        let abi: Value = serde_json::from_str(ERC20_ABI).unwrap();
+        let comparison = compare_abi_with_source(&abi, ERC20_SOURCE);
+
+        assert!(!comparison.is_likely_wrong_contract());
+        assert!(comparison.missing().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_contract_is_detected() {
+        let abi: Value = serde_json::from_str(ERC20_ABI).unwrap();
+        let comparison = compare_abi_with_source(&abi, COUNTER_SOURCE);
+
+        assert!(comparison.is_likely_wrong_contract());
+        // Commented out functions must not count as a match.
+        assert!(!comparison.matching.contains("transfer"));
+    }
+
+    #[test]
+    fn test_empty_abi_is_never_flagged() {
+        let comparison = compare_abi_with_source(&Value::Null, COUNTER_SOURCE);
+        assert!(!comparison.is_likely_wrong_contract());
+    }
+}
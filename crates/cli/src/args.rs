@@ -0,0 +1,170 @@
+use std::{env, time::Duration};
+
+use clap::Args;
+use url::Url;
+
+use crate::api::{check_endpoints, parse_endpoint, CustomEndpoints, Network, API_KEY_VAR};
+use crate::http_retry::{self, OnMaintenance};
+use crate::ip_version::{set_ip_version, IpVersion};
+use crate::network::{is_network_url, resolve_network_url};
+use crate::polling::{parse_poll_interval, parse_seconds, PollingConfig};
+use crate::transfer;
+
+/**
+ * Flags selecting the network, for the commands that talk to one.
+ */
+#[derive(Args, Debug)]
+pub struct NetworkArgs {
+    #[arg(
+        long,
+        help = "Network name, or base url of a self-hosted instance serving both apis"
+    )]
+    pub network: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        value_parser = parse_endpoint,
+        help = "Internal API of the custom network [env: CUSTOM_INTERNAL_API_ENDPOINT_URL]"
+    )]
+    pub api_url: Option<Url>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        value_parser = parse_endpoint,
+        help = "Public API of the custom network [env: CUSTOM_PUBLIC_API_ENDPOINT_URL]"
+    )]
+    pub public_api_url: Option<Url>,
+}
+
+impl NetworkArgs {
+    pub fn name(&self) -> Option<&str> {
+        self.network.as_deref()
+    }
+
+    pub fn custom(&self) -> CustomEndpoints {
+        CustomEndpoints {
+            internal: self.api_url.clone(),
+            public: self.public_api_url.clone(),
+        }
+    }
+
+    /**
+     * Network of `--network` for the subcommands, mainnet by default. A url
+     * configures the custom network without prompting, and so do the custom
+     * endpoints when no network is given.
+     */
+    pub fn select(&self) -> anyhow::Result<Network> {
+        let custom = self.custom();
+        let network = match self.name() {
+            Some(network) if is_network_url(network) => {
+                let endpoints = resolve_network_url(network, false)?;
+                Network::Custom {
+                    internal: endpoints.internal,
+                    public: endpoints.public,
+                }
+            }
+            Some(network) => custom.select(network)?,
+            None if custom.is_given() => custom.network()?,
+            None => Network::Mainnet,
+        };
+        check_endpoints(&network)?;
+        Ok(network)
+    }
+}
+
+/**
+ * Flag of the commands sending requests that need a key on some instances.
+ */
+#[derive(Args, Debug)]
+pub struct ApiKeyArgs {
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "API key sent to instances that require one [env: VOYAGER_API_KEY]"
+    )]
+    pub api_key: Option<String>,
+}
+
+impl ApiKeyArgs {
+    /**
+     * The key of `--api-key`, or else of the environment. Read once the .env
+     * file of the project and the user config are loaded, which can both set
+     * it.
+     */
+    pub fn resolve(&self) -> String {
+        self.api_key
+            .clone()
+            .or_else(|| env::var(API_KEY_VAR).ok())
+            .unwrap_or_default()
+    }
+}
+
+/**
+ * Flags of the connections to the network.
+ */
+#[derive(Args, Debug)]
+pub struct HttpArgs {
+    #[arg(
+        long,
+        value_enum,
+        help = "When the backend is down for maintenance, wait for its announced end or fail right away. Defaults to fail in CI and wait otherwise"
+    )]
+    pub on_maintenance: Option<OnMaintenance>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = IpVersion::Auto,
+        help = "Address family to connect over. auto tries the other one when the first is slow to connect"
+    )]
+    pub ip_version: IpVersion,
+
+    #[arg(
+        long,
+        help = "Print each HTTP response with the address and family it came from on stderr"
+    )]
+    pub debug_http: bool,
+}
+
+impl HttpArgs {
+    /**
+     * Apply the flags to every request of the run.
+     */
+    pub fn configure(&self) {
+        if let Some(on_maintenance) = self.on_maintenance {
+            http_retry::set_on_maintenance(on_maintenance);
+        }
+        set_ip_version(self.ip_version);
+        transfer::global().set_debug(self.debug_http);
+    }
+}
+
+/**
+ * Flags of the commands following a job until it finishes.
+ */
+#[derive(Args, Debug)]
+pub struct PollingArgs {
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = parse_poll_interval,
+        help = "Seconds between two status checks of the job, unless the backend supports long polling [default: 5]"
+    )]
+    pub poll_interval: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = parse_seconds,
+        help = "Give up polling the job after this many seconds, not counting system suspends. Polls until the job finishes by default"
+    )]
+    pub poll_timeout: Option<Duration>,
+}
+
+impl PollingArgs {
+    pub fn config(&self) -> PollingConfig {
+        PollingConfig::from_flags(self.poll_interval, self.poll_timeout)
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+};
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::corrections::CorrectionRequest;
+
+/// Requests made to the operators of a backend on behalf of the user, one
+/// JSON object per line, in the state directory.
+pub const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/**
+ * An entry of the audit log, tagged with what was requested.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEntry {
+    CorrectionRequest(CorrectionAudit),
+}
+
+/**
+ * A correction request as sent, with the id the backend acknowledged it with,
+ * or why it wasn't.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorrectionAudit {
+    /// Unix time the request was sent.
+    pub at: u64,
+    pub network: String,
+    #[serde(flatten)]
+    pub request: CorrectionRequest,
+    pub request_id: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn audit_log_path(state_dir: &Utf8Path) -> Utf8PathBuf {
+    state_dir.join(AUDIT_LOG_FILE)
+}
+
+/**
+ * Append `entry` to the audit log of the user, shared by every project and
+ * never rotated.
+ */
+pub fn append_audit(state_dir: &Utf8Path, entry: &AuditEntry) -> Result<()> {
+    fs::create_dir_all(state_dir)?;
+    let path = audit_log_path(state_dir);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Unable to open the audit log {}", path))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+        .with_context(|| format!("Unable to write to the audit log {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corrections::CorrectionField;
+
+    #[test]
+    fn test_corrections_are_appended_to_the_audit_log() {
+        let state_dir = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-audit-{}", std::process::id())),
+        )
+        .unwrap();
+        let _ = fs::remove_dir_all(&state_dir);
+        let entry = |request_id: Option<&str>, error: Option<&str>| {
+            AuditEntry::CorrectionRequest(CorrectionAudit {
+                at: 1_700_000_000,
+                network: "mainnet".to_owned(),
+                request: CorrectionRequest {
+                    class_hash: "0x1".to_owned(),
+                    field: CorrectionField::Name,
+                    proposed_value: "MyToken".to_owned(),
+                    justification: "Verified under the name of another project".to_owned(),
+                    contact: "security@example.com".to_owned(),
+                },
+                request_id: request_id.map(str::to_owned),
+                error: error.map(str::to_owned),
+            })
+        };
+        let rejected = entry(None, Some("VER-057: already under review"));
+        let acknowledged = entry(Some("corr-42"), None);
+
+        append_audit(&state_dir, &rejected).unwrap();
+        append_audit(&state_dir, &acknowledged).unwrap();
+
+        let log = fs::read_to_string(audit_log_path(&state_dir)).unwrap();
+        let lines: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["event"], "correction_request");
+        assert_eq!(lines[1]["class_hash"], "0x1");
+        assert_eq!(lines[1]["request_id"], "corr-42");
+        assert_eq!(
+            serde_json::from_value::<AuditEntry>(lines[0].clone()).unwrap(),
+            rejected
+        );
+
+        fs::remove_dir_all(&state_dir).unwrap();
+    }
+}
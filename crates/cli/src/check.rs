@@ -0,0 +1,68 @@
+use clap::Args;
+
+use crate::api::{class_code_url, get_class_info};
+use crate::args::{HttpArgs, NetworkArgs};
+use crate::class_hash::ClassHash;
+use crate::display::sanitize;
+use crate::interrupt;
+use crate::verified_metadata::ClassStatus;
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    #[arg(help = "Hash of the class")]
+    pub class_hash: ClassHash,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+
+    #[command(flatten)]
+    pub http: HttpArgs,
+}
+
+/**
+ * Print whether the class is declared and verified, and what it was verified
+ * as.
+ */
+pub fn run_check(args: CheckArgs, json: bool) -> anyhow::Result<()> {
+    let class_hash = &args.class_hash;
+    let network = args.network.select()?;
+    let info = get_class_info(network.clone(), class_hash, interrupt::token())?;
+    let url = class_code_url(network.clone(), class_hash.as_str());
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "class_hash": class_hash.to_string(),
+                "network": network.to_string(),
+                "status": info.status,
+                "name": info.name,
+                "license": info.license,
+                "compiler_version": info.compiler_version,
+                "url": url.filter(|_| info.status != ClassStatus::NotDeclared),
+            })
+        );
+        return Ok(());
+    }
+    println!("Class {} on {} is {}", class_hash, network, info.status);
+    for (field, value) in [
+        ("Name", &info.name),
+        ("License", &info.license),
+        ("Compiler version", &info.compiler_version),
+    ] {
+        if let Some(value) = value {
+            println!("  {}: {}", field, sanitize(value));
+        }
+    }
+    match info.status {
+        ClassStatus::Verified => {
+            if let Some(url) = url {
+                println!("View it at {}", url);
+            }
+        }
+        ClassStatus::NotDeclared => {
+            println!("Declare the class on {} before verifying it", network)
+        }
+        ClassStatus::NotVerified | ClassStatus::Unknown => {}
+    }
+    Ok(())
+}
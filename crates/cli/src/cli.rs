@@ -1,64 +1,2174 @@
-mod api;
+mod abi_check;
+mod args;
+mod audit;
+mod check;
+mod collection;
+mod diagnostics;
+mod display;
+mod docs;
+mod doctor;
+mod dry_run;
+mod errors;
+mod events;
+mod exit_codes;
+mod git_ref;
+mod hooks;
+mod humanize;
+mod interrupt;
+mod json_errors;
+mod json_output;
 mod license;
+mod local_hash;
+mod locked;
+mod module_graph;
+mod network;
+mod outcome;
+mod path_scan;
+mod payload;
+mod payload_cache;
+mod plan;
+mod plugins;
+mod policy;
+mod preview;
+mod progress;
+mod project_state;
+mod prompt;
+mod provenance;
+mod quick_check;
+mod report;
 mod resolver;
+mod rpc;
+mod schema;
+mod staging;
+mod status;
+mod status_file;
+mod stdin_tar;
+mod supported_versions;
+mod syntax_scan;
+mod tables;
+mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+mod testing;
+mod timing;
+mod upload_tree;
+mod user_dirs;
 mod utils;
-mod validation;
 mod verify;
 
-use crate::api::{does_class_exist, Network};
+#[cfg(test)]
+use starknet_verifier_api::mock_server;
+use starknet_verifier_api::{
+    api, cancel, class_hash, client, corrections, dependencies, dialect, form, http_retry,
+    ip_version, polling, preflight, resubmit, sierra, sources, transfer, validation,
+    verified_metadata,
+};
+
+use crate::abi_check::compare_abi_with_source;
+use crate::api::{
+    abi_from_class, check_endpoints, fetch_capabilities, fetch_class, fetch_in_progress_jobs,
+    get_network_api, job_status_url, negotiate_dependencies, submit_correction,
+    verification_endpoint, verification_url, BackendCapabilities, CairoPlugin, CustomEndpoints,
+    Network, ProjectMetadataInfo, API_KEY_VAR, CUSTOM_INTERNAL_API_VAR, CUSTOM_PUBLIC_API_VAR,
+};
+use crate::args::{ApiKeyArgs, HttpArgs, NetworkArgs, PollingArgs};
+use crate::audit::{append_audit, AuditEntry, CorrectionAudit};
+use crate::check::{run_check, CheckArgs};
+use crate::class_hash::ClassHash;
+use crate::collection::{
+    collection_root, count_files, max_collection_files, path_dependencies, source_files,
+    CollectionFilter, SKIPPED_DIRECTORIES,
+};
+use crate::corrections::{CorrectionField, CorrectionRequest};
+use crate::dependencies::{vendored_dependencies, DependencyPlan};
+use crate::diagnostics::{Diagnostic, DiagnosticsView};
+use crate::dialect::{select_dialect, Dialect, DIALECT_VAR};
+use crate::display::sanitize;
+use crate::docs::collect_docs;
+use crate::doctor::run_doctor;
+use crate::dry_run::{manifest_files, UploadManifest};
+use crate::errors::{find_error_code, CliError, VerifierError};
+use crate::events::{BufferPolicy, Event, EventStream};
+use crate::exit_codes::{exit_code, EXIT_CODES_HELP};
+use crate::form::{check_upload_size, validate_file_name, FormStyle, DEFAULT_MAX_UPLOAD_SIZE};
+use crate::git_ref::{checkout_git_ref, clean_head_commit, uncommitted_changes};
+use crate::hooks::{load_hooks, run_hook, PRE_VERIFY};
+use crate::http_retry::RetryPolicy;
+use crate::humanize::{format_count, format_duration, format_size};
+use crate::json_errors::{flag_requested, flag_value, ErrorEnvelope, JSON_ERRORS_FLAG};
+use crate::json_output::{JsonOutput, OutputFormat, JSON_FLAG, OUTPUT_FLAG};
 use crate::license::LicenseType;
-use crate::resolver::TargetType;
-use crate::utils::detect_local_tools;
-use camino::Utf8PathBuf;
+use crate::local_hash::{check_local_hash, local_classes};
+use crate::locked::{
+    check_lockfile, check_pinned_versions, find_lockfile, Reproducibility, CAIRO_VERSION_VAR,
+    SCARB_VERSION_VAR,
+};
+use crate::module_graph::{check_contract_file, ContractFileCheck};
+use crate::network::{as_endpoint, is_network_url, resolve_network_url};
+use crate::outcome::VerificationOutcome;
+use crate::path_scan::scan_paths;
+use crate::payload::{unix_now, DispatchCache, SourceRevision};
+use crate::payload_cache::{parse_size, PayloadCache, DEFAULT_COMPRESSION_LEVEL};
+use crate::plan::{
+    CollectionPlan, ExecutionPlan, NetworkPlan, PlanFlags, ProjectPlan, Step, StepPlan,
+    SubmissionPlan, WaitMode,
+};
+use crate::plugins::detect_plugins;
+use crate::policy::{Policy, PolicyContext};
+use crate::polling::DEFAULT_POLL_INTERVAL;
+use crate::preflight::{declaration_from_class, Preflight};
+use crate::preview::{run_preview, Preview, PreviewArgs};
+use crate::progress::PollProgress;
+use crate::prompt::can_prompt;
+use crate::provenance::{set_by, ConfigSnapshot, EnvOrigins, Layer, ENV_SETTINGS};
+use crate::quick_check::{format_findings, QuickCheck, LARGE_PAYLOAD_SIZE, QUICK_CHECK_BUDGET};
+use crate::resolver::{
+    crate_root, discover_contract, package_version, read_manifest, resolver_info,
+    selected_contract, TargetType,
+};
+use crate::rpc::{
+    chain_mismatch, contract_class_version, describe_split, expected_chain_id, rpc_url_for,
+    ClassSource, RpcClient, RpcUrl, RPC_URL_VAR,
+};
+use crate::schema::{schema_json, SchemaKind};
+use crate::sierra::{diagnose_sierra_mismatch, sierra_version_from_class};
+use crate::sources::SourceSet;
+use crate::staging::{staging_max_age, Staging};
+use crate::status::{run_status, StatusArgs};
+use crate::stdin_tar::extract_tar;
+use crate::supported_versions::{run_supported_versions, SupportedVersionsArgs};
+use crate::syntax_scan::{required_version, scan_files};
+use crate::tables::{format_metadata_diff, format_preflight};
+use crate::telemetry::{record_toolchain, run_telemetry, TelemetryCommand, TelemetryContext};
+use crate::upload_tree::validate_upload_paths;
+use crate::user_dirs::{migrate_legacy_state, Environment, Overrides, UserDirs};
+use crate::utils::{detect_local_versions, detect_project_tools};
+use crate::verified_metadata::{diff_metadata, verified_metadata_from_class};
+use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{
+    error::ErrorKind, parser::ValueSource, Args, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
+use comfy_table::Table;
 use console::{style, Emoji};
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use dirs::home_dir;
 use dotenv::dotenv;
-use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use dyn_compiler::dyn_compiler::{format_version, SupportedCairoVersions};
+use gag::{Gag, Redirect};
+use indicatif::{ProgressBar, ProgressStyle};
 use std::{
-    env,
+    collections::BTreeMap,
+    env, fs,
+    io::{self, Write},
+    process::ExitCode,
     str::FromStr,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use strum::IntoEnumIterator;
-use validation::is_class_hash_valid;
+use url::Url;
+use validation::{is_class_hash_valid, validate_class_name};
 use verify::VerifyProjectArgs;
 
-fn main() -> anyhow::Result<()> {
-    dotenv().ok();
-
-    // TODO: make this cli use a secure api
-    // let api_key = match env::var("API_KEY") {
-    //     Ok(api_key) => Some(api_key),
-    //     Err(_) => None,
-    // };
-
-    // let api_key = match api_key {
-    //     Some(key_values) => key_values,
-    //     None => {
-    //         println!("API_KEY not detected in environment variables. You can get one at https://forms.gle/34RE6d4aiiv16HoW6");
-    //         return Ok(());
-    //     }
-    // };
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, after_help = EXIT_CODES_HELP)]
+struct Cli {
+    #[arg(
+        long,
+        help = "Skip comparing the declared class ABI against the selected contract"
+    )]
+    skip_abi_check: bool,
+
+    #[arg(
+        long,
+        help = "Submit even when the sources don't compile to the class hash, for backends building with other settings"
+    )]
+    skip_hash_check: bool,
+
+    #[arg(
+        long,
+        help = "Upload every vendored dependency, even those the backend already has"
+    )]
+    vendor_all_dependencies: bool,
+
+    #[arg(long, help = "Record the verification progress in a JSON status file")]
+    status_file: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        requires = "status_file",
+        help = "Exit right after dispatching and keep polling in a background waiter"
+    )]
+    daemonless_wait: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "daemonless_wait",
+        help = "Exit right after dispatching, printing only the job id on stdout"
+    )]
+    no_wait: bool,
+
+    #[arg(
+        long,
+        help = "Always submit a new verification job, even if the payload is unchanged"
+    )]
+    no_cache: bool,
+
+    #[arg(
+        long,
+        help = "Submit the payload even if a job already failed with the exact same one"
+    )]
+    resubmit_identical: bool,
+
+    #[arg(
+        long,
+        help = "Verify the sources of a git revision instead of the working tree"
+    )]
+    git_ref: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "git_ref",
+        help = "Read the sources as a tar stream, uncompressed or gzip, from stdin"
+    )]
+    stdin_tar: bool,
+
+    #[arg(
+        long,
+        requires = "stdin_tar",
+        help = "Directory of the Scarb project inside the --stdin-tar stream, defaults to its root"
+    )]
+    project_dir: Option<Utf8PathBuf>,
+
+    #[arg(long, help = "Append the verification result to a JSONL report file")]
+    report: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        help = "Directory to collect sources from, defaults to the package and its path dependencies"
+    )]
+    collection_root: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Collect the files matching this glob even if ignored by .gitignore or .voyagerignore, e.g. a generated Scarb.lock. Can be repeated"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Largest upload to submit, files and documentation together, e.g. 20M. Defaults to the 10M limit of the backend"
+    )]
+    max_upload_size: Option<u64>,
+
+    #[command(flatten)]
+    network: NetworkArgs,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Starknet RPC to check the class against instead of --network, e.g. a fork of it. The verification is still submitted to --network"
+    )]
+    rpc_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CHAIN_ID",
+        help = "Chain the RPC of the class checks must serve, e.g. SN_DEVNET. Defaults to the chain of --network when it is a public one"
+    )]
+    chain_id: Option<String>,
+
+    #[command(flatten)]
+    polling: PollingArgs,
+
+    #[command(flatten)]
+    http: HttpArgs,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["output", "json"],
+        help = "Print any failure, invalid arguments included, as a JSON line on stdout, the same as --output json-errors"
+    )]
+    json_errors: bool,
+
+    /// `--output json` under its former name, see `output_format`.
+    #[arg(long, global = true, hide = true, conflicts_with = "output")]
+    json: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Human,
+        help = "How results and failures are printed: json prints the job and its result as JSON lines on stdout and any failure as JSON on stderr, json-errors only prints failures, as a JSON line on stdout"
+    )]
+    output: OutputFormat,
+
+    #[command(flatten)]
+    api_key: ApiKeyArgs,
+
+    #[arg(long, help = "Never read or write the dispatch cache of the project")]
+    no_project_state: bool,
+
+    #[arg(
+        long,
+        help = "Fail unless Scarb.lock is up to date and the compiler versions are pinned in the user config"
+    )]
+    locked: bool,
+
+    #[arg(
+        long,
+        help = "Same as --locked, and without any network access besides the verification endpoints"
+    )]
+    frozen: bool,
+
+    #[arg(
+        long,
+        help = "Keep a zstd-compressed copy of every submitted payload in the cache directory"
+    )]
+    payload_cache: bool,
+
+    #[arg(
+        long,
+        requires = "payload_cache",
+        default_value_t = DEFAULT_COMPRESSION_LEVEL,
+        value_parser = clap::value_parser!(i32).range(1..=22),
+        help = "zstd compression level of the payload cache, from 1 (fastest) to 22 (smallest)"
+    )]
+    payload_cache_level: i32,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Stream the verification progress as JSONL events to a file or pipe, - for stdout"
+    )]
+    events: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        requires = "events",
+        default_value_t = BufferPolicy::Drop,
+        help = "Whether to drop progress events or wait when the event consumer can't keep up"
+    )]
+    event_buffer_policy: BufferPolicy,
+
+    #[arg(
+        long,
+        global = true,
+        help = "User config file in dotenv syntax, defaults to config.env in the platform config directory [env: VOYAGER_CONFIG]"
+    )]
+    config: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Cache directory, defaults to the platform cache directory [env: VOYAGER_CACHE_DIR]"
+    )]
+    cache_dir: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Policy file with the rules of each network, defaults to policy.toml next to the user config"
+    )]
+    policy_file: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FormStyle::Parts,
+        help = "How files are laid out in the form, legacy for older self-hosted backends"
+    )]
+    form_style: FormStyle,
+
+    #[arg(
+        long,
+        conflicts_with = "form_style",
+        help = "Same as --form-style legacy, kept for existing scripts"
+    )]
+    legacy_form_encoding: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Names of the form fields, for self-hosted backends that renamed them, defaults to VOYAGER_FORM_DIALECT or what the backend advertises"
+    )]
+    dialect: Option<Dialect>,
+
+    #[arg(
+        long,
+        help = "Send the same bytes for the same sources, with a boundary derived from their digest"
+    )]
+    deterministic_body: bool,
+
+    #[arg(
+        long,
+        help = "Markdown README uploaded next to the sources, defaults to README.md at the package root"
+    )]
+    readme: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with = "readme",
+        help = "Don't upload the package README"
+    )]
+    no_readme: bool,
+
+    #[arg(
+        long,
+        help = "Additional markdown documentation file to upload, can be repeated"
+    )]
+    doc: Vec<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Resubmit up to this many times when the job fails for a known transient backend reason"
+    )]
+    auto_resubmit: u32,
+
+    #[arg(
+        long,
+        help = "Fail instead of warning when the sources likely need a newer compiler, and confirm the paths found by --scan-paths"
+    )]
+    strict: bool,
+
+    #[arg(
+        long,
+        help = "Warn when the uploaded files contain absolute paths or home directories of the build machine"
+    )]
+    scan_paths: bool,
+
+    #[arg(
+        long,
+        help = "List every diagnostic of the local checks, instead of the first ones on a terminal"
+    )]
+    all_diagnostics: bool,
+
+    #[arg(
+        long,
+        help = "Show the files and form fields that would be uploaded, and where to, without submitting them"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        help = "Print the steps the verification would run and their inputs, then exit without running them"
+    )]
+    explain_plan: bool,
+
+    #[arg(
+        long,
+        help = "Only run the validations that need neither compiling nor the network, within a few seconds, and exit"
+    )]
+    quick_check: bool,
+
+    #[arg(
+        long,
+        help = "Hash of the class to verify, prompted for when missing. --quick-check only checks its format"
+    )]
+    class_hash: Option<String>,
+
+    #[arg(
+        long,
+        help = "Name to verify the class under, prompted for when missing"
+    )]
+    name: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "License to verify the class under, prompted for when missing"
+    )]
+    license: Option<LicenseType>,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Ignore a syntax marker reported by the compiler version check, can be repeated"
+    )]
+    allow_syntax: Vec<String>,
+
+    #[arg(
+        long = "alias",
+        value_name = "NAME",
+        help = "Additional name the class is displayed under, can be repeated"
+    )]
+    aliases: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Drop the aliases with a warning instead of failing when the backend doesn't support them"
+    )]
+    aliases_best_effort: bool,
+
+    #[arg(
+        long,
+        value_name = "HASH",
+        help = "Fail with exit code 3 unless the verified class hash matches HASH"
+    )]
+    expect_hash: Option<ClassHash>,
+
+    #[arg(long, help = "Resubmit the class even if it is already verified")]
+    force: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "force_new",
+        help = "Wait for the verification job already in progress for the class without asking"
+    )]
+    attach_existing: bool,
+
+    #[arg(
+        long,
+        help = "Submit a new verification job even if one is already in progress for the class"
+    )]
+    force_new: bool,
+
+    #[arg(
+        long,
+        help = "Run the [tool.voyager.hooks] of the project in CI, where they are skipped by default"
+    )]
+    allow_hooks: bool,
+
+    #[arg(
+        long,
+        help = "Don't ask to confirm the class about to be marked verified, nor when resubmitting changes the verified name or license, or when publishing paths found by --scan-paths"
+    )]
+    yes: bool,
+
+    #[arg(
+        long,
+        help = "Print how long each phase of the command took and the network traffic"
+    )]
+    verbose: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "verbose",
+        help = "Print nothing but the final result, neither the steps nor the progress of the job"
+    )]
+    quiet: bool,
+
+    #[arg(
+        long,
+        help = "Print a detailed timing breakdown, including project walking and the slowest operations"
+    )]
+    profile_startup: bool,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+impl Cli {
+    /**
+     * The format of `--output`, or of the flag standing for it. Clap rejects
+     * them together, so only one of them can be set.
+     */
+    fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else if self.json_errors {
+            OutputFormat::JsonErrors
+        } else {
+            self.output
+        }
+    }
+
+    /**
+     * The network flags of the subcommand, or of the verification without one.
+     */
+    fn network_args(&self) -> &NetworkArgs {
+        match &self.command {
+            Some(Commands::Status(args)) => &args.network,
+            Some(Commands::Check(args)) => &args.network,
+            Some(Commands::Networks {
+                command: NetworksCommand::List { network },
+            })
+            | Some(Commands::Doctor { network })
+            | Some(Commands::Config {
+                command: ConfigCommand::Show { network, .. },
+            }) => network,
+            Some(Commands::SupportedVersions(args)) => &args.network,
+            Some(Commands::ReportIssue(args)) => &args.network,
+            _ => &self.network,
+        }
+    }
+
+    fn api_key_args(&self) -> &ApiKeyArgs {
+        match &self.command {
+            Some(Commands::Status(args)) => &args.api_key,
+            Some(Commands::Config {
+                command: ConfigCommand::Show { api_key, .. },
+            }) => api_key,
+            Some(Commands::SupportedVersions(args)) => &args.api_key,
+            Some(Commands::ReportIssue(args)) => &args.api_key,
+            _ => &self.api_key,
+        }
+    }
+
+    fn http(&self) -> &HttpArgs {
+        match &self.command {
+            Some(Commands::Status(args)) => &args.http,
+            Some(Commands::Check(args)) => &args.http,
+            Some(Commands::SupportedVersions(args)) => &args.http,
+            Some(Commands::ReportIssue(args)) => &args.http,
+            _ => &self.http,
+        }
+    }
+
+    fn polling(&self) -> &PollingArgs {
+        match &self.command {
+            Some(Commands::Status(args)) => &args.polling,
+            Some(Commands::Config {
+                command: ConfigCommand::Show { polling, .. },
+            }) => polling,
+            _ => &self.polling,
+        }
+    }
+
+    /**
+     * What telemetry needs of the arguments, taken before `run` consumes them.
+     */
+    fn telemetry_context(&self) -> TelemetryContext {
+        let command = match &self.command {
+            None => "verify",
+            Some(Commands::Status(_)) => "status",
+            Some(Commands::Check(_)) => "check",
+            Some(Commands::Networks { .. }) => "networks",
+            Some(Commands::Schema { .. }) => "schema",
+            Some(Commands::Explain { .. }) => "explain",
+            Some(Commands::Paths) => "paths",
+            Some(Commands::Doctor { .. }) => "doctor",
+            Some(Commands::Config { .. }) => "config",
+            Some(Commands::Cache { .. }) => "cache",
+            Some(Commands::SupportedVersions(_)) => "supported-versions",
+            Some(Commands::Telemetry { .. }) => "telemetry",
+            Some(Commands::ReportIssue(_)) => "report-issue",
+            Some(Commands::Jobs { .. }) => "jobs",
+            Some(Commands::Preview(_)) => "preview",
+        };
+        TelemetryContext {
+            command,
+            network: self.network_args().network.clone(),
+            frozen: self.frozen,
+            overrides: Overrides {
+                config: self.config.clone(),
+                cache_dir: self.cache_dir.clone(),
+            },
+        }
+    }
+}
+
+/**
+ * Parse the command line. The flags of the verification itself only apply to
+ * it and to `preview`, which runs it up to the submission, so they are
+ * rejected before any other subcommand rather than silently ignored.
+ */
+fn parse_cli() -> Result<Cli, clap::Error> {
+    let mut command = Cli::command();
+    let matches = command.try_get_matches_from_mut(env::args_os())?;
+    if let Some((name, _)) = matches.subcommand() {
+        if name != "preview" {
+            let given = command
+                .get_arguments()
+                .filter(|arg| !arg.is_global_set())
+                .find(|arg| {
+                    matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+                });
+            if let Some(arg) = given {
+                let message = format!(
+                    "the subcommand '{}' cannot be used with '--{}'",
+                    name,
+                    arg.get_long().unwrap_or_default()
+                );
+                return Err(command.error(ErrorKind::ArgumentConflict, message));
+            }
+        }
+    }
+    Cli::from_arg_matches(&matches)
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Show the state of a verification job
+    Status(StatusArgs),
+    /// Tell whether a class is already verified on --network, before
+    /// submitting it
+    Check(CheckArgs),
+    /// Inspect the networks available for verification
+    Networks {
+        #[command(subcommand)]
+        command: NetworksCommand,
+    },
+    /// Print the JSON Schema of a file written by the verifier
+    Schema {
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+    /// Explain an error code, e.g. VER-003
+    Explain { code: String },
+    /// Print where the user config, the cache, and the state are kept
+    Paths,
+    /// Measure how long connecting to the endpoints of --network takes over
+    /// IPv4 and IPv6, and tell which --ip-version to use when one is broken
+    Doctor {
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+    /// Inspect the user config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Inspect and bound the payload cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// List the Scarb and Cairo versions this client supports, and those the
+    /// backend of --network accepts
+    SupportedVersions(SupportedVersionsArgs),
+    /// Opt in or out of the anonymous usage telemetry, off by default
+    Telemetry {
+        #[command(subcommand)]
+        command: TelemetryCommand,
+    },
+    /// Ask the backend of --network to correct the name or license a class was
+    /// verified with, or show where to ask when it takes no correction requests
+    ReportIssue(ReportIssueArgs),
+    /// Inspect the jobs dispatched for the project
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommand,
+    },
+    /// Collect and check the project like a --dry-run, then serve the staged
+    /// files, form fields and warnings over HTTP for review, without submitting
+    Preview(PreviewArgs),
+}
+
+#[derive(Subcommand, Debug)]
+enum JobsCommand {
+    /// List the jobs dispatched for the project in the current directory, most
+    /// recent first, tagged current, stale or unknown against its commit and
+    /// package version. Prints them as JSON with --json
+    Local {
+        #[arg(
+            long,
+            help = "Only list the jobs dispatched for the current commit and package version"
+        )]
+        current_only: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Show how many payloads are cached and how much disk space they use
+    Stats {
+        #[arg(long, help = "Print the counts and sizes in bytes as JSON")]
+        json: bool,
+    },
+    /// Remove the oldest cached payloads until the cache fits in --max-size,
+    /// and with --temp the staging directories left behind by crashed runs
+    Gc {
+        #[arg(
+            long,
+            required_unless_present = "temp",
+            value_parser = parse_size,
+            help = "Size to shrink the cache to, e.g. 500M or 2G"
+        )]
+        max_size: Option<u64>,
+
+        #[arg(
+            long,
+            help = "Remove the leftover staging directories of crashed runs, whatever their age"
+        )]
+        temp: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Show the config and policy files, and the policy rules active for --network
+    Show {
+        #[arg(
+            long,
+            help = "Also show the value of each setting and whether a flag, the environment, .env or the user config supplied it"
+        )]
+        provenance: bool,
+
+        #[command(flatten)]
+        network: NetworkArgs,
+
+        #[command(flatten)]
+        api_key: ApiKeyArgs,
+
+        #[command(flatten)]
+        polling: PollingArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NetworksCommand {
+    /// List the known networks and their endpoints
+    List {
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+struct ReportIssueArgs {
+    #[arg(help = "Hash of the verified class")]
+    class_hash: ClassHash,
+
+    #[arg(long, value_enum, help = "Field to correct, prompted for when missing")]
+    field: Option<CorrectionField>,
+
+    #[arg(
+        long,
+        help = "Proposed value of the field, a license by its command line name, e.g. MIT. Prompted for when missing"
+    )]
+    value: Option<String>,
+
+    #[arg(
+        long,
+        help = "Why the verified value is wrong, prompted for when missing"
+    )]
+    justification: Option<String>,
+
+    #[arg(
+        long,
+        help = "How the operators of the backend can reach you, prompted for when missing"
+    )]
+    contact: Option<String>,
+
+    #[command(flatten)]
+    network: NetworkArgs,
+
+    #[command(flatten)]
+    api_key: ApiKeyArgs,
+
+    #[command(flatten)]
+    http: HttpArgs,
+}
+
+fn run_cache(cache: &PayloadCache, staging: &Staging, command: CacheCommand) -> anyhow::Result<()> {
+    match command {
+        CacheCommand::Stats { json } => {
+            let stats = cache.stats()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+            println!("Payload cache: {}", cache.dir);
+            println!("Payloads: {}", format_count(stats.entries as u64));
+            println!("Disk usage: {}", format_size(stats.disk_size));
+            println!("Uncompressed: {}", format_size(stats.original_size));
+        }
+        CacheCommand::Gc { max_size, temp } => {
+            if let Some(max_size) = max_size {
+                let outcome = cache.gc(max_size)?;
+                println!(
+                    "Removed {} cached payloads, freeing {}. The cache now uses {}.",
+                    format_count(outcome.removed as u64),
+                    format_size(outcome.freed),
+                    format_size(outcome.remaining)
+                );
+            }
+            if temp {
+                let sweep = staging.sweep(None, SystemTime::now())?;
+                println!(
+                    "Removed {} staging directories, freeing {}. {} still in use.",
+                    sweep.removed,
+                    format_size(sweep.freed),
+                    sweep.in_use
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_config_show(
+    user_dirs: &UserDirs,
+    policy_file: &Utf8Path,
+    policy: &Policy,
+    network: Option<&str>,
+) -> anyhow::Result<()> {
+    let missing = |path: &Utf8Path| if path.is_file() { "" } else { " (not found)" };
+    println!(
+        "Config file: {}{}",
+        user_dirs.config_file,
+        missing(&user_dirs.config_file)
+    );
+    println!("Policy file: {}{}", policy_file, missing(policy_file));
+
+    let networks: Vec<String> = match network {
+        // Only the name of the custom network matters here, not its endpoints
+        Some(network) if is_network_url(network) || network.eq_ignore_ascii_case("custom") => {
+            vec!["custom".to_owned()]
+        }
+        Some(network) => vec![Network::from_str(network)?.to_string()],
+        None => policy.networks().map(str::to_owned).collect(),
+    };
+    if networks.is_empty() {
+        println!("No policy rule is active");
+    }
+    for network in networks {
+        let rules = policy.for_network(&network).active_rules();
+        if rules.is_empty() {
+            println!("No policy rule is active for {}", network);
+            continue;
+        }
+        println!("Policy rules active for {}:", network);
+        for rule in rules {
+            println!("  {}", rule);
+        }
+    }
+    Ok(())
+}
+
+/**
+ * The settings of the run, each with the flag, variable or default it was
+ * resolved from, like the verifier resolves them.
+ */
+fn effective_config(
+    cli: &Cli,
+    user_dirs: &UserDirs,
+    policy_file: &Utf8Path,
+    origins: &EnvOrigins,
+) -> ConfigSnapshot {
+    let vars = |var: &str| env::var(var).ok();
+    let path = |path: &Option<Utf8PathBuf>| path.as_ref().map(Utf8PathBuf::to_string);
+    let seconds = |duration: &Option<Duration>| duration.map(|d| d.as_secs().to_string());
+    let network = cli.network_args();
+    let polling = cli.polling();
+    let mut settings = BTreeMap::from([
+        (
+            "network".to_owned(),
+            origins.resolve(
+                Some(("--network", network.network.clone())),
+                None,
+                None,
+                &vars,
+            ),
+        ),
+        (
+            "rpc_url".to_owned(),
+            origins.resolve(
+                Some(("--rpc-url", cli.rpc_url.clone())),
+                Some(RPC_URL_VAR),
+                None,
+                &vars,
+            ),
+        ),
+        (
+            "poll_interval".to_owned(),
+            origins.resolve(
+                Some(("--poll-interval", seconds(&polling.poll_interval))),
+                None,
+                Some(DEFAULT_POLL_INTERVAL.as_secs().to_string()),
+                &vars,
+            ),
+        ),
+        (
+            "poll_timeout".to_owned(),
+            origins.resolve(
+                Some(("--poll-timeout", seconds(&polling.poll_timeout))),
+                None,
+                None,
+                &vars,
+            ),
+        ),
+        (
+            CUSTOM_INTERNAL_API_VAR.to_lowercase(),
+            origins.resolve(
+                Some(("--api-url", network.api_url.as_ref().map(Url::to_string))),
+                Some(CUSTOM_INTERNAL_API_VAR),
+                None,
+                &vars,
+            ),
+        ),
+        (
+            CUSTOM_PUBLIC_API_VAR.to_lowercase(),
+            origins.resolve(
+                Some((
+                    "--public-api-url",
+                    network.public_api_url.as_ref().map(Url::to_string),
+                )),
+                Some(CUSTOM_PUBLIC_API_VAR),
+                None,
+                &vars,
+            ),
+        ),
+        (
+            "api_key".to_owned(),
+            origins.resolve(
+                Some(("--api-key", cli.api_key_args().api_key.clone())),
+                Some(API_KEY_VAR),
+                None,
+                &vars,
+            ),
+        ),
+        (
+            DIALECT_VAR.to_lowercase(),
+            origins.resolve(
+                Some(("--dialect", cli.dialect.map(|dialect| dialect.to_string()))),
+                Some(DIALECT_VAR),
+                Some(Dialect::default().to_string()),
+                &vars,
+            ),
+        ),
+        (
+            "config_file".to_owned(),
+            origins.resolve(
+                Some(("--config", path(&cli.config))),
+                Some("VOYAGER_CONFIG"),
+                Some(user_dirs.config_file.to_string()),
+                &vars,
+            ),
+        ),
+        (
+            "cache_dir".to_owned(),
+            origins.resolve(
+                Some(("--cache-dir", path(&cli.cache_dir))),
+                Some("VOYAGER_CACHE_DIR"),
+                Some(user_dirs.cache_dir.to_string()),
+                &vars,
+            ),
+        ),
+        (
+            "policy_file".to_owned(),
+            origins.resolve(
+                Some(("--policy-file", path(&cli.policy_file))),
+                None,
+                Some(policy_file.to_string()),
+                &vars,
+            ),
+        ),
+    ]);
+    for var in ENV_SETTINGS {
+        settings.insert(
+            var.to_lowercase(),
+            origins.resolve(None, Some(var), None, &vars),
+        );
+    }
+    ConfigSnapshot(settings)
+}
+
+/**
+ * The proposed value of `field`, following the rules of a verification. A
+ * license is given by its command line name and sent in its long form, as
+ * verifications submit it.
+ */
+fn proposed_value(field: CorrectionField, value: Option<String>) -> anyhow::Result<String> {
+    match field {
+        CorrectionField::Name => {
+            let name = match value {
+                Some(name) => name,
+                None => Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Proposed name:")
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        validate_class_name(input)
+                    })
+                    .interact_text()?,
+            };
+            Ok(name.trim().to_owned())
+        }
+        CorrectionField::License => {
+            let license = match value {
+                Some(license) => LicenseType::from_str(&license, true).map_err(|_| {
+                    CliError::InvalidArguments(format!(
+                        "Unknown license {}, expected one of {}",
+                        license,
+                        LicenseType::iter()
+                            .map(|license| license.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                })?,
+                None => {
+                    let licenses: Vec<LicenseType> = LicenseType::iter().collect();
+                    let index = Select::with_theme(&ColorfulTheme::default())
+                        .with_prompt("Proposed license:")
+                        .items(&licenses)
+                        .default(0)
+                        .interact()?;
+                    licenses[index]
+                }
+            };
+            Ok(license.to_long_string())
+        }
+    }
+}
+
+/**
+ * Send a correction request for a verified class, recorded in the audit log
+ * of the user whether the backend acknowledges it or not. Backends without the
+ * capability get no request, the escalation path of the network policy is
+ * printed instead.
+ */
+fn run_report_issue(
+    args: ReportIssueArgs,
+    network: Network,
+    escalation: Option<&str>,
+    api_key: &str,
+    state_dir: &Utf8Path,
+    json: bool,
+) -> anyhow::Result<()> {
+    let takes_corrections = backend_capabilities(&network, api_key)?
+        .is_some_and(|capabilities| capabilities.metadata_corrections);
+    if !takes_corrections {
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "network": network.to_string(),
+                    "class_hash": args.class_hash.to_string(),
+                    "escalation": escalation,
+                })
+            );
+            return Ok(());
+        }
+        println!("The backend of {} takes no correction requests.", network);
+        match escalation {
+            Some(escalation) => println!(
+                "Report the metadata of {} to {}",
+                args.class_hash,
+                sanitize(escalation)
+            ),
+            None => println!(
+                "No escalation path is configured for {}, set escalation in [policy.{}] of the policy file",
+                network, network
+            ),
+        }
+        return Ok(());
+    }
+
+    let field = match args.field {
+        Some(field) => field,
+        None => {
+            let fields = CorrectionField::ALL;
+            let index = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Field to correct:")
+                .items(fields)
+                .default(0)
+                .interact()?;
+            fields[index]
+        }
+    };
+    let proposed_value = proposed_value(field, args.value)?;
+    let prompt = |value: Option<String>, question: &str| -> anyhow::Result<String> {
+        match value {
+            Some(value) => Ok(value),
+            None => Ok(Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(question)
+                .interact_text()?),
+        }
+    };
+    let justification = prompt(args.justification, "Why is the verified value wrong?")?;
+    let contact = prompt(args.contact, "How can the operators reach you?")?;
+    let request = CorrectionRequest {
+        class_hash: args.class_hash.to_string(),
+        field,
+        proposed_value,
+        justification,
+        contact,
+    };
+    // Invalid requests are never sent, nor recorded
+    request.validate()?;
+
+    let result = submit_correction(network.clone(), api_key, &request);
+    let entry = AuditEntry::CorrectionRequest(CorrectionAudit {
+        at: unix_now(),
+        network: network.to_string(),
+        request,
+        request_id: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().map(|error| format!("{:#}", error)),
+    });
+    if let Err(e) = append_audit(state_dir, &entry) {
+        println!("Unable to record the correction request: {:#}", e);
+    }
+    let request_id = result?;
+    if json {
+        println!("{}", serde_json::to_string(&entry)?);
+    } else {
+        println!(
+            "{} Correction request {} sent, the operators of {} will review it",
+            Emoji("📨", ""),
+            sanitize(&request_id),
+            network
+        );
+    }
+    Ok(())
+}
+
+/**
+ * Commit and package version of the project at `project_dir`, recorded with
+ * the jobs dispatched for it. `commit` is that of --git-ref, whose export
+ * isn't a repository.
+ */
+fn source_revision(project_dir: &Utf8Path, commit: Option<&str>) -> SourceRevision {
+    SourceRevision {
+        git_commit: commit
+            .map(str::to_owned)
+            .or_else(|| clean_head_commit(project_dir)),
+        package_version: fs::read_to_string(project_dir.join("Scarb.toml"))
+            .ok()
+            .and_then(|manifest| package_version(&manifest)),
+    }
+}
+
+/**
+ * The dialect of the form sent to `network`, see `select_dialect`. Its
+ * capabilities are only asked for when neither --dialect nor the setting
+ * choose one.
+ */
+fn form_dialect(
+    flag: Option<Dialect>,
+    network: &Network,
+    api_key: &str,
+) -> anyhow::Result<Dialect> {
+    let setting = env::var(DIALECT_VAR).ok().filter(|name| !name.is_empty());
+    let advertised = match (flag, &setting) {
+        (None, None) => backend_capabilities(network, api_key)?
+            .and_then(|capabilities| capabilities.form_dialect),
+        _ => None,
+    };
+    select_dialect(flag, setting.as_deref(), advertised.as_deref())
+}
+
+/**
+ * The capabilities of the backend of `network`, see `fetch_capabilities`.
+ * Failing to fetch them is only a warning, the callers then go without, as
+ * with a backend predating the endpoint.
+ */
+fn backend_capabilities(
+    network: &Network,
+    api_key: &str,
+) -> anyhow::Result<Option<BackendCapabilities>> {
+    match fetch_capabilities(network.clone(), api_key, interrupt::token()) {
+        Ok(capabilities) => Ok(capabilities),
+        Err(e) => {
+            // Cancelled rather than unable to fetch them
+            interrupt::token().check(None)?;
+            eprintln!("Unable to fetch the capabilities of the backend: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+fn run_jobs_local(
+    cache_dir: &Utf8Path,
+    current: &SourceRevision,
+    current_only: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let cache = DispatchCache::load(cache_dir);
+    let jobs = cache.local_jobs(current, current_only);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&jobs)?);
+        return Ok(());
+    }
+    if jobs.is_empty() {
+        println!(
+            "No jobs were dispatched for this project{}.",
+            if current_only {
+                " in its current state"
+            } else {
+                ""
+            }
+        );
+        return Ok(());
+    }
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Job id",
+        "Network",
+        "Class hash",
+        "Status",
+        "Version",
+        "Commit",
+        "Dispatched at (unix)",
+        "Finished at (unix)",
+        "State",
+    ]);
+    for job in jobs {
+        let entry = job.entry;
+        table.add_row(vec![
+            sanitize(&entry.job_id).into_owned(),
+            entry.network.clone(),
+            entry.class_hash.clone(),
+            optional(
+                entry
+                    .status
+                    .as_deref()
+                    .map(|status| sanitize(status).into_owned()),
+            ),
+            optional(entry.revision.package_version.clone()),
+            optional(
+                entry
+                    .revision
+                    .git_commit
+                    .as_ref()
+                    .map(|commit| commit.chars().take(12).collect()),
+            ),
+            optional(entry.dispatched_at.map(|at| at.to_string())),
+            optional(entry.finished_at.map(|at| at.to_string())),
+            job.freshness.to_string(),
+        ]);
+    }
+    println!("{}", table);
+    Ok(())
+}
+
+fn run_networks_list(network: Option<&str>, custom: &CustomEndpoints) -> anyhow::Result<()> {
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Network",
+        "Internal endpoint",
+        "Public endpoint",
+        "Source",
+    ]);
+
+    let mut networks = vec![Network::Mainnet, Network::Sepolia];
+    if matches!(env::var("DEBUG_NETWORK"), Ok(value) if value.to_lowercase() == "true") {
+        networks.push(Network::Local);
+    }
+    for network in networks {
+        let (internal, public) = get_network_api(network.clone());
+        table.add_row(vec![
+            network.to_string(),
+            internal,
+            public,
+            "built-in".to_owned(),
+        ]);
+    }
+
+    match network.filter(|network| is_network_url(network)) {
+        Some(base) => {
+            let endpoints = resolve_network_url(base, false)?;
+            table.add_row(vec![
+                "custom".to_owned(),
+                format!("{} (derived)", as_endpoint(&endpoints.internal)),
+                format!("{} (derived)", as_endpoint(&endpoints.public)),
+                format!("derived from {} using {}", base, endpoints.source),
+            ]);
+        }
+        None => {
+            if let Ok(custom_network) = custom.network() {
+                let source = match (custom.internal.is_some(), custom.public.is_some()) {
+                    (true, true) => "flags",
+                    (false, false) => "environment",
+                    _ => "flags and environment",
+                };
+                let (internal, public) = get_network_api(custom_network);
+                table.add_row(vec![
+                    "custom".to_owned(),
+                    internal,
+                    public,
+                    source.to_owned(),
+                ]);
+            }
+        }
+    }
+
+    println!("{table}");
+    Ok(())
+}
+
+/**
+ * Warn, or fail under `--strict`, when the sources use language features that
+ * the selected compiler predates, instead of waiting for a remote compile failure.
+ * Returns the warning, which a strict policy fails on once the network is known.
+ */
+fn check_compiler_supports_sources(
+    files: &SourceSet,
+    cairo_version: SupportedCairoVersions,
+    allowed: &[String],
+    strict: bool,
+    view: &DiagnosticsView,
+) -> anyhow::Result<Option<VerifierError>> {
+    let findings = scan_files(files, cairo_version.version(), allowed);
+    let Some(required) = required_version(&findings) else {
+        return Ok(None);
+    };
+
+    let diagnostics: Vec<Diagnostic> = findings.iter().map(Diagnostic::from).collect();
+    let error = VerifierError::CompilerTooOld {
+        selected: cairo_version.to_string(),
+        required: format_version(required),
+        findings: view.format(&diagnostics),
+    };
+    if strict {
+        view.offer_pager(&diagnostics);
+        return Err(error.into());
+    }
+    println!("{} {}", Emoji("⚠️ ", ""), style(&error).yellow());
+    view.offer_pager(&diagnostics);
+    Ok(Some(error))
+}
+
+/**
+ * Check that the contract file is inside the source root of the package, and
+ * that it defines the contract selected in [tool.voyager] rather than only
+ * re-exporting it, see `check_contract_file`. Returns the warning, like
+ * `check_compiler_supports_sources`.
+ */
+fn check_contract_file_defines_contract(
+    files: &SourceSet,
+    project_metadata: &ProjectMetadataInfo,
+    package_dir: &Utf8PathBuf,
+    strict: bool,
+) -> anyhow::Result<Option<VerifierError>> {
+    let (manifest, _) = read_manifest(package_dir)?;
+    let crate_root = format!(
+        "{}/{}",
+        project_metadata.project_dir_path,
+        crate_root(package_dir, &manifest.name)
+    );
+    let source_root = crate_root.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let contract_file = &project_metadata.contract_file;
+    let relative = contract_file
+        .strip_prefix(source_root)
+        .and_then(|path| path.strip_prefix('/'));
+    if !relative.is_some_and(|path| !path.split('/').any(|segment| segment == "..")) {
+        return Err(VerifierError::ContractFileOutsideSourceRoot {
+            contract_file: contract_file.clone(),
+            source_root: source_root.to_owned(),
+        }
+        .into());
+    }
+
+    let contract = fs::read_to_string(package_dir.join("Scarb.toml"))
+        .ok()
+        .and_then(|manifest| selected_contract(&manifest))
+        .unwrap_or_default();
+    let error = match check_contract_file(files, contract_file, &crate_root, &contract) {
+        ContractFileCheck::Defines(_) => return Ok(None),
+        ContractFileCheck::Reexports(module) => VerifierError::ContractFileReexport {
+            contract_file: contract_file.clone(),
+            contract: module.name,
+            suggestion: module
+                .file
+                .strip_prefix(source_root)
+                .map_or(module.file.as_str(), |path| path.trim_start_matches('/'))
+                .to_owned(),
+            defined_in: module.file,
+        },
+        ContractFileCheck::NotFound => VerifierError::ContractModuleNotFound {
+            contract_file: contract_file.clone(),
+            contract,
+        },
+    };
+    if strict {
+        return Err(error.into());
+    }
+    println!("{} {}", Emoji("⚠️ ", ""), style(&error).yellow());
+    Ok(Some(error))
+}
+
+/**
+ * Warn when the uploaded files reference paths of the build machine, which
+ * explorers would publish. Under `--strict` they have to be confirmed, or
+ * acknowledged with --yes. Returns the warning unless acknowledged, like
+ * `check_compiler_supports_sources`.
+ */
+fn check_sources_for_paths(
+    files: &[&SourceSet],
+    strict: bool,
+    acknowledged: bool,
+    view: &DiagnosticsView,
+    reproducibility: Reproducibility,
+) -> anyhow::Result<Option<VerifierError>> {
+    let diagnostics: Vec<Diagnostic> = files
+        .iter()
+        .flat_map(|files| scan_paths(files))
+        .map(|finding| Diagnostic::from(&finding))
+        .collect();
+    if diagnostics.is_empty() {
+        return Ok(None);
+    }
+
+    let error = VerifierError::EnvironmentPaths {
+        findings: view.format(&diagnostics),
+    };
+    if strict && !acknowledged && !can_prompt() {
+        return Err(error.into());
+    }
+    println!("{} {}", Emoji("⚠️ ", ""), style(&error).yellow());
+    view.offer_pager(&diagnostics);
+    if acknowledged {
+        return Ok(None);
+    }
+    if strict {
+        reproducibility.forbid("the prompt to publish these paths", "pass --yes")?;
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Publish the sources with these paths?")
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Err(error.into());
+        }
+        return Ok(None);
+    }
+    Ok(Some(error))
+}
+
+/**
+ * Warn, or fail under `--strict`, when compiler plugins are path dependencies
+ * that the verification server can't fetch. Returns the warning, like
+ * `check_compiler_supports_sources`.
+ */
+fn check_plugins_publishable(
+    plugins: &[CairoPlugin],
+    strict: bool,
+) -> anyhow::Result<Option<VerifierError>> {
+    let local: Vec<&str> = plugins
+        .iter()
+        .filter(|plugin| plugin.is_local())
+        .map(|plugin| plugin.name.as_str())
+        .collect();
+    if local.is_empty() {
+        return Ok(None);
+    }
+
+    let error = VerifierError::LocalPlugin {
+        plugins: local.join(", "),
+    };
+    if strict {
+        return Err(error.into());
+    }
+    println!("{} {}", Emoji("⚠️ ", ""), style(&error).yellow());
+    Ok(Some(error))
+}
+
+/**
+ * The validations of a real run that need neither compiling nor the network,
+ * through the same code, so that passing them means the run won't fail on them.
+ */
+fn run_quick_check(
+    cli: &Cli,
+    package_dir: &Utf8PathBuf,
+    reproducibility: Reproducibility,
+) -> anyhow::Result<()> {
+    let mut quick_check = QuickCheck::new(QUICK_CHECK_BUDGET);
+    let mut license_file = None;
+    quick_check.check("manifest", || {
+        let (manifest, _) = read_manifest(package_dir)?;
+        license_file = Some(manifest.license_file).filter(|file| !file.is_empty());
+        Ok(vec![])
+    });
+    quick_check.check("contracts", || {
+        let (_, cairo_version) = detect_project_tools(package_dir)?;
+        discover_contract(package_dir, cairo_version)?;
+        Ok(vec![])
+    });
+    if let Some(class_hash) = &cli.class_hash {
+        quick_check.check("class hash", || {
+            if !is_class_hash_valid(class_hash) {
+                return Err(anyhow::anyhow!("{} is not a class hash", class_hash));
+            }
+            Ok(vec![])
+        });
+    }
+    quick_check.check("license", || {
+        Ok(license_file
+            .iter()
+            .filter(|file| !package_dir.join(file).is_file())
+            .map(|file| {
+                format!(
+                    "license-file {} of Scarb.toml doesn't exist and won't be uploaded",
+                    file
+                )
+            })
+            .collect())
+    });
+    quick_check.check("aliases", || {
+        for alias in cli.aliases.iter() {
+            validate_class_name(alias).map_err(|reason| VerifierError::InvalidAlias {
+                alias: alias.clone(),
+                reason: reason.to_owned(),
+            })?;
+        }
+        Ok(vec![])
+    });
+    let mut payload_size = 0;
+    let mut payload_files = vec![];
+    quick_check.check("paths", || {
+        let root = collection_root(
+            package_dir,
+            &path_dependencies(package_dir)?,
+            cli.collection_root.as_deref(),
+        )?;
+        let mut warnings = vec![];
+        let max_files = max_collection_files();
+        if count_files(&root, max_files) > max_files {
+            warnings.push(format!(
+                "more than {} files under the collection root {}",
+                max_files, root
+            ));
+        }
+        for file in source_files(&root, &CollectionFilter::new(&cli.include)?) {
+            let name = file.strip_prefix(&root).unwrap_or(file.as_path());
+            validate_file_name(name.as_str())?;
+            let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            payload_files.push((name.to_string(), size));
+            payload_size += size;
+        }
+        Ok(warnings)
+    });
+    quick_check.check("docs", || {
+        for doc in collect_docs(package_dir, cli.readme.as_deref(), cli.no_readme, &cli.doc)? {
+            let size = doc
+                .path
+                .metadata()
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            payload_files.push((doc.name, size));
+            payload_size += size;
+        }
+        Ok(vec![])
+    });
+    quick_check.check("payload size", || {
+        check_upload_size(
+            payload_files
+                .iter()
+                .map(|(name, size)| (name.as_str(), *size)),
+            cli.max_upload_size.unwrap_or(DEFAULT_MAX_UPLOAD_SIZE),
+        )?;
+        if payload_size > LARGE_PAYLOAD_SIZE {
+            return Ok(vec![format!(
+                "about {} would be uploaded",
+                format_size(payload_size)
+            )]);
+        }
+        Ok(vec![])
+    });
+    quick_check.check("lockfile", || {
+        if reproducibility.is_unlocked() && find_lockfile(package_dir).is_none() {
+            return Ok(vec![
+                "no Scarb.lock, the dependencies are resolved anew when building".to_owned(),
+            ]);
+        }
+        check_lockfile(package_dir, reproducibility)?;
+        Ok(vec![])
+    });
+
+    let findings = quick_check.findings();
+    if !findings.is_empty() {
+        println!("{}", format_findings(findings));
+    }
+    let errors = quick_check.errors();
+    println!(
+        "Quick check finished in {}: {} errors, {} warnings, about {} to upload.",
+        format_duration(quick_check.elapsed()),
+        errors,
+        findings.len() - errors,
+        format_size(payload_size)
+    );
+    if errors > 0 {
+        return Err(CliError::QuickCheckFailed { errors }.into());
+    }
+    Ok(())
+}
+
+/**
+ * Show which class is about to be marked verified, and have it confirmed
+ * interactively or with --yes. Runs with --events also get it as a preflight
+ * record, so that scripts can check it.
+ */
+fn confirm_class(
+    preflight: &Preflight,
+    events: Option<&EventStream>,
+    acknowledged: bool,
+    reproducibility: Reproducibility,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now();
+    let escalations = preflight.escalations(now);
+    if let Some(events) = events {
+        events.emit(Event::Preflight {
+            preflight: preflight.clone(),
+            escalations: escalations.clone(),
+        });
+    }
+
+    println!("{}", style("About to mark this class verified:").bold());
+    println!("{}", format_preflight(preflight, now));
+    for escalation in &escalations {
+        println!("{} {}", Emoji("❗ ", ""), style(escalation).red().bold());
+    }
+    if acknowledged {
+        return Ok(());
+    }
+    if !can_prompt() {
+        return Err(VerifierError::ClassNotConfirmed {
+            class_hash: preflight.class_hash.clone(),
+        }
+        .into());
+    }
+    reproducibility.forbid("the class confirmation", "pass --yes")?;
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Is this the class the sources were built into?")
+        .default(escalations.is_empty())
+        .interact()?;
+    if !confirmed {
+        return Err(VerifierError::ClassAborted.into());
+    }
+    Ok(())
+}
+
+/**
+ * Announce which RPC the class is checked against, as a split when it came
+ * from --rpc-url and may be another chain than the one it is submitted to. A
+ * chain id that isn't the expected one has to be confirmed, or acknowledged
+ * with --yes.
+ */
+fn confirm_rpc_chain(
+    rpc_url: &RpcUrl,
+    chain_id: &str,
+    expected: Option<&str>,
+    network: &Network,
+    yes: bool,
+    reproducibility: Reproducibility,
+) -> anyhow::Result<()> {
+    match rpc_url {
+        RpcUrl::Flag(url) => println!(
+            "{} {}",
+            Emoji("🔀", ""),
+            style(describe_split(url, chain_id, network))
+                .yellow()
+                .bold()
+        ),
+        RpcUrl::Network(url) => println!(
+            "Checking the class against the RPC of {} at {} (chain {})",
+            network, url, chain_id
+        ),
+    }
+    let Some(mismatch) = chain_mismatch(network, expected, chain_id) else {
+        return Ok(());
+    };
+    println!("{} {}", Emoji("⚠️ ", ""), style(mismatch).red().bold());
+    if yes {
+        return Ok(());
+    }
+    if !can_prompt() {
+        return Err(VerifierError::ChainMismatchNotConfirmed {
+            chain_id: chain_id.to_owned(),
+            network: network.to_string(),
+        }
+        .into());
+    }
+    reproducibility.forbid("the chain confirmation", "pass --yes")?;
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Check the class against this chain anyway?")
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        return Err(VerifierError::ClassAborted.into());
+    }
+    Ok(())
+}
+
+/**
+ * A verification job already in progress for the class, to poll instead of
+ * dispatching duplicate work. Unless --attach-existing or --force-new decides,
+ * the user is asked, and non-interactive runs attach.
+ */
+fn find_job_to_attach(
+    network: Network,
+    api_key: &str,
+    class_hash: &ClassHash,
+    attach_existing: bool,
+    force_new: bool,
+    reproducibility: Reproducibility,
+) -> anyhow::Result<Option<String>> {
+    let jobs = match fetch_in_progress_jobs(network, api_key, class_hash, interrupt::token()) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            // Cancelled rather than unable to check
+            interrupt::token().check(None)?;
+            println!("Unable to check for verification jobs in progress: {}", e);
+            return Ok(None);
+        }
+    };
+    let Some(job) = jobs.last() else {
+        return Ok(None);
+    };
+
+    let attach = if force_new {
+        false
+    } else if attach_existing || !can_prompt() {
+        true
+    } else {
+        reproducibility.forbid(
+            "the prompt to attach to the job in progress",
+            "pass --attach-existing or --force-new",
+        )?;
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Verification job {} is already in progress for this class ({}), wait for it instead of submitting a new one?",
+                job.job_id(),
+                job.status()
+            ))
+            .default(true)
+            .interact()?
+    };
+    if attach {
+        println!(
+            "{} Attaching to verification job {} already in progress for this class ({}).",
+            Emoji("🔗", ""),
+            job.job_id(),
+            job.status()
+        );
+        Ok(Some(job.job_id().to_owned()))
+    } else {
+        println!(
+            "Verification job {} is already in progress for this class, submitting a new one.",
+            job.job_id()
+        );
+        Ok(None)
+    }
+}
+
+/**
+ * Best-effort check that the selected compiler emits the Sierra version the class
+ * was declared with, which the class hash depends on.
+ */
+fn warn_on_sierra_mismatch(class: &Value, cairo_version: SupportedCairoVersions) {
+    let Some(declared) = sierra_version_from_class(class) else {
+        return;
+    };
+    if let Some(diagnosis) = diagnose_sierra_mismatch(declared, cairo_version) {
+        println!("{} {}", Emoji("⚠️ ", ""), style(diagnosis).yellow().bold());
+    }
+}
+
+/**
+ * Best-effort check that the selected contract actually matches the declared class.
+ * Pointing `contract_file` at another contract of the same package is accepted by the
+ * backend but can never verify, so we warn early.
+ */
+fn warn_on_abi_mismatch(
+    class: &Value,
+    sources: &SourceSet,
+    project_metadata: &ProjectMetadataInfo,
+) {
+    let Some(abi) = abi_from_class(class) else {
+        return;
+    };
+
+    let source = match sources.contents(&project_metadata.contract_file) {
+        Some(source) => String::from_utf8_lossy(source),
+        None => {
+            println!(
+                "Skipping ABI check, {} is not part of the sources",
+                sanitize(&project_metadata.contract_file)
+            );
+            return;
+        }
+    };
+
+    let comparison = compare_abi_with_source(&abi, &source);
+    if comparison.is_likely_wrong_contract() {
+        println!(
+            "{} {}",
+            Emoji("⚠️ ", ""),
+            style(format!(
+                "Only {} of the {} entry points declared by this class were found in {}. \
+                 You might have selected the wrong contract.",
+                comparison.matching.len(),
+                comparison.declared.len(),
+                sanitize(&project_metadata.contract_file)
+            ))
+            .yellow()
+            .bold()
+        );
+        println!(
+            "Missing entry points: {}",
+            comparison
+                .missing()
+                .into_iter()
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let parsing_started = Instant::now();
+    let cli = match parse_cli() {
+        Ok(cli) => cli,
+        // Help and version aren't failures
+        Err(e) if e.use_stderr() => {
+            let args: Vec<String> = env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            let envelope = ErrorEnvelope::from_clap(&e);
+            let output = flag_value(&args, OUTPUT_FLAG);
+            if flag_requested(&args, JSON_FLAG) || output.as_deref() == Some("json") {
+                envelope.eprint();
+            } else if flag_requested(&args, JSON_ERRORS_FLAG)
+                || output.as_deref() == Some("json-errors")
+            {
+                envelope.print();
+            } else {
+                e.exit()
+            }
+            return ExitCode::from(envelope.exit_code);
+        }
+        Err(e) => e.exit(),
+    };
+    let output = cli.output_format();
+    interrupt::install();
+    let telemetry = cli.telemetry_context();
+    let result = run(cli, parsing_started.elapsed());
+    let code = match &result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let code = exit_code(e);
+            match output {
+                OutputFormat::Json => ErrorEnvelope::from_error(e, code).eprint(),
+                OutputFormat::JsonErrors => ErrorEnvelope::from_error(e, code).print(),
+                OutputFormat::Human => eprintln!("Error: {:?}", e),
+            }
+            ExitCode::from(code)
+        }
+    };
+    // Once everything is printed, so that it can't hold the output back
+    telemetry.record(&result, parsing_started.elapsed());
+    code
+}
+
+fn run(mut cli: Cli, arg_parsing: Duration) -> anyhow::Result<()> {
+    let output = cli.output_format();
+    let json = output == OutputFormat::Json;
+    let config_started = Instant::now();
+    let mut origins = EnvOrigins::default();
+    let (project_env, loaded) = set_by(|| dotenv().ok());
+    if let Some(project_env) = project_env.and_then(|path| Utf8PathBuf::from_path_buf(path).ok()) {
+        origins.record(loaded, Layer::ProjectEnv, &project_env);
+    }
+    let mut config_load = config_started.elapsed();
+
+    // The user config comes after the project's .env, which takes precedence
+    let user_config_started = Instant::now();
+    let user_dirs = UserDirs::resolve(
+        &Environment::current(),
+        &Overrides {
+            config: cli.config.clone(),
+            cache_dir: cli.cache_dir.clone(),
+        },
+    )
+    .context(CliError::InvalidConfig)?;
+    let (config_loaded, loaded) = set_by(|| user_dirs.load_config());
+    config_loaded.context(CliError::InvalidConfig)?;
+    origins.record(loaded, Layer::UserConfig, &user_dirs.config_file);
+    let policy_file = cli
+        .policy_file
+        .clone()
+        .unwrap_or_else(|| user_dirs.policy_file());
+    let policy =
+        Policy::load(&policy_file, cli.policy_file.is_some()).context(CliError::InvalidConfig)?;
+    config_load += user_config_started.elapsed();
+    let config = effective_config(&cli, &user_dirs, &policy_file, &origins);
+    let staging = Staging::system(user_dirs.staging_dir())?;
+
+    if cli.verbose || cli.profile_startup {
+        timing::enable(cli.profile_startup);
+        timing::record_phase("config load", config_load);
+        timing::record_phase("arg parsing", arg_parsing);
+    }
+    cli.http().configure();
+    // Runs the verification up to the submission, see below
+    let mut preview = None;
+    match cli.command.take() {
+        Some(Commands::Status(args)) => return run_status(args, json),
+        Some(Commands::Check(args)) => return run_check(args, json),
+        Some(Commands::Networks {
+            command: NetworksCommand::List { network },
+        }) => return run_networks_list(network.name(), &network.custom()),
+        Some(Commands::Schema { kind }) => {
+            println!("{}", schema_json(kind)?);
+            return Ok(());
+        }
+        Some(Commands::Explain { code }) => {
+            let entry = find_error_code(&code)
+                .ok_or_else(|| anyhow::anyhow!("Unknown error code {}", code))?;
+            println!(
+                "{}: {}{}",
+                entry.code,
+                entry.summary,
+                if entry.deprecated {
+                    " (deprecated)"
+                } else {
+                    ""
+                }
+            );
+            println!();
+            println!("{}", entry.explanation);
+            return Ok(());
+        }
+        Some(Commands::Doctor { network }) => return run_doctor(&network.select()?),
+        Some(Commands::Paths) => {
+            let missing = if user_dirs.config_file.is_file() {
+                ""
+            } else {
+                " (not found)"
+            };
+            println!("Config file: {}{}", user_dirs.config_file, missing);
+            println!("Cache: {}", user_dirs.cache_dir);
+            println!("State: {}", user_dirs.state_dir);
+            return Ok(());
+        }
+        Some(Commands::Config {
+            command:
+                ConfigCommand::Show {
+                    provenance,
+                    network,
+                    ..
+                },
+        }) => {
+            run_config_show(&user_dirs, &policy_file, &policy, network.name())?;
+            if provenance {
+                println!("Effective configuration:");
+                println!("{}", config.format());
+            }
+            return Ok(());
+        }
+        Some(Commands::Cache { command }) => {
+            let cache = PayloadCache::new(user_dirs.payload_cache_dir(), DEFAULT_COMPRESSION_LEVEL);
+            return run_cache(&cache, &staging, command);
+        }
+        Some(Commands::Telemetry { command }) => {
+            return run_telemetry(&user_dirs, command);
+        }
+        Some(Commands::ReportIssue(args)) => {
+            let network = args.network.select()?;
+            let api_key = args.api_key.resolve();
+            let escalation = policy.for_network(&network.to_string()).escalation;
+            return run_report_issue(
+                args,
+                network,
+                escalation.as_deref(),
+                &api_key,
+                &user_dirs.state_dir,
+                json,
+            );
+        }
+        Some(Commands::Jobs {
+            command: JobsCommand::Local { current_only },
+        }) => {
+            let project_dir = Utf8PathBuf::try_from(env::current_dir()?)?;
+            return run_jobs_local(
+                &user_dirs.project_cache_dir(&project_dir),
+                &source_revision(&project_dir, None),
+                current_only,
+                json,
+            );
+        }
+        Some(Commands::SupportedVersions(args)) => return run_supported_versions(args),
+        Some(Commands::Preview(args)) => preview = Some(args),
+        None => {}
+    }
+    let api_key = cli.api_key.resolve();
+    let custom = cli.network.custom();
+
+    // Taken before stdout is redirected, so that it keeps writing there
+    let json_output = json
+        .then(JsonOutput::stdout)
+        .transpose()
+        .context("Unable to open stdout for the JSON output")?;
+    // With --no-wait or --json, stdout is left to the job id or JSON lines alone
+    let progress_to_stderr = (!cli.quiet && (cli.no_wait || json))
+        .then(|| Redirect::stdout(io::stderr()))
+        .transpose()
+        .context("Unable to send the progress output to stderr")?;
+    // With --quiet, it is only given back for the final result
+    let quiet = cli
+        .quiet
+        .then(Gag::stdout)
+        .transpose()
+        .context("Unable to silence the progress output")?;
+
+    // Best effort, a crashed run must not prevent the next ones
+    match staging.sweep(Some(staging_max_age()), SystemTime::now()) {
+        Ok(sweep) if sweep.removed > 0 => println!(
+            "{} Removed {} leftover staging directories, freeing {}",
+            Emoji("🧹", ""),
+            sweep.removed,
+            format_size(sweep.freed)
+        ),
+        Ok(_) => {}
+        Err(e) => println!(
+            "{} {}",
+            Emoji("⚠️ ", ""),
+            style(format!("Unable to sweep the staging directories: {:#}", e)).yellow()
+        ),
+    }
+
     println!(
         "{} {} Getting project information...",
         style("[1/4]").bold().dim(),
         Emoji("📝", "")
     );
 
+    // Keep the extracted stream alive until the end of the verification,
+    // it is removed from disk when dropped.
+    let tar_checkout = if cli.stdin_tar {
+        let project_dir = cli.project_dir.as_deref().unwrap_or(Utf8Path::new("."));
+        let checkout = extract_tar(io::stdin().lock(), project_dir, &staging)?;
+        for link in checkout.skipped_links.iter() {
+            println!(
+                "{} {}",
+                Emoji("⚠️ ", ""),
+                style(format!(
+                    "Skipped the link {} of the tar stream",
+                    sanitize(link)
+                ))
+                .yellow()
+            );
+        }
+        Some(checkout)
+    } else {
+        None
+    };
+
+    // A locked run gets every answer from flags, never from a prompt
+    let reproducibility = Reproducibility::from_flags(cli.locked, cli.frozen);
+
     // Project type + Path entry
     let target_type = TargetType::ScarbProject; // by default we assume the user is in a Scarb project
     let is_current_dir_scarb = env::current_dir()?.join("Scarb.toml").exists();
-    let utf8_path = if is_current_dir_scarb {
+    let utf8_path = if let Some(checkout) = &tar_checkout {
+        checkout.project_path.clone()
+    } else if is_current_dir_scarb {
         let current_path = env::current_dir()?.to_str().unwrap().trim().to_string();
         Utf8PathBuf::from(&current_path)
     } else {
+        if !can_prompt() {
+            return Err(CliError::MissingInput {
+                what: "the path of the Scarb project".to_owned(),
+                hint: "run from the root of the project".to_owned(),
+            }
+            .into());
+        }
+        reproducibility.forbid(
+            "the project path prompt",
+            "run from the root of the project",
+        )?;
         loop {
             // TODO, add TargetType::File path input here
             let input_path = Input::<String>::with_theme(&ColorfulTheme::default())
                 .with_prompt("Enter Path to Scarb project root:")
-                .interact_text()
-                .expect("Aborted at path input, terminating...")
+                .interact_text()?
                 .trim()
                 .to_string();
             let mut utf8_input_path: Utf8PathBuf = Utf8PathBuf::from(&input_path);
@@ -77,6 +2187,103 @@ fn main() -> anyhow::Result<()> {
         }
     };
 
+    // Keep the exported revision alive until the end of the verification,
+    // it is removed from disk when dropped.
+    let git_checkout = match &cli.git_ref {
+        Some(git_ref) => {
+            let checkout = checkout_git_ref(&utf8_path, git_ref, &staging)?;
+            println!(
+                "Using sources from git revision {} ({})",
+                checkout.git_ref, checkout.commit
+            );
+            for warning in checkout.warnings.iter() {
+                println!("{} {}", Emoji("⚠️ ", ""), style(warning).yellow());
+            }
+            Some(checkout)
+        }
+        None => None,
+    };
+    // A tar stream has no location of its own, it shares the state of the directory the build runs in
+    let state_path = match &tar_checkout {
+        Some(_) => Utf8PathBuf::try_from(env::current_dir()?)?
+            .join(cli.project_dir.as_deref().unwrap_or(Utf8Path::new("."))),
+        None => utf8_path.clone(),
+    };
+    // Keyed on the working tree, so that verifying revisions of the project shares its cache
+    let project_cache_dir = user_dirs.project_cache_dir(&state_path);
+    if !cli.no_project_state {
+        if let Some(legacy) = migrate_legacy_state(&state_path, &project_cache_dir)? {
+            println!(
+                "{} Moved the dispatch cache of this project from {} to {}",
+                Emoji("📦", ""),
+                legacy,
+                project_cache_dir
+            );
+        }
+    }
+    let utf8_path = match &git_checkout {
+        Some(checkout) => checkout.project_path.clone(),
+        None => utf8_path,
+    };
+
+    if cli.quick_check {
+        return run_quick_check(&cli, &utf8_path, reproducibility);
+    }
+    check_lockfile(&utf8_path, reproducibility)?;
+    if reproducibility.is_frozen() {
+        // Dependencies must already be in the Scarb cache
+        env::set_var("SCARB_OFFLINE", "true");
+    }
+
+    let hooks = load_hooks(&utf8_path, cli.allow_hooks)?;
+    // Every optional step below runs if and only if the plan says so
+    let steps = StepPlan::new(
+        PlanFlags {
+            reproducibility,
+            pre_verify_hook: hooks.pre_verify.is_some(),
+            scan_paths: cli.scan_paths,
+            skip_abi_check: cli.skip_abi_check,
+            skip_hash_check: cli.skip_hash_check,
+            vendor_all_dependencies: cli.vendor_all_dependencies,
+            dry_run: cli.dry_run || preview.is_some(),
+        },
+        cli.explain_plan,
+    );
+    if steps.runs(Step::PreVerifyHook) {
+        if let Some(command) = &hooks.pre_verify {
+            run_hook(PRE_VERIFY, command, &utf8_path, &[])?;
+        }
+    }
+
+    // Only the package and its path dependencies are collected, never the whole repository
+    let collection_root = collection_root(
+        &utf8_path,
+        &path_dependencies(&utf8_path)?,
+        cli.collection_root.as_deref(),
+    )?;
+    let max_files = max_collection_files();
+    if count_files(&collection_root, max_files) > max_files {
+        println!(
+            "{} {}",
+            Emoji("⚠️ ", ""),
+            style(format!(
+                "More than {} files under the collection root {}, collecting sources may be slow. \
+                 Use --collection-root to narrow it down or raise VOYAGER_MAX_COLLECTION_FILES.",
+                max_files, collection_root
+            ))
+            .yellow()
+        );
+    }
+
+    for alias in cli.aliases.iter() {
+        validate_class_name(alias).map_err(|reason| VerifierError::InvalidAlias {
+            alias: alias.clone(),
+            reason: reason.to_owned(),
+        })?;
+    }
+
+    let docs = collect_docs(&utf8_path, cli.readme.as_deref(), cli.no_readme, &cli.doc)?;
+
     // Start the whole process
     let _spinner_style = ProgressStyle::with_template("{prefix:.bold.dim} {spinner} {wide_msg}")
         .unwrap()
@@ -89,18 +2296,90 @@ fn main() -> anyhow::Result<()> {
     );
 
     // Resolve project
-    let (project_files, project_metadata) = match target_type {
+    let (project_files, mut project_metadata, artifacts) = match target_type {
         TargetType::File => {
             panic!("Single contract file verification is not yet implemented, please use a Scarb project instead.");
         }
         TargetType::ScarbProject => {
-            let (local_scarb_version, local_cairo_version) = detect_local_tools();
+            if !reproducibility.is_unlocked() {
+                let (scarb, cairo) = detect_local_versions();
+                check_pinned_versions(
+                    &[(SCARB_VERSION_VAR, scarb), (CAIRO_VERSION_VAR, cairo)],
+                    |var| env::var(var).ok(),
+                    reproducibility,
+                )?;
+            }
+            let (local_scarb_version, local_cairo_version) = detect_project_tools(&utf8_path)?;
             // TODO: do a first pass to find all the contracts in the project
             // For now we keep using the hardcoded value in the Scarb.toml file
 
-            resolver::resolve_scarb(utf8_path.clone(), local_cairo_version, local_scarb_version)?
+            resolver::resolve_scarb(
+                utf8_path.clone(),
+                local_cairo_version,
+                local_scarb_version,
+                &CollectionFilter::new(&cli.include)?,
+            )?
         }
     };
+    // Generated sources take the same path from here on, see verify_file_set
+    let mut sources = SourceSet::from_files_with(&project_files, &mut |file, elapsed| {
+        if timing::is_detailed() {
+            timing::record_operation(&format!("read {}", file.name), elapsed);
+        }
+    })?;
+    let docs = SourceSet::from_files(&docs)?;
+    validate_upload_paths(&sources, &project_metadata)?;
+    record_toolchain(
+        project_metadata.scarb_version,
+        project_metadata.cairo_version,
+    );
+
+    // Limited on a terminal, complete for the JSON outputs
+    let mut diagnostics_view =
+        DiagnosticsView::new(cli.all_diagnostics, output != OutputFormat::Human);
+    if !reproducibility.is_unlocked() {
+        // Offering the pager is a prompt too
+        diagnostics_view.pager = None;
+    }
+    // Kept for the policy of the network, which may be strict
+    let mut strict_warnings = vec![];
+    strict_warnings.extend(check_contract_file_defines_contract(
+        &sources,
+        &project_metadata,
+        &utf8_path,
+        cli.strict,
+    )?);
+    if steps.runs(Step::SyntaxCheck) {
+        strict_warnings.extend(check_compiler_supports_sources(
+            &sources,
+            project_metadata.cairo_version,
+            &cli.allow_syntax,
+            cli.strict,
+            &diagnostics_view,
+        )?);
+    }
+    if steps.runs(Step::PathScan) {
+        strict_warnings.extend(check_sources_for_paths(
+            &[&sources, &docs],
+            cli.strict,
+            cli.yes,
+            &diagnostics_view,
+            reproducibility,
+        )?);
+    }
+    project_metadata.plugins = match detect_plugins(&utf8_path) {
+        Ok(plugins) => plugins,
+        Err(e) => {
+            println!("Unable to detect compiler plugins: {:#}", e);
+            vec![]
+        }
+    };
+    if steps.runs(Step::PluginCheck) {
+        strict_warnings.extend(check_plugins_publishable(
+            &project_metadata.plugins,
+            cli.strict,
+        )?);
+    }
 
     // TODO: try to calculate the class hash automatically later after contract selection?
     // println!(
@@ -116,11 +2395,32 @@ fn main() -> anyhow::Result<()> {
 
     // -- Network selection --
 
-    // Custom network selection
-    let custom_internal_api_endpoint_url = env::var("CUSTOM_INTERNAL_API_ENDPOINT_URL");
-    let custom_public_api_endpoint_url = env::var("CUSTOM_PUBLIC_API_ENDPOINT_URL");
-    let is_custom_network =
-        custom_internal_api_endpoint_url.is_ok() && custom_public_api_endpoint_url.is_ok();
+    // A network url configures the custom network, a network name skips the selection.
+    let mut custom_network = None;
+    let network_arg = match &cli.network.network {
+        Some(network) if is_network_url(network) => {
+            reproducibility.forbid_network(
+                "endpoint discovery",
+                "pass --api-url and --public-api-url instead",
+            )?;
+            let endpoints =
+                resolve_network_url(network, can_prompt() && reproducibility.is_unlocked())?;
+            println!("Using endpoints derived from {}", endpoints.base);
+            custom_network = Some(Network::Custom {
+                internal: endpoints.internal,
+                public: endpoints.public,
+            });
+            None
+        }
+        other => other.clone(),
+    };
+
+    // Custom network selection, a single endpoint serving both APIs
+    let custom_in_env =
+        env::var(CUSTOM_INTERNAL_API_VAR).is_ok() || env::var(CUSTOM_PUBLIC_API_VAR).is_ok();
+    if custom_network.is_none() && network_arg.is_none() && (custom.is_given() || custom_in_env) {
+        custom_network = Some(custom.network()?);
+    }
 
     // Only show local if debug network option is up.
     let is_debug_network = match env::var("DEBUG_NETWORK") {
@@ -134,88 +2434,435 @@ fn main() -> anyhow::Result<()> {
     };
 
     // defaults to the first item.
-    let selected_network = if !is_custom_network {
-        let network_index = Select::with_theme(&ColorfulTheme::default())
-            .items(&network_items)
-            .with_prompt("Which network would you like to verify on : ")
-            .default(0)
-            .interact_opt()
-            .expect("Aborted at network selection, terminating...")
-            .expect("Aborted at network selection, terminating...");
-
-        network_items[network_index]
-    } else {
+    let network_enum = if let Some(network) = network_arg {
+        custom.select(&network)?
+    } else if let Some(network) = custom_network {
+        let (internal, public) = get_network_api(network.clone());
         println!(
             "🔔 {}",
             style("Custom verification endpoint provided:").bold()
         );
-        println!(
-            "Internal endpoint url: {}",
-            custom_internal_api_endpoint_url.unwrap_or("".to_string())
-        );
-        println!(
-            "Public endpoint url: {}",
-            custom_public_api_endpoint_url.unwrap_or("".to_string())
-        );
+        println!("Internal endpoint url: {}", internal);
+        println!("Public endpoint url: {}", public);
+
+        network
+    } else {
+        if !can_prompt() {
+            return Err(CliError::MissingInput {
+                what: "the network".to_owned(),
+                hint: "pass --network".to_owned(),
+            }
+            .into());
+        }
+        reproducibility.forbid("the network prompt", "pass --network")?;
+        let network_index = Select::with_theme(&ColorfulTheme::default())
+            .items(&network_items)
+            .with_prompt("Which network would you like to verify on : ")
+            .default(0)
+            .interact_opt()?
+            .ok_or(VerifierError::ClassAborted)?;
 
-        "custom"
+        Network::from_str(network_items[network_index])?
     };
+    check_endpoints(&network_enum)?;
 
-    let network_enum = Network::from_str(selected_network)?;
-    let mut class_hash: String;
-    loop {
-        class_hash = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Input class hash to verify : ")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if is_class_hash_valid(input) {
-                    Ok(())
-                } else {
-                    Err("This is not a class hash.")
+    if cli.explain_plan {
+        let (internal_api, public_api) = get_network_api(network_enum.clone());
+        let resolver = resolver_info(project_metadata.cairo_version);
+        let files = manifest_files(&sources);
+        let docs = manifest_files(&docs);
+        let plan = ExecutionPlan {
+            config,
+            project: ProjectPlan {
+                path: utf8_path.to_string(),
+                contract_file: project_metadata.contract_file.clone(),
+                resolver: resolver.name.to_owned(),
+                scarb_version: project_metadata.scarb_version.to_string(),
+                cairo_version: project_metadata.cairo_version.to_string(),
+            },
+            collection: CollectionPlan {
+                root: collection_root.to_string(),
+                max_files,
+                skipped_directories: SKIPPED_DIRECTORIES.map(str::to_owned).to_vec(),
+            },
+            network: NetworkPlan {
+                name: network_enum.to_string(),
+                internal_api,
+                public_api,
+                verify_endpoint: verification_endpoint(network_enum.clone())?,
+            },
+            retry: RetryPolicy::from_env().into(),
+            wait: if cli.daemonless_wait {
+                WaitMode::Background
+            } else if cli.no_wait {
+                WaitMode::Exit
+            } else {
+                WaitMode::Poll
+            },
+            steps: steps.steps().to_vec(),
+            submission: SubmissionPlan {
+                total_size: files.iter().chain(&docs).map(|file| file.size).sum(),
+                files,
+                docs,
+            },
+        };
+        match &json_output {
+            Some(output) => output.emit(&plan),
+            None => println!("{}", plan.format()),
+        }
+        return Ok(());
+    }
+
+    let class_source = match rpc_url_for(&network_enum, cli.rpc_url.as_deref()) {
+        Some(rpc_url) => {
+            if let RpcUrl::Flag(_) = rpc_url {
+                reproducibility
+                    .forbid_network("the class checks of --rpc-url", "drop --rpc-url")?;
+            }
+            let rpc = RpcClient::new(rpc_url.as_str());
+            let chain_id = rpc
+                .chain_id()
+                .with_context(|| format!("Unable to get the chain id of the RPC at {}", rpc.url))?;
+            let expected = expected_chain_id(&network_enum, cli.chain_id.as_deref());
+            confirm_rpc_chain(
+                &rpc_url,
+                &chain_id,
+                expected.as_deref(),
+                &network_enum,
+                cli.yes,
+                reproducibility,
+            )?;
+            ClassSource::Rpc(rpc)
+        }
+        None => ClassSource::Explorer(network_enum.clone()),
+    };
+    let class_lookup = steps.runs(Step::ClassLookup);
+    let mut given_class_hash = cli.class_hash.clone();
+    let (class_hash, class) = loop {
+        let input: String = match given_class_hash.take() {
+            Some(input) if is_class_hash_valid(&input) => input,
+            Some(input) => {
+                return Err(CliError::InvalidArguments(format!(
+                    "--class-hash {} is not a class hash",
+                    input
+                ))
+                .into())
+            }
+            None if !can_prompt() => {
+                return Err(CliError::MissingInput {
+                    what: "the class hash".to_owned(),
+                    hint: "pass --class-hash".to_owned(),
                 }
-            })
-            .interact()?;
+                .into())
+            }
+            None => {
+                reproducibility.forbid("the class hash prompt", "pass --class-hash")?;
+                Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Input class hash to verify : ")
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if is_class_hash_valid(input) {
+                            Ok(())
+                        } else {
+                            Err("This is not a class hash.")
+                        }
+                    })
+                    .interact()?
+            }
+        };
+        let class_hash = ClassHash::from_str(&input)?;
+        if !class_lookup {
+            // A class that doesn't exist fails the verification job instead
+            break (class_hash, None);
+        }
 
-        // Check if the class exists on the network
-        match does_class_exist(network_enum.clone(), &class_hash) {
-            Ok(true) => break,
-            Ok(false) => {
+        // Check if the class exists on the network, the checks below read it
+        match class_source.class(&class_hash, interrupt::token()) {
+            Ok(Some(class)) => break (class_hash, Some(class)),
+            // Asking again is pointless when the hash came from --class-hash
+            Ok(None) if cli.class_hash.is_some() => {
+                return Err(VerifierError::ClassNotFound {
+                    class_hash: class_hash.to_string(),
+                    network: network_enum.to_string(),
+                }
+                .into())
+            }
+            Ok(None) => {
                 println!("This class hash does not exist for the given network. Please try again.")
             }
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Error while checking if class exists: {}",
-                    e
-                ))
+            // Coded, so that an unreachable RPC isn't taken for a missing class
+            Err(e) => return Err(e.context("Error while checking if class exists")),
+        }
+    };
+
+    // Before anything is uploaded, sources of another commit fail here
+    if steps.runs(Step::HashCheck) {
+        let declared_version = class.as_ref().and_then(contract_class_version);
+        let classes = local_classes(&artifacts, declared_version.as_deref())?;
+        let local = check_local_hash(&class_hash, &classes)?;
+        println!(
+            "{} The sources compile to the class, as {} (hashed as class version {})",
+            Emoji("✅", ""),
+            local
+                .artifact
+                .file_name()
+                .unwrap_or(local.artifact.as_str()),
+            local.version
+        );
+    }
+
+    // The verified metadata and the declaration are only known to the explorer,
+    // which the class was already fetched from unless it was checked on a node
+    let explorer_class = match &class_source {
+        ClassSource::Explorer(_) => class.clone(),
+        ClassSource::Rpc(_)
+            if steps.includes(Step::VerifiedCheck) || steps.includes(Step::Declaration) =>
+        {
+            match fetch_class(network_enum.clone(), &class_hash, interrupt::token()) {
+                Ok(class) => class,
+                Err(e) => {
+                    println!("Unable to fetch the class from the explorer: {}", e);
+                    None
+                }
+            }
+        }
+        ClassSource::Rpc(_) => None,
+    };
+
+    // Already verified classes are only resubmitted on request. Backends that don't
+    // return the verified metadata skip this check, as do frozen runs.
+    let verified_metadata = if !steps.runs(Step::VerifiedCheck) {
+        None
+    } else {
+        explorer_class
+            .as_ref()
+            .and_then(verified_metadata_from_class)
+    };
+    if let Some(existing) = &verified_metadata {
+        if !cli.force {
+            return Err(VerifierError::AlreadyVerified {
+                class_hash: class_hash.to_string(),
+                name: existing.name.as_deref().unwrap_or("<unnamed>").to_owned(),
             }
+            .into());
         }
     }
 
-    // Get name that you want to use for the contract
-    let class_name: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter your desired class name: ")
-        .validate_with(|input: &String| -> Result<(), &str> {
-            if input.len() > 50 {
-                Err("Given name is too long")
-            } else {
-                Ok(())
+    let attach_job_id = if steps.runs(Step::InProgressJobs) {
+        find_job_to_attach(
+            network_enum.clone(),
+            &api_key,
+            &class_hash,
+            cli.attach_existing,
+            cli.force_new,
+            reproducibility,
+        )?
+    } else {
+        None
+    };
+
+    // Frozen runs don't look the class up, and go without the checks below
+    if let (true, Some(class)) = (steps.runs(Step::SierraCheck), &class) {
+        warn_on_sierra_mismatch(class, project_metadata.cairo_version);
+    }
+
+    // Shown before dispatching, frozen runs go without it like the checks below
+    let declaration = if !steps.runs(Step::Declaration) {
+        None
+    } else {
+        explorer_class.as_ref().and_then(declaration_from_class)
+    };
+
+    if let (true, Some(class)) = (steps.runs(Step::AbiCheck), &class) {
+        warn_on_abi_mismatch(class, &sources, &project_metadata);
+    }
+
+    // Dependencies the backend already has are only referenced in the form
+    let vendored = vendored_dependencies(&sources, &project_metadata.project_dir_path);
+    let dependencies = if steps.runs(Step::DependencyCache) {
+        negotiate_dependencies(network_enum.clone(), &api_key, vendored, interrupt::token())
+    } else {
+        DependencyPlan::disabled(vendored)
+    };
+    if let Some(error) = &dependencies.lookup_error {
+        // Cancelled rather than unable to ask
+        interrupt::token().check(None)?;
+        eprintln!(
+            "Unable to check which dependencies the backend has, uploading them all: {}",
+            error
+        );
+    }
+    dependencies.strip(&mut sources);
+    project_metadata.cached_dependencies = dependencies.skipped();
+    check_upload_size(
+        sources
+            .iter()
+            .chain(docs.iter())
+            .map(|(name, contents)| (name, contents.len() as u64)),
+        cli.max_upload_size.unwrap_or(DEFAULT_MAX_UPLOAD_SIZE),
+    )?;
+
+    // Get name that you want to use for the contract, named after the
+    // starknet-contract target like the artifacts are
+    let default_class_name = project_metadata
+        .target
+        .name
+        .clone()
+        .unwrap_or_else(|| project_metadata.project_dir_path.clone());
+    let class_name: String = match &cli.name {
+        Some(name) => {
+            validate_class_name(name).map_err(|reason| {
+                CliError::InvalidArguments(format!("--name {}: {}", name, reason))
+            })?;
+            name.trim().to_string()
+        }
+        None if !can_prompt() => {
+            return Err(CliError::MissingInput {
+                what: "the class name".to_owned(),
+                hint: "pass --name".to_owned(),
             }
-        })
-        .interact_text()
-        .expect("Aborted at class name input, terminating...")
-        .trim()
-        .to_string();
+            .into())
+        }
+        None => {
+            reproducibility.forbid("the class name prompt", "pass --name")?;
+            Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Enter your desired class name: ")
+                .default(default_class_name)
+                .validate_with(|input: &String| -> Result<(), &str> { validate_class_name(input) })
+                .interact_text()?
+                .trim()
+                .to_string()
+        }
+    };
 
     // Set license for your contract code
-    let licenses: Vec<LicenseType> = LicenseType::iter().collect();
-    let license_index = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select license you'd like to verify under :")
-        .items(&licenses)
-        .default(0)
-        .interact_opt()
-        .expect("Aborted at license version selection, terminating...")
-        .expect("Aborted at license version selection, terminating...");
-
-    let verification_start = Instant::now();
+    let license = match cli.license {
+        Some(license) => license,
+        None if !can_prompt() => {
+            return Err(CliError::MissingInput {
+                what: "the license".to_owned(),
+                hint: "pass --license".to_owned(),
+            }
+            .into())
+        }
+        None => {
+            reproducibility.forbid("the license prompt", "pass --license")?;
+            let licenses: Vec<LicenseType> = LicenseType::iter().collect();
+            let license_index = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Select license you'd like to verify under :")
+                .items(&licenses)
+                .default(0)
+                .interact_opt()?
+                .ok_or(VerifierError::ClassAborted)?;
+            licenses[license_index]
+        }
+    };
+
+    if !steps.includes(Step::Submit) {
+        let manifest = UploadManifest::new(
+            verification_url(network_enum.clone(), &class_hash)?.as_str(),
+            &network_enum.to_string(),
+            class_hash.as_str(),
+            &class_name,
+            &license.to_long_string(),
+            &project_metadata,
+            &sources,
+            &docs,
+            &dependencies,
+        );
+        if let Some(args) = preview {
+            return run_preview(
+                args,
+                Preview {
+                    manifest,
+                    files: sources.clone(),
+                    docs: docs.clone(),
+                    warnings: strict_warnings.iter().map(|w| w.to_string()).collect(),
+                },
+            );
+        }
+        match &json_output {
+            Some(output) => output.emit(&manifest),
+            None => println!("{}", manifest.format()),
+        }
+        return Ok(());
+    }
+
+    let metadata_changes = match &verified_metadata {
+        Some(existing) => diff_metadata(existing, &class_name, &license.to_long_string()),
+        None => vec![],
+    };
+    if !metadata_changes.is_empty() {
+        println!(
+            "{} {}",
+            Emoji("⚠️ ", ""),
+            style("Resubmitting changes what the explorer displays for this class:")
+                .yellow()
+                .bold()
+        );
+        println!("{}", format_metadata_diff(&metadata_changes));
+        if !cli.yes {
+            if !can_prompt() {
+                return Err(VerifierError::MetadataChangeNotConfirmed.into());
+            }
+            reproducibility.forbid("the metadata change confirmation", "pass --yes")?;
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("Proceed with the new metadata?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                return Err(VerifierError::MetadataChangeAborted.into());
+            }
+        }
+    }
+
+    if steps.runs(Step::Policy) {
+        let network_policy = policy.for_network(&network_enum.to_string());
+        // The exported sources of a --git-ref are committed by definition
+        let git_changes = if network_policy.require_clean_git && git_checkout.is_none() {
+            uncommitted_changes(&utf8_path)
+        } else {
+            Some(vec![])
+        };
+        network_policy.check(
+            &network_enum.to_string(),
+            &PolicyContext {
+                license,
+                git_changes: git_changes.as_deref(),
+                strict_warnings: &strict_warnings,
+            },
+        )?;
+    }
+
+    let events = cli
+        .events
+        .as_deref()
+        .map(|path| EventStream::open(path, cli.event_buffer_policy))
+        .transpose()?;
+    let confirmed = if !steps.runs(Step::Confirmation) {
+        Ok(())
+    } else {
+        confirm_class(
+            &Preflight {
+                class_hash: class_hash.to_string(),
+                network: network_enum.to_string(),
+                name: class_name.clone(),
+                declaration,
+                expected_hash: cli.expect_hash.as_ref().map(ClassHash::to_string),
+                verified_name: verified_metadata.and_then(|existing| existing.name),
+            },
+            events.as_ref(),
+            cli.yes,
+            reproducibility,
+        )
+    };
+    if let Err(e) = confirmed {
+        // The preflight record is written before giving up
+        if let Some(events) = events {
+            events.close();
+        }
+        return Err(e);
+    }
+
     println!(
         "{} {} Verifying project...",
         style("[4/4]").bold().dim(),
@@ -223,45 +2870,136 @@ fn main() -> anyhow::Result<()> {
     );
 
     // Create and configure a progress bar
-    let pb_verification = ProgressBar::new_spinner();
+    let pb_verification = match cli.quiet {
+        true => ProgressBar::hidden(),
+        false => ProgressBar::new_spinner(),
+    };
     pb_verification.set_style(_spinner_style);
     pb_verification.enable_steady_tick(Duration::from_millis(100));
     pb_verification.set_message("Please wait...");
 
+    let revision = source_revision(
+        &utf8_path,
+        git_checkout
+            .as_ref()
+            .map(|checkout| checkout.commit.as_str()),
+    );
+    let dialect = form_dialect(cli.dialect, &network_enum, &api_key)?;
     // Parse args into VerifyProjectArgs
     let verify_args = VerifyProjectArgs {
-        network: selected_network.to_string(),
-        hash: class_hash,
-        license: licenses[license_index],
+        network: network_enum.clone(),
+        hash: class_hash.clone(),
+        license,
         name: class_name,
-        max_retries: Some(10),
-        api_key: "".to_string(),
+        polling: cli.polling.config(),
+        api_key,
         path: utf8_path,
+        status_file: cli.status_file.clone(),
+        daemonless_wait: cli.daemonless_wait,
+        no_wait: cli.no_wait,
+        json_output,
+        progress: (!cli.quiet).then(|| PollProgress::stderr(pb_verification.clone())),
+        no_cache: cli.no_cache,
+        resubmit_identical: cli.resubmit_identical,
+        report: cli.report.clone(),
+        no_project_state: cli.no_project_state,
+        form_style: if cli.legacy_form_encoding {
+            FormStyle::Legacy
+        } else {
+            cli.form_style
+        },
+        dialect,
+        deterministic_body: cli.deterministic_body,
+        auto_resubmit: cli.auto_resubmit,
+        aliases: cli.aliases.clone(),
+        aliases_best_effort: cli.aliases_best_effort,
+        expect_hash: cli.expect_hash.clone(),
+        reproducibility,
+        config: Some(config),
+        metadata_changes,
+        revision,
+        attach_job_id,
+        hooks,
+        payload_cache: cli
+            .payload_cache
+            .then(|| PayloadCache::new(user_dirs.payload_cache_dir(), cli.payload_cache_level)),
+        events,
+        cache_dir: project_cache_dir,
+        cancel: interrupt::token().clone(),
     };
 
     let verification_result = match target_type {
-        TargetType::ScarbProject => {
-            verify::verify_project(verify_args, project_metadata, project_files)
+        TargetType::ScarbProject if steps.runs(Step::Submit) => {
+            verify::verify_file_set(verify_args, project_metadata, sources, docs)
         }
+        TargetType::ScarbProject => unreachable!("runs that don't submit return earlier"),
         TargetType::File => panic!("Single contract file verification is not yet implemented"),
     };
+    // Every step was asked for on the way here, in the order --explain-plan shows
+    debug_assert_eq!(steps.executed(), steps.steps());
 
     // Stop and clear the progress bar
     pb_verification.finish_with_message("Done");
+    timing::print_report();
+    if cli.verbose {
+        println!("{}", transfer::global().summary());
+    }
+    // Shown whenever requests were retried, even if the verification succeeded
+    if let Some(summary) = http_retry::global().summary() {
+        println!("{}", summary);
+    }
 
-    match verification_result {
-        Ok(_) => {
+    let outcome = verification_result
+        .and_then(VerificationOutcome::into_result)
+        .map_err(|e| e.context(format!("Verification failed! {}", Emoji("❌", ""))))?;
+    if cli.verbose {
+        println!("{}", outcome.duration);
+    }
+    io::stdout().flush()?;
+    drop(quiet);
+    match outcome.status {
+        None if cli.no_wait => {
+            let job_id = outcome.job_id.unwrap_or_default();
+            if !cli.quiet {
+                eprintln!(
+                    "{} Verification job dispatched, check it with `starknet-contract-verifier status {} --network {}`",
+                    Emoji("🕐", ""),
+                    job_id,
+                    outcome.network
+                );
+                if let Some(url) = job_status_url(network_enum, &job_id) {
+                    eprintln!("Its status is also at {}", url);
+                }
+            }
+            io::stdout().flush()?;
+            drop(progress_to_stderr);
+            // Already printed as JSON
+            if !json {
+                println!("{}", job_id);
+            }
+        }
+        // With --daemonless-wait, the waiter follows the job
+        None => println!(
+            "{} Verification job dispatched, progress is recorded in {}",
+            Emoji("🕐", ""),
+            cli.status_file.unwrap_or_default()
+        ),
+        Some(_) => {
             println!(
                 "{} Successfully verified in {}",
                 Emoji("✅", ""),
-                HumanDuration(verification_start.elapsed())
+                format_duration(outcome.duration.total)
             );
-            Ok(())
+            if let Some(summary) = outcome.summary() {
+                println!("{}", summary);
+            }
+            if let Some(checkout) = &git_checkout {
+                println!(
+                    "Verified sources from git revision {} ({})",
+                    checkout.git_ref, checkout.commit
+                );
+            }
         }
-        Err(e) => Err(anyhow::anyhow!(
-            "Verification failed! {} {}",
-            Emoji("❌", ""),
-            e
-        )),
     }
+    Ok(())
 }
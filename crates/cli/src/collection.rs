@@ -0,0 +1,329 @@
+use std::{collections::BTreeSet, env, fs};
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder,
+};
+use walkdir::WalkDir;
+
+use crate::errors::{CliError, VerifierError};
+
+/// Above this many files under the collection root, the user is warned.
+pub const DEFAULT_MAX_COLLECTION_FILES: usize = 10_000;
+
+/// Directories that never contain sources to verify.
+pub const SKIPPED_DIRECTORIES: [&str; 3] = [".git", "target", "node_modules"];
+/// Exclusions of the verifier only, in the format of `.gitignore`.
+pub const IGNORE_FILE: &str = ".voyagerignore";
+
+/**
+ * Maximum number of files expected under the collection root, configurable
+ * through `VOYAGER_MAX_COLLECTION_FILES`.
+ */
+pub fn max_collection_files() -> usize {
+    env::var("VOYAGER_MAX_COLLECTION_FILES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_COLLECTION_FILES)
+}
+
+/**
+ * Directories of the path dependencies declared in the package manifest,
+ * resolved relative to the package.
+ */
+pub fn path_dependencies(package_dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let manifest_path = package_dir.join("Scarb.toml");
+    let manifest: toml::Value = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Unable to read {}", manifest_path))?
+        .parse()
+        .with_context(|| format!("Unable to parse {}", manifest_path))?;
+
+    let dependencies = manifest
+        .get("dependencies")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, dependency)| dependency.get("path").and_then(toml::Value::as_str))
+        .map(|path| normalize(&package_dir.join(path)))
+        .collect();
+    Ok(dependencies)
+}
+
+/**
+ * Lexically resolve `.` and `..` components, without touching the filesystem.
+ */
+fn normalize(path: &Utf8Path) -> Utf8PathBuf {
+    let mut normalized = Utf8PathBuf::new();
+    for component in path.components() {
+        match component.as_str() {
+            "." => (),
+            ".." if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn common_ancestor(a: &Utf8Path, b: &Utf8Path) -> Utf8PathBuf {
+    a.components()
+        .zip(b.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/**
+ * The root under which sources are collected: the package directory, widened
+ * only as much as needed to reach its path dependencies. An explicit root must
+ * contain all of them.
+ */
+pub fn collection_root(
+    package_dir: &Utf8Path,
+    dependencies: &[Utf8PathBuf],
+    explicit_root: Option<&Utf8Path>,
+) -> Result<Utf8PathBuf> {
+    let package_dir = normalize(package_dir);
+    match explicit_root {
+        Some(root) => {
+            let root = normalize(root);
+            let outside: Vec<&Utf8Path> = std::iter::once(package_dir.as_path())
+                .chain(dependencies.iter().map(Utf8PathBuf::as_path))
+                .filter(|path| !path.starts_with(&root))
+                .collect();
+            if !outside.is_empty() {
+                return Err(VerifierError::CollectionRootTooNarrow {
+                    root: root.to_string(),
+                    outside: outside
+                        .iter()
+                        .map(|path| path.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                }
+                .into());
+            }
+            Ok(root)
+        }
+        None => Ok(dependencies
+            .iter()
+            .fold(package_dir.clone(), |root, dependency| {
+                common_ancestor(&root, dependency)
+            })),
+    }
+}
+
+/**
+ * The files under `root` that aren't ignored: by the `.gitignore` and
+ * `IGNORE_FILE` files under it, hidden, or in `SKIPPED_DIRECTORIES`. Ignore
+ * files above `root` and the global ones of git are left out, so that the
+ * same tree is collected wherever it is checked out.
+ */
+fn unignored_files(root: &Utf8Path) -> impl Iterator<Item = Utf8PathBuf> {
+    WalkBuilder::new(root)
+        .hidden(true)
+        .ignore(false)
+        .parents(false)
+        .git_global(false)
+        .git_exclude(false)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(|entry| {
+            !SKIPPED_DIRECTORIES
+                .iter()
+                .any(|skipped| entry.file_name() == *skipped)
+        })
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.into_path()).ok())
+}
+
+/**
+ * Which files are collected: those `unignored_files` finds, and the ignored
+ * ones matching an `--include` glob, e.g. a generated `Scarb.lock`.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CollectionFilter {
+    includes: Vec<String>,
+}
+
+impl CollectionFilter {
+    /**
+     * `includes` are globs in the format of `.gitignore`, relative to the
+     * walked root.
+     */
+    pub fn new(includes: &[String]) -> Result<Self> {
+        let filter = Self {
+            includes: includes.to_vec(),
+        };
+        filter.overrides(Utf8Path::new(""))?;
+        Ok(filter)
+    }
+
+    fn overrides(&self, root: &Utf8Path) -> Result<Override> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in &self.includes {
+            builder
+                .add(glob)
+                .map_err(|e| CliError::InvalidArguments(format!("--include {}: {}", glob, e)))?;
+        }
+        Ok(builder.build()?)
+    }
+
+    /**
+     * The collected files under `root`, sorted.
+     */
+    pub fn files(&self, root: &Utf8Path) -> Vec<Utf8PathBuf> {
+        let mut files: BTreeSet<Utf8PathBuf> = unignored_files(root).collect();
+        let overrides = self.overrides(root).unwrap_or_else(|_| Override::empty());
+        if !overrides.is_empty() {
+            files.extend(
+                WalkDir::new(root)
+                    .into_iter()
+                    .filter_entry(|entry| entry.file_name() != ".git")
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_file())
+                    .filter(|entry| overrides.matched(entry.path(), false).is_whitelist())
+                    .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.into_path()).ok()),
+            );
+        }
+        files.into_iter().collect()
+    }
+}
+
+/**
+ * Count the files under `root` that aren't ignored, stopping as soon as
+ * `limit` is exceeded so that a huge tree doesn't get walked entirely just
+ * to warn about it.
+ */
+pub fn count_files(root: &Utf8Path, limit: usize) -> usize {
+    unignored_files(root).take(limit + 1).count()
+}
+
+/**
+ * The Cairo files and manifests under `root` that `filter` collects, which
+ * are what gets uploaded once the package is resolved.
+ */
+pub fn source_files(root: &Utf8Path, filter: &CollectionFilter) -> Vec<Utf8PathBuf> {
+    filter
+        .files(root)
+        .into_iter()
+        .filter(|path| path.extension() == Some("cairo") || path.file_name() == Some("Scarb.toml"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monorepo() -> Utf8PathBuf {
+        Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_data/collection/monorepo")
+    }
+
+    #[test]
+    fn test_collection_root_reaches_path_dependencies_only() {
+        let package = monorepo().join("chain/contracts");
+        let dependencies = path_dependencies(&package).unwrap();
+        assert_eq!(dependencies, vec![monorepo().join("chain/shared")]);
+
+        let root = collection_root(&package, &dependencies, None).unwrap();
+        assert_eq!(root, monorepo().join("chain"));
+        // The rest of the monorepo is never walked
+        assert_eq!(count_files(&root, 100), 4);
+        assert_eq!(count_files(&monorepo(), 100), 6);
+    }
+
+    #[test]
+    fn test_collection_root_defaults_to_package() {
+        let package = monorepo().join("chain/shared");
+        assert_eq!(collection_root(&package, &[], None).unwrap(), package);
+    }
+
+    #[test]
+    fn test_explicit_collection_root() {
+        let package = monorepo().join("chain/contracts");
+        let dependencies = vec![monorepo().join("chain/shared")];
+
+        let root = monorepo();
+        assert_eq!(
+            collection_root(&package, &dependencies, Some(&root)).unwrap(),
+            root
+        );
+        assert!(collection_root(&package, &dependencies, Some(&package)).is_err());
+    }
+
+    #[test]
+    fn test_source_files_skip_other_files() {
+        let files = source_files(&monorepo(), &CollectionFilter::default());
+        assert_eq!(
+            files,
+            vec![
+                monorepo().join("chain/contracts/Scarb.toml"),
+                monorepo().join("chain/contracts/src/lib.cairo"),
+                monorepo().join("chain/shared/Scarb.toml"),
+                monorepo().join("chain/shared/src/lib.cairo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignored_files_are_not_collected_unless_included() {
+        let root = Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("voyager-collection-{}", std::process::id())),
+        )
+        .unwrap();
+        let _ = fs::remove_dir_all(&root);
+        for (path, contents) in [
+            (".gitignore", "generated/\n*.log\n"),
+            (IGNORE_FILE, "src/scratch.cairo\n"),
+            (".env", "PRIVATE_KEY=0x1"),
+            ("Scarb.toml", "[package]"),
+            ("src/lib.cairo", "mod token;"),
+            ("src/scratch.cairo", "fn main() {}"),
+            (".cache/lib.cairo", "mod cached;"),
+            ("target/dev/token.cairo", "mod built;"),
+            ("generated/Scarb.lock", "version = 1"),
+            ("build.log", "ok"),
+        ] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), contents).unwrap();
+        }
+        let relative = |files: Vec<Utf8PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|file| file.strip_prefix(&root).unwrap().to_string())
+                .collect()
+        };
+
+        let default = CollectionFilter::default();
+        assert_eq!(
+            relative(default.files(&root)),
+            ["Scarb.toml", "src/lib.cairo"]
+        );
+        assert_eq!(count_files(&root, 100), 2);
+
+        let filter = CollectionFilter::new(&["generated/Scarb.lock".to_owned()]).unwrap();
+        assert_eq!(
+            relative(filter.files(&root)),
+            ["Scarb.toml", "generated/Scarb.lock", "src/lib.cairo"]
+        );
+        assert_eq!(
+            relative(source_files(&root, &filter)),
+            ["Scarb.toml", "src/lib.cairo"]
+        );
+        assert!(CollectionFilter::new(&["src/{lib".to_owned()]).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_count_files_stops_past_limit() {
+        assert_eq!(count_files(&monorepo(), 2), 3);
+    }
+}
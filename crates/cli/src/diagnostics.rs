@@ -0,0 +1,264 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+use console::Term;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use dyn_compiler::dyn_compiler::format_version;
+
+use crate::display::sanitize;
+use crate::prompt::can_prompt;
+use crate::syntax_scan::SyntaxFinding;
+
+/// Diagnostics listed on a terminal, unless --all-diagnostics is given.
+pub const SHOWN_DIAGNOSTICS: usize = 20;
+
+/// Pager the complete list can be opened in.
+pub const PAGER_VAR: &str = "PAGER";
+
+/**
+ * Something a local check found at a line of the sources, such as a syntax
+ * marker or a path of the build machine.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            sanitize(&self.file),
+            self.line,
+            sanitize(&self.message)
+        )
+    }
+}
+
+impl From<&SyntaxFinding> for Diagnostic {
+    fn from(finding: &SyntaxFinding) -> Self {
+        Self {
+            file: finding.file.clone(),
+            line: finding.line,
+            message: format!(
+                "{} (Cairo {}, allow with --allow-syntax {})",
+                finding.marker.feature,
+                format_version(finding.marker.introduced_in),
+                finding.marker.id
+            ),
+        }
+    }
+}
+
+/**
+ * The files of `diagnostics` with how many each has, in the order they first
+ * appear.
+ */
+pub fn count_by_file(diagnostics: &[Diagnostic]) -> Vec<(&str, usize)> {
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for diagnostic in diagnostics {
+        match counts.iter_mut().find(|(file, _)| *file == diagnostic.file) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&diagnostic.file, 1)),
+        }
+    }
+    counts
+}
+
+/**
+ * `diagnostics` grouped by file, preceded by the count of each file when there
+ * are several. With a `limit`, only the first ones are listed and the others
+ * counted.
+ */
+pub fn format_diagnostics(diagnostics: &[Diagnostic], limit: Option<usize>) -> String {
+    let counts = count_by_file(diagnostics);
+    let mut lines = vec![];
+    if diagnostics.len() > 1 {
+        let files: Vec<String> = counts
+            .iter()
+            .map(|(file, count)| format!("{} ({})", sanitize(file), count))
+            .collect();
+        lines.push(format!(
+            "  {} in {} file{}: {}",
+            diagnostics.len(),
+            counts.len(),
+            if counts.len() == 1 { "" } else { "s" },
+            files.join(", ")
+        ));
+    }
+    let grouped = counts.iter().flat_map(|(file, _)| {
+        diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.file == *file)
+    });
+    let shown = limit.unwrap_or(diagnostics.len());
+    lines.extend(
+        grouped
+            .take(shown)
+            .map(|diagnostic| format!("  {}", diagnostic)),
+    );
+    if diagnostics.len() > shown {
+        lines.push(format!(
+            "  and {} more (use --all-diagnostics or pipe to a pager)",
+            diagnostics.len() - shown
+        ));
+    }
+    lines.join("\n")
+}
+
+/**
+ * How diagnostics are shown. Terminals get the first `SHOWN_DIAGNOSTICS` and
+ * the pager; pipes, --all-diagnostics and the JSON outputs get all of them.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsView {
+    pub limit: Option<usize>,
+    /// Command of `PAGER_VAR`, only offered on a terminal.
+    pub pager: Option<String>,
+}
+
+impl DiagnosticsView {
+    pub fn new(all: bool, machine_output: bool) -> Self {
+        if all || machine_output || !Term::stdout().is_term() {
+            return Self::default();
+        }
+        Self {
+            limit: Some(SHOWN_DIAGNOSTICS),
+            // Offering it is a prompt, which stdout being a terminal says nothing about
+            pager: env::var(PAGER_VAR)
+                .ok()
+                .filter(|pager| !pager.trim().is_empty() && can_prompt()),
+        }
+    }
+
+    pub fn format(&self, diagnostics: &[Diagnostic]) -> String {
+        format_diagnostics(diagnostics, self.limit)
+    }
+
+    /**
+     * Offer to open the complete list in the pager when some diagnostics
+     * were left out. A pager that can't be started is only reported.
+     */
+    pub fn offer_pager(&self, diagnostics: &[Diagnostic]) {
+        let Some(pager) = &self.pager else {
+            return;
+        };
+        if self.limit.is_none_or(|limit| diagnostics.len() <= limit) {
+            return;
+        }
+        let open = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Open the {} diagnostics in {}?",
+                diagnostics.len(),
+                pager
+            ))
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+        if open {
+            if let Err(e) = page(pager, &format_diagnostics(diagnostics, None)) {
+                println!("{:#}", e);
+            }
+        }
+    }
+}
+
+/**
+ * Show `text` in `pager`, a command with its arguments, and wait until it is
+ * closed.
+ */
+fn page(pager: &str, text: &str) -> Result<()> {
+    let mut words = pager.split_whitespace();
+    let program = words.next().context("PAGER is empty")?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Unable to start the pager {}", pager))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may be closed before reading everything
+        let _ = writeln!(stdin, "{}", text);
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(file: &str, line: usize) -> Diagnostic {
+        Diagnostic {
+            file: file.to_owned(),
+            line,
+            message: "closures (Cairo 2.9.0, allow with --allow-syntax closures)".to_owned(),
+        }
+    }
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        vec![
+            diagnostic("token/src/lib.cairo", 3),
+            diagnostic("token/src/utils.cairo", 1),
+            diagnostic("token/src/lib.cairo", 8),
+            diagnostic("token/src/lib.cairo", 12),
+            diagnostic("token/src/erc20.cairo", 40),
+        ]
+    }
+
+    #[test]
+    fn test_diagnostics_are_grouped_by_file() {
+        assert_eq!(
+            count_by_file(&diagnostics()),
+            vec![
+                ("token/src/lib.cairo", 3),
+                ("token/src/utils.cairo", 1),
+                ("token/src/erc20.cairo", 1)
+            ]
+        );
+        assert_eq!(
+            format_diagnostics(&diagnostics(), None),
+            "  5 in 3 files: token/src/lib.cairo (3), token/src/utils.cairo (1), token/src/erc20.cairo (1)\n\
+             \x20 token/src/lib.cairo:3: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 token/src/lib.cairo:8: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 token/src/lib.cairo:12: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 token/src/utils.cairo:1: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 token/src/erc20.cairo:40: closures (Cairo 2.9.0, allow with --allow-syntax closures)"
+        );
+    }
+
+    #[test]
+    fn test_limited_diagnostics_count_the_others() {
+        assert_eq!(
+            format_diagnostics(&diagnostics(), Some(2)),
+            "  5 in 3 files: token/src/lib.cairo (3), token/src/utils.cairo (1), token/src/erc20.cairo (1)\n\
+             \x20 token/src/lib.cairo:3: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 token/src/lib.cairo:8: closures (Cairo 2.9.0, allow with --allow-syntax closures)\n\
+             \x20 and 3 more (use --all-diagnostics or pipe to a pager)"
+        );
+        // A limit above the count changes nothing, and a single one needs no summary
+        assert_eq!(
+            format_diagnostics(&diagnostics(), Some(SHOWN_DIAGNOSTICS)),
+            format_diagnostics(&diagnostics(), None)
+        );
+        assert_eq!(
+            format_diagnostics(&diagnostics()[1..2], Some(1)),
+            "  token/src/utils.cairo:1: closures (Cairo 2.9.0, allow with --allow-syntax closures)"
+        );
+    }
+
+    #[test]
+    fn test_machine_output_gets_every_diagnostic() {
+        let view = DiagnosticsView::new(false, true);
+        assert_eq!(view, DiagnosticsView::default());
+        assert!(!view.format(&diagnostics()).contains("more"));
+        assert_eq!(DiagnosticsView::new(true, false).limit, None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::api::FileInfo;
+use crate::errors::VerifierError;
+use crate::form::validate_file_name;
+
+/// Largest documentation file accepted by the backend.
+pub const MAX_DOC_SIZE: u64 = 256 * 1024;
+
+/// Only markdown is rendered next to the verified code.
+const DOC_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+const DEFAULT_README: &str = "README.md";
+
+/**
+ * Name a documentation file is uploaded under: its path relative to the
+ * package when inside of it, its file name otherwise.
+ */
+fn doc_name(package_dir: &Utf8Path, path: &Utf8Path) -> String {
+    match path.strip_prefix(package_dir) {
+        // `/` separated like the sources, see `upload_file_name`
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_str())
+            .collect::<Vec<&str>>()
+            .join("/"),
+        Err(_) => path.file_name().unwrap_or(path.as_str()).to_owned(),
+    }
+}
+
+pub fn validate_doc(path: &Utf8Path) -> Result<()> {
+    let is_markdown = path
+        .extension()
+        .is_some_and(|extension| DOC_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    if !is_markdown {
+        return Err(VerifierError::DocumentNotMarkdown(path.to_string()).into());
+    }
+
+    let size = fs::metadata(path)
+        .with_context(|| format!("Unable to read documentation file {}", path))?
+        .len();
+    if size > MAX_DOC_SIZE {
+        return Err(VerifierError::DocumentTooLarge {
+            path: path.to_string(),
+            size,
+            limit: MAX_DOC_SIZE,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/**
+ * Documentation files to upload next to the sources. The README at the package
+ * root is picked up when present, unless another one is given explicitly or
+ * `skip_default_readme` is set.
+ */
+pub fn collect_docs(
+    package_dir: &Utf8Path,
+    readme: Option<&Utf8Path>,
+    skip_default_readme: bool,
+    docs: &[Utf8PathBuf],
+) -> Result<Vec<FileInfo>> {
+    let default_readme = package_dir.join(DEFAULT_README);
+    let readme = match readme {
+        Some(readme) => Some(readme.to_owned()),
+        None if !skip_default_readme && default_readme.is_file() => Some(default_readme),
+        None => None,
+    };
+
+    let mut collected: Vec<FileInfo> = vec![];
+    for path in readme.iter().chain(docs.iter()) {
+        validate_doc(path)?;
+        let name = doc_name(package_dir, path);
+        validate_file_name(&name)?;
+        if collected.iter().any(|doc| doc.name == name) {
+            continue;
+        }
+        collected.push(FileInfo {
+            name,
+            path: path.clone().into_std_path_buf(),
+        });
+    }
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_package(name: &str) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::from_path_buf(env::temp_dir())
+            .unwrap()
+            .join(format!("voyager-docs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_default_readme_is_collected() {
+        let package = temp_package("default");
+        fs::write(package.join("README.md"), "# MyToken").unwrap();
+        fs::write(package.join("docs/usage.md"), "## Usage").unwrap();
+
+        let docs = collect_docs(&package, None, false, &[package.join("docs/usage.md")]).unwrap();
+        let names: Vec<&str> = docs.iter().map(|doc| doc.name.as_str()).collect();
+        assert_eq!(names, vec!["README.md", "docs/usage.md"]);
+
+        assert!(collect_docs(&package, None, true, &[]).unwrap().is_empty());
+        // Giving the default README again doesn't upload it twice
+        let docs = collect_docs(&package, None, false, &[package.join("README.md")]).unwrap();
+        assert_eq!(docs.len(), 1);
+    }
+
+    #[test]
+    fn test_docs_must_be_small_markdown_files() {
+        let package = temp_package("invalid");
+        fs::write(package.join("notes.txt"), "notes").unwrap();
+        fs::write(
+            package.join("huge.md"),
+            "#".repeat(MAX_DOC_SIZE as usize + 1),
+        )
+        .unwrap();
+
+        let not_markdown = collect_docs(&package, None, false, &[package.join("notes.txt")]);
+        assert!(not_markdown.unwrap_err().to_string().starts_with("VER-020"));
+        let too_large = collect_docs(&package, Some(&package.join("huge.md")), false, &[]);
+        assert!(too_large.unwrap_err().to_string().starts_with("VER-021"));
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use url::Url;
+
+use crate::api::{get_network_api, Network};
+use crate::humanize::format_duration;
+use crate::ip_version::{probe, recommend, EndpointProbe, FamilyProbe, PROBE_TIMEOUT};
+
+fn format_family(name: &str, family: &Option<FamilyProbe>) -> String {
+    match family {
+        None => format!("{}: no address", name),
+        Some(FamilyProbe {
+            address,
+            result: Ok(latency),
+        }) => format!("{}: {} via {}", name, format_duration(*latency), address),
+        Some(FamilyProbe {
+            address,
+            result: Err(e),
+        }) => format!("{}: failed via {} ({})", name, address, e),
+    }
+}
+
+/**
+ * One line per endpoint with how connecting over each family went.
+ */
+pub fn format_probe(probe: &EndpointProbe) -> String {
+    format!(
+        "  {}\n    {}\n    {}",
+        probe.endpoint,
+        format_family("IPv4", &probe.ipv4),
+        format_family("IPv6", &probe.ipv6)
+    )
+}
+
+/**
+ * Measure how long connecting to the endpoints of `network` takes over IPv4
+ * and IPv6, and recommend an --ip-version when one of them is clearly broken.
+ */
+pub fn run_doctor(network: &Network) -> Result<()> {
+    let (internal, public) = get_network_api(network.clone());
+    let mut probes = vec![];
+    println!("Connecting to the endpoints of {}:", network);
+    for endpoint in [internal, public] {
+        let url = Url::parse(&endpoint)?;
+        let probe = probe(&url, PROBE_TIMEOUT);
+        println!("{}", format_probe(&probe));
+        probes.push(probe);
+    }
+    match recommend(&probes) {
+        Some(version) => println!(
+            "The other address family looks broken from here, run with --ip-version {}",
+            version
+        ),
+        None => println!("No address family needs to be forced"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_probe_lists_both_families() {
+        let probe = EndpointProbe {
+            endpoint: "https://api.voyager.online/beta".to_owned(),
+            ipv4: Some(FamilyProbe {
+                address: "1.2.3.4:443".parse().unwrap(),
+                result: Ok(Duration::from_millis(40)),
+            }),
+            ipv6: Some(FamilyProbe {
+                address: "[2001:db8::1]:443".parse().unwrap(),
+                result: Err("connection timed out".to_owned()),
+            }),
+        };
+        assert_eq!(
+            format_probe(&probe),
+            "  https://api.voyager.online/beta\n    \
+             IPv4: 40ms via 1.2.3.4:443\n    \
+             IPv6: failed via [2001:db8::1]:443 (connection timed out)"
+        );
+
+        let probe = EndpointProbe {
+            ipv6: None,
+            ..probe
+        };
+        assert!(format_probe(&probe).ends_with("IPv6: no address"));
+    }
+}
@@ -0,0 +1,314 @@
+use serde::Serialize;
+
+use crate::api::ProjectMetadataInfo;
+use crate::dependencies::{DependencyPlan, DependencyStatus};
+use crate::humanize::format_size;
+use crate::sources::SourceSet;
+
+/**
+ * An uploaded file and its size in bytes.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/**
+ * A vendored dependency, and whether its files are uploaded.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestDependency {
+    pub name: String,
+    pub version: String,
+    pub directory: String,
+    pub size: u64,
+    pub status: DependencyStatus,
+    pub reason: String,
+}
+
+/**
+ * What `--dry-run` shows instead of submitting: the form fields and files that
+ * would be uploaded, and where to. Files are sorted by name so that manifests
+ * of two runs can be diffed.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UploadManifest {
+    pub endpoint: String,
+    pub network: String,
+    pub class_hash: String,
+    pub name: String,
+    pub license: String,
+    pub contract_file: String,
+    pub scarb_version: String,
+    pub cairo_version: String,
+    pub files: Vec<ManifestFile>,
+    /// Uploaded along with the sources, but never compiled.
+    pub docs: Vec<ManifestFile>,
+    /// Those skipped are referenced in the form, their files aren't in `files`.
+    pub dependencies: Vec<ManifestDependency>,
+    /// Of the files and docs together.
+    pub total_size: u64,
+}
+
+/**
+ * The files of `set` with their sizes, sorted by name.
+ */
+pub fn manifest_files(set: &SourceSet) -> Vec<ManifestFile> {
+    let mut files: Vec<ManifestFile> = set
+        .iter()
+        .map(|(name, contents)| ManifestFile {
+            name: name.to_owned(),
+            size: contents.len() as u64,
+        })
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files
+}
+
+/**
+ * A line of the tree of the uploaded files, at its depth from the root.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeRow<'a> {
+    Directory(&'a str),
+    /// A file, with the last component of its path.
+    File(&'a ManifestFile, &'a str),
+}
+
+/**
+ * `files`, sorted by name, as a tree of their `/` separated paths, each
+ * directory listed once before its contents.
+ */
+pub fn tree_rows(files: &[ManifestFile]) -> Vec<(usize, TreeRow<'_>)> {
+    let mut rows = vec![];
+    let mut open: Vec<&str> = vec![];
+    for file in files {
+        let mut components: Vec<&str> = file.name.split('/').collect();
+        let file_name = components.pop().unwrap_or_default();
+        let common = open
+            .iter()
+            .zip(&components)
+            .take_while(|(directory, component)| directory == component)
+            .count();
+        open.truncate(common);
+        for component in &components[common..] {
+            rows.push((open.len(), TreeRow::Directory(component)));
+            open.push(*component);
+        }
+        rows.push((open.len(), TreeRow::File(file, file_name)));
+    }
+    rows
+}
+
+/**
+ * `files` as a tree of their `/` separated paths, indented by depth.
+ */
+fn format_tree(files: &[ManifestFile]) -> Vec<String> {
+    tree_rows(files)
+        .into_iter()
+        .map(|(depth, row)| {
+            let indent = "  ".repeat(depth + 1);
+            match row {
+                TreeRow::Directory(name) => format!("{}{}/", indent, name),
+                TreeRow::File(file, name) => {
+                    format!("{}{} ({})", indent, name, format_size(file.size))
+                }
+            }
+        })
+        .collect()
+}
+
+impl UploadManifest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        endpoint: &str,
+        network: &str,
+        class_hash: &str,
+        name: &str,
+        license: &str,
+        metadata: &ProjectMetadataInfo,
+        files: &SourceSet,
+        docs: &SourceSet,
+        dependencies: &DependencyPlan,
+    ) -> Self {
+        let files = manifest_files(files);
+        let docs = manifest_files(docs);
+        let total_size = files.iter().chain(&docs).map(|file| file.size).sum();
+        Self {
+            endpoint: endpoint.to_owned(),
+            network: network.to_owned(),
+            class_hash: class_hash.to_owned(),
+            name: name.to_owned(),
+            license: license.to_owned(),
+            contract_file: metadata.contract_file.clone(),
+            scarb_version: metadata.scarb_version.to_string(),
+            cairo_version: metadata.cairo_version.to_string(),
+            files,
+            docs,
+            dependencies: dependencies
+                .dependencies
+                .iter()
+                .map(|(dependency, status)| ManifestDependency {
+                    name: dependency.name.clone(),
+                    version: dependency.version.clone(),
+                    directory: dependency.directory.clone(),
+                    size: dependency.size,
+                    status: *status,
+                    reason: status.describe().to_owned(),
+                })
+                .collect(),
+            total_size,
+        }
+    }
+
+    pub fn format(&self) -> String {
+        let mut lines = vec![
+            format!("Would submit to {} ({})", self.endpoint, self.network),
+            format!("Class hash: {}", self.class_hash),
+            format!("Name: {}", self.name),
+            format!("License: {}", self.license),
+            format!("Contract file: {}", self.contract_file),
+            format!("Scarb {}, Cairo {}", self.scarb_version, self.cairo_version),
+            "Files:".to_owned(),
+        ];
+        lines.extend(format_tree(&self.files));
+        if !self.docs.is_empty() {
+            lines.push("Docs:".to_owned());
+            lines.extend(format_tree(&self.docs));
+        }
+        if !self.dependencies.is_empty() {
+            lines.push("Dependencies:".to_owned());
+        }
+        for dependency in &self.dependencies {
+            lines.push(format!(
+                "  {} {} ({}/, {}): {}",
+                dependency.name,
+                dependency.version,
+                dependency.directory,
+                format_size(dependency.size),
+                dependency.reason
+            ));
+        }
+        lines.push(format!(
+            "{} files, {} in total",
+            self.files.len() + self.docs.len(),
+            format_size(self.total_size)
+        ));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependencies::vendored_dependencies;
+    use dyn_compiler::contract_target::StarknetContractTarget;
+    use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+
+    fn manifest() -> UploadManifest {
+        let mut files = SourceSet::new();
+        files.add("token/src/lib.cairo", "mod token;").unwrap();
+        files.add("token/Scarb.toml", "[package]").unwrap();
+        files
+            .add("token/src/token/erc20.cairo", vec![b'a'; 2048])
+            .unwrap();
+        files.add("token/src/token.cairo", "mod erc20;").unwrap();
+        let mut docs = SourceSet::new();
+        docs.add("README.md", "# Token").unwrap();
+        manifest_of(&files, &docs, &DependencyPlan::default())
+    }
+
+    fn manifest_of(
+        files: &SourceSet,
+        docs: &SourceSet,
+        dependencies: &DependencyPlan,
+    ) -> UploadManifest {
+        let metadata = ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: "token".to_owned(),
+            contract_file: "token/src/token/erc20.cairo".to_owned(),
+            target: StarknetContractTarget::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        };
+        UploadManifest::new(
+            "https://api.voyager.online/beta/class-verify/0x1",
+            "mainnet",
+            "0x1",
+            "Token",
+            "MIT",
+            &metadata,
+            files,
+            docs,
+            dependencies,
+        )
+    }
+
+    #[test]
+    fn test_files_are_listed_as_a_sorted_tree() {
+        let manifest = manifest();
+        let names: Vec<&str> = manifest.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "token/Scarb.toml",
+                "token/src/lib.cairo",
+                "token/src/token.cairo",
+                "token/src/token/erc20.cairo",
+            ]
+        );
+        assert_eq!(manifest.total_size, 9 + 10 + 10 + 2048 + 7);
+
+        let text = manifest.format();
+        assert!(text.starts_with(
+            "Would submit to https://api.voyager.online/beta/class-verify/0x1 (mainnet)"
+        ));
+        assert!(text.contains("Contract file: token/src/token/erc20.cairo"));
+        assert!(text.contains(
+            "Files:\n  token/\n    Scarb.toml (9 B)\n    src/\n      lib.cairo (10 B)\n      token.cairo (10 B)\n      token/\n        erc20.cairo (2.0 KiB)\nDocs:\n  README.md (7 B)\n"
+        ));
+        assert!(text.ends_with("5 files, 2.0 KiB in total"));
+    }
+
+    #[test]
+    fn test_skipped_dependencies_are_listed_with_the_reason() {
+        let mut files = SourceSet::new();
+        files.add("token/src/lib.cairo", "mod token;").unwrap();
+        for dependency in ["alexandria", "openzeppelin"] {
+            files
+                .add(
+                    format!("{}/Scarb.toml", dependency),
+                    format!(
+                        "[package]\nname = \"{}\"\nversion = \"1.0.0\"\n",
+                        dependency
+                    ),
+                )
+                .unwrap();
+        }
+        let vendored = vendored_dependencies(&files, "token");
+        let known = vec![vendored[1].fingerprint.clone()];
+        let plan = DependencyPlan::new(vendored, Some(known.as_slice()));
+        plan.strip(&mut files);
+
+        let manifest = manifest_of(&files, &SourceSet::new(), &plan);
+        assert_eq!(manifest.files.len(), 2);
+        let text = manifest.format();
+        assert!(text.contains(
+            "Dependencies:\n  alexandria 1.0.0 (alexandria/, 48 B): uploaded, the backend doesn't have it\n  openzeppelin 1.0.0 (openzeppelin/, 50 B): skipped, the backend already has it\n"
+        ), "{}", text);
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["dependencies"][1]["status"], "cached");
+    }
+
+    #[test]
+    fn test_manifest_json() {
+        let json = serde_json::to_value(manifest()).unwrap();
+        assert_eq!(json["license"], "MIT");
+        assert_eq!(json["cairo_version"], "2.8.4");
+        assert_eq!(json["files"][0]["name"], "token/Scarb.toml");
+        assert_eq!(json["files"][0]["size"], 9);
+        assert_eq!(json["docs"][0]["name"], "README.md");
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::Display;
+
+pub use starknet_verifier_api::errors::{
+    causes, Error, ErrorCodeEntry, Result, VerifierError, ERROR_CODES,
+};
+
+/**
+ * Codes of the errors of the command line itself, in the same sequence as the
+ * ones of the library. The same rules apply: never removed or reused.
+ */
+pub const CLI_ERROR_CODES: &[ErrorCodeEntry] = &[
+    ErrorCodeEntry {
+        code: "VER-025",
+        summary: "Hook failed",
+        explanation: "The pre_verify hook of [tool.voyager.hooks] exited with an error, so nothing was submitted. Its output is printed above.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-031",
+        summary: "Policy violation",
+        explanation: "The verification doesn't meet a rule of [policy.<network>] in the policy file, such as require_license or require_clean_git. `config show` lists the rules active for a network.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-035",
+        summary: "Quick check failed",
+        explanation: "--quick-check found errors that a verification would fail with, each listed with the check that found it. Fix them and rerun it, warnings don't fail it.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-039",
+        summary: "Invalid arguments",
+        explanation: "The command line couldn't be parsed, the message names the argument. Run with --help for the accepted ones.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-040",
+        summary: "Invalid configuration",
+        explanation: "The user config, the policy file or the directories they are resolved from couldn't be loaded. `paths` and `config show --provenance` show where each setting is read from.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-061",
+        summary: "No terminal to ask on",
+        explanation: "A value the verification needs wasn't given, and can't be asked for without a terminal. Pass --network, --class-hash, --name and --license so that a run never prompts.",
+        deprecated: false,
+    },
+];
+
+/**
+ * The entry of `code`, whether the library or the command line assigned it.
+ */
+pub fn find_error_code(code: &str) -> Option<&'static ErrorCodeEntry> {
+    CLI_ERROR_CODES
+        .iter()
+        .chain(ERROR_CODES)
+        .find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+/**
+ * Errors reported to the user by the command line: the ones of the library,
+ * and the ones of what only the command line does, such as parsing arguments
+ * or running hooks. Their message always starts with their code.
+ */
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    Verifier(VerifierError),
+    HookFailed {
+        hook: String,
+        status: String,
+    },
+    PolicyViolation {
+        network: String,
+        rule: String,
+        reason: String,
+    },
+    QuickCheckFailed {
+        errors: usize,
+    },
+    InvalidArguments(String),
+    InvalidConfig,
+    MissingInput {
+        what: String,
+        hint: String,
+    },
+}
+
+impl CliError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CliError::Verifier(error) => error.code(),
+            CliError::HookFailed { .. } => "VER-025",
+            CliError::PolicyViolation { .. } => "VER-031",
+            CliError::QuickCheckFailed { .. } => "VER-035",
+            CliError::InvalidArguments(_) => "VER-039",
+            CliError::InvalidConfig => "VER-040",
+            CliError::MissingInput { .. } => "VER-061",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            CliError::Verifier(_) => unreachable!("displayed as the library error"),
+            CliError::HookFailed { hook, status } => {
+                format!("The {} hook failed ({})", hook, status)
+            }
+            CliError::PolicyViolation {
+                network,
+                rule,
+                reason,
+            } => format!(
+                "Policy rule {} of {} is violated: {}",
+                rule, network, reason
+            ),
+            CliError::QuickCheckFailed { errors } => {
+                format!("The quick check found {} errors", errors)
+            }
+            CliError::InvalidArguments(message) => format!("Invalid arguments: {}", message),
+            CliError::InvalidConfig => "Unable to load the configuration".to_owned(),
+            CliError::MissingInput { what, hint } => {
+                format!("No terminal to ask for {}, {}", what, hint)
+            }
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Verifier(error) => error.fmt(f),
+            _ => write!(f, "{}: {}", self.code(), self.message()),
+        }
+    }
+}
+
+impl From<VerifierError> for CliError {
+    fn from(error: VerifierError) -> Self {
+        CliError::Verifier(error)
+    }
+}
+
+/**
+ * Code of the first coded error in the chain of `error`, if any, whether the
+ * library or the command line reported it.
+ */
+pub fn error_code(error: &anyhow::Error) -> Option<&'static str> {
+    causes(error).find_map(|cause| {
+        cause
+            .downcast_ref::<CliError>()
+            .map(CliError::code)
+            .or_else(|| {
+                cause
+                    .downcast_ref::<VerifierError>()
+                    .map(VerifierError::code)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Every code ever assigned here. Codes may be deprecated but never removed from the registry.
+    const ASSIGNED_CODES: &[&str] = &[
+        "VER-025", "VER-031", "VER-035", "VER-039", "VER-040", "VER-061",
+    ];
+
+    fn one_of_each_variant() -> Vec<CliError> {
+        vec![
+            CliError::Verifier(VerifierError::NoContracts),
+            CliError::HookFailed {
+                hook: "pre_verify".to_owned(),
+                status: "exit status: 1".to_owned(),
+            },
+            CliError::PolicyViolation {
+                network: "mainnet".to_owned(),
+                rule: "require_license".to_owned(),
+                reason: "".to_owned(),
+            },
+            CliError::QuickCheckFailed { errors: 1 },
+            CliError::InvalidArguments("unexpected argument '--foo' found".to_owned()),
+            CliError::InvalidConfig,
+            CliError::MissingInput {
+                what: "the class hash".to_owned(),
+                hint: "pass --class-hash".to_owned(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_codes_are_unique_across_the_registries() {
+        let registered: Vec<&str> = CLI_ERROR_CODES
+            .iter()
+            .chain(ERROR_CODES)
+            .map(|entry| entry.code)
+            .collect();
+        let unique: HashSet<&str> = registered.iter().copied().collect();
+        assert_eq!(unique.len(), registered.len(), "Duplicate error code");
+
+        for code in ASSIGNED_CODES {
+            assert!(
+                CLI_ERROR_CODES.iter().any(|entry| entry.code == *code),
+                "Error code {} was removed",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_variant_has_an_active_code() {
+        for error in one_of_each_variant() {
+            let entry = find_error_code(error.code()).expect("Variant code missing");
+            assert!(
+                !entry.deprecated,
+                "Variant uses deprecated code {}",
+                entry.code
+            );
+            assert!(error
+                .to_string()
+                .starts_with(&format!("{}: ", error.code())));
+        }
+        assert_eq!(
+            CliError::from(VerifierError::NoContracts).to_string(),
+            VerifierError::NoContracts.to_string()
+        );
+    }
+
+    #[test]
+    fn test_codes_of_both_kinds_are_found() {
+        let error = anyhow::Error::new(CliError::InvalidConfig).context("Unable to start");
+        assert_eq!(error_code(&error), Some("VER-040"));
+
+        let error = anyhow::Error::new(VerifierError::JobNotFound).context("Polling failed");
+        assert_eq!(error_code(&error), Some("VER-009"));
+        assert_eq!(error_code(&anyhow::anyhow!("uncoded")), None);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::http_retry::{RetryCause, RetryLog};
+use crate::humanize::{FormatChange, FORMAT_CHANGES};
+use crate::preflight::Preflight;
+use crate::report::Submission;
+
+/// Events buffered between the verification and the writer thread.
+pub const EVENT_BUFFER_CAPACITY: usize = 64;
+
+/**
+ * A line of the JSONL event stream written with `--events`. Timings are
+ * measured from the start of the verification when the event happens, not
+ * when it is written, so that a slow consumer can't distort them.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// What is about to be marked verified, before anything is dispatched.
+    Preflight {
+        #[serde(flatten)]
+        preflight: Preflight,
+        escalations: Vec<String>,
+    },
+    /// The job polled by an attempt, dispatched or attached to.
+    Job {
+        job_id: String,
+        attempt: u32,
+        submission: Submission,
+        elapsed_ms: u64,
+    },
+    /// A status received while polling, the only event that may be dropped.
+    Status {
+        job_id: String,
+        status: String,
+        elapsed_ms: u64,
+    },
+    /// A request sent again after a transient failure, see `emit_retries`.
+    Retry {
+        endpoint: String,
+        /// The attempt that failed, counted from 1.
+        attempt: u32,
+        cause: RetryCause,
+        wait_ms: u64,
+        /// Whether the wait is the `Retry-After` asked for by the backend.
+        retry_after: bool,
+        elapsed_ms: u64,
+    },
+    Finished {
+        job_id: Option<String>,
+        error_code: Option<String>,
+        elapsed_ms: u64,
+    },
+    /// Progress events dropped since the previous event, see `BufferPolicy`.
+    Dropped { count: u64 },
+    /// A human output changed format, written first for consumers parsing it.
+    FormatChange {
+        #[serde(flatten)]
+        change: FormatChange,
+    },
+}
+
+impl Event {
+    fn is_progress(&self) -> bool {
+        matches!(self, Event::Status { .. })
+    }
+}
+
+/**
+ * What to do with a progress event when the writer can't keep up.
+ */
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BufferPolicy {
+    /// Drop it, and report how many were dropped with the next event.
+    #[default]
+    Drop,
+    /// Wait for the writer, slowing down the verification.
+    Block,
+}
+
+/**
+ * Streams events to a writer from a dedicated thread, so that writing to a
+ * slow pipe never delays the polling. Events other than progress always block
+ * when the buffer is full, they are never lost.
+ */
+#[derive(Debug)]
+pub struct EventStream {
+    sender: SyncSender<Event>,
+    policy: BufferPolicy,
+    dropped: AtomicU64,
+    writer: JoinHandle<()>,
+}
+
+impl EventStream {
+    /**
+     * `-` streams to stdout, anything else is created or truncated, which
+     * includes named pipes. The stream starts with a `format_change` event
+     * for each entry of `FORMAT_CHANGES`.
+     */
+    pub fn open(path: &Utf8Path, policy: BufferPolicy) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                File::create(path)
+                    .with_context(|| format!("Unable to open event stream {}", path))?,
+            )
+        };
+        let stream = Self::spawn(writer, EVENT_BUFFER_CAPACITY, policy);
+        for change in FORMAT_CHANGES {
+            stream.emit(Event::FormatChange { change: *change });
+        }
+        Ok(stream)
+    }
+
+    pub fn spawn(writer: Box<dyn Write + Send>, capacity: usize, policy: BufferPolicy) -> Self {
+        let (sender, receiver) = sync_channel(capacity);
+        Self {
+            sender,
+            policy,
+            dropped: AtomicU64::new(0),
+            writer: thread::spawn(move || write_events(writer, receiver)),
+        }
+    }
+
+    pub fn emit(&self, event: Event) {
+        if event.is_progress() && self.policy == BufferPolicy::Drop {
+            match self.sender.try_send(event) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => (),
+                Err(TrySendError::Full(_)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            return;
+        }
+        self.send_dropped();
+        // Only fails once the writer is gone, which already warned
+        let _ = self.sender.send(event);
+    }
+
+    /**
+     * A `retry` event for each retry of `retries` not emitted yet, timed from
+     * `started`. Retries happen within requests, they are emitted before the
+     * event of the request that went through.
+     */
+    pub fn emit_retries(&self, retries: &RetryLog, started: Instant) {
+        for retry in retries.unreported() {
+            self.emit(Event::Retry {
+                endpoint: retry.endpoint,
+                attempt: retry.attempt,
+                cause: retry.cause,
+                wait_ms: retry.wait.as_millis() as u64,
+                retry_after: retry.retry_after,
+                elapsed_ms: retry.at.saturating_duration_since(started).as_millis() as u64,
+            });
+        }
+    }
+
+    /**
+     * Wait for every event to be written.
+     */
+    pub fn close(self) {
+        self.send_dropped();
+        drop(self.sender);
+        let _ = self.writer.join();
+    }
+
+    fn send_dropped(&self) {
+        let count = self.dropped.swap(0, Ordering::Relaxed);
+        if count > 0 {
+            let _ = self.sender.send(Event::Dropped { count });
+        }
+    }
+}
+
+/**
+ * Failing to write should never fail the verification, the remaining events
+ * are drained so that the verification never waits for a broken pipe.
+ */
+fn write_events(mut writer: Box<dyn Write + Send>, receiver: Receiver<Event>) {
+    let mut failed = false;
+    for event in receiver {
+        if failed {
+            continue;
+        }
+        let written = serde_json::to_writer(&mut writer, &event)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush());
+        if let Err(e) = written {
+            println!("Unable to write the verification events: {}", e);
+            failed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_retry::RetryRecord;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    /// A consumer reading a line every 20ms.
+    #[derive(Clone, Default)]
+    struct SlowWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            thread::sleep(Duration::from_millis(20));
+            Ok(())
+        }
+    }
+
+    impl SlowWriter {
+        fn events(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    /// Polls 50 statuses 2ms apart, returning how late the last poll was.
+    fn poll_with(stream: &EventStream) -> Duration {
+        let started = Instant::now();
+        for i in 0..50 {
+            let due = started + Duration::from_millis(2 * i);
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+            stream.emit(Event::Status {
+                job_id: "job".to_owned(),
+                status: "Submitted".to_owned(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        started
+            .elapsed()
+            .saturating_sub(Duration::from_millis(2 * 49))
+    }
+
+    #[test]
+    fn test_slow_consumer_doesnt_delay_polling() {
+        let writer = SlowWriter::default();
+        let stream = EventStream::spawn(Box::new(writer.clone()), 4, BufferPolicy::Drop);
+        let late = poll_with(&stream);
+        stream.emit(Event::Finished {
+            job_id: Some("job".to_owned()),
+            error_code: None,
+            elapsed_ms: 100,
+        });
+        stream.close();
+
+        // Writing every status would take a second
+        assert!(late < Duration::from_millis(500), "{:?}", late);
+        let events = writer.events();
+        let written = events.iter().filter(|e| e["event"] == "status").count() as u64;
+        let dropped = events
+            .iter()
+            .filter(|e| e["event"] == "dropped")
+            .map(|e| e["count"].as_u64().unwrap())
+            .sum::<u64>();
+        assert!(dropped > 0);
+        assert_eq!(written + dropped, 50);
+        assert_eq!(events.last().unwrap()["event"], "finished");
+    }
+
+    #[test]
+    fn test_stream_starts_with_the_format_changes() {
+        let path = camino::Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-events-{}.jsonl", std::process::id())),
+        )
+        .unwrap();
+        EventStream::open(&path, BufferPolicy::Drop)
+            .unwrap()
+            .close();
+
+        let events: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), FORMAT_CHANGES.len());
+        assert_eq!(events[0]["event"], "format_change");
+        assert_eq!(events[0]["output"], FORMAT_CHANGES[0].output);
+        assert!(events[0]["stable_field"].is_string());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_retries_are_emitted_once() {
+        let started = Instant::now();
+        let retries = RetryLog::default();
+        retries.record(RetryRecord {
+            endpoint: "job-status".to_owned(),
+            attempt: 1,
+            cause: RetryCause::RateLimited,
+            wait: Duration::from_secs(7),
+            retry_after: true,
+            at: started + Duration::from_millis(1500),
+        });
+        let writer = SlowWriter::default();
+        let stream = EventStream::spawn(Box::new(writer.clone()), 4, BufferPolicy::Drop);
+        stream.emit_retries(&retries, started);
+        stream.emit_retries(&retries, started);
+        stream.close();
+
+        let events = writer.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["event"], "retry");
+        assert_eq!(events[0]["cause"], "rate_limited");
+        assert_eq!(events[0]["wait_ms"], 7000);
+        assert_eq!(events[0]["retry_after"], true);
+        assert_eq!(events[0]["elapsed_ms"], 1500);
+    }
+
+    #[test]
+    fn test_blocking_policy_writes_every_event() {
+        let writer = SlowWriter::default();
+        let stream = EventStream::spawn(Box::new(writer.clone()), 4, BufferPolicy::Block);
+        let late = poll_with(&stream);
+        stream.close();
+
+        assert!(late >= Duration::from_millis(500), "{:?}", late);
+        assert_eq!(writer.events().len(), 50);
+    }
+}
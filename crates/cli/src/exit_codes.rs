@@ -0,0 +1,148 @@
+use crate::errors::{causes, CliError, VerifierError};
+
+/// Exit code of the failures without a more specific one.
+pub const FAILURE_EXIT_CODE: u8 = 1;
+/// Exit code of invalid arguments, the same as clap's.
+pub const INVALID_ARGUMENTS_EXIT_CODE: u8 = 2;
+/// Exit code when the verified class hash isn't the one given with --expect-hash,
+/// or the sources don't compile to the class.
+pub const HASH_MISMATCH_EXIT_CODE: u8 = 3;
+/// Exit code of `status <JOB_ID>` when the job hasn't finished yet.
+pub const JOB_IN_PROGRESS_EXIT_CODE: u8 = 4;
+pub const CLASS_NOT_FOUND_EXIT_CODE: u8 = 5;
+pub const COMPILATION_FAILED_EXIT_CODE: u8 = 6;
+pub const VERIFICATION_FAILED_EXIT_CODE: u8 = 7;
+pub const JOB_NOT_FOUND_EXIT_CODE: u8 = 8;
+/// Exit code when the API answered with an unexpected status or refused the key.
+pub const API_ERROR_EXIT_CODE: u8 = 9;
+/// Exit code when the API couldn't be reached, even after retries.
+pub const NETWORK_ERROR_EXIT_CODE: u8 = 10;
+/// Exit code of a run cancelled with Ctrl-C, as shells report a SIGINT.
+pub const CANCELLED_EXIT_CODE: u8 = 130;
+
+/// Shown after `--help`, so that scripts can branch on the exit code.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    Success
+  1    Any other failure, see the VER-XXX code of the error
+  2    Invalid arguments
+  3    The verified class hash isn't the one given with --expect-hash, or the sources
+       don't compile to the class
+  4    `status`: the job hasn't finished yet
+  5    The class isn't declared on the network
+  6    The job failed to compile the sources
+  7    The job compiled but the class didn't match
+  8    The job isn't known to the backend
+  9    The API answered with an error, or refused the API key
+  10   The API couldn't be reached, even after retries
+  130  Cancelled with Ctrl-C";
+
+/**
+ * Exit code of a failed run, from the first coded error of the chain that has
+ * one of its own, see `EXIT_CODES_HELP`.
+ */
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    causes(error)
+        .find_map(|cause| {
+            if let Some(error) = cause.downcast_ref::<CliError>() {
+                return cli_exit_code(error);
+            }
+            match cause.downcast_ref::<VerifierError>() {
+                Some(error) => verifier_exit_code(error),
+                None => cause
+                    .downcast_ref::<reqwest::Error>()
+                    .filter(|error| error.is_connect() || error.is_timeout() || error.is_request())
+                    .map(|_| NETWORK_ERROR_EXIT_CODE),
+            }
+        })
+        .unwrap_or(FAILURE_EXIT_CODE)
+}
+
+fn cli_exit_code(error: &CliError) -> Option<u8> {
+    match error {
+        CliError::Verifier(error) => verifier_exit_code(error),
+        CliError::InvalidArguments(_) | CliError::MissingInput { .. } => {
+            Some(INVALID_ARGUMENTS_EXIT_CODE)
+        }
+        _ => None,
+    }
+}
+
+fn verifier_exit_code(error: &VerifierError) -> Option<u8> {
+    match error {
+        VerifierError::InvalidCorrection(_) => Some(INVALID_ARGUMENTS_EXIT_CODE),
+        VerifierError::HashMismatch { .. } | VerifierError::LocalHashMismatch { .. } => {
+            Some(HASH_MISMATCH_EXIT_CODE)
+        }
+        VerifierError::JobInProgress { .. } => Some(JOB_IN_PROGRESS_EXIT_CODE),
+        VerifierError::ClassNotFound { .. } => Some(CLASS_NOT_FOUND_EXIT_CODE),
+        VerifierError::CompilationFailed(_) => Some(COMPILATION_FAILED_EXIT_CODE),
+        VerifierError::VerificationFailed(_) => Some(VERIFICATION_FAILED_EXIT_CODE),
+        VerifierError::JobNotFound => Some(JOB_NOT_FOUND_EXIT_CODE),
+        VerifierError::ClassLookupFailed { .. }
+        | VerifierError::DispatchFailed { .. }
+        | VerifierError::UnexpectedJobStatus { .. }
+        | VerifierError::ApiKeyRejected { .. }
+        | VerifierError::Maintenance { .. }
+        | VerifierError::UploadRejectedTooLarge
+        | VerifierError::CorrectionRejected { .. } => Some(API_ERROR_EXIT_CODE),
+        VerifierError::RetriesExhausted { .. } | VerifierError::RpcUnreachable { .. } => {
+            Some(NETWORK_ERROR_EXIT_CODE)
+        }
+        VerifierError::Cancelled { .. } => Some(CANCELLED_EXIT_CODE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct_and_documented() {
+        let codes = [
+            FAILURE_EXIT_CODE,
+            INVALID_ARGUMENTS_EXIT_CODE,
+            HASH_MISMATCH_EXIT_CODE,
+            JOB_IN_PROGRESS_EXIT_CODE,
+            CLASS_NOT_FOUND_EXIT_CODE,
+            COMPILATION_FAILED_EXIT_CODE,
+            VERIFICATION_FAILED_EXIT_CODE,
+            JOB_NOT_FOUND_EXIT_CODE,
+            API_ERROR_EXIT_CODE,
+            NETWORK_ERROR_EXIT_CODE,
+            CANCELLED_EXIT_CODE,
+        ];
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[..i].contains(code), "{} is used twice", code);
+            let line = format!("\n  {:<4} ", code);
+            assert!(EXIT_CODES_HELP.contains(&line), "{} isn't documented", code);
+        }
+    }
+
+    #[test]
+    fn test_exit_code_of_the_first_coded_cause() {
+        let failed = anyhow::Error::new(VerifierError::CompilationFailed("oops".to_owned()))
+            .context("Verification failed");
+        assert_eq!(exit_code(&failed), COMPILATION_FAILED_EXIT_CODE);
+
+        let not_found = anyhow::Error::new(VerifierError::ClassNotFound {
+            class_hash: "0x1".to_owned(),
+            network: "sepolia".to_owned(),
+        });
+        assert_eq!(exit_code(&not_found), CLASS_NOT_FOUND_EXIT_CODE);
+
+        let missing = anyhow::Error::new(CliError::MissingInput {
+            what: "the class hash".to_owned(),
+            hint: "pass --class-hash".to_owned(),
+        });
+        assert_eq!(exit_code(&missing), INVALID_ARGUMENTS_EXIT_CODE);
+
+        // Coded errors without an exit code of their own
+        assert_eq!(
+            exit_code(&VerifierError::NoContracts.into()),
+            FAILURE_EXIT_CODE
+        );
+        assert_eq!(exit_code(&anyhow::anyhow!("uncoded")), FAILURE_EXIT_CODE);
+    }
+}
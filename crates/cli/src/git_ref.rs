@@ -0,0 +1,199 @@
+use std::{fs, io::Cursor, process::Command};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::errors::VerifierError;
+use crate::staging::{Staging, StagingDir};
+
+/**
+ * A temporary export of a git revision. The exported tree is removed when this
+ * is dropped, which also covers early returns and panics.
+ */
+#[derive(Debug)]
+pub struct GitRefCheckout {
+    pub git_ref: String,
+    pub commit: String,
+    pub project_path: Utf8PathBuf,
+    pub warnings: Vec<String>,
+    /// Removes the exported tree when dropped.
+    _staging_dir: StagingDir,
+}
+
+fn git(repo: &Utf8Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo.as_str())
+        .args(args)
+        .output()
+        .context("Unable to run git, is it installed?")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn git_str(repo: &Utf8Path, args: &[&str]) -> Result<String> {
+    Ok(String::from_utf8_lossy(&git(repo, args)?).trim().to_owned())
+}
+
+/**
+ * Export the tree of `git_ref` into a temporary directory and locate the project
+ * inside of it, so that the verification runs on exactly that revision instead
+ * of the working tree. The directory is tracked by `staging`, so that a crash
+ * doesn't leave it behind for good.
+ */
+pub fn checkout_git_ref(
+    project_path: &Utf8Path,
+    git_ref: &str,
+    staging: &Staging,
+) -> Result<GitRefCheckout> {
+    let repo_root = Utf8PathBuf::from(git_str(project_path, &["rev-parse", "--show-toplevel"])?);
+    let commit = git_str(
+        project_path,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", git_ref)],
+    )
+    .context(VerifierError::UnknownGitRevision(git_ref.to_owned()))?;
+
+    let canonical_project = Utf8PathBuf::from_path_buf(project_path.canonicalize()?)
+        .map_err(|p| anyhow!("Non UTF-8 project path {}", p.display()))?;
+    let canonical_root = Utf8PathBuf::from_path_buf(repo_root.canonicalize()?)
+        .map_err(|p| anyhow!("Non UTF-8 repository path {}", p.display()))?;
+    let relative_project = canonical_project
+        .strip_prefix(&canonical_root)
+        .map_err(|_| anyhow!("{} is not inside {}", project_path, repo_root))?
+        .to_owned();
+
+    // Created before extracting so that the staging directory is cleaned up on failure too.
+    let staging_dir = staging.create("git-ref")?;
+    let export = staging_dir.path().to_owned();
+    let mut checkout = GitRefCheckout {
+        git_ref: git_ref.to_owned(),
+        commit: commit.clone(),
+        project_path: export.join(&relative_project),
+        warnings: vec![],
+        _staging_dir: staging_dir,
+    };
+
+    let archive = git(&repo_root, &["archive", "--format=tar", &commit])?;
+    tar::Archive::new(Cursor::new(archive))
+        .unpack(&export)
+        .context("Unable to extract the git archive")?;
+
+    if export.join(".gitmodules").exists() {
+        checkout.warnings.push(
+            "The repository uses git submodules, their content is not included in the exported revision."
+                .to_owned(),
+        );
+    }
+    let attributes = fs::read_to_string(export.join(".gitattributes")).unwrap_or_default();
+    if attributes.contains("filter=lfs") {
+        checkout.warnings.push(
+            "The repository uses git LFS, LFS tracked files are exported as pointer files."
+                .to_owned(),
+        );
+    }
+
+    if !checkout.project_path.join("Scarb.toml").exists() {
+        return Err(anyhow!(
+            "No Scarb.toml found at {} in revision {}",
+            relative_project,
+            git_ref
+        ));
+    }
+
+    Ok(checkout)
+}
+
+/**
+ * Files of the project with uncommitted changes, including untracked ones,
+ * relative to the repository root. `None` when the project isn't in a git
+ * repository.
+ */
+pub fn uncommitted_changes(project_path: &Utf8Path) -> Option<Vec<String>> {
+    let status = git(project_path, &["status", "--porcelain", "--", "."]).ok()?;
+    Some(
+        String::from_utf8_lossy(&status)
+            .lines()
+            .filter_map(|line| line.get(3..))
+            .map(str::to_owned)
+            .collect(),
+    )
+}
+
+/**
+ * Commit checked out in the repository of the project, `None` outside of git
+ * or when the project has uncommitted changes, since the commit then doesn't
+ * identify its sources.
+ */
+pub fn clean_head_commit(project_path: &Utf8Path) -> Option<String> {
+    if !uncommitted_changes(project_path)?.is_empty() {
+        return None;
+    }
+    git_str(project_path, &["rev-parse", "--verify", "HEAD"]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_git(dir: &Utf8Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir.as_str())
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_checkout_git_ref_uses_committed_sources() {
+        let repo = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-git-ref-test-{}", std::process::id())),
+        )
+        .unwrap();
+        let _ = fs::remove_dir_all(&repo);
+        let project = repo.join("contracts");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("Scarb.toml"), "[package]\nname = \"test\"\n").unwrap();
+        fs::write(project.join("src/lib.cairo"), "fn released() {}").unwrap();
+
+        run_git(&repo, &["init", "-q"]);
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "release"]);
+        run_git(&repo, &["tag", "v1.0.0"]);
+        assert_eq!(uncommitted_changes(&project), Some(vec![]));
+        let head = clean_head_commit(&project).unwrap();
+
+        // Uncommitted changes must not end up in the export
+        fs::write(project.join("src/lib.cairo"), "fn work_in_progress() {}").unwrap();
+        assert_eq!(
+            uncommitted_changes(&project),
+            Some(vec!["contracts/src/lib.cairo".to_owned()])
+        );
+        assert_eq!(clean_head_commit(&project), None);
+
+        let staging = Staging::new(repo.join("ledger"), repo.join("tmp"));
+        let checkout = checkout_git_ref(&project, "v1.0.0", &staging).unwrap();
+        assert_eq!(checkout.commit, head);
+        assert!(checkout.warnings.is_empty());
+        assert_eq!(
+            fs::read_to_string(checkout.project_path.join("src/lib.cairo")).unwrap(),
+            "fn released() {}"
+        );
+
+        let staged = checkout.project_path.clone();
+        drop(checkout);
+        assert!(!staged.exists());
+
+        assert!(checkout_git_ref(&project, "does-not-exist", &staging).is_err());
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
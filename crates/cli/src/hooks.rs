@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8Path;
+use console::{style, Emoji};
+
+use crate::errors::CliError;
+
+pub const PRE_VERIFY: &str = "pre_verify";
+pub const POST_SUCCESS: &str = "post_success";
+pub const POST_FAILURE: &str = "post_failure";
+
+/// Name of the hook being run.
+pub const HOOK_VAR: &str = "VOYAGER_HOOK";
+/// Directory of the package being verified, also the working directory of the hook.
+pub const PROJECT_DIR_VAR: &str = "VOYAGER_PROJECT_DIR";
+// Only set for the post hooks, empty when unknown
+pub const NETWORK_VAR: &str = "VOYAGER_NETWORK";
+pub const CLASS_HASH_VAR: &str = "VOYAGER_CLASS_HASH";
+pub const JOB_ID_VAR: &str = "VOYAGER_JOB_ID";
+/// Last status of the job, e.g. `Success` or `CompileFailed`.
+pub const STATUS_VAR: &str = "VOYAGER_STATUS";
+/// Error the verification failed with, for `post_failure`.
+pub const ERROR_VAR: &str = "VOYAGER_ERROR";
+/// Path given with --report.
+pub const REPORT_VAR: &str = "VOYAGER_REPORT";
+
+/**
+ * Shell commands configured in `[tool.voyager.hooks]` of the package manifest:
+ * `pre_verify` runs before the sources are collected and aborts the run when it
+ * fails, `post_success` and `post_failure` run once the verification finished
+ * and only warn when they fail.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    pub pre_verify: Option<String>,
+    pub post_success: Option<String>,
+    pub post_failure: Option<String>,
+}
+
+impl Hooks {
+    pub fn from_manifest(contents: &str) -> Result<Self> {
+        let manifest: toml::Value = contents.parse()?;
+        let Some(hooks) = manifest
+            .get("tool")
+            .and_then(|tool| tool.get("voyager"))
+            .and_then(|voyager| voyager.get("hooks"))
+        else {
+            return Ok(Self::default());
+        };
+        let command = |name: &str| match hooks.get(name) {
+            None => Ok(None),
+            Some(toml::Value::String(command)) => Ok(Some(command.clone())),
+            Some(_) => Err(anyhow!(
+                "{} in [tool.voyager.hooks] must be a command string",
+                name
+            )),
+        };
+        Ok(Self {
+            pre_verify: command(PRE_VERIFY)?,
+            post_success: command(POST_SUCCESS)?,
+            post_failure: command(POST_FAILURE)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/**
+ * Hooks come from the repository, which in CI may be an untrusted pull request,
+ * so they only run there with --allow-hooks.
+ */
+pub fn hooks_allowed(allow_hooks: bool, ci: Option<&str>) -> bool {
+    allow_hooks || !ci.is_some_and(|ci| !ci.is_empty() && ci != "false" && ci != "0")
+}
+
+/**
+ * The hooks of the package at `package_dir`, or none when they aren't allowed.
+ */
+pub fn load_hooks(package_dir: &Utf8Path, allow_hooks: bool) -> Result<Hooks> {
+    let manifest_path = package_dir.join("Scarb.toml");
+    let hooks = Hooks::from_manifest(
+        &fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Unable to read {}", manifest_path))?,
+    )
+    .with_context(|| format!("Unable to read the hooks of {}", manifest_path))?;
+    if hooks.is_empty() || hooks_allowed(allow_hooks, std::env::var("CI").ok().as_deref()) {
+        return Ok(hooks);
+    }
+    println!(
+        "{} {}",
+        Emoji("⚠️ ", ""),
+        style("Skipping the [tool.voyager.hooks] of the project in CI, pass --allow-hooks to run them.")
+            .yellow()
+    );
+    Ok(Hooks::default())
+}
+
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/**
+ * Output of a hook as printed, every line prefixed with the hook name and the
+ * stream it was written to.
+ */
+fn prefixed_output(hook: &str, stdout: &[u8], stderr: &[u8]) -> Vec<String> {
+    let prefixed = |stream: &str, output: &[u8]| {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| format!("[{} {}] {}", hook, stream, line))
+            .collect::<Vec<String>>()
+    };
+    let mut lines = prefixed("stdout", stdout);
+    lines.extend(prefixed("stderr", stderr));
+    lines
+}
+
+/**
+ * Run `command` in `package_dir` with `vars` set on top of `VOYAGER_HOOK` and
+ * `VOYAGER_PROJECT_DIR`. Fails when the command exits with an error.
+ */
+pub fn run_hook(
+    hook: &str,
+    command: &str,
+    package_dir: &Utf8Path,
+    vars: &[(&str, String)],
+) -> Result<()> {
+    println!("Running the {} hook: {}", hook, command);
+    let output = shell(command)
+        .current_dir(package_dir)
+        .env(HOOK_VAR, hook)
+        .env(PROJECT_DIR_VAR, package_dir.as_str())
+        .envs(vars.iter().cloned())
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Unable to run the {} hook", hook))?;
+    for line in prefixed_output(hook, &output.stdout, &output.stderr) {
+        println!("{}", line);
+    }
+    if !output.status.success() {
+        return Err(CliError::HookFailed {
+            hook: hook.to_owned(),
+            status: output.status.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/**
+ * Post hooks never fail the verification, which has already completed.
+ */
+pub fn run_post_hook(hook: &str, command: &str, package_dir: &Utf8Path, vars: &[(&str, String)]) {
+    if let Err(e) = run_hook(hook, command, package_dir, vars) {
+        println!(
+            "{} {}",
+            Emoji("⚠️ ", ""),
+            style(format!("{:#}", e)).yellow()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+    use camino::Utf8PathBuf;
+
+    #[test]
+    fn test_hooks_from_manifest() {
+        let hooks = Hooks::from_manifest(
+            r#"
+            [package]
+            name = "my_token"
+
+            [tool.voyager]
+            my_token = { path = "lib.cairo" }
+
+            [tool.voyager.hooks]
+            pre_verify = "scarb fmt --check"
+            post_success = "./notify.sh"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(hooks.pre_verify.as_deref(), Some("scarb fmt --check"));
+        assert_eq!(hooks.post_success.as_deref(), Some("./notify.sh"));
+        assert_eq!(hooks.post_failure, None);
+
+        assert!(Hooks::from_manifest("[package]\nname = \"my_token\"")
+            .unwrap()
+            .is_empty());
+        assert!(Hooks::from_manifest("[tool.voyager.hooks]\npre_verify = 1").is_err());
+    }
+
+    #[test]
+    fn test_hooks_only_run_in_ci_when_allowed() {
+        assert!(hooks_allowed(false, None));
+        assert!(hooks_allowed(false, Some("false")));
+        assert!(!hooks_allowed(false, Some("true")));
+        assert!(!hooks_allowed(false, Some("1")));
+        assert!(hooks_allowed(true, Some("true")));
+    }
+
+    #[test]
+    fn test_hook_output_is_prefixed() {
+        assert_eq!(
+            prefixed_output("post_success", b"sent\n", b"retrying\ndone\n"),
+            vec![
+                "[post_success stdout] sent",
+                "[post_success stderr] retrying",
+                "[post_success stderr] done",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks_see_their_variables_and_fail_on_error() {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir()).unwrap();
+        let vars = [(JOB_ID_VAR, "job-1".to_owned())];
+
+        run_hook(
+            POST_SUCCESS,
+            r#"test "$VOYAGER_HOOK" = post_success && test "$VOYAGER_JOB_ID" = job-1"#,
+            &dir,
+            &vars,
+        )
+        .unwrap();
+
+        let error = run_hook(PRE_VERIFY, "exit 3", &dir, &[]).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-025"));
+        assert!(error.to_string().contains("exit status: 3"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/**
+ * Human readable duration: `820ms`, `7.2s`, `6m 07s` or `1h 02m`.
+ */
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1_000 {
+        return format!("{}ms", millis);
+    }
+    // Rounded first, so that 59.96s doesn't print as 60.0s
+    let tenths = (millis + 50) / 100;
+    if tenths < 600 {
+        return format!("{}.{}s", tenths / 10, tenths % 10);
+    }
+    let secs = (millis + 500) / 1_000;
+    if secs < 60 * 60 {
+        return format!("{}m {:02}s", secs / 60, secs % 60);
+    }
+    let mins = secs / 60;
+    format!("{}h {:02}m", mins / 60, mins % 60)
+}
+
+/**
+ * Count with thousands separators, e.g. `48,213`.
+ */
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/**
+ * Human readable size, e.g. `1.5 MiB`.
+ */
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/**
+ * A human output whose format changed, announced to `--events` consumers
+ * that may have been parsing it, with the stable field to read instead.
+ *
+ * Numbers printed for people may change from one release to the next. The
+ * machine formats, `--report`, `--events`, the outcome and `--json`, carry
+ * durations in milliseconds and sizes in bytes, and those never change.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FormatChange {
+    /// Start of the line that changed.
+    pub output: &'static str,
+    pub before: &'static str,
+    pub after: &'static str,
+    pub stable_field: &'static str,
+}
+
+/// Every human output whose format changed, oldest first.
+pub const FORMAT_CHANGES: &[FormatChange] = &[
+    FormatChange {
+        output: "Successfully verified in",
+        before: "6 minutes",
+        after: "6m 07s",
+        stable_field: "elapsed_ms of the finished event, duration_ms of --report",
+    },
+    FormatChange {
+        output: "Verification took",
+        before: "367.2s",
+        after: "6m 07s",
+        stable_field: "duration.total_ms of the outcome, duration_ms of --report",
+    },
+    FormatChange {
+        output: "Network:",
+        before: "48213 bytes sent",
+        after: "47.1 KiB sent",
+        stable_field: "transfer.bytes_sent and transfer.bytes_received of --report",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(820)), "820ms");
+        assert_eq!(format_duration(Duration::from_millis(7_240)), "7.2s");
+        assert_eq!(format_duration(Duration::from_millis(59_960)), "1m 00s");
+        assert_eq!(format_duration(Duration::from_millis(367_200)), "6m 07s");
+        assert_eq!(format_duration(Duration::from_secs(3_720)), "1h 02m");
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(48_213), "48,213");
+        assert_eq!(format_count(1_000_000), "1,000,000");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(12), "12 B");
+    }
+}
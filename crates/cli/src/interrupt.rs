@@ -0,0 +1,33 @@
+use std::process;
+use std::sync::OnceLock;
+
+use crate::cancel::CancelToken;
+use crate::exit_codes::CANCELLED_EXIT_CODE;
+
+static TOKEN: OnceLock<CancelToken> = OnceLock::new();
+
+/**
+ * The token cancelled by Ctrl-C, shared by every request of the run.
+ */
+pub fn token() -> &'static CancelToken {
+    TOKEN.get_or_init(CancelToken::new)
+}
+
+/**
+ * Cancel `token()` on the first Ctrl-C, so that the requests in flight and
+ * the waits stop while the job id can still be reported, and exit right away
+ * on the second one.
+ */
+pub fn install() {
+    let installed = ctrlc::set_handler(|| {
+        if token().is_cancelled() {
+            process::exit(CANCELLED_EXIT_CODE.into());
+        }
+        eprintln!("Cancelling, press Ctrl-C again to exit right away");
+        token().cancel();
+    });
+    // Without the handler Ctrl-C still stops the run, only more abruptly
+    if let Err(e) = installed {
+        eprintln!("Warning: Ctrl-C won't cancel cleanly: {}", e);
+    }
+}
@@ -0,0 +1,212 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::errors::{error_code, CliError};
+
+/// Flag asking for failures as JSON, found before the arguments are parsed.
+pub const JSON_ERRORS_FLAG: &str = "--json-errors";
+
+/**
+ * Whether `flag`, `--json-errors` or `--json`, is among the arguments. Looked
+ * up by hand, since it must also apply when clap fails to parse them.
+ */
+pub fn flag_requested<I, S>(args: I, flag: &str) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .skip(1)
+        .take_while(|arg| arg.as_ref() != "--")
+        .any(|arg| arg.as_ref() == flag)
+}
+
+/**
+ * Value of `flag` among the arguments, given as `--flag value` or
+ * `--flag=value`. Looked up by hand like `flag_requested`.
+ */
+pub fn flag_value<I, S>(args: I, flag: &str) -> Option<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args
+        .into_iter()
+        .skip(1)
+        .take_while(|arg| arg.as_ref() != "--");
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        if arg == flag {
+            return args.next().map(|value| value.as_ref().to_owned());
+        }
+        if let Some(value) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
+
+/**
+ * A failure as printed with `--json-errors`, as the last line of stdout, or
+ * with `--json`, as the last line of stderr, whatever stage it happened at:
+ * argument parsing, configuration, checks before submitting or the
+ * verification itself.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ErrorEnvelope {
+    /// Code of the error, see `explain`. `null` for errors without a code yet.
+    pub code: Option<String>,
+    /// Outermost message of the error.
+    pub message: String,
+    /// Underlying causes, outermost first.
+    pub details: Vec<String>,
+    /// Exit code of the process.
+    pub exit_code: u8,
+}
+
+impl ErrorEnvelope {
+    pub fn from_error(error: &anyhow::Error, exit_code: u8) -> Self {
+        let mut chain = error.chain().map(|cause| cause.to_string());
+        Self {
+            code: error_code(error).map(str::to_owned),
+            message: chain.next().unwrap_or_default(),
+            details: chain.collect(),
+            exit_code,
+        }
+    }
+
+    /**
+     * Envelope of an error of clap, whose first line says what is wrong and
+     * the others how to call the command instead.
+     */
+    pub fn from_clap(error: &clap::Error) -> Self {
+        let rendered = error.render().to_string();
+        let mut lines = rendered
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let summary = lines
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("error: ")
+            .to_owned();
+        let invalid = CliError::InvalidArguments(summary);
+        Self {
+            code: Some(invalid.code().to_owned()),
+            message: invalid.to_string(),
+            details: lines.map(str::to_owned).collect(),
+            exit_code: error.exit_code().clamp(0, u8::MAX as i32) as u8,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("strings and integers always serialize")
+    }
+
+    pub fn print(&self) {
+        println!("{}", self.to_json());
+    }
+
+    pub fn eprint(&self) {
+        eprintln!("{}", self.to_json());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_json_errors_flag_is_found_before_parsing() {
+        assert!(flag_requested(
+            ["starknet-contract-verifier", "--json-errors", "--bogus"],
+            JSON_ERRORS_FLAG
+        ));
+        assert!(!flag_requested(
+            ["starknet-contract-verifier"],
+            JSON_ERRORS_FLAG
+        ));
+        // Flags are matched whole
+        assert!(!flag_requested(
+            ["starknet-contract-verifier", "--json-errors"],
+            "--json"
+        ));
+        // The binary itself and positional values after -- don't count
+        assert!(!flag_requested(["--json-errors"], JSON_ERRORS_FLAG));
+        assert!(!flag_requested(
+            ["starknet-contract-verifier", "--", "--json-errors"],
+            JSON_ERRORS_FLAG
+        ));
+    }
+
+    #[test]
+    fn test_output_format_is_found_before_parsing() {
+        assert_eq!(
+            flag_value(
+                ["starknet-contract-verifier", "--output", "json", "--bogus"],
+                "--output"
+            )
+            .as_deref(),
+            Some("json")
+        );
+        assert_eq!(
+            flag_value(["starknet-contract-verifier", "--output=json"], "--output").as_deref(),
+            Some("json")
+        );
+        assert_eq!(
+            flag_value(["starknet-contract-verifier", "--output"], "--output"),
+            None
+        );
+        assert_eq!(
+            flag_value(["starknet-contract-verifier", "--outputs=json"], "--output"),
+            None
+        );
+        assert_eq!(
+            flag_value(
+                ["starknet-contract-verifier", "--", "--output", "json"],
+                "--output"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_envelope_keeps_code_and_causes() {
+        let error = anyhow::Result::<()>::Err(CliError::InvalidConfig.into())
+            .context("Unable to start")
+            .unwrap_err();
+        let envelope = ErrorEnvelope::from_error(&error, 1);
+        assert_eq!(envelope.code.as_deref(), Some("VER-040"));
+        assert_eq!(envelope.message, "Unable to start");
+        assert_eq!(
+            envelope.details,
+            vec!["VER-040: Unable to load the configuration"]
+        );
+
+        let uncoded = ErrorEnvelope::from_error(&anyhow::anyhow!("boom"), 1);
+        assert_eq!(uncoded.code, None);
+        assert!(uncoded.details.is_empty());
+    }
+
+    #[test]
+    fn test_clap_errors_are_invalid_arguments() {
+        use clap::{error::ErrorKind, Command};
+
+        let error = Command::new("verifier")
+            .try_get_matches_from(["verifier", "--bogus"])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownArgument);
+        let envelope = ErrorEnvelope::from_clap(&error);
+        assert_eq!(envelope.code.as_deref(), Some("VER-039"));
+        assert!(envelope.message.contains("--bogus"), "{}", envelope.message);
+        assert!(!envelope.message.contains("error:"), "{}", envelope.message);
+        assert_eq!(envelope.exit_code, 2);
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&envelope).unwrap()).unwrap();
+        assert_eq!(json["code"], "VER-039");
+    }
+}
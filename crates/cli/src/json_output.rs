@@ -0,0 +1,90 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Hidden alias of `--output json`, found before the arguments are parsed.
+pub const JSON_FLAG: &str = "--json";
+
+/// Flag picking the output format, found before the arguments are parsed.
+pub const OUTPUT_FLAG: &str = "--output";
+
+/**
+ * How the results and the failures are printed.
+ */
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Text for the terminal, failures on stderr.
+    #[default]
+    Human,
+    /// JSON lines on stdout, failures as JSON on stderr.
+    Json,
+    /// Text for the terminal, failures as a JSON line on stdout, like `--json-errors`.
+    JsonErrors,
+}
+
+/**
+ * Results printed with `--json`, one JSON object per line. They are written to
+ * the stdout the process started with, while everything else printed there is
+ * sent to stderr, so that stdout carries nothing but them.
+ */
+pub struct JsonOutput {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl fmt::Debug for JsonOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonOutput").finish_non_exhaustive()
+    }
+}
+
+impl JsonOutput {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /**
+     * Writes to a duplicate of stdout, which keeps pointing to it once stdout
+     * is redirected to stderr.
+     */
+    pub fn stdout() -> io::Result<Self> {
+        Ok(Self::new(Box::new(duplicate_stdout()?)))
+    }
+
+    pub fn emit<T: Serialize>(&self, value: &T) {
+        let line = serde_json::to_string(value).expect("results always serialize");
+        let mut writer = self.writer.lock().unwrap();
+        // Like the progress output, a closed pipe doesn't fail the verification
+        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+    }
+}
+
+#[cfg(unix)]
+fn duplicate_stdout() -> io::Result<File> {
+    use std::os::fd::AsFd;
+    Ok(File::from(io::stdout().as_fd().try_clone_to_owned()?))
+}
+
+#[cfg(windows)]
+fn duplicate_stdout() -> io::Result<File> {
+    use std::os::windows::io::AsHandle;
+    Ok(File::from(io::stdout().as_handle().try_clone_to_owned()?))
+}
+
+/**
+ * Printed with `--json` as soon as the job is known, dispatched or attached
+ * to, so that it can be followed even if the verifier is stopped.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DispatchedJob {
+    pub job_id: String,
+    pub class_hash: String,
+    pub network: String,
+}
@@ -0,0 +1,229 @@
+use std::{fs, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use starknet::core::types::contract::SierraClass;
+
+use crate::class_hash::ClassHash;
+use crate::errors::VerifierError;
+
+/**
+ * Computes the hash of Sierra contract classes of one version. Starknet
+ * changed how classes are hashed before, so each version gets a hasher of its
+ * own instead of the comparison assuming one, see `hasher_for`.
+ */
+pub trait ClassHasher {
+    /// The `contract_class_version` of the classes it hashes.
+    fn version(&self) -> &'static str;
+
+    fn class_hash(&self, class: &SierraClass) -> Result<ClassHash>;
+}
+
+/**
+ * Poseidon hash over the entry points, the Sierra program and the keccak of
+ * the ABI, of the classes declared since Starknet 0.11.
+ */
+pub struct SierraV010Hasher;
+
+impl ClassHasher for SierraV010Hasher {
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn class_hash(&self, class: &SierraClass) -> Result<ClassHash> {
+        ClassHash::from_str(&format!("{:#x}", class.class_hash()?))
+    }
+}
+
+/// Every class version whose hash can be computed locally.
+pub const CLASS_HASHERS: &[&dyn ClassHasher] = &[&SierraV010Hasher];
+
+/**
+ * The hasher of the classes of `version`, that of the declared class when the
+ * node returns it, else that of the compiled artifact.
+ */
+pub fn hasher_for(version: &str) -> Result<&'static dyn ClassHasher> {
+    CLASS_HASHERS
+        .iter()
+        .copied()
+        .find(|hasher| hasher.version() == version)
+        .ok_or_else(|| {
+            anyhow!(
+                "Unable to hash classes of version {}, the known versions are {}. Use --skip-hash-check to submit anyway",
+                version,
+                CLASS_HASHERS
+                    .iter()
+                    .map(|hasher| hasher.version())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/**
+ * A contract class the sources compiled to, with the hash it is declared
+ * under and the class version it was hashed as.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalClass {
+    pub artifact: Utf8PathBuf,
+    pub hash: ClassHash,
+    pub version: &'static str,
+}
+
+/**
+ * Class hash of a Sierra contract class artifact, as written by Scarb, hashed
+ * as `declared_version` when known, else as its own version.
+ */
+pub fn sierra_class_hash(
+    artifact: &Utf8Path,
+    declared_version: Option<&str>,
+) -> Result<LocalClass> {
+    let contents = fs::read_to_string(artifact)
+        .with_context(|| format!("Unable to read the contract class {}", artifact))?;
+    let class: SierraClass = serde_json::from_str(&contents)
+        .with_context(|| format!("Invalid contract class {}", artifact))?;
+    let hasher = hasher_for(declared_version.unwrap_or(class.contract_class_version.as_str()))?;
+    let hash = hasher
+        .class_hash(&class)
+        .with_context(|| format!("Unable to compute the class hash of {}", artifact))?;
+    Ok(LocalClass {
+        artifact: artifact.to_owned(),
+        hash,
+        version: hasher.version(),
+    })
+}
+
+pub fn local_classes(
+    artifacts: &[Utf8PathBuf],
+    declared_version: Option<&str>,
+) -> Result<Vec<LocalClass>> {
+    artifacts
+        .iter()
+        .map(|artifact| sierra_class_hash(artifact, declared_version))
+        .collect()
+}
+
+/**
+ * The class among `classes` declared as `given`, failing with every hash
+ * the sources compile to when none is, i.e. the sources aren't those of the
+ * declared class.
+ */
+pub fn check_local_hash<'a>(
+    given: &ClassHash,
+    classes: &'a [LocalClass],
+) -> Result<&'a LocalClass> {
+    classes
+        .iter()
+        .find(|class| class.hash == *given)
+        .ok_or_else(|| {
+            VerifierError::LocalHashMismatch {
+                given: given.to_string(),
+                local: classes
+                    .iter()
+                    .map(|class| {
+                        format!(
+                            "{} ({}, class version {})",
+                            class.hash, class.artifact, class.version
+                        )
+                    })
+                    .collect(),
+            }
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+
+    /// Contracts compiled by Cairo 2.6.4, with the hash they are declared under,
+    /// for each class version there is a hasher of.
+    const KNOWN_CLASSES: &[(&str, &str, &str)] = &[
+        (
+            "0.1.0",
+            "minimal_contract.contract_class.json",
+            "0x3c688ffe27c87b217eb299a6d623319ff0914f467c1ef06043e8dd9e9ba4049",
+        ),
+        (
+            "0.1.0",
+            "hello_starknet.contract_class.json",
+            "0x7a107b80f225e95a58e6ba915c89fa4676b7bc439eeac6c38d56616c7f313c4",
+        ),
+    ];
+
+    fn local_class(name: &str, hash: &str) -> LocalClass {
+        LocalClass {
+            artifact: Utf8PathBuf::from(format!("target/dev/token_{}.contract_class.json", name)),
+            hash: ClassHash::from_str(hash).unwrap(),
+            version: "0.1.0",
+        }
+    }
+
+    #[test]
+    fn test_given_hash_must_be_one_of_the_compiled_classes() {
+        let classes = [
+            local_class("MyToken", "0x044dc2b3"),
+            local_class("Vault", "0x0abc"),
+        ];
+        let given = ClassHash::from_str("0xABC").unwrap();
+        assert_eq!(check_local_hash(&given, &classes).unwrap(), &classes[1]);
+
+        let given = ClassHash::from_str("0x123").unwrap();
+        let error = check_local_hash(&given, &classes).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-060"));
+        let message = error.to_string();
+        assert!(message.contains("0x123"), "{}", message);
+        assert!(
+            message.contains(
+                "0x44dc2b3 (target/dev/token_MyToken.contract_class.json, class version 0.1.0)"
+            ),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_known_class_hashes() {
+        for hasher in CLASS_HASHERS {
+            assert!(
+                KNOWN_CLASSES
+                    .iter()
+                    .any(|(version, ..)| *version == hasher.version()),
+                "No known class of version {}",
+                hasher.version()
+            );
+        }
+        let dir = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_data/classes");
+        for (version, file, hash) in KNOWN_CLASSES {
+            let class = sierra_class_hash(&dir.join(file), None).unwrap();
+            assert_eq!(class.hash, ClassHash::from_str(hash).unwrap(), "{}", file);
+            assert_eq!(class.version, *version, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_invalid_artifact_is_reported() {
+        let dir = std::env::temp_dir().join(format!("voyager-local-hash-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let artifact = Utf8PathBuf::from_path_buf(dir.join("broken.contract_class.json")).unwrap();
+        fs::write(&artifact, "{}").unwrap();
+
+        let error = sierra_class_hash(&artifact, None).unwrap_err();
+        assert!(
+            error.to_string().starts_with("Invalid contract class"),
+            "{}",
+            error
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_class_versions_are_not_hashed() {
+        assert_eq!(hasher_for("0.1.0").unwrap().version(), "0.1.0");
+        let error = hasher_for("0.2.0").err().unwrap().to_string();
+        assert!(error.contains("0.2.0"), "{}", error);
+        assert!(error.contains("the known versions are 0.1.0"), "{}", error);
+    }
+}
@@ -0,0 +1,296 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::VerifierError;
+
+/// Scarb version a locked run must detect, from the user config or the environment.
+pub const SCARB_VERSION_VAR: &str = "VOYAGER_SCARB_VERSION";
+/// Cairo version a locked run must detect, from the user config or the environment.
+pub const CAIRO_VERSION_VAR: &str = "VOYAGER_CAIRO_VERSION";
+
+/// Packages shipped with Scarb, never listed in Scarb.lock.
+const BUILTIN_PACKAGES: [&str; 4] = ["core", "starknet", "cairo_test", "assert_macros"];
+
+/**
+ * How much nondeterminism a run tolerates, see `--locked` and `--frozen`.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Reproducibility {
+    #[default]
+    Unlocked,
+    /// Up-to-date Scarb.lock and pinned compiler versions.
+    Locked,
+    /// Locked, and no network access besides the verification endpoints.
+    Frozen,
+}
+
+impl Reproducibility {
+    pub fn from_flags(locked: bool, frozen: bool) -> Self {
+        if frozen {
+            Reproducibility::Frozen
+        } else if locked {
+            Reproducibility::Locked
+        } else {
+            Reproducibility::Unlocked
+        }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        *self == Reproducibility::Unlocked
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        *self == Reproducibility::Frozen
+    }
+
+    fn flag(&self) -> &'static str {
+        match self {
+            Reproducibility::Frozen => "--frozen",
+            _ => "--locked",
+        }
+    }
+
+    /**
+     * Fail a locked run that would need `what`, e.g. a prompt.
+     */
+    pub fn forbid(&self, what: &str, hint: &str) -> Result<()> {
+        if self.is_unlocked() {
+            return Ok(());
+        }
+        Err(VerifierError::NotReproducible {
+            flag: self.flag().to_owned(),
+            what: what.to_owned(),
+            hint: hint.to_owned(),
+        }
+        .into())
+    }
+
+    /**
+     * Fail a frozen run that would need `what`, some network access.
+     */
+    pub fn forbid_network(&self, what: &str, hint: &str) -> Result<()> {
+        match self.is_frozen() {
+            true => self.forbid(what, hint),
+            false => Ok(()),
+        }
+    }
+}
+
+/**
+ * The Scarb.lock of the package, in its directory or the closest parent, as
+ * the lockfile of a workspace is kept at its root.
+ */
+pub fn find_lockfile(package_dir: &Utf8Path) -> Option<Utf8PathBuf> {
+    package_dir
+        .ancestors()
+        .map(|dir| dir.join("Scarb.lock"))
+        .find(|lockfile| lockfile.is_file())
+}
+
+/**
+ * Check that Scarb.lock exists and pins the package at its current version
+ * along with each of its dependencies. A lockfile that doesn't would be
+ * rewritten by the build, resolving the dependencies anew.
+ */
+pub fn check_lockfile(package_dir: &Utf8Path, reproducibility: Reproducibility) -> Result<()> {
+    let Some(lockfile) = find_lockfile(package_dir) else {
+        return reproducibility.forbid(
+            "a missing Scarb.lock",
+            "run `scarb build` once and commit Scarb.lock",
+        );
+    };
+    let manifest_path = package_dir.join("Scarb.toml");
+    let manifest: toml::Value = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Unable to read {}", manifest_path))?
+        .parse()
+        .with_context(|| format!("Unable to parse {}", manifest_path))?;
+    let lock: toml::Value = fs::read_to_string(&lockfile)
+        .with_context(|| format!("Unable to read {}", lockfile))?
+        .parse()
+        .with_context(|| format!("Unable to parse {}", lockfile))?;
+
+    let unpinned = unpinned_packages(&manifest, &lock);
+    if unpinned.is_empty() {
+        return Ok(());
+    }
+    reproducibility.forbid(
+        &format!("an outdated {} (missing {})", lockfile, unpinned.join(", ")),
+        "run `scarb build` and commit the updated Scarb.lock",
+    )
+}
+
+/**
+ * Packages of the manifest missing from the lockfile: the package itself at
+ * its current version, and its dependencies.
+ */
+fn unpinned_packages(manifest: &toml::Value, lock: &toml::Value) -> Vec<String> {
+    let locked: Vec<(&str, Option<&str>)> = lock
+        .get("package")
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            let name = package.get("name")?.as_str()?;
+            Some((name, package.get("version").and_then(toml::Value::as_str)))
+        })
+        .collect();
+    let mut unpinned = vec![];
+
+    let package = manifest.get("package");
+    let name = package
+        .and_then(|package| package.get("name"))
+        .and_then(toml::Value::as_str);
+    let version = package
+        .and_then(|package| package.get("version"))
+        .and_then(toml::Value::as_str);
+    if let Some(name) = name {
+        if !locked.contains(&(name, version)) {
+            unpinned.push(match version {
+                Some(version) => format!("{} {}", name, version),
+                None => name.to_owned(),
+            });
+        }
+    }
+
+    let dependencies = manifest
+        .get("dependencies")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| !BUILTIN_PACKAGES.contains(name));
+    for dependency in dependencies {
+        if !locked.iter().any(|(name, _)| *name == dependency) {
+            unpinned.push(dependency.to_owned());
+        }
+    }
+    unpinned
+}
+
+/**
+ * Check that the detected tool versions are the pinned ones, so that a locked
+ * run never silently switches to another compiler.
+ */
+pub fn check_pinned_versions(
+    detected: &[(&str, String)],
+    pinned: impl Fn(&str) -> Option<String>,
+    reproducibility: Reproducibility,
+) -> Result<()> {
+    for (var, detected) in detected {
+        match pinned(var) {
+            Some(version) if version.trim() == detected => (),
+            Some(version) => reproducibility.forbid(
+                &format!(
+                    "running with {} while {} pins {}",
+                    detected,
+                    var,
+                    version.trim()
+                ),
+                "install the pinned version or update the pin",
+            )?,
+            None => reproducibility.forbid(
+                &format!("an unpinned compiler version (detected {})", detected),
+                &format!("set {} in the user config", var),
+            )?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpinned_packages() {
+        let manifest: toml::Value = r#"
+            [package]
+            name = "my_token"
+            version = "0.2.0"
+
+            [dependencies]
+            starknet = "2.8.4"
+            openzeppelin = "0.17.0"
+            my_lib = { path = "../my_lib" }
+        "#
+        .parse()
+        .unwrap();
+        let lock = |packages: &str| -> toml::Value { packages.parse().unwrap() };
+
+        let up_to_date = lock(
+            r#"
+            version = 1
+
+            [[package]]
+            name = "my_token"
+            version = "0.2.0"
+            dependencies = ["my_lib", "openzeppelin"]
+
+            [[package]]
+            name = "my_lib"
+            version = "0.1.0"
+
+            [[package]]
+            name = "openzeppelin"
+            version = "0.17.0"
+            source = "registry+https://scarbs.xyz/"
+        "#,
+        );
+        assert!(unpinned_packages(&manifest, &up_to_date).is_empty());
+
+        let outdated = lock(
+            r#"
+            version = 1
+
+            [[package]]
+            name = "my_token"
+            version = "0.1.0"
+            dependencies = ["my_lib"]
+
+            [[package]]
+            name = "my_lib"
+            version = "0.1.0"
+        "#,
+        );
+        assert_eq!(
+            unpinned_packages(&manifest, &outdated),
+            vec!["my_token 0.2.0", "openzeppelin"]
+        );
+    }
+
+    #[test]
+    fn test_pinned_versions() {
+        let detected = [(SCARB_VERSION_VAR, "2.8.4".to_owned())];
+        let pinned = |version: &'static str| move |_: &str| Some(version.to_owned());
+
+        assert!(check_pinned_versions(&detected, pinned("2.8.4"), Reproducibility::Locked).is_ok());
+        let error =
+            check_pinned_versions(&detected, pinned("2.9.1"), Reproducibility::Frozen).unwrap_err();
+        assert!(error.to_string().contains("--frozen"));
+        assert!(error
+            .to_string()
+            .contains("VOYAGER_SCARB_VERSION pins 2.9.1"));
+
+        let error =
+            check_pinned_versions(&detected, |_| None, Reproducibility::Locked).unwrap_err();
+        assert!(error.to_string().contains("set VOYAGER_SCARB_VERSION"));
+        // Unlocked runs follow whatever is installed
+        assert!(check_pinned_versions(&detected, |_| None, Reproducibility::Unlocked).is_ok());
+    }
+
+    #[test]
+    fn test_only_frozen_runs_forbid_the_network() {
+        assert!(Reproducibility::Locked
+            .forbid_network("endpoint discovery", "")
+            .is_ok());
+        assert!(Reproducibility::Frozen
+            .forbid_network("endpoint discovery", "")
+            .is_err());
+        assert!(Reproducibility::Unlocked.forbid("a prompt", "").is_ok());
+    }
+}
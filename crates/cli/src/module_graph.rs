@@ -0,0 +1,407 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::sources::SourceSet;
+use crate::syntax_scan::{code_lines, is_identifier_char};
+
+/**
+ * What a `mod` declaration is followed by.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModBody {
+    /// `mod foo;`, the module is in a file of its own.
+    File,
+    /// `mod foo { .. }`, marked `#[starknet::contract]` or not.
+    Inline { contract: bool },
+}
+
+/**
+ * A `mod` declaration of a Cairo file.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModDeclaration {
+    pub name: String,
+    /// Counted from 1.
+    pub line: usize,
+    /// Inline modules it is declared in, the outermost first.
+    pub parents: Vec<String>,
+    pub body: ModBody,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Identifier(&'a str),
+    Punctuation(char),
+}
+
+/**
+ * Identifiers and punctuation of `source` with their line, without comments
+ * and literals, which could hold braces or `mod`.
+ */
+fn tokens(source: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = vec![];
+    for (line, code) in code_lines(source) {
+        let mut chars = code.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if is_identifier_char(c) {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|(_, c)| is_identifier_char(*c)) {
+                    end = index + c.len_utf8();
+                }
+                tokens.push((line, Token::Identifier(&code[start..end])));
+            } else if c == '"' || c == '\'' {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+            } else if !c.is_whitespace() {
+                tokens.push((line, Token::Punctuation(c)));
+            }
+        }
+    }
+    tokens
+}
+
+/**
+ * Whether the attribute starting at `tokens[0]`, after its `#[`, is
+ * `#[starknet::contract]`, and the index of the token following it.
+ */
+fn attribute(tokens: &[(usize, Token)]) -> (bool, usize) {
+    let path: Vec<Token> = tokens.iter().take(4).map(|(_, token)| *token).collect();
+    let contract = path
+        == [
+            Token::Identifier("starknet"),
+            Token::Punctuation(':'),
+            Token::Punctuation(':'),
+            Token::Identifier("contract"),
+        ];
+    let mut depth = 1;
+    for (index, (_, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Punctuation('[') => depth += 1,
+            Token::Punctuation(']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return (contract, index + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    (contract, tokens.len())
+}
+
+/**
+ * The `mod` declarations of `source`, inline modules included, in the order
+ * they are declared. Only the tokens are looked at, like the syntax scan:
+ * modules generated by macros aren't found.
+ */
+pub fn mod_declarations(source: &str) -> Vec<ModDeclaration> {
+    let tokens = tokens(source);
+    let mut declarations = vec![];
+    // The inline module each open brace starts, if it starts one
+    let mut braces: Vec<Option<String>> = vec![];
+    let mut contract = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i].1 {
+            Token::Punctuation('#')
+                if tokens.get(i + 1).map(|(_, t)| *t) == Some(Token::Punctuation('[')) =>
+            {
+                let (is_contract, length) = attribute(&tokens[i + 2..]);
+                contract |= is_contract;
+                i += 2 + length;
+                continue;
+            }
+            // Visibility, e.g. `pub(crate)`, doesn't end the attributes
+            Token::Identifier("pub") => {
+                i += 1;
+                if tokens.get(i).map(|(_, t)| *t) == Some(Token::Punctuation('(')) {
+                    while i < tokens.len() && tokens[i].1 != Token::Punctuation(')') {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+            Token::Identifier("mod") => {
+                if let (Some((line, Token::Identifier(name))), Some((_, next))) =
+                    (tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    let body = match next {
+                        Token::Punctuation(';') => Some(ModBody::File),
+                        Token::Punctuation('{') => Some(ModBody::Inline { contract }),
+                        _ => None,
+                    };
+                    if let Some(body) = body {
+                        declarations.push(ModDeclaration {
+                            name: name.to_string(),
+                            line: *line,
+                            parents: braces.iter().flatten().cloned().collect(),
+                            body,
+                        });
+                        if let ModBody::Inline { .. } = body {
+                            braces.push(Some(name.to_string()));
+                        }
+                        i += 3;
+                        contract = false;
+                        continue;
+                    }
+                }
+            }
+            Token::Punctuation('{') => braces.push(None),
+            Token::Punctuation('}') => {
+                braces.pop();
+            }
+            _ => (),
+        }
+        contract = false;
+        i += 1;
+    }
+    declarations
+}
+
+/**
+ * Directory of the files of the modules `file` declares with `mod foo;`:
+ * that of `crate_root` for the crate root, and one named after the file for
+ * the others, e.g. `src/token/` for `src/token.cairo`.
+ */
+pub fn module_dir(file: &str, crate_root: &str) -> String {
+    if file == crate_root {
+        return file.rsplit_once('/').map_or("", |(dir, _)| dir).to_owned();
+    }
+    file.strip_suffix(".cairo").unwrap_or(file).to_owned()
+}
+
+/**
+ * A `#[starknet::contract]` module.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractModule {
+    pub name: String,
+    /// Path of the file defining it.
+    pub file: String,
+    pub line: usize,
+    /// Whether it is reached through a `mod foo;` declaration rather than
+    /// defined in the file the walk started from.
+    pub in_other_file: bool,
+}
+
+/**
+ * The contract modules of `file` and of the modules it declares, inline or
+ * in files of their own, in `sources`. `crate_root` is the path of the root
+ * file of the crate, see `module_dir`. Declared files missing from `sources`
+ * are skipped.
+ */
+pub fn contract_modules(sources: &SourceSet, file: &str, crate_root: &str) -> Vec<ContractModule> {
+    let mut contracts = vec![];
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([file.to_owned()]);
+    while let Some(current) = queue.pop_front() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        let Some(contents) = sources.contents(&current) else {
+            continue;
+        };
+        let dir = module_dir(&current, crate_root);
+        for declaration in mod_declarations(&String::from_utf8_lossy(contents)) {
+            match declaration.body {
+                ModBody::File => {
+                    let path: Vec<&str> = std::iter::once(dir.as_str())
+                        .chain(declaration.parents.iter().map(String::as_str))
+                        .filter(|segment| !segment.is_empty())
+                        .collect();
+                    let path = match path.is_empty() {
+                        true => format!("{}.cairo", declaration.name),
+                        false => format!("{}/{}.cairo", path.join("/"), declaration.name),
+                    };
+                    queue.push_back(path);
+                }
+                ModBody::Inline { contract: true } => contracts.push(ContractModule {
+                    name: declaration.name,
+                    file: current.clone(),
+                    line: declaration.line,
+                    in_other_file: current != file,
+                }),
+                ModBody::Inline { contract: false } => (),
+            }
+        }
+    }
+    contracts
+}
+
+/**
+ * Whether the `[tool.voyager]` name of a contract refers to the module
+ * `module`: they are compared regardless of case and underscores, since
+ * the name is often the module's in another case, e.g. `erc_20` for `ERC20`.
+ */
+pub fn names_module(name: &str, module: &str) -> bool {
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(|c| *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    normalize(name) == normalize(module)
+}
+
+/**
+ * Where the contract selected with `contract_file` is defined.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractFileCheck {
+    /// In the file, directly or through inline modules.
+    Defines(ContractModule),
+    /// In another file, which the file only declares or re-exports.
+    Reexports(ContractModule),
+    NotFound,
+}
+
+/**
+ * Find the contract module `contract` names among those `contract_file`
+ * declares, see `contract_modules`. When none is named after it, the name
+ * is taken as a mere label and any contract module is accepted.
+ */
+pub fn check_contract_file(
+    sources: &SourceSet,
+    contract_file: &str,
+    crate_root: &str,
+    contract: &str,
+) -> ContractFileCheck {
+    let contracts = contract_modules(sources, contract_file, crate_root);
+    let named: Vec<&ContractModule> = contracts
+        .iter()
+        .filter(|module| names_module(contract, &module.name))
+        .collect();
+    let candidates = match named.is_empty() {
+        true => contracts.iter().collect(),
+        false => named,
+    };
+    if let Some(module) = candidates.iter().find(|module| !module.in_other_file) {
+        return ContractFileCheck::Defines((*module).clone());
+    }
+    match candidates.first() {
+        Some(module) => ContractFileCheck::Reexports((*module).clone()),
+        None => ContractFileCheck::NotFound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: [(&str, &str); 4] = [
+        (
+            "token/src/lib.cairo",
+            include_str!("../tests/test_data/modules/lib.cairo"),
+        ),
+        (
+            "token/src/token.cairo",
+            include_str!("../tests/test_data/modules/token.cairo"),
+        ),
+        (
+            "token/src/token/erc20.cairo",
+            include_str!("../tests/test_data/modules/token/erc20.cairo"),
+        ),
+        (
+            "token/src/utils/math.cairo",
+            include_str!("../tests/test_data/modules/utils/math.cairo"),
+        ),
+    ];
+    const CRATE_ROOT: &str = "token/src/lib.cairo";
+
+    fn sources() -> SourceSet {
+        let mut sources = SourceSet::new();
+        for (path, contents) in FIXTURES {
+            sources.add(path, contents).unwrap();
+        }
+        sources
+    }
+
+    #[test]
+    fn test_file_and_inline_modules_are_declared() {
+        let declarations = mod_declarations(FIXTURES[0].1);
+        let names: Vec<(&str, Vec<String>, ModBody)> = declarations
+            .iter()
+            .map(|d| (d.name.as_str(), d.parents.clone(), d.body))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("token", vec![], ModBody::File),
+                ("utils", vec![], ModBody::Inline { contract: false }),
+                ("math", vec!["utils".to_owned()], ModBody::File),
+                (
+                    "constants",
+                    vec!["utils".to_owned()],
+                    ModBody::Inline { contract: false }
+                ),
+            ]
+        );
+        assert_eq!(declarations[2].line, 4);
+
+        // Neither the comment nor the literal declare anything
+        let declarations = mod_declarations(FIXTURES[2].1);
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[1].name, "MyToken");
+        assert_eq!(declarations[1].parents, ["presets"]);
+        assert_eq!(declarations[1].body, ModBody::Inline { contract: true });
+    }
+
+    #[test]
+    fn test_module_files_follow_the_crate_layout() {
+        assert_eq!(module_dir(CRATE_ROOT, CRATE_ROOT), "token/src");
+        assert_eq!(
+            module_dir("token/src/token.cairo", CRATE_ROOT),
+            "token/src/token"
+        );
+
+        let contracts = contract_modules(&sources(), CRATE_ROOT, CRATE_ROOT);
+        assert_eq!(
+            contracts,
+            [ContractModule {
+                name: "MyToken".to_owned(),
+                file: "token/src/token/erc20.cairo".to_owned(),
+                line: 8,
+                in_other_file: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reexports_point_at_the_defining_file() {
+        let sources = sources();
+        match check_contract_file(&sources, CRATE_ROOT, CRATE_ROOT, "my_token") {
+            ContractFileCheck::Reexports(module) => {
+                assert_eq!(module.file, "token/src/token/erc20.cairo")
+            }
+            check => panic!("unexpected {:?}", check),
+        }
+        assert!(matches!(
+            check_contract_file(
+                &sources,
+                "token/src/token/erc20.cairo",
+                CRATE_ROOT,
+                "MyToken"
+            ),
+            ContractFileCheck::Defines(_)
+        ));
+        // The name is only a label when no module is named after it
+        assert!(matches!(
+            check_contract_file(&sources, "token/src/token/erc20.cairo", CRATE_ROOT, "token"),
+            ContractFileCheck::Defines(_)
+        ));
+        assert_eq!(
+            check_contract_file(
+                &sources,
+                "token/src/utils/math.cairo",
+                CRATE_ROOT,
+                "MyToken"
+            ),
+            ContractFileCheck::NotFound
+        );
+    }
+}
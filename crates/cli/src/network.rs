@@ -0,0 +1,225 @@
+use std::{fmt::Display, time::Duration};
+
+use anyhow::{anyhow, Result};
+use dialoguer::{theme::ColorfulTheme, Input};
+use url::Url;
+
+use crate::ip_version;
+use crate::transfer::{self, DISCOVERY_ENDPOINT};
+
+pub const DISCOVERY_PATH: &str = ".well-known/voyager.json";
+pub const CONVENTIONAL_PUBLIC_API_PATH: &str = "api";
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * Document served by self-hosted instances at `/.well-known/voyager.json`
+ * describing where each of their endpoints lives. Relative urls are resolved
+ * against the instance base url.
+ */
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DiscoveryDocument {
+    pub internal_api_url: Option<String>,
+    pub public_api_url: Option<String>,
+    pub explorer_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointSource {
+    Discovery,
+    Convention,
+}
+
+impl Display for EndpointSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointSource::Discovery => write!(f, "discovery document"),
+            EndpointSource::Convention => write!(f, "conventional paths"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedEndpoints {
+    pub base: Url,
+    pub internal: Url,
+    pub public: Url,
+    pub explorer: Url,
+    pub source: EndpointSource,
+}
+
+pub fn is_network_url(network: &str) -> bool {
+    network.starts_with("http://") || network.starts_with("https://")
+}
+
+/**
+ * Parse the base url, making sure it is treated as a directory when joining paths.
+ */
+pub fn parse_base_url(base: &str) -> Result<Url> {
+    let mut url = Url::parse(base).map_err(|e| anyhow!("Invalid network url {}: {}", base, e))?;
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+/**
+ * Derive every endpoint of a self-hosted instance from its base url. Values of the
+ * discovery document take precedence, anything missing falls back to conventions:
+ * the internal api and explorer live at the base, the public api under `/api`.
+ */
+pub fn derive_endpoints(
+    base: &Url,
+    discovery: Option<&DiscoveryDocument>,
+) -> Result<DerivedEndpoints> {
+    let resolve = |value: Option<&String>, default: &str| -> Result<Url> {
+        let target = value.map(String::as_str).unwrap_or(default);
+        base.join(target)
+            .map_err(|e| anyhow!("Invalid endpoint {} for {}: {}", target, base, e))
+    };
+
+    let document = match discovery {
+        Some(document) => document,
+        None => &DiscoveryDocument::default(),
+    };
+
+    Ok(DerivedEndpoints {
+        base: base.clone(),
+        internal: resolve(document.internal_api_url.as_ref(), "")?,
+        public: resolve(
+            document.public_api_url.as_ref(),
+            CONVENTIONAL_PUBLIC_API_PATH,
+        )?,
+        explorer: resolve(document.explorer_url.as_ref(), "")?,
+        source: if discovery.is_some() {
+            EndpointSource::Discovery
+        } else {
+            EndpointSource::Convention
+        },
+    })
+}
+
+pub fn fetch_discovery_document(base: &Url) -> Option<DiscoveryDocument> {
+    let client = ip_version::client_builder()
+        .timeout(DISCOVERY_TIMEOUT)
+        .build()
+        .ok()?;
+    let log = transfer::global();
+    log.record_request(DISCOVERY_ENDPOINT, false);
+    let response = client.get(base.join(DISCOVERY_PATH).ok()?).send().ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    serde_json::from_slice(&log.read_body(DISCOVERY_ENDPOINT, response).ok()?).ok()
+}
+
+/**
+ * Endpoint urls are concatenated with paths starting with `/`, strip the trailing one.
+ */
+pub fn as_endpoint(url: &Url) -> String {
+    url.as_str().trim_end_matches('/').to_owned()
+}
+
+/**
+ * Derive the endpoints of a self-hosted instance from its base url. When the
+ * instance doesn't serve a discovery document and we can prompt, the user is
+ * asked for explicit urls, defaulting to the conventional ones.
+ */
+pub fn resolve_network_url(base: &str, interactive: bool) -> Result<DerivedEndpoints> {
+    let base = parse_base_url(base)?;
+    let discovery = fetch_discovery_document(&base);
+    let mut endpoints = derive_endpoints(&base, discovery.as_ref())?;
+    if discovery.is_none() && interactive {
+        println!(
+            "No discovery document found at {}, please confirm the endpoints.",
+            base
+        );
+        let internal: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Internal endpoint url: ")
+            .default(as_endpoint(&endpoints.internal))
+            .interact_text()?;
+        let public: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Public endpoint url: ")
+            .default(as_endpoint(&endpoints.public))
+            .interact_text()?;
+        endpoints.internal = parse_base_url(internal.trim())?;
+        endpoints.public = parse_base_url(public.trim())?;
+    }
+    Ok(endpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_network_url() {
+        assert!(is_network_url("https://verifier.mycorp.dev"));
+        assert!(is_network_url("http://localhost:8899"));
+        assert!(!is_network_url("sepolia"));
+    }
+
+    #[test]
+    fn test_derive_endpoints_by_convention() {
+        let base = parse_base_url("https://verifier.mycorp.dev").unwrap();
+        let endpoints = derive_endpoints(&base, None).unwrap();
+
+        assert_eq!(endpoints.source, EndpointSource::Convention);
+        assert_eq!(
+            as_endpoint(&endpoints.internal),
+            "https://verifier.mycorp.dev"
+        );
+        assert_eq!(
+            as_endpoint(&endpoints.public),
+            "https://verifier.mycorp.dev/api"
+        );
+        assert_eq!(
+            as_endpoint(&endpoints.explorer),
+            "https://verifier.mycorp.dev"
+        );
+    }
+
+    #[test]
+    fn test_derive_endpoints_keeps_base_path() {
+        let base = parse_base_url("https://mycorp.dev/verifier").unwrap();
+        let endpoints = derive_endpoints(&base, None).unwrap();
+
+        assert_eq!(
+            as_endpoint(&endpoints.internal),
+            "https://mycorp.dev/verifier"
+        );
+        assert_eq!(
+            as_endpoint(&endpoints.public),
+            "https://mycorp.dev/verifier/api"
+        );
+    }
+
+    #[test]
+    fn test_derive_endpoints_from_discovery() {
+        let base = parse_base_url("https://verifier.mycorp.dev/").unwrap();
+        let document = DiscoveryDocument {
+            internal_api_url: None,
+            public_api_url: Some("https://api.mycorp.dev/beta".to_owned()),
+            explorer_url: Some("explorer/".to_owned()),
+        };
+        let endpoints = derive_endpoints(&base, Some(&document)).unwrap();
+
+        assert_eq!(endpoints.source, EndpointSource::Discovery);
+        assert_eq!(
+            as_endpoint(&endpoints.internal),
+            "https://verifier.mycorp.dev"
+        );
+        assert_eq!(
+            as_endpoint(&endpoints.public),
+            "https://api.mycorp.dev/beta"
+        );
+        assert_eq!(
+            as_endpoint(&endpoints.explorer),
+            "https://verifier.mycorp.dev/explorer"
+        );
+    }
+
+    #[test]
+    fn test_invalid_network_url() {
+        assert!(parse_base_url("https://").is_err());
+    }
+}
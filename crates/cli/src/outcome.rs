@@ -0,0 +1,261 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Serialize, Serializer};
+
+use crate::api::VerifyJobStatus;
+use crate::errors::error_code;
+use crate::humanize::format_duration;
+use crate::report::StatusObservation;
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/**
+ * Where the time of a verification went. The remote phases are as observed
+ * while polling, so each one may be late by up to a poll interval.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct OutcomeDuration {
+    /// Whole verification, resubmissions included.
+    #[serde(rename = "total_ms", serialize_with = "as_millis")]
+    #[schemars(with = "u64")]
+    pub total: Duration,
+    /// Sending the payload of the last attempt, zero when attached to a job.
+    #[serde(rename = "upload_ms", serialize_with = "as_millis")]
+    #[schemars(with = "u64")]
+    pub upload: Duration,
+    /// From the end of the upload until the job was seen past compilation.
+    #[serde(rename = "remote_compile_ms", serialize_with = "as_millis")]
+    #[schemars(with = "u64")]
+    pub remote_compile: Duration,
+    /// From then until the job was seen in its terminal status.
+    #[serde(rename = "remote_verify_ms", serialize_with = "as_millis")]
+    #[schemars(with = "u64")]
+    pub remote_verify: Duration,
+}
+
+impl OutcomeDuration {
+    /**
+     * Split the last attempt along its status `history`, whose times are
+     * counted from the start of the attempt.
+     */
+    pub fn from_history(total: Duration, upload: Duration, history: &[StatusObservation]) -> Self {
+        let submitted = VerifyJobStatus::Submitted.to_string();
+        let compiled = VerifyJobStatus::Compiled.to_string();
+        let at = |observation: &StatusObservation| Duration::from_millis(observation.elapsed_ms);
+        let compiled_at = history
+            .iter()
+            .find(|observation| observation.status != submitted)
+            .map(at);
+        let terminal_at = history
+            .last()
+            .filter(|observation| observation.status != submitted && observation.status != compiled)
+            .map(at);
+        Self {
+            total,
+            upload,
+            remote_compile: compiled_at
+                .map(|compiled_at| compiled_at.saturating_sub(upload))
+                .unwrap_or_default(),
+            remote_verify: compiled_at
+                .zip(terminal_at)
+                .map(|(compiled_at, terminal_at)| terminal_at.saturating_sub(compiled_at))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Display for OutcomeDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Verification took {}: upload {}, remote compilation {}, remote verification {}",
+            format_duration(self.total),
+            format_duration(self.upload),
+            format_duration(self.remote_compile),
+            format_duration(self.remote_verify)
+        )
+    }
+}
+
+/**
+ * Everything a verification produced, as returned by `verify_file_set`. The
+ * CLI renders its messages from it, so that library consumers get the same
+ * information as the terminal. Its serialized form is versioned like the
+ * report, see `schema`.
+ *
+ * A job that ends in another status than `Success` is still an outcome, with
+ * `error` set, so that consumers can branch on the status:
+ *
+ * ```ignore
+ * let outcome = verify_file_set(args, metadata, sources, docs)?;
+ * match outcome.status {
+ *     Some(VerifyJobStatus::Success) => {
+ *         println!("remote compilation took {:?}", outcome.duration.remote_compile);
+ *     }
+ *     Some(VerifyJobStatus::CompileFailed) => {
+ *         eprintln!("the sources don't build remotely: {:?}", outcome.error);
+ *     }
+ *     _ => return outcome.into_result().map(|_| ()),
+ * }
+ * ```
+ */
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct VerificationOutcome {
+    /// Terminal status of the last job. `None` when no job finished, with
+    /// --daemonless-wait or when the dispatch failed.
+    pub status: Option<VerifyJobStatus>,
+    pub job_id: Option<String>,
+    pub class_hash: String,
+    pub network: String,
+    /// Name the class was submitted under.
+    pub name: String,
+    /// Code tab of the class on the explorer of the network, when it has one.
+    pub explorer_url: Option<String>,
+    /// Status transitions of the last job, counted from the start of its attempt.
+    pub history: Vec<StatusObservation>,
+    pub duration: OutcomeDuration,
+    /// From the submission of the last job to its terminal status, as
+    /// timestamped by the backend, when it does.
+    pub remote_elapsed_ms: Option<u64>,
+    /// Warnings printed during the verification.
+    pub warnings: Vec<String>,
+    /// Digest of the submitted payload, see `payload::compute_payload_digest`.
+    pub payload_digest: String,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub(crate) failure: Option<anyhow::Error>,
+}
+
+impl VerificationOutcome {
+    pub fn with_result(mut self, result: Result<()>) -> Self {
+        if let Err(e) = result {
+            self.error = Some(format!("{:#}", e));
+            self.error_code = error_code(&e).map(str::to_owned);
+            self.failure = Some(e);
+        }
+        self
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none() && self.status == Some(VerifyJobStatus::Success)
+    }
+
+    /**
+     * What a successful verification verified, `None` for any other outcome.
+     */
+    pub fn summary(&self) -> Option<VerificationSummary> {
+        self.is_success().then(|| VerificationSummary {
+            name: self.name.clone(),
+            class_hash: self.class_hash.clone(),
+            network: self.network.clone(),
+            remote_elapsed_ms: self.remote_elapsed_ms,
+            url: self.explorer_url.clone(),
+        })
+    }
+
+    /**
+     * The error the verification failed with, with its causes, for callers
+     * that treat any failure as an error.
+     */
+    pub fn into_result(mut self) -> Result<Self> {
+        match self.failure.take() {
+            Some(e) => Err(e),
+            None => Ok(self),
+        }
+    }
+}
+
+/**
+ * Printed once a verification succeeded, as a block on the terminal and as
+ * the last line with `--json`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerificationSummary {
+    pub name: String,
+    pub class_hash: String,
+    pub network: String,
+    pub remote_elapsed_ms: Option<u64>,
+    /// `None` on a custom network without an explorer.
+    pub url: Option<String>,
+}
+
+impl Display for VerificationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  Contract:   {}", self.name)?;
+        writeln!(f, "  Class hash: {}", self.class_hash)?;
+        write!(f, "  Network:    {}", self.network)?;
+        if let Some(elapsed_ms) = self.remote_elapsed_ms {
+            let elapsed = format_duration(Duration::from_millis(elapsed_ms));
+            write!(f, "\n  Verified in {} by the backend", elapsed)?;
+        }
+        match &self.url {
+            Some(url) => write!(f, "\n  View it at {}", url),
+            None => write!(f, "\n  The network has no explorer configured"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::VerifierError;
+
+    fn observed(status: VerifyJobStatus, elapsed_ms: u64) -> StatusObservation {
+        StatusObservation {
+            status: status.to_string(),
+            elapsed_ms,
+        }
+    }
+
+    #[test]
+    fn test_duration_is_split_along_the_history() {
+        let history = [
+            observed(VerifyJobStatus::Submitted, 2_000),
+            observed(VerifyJobStatus::Compiled, 32_000),
+            observed(VerifyJobStatus::Success, 37_000),
+        ];
+        let duration = OutcomeDuration::from_history(
+            Duration::from_secs(40),
+            Duration::from_secs(1),
+            &history,
+        );
+        assert_eq!(duration.remote_compile, Duration::from_secs(31));
+        assert_eq!(duration.remote_verify, Duration::from_secs(5));
+
+        // Still polling, nothing is known about the remote verification
+        let pending =
+            OutcomeDuration::from_history(Duration::from_secs(40), Duration::ZERO, &history[..2]);
+        assert_eq!(pending.remote_compile, Duration::from_secs(32));
+        assert_eq!(pending.remote_verify, Duration::ZERO);
+
+        let json = serde_json::to_value(duration).unwrap();
+        assert_eq!(json["remote_compile_ms"], 31_000);
+    }
+
+    #[test]
+    fn test_failed_job_is_an_outcome() {
+        let outcome = VerificationOutcome {
+            status: Some(VerifyJobStatus::CompileFailed),
+            job_id: Some("job".to_owned()),
+            ..Default::default()
+        }
+        .with_result(Err(VerifierError::NoContracts.into()));
+
+        // Consumers branch on the status, the error is kept for the others
+        assert!(!outcome.is_success());
+        assert_eq!(outcome.error_code.as_deref(), Some("VER-014"));
+        let json = serde_json::to_value(&outcome).unwrap();
+        assert_eq!(json["status"], "CompileFailed");
+        assert_eq!(outcome.summary(), None);
+        assert!(outcome.into_result().is_err());
+    }
+}
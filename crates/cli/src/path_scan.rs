@@ -0,0 +1,241 @@
+use crate::diagnostics::Diagnostic;
+use crate::sources::SourceSet;
+
+/// Prefixes of home directories, followed by the name of a user.
+const HOME_PATTERNS: [&str; 4] = ["/home/", "/Users/", "/root/", "\\Users\\"];
+
+/// Segments an absolute path needs to be flagged, so that `/` or `/api/v1` are not.
+const MIN_PATH_SEGMENTS: usize = 3;
+
+/**
+ * What a reference to the build machine looks like.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    /// Anywhere in a file, including comments and documentation.
+    HomeDirectory,
+    /// In a string literal of a Cairo or TOML file.
+    AbsolutePath,
+}
+
+/**
+ * A path specific to the machine the sources were built on, which explorers
+ * would publish along with the sources.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFinding {
+    pub file: String,
+    pub line: usize,
+    pub kind: PathKind,
+    pub path: String,
+}
+
+/**
+ * Contents of the string literals of `line`, up to its comment if any.
+ * Escaped quotes don't end a literal.
+ */
+fn string_literals<'a>(line: &'a str, comment: &str) -> Vec<&'a str> {
+    let mut literals = vec![];
+    let mut open: Option<(char, usize)> = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match open {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some((quote, start)) if c == quote => {
+                literals.push(&line[start..index]);
+                open = None;
+            }
+            Some(_) => (),
+            None if c == '"' || c == '\'' => open = Some((c, index + c.len_utf8())),
+            None if line[index..].starts_with(comment) => break,
+            None => (),
+        }
+    }
+    literals
+}
+
+/**
+ * Words of `text` that may be paths, URLs are never paths even when they
+ * contain one.
+ */
+fn path_words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+        .filter(|word| !word.contains("://"))
+        .map(|word| word.trim_matches(|c: char| "\"'`,;:()[]<>".contains(c)))
+}
+
+fn home_directory(line: &str) -> Option<&str> {
+    path_words(line).find_map(|word| {
+        HOME_PATTERNS
+            .iter()
+            .find_map(|pattern| word.find(pattern))
+            .map(|index| &word[index..])
+    })
+}
+
+fn is_absolute_path(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let is_windows_drive = bytes.len() > 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    let is_unix_path = word.starts_with('/')
+        && word
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .count()
+            >= MIN_PATH_SEGMENTS;
+    is_windows_drive || is_unix_path
+}
+
+/**
+ * Find the home directories referenced by `source`, and the absolute paths in
+ * its string literals when `comment` is the line comment of its language.
+ * Module paths such as `core::traits::Into` never contain a slash.
+ */
+pub fn scan_source(file: &str, source: &str, comment: Option<&str>) -> Vec<PathFinding> {
+    let mut findings = vec![];
+    for (index, line) in source.lines().enumerate() {
+        // Escaped backslashes of Windows paths in literals
+        let line = line.replace("\\\\", "\\");
+        let home = home_directory(&line).map(|path| (PathKind::HomeDirectory, path));
+        let absolute = || {
+            let literals = string_literals(&line, comment?);
+            literals
+                .into_iter()
+                .flat_map(path_words)
+                .find(|word| is_absolute_path(word))
+                .map(|path| (PathKind::AbsolutePath, path))
+        };
+        if let Some((kind, path)) = home.or_else(absolute) {
+            findings.push(PathFinding {
+                file: file.to_owned(),
+                line: index + 1,
+                kind,
+                path: path.to_owned(),
+            });
+        }
+    }
+    findings
+}
+
+pub fn scan_paths(files: &SourceSet) -> Vec<PathFinding> {
+    let mut findings = vec![];
+    for (name, contents) in files.iter() {
+        // Binary files are never displayed as text
+        let Ok(source) = std::str::from_utf8(contents) else {
+            continue;
+        };
+        let comment = match name.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("cairo") => Some("//"),
+            Some("toml") => Some("#"),
+            _ => None,
+        };
+        findings.extend(scan_source(name, source, comment));
+    }
+    findings
+}
+
+impl From<&PathFinding> for Diagnostic {
+    fn from(finding: &PathFinding) -> Self {
+        let kind = match finding.kind {
+            PathKind::HomeDirectory => "home directory",
+            PathKind::AbsolutePath => "absolute path",
+        };
+        Self {
+            file: finding.file.clone(),
+            line: finding.line,
+            message: format!("{} {}", kind, finding.path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::format_diagnostics;
+
+    fn scan_cairo(source: &str) -> Vec<(PathKind, String)> {
+        scan_source("lib.cairo", source, Some("//"))
+            .into_iter()
+            .map(|finding| (finding.kind, finding.path))
+            .collect()
+    }
+
+    #[test]
+    fn test_absolute_path_literals_are_not_module_paths() {
+        let source = r#"use core::traits::Into;
+fn data() -> ByteArray {
+    let _ = starknet::syscalls::get_block_hash_syscall;
+    "/long/path/file.txt"
+}
+"#;
+        assert_eq!(
+            scan_cairo(source),
+            vec![(PathKind::AbsolutePath, "/long/path/file.txt".to_owned())]
+        );
+        assert_eq!(scan_source("lib.cairo", source, Some("//"))[0].line, 4);
+    }
+
+    #[test]
+    fn test_urls_short_paths_and_comments_are_ignored() {
+        let source = r#"const URL: felt252 = 'https://x.io/a/b/c';
+let route: ByteArray = "/api/v1";
+let ratio = a / b / c / d;
+// generated from /opt/build/out/lib.cairo
+let s: ByteArray = "see https://github.com/home/user/repo";
+"#;
+        assert!(scan_cairo(source).is_empty());
+    }
+
+    #[test]
+    fn test_home_directories_are_found_anywhere() {
+        let source = r#"// built in /home/ci/builds/token
+let p: ByteArray = "C:\\Users\\ci\\token";
+let q: ByteArray = "at /Users/alice/token/src/lib.cairo:12";
+"#;
+        assert_eq!(
+            scan_cairo(source),
+            vec![
+                (PathKind::HomeDirectory, "/home/ci/builds/token".to_owned()),
+                (PathKind::HomeDirectory, "\\Users\\ci\\token".to_owned()),
+                (
+                    PathKind::HomeDirectory,
+                    "/Users/alice/token/src/lib.cairo:12".to_owned()
+                ),
+            ]
+        );
+
+        let readme = "Clone it in `/root/token` first, don't use /usr/local/share/x.";
+        let findings = scan_source("README.md", readme, None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].path, "/root/token");
+    }
+
+    #[test]
+    fn test_escaped_quotes_and_windows_drives() {
+        let source = r#"let s: ByteArray = "say \"hi\" from D:\\build\\out";"#;
+        assert_eq!(
+            scan_cairo(source),
+            vec![(PathKind::AbsolutePath, "D:\\build\\out".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_format_findings() {
+        let mut files = SourceSet::new();
+        files
+            .add(
+                "token/Scarb.toml",
+                "[tool]\nout = \"/var/lib/ci/out\" # x\n",
+            )
+            .unwrap();
+        let diagnostics: Vec<Diagnostic> =
+            scan_paths(&files).iter().map(Diagnostic::from).collect();
+        assert_eq!(
+            format_diagnostics(&diagnostics, None),
+            "  token/Scarb.toml:2: absolute path /var/lib/ci/out"
+        );
+    }
+}
@@ -0,0 +1,707 @@
+use std::{
+    fmt::Display,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::project_state::{write_atomic, StateLock, LOCK_TIMEOUT};
+use crate::resubmit::TransientFailures;
+use crate::sources::SourceSet;
+
+pub const DISPATCH_CACHE_FILE: &str = "dispatch-cache.json";
+
+/**
+ * Digest of everything that is sent to the backend for a verification.
+ * The partial digests are kept around so that cache misses can be explained.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PayloadDigest {
+    pub digest: String,
+    pub paths: String,
+    pub fields: String,
+    pub contents: String,
+}
+
+impl PayloadDigest {
+    /**
+     * Names of the payload components that differ between two digests.
+     */
+    pub fn changed_components(&self, other: &PayloadDigest) -> Vec<&'static str> {
+        let mut changed = vec![];
+        if self.paths != other.paths {
+            changed.push("file paths");
+        }
+        if self.fields != other.fields {
+            changed.push("form fields");
+        }
+        if self.contents != other.contents {
+            changed.push("file contents");
+        }
+        changed
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn compute_payload_digest(fields: &[(&str, String)], files: &SourceSet) -> PayloadDigest {
+    let mut named_hashes = files
+        .iter()
+        .map(|(name, contents)| (name.to_owned(), sha256_hex(contents)))
+        .collect::<Vec<(String, String)>>();
+    named_hashes.sort();
+
+    let paths = named_hashes
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    // Content hashes are sorted on their own, so that a pure rename leaves the
+    // contents digest untouched and only the paths digest changes.
+    let mut content_hashes = named_hashes
+        .iter()
+        .map(|(_, hash)| hash.as_str())
+        .collect::<Vec<&str>>();
+    content_hashes.sort();
+
+    let serialized_fields = fields
+        .iter()
+        .map(|(field, value)| format!("{}={}", field, value))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let serialized_files = named_hashes
+        .iter()
+        .map(|(name, hash)| format!("{}:{}", name, hash))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    PayloadDigest {
+        digest: sha256_hex(format!("{}\n--\n{}", serialized_fields, serialized_files).as_bytes()),
+        paths: sha256_hex(paths.as_bytes()),
+        fields: sha256_hex(serialized_fields.as_bytes()),
+        contents: sha256_hex(content_hashes.join("\n").as_bytes()),
+    }
+}
+
+/**
+ * What the sources of a dispatched job were taken from, besides their payload
+ * digest. Either may be missing, e.g. outside of git.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRevision {
+    /// Commit of the sources, only recorded when the project had no
+    /// uncommitted changes, the commit alone wouldn't identify them otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// Version of the package in its Scarb.toml.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispatchCacheEntry {
+    pub network: String,
+    pub class_hash: String,
+    pub payload: PayloadDigest,
+    pub job_id: String,
+    pub status: Option<String>,
+    /// Status description of the job once it finished.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Missing for the entries of older releases.
+    #[serde(default, flatten)]
+    pub revision: SourceRevision,
+    /// Unix time of the dispatch.
+    #[serde(default)]
+    pub dispatched_at: Option<u64>,
+    /// Unix time the job was seen finished.
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+}
+
+/**
+ * Whether an entry of the dispatch cache was submitted for the project as it
+ * is now, see `freshness`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    Current,
+    Stale,
+    /// Nothing recorded can be compared with the project.
+    Unknown,
+}
+
+impl Display for Freshness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Freshness::Current => "current",
+            Freshness::Stale => "stale",
+            Freshness::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * Whether `entry` was dispatched for the project in its `current` revision.
+ * Another package version makes it stale. Then the payload digest, when
+ * `payload` of the project is known, decides over the commit, which also
+ * changes with files that aren't uploaded. Entries of older releases and
+ * projects outside of git are unknown rather than guessed.
+ */
+pub fn freshness(
+    entry: &DispatchCacheEntry,
+    current: &SourceRevision,
+    payload: Option<&PayloadDigest>,
+) -> Freshness {
+    let differs = |recorded: &Option<String>, current: &Option<String>| match (recorded, current) {
+        (Some(recorded), Some(current)) => Some(recorded != current),
+        _ => None,
+    };
+    if differs(&entry.revision.package_version, &current.package_version) == Some(true) {
+        return Freshness::Stale;
+    }
+    let changed = match payload {
+        Some(payload) => Some(entry.payload != *payload),
+        None => differs(&entry.revision.git_commit, &current.git_commit),
+    };
+    match changed {
+        Some(false) => Freshness::Current,
+        Some(true) => Freshness::Stale,
+        None => Freshness::Unknown,
+    }
+}
+
+/**
+ * An entry of the dispatch cache as listed by `jobs local`.
+ */
+#[derive(Debug, Serialize)]
+pub struct LocalJob<'a> {
+    #[serde(flatten)]
+    pub entry: &'a DispatchCacheEntry,
+    pub freshness: Freshness,
+}
+
+/**
+ * Unix time now, as recorded in the dispatch cache.
+ */
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub enum CacheLookup<'a> {
+    Hit(&'a DispatchCacheEntry),
+    /// The closest previous submission for the same class, with what changed since.
+    Miss(Option<(&'a DispatchCacheEntry, Vec<&'static str>)>),
+}
+
+/**
+ * Remembers which job was dispatched for a given payload, so that retrying an
+ * unchanged verification attaches to the existing job instead of resubmitting.
+ * Kept in the cache directory of the project, see `UserDirs::project_cache_dir`.
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DispatchCache {
+    entries: Vec<DispatchCacheEntry>,
+}
+
+impl DispatchCache {
+    pub fn path(dir: &Utf8Path) -> Utf8PathBuf {
+        dir.join(DISPATCH_CACHE_FILE)
+    }
+
+    /**
+     * A missing or unreadable cache is treated as empty.
+     */
+    pub fn load(dir: &Utf8Path) -> Self {
+        fs::read_to_string(Self::path(dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /**
+     * Apply `f` to the cache on disk while holding the state lock, so that
+     * concurrent verifications of the same project don't lose each other's entries.
+     */
+    pub fn update(dir: &Utf8Path, f: impl FnOnce(&mut Self)) -> Result<()> {
+        let _lock = StateLock::acquire(dir, LOCK_TIMEOUT)?;
+        let mut cache = Self::load(dir);
+        f(&mut cache);
+        write_atomic(
+            &Self::path(dir),
+            serde_json::to_string_pretty(&cache)?.as_bytes(),
+        )
+    }
+
+    /**
+     * Only jobs that did not end in a failure can be reused.
+     */
+    pub fn lookup(
+        &self,
+        network: &str,
+        class_hash: &str,
+        payload: &PayloadDigest,
+    ) -> CacheLookup<'_> {
+        let previous = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.network == network && entry.class_hash == class_hash);
+
+        let mut closest = None;
+        for entry in previous {
+            let reusable = matches!(entry.status.as_deref(), None | Some("Success"));
+            if entry.payload == *payload && reusable {
+                return CacheLookup::Hit(entry);
+            }
+            if closest.is_none() {
+                closest = Some((entry, payload.changed_components(&entry.payload)));
+            }
+        }
+        CacheLookup::Miss(closest)
+    }
+
+    /**
+     * The entries, most recent dispatch first, with their freshness against
+     * the `current` revision of the project. Only the current ones with
+     * `current_only`.
+     */
+    pub fn local_jobs(&self, current: &SourceRevision, current_only: bool) -> Vec<LocalJob<'_>> {
+        self.entries
+            .iter()
+            .rev()
+            .map(|entry| LocalJob {
+                entry,
+                freshness: freshness(entry, current, None),
+            })
+            .filter(|job| !current_only || job.freshness == Freshness::Current)
+            .collect()
+    }
+
+    pub fn record(&mut self, entry: DispatchCacheEntry) {
+        self.entries.retain(|e| {
+            !(e.network == entry.network
+                && e.class_hash == entry.class_hash
+                && e.payload == entry.payload)
+        });
+        self.entries.push(entry);
+    }
+
+    pub fn set_status(&mut self, job_id: &str, status: String, description: Option<String>) {
+        for entry in self.entries.iter_mut().filter(|e| e.job_id == job_id) {
+            entry.status = Some(status.clone());
+            entry.description = description.clone();
+            entry.finished_at = Some(unix_now());
+        }
+    }
+
+    /**
+     * The job the identical payload failed with on the same network and class,
+     * if any. Submitting it again would fail the same way, unless the failure
+     * was transient.
+     */
+    pub fn previous_failure(
+        &self,
+        network: &str,
+        class_hash: &str,
+        payload: &PayloadDigest,
+        transient_failures: &TransientFailures,
+    ) -> Option<&DispatchCacheEntry> {
+        self.entries.iter().rev().find(|entry| {
+            let failed = match entry.status.as_deref() {
+                Some("CompileFailed") => true,
+                Some("Fail") => !entry
+                    .description
+                    .as_deref()
+                    .is_some_and(|description| transient_failures.matches_description(description)),
+                _ => false,
+            };
+            failed
+                && entry.network == network
+                && entry.class_hash == class_hash
+                && entry.payload == *payload
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::FileInfo;
+    use std::path::{Path, PathBuf};
+
+    fn temp_project(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("voyager-payload-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    fn file_info(root: &Path, name: &str) -> FileInfo {
+        FileInfo {
+            name: name.to_owned(),
+            path: root.join(name),
+        }
+    }
+
+    fn fields() -> Vec<(&'static str, String)> {
+        vec![
+            ("contract_file", "my_project/src/lib.cairo".to_owned()),
+            ("project_dir_path", "my_project".to_owned()),
+        ]
+    }
+
+    #[test]
+    fn test_renamed_file_is_a_cache_miss() {
+        let root = temp_project("rename");
+        fs::write(root.join("src/lib.cairo"), "mod erc20;").unwrap();
+        fs::write(root.join("src/erc20.cairo"), "fn foo() {}").unwrap();
+
+        let files = vec![
+            file_info(&root, "src/lib.cairo"),
+            file_info(&root, "src/erc20.cairo"),
+        ];
+        let original = compute_payload_digest(&fields(), &SourceSet::from_files(&files).unwrap());
+
+        let mut cache = DispatchCache::default();
+        cache.record(DispatchCacheEntry {
+            network: "sepolia".to_owned(),
+            class_hash: "0x123".to_owned(),
+            payload: original.clone(),
+            job_id: "job-1".to_owned(),
+            status: None,
+            description: None,
+            revision: SourceRevision::default(),
+            dispatched_at: None,
+            finished_at: None,
+        });
+        assert!(matches!(
+            cache.lookup("sepolia", "0x123", &original),
+            CacheLookup::Hit(entry) if entry.job_id == "job-1"
+        ));
+
+        // Same contents under a different path
+        fs::rename(root.join("src/erc20.cairo"), root.join("src/token.cairo")).unwrap();
+        let files = vec![
+            file_info(&root, "src/lib.cairo"),
+            file_info(&root, "src/token.cairo"),
+        ];
+        let renamed = compute_payload_digest(&fields(), &SourceSet::from_files(&files).unwrap());
+
+        assert_ne!(original.digest, renamed.digest);
+        assert_eq!(original.contents, renamed.contents);
+        match cache.lookup("sepolia", "0x123", &renamed) {
+            CacheLookup::Miss(Some((entry, changed))) => {
+                assert_eq!(entry.job_id, "job-1");
+                assert_eq!(changed, vec!["file paths"]);
+            }
+            _ => panic!("Expected a cache miss after renaming a file"),
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_every_entry() {
+        let root = temp_project("concurrent");
+        let project = Utf8PathBuf::from_path_buf(root.clone()).unwrap();
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+
+        let workers: Vec<_> = (0..2)
+            .map(|worker| {
+                let project = project.clone();
+                let payload = payload.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        DispatchCache::update(&project, |cache| {
+                            cache.record(DispatchCacheEntry {
+                                network: "sepolia".to_owned(),
+                                class_hash: format!("0x{}{}", worker, i),
+                                payload: payload.clone(),
+                                job_id: format!("job-{}-{}", worker, i),
+                                status: None,
+                                description: None,
+                                revision: SourceRevision::default(),
+                                dispatched_at: None,
+                                finished_at: None,
+                            })
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let content = fs::read_to_string(DispatchCache::path(&project)).unwrap();
+        let cache: DispatchCache = serde_json::from_str(&content).unwrap();
+        assert_eq!(cache.entries.len(), 50);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_failed_jobs_are_not_reused() {
+        let root = temp_project("failed");
+        fs::write(root.join("src/lib.cairo"), "mod erc20;").unwrap();
+        let files = vec![file_info(&root, "src/lib.cairo")];
+        let payload = compute_payload_digest(&fields(), &SourceSet::from_files(&files).unwrap());
+
+        let mut cache = DispatchCache::default();
+        cache.record(DispatchCacheEntry {
+            network: "sepolia".to_owned(),
+            class_hash: "0x123".to_owned(),
+            payload: payload.clone(),
+            job_id: "job-1".to_owned(),
+            status: None,
+            description: None,
+            revision: SourceRevision::default(),
+            dispatched_at: None,
+            finished_at: None,
+        });
+        cache.set_status("job-1", "CompileFailed".to_owned(), None);
+
+        assert!(matches!(
+            cache.lookup("sepolia", "0x123", &payload),
+            CacheLookup::Miss(Some((_, ref changed))) if changed.is_empty()
+        ));
+        assert!(matches!(
+            cache.lookup("mainnet", "0x123", &payload),
+            CacheLookup::Miss(None)
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_form_fields_change_the_digest() {
+        let root = temp_project("fields");
+        fs::write(root.join("src/lib.cairo"), "mod erc20;").unwrap();
+        let files = vec![file_info(&root, "src/lib.cairo")];
+
+        let original = compute_payload_digest(&fields(), &SourceSet::from_files(&files).unwrap());
+        let mut other_fields = fields();
+        other_fields[0].1 = "my_project/src/other.cairo".to_owned();
+        let changed =
+            compute_payload_digest(&other_fields, &SourceSet::from_files(&files).unwrap());
+
+        assert_eq!(original.changed_components(&changed), vec!["form fields"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_previous_failure_of_an_identical_payload() {
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+        let mut sources = SourceSet::new();
+        sources.add("src/lib.cairo", "mod token;").unwrap();
+        let changed = compute_payload_digest(&fields(), &sources);
+        let transient = TransientFailures::default();
+        let failed = |status: &str, description: &str| {
+            let mut cache = DispatchCache::default();
+            cache.record(DispatchCacheEntry {
+                network: "sepolia".to_owned(),
+                class_hash: "0x123".to_owned(),
+                payload: payload.clone(),
+                job_id: "job-1".to_owned(),
+                status: None,
+                description: None,
+                revision: SourceRevision::default(),
+                dispatched_at: None,
+                finished_at: None,
+            });
+            cache.set_status("job-1", status.to_owned(), Some(description.to_owned()));
+            cache
+        };
+
+        let cache = failed("CompileFailed", "unknown module token");
+        let entry = cache
+            .previous_failure("sepolia", "0x123", &payload, &transient)
+            .unwrap();
+        assert_eq!(entry.job_id, "job-1");
+        assert_eq!(entry.description.as_deref(), Some("unknown module token"));
+        // Anything changed is worth another try
+        assert!(cache
+            .previous_failure("sepolia", "0x123", &changed, &transient)
+            .is_none());
+        assert!(cache
+            .previous_failure("mainnet", "0x123", &payload, &transient)
+            .is_none());
+        assert!(cache
+            .previous_failure("sepolia", "0x456", &payload, &transient)
+            .is_none());
+
+        let cache = failed("Fail", "Class hash mismatch");
+        assert!(cache
+            .previous_failure("sepolia", "0x123", &payload, &transient)
+            .is_some());
+        // Transient failures may not happen again
+        let cache = failed("Fail", "Worker timeout after 600s");
+        assert!(cache
+            .previous_failure("sepolia", "0x123", &payload, &transient)
+            .is_none());
+        let cache = failed("Success", "");
+        assert!(cache
+            .previous_failure("sepolia", "0x123", &payload, &transient)
+            .is_none());
+    }
+
+    fn dispatched(
+        payload: &PayloadDigest,
+        commit: Option<&str>,
+        version: Option<&str>,
+    ) -> DispatchCacheEntry {
+        DispatchCacheEntry {
+            network: "sepolia".to_owned(),
+            class_hash: "0x123".to_owned(),
+            payload: payload.clone(),
+            job_id: "job-1".to_owned(),
+            status: Some("Success".to_owned()),
+            description: None,
+            revision: SourceRevision {
+                git_commit: commit.map(str::to_owned),
+                package_version: version.map(str::to_owned),
+            },
+            dispatched_at: Some(1_700_000_000),
+            finished_at: Some(1_700_000_060),
+        }
+    }
+
+    fn revision(commit: Option<&str>, version: Option<&str>) -> SourceRevision {
+        SourceRevision {
+            git_commit: commit.map(str::to_owned),
+            package_version: version.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_freshness_of_a_version_bump() {
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+        let entry = dispatched(&payload, Some("abc"), Some("1.0.0"));
+
+        assert_eq!(
+            freshness(&entry, &revision(Some("abc"), Some("1.0.0")), None),
+            Freshness::Current
+        );
+        // Even with the same sources and commit
+        assert_eq!(
+            freshness(
+                &entry,
+                &revision(Some("abc"), Some("1.1.0")),
+                Some(&payload)
+            ),
+            Freshness::Stale
+        );
+        assert_eq!(
+            freshness(&entry, &revision(Some("abc"), None), None),
+            Freshness::Current
+        );
+    }
+
+    #[test]
+    fn test_freshness_of_a_digest_change() {
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+        let mut sources = SourceSet::new();
+        sources.add("src/lib.cairo", "mod token;").unwrap();
+        let changed = compute_payload_digest(&fields(), &sources);
+        let entry = dispatched(&payload, Some("abc"), Some("1.0.0"));
+        let current = revision(Some("def"), Some("1.0.0"));
+
+        assert_eq!(freshness(&entry, &current, None), Freshness::Stale);
+        // The payload decides over the commit
+        assert_eq!(
+            freshness(&entry, &current, Some(&payload)),
+            Freshness::Current
+        );
+        assert_eq!(
+            freshness(
+                &entry,
+                &revision(Some("abc"), Some("1.0.0")),
+                Some(&changed)
+            ),
+            Freshness::Stale
+        );
+    }
+
+    #[test]
+    fn test_freshness_without_git_data() {
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+        let recorded_without = dispatched(&payload, None, Some("1.0.0"));
+        let recorded_with = dispatched(&payload, Some("abc"), Some("1.0.0"));
+        let outside_git = revision(None, Some("1.0.0"));
+
+        assert_eq!(
+            freshness(
+                &recorded_without,
+                &revision(Some("abc"), Some("1.0.0")),
+                None
+            ),
+            Freshness::Unknown
+        );
+        assert_eq!(
+            freshness(&recorded_with, &outside_git, None),
+            Freshness::Unknown
+        );
+        assert_eq!(
+            freshness(&recorded_with, &outside_git, Some(&payload)),
+            Freshness::Current
+        );
+
+        // Entries of older releases have none of the fields
+        let legacy: DispatchCacheEntry = serde_json::from_value(serde_json::json!({
+            "network": "sepolia",
+            "class_hash": "0x123",
+            "payload": payload,
+            "job_id": "job-1",
+            "status": null,
+        }))
+        .unwrap();
+        assert_eq!(legacy.revision, SourceRevision::default());
+        assert_eq!(legacy.dispatched_at, None);
+        assert_eq!(
+            freshness(&legacy, &revision(Some("abc"), Some("1.0.0")), None),
+            Freshness::Unknown
+        );
+    }
+
+    #[test]
+    fn test_local_jobs_most_recent_first() {
+        let payload = compute_payload_digest(&fields(), &SourceSet::new());
+        let mut cache = DispatchCache::default();
+        cache.record(dispatched(&payload, Some("abc"), Some("1.0.0")));
+        cache.record(DispatchCacheEntry {
+            class_hash: "0x456".to_owned(),
+            job_id: "job-2".to_owned(),
+            ..dispatched(&payload, Some("def"), Some("1.0.0"))
+        });
+        let current = revision(Some("def"), Some("1.0.0"));
+
+        let jobs = cache.local_jobs(&current, false);
+        let listed: Vec<(&str, Freshness)> = jobs
+            .iter()
+            .map(|job| (job.entry.job_id.as_str(), job.freshness))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("job-2", Freshness::Current), ("job-1", Freshness::Stale)]
+        );
+        assert_eq!(cache.local_jobs(&current, true).len(), 1);
+    }
+}
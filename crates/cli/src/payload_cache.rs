@@ -0,0 +1,481 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{ensure, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use console::{style, Emoji};
+use serde::{Deserialize, Serialize};
+
+use crate::payload::{sha256_hex, PayloadDigest};
+use crate::project_state::write_atomic;
+use crate::sources::SourceSet;
+
+pub const INDEX_FILE: &str = "index.json";
+pub const PAYLOAD_FILE: &str = "payload.zst";
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+/// Entries written with another layout are discarded on read.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CachedFile {
+    pub name: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/**
+ * Describes a cached payload, stored next to it. The hashes of the compressed
+ * payload and of every file are checked whenever the payload is read back.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PayloadIndex {
+    pub format: u32,
+    pub digest: String,
+    /// Unix time the payload was cached at, in seconds.
+    pub created_at: u64,
+    pub tool_version: String,
+    pub compression_level: i32,
+    pub compressed_sha256: String,
+    pub files: Vec<CachedFile>,
+}
+
+impl PayloadIndex {
+    pub fn original_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Space used on disk, indexes included.
+    pub disk_size: u64,
+    /// Size of the cached files before compression.
+    pub original_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcOutcome {
+    pub removed: usize,
+    pub freed: u64,
+    pub remaining: u64,
+}
+
+struct CacheEntry {
+    dir: Utf8PathBuf,
+    /// `None` when the index can't be read, e.g. for an interrupted write.
+    index: Option<PayloadIndex>,
+    disk_size: u64,
+}
+
+/**
+ * Compressed copies of submitted payloads, one directory per payload digest
+ * holding the zstd-compressed files and their index. Shared by all projects,
+ * see `UserDirs::payload_cache_dir`, so identical vendored sources are only
+ * stored once.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadCache {
+    pub dir: Utf8PathBuf,
+    pub compression_level: i32,
+}
+
+impl PayloadCache {
+    pub fn new(dir: Utf8PathBuf, compression_level: i32) -> Self {
+        Self {
+            dir,
+            compression_level,
+        }
+    }
+
+    fn entry_dir(&self, digest: &str) -> Utf8PathBuf {
+        self.dir.join(digest)
+    }
+
+    /**
+     * Cache `files` under their payload digest, unless an intact copy is
+     * already there.
+     */
+    pub fn store(&self, digest: &PayloadDigest, files: &SourceSet) -> Result<()> {
+        if self.load(digest).is_some() {
+            return Ok(());
+        }
+        let dir = self.entry_dir(&digest.digest);
+        let compressed = zstd::encode_all(encode_files(files).as_slice(), self.compression_level)
+            .context("Unable to compress the payload")?;
+        let index = PayloadIndex {
+            format: FORMAT_VERSION,
+            digest: digest.digest.clone(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            compression_level: self.compression_level,
+            compressed_sha256: sha256_hex(&compressed),
+            files: files
+                .iter()
+                .map(|(name, contents)| CachedFile {
+                    name: name.to_owned(),
+                    sha256: sha256_hex(contents),
+                    size: contents.len() as u64,
+                })
+                .collect(),
+        };
+        // The index goes last, an entry without one is incomplete
+        write_atomic(&dir.join(PAYLOAD_FILE), &compressed)?;
+        write_atomic(
+            &dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(&index)?.as_bytes(),
+        )
+    }
+
+    /**
+     * The cached payload for `digest`. Entries failing the integrity checks
+     * are removed with a warning and never returned.
+     */
+    pub fn load(&self, digest: &PayloadDigest) -> Option<SourceSet> {
+        let dir = self.entry_dir(&digest.digest);
+        if !dir.is_dir() {
+            return None;
+        }
+        match read_entry(&dir, &digest.digest) {
+            Ok(files) => Some(files),
+            Err(e) => {
+                println!(
+                    "{} {}",
+                    Emoji("⚠️ ", ""),
+                    style(format!("Discarding the cached payload {}: {:#}", dir, e)).yellow()
+                );
+                if let Err(e) = fs::remove_dir_all(&dir) {
+                    println!("Unable to remove {}: {}", dir, e);
+                }
+                None
+            }
+        }
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut entries = vec![];
+        for dir in self
+            .dir
+            .read_dir_utf8()
+            .with_context(|| format!("Unable to read {}", self.dir))?
+        {
+            let dir = dir?.into_path();
+            if !dir.is_dir() {
+                continue;
+            }
+            let index = fs::read(dir.join(INDEX_FILE))
+                .ok()
+                .and_then(|index| serde_json::from_slice::<PayloadIndex>(&index).ok());
+            entries.push(CacheEntry {
+                disk_size: dir_size(&dir),
+                dir,
+                index,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        Ok(self
+            .entries()?
+            .iter()
+            .fold(CacheStats::default(), |stats, entry| CacheStats {
+                entries: stats.entries + 1,
+                disk_size: stats.disk_size + entry.disk_size,
+                original_size: stats.original_size
+                    + entry.index.as_ref().map_or(0, PayloadIndex::original_size),
+            }))
+    }
+
+    /**
+     * Remove the oldest payloads until the cache takes at most `max_size`
+     * bytes. Entries without a readable index go first.
+     */
+    pub fn gc(&self, max_size: u64) -> Result<GcOutcome> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|entry| entry.index.as_ref().map_or(0, |index| index.created_at));
+        let mut outcome = GcOutcome {
+            remaining: entries.iter().map(|entry| entry.disk_size).sum(),
+            ..Default::default()
+        };
+        for entry in entries {
+            if outcome.remaining <= max_size {
+                break;
+            }
+            fs::remove_dir_all(&entry.dir)
+                .with_context(|| format!("Unable to remove {}", entry.dir))?;
+            outcome.removed += 1;
+            outcome.freed += entry.disk_size;
+            outcome.remaining -= entry.disk_size;
+        }
+        Ok(outcome)
+    }
+}
+
+fn read_entry(dir: &Utf8Path, digest: &str) -> Result<SourceSet> {
+    let index: PayloadIndex = serde_json::from_slice(
+        &fs::read(dir.join(INDEX_FILE)).context("missing or unreadable index")?,
+    )
+    .context("malformed index")?;
+    ensure!(
+        index.format == FORMAT_VERSION,
+        "written with cache format {}, expected {}",
+        index.format,
+        FORMAT_VERSION
+    );
+    ensure!(index.digest == digest, "the index is for another payload");
+
+    let compressed = fs::read(dir.join(PAYLOAD_FILE)).context("missing or unreadable payload")?;
+    ensure!(
+        sha256_hex(&compressed) == index.compressed_sha256,
+        "the compressed payload doesn't match its hash"
+    );
+    let files = decode_files(
+        &zstd::decode_all(compressed.as_slice()).context("unable to decompress the payload")?,
+    )?;
+
+    let cached = files
+        .iter()
+        .map(|(name, contents)| CachedFile {
+            name: name.to_owned(),
+            sha256: sha256_hex(contents),
+            size: contents.len() as u64,
+        })
+        .collect::<Vec<CachedFile>>();
+    ensure!(
+        cached == index.files,
+        "the files don't match the hashes of the index"
+    );
+    Ok(files)
+}
+
+/**
+ * Every file as its name and contents, each prefixed with its length.
+ */
+fn encode_files(files: &SourceSet) -> Vec<u8> {
+    let mut encoded = vec![];
+    for (name, contents) in files.iter() {
+        encoded.extend((name.len() as u64).to_le_bytes());
+        encoded.extend(name.as_bytes());
+        encoded.extend((contents.len() as u64).to_le_bytes());
+        encoded.extend(contents);
+    }
+    encoded
+}
+
+/// The next length-prefixed field of an encoded payload.
+fn take_field(encoded: &mut &[u8]) -> Result<Vec<u8>> {
+    ensure!(encoded.len() >= 8, "truncated payload");
+    let (len, rest) = encoded.split_at(8);
+    let len = u64::from_le_bytes(len.try_into()?) as usize;
+    ensure!(rest.len() >= len, "truncated payload");
+    let (data, rest) = rest.split_at(len);
+    *encoded = rest;
+    Ok(data.to_vec())
+}
+
+fn decode_files(mut encoded: &[u8]) -> Result<SourceSet> {
+    let mut files = SourceSet::new();
+    while !encoded.is_empty() {
+        let name = String::from_utf8(take_field(&mut encoded)?).context("invalid file name")?;
+        let contents = take_field(&mut encoded)?;
+        // Checks the name like a collected one, the payload is replayed as it is
+        files.add(name, contents)?;
+    }
+    Ok(files)
+}
+
+pub fn dir_size(dir: &Utf8Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/**
+ * A size such as `500M` or `2G`, in bytes. Units are powers of 1024.
+ */
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, unit)) if unit.is_ascii_alphabetic() => {
+            let multiplier = match unit.to_ascii_uppercase() {
+                'B' => 1,
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                _ => return Err(format!("unknown size unit {}, use K, M or G", unit)),
+            };
+            (&size[..i], multiplier)
+        }
+        _ => (size, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size {}, e.g. 500M or 2G", size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::FileInfo;
+    use crate::errors::error_code;
+    use crate::payload::compute_payload_digest;
+    use std::path::Path;
+
+    fn temp_dir(name: &str) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!(
+                "voyager-payload-cache-{}-{}",
+                name,
+                std::process::id()
+            ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn collect(root: &Path, names: &[&str]) -> SourceSet {
+        let files = names
+            .iter()
+            .map(|name| FileInfo {
+                name: name.to_string(),
+                path: root.join(name),
+            })
+            .collect::<Vec<FileInfo>>();
+        SourceSet::from_files(&files).unwrap()
+    }
+
+    fn project(dir: &Utf8Path) -> (SourceSet, PayloadDigest) {
+        let root = dir.join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Scarb.toml"), "[package]\nname = \"vendored\"\n").unwrap();
+        fs::write(root.join("src/lib.cairo"), "mod big;\n".repeat(1000)).unwrap();
+        // Not valid UTF-8, the cache must not care
+        fs::write(root.join("src/big.cairo"), [0xff, 0x00, 0xfe, 0x0a]).unwrap();
+        let files = collect(
+            root.as_std_path(),
+            &["Scarb.toml", "src/lib.cairo", "src/big.cairo"],
+        );
+        let digest = compute_payload_digest(&[("name", "Vendored".to_owned())], &files);
+        (files, digest)
+    }
+
+    #[test]
+    fn test_restored_payload_is_byte_identical() {
+        let dir = temp_dir("round-trip");
+        let (files, digest) = project(&dir);
+        let cache = PayloadCache::new(dir.join("cache"), 19);
+
+        cache.store(&digest, &files).unwrap();
+        let restored = cache.load(&digest).unwrap();
+        assert_eq!(restored, files);
+        assert_eq!(
+            compute_payload_digest(&[("name", "Vendored".to_owned())], &restored),
+            digest
+        );
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(
+            stats.original_size,
+            files.iter().map(|(_, c)| c.len() as u64).sum::<u64>()
+        );
+        assert!(stats.disk_size < stats.original_size);
+    }
+
+    #[test]
+    fn test_corrupted_entries_are_discarded() {
+        let dir = temp_dir("corrupted");
+        let (files, digest) = project(&dir);
+        let cache = PayloadCache::new(dir.join("cache"), DEFAULT_COMPRESSION_LEVEL);
+        let payload_path = cache.entry_dir(&digest.digest).join(PAYLOAD_FILE);
+
+        cache.store(&digest, &files).unwrap();
+        let mut compressed = fs::read(&payload_path).unwrap();
+        let last = compressed.len() - 1;
+        compressed[last] ^= 0xff;
+        fs::write(&payload_path, compressed).unwrap();
+        assert_eq!(cache.load(&digest), None);
+        assert!(!cache.entry_dir(&digest.digest).exists());
+
+        // A tampered index is caught by the per-file hashes
+        cache.store(&digest, &files).unwrap();
+        let index_path = cache.entry_dir(&digest.digest).join(INDEX_FILE);
+        let mut index: PayloadIndex =
+            serde_json::from_slice(&fs::read(&index_path).unwrap()).unwrap();
+        index.files[0].sha256 = sha256_hex(b"something else");
+        fs::write(&index_path, serde_json::to_vec(&index).unwrap()).unwrap();
+        assert_eq!(cache.load(&digest), None);
+
+        // Storing again replaces the discarded entry
+        cache.store(&digest, &files).unwrap();
+        assert_eq!(cache.load(&digest), Some(files));
+    }
+
+    #[test]
+    fn test_replayed_names_are_validated() {
+        let mut encoded = vec![];
+        for field in [&b"src/lib\0.cairo"[..], &b"mod token;"[..]] {
+            encoded.extend((field.len() as u64).to_le_bytes());
+            encoded.extend(field);
+        }
+        let error = decode_files(&encoded).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-017"));
+        assert!(error.to_string().contains("\\u{0}"), "{}", error);
+    }
+
+    #[test]
+    fn test_gc_removes_the_oldest_payloads_first() {
+        let dir = temp_dir("gc");
+        let cache = PayloadCache::new(dir.join("cache"), DEFAULT_COMPRESSION_LEVEL);
+        let mut digests = vec![];
+        for i in 0..3 {
+            let mut files = SourceSet::new();
+            files.add("src/lib.cairo", format!("// {}", i)).unwrap();
+            let digest = compute_payload_digest(&[], &files);
+            cache.store(&digest, &files).unwrap();
+            // Backdate the entries so that their order doesn't depend on the clock
+            let index_path = cache.entry_dir(&digest.digest).join(INDEX_FILE);
+            let mut index: PayloadIndex =
+                serde_json::from_slice(&fs::read(&index_path).unwrap()).unwrap();
+            index.created_at = i;
+            fs::write(&index_path, serde_json::to_vec(&index).unwrap()).unwrap();
+            digests.push(digest);
+        }
+        let total = cache.stats().unwrap().disk_size;
+
+        let outcome = cache.gc(total - 1).unwrap();
+        assert_eq!(outcome.removed, 1);
+        assert_eq!(outcome.remaining, total - outcome.freed);
+        assert!(!cache.entry_dir(&digests[0].digest).exists());
+        assert!(cache.entry_dir(&digests[2].digest).exists());
+
+        assert_eq!(cache.gc(0).unwrap().remaining, 0);
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert!(parse_size("2T").is_err());
+        assert!(parse_size("M").is_err());
+    }
+}
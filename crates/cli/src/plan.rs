@@ -0,0 +1,402 @@
+use std::{sync::Mutex, time::Duration};
+
+use serde::Serialize;
+
+use crate::dry_run::ManifestFile;
+use crate::http_retry::RetryPolicy;
+use crate::humanize::{format_duration, format_size};
+use crate::locked::Reproducibility;
+use crate::provenance::ConfigSnapshot;
+
+/**
+ * A step of the verification that the flags may leave out, in the order
+ * they run. Resolving the project and collecting its files always run.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// The `pre_verify` hook of the project.
+    PreVerifyHook,
+    /// Warn about syntax the selected compiler doesn't parse.
+    SyntaxCheck,
+    /// Look for build machine paths in the uploaded files, `--scan-paths`.
+    PathScan,
+    /// Check that the compiler plugins can be published.
+    PluginCheck,
+    /// Check that the class exists on the network.
+    ClassLookup,
+    /// Compare the hash of the classes the sources compile to with the class.
+    HashCheck,
+    /// Refuse classes already verified, unless `--force`.
+    VerifiedCheck,
+    /// Look for a job already in progress for the class.
+    InProgressJobs,
+    /// Compare the Sierra version of the class with the compiler.
+    SierraCheck,
+    /// Fetch when the class was declared, for the confirmation.
+    Declaration,
+    /// Compare the ABI of the class with the sources.
+    AbiCheck,
+    /// Leave out the vendored dependencies the backend already has.
+    DependencyCache,
+    /// Apply the policy of the network.
+    Policy,
+    /// Confirm the class about to be marked verified.
+    Confirmation,
+    /// Submit the sources, then follow the job as `wait` says.
+    Submit,
+}
+
+impl Step {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Step::PreVerifyHook => "run the pre_verify hook",
+            Step::SyntaxCheck => "check the sources parse with the selected compiler",
+            Step::PathScan => "scan the uploaded files for build machine paths",
+            Step::PluginCheck => "check the compiler plugins can be published",
+            Step::ClassLookup => "check the class exists on the network",
+            Step::HashCheck => "compare the class hash with the compiled classes",
+            Step::VerifiedCheck => "check whether the class is already verified",
+            Step::InProgressJobs => "look for a verification job in progress",
+            Step::SierraCheck => "compare the Sierra version of the class",
+            Step::Declaration => "fetch when the class was declared",
+            Step::AbiCheck => "compare the class ABI with the sources",
+            Step::DependencyCache => "ask which vendored dependencies the backend already has",
+            Step::Policy => "apply the network policy",
+            Step::Confirmation => "confirm the class to mark verified",
+            Step::Submit => "submit the sources",
+        }
+    }
+}
+
+/**
+ * The flags deciding which steps run.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanFlags {
+    pub reproducibility: Reproducibility,
+    pub pre_verify_hook: bool,
+    pub scan_paths: bool,
+    pub skip_abi_check: bool,
+    pub skip_hash_check: bool,
+    pub vendor_all_dependencies: bool,
+    pub dry_run: bool,
+}
+
+/**
+ * The steps to run, which the verification asks through `runs` before running
+ * each of them, so that what `--explain-plan` shows is what runs.
+ */
+#[derive(Debug)]
+pub struct StepPlan {
+    steps: Vec<Step>,
+    /// Only explaining the plan, nothing runs.
+    explain: bool,
+    executed: Mutex<Vec<Step>>,
+}
+
+impl StepPlan {
+    pub fn new(flags: PlanFlags, explain: bool) -> Self {
+        let frozen = flags.reproducibility.is_frozen();
+        // A dry run only looks the class up, like frozen runs it skips the other checks
+        let remote_checks = !frozen && !flags.dry_run;
+        let steps = [
+            (Step::PreVerifyHook, flags.pre_verify_hook),
+            (Step::SyntaxCheck, true),
+            (Step::PathScan, flags.scan_paths),
+            (Step::PluginCheck, true),
+            (Step::ClassLookup, !frozen),
+            // Local, frozen runs and dry runs compare too
+            (Step::HashCheck, !flags.skip_hash_check),
+            (Step::VerifiedCheck, remote_checks),
+            (Step::InProgressJobs, remote_checks),
+            (Step::SierraCheck, remote_checks),
+            (Step::Declaration, remote_checks),
+            (Step::AbiCheck, remote_checks && !flags.skip_abi_check),
+            // Shown by dry runs too, frozen runs upload exactly what was resolved
+            (
+                Step::DependencyCache,
+                !frozen && !flags.vendor_all_dependencies,
+            ),
+            (Step::Policy, !flags.dry_run),
+            (Step::Confirmation, !flags.dry_run),
+            (Step::Submit, !flags.dry_run),
+        ];
+        Self {
+            steps: steps
+                .into_iter()
+                .filter(|(_, planned)| *planned)
+                .map(|(step, _)| step)
+                .collect(),
+            explain,
+            executed: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /**
+     * Whether to run `step`, recording it as executed when it does.
+     */
+    pub fn runs(&self, step: Step) -> bool {
+        let runs = !self.explain && self.steps.contains(&step);
+        if runs {
+            if let Ok(mut executed) = self.executed.lock() {
+                executed.push(step);
+            }
+        }
+        runs
+    }
+
+    /// Whether `step` is planned, without running it.
+    pub fn includes(&self, step: Step) -> bool {
+        self.steps.contains(&step)
+    }
+
+    /// The steps `runs` allowed so far, in order.
+    pub fn executed(&self) -> Vec<Step> {
+        self.executed
+            .lock()
+            .map(|executed| executed.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectPlan {
+    pub path: String,
+    pub contract_file: String,
+    pub resolver: String,
+    pub scarb_version: String,
+    pub cairo_version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CollectionPlan {
+    pub root: String,
+    pub max_files: usize,
+    pub skipped_directories: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkPlan {
+    pub name: String,
+    pub internal_api: String,
+    pub public_api: String,
+    pub verify_endpoint: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RetryPlan {
+    pub attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl From<RetryPolicy> for RetryPlan {
+    fn from(policy: RetryPolicy) -> Self {
+        Self {
+            attempts: policy.attempts,
+            base_delay_ms: policy.base_delay.as_millis() as u64,
+            max_delay_ms: policy.max_delay.as_millis() as u64,
+        }
+    }
+}
+
+/**
+ * How the verification follows the job once submitted.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitMode {
+    /// Poll until the job finishes.
+    Poll,
+    /// Exit right after dispatching, `--no-wait`.
+    Exit,
+    /// Exit and leave polling to a background waiter, `--daemonless-wait`.
+    Background,
+}
+
+/**
+ * The files to submit. The class hash, name and license are asked for later.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubmissionPlan {
+    pub files: Vec<ManifestFile>,
+    pub docs: Vec<ManifestFile>,
+    pub total_size: u64,
+}
+
+/**
+ * What `--explain-plan` shows: the inputs of the verification as resolved,
+ * and the steps of the `StepPlan` driving it.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutionPlan {
+    pub config: ConfigSnapshot,
+    pub project: ProjectPlan,
+    pub collection: CollectionPlan,
+    pub network: NetworkPlan,
+    pub retry: RetryPlan,
+    pub wait: WaitMode,
+    pub steps: Vec<Step>,
+    pub submission: SubmissionPlan,
+}
+
+impl ExecutionPlan {
+    pub fn format(&self) -> String {
+        let mut lines = vec!["Configuration:".to_owned(), self.config.format()];
+        lines.extend([
+            format!("Project: {}", self.project.path),
+            format!("  contract file: {}", self.project.contract_file),
+            format!(
+                "  resolver: {} (Scarb {}, Cairo {})",
+                self.project.resolver, self.project.scarb_version, self.project.cairo_version
+            ),
+            format!("Collection root: {}", self.collection.root),
+            format!(
+                "  skipping {}, warning above {} files",
+                self.collection.skipped_directories.join(", "),
+                self.collection.max_files
+            ),
+            format!("Network: {}", self.network.name),
+            format!("  internal API: {}", self.network.internal_api),
+            format!("  public API: {}", self.network.public_api),
+            format!("  submitted to: {}", self.network.verify_endpoint),
+            format!(
+                "Retries: {} attempts, backoff from {} up to {}",
+                self.retry.attempts,
+                format_duration(Duration::from_millis(self.retry.base_delay_ms)),
+                format_duration(Duration::from_millis(self.retry.max_delay_ms))
+            ),
+            "Steps:".to_owned(),
+        ]);
+        for (i, step) in self.steps.iter().enumerate() {
+            lines.push(format!("  {}. {}", i + 1, step.describe()));
+        }
+        let wait = match self.wait {
+            WaitMode::Poll => "poll the job until it finishes",
+            WaitMode::Exit => "exit once the job is dispatched",
+            WaitMode::Background => "exit and poll the job in the background",
+        };
+        if self.steps.contains(&Step::Submit) {
+            lines.push(format!("  then {}", wait));
+        }
+        lines.push(format!(
+            "Submission: {} files and {} docs, {} in total",
+            self.submission.files.len(),
+            self.submission.docs.len(),
+            format_size(self.submission.total_size)
+        ));
+        for file in self.submission.files.iter().chain(&self.submission.docs) {
+            lines.push(format!("  {} ({})", file.name, format_size(file.size)));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_STEPS: [Step; 15] = [
+        Step::PreVerifyHook,
+        Step::SyntaxCheck,
+        Step::PathScan,
+        Step::PluginCheck,
+        Step::ClassLookup,
+        Step::HashCheck,
+        Step::VerifiedCheck,
+        Step::InProgressJobs,
+        Step::SierraCheck,
+        Step::Declaration,
+        Step::AbiCheck,
+        Step::DependencyCache,
+        Step::Policy,
+        Step::Confirmation,
+        Step::Submit,
+    ];
+
+    /// Asks for every step in order, like the verification does.
+    fn execute(plan: &StepPlan) -> Vec<Step> {
+        for step in ALL_STEPS {
+            plan.runs(step);
+        }
+        plan.executed()
+    }
+
+    #[test]
+    fn test_executed_steps_are_the_planned_ones() {
+        let combinations = [
+            PlanFlags::default(),
+            PlanFlags {
+                reproducibility: Reproducibility::Frozen,
+                ..Default::default()
+            },
+            PlanFlags {
+                pre_verify_hook: true,
+                scan_paths: true,
+                skip_abi_check: true,
+                skip_hash_check: true,
+                vendor_all_dependencies: true,
+                ..Default::default()
+            },
+            PlanFlags {
+                dry_run: true,
+                ..Default::default()
+            },
+        ];
+        for flags in combinations {
+            let plan = StepPlan::new(flags, false);
+            assert_eq!(execute(&plan), plan.steps(), "{:?}", flags);
+        }
+    }
+
+    #[test]
+    fn test_flags_select_the_steps() {
+        let steps = |flags| StepPlan::new(flags, false).steps().to_vec();
+        let default = steps(PlanFlags::default());
+        assert!(!default.contains(&Step::PreVerifyHook) && !default.contains(&Step::PathScan));
+        assert_eq!(default.last(), Some(&Step::Submit));
+
+        let frozen = steps(PlanFlags {
+            reproducibility: Reproducibility::Frozen,
+            ..Default::default()
+        });
+        assert_eq!(
+            frozen,
+            [
+                Step::SyntaxCheck,
+                Step::PluginCheck,
+                Step::HashCheck,
+                Step::Policy,
+                Step::Confirmation,
+                Step::Submit
+            ]
+        );
+
+        let dry_run = steps(PlanFlags {
+            dry_run: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            dry_run,
+            [
+                Step::SyntaxCheck,
+                Step::PluginCheck,
+                Step::ClassLookup,
+                Step::HashCheck,
+                Step::DependencyCache
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explaining_runs_nothing() {
+        let plan = StepPlan::new(PlanFlags::default(), true);
+        assert!(execute(&plan).is_empty());
+        assert!(!plan.steps().is_empty());
+    }
+}
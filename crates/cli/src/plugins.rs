@@ -0,0 +1,156 @@
+use std::{fs, process::Command};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+use crate::api::CairoPlugin;
+
+/// Kind of the targets of compiler plugin packages in `scarb metadata`.
+pub const PLUGIN_TARGET_KIND: &str = "cairo-plugin";
+
+#[derive(Deserialize)]
+struct Metadata {
+    workspace: Workspace,
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    root: Utf8PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+    source: String,
+    #[serde(default)]
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    kind: String,
+}
+
+/**
+ * Plugins among the packages of `scarb metadata --format-version 1`, with
+ * their checksum from the Scarb.lock at the workspace root. Built-in plugins
+ * ship with the compiler and are left out.
+ */
+pub fn plugins_from_metadata(
+    metadata: &str,
+    read_lockfile: impl Fn(&Utf8Path) -> Option<String>,
+) -> Result<Vec<CairoPlugin>> {
+    let metadata: Metadata =
+        serde_json::from_str(metadata).context("Unable to parse the scarb metadata")?;
+    let lock = read_lockfile(&metadata.workspace.root)
+        .map(|lock| lock.parse::<toml::Value>())
+        .transpose()
+        .context("Unable to parse Scarb.lock")?;
+    let checksum = |name: &str, version: &str| -> Option<String> {
+        lock.as_ref()?
+            .get("package")?
+            .as_array()?
+            .iter()
+            .find(|package| {
+                package.get("name").and_then(toml::Value::as_str) == Some(name)
+                    && package.get("version").and_then(toml::Value::as_str) == Some(version)
+            })?
+            .get("checksum")?
+            .as_str()
+            .map(str::to_owned)
+    };
+
+    let mut plugins: Vec<CairoPlugin> = metadata
+        .packages
+        .into_iter()
+        .filter(|package| package.source != "std")
+        .filter(|package| {
+            package
+                .targets
+                .iter()
+                .any(|target| target.kind == PLUGIN_TARGET_KIND)
+        })
+        .map(|package| CairoPlugin {
+            checksum: checksum(&package.name, &package.version),
+            name: package.name,
+            version: package.version,
+            source: package.source,
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+/**
+ * Compiler plugins of the package, from `scarb metadata`.
+ */
+pub fn detect_plugins(package_dir: &Utf8Path) -> Result<Vec<CairoPlugin>> {
+    let output = Command::new("scarb")
+        .arg("--manifest-path")
+        .arg(package_dir.join("Scarb.toml").as_str())
+        .args(["metadata", "--format-version", "1"])
+        .output()
+        .context("Unable to run scarb metadata")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "scarb metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    plugins_from_metadata(&String::from_utf8_lossy(&output.stdout), |root| {
+        fs::read_to_string(root.join("Scarb.lock")).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = include_str!("../tests/test_data/plugins/metadata.json");
+    const LOCKFILE: &str = include_str!("../tests/test_data/plugins/Scarb.lock");
+
+    #[test]
+    fn test_plugins_are_detected_from_the_metadata() {
+        let plugins = plugins_from_metadata(METADATA, |root| {
+            assert_eq!(root, "/work/my_token");
+            Some(LOCKFILE.to_owned())
+        })
+        .unwrap();
+
+        assert_eq!(
+            plugins,
+            vec![
+                CairoPlugin {
+                    name: "local_macros".to_owned(),
+                    version: "0.1.0".to_owned(),
+                    source: "path+file:///work/local_macros/Scarb.toml".to_owned(),
+                    checksum: None,
+                },
+                CairoPlugin {
+                    name: "token_macros".to_owned(),
+                    version: "0.3.1".to_owned(),
+                    source: "registry+https://scarbs.xyz/".to_owned(),
+                    checksum: Some(
+                        "sha256:5d3a1c1e1bb2b8a1cdd1f0e4cf0fd0a3fa3c0c6f3ab4b9e1a0f4b6b3ad3b0c1e"
+                            .to_owned()
+                    ),
+                },
+            ]
+        );
+        assert!(plugins[0].is_local());
+        assert!(!plugins[1].is_local());
+    }
+
+    #[test]
+    fn test_missing_lockfile_leaves_checksums_out() {
+        let plugins = plugins_from_metadata(METADATA, |_| None).unwrap();
+        assert!(plugins.iter().all(|plugin| plugin.checksum.is_none()));
+        assert_eq!(
+            serde_json::to_string(&plugins[1]).unwrap(),
+            r#"{"name":"token_macros","version":"0.3.1","source":"registry+https://scarbs.xyz/"}"#
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::{collections::BTreeMap, fs};
+
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8Path;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::errors::{CliError, VerifierError};
+use crate::license::LicenseType;
+
+/**
+ * Rules a network requires of every verification, in `[policy.<network>]` of
+ * the policy file. They are checked before dispatching, so that a violation
+ * never reaches the explorer.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkPolicy {
+    /// Anything but NoLicense.
+    #[serde(default)]
+    pub require_license: bool,
+    /// Licenses by their command line name, e.g. `MIT` or `Apache2`.
+    pub allowed_licenses: Option<Vec<String>>,
+    /// No uncommitted change in the project, unless verifying a --git-ref.
+    #[serde(default)]
+    pub require_clean_git: bool,
+    /// Same as passing --strict.
+    #[serde(default)]
+    pub strict: bool,
+    /// Where `report-issue` sends users when the backend of the network takes
+    /// no correction requests, e.g. a support address. Not a rule.
+    pub escalation: Option<String>,
+}
+
+/**
+ * What the rules are checked against.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct PolicyContext<'a> {
+    pub license: LicenseType,
+    /// Uncommitted changes of the project, `None` when it isn't a git repository.
+    pub git_changes: Option<&'a [String]>,
+    /// Warnings of the checks that fail under --strict, already reported.
+    pub strict_warnings: &'a [VerifierError],
+}
+
+impl NetworkPolicy {
+    /**
+     * Rules that are enabled, as written in the policy file.
+     */
+    pub fn active_rules(&self) -> Vec<String> {
+        let mut rules = vec![];
+        if self.require_license {
+            rules.push("require_license = true".to_owned());
+        }
+        if let Some(allowed) = &self.allowed_licenses {
+            rules.push(format!("allowed_licenses = [{}]", allowed.join(", ")));
+        }
+        if self.require_clean_git {
+            rules.push("require_clean_git = true".to_owned());
+        }
+        if self.strict {
+            rules.push("strict = true".to_owned());
+        }
+        rules
+    }
+
+    /**
+     * The first rule `context` violates, in the order of `active_rules`.
+     */
+    pub fn check(&self, network: &str, context: &PolicyContext) -> Result<()> {
+        let violation = |rule: &str, reason: String| -> Result<()> {
+            Err(CliError::PolicyViolation {
+                network: network.to_owned(),
+                rule: rule.to_owned(),
+                reason,
+            }
+            .into())
+        };
+        let license = context.license.to_string();
+        if self.require_license && matches!(context.license, LicenseType::NoLicense) {
+            return violation("require_license", "the class has no license".to_owned());
+        }
+        if let Some(allowed) = &self.allowed_licenses {
+            if !allowed.contains(&license) {
+                return violation(
+                    "allowed_licenses",
+                    format!("{} is not one of {}", license, allowed.join(", ")),
+                );
+            }
+        }
+        if self.require_clean_git {
+            match context.git_changes {
+                None => {
+                    return violation(
+                        "require_clean_git",
+                        "the project is not in a git repository".to_owned(),
+                    )
+                }
+                Some(changes) if !changes.is_empty() => {
+                    return violation(
+                        "require_clean_git",
+                        format!("uncommitted changes to {}", changes.join(", ")),
+                    )
+                }
+                Some(_) => (),
+            }
+        }
+        // The checks already ran, only their outcome changes
+        if let Some(warning) = context.strict_warnings.first().filter(|_| self.strict) {
+            return violation("strict", warning.to_string());
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Policies of every network, read from the policy file.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    policy: BTreeMap<String, NetworkPolicy>,
+}
+
+impl Policy {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let policy: Self = toml::from_str(contents)?;
+        let known: Vec<String> = LicenseType::value_variants()
+            .iter()
+            .map(LicenseType::to_string)
+            .collect();
+        for (network, rules) in policy.policy.iter() {
+            let unknown = rules
+                .allowed_licenses
+                .iter()
+                .flatten()
+                .find(|license| !known.contains(license));
+            if let Some(license) = unknown {
+                return Err(anyhow!(
+                    "Unknown license {} in [policy.{}], expected one of {}",
+                    license,
+                    network,
+                    known.join(", ")
+                ));
+            }
+        }
+        Ok(policy)
+    }
+
+    /**
+     * A missing policy file is no policy, unless it was given explicitly.
+     */
+    pub fn load(path: &Utf8Path, required: bool) -> Result<Self> {
+        if !required && !path.is_file() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read the policy file {}", path))?;
+        Self::parse(&contents).with_context(|| format!("Invalid policy file {}", path))
+    }
+
+    pub fn for_network(&self, network: &str) -> NetworkPolicy {
+        self.policy
+            .get(&network.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = &str> {
+        self.policy.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+        [policy.mainnet]
+        require_license = true
+        allowed_licenses = ["MIT", "Apache2"]
+        require_clean_git = true
+        strict = true
+
+        [policy.sepolia]
+        escalation = "https://github.com/example/voyager-support/issues"
+    "#;
+
+    const NO_CHANGES: &[String] = &[];
+
+    fn submission(license: LicenseType) -> PolicyContext<'static> {
+        PolicyContext {
+            license,
+            git_changes: Some(NO_CHANGES),
+            strict_warnings: &[],
+        }
+    }
+
+    fn violated_rule(policy: &NetworkPolicy, submission: &PolicyContext) -> Option<String> {
+        let error = policy.check("mainnet", submission).err()?;
+        match error.downcast::<CliError>().unwrap() {
+            CliError::PolicyViolation { rule, .. } => Some(rule),
+            other => panic!("{}", other),
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_per_network() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let mainnet = policy.for_network("Mainnet");
+        assert_eq!(mainnet.active_rules().len(), 4);
+        assert!(policy.for_network("sepolia").active_rules().is_empty());
+        assert_eq!(
+            policy.for_network("sepolia").escalation.as_deref(),
+            Some("https://github.com/example/voyager-support/issues")
+        );
+        assert_eq!(policy.for_network("local"), NetworkPolicy::default());
+
+        assert_eq!(
+            violated_rule(&mainnet, &submission(LicenseType::NoLicense)).as_deref(),
+            Some("require_license")
+        );
+        assert_eq!(
+            violated_rule(&mainnet, &submission(LicenseType::GPLv3)).as_deref(),
+            Some("allowed_licenses")
+        );
+        assert_eq!(violated_rule(&mainnet, &submission(LicenseType::MIT)), None);
+        assert!(policy
+            .for_network("sepolia")
+            .check("sepolia", &submission(LicenseType::NoLicense))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_clean_git_and_strict_rules() {
+        let policy = Policy::parse(POLICY).unwrap().for_network("mainnet");
+        let changes = ["src/lib.cairo".to_owned()];
+        let dirty = PolicyContext {
+            git_changes: Some(&changes[..]),
+            ..submission(LicenseType::MIT)
+        };
+        let error = policy.check("mainnet", &dirty).unwrap_err().to_string();
+        assert!(error.contains("require_clean_git"), "{}", error);
+        assert!(error.contains("src/lib.cairo"), "{}", error);
+        let untracked = PolicyContext {
+            git_changes: None,
+            ..submission(LicenseType::MIT)
+        };
+        assert!(policy.check("mainnet", &untracked).is_err());
+
+        let warnings = [VerifierError::EnvironmentPaths {
+            findings: "  lib.cairo:1: home directory /home/ci".to_owned(),
+        }];
+        let warned = PolicyContext {
+            strict_warnings: &warnings,
+            ..submission(LicenseType::MIT)
+        };
+        assert_eq!(violated_rule(&policy, &warned).as_deref(), Some("strict"));
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        assert!(Policy::parse("[policy.mainnet]\nrequire_licence = true\n").is_err());
+        let error = Policy::parse("[policy.mainnet]\nallowed_licenses = [\"WTFPL\"]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Unknown license WTFPL"), "{}", error);
+    }
+}
@@ -0,0 +1,476 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::cancel::CancelToken;
+use crate::dry_run::{tree_rows, ManifestFile, TreeRow, UploadManifest};
+use crate::errors::CliError;
+use crate::humanize::format_size;
+use crate::interrupt;
+use crate::polling::parse_seconds;
+use crate::sources::SourceSet;
+
+/// How often the server checks for Ctrl-C and the timeout between requests.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A client that sends nothing for this long is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Characters left as-is in the links to the files, `/` separating directories.
+const PATH_SAFE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const FILES_PATH: &str = "/files/";
+const DOCS_PATH: &str = "/docs/";
+
+/// Shown on top of every page, so that a preview is never taken for the explorer.
+const BANNER: &str = "This is a local preview, nothing has been submitted";
+
+#[derive(Args, Debug)]
+pub struct PreviewArgs {
+    #[arg(
+        long,
+        default_value = "127.0.0.1:0",
+        help = "Address to serve the preview on, a random port of localhost by default"
+    )]
+    pub listen: SocketAddr,
+
+    #[arg(
+        long,
+        help = "Serve the preview on a --listen address other machines can reach"
+    )]
+    pub allow_remote: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = parse_seconds,
+        help = "Stop serving after this many seconds. Serves until Ctrl-C by default"
+    )]
+    pub timeout: Option<Duration>,
+}
+
+/**
+ * What `preview` serves: the staged submission, exactly as `--dry-run` would
+ * list it, with the contents of its files and the warnings of the checks.
+ */
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub manifest: UploadManifest,
+    pub files: SourceSet,
+    pub docs: SourceSet,
+    pub warnings: Vec<String>,
+}
+
+struct Page {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Page {
+    fn html(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.as_bytes().to_vec(),
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn file_link(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, utf8_percent_encode(name, PATH_SAFE))
+}
+
+fn html_tree(prefix: &str, files: &[ManifestFile]) -> String {
+    let rows: Vec<String> = tree_rows(files)
+        .into_iter()
+        .map(|(depth, row)| {
+            let content = match row {
+                TreeRow::Directory(name) => format!("{}/", escape_html(name)),
+                TreeRow::File(file, name) => format!(
+                    "<a href=\"{}\">{}</a> <small>{}</small>",
+                    file_link(prefix, &file.name),
+                    escape_html(name),
+                    format_size(file.size)
+                ),
+            };
+            format!(
+                "<div style=\"padding-left: {}em\">{}</div>",
+                depth * 2,
+                content
+            )
+        })
+        .collect();
+    rows.join("\n")
+}
+
+impl Preview {
+    fn index(&self) -> String {
+        let manifest = &self.manifest;
+        let fields = [
+            ("Endpoint", manifest.endpoint.as_str()),
+            ("Network", manifest.network.as_str()),
+            ("Class hash", manifest.class_hash.as_str()),
+            ("Name", manifest.name.as_str()),
+            ("License", manifest.license.as_str()),
+            ("Contract file", manifest.contract_file.as_str()),
+            ("Scarb version", manifest.scarb_version.as_str()),
+            ("Cairo version", manifest.cairo_version.as_str()),
+        ];
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(field, value)| {
+                format!(
+                    "<tr><th>{}</th><td><code>{}</code></td></tr>",
+                    field,
+                    escape_html(value)
+                )
+            })
+            .collect();
+        let warnings = if self.warnings.is_empty() {
+            "<p>No warnings</p>".to_owned()
+        } else {
+            let items: Vec<String> = self
+                .warnings
+                .iter()
+                .map(|warning| format!("<li>{}</li>", escape_html(warning)))
+                .collect();
+            format!("<ul>{}</ul>", items.join(""))
+        };
+        let dependencies: Vec<String> = manifest
+            .dependencies
+            .iter()
+            .map(|dependency| {
+                format!(
+                    "<li>{} {} ({}/, {}): {}</li>",
+                    escape_html(&dependency.name),
+                    escape_html(&dependency.version),
+                    escape_html(&dependency.directory),
+                    format_size(dependency.size),
+                    escape_html(&dependency.reason)
+                )
+            })
+            .collect();
+        let mut sections = vec![
+            format!(
+                "<div style=\"background: #b00020; color: white; font-size: 1.6em; font-weight: bold; padding: 1em; text-align: center\">{}</div>",
+                BANNER
+            ),
+            format!("<h2>Metadata</h2><table>{}</table>", fields.join("")),
+            format!("<h2>Warnings</h2>{}", warnings),
+            format!(
+                "<h2>Files</h2><p>{} files, {} in total</p>{}",
+                manifest.files.len() + manifest.docs.len(),
+                format_size(manifest.total_size),
+                html_tree(FILES_PATH, &manifest.files)
+            ),
+        ];
+        if !manifest.docs.is_empty() {
+            sections.push(format!(
+                "<h2>Docs</h2>{}",
+                html_tree(DOCS_PATH, &manifest.docs)
+            ));
+        }
+        if !dependencies.is_empty() {
+            sections.push(format!(
+                "<h2>Dependencies</h2><ul>{}</ul>",
+                dependencies.join("")
+            ));
+        }
+        format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Preview of {}</title></head><body style=\"font-family: sans-serif\">\n{}\n</body></html>\n",
+            escape_html(&manifest.name),
+            sections.join("\n")
+        )
+    }
+
+    /**
+     * The page of `path`: the index, or a file as the plain bytes uploaded.
+     */
+    fn page(&self, path: &str) -> Page {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        if path == "/" {
+            return Page::html(self.index());
+        }
+        let file = [(FILES_PATH, &self.files), (DOCS_PATH, &self.docs)]
+            .into_iter()
+            .find_map(|(prefix, set)| {
+                let name = percent_decode_str(path.strip_prefix(prefix)?)
+                    .decode_utf8()
+                    .ok()?;
+                set.contents(&name)
+            });
+        match file {
+            // Never rendered, whatever the file contains
+            Some(contents) => Page {
+                status: 200,
+                content_type: "text/plain; charset=utf-8",
+                body: contents.to_vec(),
+            },
+            None => Page::error(404, "Not part of the submission"),
+        }
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Bad Request",
+    }
+}
+
+/**
+ * Answer the request of `stream`, which is closed afterwards.
+ */
+fn respond(stream: TcpStream, preview: &Preview) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers are of no use, only read so that the client sees its request consumed
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let page = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => preview.page(path),
+        (Some(_), Some(_)) => Page::error(405, "The preview is read-only"),
+        _ => Page::error(400, "Invalid request"),
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nx-content-type-options: nosniff\r\ncontent-security-policy: default-src 'none'; style-src 'unsafe-inline'\r\ncache-control: no-store\r\nconnection: close\r\n\r\n",
+        page.status,
+        status_text(page.status),
+        page.content_type,
+        page.body.len()
+    );
+    let stream = reader.get_mut();
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&page.body)?;
+    stream.flush()
+}
+
+/**
+ * A server of the preview, only reachable from this machine unless remote
+ * access is allowed.
+ */
+pub struct PreviewServer {
+    listener: TcpListener,
+    preview: Preview,
+}
+
+impl PreviewServer {
+    pub fn bind(address: SocketAddr, allow_remote: bool, preview: Preview) -> Result<Self> {
+        if !address.ip().is_loopback() && !allow_remote {
+            return Err(CliError::InvalidArguments(format!(
+                "--listen {} isn't a loopback address, add --allow-remote to serve the preview to other machines",
+                address
+            ))
+            .into());
+        }
+        let listener = TcpListener::bind(address)
+            .with_context(|| format!("Unable to listen on {}", address))?;
+        Ok(Self { listener, preview })
+    }
+
+    pub fn url(&self) -> Result<String> {
+        Ok(format!("http://{}/", self.listener.local_addr()?))
+    }
+
+    /**
+     * Answer requests one at a time until `cancel` is cancelled, e.g. by
+     * Ctrl-C, or `timeout` has passed.
+     */
+    pub fn serve(&self, cancel: &CancelToken, timeout: Option<Duration>) -> Result<()> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.listener.set_nonblocking(true)?;
+        while !cancel.is_cancelled() && deadline.is_none_or(|deadline| Instant::now() < deadline) {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    // A client going away only fails its own request
+                    let _ = respond(stream, &self.preview);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    cancel.wait(ACCEPT_INTERVAL);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Serve `preview` as `args` say until Ctrl-C or the timeout, submitting
+ * nothing.
+ */
+pub fn run_preview(args: PreviewArgs, preview: Preview) -> Result<()> {
+    let server = PreviewServer::bind(args.listen, args.allow_remote, preview)?;
+    println!(
+        "Serving the preview on {}, nothing is submitted. Press Ctrl-C to stop",
+        server.url()?
+    );
+    server.serve(interrupt::token(), args.timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ProjectMetadataInfo;
+    use crate::dependencies::DependencyPlan;
+    use dyn_compiler::contract_target::StarknetContractTarget;
+    use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+    use reqwest::blocking::Client;
+    use std::thread;
+
+    fn preview() -> Preview {
+        let mut files = SourceSet::new();
+        files
+            .add("token/Scarb.toml", "[package]\nname = \"token\"\n")
+            .unwrap();
+        files
+            .add(
+                "token/src/lib.cairo",
+                "// <script>alert(1)</script>\nfn main() -> felt252 { 'é' }\n",
+            )
+            .unwrap();
+        files.add("token/src/my token.cairo", "mod erc20;").unwrap();
+        let mut docs = SourceSet::new();
+        docs.add("README.md", "# Token").unwrap();
+        let metadata = ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: "token".to_owned(),
+            contract_file: "token/src/lib.cairo".to_owned(),
+            target: StarknetContractTarget::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        };
+        let manifest = UploadManifest::new(
+            "https://api.voyager.online/beta/class-verify/0x1",
+            "mainnet",
+            "0x1",
+            "<Token>",
+            "MIT",
+            &metadata,
+            &files,
+            &docs,
+            &DependencyPlan::default(),
+        );
+        Preview {
+            manifest,
+            files,
+            docs,
+            warnings: vec!["VER-030: /home/alice/token is a path of the build machine".to_owned()],
+        }
+    }
+
+    #[test]
+    fn test_preview_serves_the_staged_bytes() {
+        let server = PreviewServer::bind("127.0.0.1:0".parse().unwrap(), false, preview()).unwrap();
+        let url = server.url().unwrap();
+        let cancel = CancelToken::new();
+        let serving = {
+            let cancel = cancel.clone();
+            thread::spawn(move || server.serve(&cancel, None))
+        };
+        let client = Client::new();
+        let get = |path: &str| client.get(format!("{}{}", url, path)).send().unwrap();
+
+        let index = get("").text().unwrap();
+        assert!(index.contains(BANNER), "{}", index);
+        assert!(index.contains("<code>0x1</code>"), "{}", index);
+        assert!(index.contains("<code>&lt;Token&gt;</code>"), "{}", index);
+        assert!(index.contains("/home/alice/token is a path of the build machine"));
+        assert!(index.contains("<a href=\"/files/token/src/my%20token.cairo\">my token.cairo</a>"));
+
+        let staged = preview();
+        for (path, name) in [
+            ("files/token/src/lib.cairo", "token/src/lib.cairo"),
+            (
+                "files/token/src/my%20token.cairo",
+                "token/src/my token.cairo",
+            ),
+        ] {
+            let response = get(path);
+            assert_eq!(response.status(), 200);
+            assert_eq!(
+                response.headers()["content-type"],
+                "text/plain; charset=utf-8"
+            );
+            assert_eq!(
+                response.bytes().unwrap().as_ref(),
+                staged.files.contents(name).unwrap()
+            );
+        }
+        assert_eq!(get("docs/README.md").text().unwrap(), "# Token");
+        assert_eq!(get("files/token/../../etc/passwd").status(), 404);
+        assert_eq!(get("files/README.md").status(), 404);
+        let post = client
+            .post(format!("{}files/token/Scarb.toml", url))
+            .send()
+            .unwrap();
+        assert_eq!(post.status(), 405);
+
+        cancel.cancel();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_preview_stays_local_unless_allowed() {
+        let error = PreviewServer::bind("0.0.0.0:0".parse().unwrap(), false, preview())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("--allow-remote"), "{}", error);
+    }
+
+    #[test]
+    fn test_preview_stops_after_the_timeout() {
+        let server = PreviewServer::bind("127.0.0.1:0".parse().unwrap(), false, preview()).unwrap();
+        let started = Instant::now();
+        server
+            .serve(&CancelToken::new(), Some(Duration::from_millis(200)))
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
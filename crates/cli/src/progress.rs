@@ -0,0 +1,164 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::Mutex,
+    time::Duration,
+};
+
+use console::Term;
+use indicatif::ProgressBar;
+
+use crate::api::VerifyJobStatus;
+use crate::display::sanitize;
+use crate::humanize::format_duration;
+use crate::payload::unix_now;
+
+enum ProgressOutput {
+    /// The status and running time as the message of the spinner, and a line
+    /// above it on every change of status.
+    Spinner(ProgressBar),
+    /// A timestamped line on every change of status, for logs and CI.
+    Lines(Box<dyn Write + Send>),
+}
+
+struct ProgressState {
+    output: ProgressOutput,
+    last: Option<(VerifyJobStatus, Option<String>)>,
+}
+
+/**
+ * Progress of a job while it is polled, so that a verification taking minutes
+ * isn't mistaken for a hung one. Printed on stderr, see `PollProgress::stderr`.
+ */
+pub struct PollProgress {
+    state: Mutex<ProgressState>,
+}
+
+impl fmt::Debug for PollProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollProgress").finish_non_exhaustive()
+    }
+}
+
+impl PollProgress {
+    fn new(output: ProgressOutput) -> Self {
+        Self {
+            state: Mutex::new(ProgressState { output, last: None }),
+        }
+    }
+
+    /**
+     * On `spinner` when stderr is a terminal, as plain lines on stderr
+     * otherwise.
+     */
+    pub fn stderr(spinner: ProgressBar) -> Self {
+        match Term::stderr().is_term() {
+            true => Self::new(ProgressOutput::Spinner(spinner)),
+            false => Self::new(ProgressOutput::Lines(Box::new(io::stderr()))),
+        }
+    }
+
+    pub fn lines(writer: Box<dyn Write + Send>) -> Self {
+        Self::new(ProgressOutput::Lines(writer))
+    }
+
+    /**
+     * Record the status seen `elapsed` after the job was submitted. Changes of
+     * status, or of its description, are printed, the spinner also shows the
+     * running time on every poll.
+     */
+    pub fn observe(&self, status: VerifyJobStatus, description: Option<&str>, elapsed: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let current = (status, description.map(str::to_owned));
+        let changed = state.last.as_ref() != Some(&current);
+        state.last = Some(current);
+        let status = match description {
+            Some(description) => format!("{}: {}", status, sanitize(description)),
+            None => status.to_string(),
+        };
+        match &mut state.output {
+            ProgressOutput::Spinner(spinner) => {
+                if changed {
+                    spinner.println(format!("{} after {}", status, format_duration(elapsed)));
+                }
+                spinner.set_message(format!("{} ({})", status, format_duration(elapsed)));
+            }
+            ProgressOutput::Lines(writer) if changed => {
+                // Like the progress output, a closed pipe doesn't fail the verification
+                let _ = writeln!(
+                    writer,
+                    "[{}] {} after {}",
+                    utc_time_of_day(unix_now()),
+                    status,
+                    format_duration(elapsed)
+                )
+                .and_then(|_| writer.flush());
+            }
+            ProgressOutput::Lines(_) => {}
+        }
+    }
+}
+
+/// `hh:mm:ssZ` of a Unix time.
+fn utc_time_of_day(unix_time: u64) -> String {
+    let seconds = unix_time % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_a_line_is_printed_per_change_of_status() {
+        let output = SharedBuffer::default();
+        let progress = PollProgress::lines(Box::new(output.clone()));
+        let seconds = Duration::from_secs;
+        progress.observe(VerifyJobStatus::Submitted, None, seconds(2));
+        progress.observe(VerifyJobStatus::Submitted, None, seconds(7));
+        progress.observe(VerifyJobStatus::Compiled, None, seconds(62));
+        progress.observe(VerifyJobStatus::Compiled, Some("verifying"), seconds(67));
+        progress.observe(VerifyJobStatus::Success, None, seconds(95));
+
+        let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = printed.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", printed);
+        assert!(lines[0].ends_with("] Submitted after 2.0s"), "{}", lines[0]);
+        assert!(
+            lines[1].ends_with("] Compiled after 1m 02s"),
+            "{}",
+            lines[1]
+        );
+        assert!(
+            lines[2].contains("Compiled: verifying after"),
+            "{}",
+            lines[2]
+        );
+        assert!(lines[3].contains("Success"), "{}", lines[3]);
+    }
+
+    #[test]
+    fn test_time_of_day_is_utc() {
+        assert_eq!(utc_time_of_day(1_700_000_000), "22:13:20Z");
+        assert_eq!(utc_time_of_day(0), "00:00:00Z");
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use fs4::FileExt;
+
+use crate::errors::VerifierError;
+
+/// Directory inside of the project where older versions kept their state,
+/// see `user_dirs::migrate_legacy_state`.
+pub const LEGACY_STATE_DIR: &str = ".voyager";
+pub const LOCK_FILE: &str = "state.lock";
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/**
+ * Advisory lock over the state of a project, kept in `dir`, so that verifier
+ * instances running in parallel on the same project don't interleave their
+ * updates. Released when dropped.
+ */
+#[derive(Debug)]
+pub struct StateLock {
+    file: File,
+}
+
+impl StateLock {
+    pub fn acquire(dir: &Utf8Path, timeout: Duration) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Unable to create the state directory {}", dir))?;
+        let path = dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Unable to open the state lock {}", path))?;
+
+        let started = Instant::now();
+        while file.try_lock_exclusive().is_err() {
+            if started.elapsed() >= timeout {
+                return Err(VerifierError::StateLocked {
+                    path: path.to_string(),
+                    timeout_secs: timeout.as_secs(),
+                }
+                .into());
+            }
+            sleep(LOCK_RETRY_INTERVAL);
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Replace `path` with `contents` through a rename, so that readers never
+ * observe a partially written file.
+ */
+pub fn write_atomic(path: &Utf8Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = Utf8PathBuf::from(format!(
+        "{}.tmp-{}-{}",
+        path,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, contents).with_context(|| format!("Unable to write {}", tmp_path))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Unable to write {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_times_out_while_held() {
+        let project = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-state-lock-{}", std::process::id())),
+        )
+        .unwrap();
+
+        let held = StateLock::acquire(&project, LOCK_TIMEOUT).unwrap();
+        let error = StateLock::acquire(&project, Duration::from_millis(100)).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Another verifier instance holds the lock"));
+
+        drop(held);
+        assert!(StateLock::acquire(&project, Duration::from_millis(100)).is_ok());
+        fs::remove_dir_all(&project).unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+use std::io::{self, IsTerminal};
+
+use console::Term;
+
+/**
+ * Whether the user can be asked. Prompts read stdin and are drawn on stderr,
+ * so stdout is no hint: it is redirected under --json, --no-wait and --quiet,
+ * while stdin is the tarball under --stdin-tar.
+ */
+pub fn can_prompt() -> bool {
+    io::stdin().is_terminal() && Term::stderr().is_term()
+}
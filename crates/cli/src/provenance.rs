@@ -0,0 +1,326 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    fmt::{self, Display},
+};
+
+use camino::Utf8Path;
+use comfy_table::Table;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::display::sanitize;
+
+/// Variables read by the verifier that only the environment sets.
+pub const ENV_SETTINGS: [&str; 11] = [
+    "DEBUG_NETWORK",
+    "USE_POLLING_MAX_RETRIES",
+    "POLLING_RESTART_GRACE_RETRIES",
+    "VOYAGER_MAX_COLLECTION_FILES",
+    "VOYAGER_TRANSIENT_FAILURES",
+    "VOYAGER_SCARB_VERSION",
+    "VOYAGER_CAIRO_VERSION",
+    "VOYAGER_STAGING_MAX_AGE_HOURS",
+    "VOYAGER_TELEMETRY_URL",
+    "VOYAGER_HTTP_ATTEMPTS",
+    "VOYAGER_LEGACY_CLASS_ENDPOINT",
+];
+
+/// Parts of variable names whose values are never recorded.
+const SECRET_MARKERS: [&str; 4] = ["KEY", "TOKEN", "SECRET", "PASSWORD"];
+
+/**
+ * Where the effective value of a setting came from. Variables are set by the
+ * environment, or else by the project's `.env`, or else by the user config.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Source {
+    Flag { name: String },
+    Environment { var: String },
+    ProjectEnv { var: String, path: String },
+    UserConfig { var: String, path: String },
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Flag { name } => write!(f, "flag {}", name),
+            Source::Environment { var } => write!(f, "environment variable {}", var),
+            Source::ProjectEnv { var, path } => write!(f, "{} in {}", var, path),
+            Source::UserConfig { var, path } => write!(f, "{} in the user config {}", var, path),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/**
+ * A resolved setting. Secrets only keep where they came from.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigValue {
+    /// `None` when unset, or redacted.
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+    pub source: Source,
+}
+
+/**
+ * The effective configuration of a run, by setting, as recorded in the report
+ * and shown by `config show --provenance`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct ConfigSnapshot(pub BTreeMap<String, ConfigValue>);
+
+impl ConfigSnapshot {
+    pub fn format(&self) -> String {
+        let mut table = Table::new();
+        table.set_header(vec!["Setting", "Value", "Source"]);
+        for (setting, resolved) in self.0.iter() {
+            let value = match (&resolved.value, resolved.redacted) {
+                (_, true) => "<redacted>".to_owned(),
+                (Some(value), false) => sanitize(value).into_owned(),
+                (None, false) => "<not set>".to_owned(),
+            };
+            table.add_row(vec![setting.clone(), value, resolved.source.to_string()]);
+        }
+        table.to_string()
+    }
+}
+
+pub fn is_secret(var: &str) -> bool {
+    let var = var.to_uppercase();
+    SECRET_MARKERS.iter().any(|marker| var.contains(marker))
+}
+
+/**
+ * Layers loaded into the environment, in the order they are loaded.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    ProjectEnv,
+    UserConfig,
+}
+
+/**
+ * Which layer set each variable of the environment. Layers never override a
+ * variable that is already set, so the first one to set it supplies it.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct EnvOrigins {
+    loaded: HashMap<String, Source>,
+}
+
+/**
+ * Run `load`, returning the variables it added to the environment.
+ */
+pub fn set_by<T>(load: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let before: HashSet<String> = env::vars_os()
+        .filter_map(|(var, _)| var.into_string().ok())
+        .collect();
+    let result = load();
+    let added = env::vars_os()
+        .filter_map(|(var, _)| var.into_string().ok())
+        .filter(|var| !before.contains(var))
+        .collect();
+    (result, added)
+}
+
+impl EnvOrigins {
+    pub fn record(&mut self, vars: Vec<String>, layer: Layer, path: &Utf8Path) {
+        for var in vars {
+            let source = match layer {
+                Layer::ProjectEnv => Source::ProjectEnv {
+                    var: var.clone(),
+                    path: path.to_string(),
+                },
+                Layer::UserConfig => Source::UserConfig {
+                    var: var.clone(),
+                    path: path.to_string(),
+                },
+            };
+            self.loaded.entry(var).or_insert(source);
+        }
+    }
+
+    pub fn source(&self, var: &str) -> Source {
+        self.loaded
+            .get(var)
+            .cloned()
+            .unwrap_or(Source::Environment {
+                var: var.to_owned(),
+            })
+    }
+
+    /**
+     * Resolve a setting the way the verifier does: its flag, then its
+     * variable, then its default.
+     */
+    pub fn resolve(
+        &self,
+        flag: Option<(&str, Option<String>)>,
+        var: Option<&str>,
+        default: Option<String>,
+        vars: &dyn Fn(&str) -> Option<String>,
+    ) -> ConfigValue {
+        let from_flag = flag.and_then(|(name, value)| {
+            value.map(|value| {
+                (
+                    value,
+                    Source::Flag {
+                        name: name.to_owned(),
+                    },
+                )
+            })
+        });
+        let from_var = || {
+            let var = var?;
+            let value = vars(var).filter(|value| !value.is_empty())?;
+            Some((value, self.source(var)))
+        };
+        let (value, source) = match from_flag.or_else(from_var) {
+            Some((value, source)) => (Some(value), source),
+            None => (default, Source::Default),
+        };
+        let redacted = var.is_some_and(is_secret) && value.is_some();
+        ConfigValue {
+            value: value.filter(|_| !redacted),
+            redacted,
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use std::fs;
+
+    /**
+     * Loads the layers like the verifier does, each test with its own
+     * variables since the environment is shared by the tests.
+     */
+    fn load_layers(
+        name: &str,
+        project_env: &[(&str, &str)],
+        user_config: &[(&str, &str)],
+    ) -> (EnvOrigins, Utf8PathBuf, Utf8PathBuf) {
+        let dir = Utf8PathBuf::from_path_buf(env::temp_dir().join(format!(
+            "voyager-provenance-{}-{}",
+            name,
+            std::process::id()
+        )))
+        .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let write = |file: &str, vars: &[(&str, &str)]| {
+            let path = dir.join(file);
+            let lines: Vec<String> = vars
+                .iter()
+                .map(|(var, value)| format!("{}={}", var, value))
+                .collect();
+            fs::write(&path, lines.join("\n")).unwrap();
+            path
+        };
+        let project_env_path = write(".env", project_env);
+        let user_config_path = write("config.env", user_config);
+
+        let mut origins = EnvOrigins::default();
+        let (_, added) = set_by(|| dotenv::from_path(&project_env_path));
+        origins.record(added, Layer::ProjectEnv, &project_env_path);
+        let (_, added) = set_by(|| dotenv::from_path(&user_config_path));
+        origins.record(added, Layer::UserConfig, &user_config_path);
+        (origins, project_env_path, user_config_path)
+    }
+
+    fn resolve(origins: &EnvOrigins, flag: Option<&str>, var: &str) -> ConfigValue {
+        origins.resolve(
+            Some(("--setting", flag.map(str::to_owned))),
+            Some(var),
+            Some("default".to_owned()),
+            &|var| env::var(var).ok(),
+        )
+    }
+
+    #[test]
+    fn test_every_precedence_combination() {
+        const CONFIG: &str = "VOYAGER_TEST_PROVENANCE_CONFIG";
+        const PROJECT: &str = "VOYAGER_TEST_PROVENANCE_PROJECT";
+        const PROCESS: &str = "VOYAGER_TEST_PROVENANCE_PROCESS";
+        const UNSET: &str = "VOYAGER_TEST_PROVENANCE_UNSET";
+        env::set_var(PROCESS, "process");
+        let (origins, project_env, user_config) = load_layers(
+            "precedence",
+            &[(PROJECT, "project"), (PROCESS, "project")],
+            &[(CONFIG, "config"), (PROJECT, "config"), (PROCESS, "config")],
+        );
+
+        let cases = [
+            (
+                CONFIG,
+                "config",
+                Source::UserConfig {
+                    var: CONFIG.to_owned(),
+                    path: user_config.to_string(),
+                },
+            ),
+            (
+                PROJECT,
+                "project",
+                Source::ProjectEnv {
+                    var: PROJECT.to_owned(),
+                    path: project_env.to_string(),
+                },
+            ),
+            (
+                PROCESS,
+                "process",
+                Source::Environment {
+                    var: PROCESS.to_owned(),
+                },
+            ),
+            (UNSET, "default", Source::Default),
+        ];
+        for (var, value, source) in cases {
+            let resolved = resolve(&origins, None, var);
+            // The recorded value is the one the verifier actually reads
+            assert_eq!(resolved.value.as_deref(), Some(value), "{}", var);
+            assert_eq!(env::var(var).ok().as_deref().unwrap_or("default"), value);
+            assert_eq!(resolved.source, source, "{}", var);
+
+            let flagged = resolve(&origins, Some("flag"), var);
+            assert_eq!(flagged.value.as_deref(), Some("flag"));
+            assert_eq!(
+                flagged.source,
+                Source::Flag {
+                    name: "--setting".to_owned()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_secrets_keep_their_source_only() {
+        const SECRET: &str = "VOYAGER_TEST_PROVENANCE_API_KEY";
+        let (origins, _, user_config) = load_layers("secrets", &[], &[(SECRET, "hunter2")]);
+
+        let resolved = origins.resolve(None, Some(SECRET), None, &|var| env::var(var).ok());
+        assert_eq!(resolved.value, None);
+        assert!(resolved.redacted);
+        let snapshot = ConfigSnapshot(BTreeMap::from([("api_key".to_owned(), resolved)]));
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(!json.contains("hunter2"), "{}", json);
+        assert!(json.contains(user_config.as_str()), "{}", json);
+        assert!(!snapshot.format().contains("hunter2"));
+
+        let unset = origins.resolve(None, Some("VOYAGER_TEST_PROVENANCE_TOKEN"), None, &|_| None);
+        assert!(!unset.redacted);
+        assert_eq!(
+            serde_json::from_str::<ConfigSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+}
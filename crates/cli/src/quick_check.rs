@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::display::sanitize;
+use crate::humanize::format_duration;
+
+/// Time the checks of --quick-check may take together.
+pub const QUICK_CHECK_BUDGET: Duration = Duration::from_secs(3);
+
+/// Above this, the payload is worth a look before uploading it.
+pub const LARGE_PAYLOAD_SIZE: u64 = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/**
+ * Something a check of --quick-check found, named after the check.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/**
+ * Runs the checks one after the other until the budget is exhausted, the
+ * remaining ones are reported as not run rather than slowing down the run.
+ */
+pub struct QuickCheck {
+    started: Instant,
+    budget: Duration,
+    findings: Vec<Finding>,
+}
+
+impl QuickCheck {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            budget,
+            findings: vec![],
+        }
+    }
+
+    /**
+     * Run `check`, which returns its warnings. The error it fails with is the
+     * same a real run would fail with, and is recorded as an error.
+     */
+    pub fn check(&mut self, name: &'static str, check: impl FnOnce() -> Result<Vec<String>>) {
+        if self.started.elapsed() >= self.budget {
+            self.findings.push(Finding {
+                check: name,
+                severity: Severity::Warning,
+                message: format!(
+                    "not run, the time budget of {} is exhausted",
+                    format_duration(self.budget)
+                ),
+            });
+            return;
+        }
+        match check() {
+            Ok(warnings) => self
+                .findings
+                .extend(warnings.into_iter().map(|message| Finding {
+                    check: name,
+                    severity: Severity::Warning,
+                    message,
+                })),
+            Err(e) => self.findings.push(Finding {
+                check: name,
+                severity: Severity::Error,
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    pub fn errors(&self) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+pub fn format_findings(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|finding| {
+            format!(
+                "{} [{}] {}",
+                match finding.severity {
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                },
+                finding.check,
+                sanitize(&finding.message)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::VerifierError;
+
+    #[test]
+    fn test_failed_checks_are_errors() {
+        let mut quick_check = QuickCheck::new(QUICK_CHECK_BUDGET);
+        quick_check.check("manifest", || Ok(vec![]));
+        quick_check.check("contracts", || Err(VerifierError::NoContracts.into()));
+        quick_check.check("lockfile", || Ok(vec!["no Scarb.lock".to_owned()]));
+
+        assert_eq!(quick_check.errors(), 1);
+        assert_eq!(
+            quick_check.findings(),
+            &[
+                Finding {
+                    check: "contracts",
+                    severity: Severity::Error,
+                    message: VerifierError::NoContracts.to_string(),
+                },
+                Finding {
+                    check: "lockfile",
+                    severity: Severity::Warning,
+                    message: "no Scarb.lock".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            format_findings(&quick_check.findings()[1..]),
+            "warning [lockfile] no Scarb.lock"
+        );
+    }
+
+    #[test]
+    fn test_checks_past_the_budget_are_not_run() {
+        let mut quick_check = QuickCheck::new(Duration::ZERO);
+        quick_check.check("manifest", || panic!("ran past the budget"));
+
+        assert_eq!(quick_check.errors(), 0);
+        assert!(quick_check.findings()[0].message.contains("not run"));
+    }
+}
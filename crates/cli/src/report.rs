@@ -0,0 +1,301 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::api::JobMetadata;
+use crate::class_hash::ClassHash;
+use crate::http_retry::ReliabilityStats;
+use crate::locked::Reproducibility;
+use crate::provenance::ConfigSnapshot;
+use crate::transfer::TransferStats;
+use crate::verified_metadata::MetadataChange;
+
+/// Maximum number of status observations kept per job in the report.
+pub const MAX_STATUS_HISTORY: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusObservation {
+    pub status: String,
+    pub elapsed_ms: u64,
+}
+
+/**
+ * Status transitions observed while polling a job. Repeated identical statuses
+ * are collapsed and only the most recent observations are retained, so that a
+ * long running job can't grow the history unboundedly.
+ */
+#[derive(Debug)]
+pub struct StatusHistory {
+    observations: VecDeque<StatusObservation>,
+    dropped: usize,
+    capacity: usize,
+}
+
+impl Default for StatusHistory {
+    fn default() -> Self {
+        Self::with_capacity(MAX_STATUS_HISTORY)
+    }
+}
+
+impl StatusHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            observations: VecDeque::with_capacity(capacity),
+            dropped: 0,
+            capacity,
+        }
+    }
+
+    pub fn observe(&mut self, status: String, elapsed_ms: u64) {
+        if self.observations.back().map(|o| &o.status) == Some(&status) {
+            return;
+        }
+        if self.observations.len() == self.capacity {
+            self.observations.pop_front();
+            self.dropped += 1;
+        }
+        self.observations
+            .push_back(StatusObservation { status, elapsed_ms });
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn to_vec(&self) -> Vec<StatusObservation> {
+        self.observations.iter().cloned().collect()
+    }
+}
+
+/**
+ * Whether the polled job was submitted by this run, or was already known and
+ * attached to instead.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Submission {
+    #[default]
+    Dispatched,
+    /// A job in progress for the class, or a previous job for the same payload.
+    Attached,
+}
+
+/**
+ * Comparison of the verified class hash with the one pinned by --expect-hash.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HashCheck {
+    pub expected: String,
+    /// Class hash reported by the backend for the successful job, as received.
+    pub remote: String,
+    pub matches: bool,
+}
+
+impl HashCheck {
+    /**
+     * Hashes are compared normalized, so that `0x0ABC` matches `0xabc`. A
+     * remote value that isn't a class hash never matches.
+     */
+    pub fn new(expected: &ClassHash, remote: &str) -> Self {
+        Self {
+            expected: expected.to_string(),
+            remote: remote.to_owned(),
+            matches: remote
+                .parse::<ClassHash>()
+                .is_ok_and(|remote| &remote == expected),
+        }
+    }
+}
+
+/**
+ * Summary of a single verification, as written to the report.
+ * Response bodies are never retained, only their summarized outcome.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReportEntry {
+    pub network: String,
+    pub class_hash: String,
+    pub job_id: Option<String>,
+    pub status: Option<String>,
+    pub status_description: Option<String>,
+    pub error: Option<String>,
+    /// Stable code of the error, see `explain`.
+    #[serde(default)]
+    pub error_code: Option<String>,
+    pub duration_ms: u64,
+    pub status_history: Vec<StatusObservation>,
+    pub dropped_status_observations: usize,
+    /// Number of times the backend restarted the job while it was polled.
+    #[serde(default)]
+    pub restarts: u32,
+    /// Metadata echoed by the backend for the job.
+    #[serde(flatten)]
+    pub job_metadata: JobMetadata,
+    /// Changes to the metadata of an already verified class made by resubmitting it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata_changes: Vec<MetadataChange>,
+    /// 1 for the first submission, incremented by each automatic resubmission.
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Aliases submitted along with the name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Documentation files uploaded next to the sources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
+    /// Requests made and body bytes exchanged with the backend by the process.
+    #[serde(default)]
+    pub transfer: TransferStats,
+    /// Requests of the process sent again after transient failures, by cause.
+    #[serde(default)]
+    pub reliability: ReliabilityStats,
+    /// Whether the polled job was dispatched by this run or attached to.
+    #[serde(default)]
+    pub submission: Submission,
+    /// Outcome of --expect-hash, for successful jobs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_check: Option<HashCheck>,
+    /// Whether the run was --locked or --frozen, for audits.
+    #[serde(default, skip_serializing_if = "Reproducibility::is_unlocked")]
+    pub reproducibility: Reproducibility,
+    /// Effective configuration of the run, with where each setting came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigSnapshot>,
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+impl ReportEntry {
+    pub fn with_history(mut self, history: &StatusHistory) -> Self {
+        self.status_history = history.to_vec();
+        self.dropped_status_observations = history.dropped();
+        self
+    }
+}
+
+/**
+ * Streams report entries to a JSONL file as soon as they are available,
+ * instead of accumulating them in memory until the end of the run.
+ */
+pub struct ReportWriter {
+    writer: BufWriter<File>,
+}
+
+impl ReportWriter {
+    /**
+     * Entries are appended, so that a single report can cover several runs.
+     */
+    pub fn open(path: &Utf8Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open report file {}", path))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /**
+     * Each entry is flushed right away so that the report stays complete up to
+     * the last finished entry even if the process dies.
+     */
+    pub fn write_entry(&mut self, entry: &ReportEntry) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/**
+ * Failing to write the report should never fail the verification itself.
+ */
+pub fn append_to_report(path: &Utf8Path, entry: &ReportEntry) {
+    if let Err(e) = ReportWriter::open(path).and_then(|mut writer| writer.write_entry(entry)) {
+        println!("Unable to write the verification report: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+
+    fn read_report(path: &Utf8Path) -> Vec<ReportEntry> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_status_history_collapses_repeats_and_is_bounded() {
+        let mut history = StatusHistory::with_capacity(4);
+        for i in 0..10_000u64 {
+            history.observe(format!("status-{}", i % 7), i);
+            history.observe(format!("status-{}", i % 7), i);
+        }
+
+        assert_eq!(history.to_vec().len(), 4);
+        assert_eq!(history.dropped(), 10_000 - 4);
+        assert_eq!(history.to_vec().last().unwrap().elapsed_ms, 9_999);
+    }
+
+    #[test]
+    fn test_hash_check_compares_normalized_hashes() {
+        let expected: ClassHash = "0x0abc".parse().unwrap();
+        assert!(HashCheck::new(&expected, "0xABC").matches);
+        assert!(!HashCheck::new(&expected, "0xabd").matches);
+        assert!(!HashCheck::new(&expected, "not a hash").matches);
+        assert_eq!(HashCheck::new(&expected, "0xABC").remote, "0xABC");
+    }
+
+    #[test]
+    fn test_report_soak_streams_every_entry() {
+        let path = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-report-{}.jsonl", std::process::id())),
+        )
+        .unwrap();
+
+        let _ = std::fs::remove_file(&path);
+        let mut writer = ReportWriter::open(&path).unwrap();
+        for i in 0..500 {
+            let mut history = StatusHistory::default();
+            for poll in 0..200u64 {
+                let status = if poll < 100 { "Submitted" } else { "Compiled" };
+                history.observe(status.to_owned(), poll * 5000);
+            }
+            history.observe("Success".to_owned(), 1_000_000);
+            assert!(history.to_vec().len() <= MAX_STATUS_HISTORY);
+
+            let entry = ReportEntry {
+                network: "sepolia".to_owned(),
+                class_hash: format!("0x{:x}", i),
+                job_id: Some(format!("job-{}", i)),
+                status: Some("Success".to_owned()),
+                duration_ms: 1_000_000,
+                ..Default::default()
+            }
+            .with_history(&history);
+            writer.write_entry(&entry).unwrap();
+        }
+        drop(writer);
+
+        let entries = read_report(&path);
+        assert_eq!(entries.len(), 500);
+        assert_eq!(entries[499].class_hash, "0x1f3");
+        assert_eq!(entries[0].status_history.len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
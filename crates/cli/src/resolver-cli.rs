@@ -1,9 +1,23 @@
-mod api;
+// Only the filter of the collected files is used
+#[allow(dead_code)]
+mod collection;
+// Only the errors raised by the shared modules are used
+#[allow(dead_code, unused_imports)]
+mod errors;
+// Only the durations of the timings are used
+#[allow(dead_code)]
+mod humanize;
 mod resolver;
+// The resolver records its phases, this binary never reports them
+#[allow(dead_code)]
+mod timing;
 mod utils;
 
+use starknet_verifier_api::{api, form};
+
+use crate::collection::CollectionFilter;
 use crate::resolver::{resolve_scarb, TargetType};
-use crate::utils::detect_local_tools;
+use crate::utils::detect_project_tools;
 
 use camino::Utf8PathBuf;
 use console::{style, Emoji};
@@ -56,13 +70,18 @@ fn main() -> anyhow::Result<()> {
     );
 
     // Resolve project
-    let (_project_files, _project_metadata) = match target_type {
+    let (_project_files, _project_metadata, _artifacts) = match target_type {
         TargetType::File => {
             panic!("Single contract file verification is not yet implemented, please use a Scarb project instead.");
         }
         TargetType::ScarbProject => {
-            let (local_scarb_version, local_cairo_version) = detect_local_tools();
-            resolve_scarb(utf8_path.clone(), local_cairo_version, local_scarb_version)?
+            let (local_scarb_version, local_cairo_version) = detect_project_tools(&utf8_path)?;
+            resolve_scarb(
+                utf8_path.clone(),
+                local_cairo_version,
+                local_scarb_version,
+                &CollectionFilter::default(),
+            )?
         }
     };
 
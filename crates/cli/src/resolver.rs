@@ -1,12 +1,29 @@
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
 use walkdir::{DirEntry, WalkDir};
 
 use crate::api::{FileInfo, ProjectMetadataInfo};
-use dyn_compiler::dyn_compiler::{DynamicCompiler, SupportedCairoVersions, SupportedScarbVersions};
-use voyager_resolver_cairo::compiler::scarb_utils::read_additional_scarb_manifest_metadata;
-use voyager_resolver_cairo::dyn_compiler::VoyagerGeneratorWrapper as VoyagerGenerator;
+use crate::collection::CollectionFilter;
+use crate::errors::VerifierError;
+use crate::form::{upload_file_name, validate_unique_file_names};
+use crate::timing;
+use dyn_compiler::dyn_compiler::{
+    DynamicCompiler, ResolverInfo, SupportedCairoVersions, SupportedScarbVersions,
+};
+use voyager_resolver_cairo::compiler::scarb_utils::{
+    read_additional_scarb_manifest_metadata, read_scarb_metadata, read_starknet_contract_target,
+    AdditionalScarbManifestMetadata, StarknetContractTarget, HOOKS_KEY,
+};
+use voyager_resolver_cairo::dyn_compiler::{
+    VoyagerGeneratorWrapper as VoyagerGenerator, RESOLVER_INFO,
+};
+
+/// Root file of a package, relative to it, unless its targets say otherwise.
+const DEFAULT_CRATE_ROOT: &str = "src/lib.cairo";
 
 #[allow(dead_code)]
 pub enum TargetType {
@@ -14,10 +31,25 @@ pub enum TargetType {
     File,
 }
 
-pub fn get_dynamic_compiler(cairo_version: SupportedCairoVersions) -> Box<dyn DynamicCompiler> {
-    match cairo_version {
-        SupportedCairoVersions::V2_8_4 => Box::new(VoyagerGenerator),
-    }
+pub fn get_dynamic_compiler(_cairo_version: SupportedCairoVersions) -> Box<dyn DynamicCompiler> {
+    // The resolver only walks the module tree, which reads the same for every
+    // supported version. The backend compiles with the selected one.
+    Box::new(VoyagerGenerator)
+}
+
+/**
+ * What the resolver `get_dynamic_compiler` returns for `cairo_version` supports.
+ */
+pub fn resolver_info(_cairo_version: SupportedCairoVersions) -> ResolverInfo {
+    RESOLVER_INFO
+}
+
+/**
+ * The resolvers `get_dynamic_compiler` can return, described without
+ * constructing any of them.
+ */
+pub fn registered_resolvers() -> Vec<ResolverInfo> {
+    vec![RESOLVER_INFO]
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -31,11 +63,110 @@ struct ScarbTomlRawData {
     package: ScarbTomlRawPackageData,
 }
 
+/**
+ * The contract to verify, found without compiling the package. Exactly one
+ * is supported.
+ */
+pub fn discover_contract(
+    source_dir: &Utf8PathBuf,
+    cairo_version: SupportedCairoVersions,
+) -> anyhow::Result<Utf8PathBuf> {
+    let compiler = get_dynamic_compiler(cairo_version);
+    let mut contract_paths = timing::phase("project discovery", || {
+        compiler.get_contracts_to_verify_path(source_dir)
+    })?;
+
+    // TODO move the contract selection before the resolving step as a 'pre-resolving' step
+    // in order to allow for automatic contracts discovery and selection
+    if contract_paths.is_empty() {
+        return Err(VerifierError::NoContracts.into());
+    }
+    if contract_paths.len() > 1 {
+        return Err(VerifierError::SeveralContracts.into());
+    }
+    Ok(contract_paths.remove(0))
+}
+
+/**
+ * The package metadata and the starknet-contract target of the manifest.
+ */
+pub fn read_manifest(
+    source_dir: &Utf8PathBuf,
+) -> anyhow::Result<(AdditionalScarbManifestMetadata, StarknetContractTarget)> {
+    // TODO: switch this to using scarb-metadata
+    let scarb_toml_content = fs::read_to_string(source_dir.join("Scarb.toml"))?;
+    let extracted_scarb_toml_data =
+        read_additional_scarb_manifest_metadata(scarb_toml_content.as_str())?;
+    let target =
+        read_starknet_contract_target(&scarb_toml_content, &extracted_scarb_toml_data.name)?
+            .unwrap_or_default();
+    Ok((extracted_scarb_toml_data, target))
+}
+
+/**
+ * Name of the contract selected in the [tool.voyager] table of a manifest.
+ */
+pub fn selected_contract(manifest: &str) -> Option<String> {
+    let manifest = manifest.parse::<toml::Value>().ok()?;
+    manifest
+        .get("tool")?
+        .get("voyager")?
+        .as_table()?
+        .keys()
+        .find(|name| name.as_str() != HOOKS_KEY)
+        .cloned()
+}
+
+/**
+ * Version of the package of a manifest. `None` when it is inherited from the
+ * workspace, whose manifest isn't read.
+ */
+pub fn package_version(manifest: &str) -> Option<String> {
+    let manifest = manifest.parse::<toml::Value>().ok()?;
+    manifest
+        .get("package")?
+        .get("version")?
+        .as_str()
+        .map(str::to_owned)
+}
+
+/**
+ * Root file of the package, relative to its directory, per Scarb metadata:
+ * that of its starknet-contract or lib target. Its directory is the source
+ * root, e.g. `src` for `src/lib.cairo`, the default when the metadata can't
+ * be read.
+ */
+pub fn crate_root(source_dir: &Utf8Path, package: &str) -> Utf8PathBuf {
+    let Ok(metadata) = read_scarb_metadata(&source_dir.join("Scarb.toml").into_std_path_buf())
+    else {
+        return DEFAULT_CRATE_ROOT.into();
+    };
+    metadata
+        .packages
+        .iter()
+        .filter(|metadata| metadata.name == package)
+        .flat_map(|metadata| {
+            metadata
+                .targets
+                .iter()
+                .filter(|target| target.kind == "starknet-contract" || target.kind == "lib")
+                .filter_map(|target| target.source_path.strip_prefix(&metadata.root).ok())
+        })
+        .next()
+        .map(Utf8Path::to_owned)
+        .unwrap_or_else(|| DEFAULT_CRATE_ROOT.into())
+}
+
+/**
+ * Compile the package, returning the files to upload, the metadata of the
+ * project and the Sierra contract classes the package compiled to.
+ */
 pub fn resolve_scarb(
     path: Utf8PathBuf,
     cairo_version: SupportedCairoVersions,
     scarb_version: SupportedScarbVersions,
-) -> anyhow::Result<(Vec<FileInfo>, ProjectMetadataInfo)> {
+    filter: &CollectionFilter,
+) -> anyhow::Result<(Vec<FileInfo>, ProjectMetadataInfo, Vec<Utf8PathBuf>)> {
     // Extract necessary files from the Scarb project for the verified contract
     let source_dir = if path.is_absolute() {
         path
@@ -46,27 +177,17 @@ pub fn resolve_scarb(
     };
 
     let compiler = get_dynamic_compiler(cairo_version);
-    let contract_paths = compiler.get_contracts_to_verify_path(&source_dir)?;
-
-    // TODO move the contract selection before the resolving step as a 'pre-resolving' step
-    // in order to allow for automatic contracts discovery and selection
-    if contract_paths.is_empty() {
-        return Err(anyhow::anyhow!("No contracts to verify"));
-    }
-    if contract_paths.len() > 1 {
-        return Err(anyhow::anyhow!(
-            "Only one contract can be verified at a time"
-        ));
+    let contract_path = discover_contract(&source_dir, cairo_version)?;
+    let (extracted_scarb_toml_data, target) = read_manifest(&source_dir)?;
+    if !target.build_external_contracts.is_empty() {
+        println!(
+            "Including the external contracts built by the package: {}",
+            target.build_external_contracts.join(", ")
+        );
     }
 
-    // Read the Scarb metadata to get more information
-    // TODO: switch this to using scarb-metadata
-    let scarb_toml_content = fs::read_to_string(source_dir.join("Scarb.toml"))?;
-    let extracted_scarb_toml_data =
-        read_additional_scarb_manifest_metadata(scarb_toml_content.as_str())?;
-
     // Compiler and extract the necessary files
-    compiler.compile_project(&source_dir)?;
+    let artifacts = timing::phase("compile", || compiler.compile_project(&source_dir))?;
 
     // Since we know that we extract the files into the `voyager-verify` directory,
     // we'll read the files from there.
@@ -79,11 +200,39 @@ pub fn resolve_scarb(
         .strip_prefix(extracted_files_dir.clone())
         .unwrap();
 
+    // The files of the package the filter leaves out, e.g. gitignored ones,
+    // are left out of its resolved copy too
+    let collected: HashSet<PathBuf> = filter
+        .files(&source_dir)
+        .iter()
+        .filter_map(|file| file.strip_prefix(&source_dir).ok())
+        .map(|file| file.as_std_path().to_owned())
+        .collect();
+    let package_copy_dir = extracted_files_dir.join(project_dir_path);
+
     // Read project directory
+    let collection_started = Instant::now();
+    let detailed_timing = timing::is_detailed();
+    let mut last_entry = Instant::now();
     let project_files = WalkDir::new(extracted_files_dir.as_path())
         .into_iter()
         .filter_map(|f| f.ok())
+        .inspect(|f| {
+            if detailed_timing {
+                let directory = f.path().parent().unwrap_or(f.path());
+                let relative = directory
+                    .strip_prefix(&extracted_files_dir)
+                    .unwrap_or(directory);
+                timing::record_directory_walk(&relative.to_string_lossy(), last_entry.elapsed());
+                last_entry = Instant::now();
+            }
+        })
         .filter(|f| f.file_type().is_file())
+        .filter(|f| match f.path().strip_prefix(&package_copy_dir) {
+            Ok(relative) => collected.contains(relative),
+            // Dependencies are resolved by Scarb, not collected
+            Err(_) => true,
+        })
         .filter(|f| {
             let file_path = f.path();
 
@@ -106,26 +255,29 @@ pub fn resolve_scarb(
 
     let project_files = project_files
         .iter()
-        .map(|f| {
+        .map(|f| -> anyhow::Result<FileInfo> {
             let actual_path = f.path().to_owned();
-            let file_name = actual_path
-                .strip_prefix(&extracted_files_dir)
-                .unwrap()
-                .to_str()
-                .to_owned()
-                .unwrap()
-                .to_string();
-            FileInfo {
+            // Relative to the layout, so that modules of the same name in
+            // different directories don't clobber each other
+            let file_name = upload_file_name(extracted_files_dir.as_std_path(), &actual_path)?;
+            Ok(FileInfo {
                 name: file_name,
                 path: actual_path,
-            }
+            })
         })
-        .collect::<Vec<FileInfo>>();
+        .collect::<anyhow::Result<Vec<FileInfo>>>()?;
+    // Each package is in its own directory, see the layout of the resolver
+    let file_names = project_files
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<&str>>();
+    validate_unique_file_names(&file_names)?;
+    timing::record_phase("collection", collection_started.elapsed());
 
     let contract_file = format!(
         "{}/src/{}",
         extracted_scarb_toml_data.name.clone(),
-        contract_paths[0].as_str()
+        contract_path.as_str()
     );
 
     let project_metadata = ProjectMetadataInfo {
@@ -133,7 +285,10 @@ pub fn resolve_scarb(
         scarb_version,
         contract_file,
         project_dir_path: project_dir_path.as_str().to_owned(),
+        target,
+        plugins: vec![],
+        cached_dependencies: vec![],
     };
 
-    Ok((project_files, project_metadata))
+    Ok((project_files, project_metadata, artifacts))
 }
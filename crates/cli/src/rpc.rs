@@ -0,0 +1,378 @@
+use std::env;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::api::{fetch_class, Network};
+use crate::cancel::CancelToken;
+use crate::class_hash::ClassHash;
+use crate::errors::VerifierError;
+use crate::ip_version;
+use crate::polling::HTTP_TIMEOUT;
+use crate::transfer::{self, RPC_ENDPOINT};
+
+/// JSON-RPC error code of `starknet_getClass` for unknown classes.
+const CLASS_HASH_NOT_FOUND: i64 = 28;
+
+/// Starknet RPC of the custom and local networks, which have no class endpoint of their own.
+pub const RPC_URL_VAR: &str = "STARKNET_RPC_URL";
+
+/**
+ * Where the class checks of `network` go. `--rpc-url` applies to any network,
+ * `STARKNET_RPC_URL` only to the custom and local ones, whose explorer may not
+ * serve classes. `None` checks against the explorer of the network.
+ */
+pub fn rpc_url_for(network: &Network, flag: Option<&str>) -> Option<RpcUrl> {
+    if let Some(url) = flag {
+        return Some(RpcUrl::Flag(url.to_owned()));
+    }
+    match network {
+        Network::Local | Network::Custom { .. } => env::var(RPC_URL_VAR)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .map(RpcUrl::Network),
+        Network::Mainnet | Network::Sepolia => None,
+    }
+}
+
+/**
+ * An RPC the class is checked against, by where it was given.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpcUrl {
+    /// From `--rpc-url`, possibly another chain than the one verified on.
+    Flag(String),
+    /// From `STARKNET_RPC_URL`, the node of the custom or local network.
+    Network(String),
+}
+
+impl RpcUrl {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RpcUrl::Flag(url) | RpcUrl::Network(url) => url,
+        }
+    }
+}
+
+/**
+ * Chain id the RPC should serve: `--chain-id`, or else the chain of the
+ * public networks.
+ */
+pub fn expected_chain_id(network: &Network, flag: Option<&str>) -> Option<String> {
+    flag.map(str::to_owned)
+        .or_else(|| network_chain_id(network).map(str::to_owned))
+}
+
+/**
+ * Chain id of the network, as a short string. `None` for the local and
+ * custom networks, whose chain isn't known in advance.
+ */
+pub fn network_chain_id(network: &Network) -> Option<&'static str> {
+    match network {
+        Network::Mainnet => Some("SN_MAIN"),
+        Network::Sepolia => Some("SN_SEPOLIA"),
+        Network::Local | Network::Custom { .. } => None,
+    }
+}
+
+/**
+ * Decode a chain id felt into its short string, e.g. `0x534e5f4d41494e` into
+ * `SN_MAIN`. Ids that aren't printable ASCII are kept as they are.
+ */
+pub fn decode_chain_id(chain_id: &str) -> String {
+    let decoded = chain_id
+        .strip_prefix("0x")
+        .filter(|digits| digits.len() % 2 == 0)
+        .and_then(|digits| {
+            (0..digits.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+                .collect::<Option<Vec<u8>>>()
+        })
+        .filter(|bytes| !bytes.is_empty() && bytes.iter().all(|b| b.is_ascii_graphic()))
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    decoded.unwrap_or_else(|| chain_id.to_owned())
+}
+
+/**
+ * Why the RPC can't be the chain of `network`, when its chain id says so.
+ * Nothing is known about the local and custom networks unless `expected`
+ * names their chain, see `expected_chain_id`.
+ */
+pub fn chain_mismatch(network: &Network, expected: Option<&str>, chain_id: &str) -> Option<String> {
+    let expected = expected?;
+    (chain_id != expected).then(|| {
+        format!(
+            "The RPC serves chain {} but the verification is submitted to {}, whose chain is {}. The class checks may not be about the class being verified.",
+            chain_id, network, expected
+        )
+    })
+}
+
+/**
+ * Class as returned by `starknet_getClass`, with its ABI parsed like the class
+ * endpoint of the explorer returns it.
+ */
+fn normalize_class(mut class: Value) -> Value {
+    let parsed = class
+        .get("abi")
+        .and_then(Value::as_str)
+        .and_then(|abi| serde_json::from_str::<Value>(abi).ok());
+    if let (Some(abi), Some(fields)) = (parsed, class.as_object_mut()) {
+        fields.insert("abi".to_owned(), abi);
+    }
+    class
+}
+
+/**
+ * Starknet JSON-RPC node, e.g. of a fork or a devnet, queried for the class
+ * checks instead of the explorer.
+ */
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    pub url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+
+    fn unreachable(&self, cause: impl ToString) -> anyhow::Error {
+        VerifierError::RpcUnreachable {
+            url: self.url.clone(),
+            cause: cause.to_string(),
+        }
+        .into()
+    }
+
+    /**
+     * Call `method`, with the JSON-RPC error as `Err` of the result. Failures
+     * to get a JSON-RPC answer at all are `RpcUnreachable` errors.
+     */
+    fn call(&self, method: &str, params: Value) -> Result<std::result::Result<Value, Value>> {
+        let client = ip_version::client_builder().timeout(HTTP_TIMEOUT).build()?;
+        let log = transfer::global();
+        log.record_request(RPC_ENDPOINT, false);
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let response = client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .map_err(|e| self.unreachable(e))?;
+        let status = response.status();
+        let body = log
+            .read_body(RPC_ENDPOINT, response)
+            .map_err(|e| self.unreachable(e))?;
+        if !status.is_success() {
+            return Err(self.unreachable(format!(
+                "{} failed with status {}: {}",
+                method,
+                status.as_u16(),
+                String::from_utf8_lossy(&body)
+            )));
+        }
+        let mut body: Value = serde_json::from_slice(&body)
+            .map_err(|_| self.unreachable(format!("invalid response to {}", method)))?;
+        match (body.get_mut("result"), body.get("error")) {
+            (Some(result), _) => Ok(Ok(result.take())),
+            (None, Some(error)) => Ok(Err(error.clone())),
+            (None, None) => Err(self.unreachable(format!("invalid response to {}", method))),
+        }
+    }
+
+    /**
+     * Chain id served by the node, decoded, see `decode_chain_id`.
+     */
+    pub fn chain_id(&self) -> Result<String> {
+        let chain_id = self
+            .call("starknet_chainId", json!([]))?
+            .map_err(|error| anyhow!("starknet_chainId failed: {}", error))?;
+        chain_id
+            .as_str()
+            .map(decode_chain_id)
+            .ok_or_else(|| anyhow!("Invalid chain id {}", chain_id))
+    }
+
+    /**
+     * The class at the latest block, `None` if the node answers that it isn't
+     * declared.
+     */
+    pub fn class(&self, class_hash: &ClassHash) -> Result<Option<Value>> {
+        let params = json!({"block_id": "latest", "class_hash": class_hash.as_str()});
+        match self.call("starknet_getClass", params)? {
+            Ok(class) => Ok(Some(normalize_class(class))),
+            Err(error)
+                if error.get("code").and_then(Value::as_i64) == Some(CLASS_HASH_NOT_FOUND) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(anyhow!("starknet_getClass failed: {}", error)),
+        }
+    }
+}
+
+/**
+ * Where the class is looked up and compared with the sources before
+ * submitting. Defaults to the explorer of the network the verification is
+ * submitted to, `--rpc-url` checks against another chain, e.g. a fork whose
+ * class hashes match the public network, and `STARKNET_RPC_URL` against the
+ * node of a custom or local network, see `rpc_url_for`.
+ */
+#[derive(Debug, Clone)]
+pub enum ClassSource {
+    Explorer(Network),
+    Rpc(RpcClient),
+}
+
+impl ClassSource {
+    /**
+     * The declared class, `None` if it isn't declared. Fetched once before
+     * submitting, the class checks all read it.
+     */
+    pub fn class(&self, class_hash: &ClassHash, cancel: &CancelToken) -> Result<Option<Value>> {
+        match self {
+            ClassSource::Explorer(network) => Ok(fetch_class(network.clone(), class_hash, cancel)?),
+            ClassSource::Rpc(rpc) => rpc.class(class_hash),
+        }
+    }
+}
+
+/**
+ * `contract_class_version` of the declared class, which picks how the
+ * compiled one is hashed. Only nodes return it, the explorer doesn't.
+ */
+pub fn contract_class_version(class: &Value) -> Option<String> {
+    class
+        .get("contract_class_version")?
+        .as_str()
+        .map(str::to_owned)
+}
+
+/**
+ * Banner shown when the class checks and the submission go to different
+ * chains, so that the split is never overlooked.
+ */
+pub fn describe_split(rpc_url: &str, chain_id: &str, network: &Network) -> String {
+    let chain = match network_chain_id(network) {
+        Some(expected) => format!(" ({})", expected),
+        None => String::new(),
+    };
+    format!(
+        "Split configuration: the class is checked against the RPC at {} (chain {}), the verification is submitted to {}{}.",
+        rpc_url, chain_id, network, chain
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::abi_from_class;
+    use crate::errors::{error_code, verifier_error};
+    use crate::mock_server::{MockResponse, MockServer};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_decode_chain_id() {
+        assert_eq!(decode_chain_id("0x534e5f4d41494e"), "SN_MAIN");
+        assert_eq!(decode_chain_id("0x534e5f5345504f4c4941"), "SN_SEPOLIA");
+        // Not a short string
+        assert_eq!(decode_chain_id("0x1"), "0x1");
+        assert_eq!(decode_chain_id("0x00ff"), "0x00ff");
+    }
+
+    #[test]
+    fn test_chain_mismatch() {
+        let expected = expected_chain_id(&Network::Mainnet, None);
+        assert_eq!(
+            chain_mismatch(&Network::Mainnet, expected.as_deref(), "SN_MAIN"),
+            None
+        );
+        let mismatch =
+            chain_mismatch(&Network::Mainnet, expected.as_deref(), "SN_SEPOLIA").unwrap();
+        assert!(mismatch.contains("SN_SEPOLIA"), "{}", mismatch);
+        assert!(mismatch.contains("SN_MAIN"), "{}", mismatch);
+        // Any chain may back a custom network, unless --chain-id names it
+        let devnet = url::Url::parse("http://localhost:5050").unwrap();
+        let custom = Network::Custom {
+            internal: devnet.clone(),
+            public: devnet,
+        };
+        assert_eq!(expected_chain_id(&custom, None), None);
+        assert_eq!(chain_mismatch(&custom, None, "SN_DEVNET"), None);
+        let expected = expected_chain_id(&custom, Some("SN_DEVNET"));
+        assert_eq!(
+            chain_mismatch(&custom, expected.as_deref(), "SN_DEVNET"),
+            None
+        );
+        assert!(chain_mismatch(&custom, expected.as_deref(), "SN_SEPOLIA").is_some());
+    }
+
+    #[test]
+    fn test_only_custom_networks_default_to_their_rpc() {
+        let flag = Some("http://localhost:5050/rpc");
+        assert_eq!(
+            rpc_url_for(&Network::Mainnet, flag),
+            Some(RpcUrl::Flag("http://localhost:5050/rpc".to_owned()))
+        );
+        // Whatever STARKNET_RPC_URL says, the public networks have an explorer
+        assert_eq!(rpc_url_for(&Network::Sepolia, None), None);
+    }
+
+    #[test]
+    fn test_unreachable_rpc_is_not_a_missing_class() {
+        let server = MockServer::start(vec![MockResponse::new(502, "Bad Gateway")]);
+        let source = ClassSource::Rpc(RpcClient::new(&server.url));
+        let class_hash = ClassHash::from_str("0x1234").unwrap();
+
+        let error = source.class(&class_hash, &CancelToken::new()).unwrap_err();
+        assert!(matches!(
+            verifier_error(&error),
+            Some(VerifierError::RpcUnreachable { .. })
+        ));
+        server.finish();
+
+        let source = ClassSource::Rpc(RpcClient::new("http://127.0.0.1:1"));
+        let error = source.class(&class_hash, &CancelToken::new()).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-059"));
+    }
+
+    #[test]
+    fn test_rpc_class_checks() {
+        let class = json!({
+            "sierra_program": ["0x1", "0x6", "0x0", "0x7"],
+            "abi": "[{\"type\": \"function\", \"name\": \"transfer\"}]",
+        });
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &json!({"jsonrpc": "2.0", "id": 1, "result": "0x534e5f4d41494e"}).to_string()),
+            MockResponse::new(200, &json!({"jsonrpc": "2.0", "id": 1, "result": class}).to_string()),
+            MockResponse::new(
+                200,
+                &json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 28, "message": "Class hash not found"}})
+                    .to_string(),
+            ),
+        ]);
+        let rpc = RpcClient::new(&server.url);
+        let source = ClassSource::Rpc(rpc.clone());
+        let class_hash = ClassHash::from_str("0x1234").unwrap();
+
+        assert_eq!(rpc.chain_id().unwrap(), "SN_MAIN");
+        // The ABI is a string over RPC, parsed like the explorer returns it
+        let class = source
+            .class(&class_hash, &CancelToken::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(abi_from_class(&class).unwrap()[0]["name"], "transfer");
+        assert!(source
+            .class(&class_hash, &CancelToken::new())
+            .unwrap()
+            .is_none());
+
+        let requests = server.finish();
+        let body: Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["method"], "starknet_getClass");
+        assert_eq!(body["params"]["block_id"], "latest");
+    }
+}
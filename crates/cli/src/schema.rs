@@ -0,0 +1,197 @@
+use anyhow::Result;
+use schemars::{schema::RootSchema, schema_for};
+use serde_json::json;
+
+use crate::{
+    json_errors::ErrorEnvelope, outcome::VerificationOutcome, report::ReportEntry,
+    status_file::StatusFile,
+};
+
+/// Bump whenever a field of the report is added, removed, or changes meaning.
+pub const REPORT_SCHEMA_VERSION: u32 = 12;
+/// Bump whenever a field of the status file is added, removed, or changes meaning.
+pub const STATUS_FILE_SCHEMA_VERSION: u32 = 2;
+/// Bump whenever a field of the verification outcome is added, removed, or changes meaning.
+pub const OUTCOME_SCHEMA_VERSION: u32 = 2;
+/// Bump whenever a field of the error envelope is added, removed, or changes meaning.
+pub const ERROR_SCHEMA_VERSION: u32 = 1;
+
+pub const SCHEMA_VERSION_KEY: &str = "x-schema-version";
+
+/**
+ * Files written by the verifier that tooling may consume.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaKind {
+    /// A line of the JSONL report written with `--report`
+    Report,
+    /// The status file written with `--status-file`
+    StatusFile,
+    /// The outcome returned by `verify_file_set`, serialized
+    Outcome,
+    /// The failures printed with `--json-errors`
+    Error,
+}
+
+/**
+ * The schemas are generated from the serialized types themselves, so that
+ * they can't drift from what is actually written.
+ */
+pub fn generate_schema(kind: SchemaKind) -> RootSchema {
+    let (mut schema, version) = match kind {
+        SchemaKind::Report => (schema_for!(ReportEntry), REPORT_SCHEMA_VERSION),
+        SchemaKind::StatusFile => (schema_for!(StatusFile), STATUS_FILE_SCHEMA_VERSION),
+        SchemaKind::Outcome => (schema_for!(VerificationOutcome), OUTCOME_SCHEMA_VERSION),
+        SchemaKind::Error => (schema_for!(ErrorEnvelope), ERROR_SCHEMA_VERSION),
+    };
+    schema
+        .schema
+        .extensions
+        .insert(SCHEMA_VERSION_KEY.to_owned(), json!(version));
+    schema
+}
+
+pub fn schema_json(kind: SchemaKind) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&generate_schema(kind))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const REPORT_FIXTURE: &str = include_str!("../tests/test_data/schema/report.jsonl");
+    const STATUS_FILE_FIXTURE: &str = include_str!("../tests/test_data/schema/status_file.json");
+    const OUTCOME_FIXTURE: &str = include_str!("../tests/test_data/schema/outcome.json");
+    const ERROR_FIXTURE: &str = include_str!("../tests/test_data/schema/error.json");
+
+    fn type_matches(value: &Value, expected: &str) -> bool {
+        match expected {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        }
+    }
+
+    /**
+     * Validates against the subset of JSON Schema that schemars generates.
+     */
+    fn validate(value: &Value, schema: &Value, root: &Value, at: &str) -> Result<(), String> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/definitions/");
+            return validate(value, &root["definitions"][name], root, at);
+        }
+        if let Some(any_of) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any_of
+                .iter()
+                .any(|schema| validate(value, schema, root, at).is_ok())
+            {
+                return Err(format!("{}: matches none of the allowed schemas", at));
+            }
+        }
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            if !types.iter().any(|t| type_matches(value, t)) {
+                return Err(format!("{}: expected {:?}, got {}", at, types, value));
+            }
+        }
+        if let (Some(object), Some(properties)) = (value.as_object(), schema.get("properties")) {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap();
+                if !object.contains_key(required) {
+                    return Err(format!("{}: missing required field {}", at, required));
+                }
+            }
+            for (key, field) in object {
+                match properties.get(key) {
+                    Some(property) => validate(field, property, root, &format!("{}.{}", at, key))?,
+                    None if schema["additionalProperties"] == json!(false) => {
+                        return Err(format!("{}: unknown field {}", at, key))
+                    }
+                    None => (),
+                }
+            }
+        }
+        if let (Some(items), Some(schema)) = (value.as_array(), schema.get("items")) {
+            for (i, item) in items.iter().enumerate() {
+                validate(item, schema, root, &format!("{}[{}]", at, i))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_fixture(kind: SchemaKind, fixture: &Value) -> Result<(), String> {
+        let schema = serde_json::to_value(generate_schema(kind)).unwrap();
+        validate(fixture, &schema, &schema, "$")
+    }
+
+    #[test]
+    fn test_schemas_are_versioned() {
+        let versioned = [
+            (SchemaKind::Report, REPORT_SCHEMA_VERSION),
+            (SchemaKind::StatusFile, STATUS_FILE_SCHEMA_VERSION),
+            (SchemaKind::Outcome, OUTCOME_SCHEMA_VERSION),
+            (SchemaKind::Error, ERROR_SCHEMA_VERSION),
+        ];
+        for (kind, version) in versioned {
+            let schema: Value = serde_json::from_str(&schema_json(kind).unwrap()).unwrap();
+            assert_eq!(schema[SCHEMA_VERSION_KEY], json!(version));
+        }
+    }
+
+    #[test]
+    fn test_report_fixture_matches_schema() {
+        for line in REPORT_FIXTURE.lines() {
+            let entry: Value = serde_json::from_str(line).unwrap();
+            validate_fixture(SchemaKind::Report, &entry).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_status_file_fixture_matches_schema() {
+        let status: Value = serde_json::from_str(STATUS_FILE_FIXTURE).unwrap();
+        validate_fixture(SchemaKind::StatusFile, &status).unwrap();
+
+        let mut invalid = status.clone();
+        invalid.as_object_mut().unwrap().remove("job_id");
+        assert!(validate_fixture(SchemaKind::StatusFile, &invalid).is_err());
+        invalid["job_id"] = json!(42);
+        assert!(validate_fixture(SchemaKind::StatusFile, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_outcome_fixture_matches_schema() {
+        let outcome: Value = serde_json::from_str(OUTCOME_FIXTURE).unwrap();
+        validate_fixture(SchemaKind::Outcome, &outcome).unwrap();
+
+        let failed = VerificationOutcome::default()
+            .with_result(Err(anyhow::anyhow!("Failed to dispatch verification job")));
+        validate_fixture(SchemaKind::Outcome, &serde_json::to_value(&failed).unwrap()).unwrap();
+
+        let mut invalid = outcome.clone();
+        invalid["duration"]["remote_compile_ms"] = json!("30s");
+        assert!(validate_fixture(SchemaKind::Outcome, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_error_fixture_matches_schema() {
+        let error: Value = serde_json::from_str(ERROR_FIXTURE).unwrap();
+        validate_fixture(SchemaKind::Error, &error).unwrap();
+
+        let uncoded = ErrorEnvelope::from_error(&anyhow::anyhow!("Unknown error code VER-999"), 1);
+        validate_fixture(SchemaKind::Error, &serde_json::to_value(&uncoded).unwrap()).unwrap();
+
+        let mut invalid = error.clone();
+        invalid["exit_code"] = json!("2");
+        assert!(validate_fixture(SchemaKind::Error, &invalid).is_err());
+    }
+}
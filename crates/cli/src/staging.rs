@@ -0,0 +1,305 @@
+use std::{
+    env, fs,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::payload_cache::dir_size;
+use crate::project_state::{write_atomic, StateLock, LOCK_TIMEOUT};
+
+/// File listing the staging directories created and not removed yet.
+const LEDGER_FILE: &str = "ledger.json";
+
+/// Inside of a staging directory, next to its lock.
+const CONTENTS_DIR: &str = "contents";
+
+/// Leftover staging directories older than this are removed on startup.
+pub const DEFAULT_STAGING_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+static STAGING_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Age after which leftover staging directories are removed, configurable in
+ * hours through `VOYAGER_STAGING_MAX_AGE_HOURS`.
+ */
+pub fn staging_max_age() -> Duration {
+    env::var("VOYAGER_STAGING_MAX_AGE_HOURS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|hours| Duration::from_secs(hours * 60 * 60))
+        .unwrap_or(DEFAULT_STAGING_MAX_AGE)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LedgerEntry {
+    path: Utf8PathBuf,
+    /// Unix time, in seconds.
+    created_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Ledger {
+    entries: Vec<LedgerEntry>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Sweep {
+    pub removed: usize,
+    pub freed: u64,
+    /// Staging directories still held by a running instance.
+    pub in_use: usize,
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+/**
+ * Creates the temporary directories the verifier stages files in, and keeps
+ * track of them in a ledger under the cache directory. A crash skips the
+ * cleanup of the directories, which are then swept by a later run.
+ *
+ * Each staging directory is locked by the instance using it, see `StateLock`.
+ * The lock is released by the OS when the instance dies, so that sweeping
+ * never removes a directory that is still in use.
+ */
+#[derive(Debug, Clone)]
+pub struct Staging {
+    ledger_dir: Utf8PathBuf,
+    temp_root: Utf8PathBuf,
+}
+
+impl Staging {
+    pub fn new(ledger_dir: Utf8PathBuf, temp_root: Utf8PathBuf) -> Self {
+        Self {
+            ledger_dir,
+            temp_root,
+        }
+    }
+
+    /**
+     * Staging in the system temp directory, with the ledger in `ledger_dir`.
+     */
+    pub fn system(ledger_dir: Utf8PathBuf) -> Result<Self> {
+        let temp_root = Utf8PathBuf::from_path_buf(env::temp_dir())
+            .map_err(|p| anyhow!("Non UTF-8 temp directory {}", p.display()))?;
+        Ok(Self::new(ledger_dir, temp_root))
+    }
+
+    fn update_ledger<T>(&self, update: impl FnOnce(&mut Ledger) -> T) -> Result<T> {
+        let _lock = StateLock::acquire(&self.ledger_dir, LOCK_TIMEOUT)?;
+        let path = self.ledger_dir.join(LEDGER_FILE);
+        // A corrupted ledger only loses track of leftovers, it is started over
+        let mut ledger: Ledger = fs::read(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        let result = update(&mut ledger);
+        write_atomic(&path, &serde_json::to_vec(&ledger)?)?;
+        Ok(result)
+    }
+
+    /**
+     * Create a staging directory, named after `kind`. It is registered and
+     * locked at once, so that neither a crash nor a concurrent sweep can leave
+     * an unknown directory behind.
+     */
+    pub fn create(&self, kind: &str) -> Result<StagingDir> {
+        let root = self.temp_root.join(format!(
+            "voyager-{}-{}-{}",
+            kind,
+            std::process::id(),
+            STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let entry = LedgerEntry {
+            path: root.clone(),
+            created_at: unix_time(SystemTime::now()),
+        };
+        // Held until the directory is removed
+        let lock = self.update_ledger(|ledger| {
+            let lock = StateLock::acquire(&root, LOCK_TIMEOUT)?;
+            ledger.entries.push(entry);
+            anyhow::Ok(lock)
+        })??;
+        let contents = root.join(CONTENTS_DIR);
+        fs::create_dir_all(&contents)
+            .with_context(|| format!("Unable to create the staging directory {}", contents))?;
+        Ok(StagingDir {
+            root,
+            contents,
+            staging: self.clone(),
+            lock: Some(lock),
+        })
+    }
+
+    /**
+     * Remove the leftover staging directories created more than `max_age`
+     * ago, or all of them when `None`. Directories still in use are kept.
+     */
+    pub fn sweep(&self, max_age: Option<Duration>, now: SystemTime) -> Result<Sweep> {
+        let now = unix_time(now);
+        self.update_ledger(|ledger| {
+            let mut sweep = Sweep::default();
+            ledger.entries.retain(|entry| {
+                if !entry.path.exists() {
+                    return false;
+                }
+                let age = Duration::from_secs(now.saturating_sub(entry.created_at));
+                if max_age.is_some_and(|max_age| age < max_age) {
+                    return true;
+                }
+                match StateLock::acquire(&entry.path, Duration::ZERO) {
+                    // Nobody else stages there, it can be unlocked before removing it
+                    Ok(lock) => drop(lock),
+                    Err(_) => {
+                        sweep.in_use += 1;
+                        return true;
+                    }
+                }
+                let size = dir_size(&entry.path);
+                if fs::remove_dir_all(&entry.path).is_err() {
+                    return true;
+                }
+                sweep.removed += 1;
+                sweep.freed += size;
+                false
+            });
+            sweep
+        })
+    }
+}
+
+/**
+ * A staging directory, removed along with its ledger entry when dropped,
+ * which also covers early returns and panics.
+ */
+#[derive(Debug)]
+pub struct StagingDir {
+    root: Utf8PathBuf,
+    contents: Utf8PathBuf,
+    staging: Staging,
+    /// Taken before removing the directory, open files can't be removed everywhere.
+    lock: Option<StateLock>,
+}
+
+impl StagingDir {
+    /**
+     * Where to stage files. The directory itself only holds them and its lock.
+     */
+    pub fn path(&self) -> &Utf8Path {
+        &self.contents
+    }
+
+    /**
+     * Give up the directory as a crashing instance would: unlocked, on disk,
+     * and still in the ledger.
+     */
+    #[cfg(test)]
+    pub fn abandon(mut self) {
+        self.lock.take();
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        self.lock.take();
+        let _ = fs::remove_dir_all(&self.root);
+        let _ = self
+            .staging
+            .update_ledger(|ledger| ledger.entries.retain(|entry| entry.path != self.root));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staging(name: &str) -> (Staging, Utf8PathBuf) {
+        let dir = Utf8PathBuf::from_path_buf(env::temp_dir().join(format!(
+            "voyager-staging-{}-{}",
+            name,
+            std::process::id()
+        )))
+        .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        (
+            Staging::new(dir.join("ledger"), dir.join("tmp")),
+            dir.clone(),
+        )
+    }
+
+    fn ledger_paths(staging: &Staging) -> Vec<Utf8PathBuf> {
+        staging
+            .update_ledger(|ledger| {
+                ledger
+                    .entries
+                    .iter()
+                    .map(|entry| entry.path.clone())
+                    .collect()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_staging_dir_is_removed_when_dropped() {
+        let (staging, dir) = staging("drop");
+        let staged = staging.create("git-ref").unwrap();
+        fs::write(staged.path().join("Scarb.toml"), "[package]").unwrap();
+        let path = staged.path().to_owned();
+        assert_eq!(ledger_paths(&staging).len(), 1);
+
+        drop(staged);
+        assert!(!path.exists());
+        assert!(ledger_paths(&staging).is_empty());
+
+        // Unwinding drops it too
+        let panicked = std::panic::catch_unwind(|| {
+            let staged = staging.create("git-ref").unwrap();
+            fs::write(staged.path().join("lib.cairo"), "mod token;").unwrap();
+            panic!("mid-operation");
+        });
+        assert!(panicked.is_err());
+        assert!(ledger_paths(&staging).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sweep_recovers_the_space_of_a_crashed_run() {
+        let (staging, dir) = staging("crash");
+        let crashed = staging.create("git-ref").unwrap();
+        fs::write(crashed.path().join("lib.cairo"), vec![b'a'; 4096]).unwrap();
+        let crashed_path = crashed.path().to_owned();
+        crashed.abandon();
+        let running = staging.create("git-ref").unwrap();
+        let now = SystemTime::now();
+
+        // Too recent for the startup sweep
+        assert_eq!(
+            staging.sweep(Some(DEFAULT_STAGING_MAX_AGE), now).unwrap(),
+            Sweep::default()
+        );
+        assert!(crashed_path.exists());
+
+        let later = now + DEFAULT_STAGING_MAX_AGE * 2;
+        let sweep = staging.sweep(Some(DEFAULT_STAGING_MAX_AGE), later).unwrap();
+        assert_eq!(sweep.removed, 1);
+        assert!(sweep.freed >= 4096);
+        assert_eq!(sweep.in_use, 1);
+        assert!(!crashed_path.exists());
+        assert!(running.path().exists());
+        assert_eq!(ledger_paths(&staging).len(), 1);
+
+        // A forced sweep still keeps the directory in use
+        assert_eq!(staging.sweep(None, now).unwrap().in_use, 1);
+        drop(running);
+        assert!(ledger_paths(&staging).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
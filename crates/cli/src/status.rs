@@ -0,0 +1,200 @@
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use crate::api::{get_job_status, poll_verification_status, Network, PollUpdate, VerificationJob};
+use crate::args::{ApiKeyArgs, HttpArgs, NetworkArgs, PollingArgs};
+use crate::class_hash::ClassHash;
+use crate::client::ApiClient;
+use crate::display::sanitize;
+use crate::interrupt;
+use crate::polling::PollingConfig;
+use crate::status_file::StatusFile;
+use crate::verify;
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    #[arg(
+        required_unless_present = "from_file",
+        conflicts_with = "from_file",
+        help = "Id of a verification job on --network, checked once unless --wait is given"
+    )]
+    pub job_id: Option<String>,
+
+    #[arg(
+        long,
+        help = "Read the job state from a status file instead of the network"
+    )]
+    pub from_file: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        requires = "from_file",
+        help = "Resume polling the job recorded in the status file until it finishes"
+    )]
+    pub resume: bool,
+
+    #[arg(
+        long,
+        requires = "job_id",
+        help = "Poll the job until it finishes, see --poll-interval and --poll-timeout"
+    )]
+    pub wait: bool,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+
+    #[command(flatten)]
+    pub api_key: ApiKeyArgs,
+
+    #[command(flatten)]
+    pub http: HttpArgs,
+
+    #[command(flatten)]
+    pub polling: PollingArgs,
+}
+
+fn print_job(job: &VerificationJob) {
+    println!("Job id: {}", sanitize(job.job_id()));
+    println!("Status: {}", job.status());
+    println!("Class hash: {}", sanitize(job.class_hash()));
+    if let Some(contract_file) = job.contract_file() {
+        println!("Contract file: {}", sanitize(contract_file));
+    }
+    if let Some(description) = job.status_description() {
+        println!("Description: {}", sanitize(description));
+    }
+    if let Some(created) = job.created_timestamp() {
+        println!("Created at (unix): {}", created as u64);
+    }
+    if let Some(updated) = job.updated_timestamp() {
+        println!("Updated at (unix): {}", updated as u64);
+    }
+}
+
+/**
+ * Check a job by its id, once or until it finishes with `wait`. Failed jobs
+ * are errors, and so are the ones still in progress, see `exit_code`.
+ */
+fn run_job_status(
+    job_id: &str,
+    network: Network,
+    api_key: &str,
+    wait: bool,
+    polling: &PollingConfig,
+    json: bool,
+) -> anyhow::Result<()> {
+    let print = |job: &VerificationJob| -> anyhow::Result<()> {
+        if json {
+            println!("{}", serde_json::to_string(job)?);
+        } else {
+            print_job(job);
+        }
+        Ok(())
+    };
+    if !wait {
+        let job = get_job_status(network, api_key, job_id, interrupt::token())?;
+        print(&job)?;
+        job.into_result()?;
+        return Ok(());
+    }
+
+    let mut last: Option<VerificationJob> = None;
+    let result = poll_verification_status(
+        api_key,
+        network,
+        job_id,
+        polling,
+        interrupt::token(),
+        &mut |update| match update {
+            PollUpdate::Job(job) => {
+                if !json && last.as_ref().map(VerificationJob::status) != Some(job.status()) {
+                    println!("{}", job.status());
+                }
+                last = Some(job.clone());
+            }
+            PollUpdate::UnknownStatus(status) => {
+                eprintln!("Received unknown job status {}, still polling.", status)
+            }
+            PollUpdate::Restarted(status) => eprintln!(
+                "Job restarted by the server ({}), compilation will rerun.",
+                status
+            ),
+        },
+    );
+    if let Some(job) = &last {
+        print(job)?;
+    }
+    result?;
+    Ok(())
+}
+
+/**
+ * Show a job, checked on the network by its id or read from a status file,
+ * whose polling can be resumed.
+ */
+pub fn run_status(args: StatusArgs, json: bool) -> anyhow::Result<()> {
+    let api_key = args.api_key.resolve();
+    let polling = args.polling.config();
+    let from_file = match (args.from_file, args.job_id) {
+        (Some(from_file), _) => from_file,
+        (None, Some(job_id)) => {
+            return run_job_status(
+                &job_id,
+                args.network.select()?,
+                &api_key,
+                args.wait,
+                &polling,
+                json,
+            )
+        }
+        // clap requires one of them
+        (None, None) => unreachable!(),
+    };
+    let status = StatusFile::read(&from_file)?;
+
+    // Resuming a finished job is a no-op so that waiters can be safely re-invoked, e.g. from cron.
+    if args.resume && !status.finished {
+        let client = ApiClient::new(
+            api_key,
+            args.network.custom().select(&status.network)?,
+            interrupt::token().clone(),
+        )?;
+        let job_id = status.job_id.clone();
+        let class_hash: ClassHash = status.class_hash.parse()?;
+        return match verify::poll_with_status_file(
+            &client,
+            &job_id,
+            &class_hash,
+            &polling,
+            Some((from_file.as_path(), status)),
+            &mut |_| {},
+        ) {
+            Err(e) if verify::is_inferred_success(&e) => {
+                println!("{}", e.root_cause());
+                Ok(())
+            }
+            result => result,
+        };
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+        return Ok(());
+    }
+    println!("Job id: {}", status.job_id);
+    println!("Network: {}", status.network);
+    println!("Class hash: {}", status.class_hash);
+    println!("Status: {}", status.status);
+    if let Some(description) = &status.status_description {
+        println!("Description: {}", description);
+    }
+    if let Some(error) = &status.error {
+        println!("Error: {}", error);
+    }
+    if !status.metadata.is_empty() {
+        println!("Submitted: {}", status.metadata);
+    }
+    println!("Finished: {}", status.finished);
+    println!("Updated at (unix): {}", status.updated_at);
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{JobMetadata, Network, VerificationJob, VerifyJobStatus, API_KEY_VAR};
+use crate::ip_version::{ip_version, IpVersion};
+use crate::polling::PollingConfig;
+use crate::project_state::write_atomic;
+use crate::report::Submission;
+
+/**
+ * Progress of a verification job, persisted on disk so that it can be inspected
+ * with `status --from-file` without hitting the network, and so that polling can
+ * be resumed from it if the waiter dies.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct StatusFile {
+    pub job_id: String,
+    pub network: String,
+    pub class_hash: String,
+    pub status: String,
+    pub status_description: Option<String>,
+    pub finished: bool,
+    pub error: Option<String>,
+    pub updated_at: u64,
+    /// Metadata echoed by the backend, absent from status files of older versions.
+    #[serde(flatten, default)]
+    pub metadata: JobMetadata,
+    #[serde(default)]
+    pub submission: Submission,
+}
+
+impl StatusFile {
+    pub fn dispatched(job_id: &str, network: &Network, class_hash: &str) -> Self {
+        Self {
+            job_id: job_id.to_owned(),
+            network: network.to_string(),
+            class_hash: class_hash.to_owned(),
+            status: VerifyJobStatus::Submitted.to_string(),
+            status_description: None,
+            finished: false,
+            error: None,
+            updated_at: unix_now(),
+            metadata: JobMetadata::default(),
+            submission: Submission::Dispatched,
+        }
+    }
+
+    pub fn update_from_job(&mut self, job: &VerificationJob) {
+        let status = job.status();
+        self.finished = matches!(
+            status,
+            VerifyJobStatus::Success | VerifyJobStatus::Fail | VerifyJobStatus::CompileFailed
+        );
+        self.status = status.to_string();
+        self.status_description = job.status_description().map(str::to_owned);
+        let metadata = job.metadata();
+        if !metadata.is_empty() {
+            self.metadata = metadata;
+        }
+        self.updated_at = unix_now();
+    }
+
+    /**
+     * The job finished successfully according to another source than the job
+     * itself, described by `description`.
+     */
+    pub fn infer_success(&mut self, description: &str) {
+        self.finished = true;
+        self.status = VerifyJobStatus::Success.to_string();
+        self.status_description = Some(description.to_owned());
+        self.updated_at = unix_now();
+    }
+
+    pub fn fail(&mut self, error: String) {
+        self.finished = true;
+        self.error = Some(error);
+        self.updated_at = unix_now();
+    }
+
+    pub fn read(path: &Utf8Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read status file {}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Status file {} is not a valid status file", path))
+    }
+
+    /**
+     * Atomically replace the status file, so that readers never observe a
+     * partially written file.
+     */
+    pub fn write(&self, path: &Utf8Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Unable to write status file {}", path))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/**
+ * Start a detached waiter that resumes polling from the status file, so that the
+ * current process can exit right after dispatching. The waiter polls as often
+ * and, from its start, for as long as `polling` says. The API key is passed in
+ * its environment rather than its arguments, which other users can list.
+ */
+pub fn spawn_waiter(
+    path: &Utf8Path,
+    network: &Network,
+    polling: &PollingConfig,
+    api_key: &str,
+) -> Result<u32> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(["status", "--from-file", path.as_str(), "--resume"])
+        .args(["--poll-interval", &polling.interval.as_secs().to_string()]);
+    if let Some(timeout) = polling.timeout {
+        command.args(["--poll-timeout", &timeout.as_secs().to_string()]);
+    }
+    // The status file only names the network, its endpoints may come from flags
+    if let Network::Custom { internal, public } = network {
+        command.args([
+            "--api-url",
+            internal.as_str(),
+            "--public-api-url",
+            public.as_str(),
+        ]);
+    }
+    if ip_version() != IpVersion::Auto {
+        command.args(["--ip-version", &ip_version().to_string()]);
+    }
+    if !api_key.is_empty() {
+        command.env(API_KEY_VAR, api_key);
+    }
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Unable to start the background waiter")?;
+    Ok(child.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+
+    #[test]
+    fn test_status_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("voyager-status-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.join("status.json")).unwrap();
+
+        let mut status = StatusFile::dispatched("job-1", &Network::Sepolia, "0x123");
+        status.write(&path).unwrap();
+        assert_eq!(StatusFile::read(&path).unwrap(), status);
+
+        status.fail("Job not found".to_owned());
+        status.write(&path).unwrap();
+        let read_back = StatusFile::read(&path).unwrap();
+        assert!(read_back.finished);
+        assert_eq!(read_back.network, "sepolia");
+        assert_eq!(read_back.error.as_deref(), Some("Job not found"));
+
+        // No temporary file should be left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
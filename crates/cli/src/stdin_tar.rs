@@ -0,0 +1,220 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::Component,
+};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use flate2::read::GzDecoder;
+
+use crate::errors::VerifierError;
+use crate::staging::{Staging, StagingDir};
+
+/// First bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/**
+ * Sources read from a tar stream, e.g. piped from a hermetic build. The
+ * extracted tree is removed when this is dropped, which also covers early
+ * returns and panics.
+ */
+#[derive(Debug)]
+pub struct TarCheckout {
+    pub project_path: Utf8PathBuf,
+    /// Symbolic and hard links of the stream, which are never extracted.
+    pub skipped_links: Vec<String>,
+    /// Removes the extracted tree when dropped.
+    _staging_dir: StagingDir,
+}
+
+/**
+ * Extract an uncompressed or gzip tar stream into a temporary directory and
+ * locate the project at `project_dir` inside of it. Entries with an absolute
+ * path or going up with `..` fail the whole extraction. Links are skipped,
+ * like links in a project directory are never collected.
+ */
+pub fn extract_tar(
+    reader: impl Read,
+    project_dir: &Utf8Path,
+    staging: &Staging,
+) -> Result<TarCheckout> {
+    if project_dir
+        .components()
+        .any(|component| !matches!(component, Utf8Component::Normal(_) | Utf8Component::CurDir))
+    {
+        return Err(anyhow!(
+            "--project-dir must be relative to the root of the tar stream, got {}",
+            project_dir
+        ));
+    }
+
+    let mut reader = BufReader::new(reader);
+    let gzipped = reader
+        .fill_buf()
+        .context("Unable to read the tar stream")?
+        .starts_with(&GZIP_MAGIC);
+    let stream: Box<dyn Read + '_> = if gzipped {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+
+    // Created before extracting so that the staging directory is cleaned up on failure too.
+    let staging_dir = staging.create("stdin-tar")?;
+    let root = staging_dir.path().to_owned();
+    let mut checkout = TarCheckout {
+        project_path: root.join(project_dir),
+        skipped_links: vec![],
+        _staging_dir: staging_dir,
+    };
+
+    let mut archive = tar::Archive::new(stream);
+    for entry in archive.entries().context("Unable to read the tar stream")? {
+        let mut entry = entry.context("Unable to read the tar stream")?;
+        let path = entry
+            .path()
+            .context("Unable to read the tar stream")?
+            .into_owned();
+        if path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(VerifierError::UnsafeArchiveEntry(path.display().to_string()).into());
+        }
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            checkout.skipped_links.push(path.display().to_string());
+            continue;
+        }
+        entry
+            .unpack_in(&root)
+            .with_context(|| format!("Unable to extract {}", path.display()))?;
+    }
+
+    if !checkout.project_path.join("Scarb.toml").exists() {
+        return Err(anyhow!(
+            "No Scarb.toml found at {} in the tar stream",
+            project_dir
+        ));
+    }
+
+    Ok(checkout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{collection_root, path_dependencies, source_files, CollectionFilter};
+    use crate::errors::error_code;
+    use flate2::{write::GzEncoder, Compression};
+    use std::{fs, io::Write};
+
+    fn monorepo() -> Utf8PathBuf {
+        Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_data/collection/monorepo")
+    }
+
+    fn staging(name: &str) -> (Utf8PathBuf, Staging) {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!(
+            "voyager-stdin-tar-{}-{}",
+            name,
+            std::process::id()
+        )))
+        .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        let staging = Staging::new(dir.join("ledger"), dir.join("tmp"));
+        (dir, staging)
+    }
+
+    /// The files a verification of `package` would upload, relative to the collection root.
+    fn submitted_files(package: &Utf8Path) -> Vec<(Utf8PathBuf, String)> {
+        let root = collection_root(package, &path_dependencies(package).unwrap(), None).unwrap();
+        let mut files: Vec<_> = source_files(&root, &CollectionFilter::default())
+            .into_iter()
+            .map(|file| {
+                let contents = fs::read_to_string(&file).unwrap();
+                (file.strip_prefix(&root).unwrap().to_owned(), contents)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn entry(builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &[u8]) {
+        // Written by hand, set_path refuses the unsafe paths under test
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, contents).unwrap();
+    }
+
+    #[test]
+    fn test_tar_stream_submits_the_same_files_as_the_directory() {
+        let mut builder = tar::Builder::new(vec![]);
+        builder.append_dir_all(".", monorepo()).unwrap();
+        let archive = builder.into_inner().unwrap();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&archive).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let (dir, staging) = staging("round-trip");
+        let project_dir = Utf8Path::new("chain/contracts");
+        let expected = submitted_files(&monorepo().join(project_dir));
+        assert_eq!(expected.len(), 4);
+        for stream in [archive, gzipped] {
+            let checkout = extract_tar(stream.as_slice(), project_dir, &staging).unwrap();
+            assert!(checkout.skipped_links.is_empty());
+            assert_eq!(submitted_files(&checkout.project_path), expected);
+
+            let extracted = checkout.project_path.clone();
+            drop(checkout);
+            assert!(!extracted.exists());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_entries_outside_of_the_root_are_rejected() {
+        let (dir, staging) = staging("traversal");
+        for path in [
+            "../escaped.cairo",
+            "src/../../escaped.cairo",
+            "/tmp/escaped.cairo",
+        ] {
+            let mut builder = tar::Builder::new(vec![]);
+            entry(&mut builder, "Scarb.toml", b"[package]\nname = \"test\"\n");
+            entry(&mut builder, path, b"fn escaped() {}");
+            let archive = builder.into_inner().unwrap();
+
+            let error = extract_tar(archive.as_slice(), Utf8Path::new("."), &staging).unwrap_err();
+            assert_eq!(error_code(&error), Some("VER-043"), "{}", path);
+        }
+        // Nothing is left behind
+        assert_eq!(fs::read_dir(dir.join("tmp")).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_links_are_skipped() {
+        let mut builder = tar::Builder::new(vec![]);
+        entry(&mut builder, "Scarb.toml", b"[package]\nname = \"test\"\n");
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "src/lib.cairo", "/etc/passwd")
+            .unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let (dir, staging) = staging("links");
+        let checkout = extract_tar(archive.as_slice(), Utf8Path::new("."), &staging).unwrap();
+        assert_eq!(checkout.skipped_links, vec!["src/lib.cairo"]);
+        assert!(!checkout.project_path.join("src/lib.cairo").exists());
+        drop(checkout);
+
+        assert!(extract_tar(archive.as_slice(), Utf8Path::new("../other"), &staging).is_err());
+        assert!(extract_tar(archive.as_slice(), Utf8Path::new("missing"), &staging).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use clap::Args;
+use comfy_table::Table;
+use dyn_compiler::dyn_compiler::{
+    supported_cairo_versions, supported_scarb_versions, ResolverInfo,
+};
+use serde::Serialize;
+
+use crate::api::BackendCapabilities;
+use crate::args::{ApiKeyArgs, HttpArgs, NetworkArgs};
+use crate::backend_capabilities;
+use crate::resolver::registered_resolvers;
+
+#[derive(Args, Debug)]
+pub struct SupportedVersionsArgs {
+    #[arg(long, help = "Print the resolvers and versions as JSON")]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+
+    #[command(flatten)]
+    pub api_key: ApiKeyArgs,
+
+    #[command(flatten)]
+    pub http: HttpArgs,
+}
+
+/**
+ * A resolver built into the client, see `registered_resolvers`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolverSupport {
+    pub name: String,
+    pub features: Vec<String>,
+    pub scarb_versions: Vec<String>,
+    pub cairo_versions: Vec<String>,
+}
+
+/**
+ * Whether the client can resolve projects for a tool version, and whether the
+ * backend accepts it. `server_accepts` is `None` when the backend doesn't say.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionSupport {
+    pub tool: String,
+    pub version: String,
+    pub client_supports: bool,
+    pub server_accepts: Option<bool>,
+}
+
+/**
+ * The compiler versions the client supports and the backend of `network`
+ * accepts, which are told apart: a version only one of them handles can't be
+ * verified.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SupportedVersions {
+    pub network: String,
+    pub resolvers: Vec<ResolverSupport>,
+    /// `None` when the backend doesn't list the versions it accepts.
+    pub server: Option<BackendCapabilities>,
+    pub versions: Vec<VersionSupport>,
+}
+
+impl SupportedVersions {
+    pub fn new(
+        network: &str,
+        resolvers: &[ResolverInfo],
+        server: Option<BackendCapabilities>,
+    ) -> Self {
+        let scarb: Vec<String> = supported_scarb_versions(resolvers)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let cairo: Vec<String> = supported_cairo_versions(resolvers)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let mut versions = vec![];
+        let tools = [
+            ("scarb", scarb, server.as_ref().map(|s| &s.scarb_versions)),
+            ("cairo", cairo, server.as_ref().map(|s| &s.cairo_versions)),
+        ];
+        for (tool, client, accepted) in tools {
+            let mut all = client.clone();
+            for version in accepted.into_iter().flatten() {
+                if !all.contains(version) {
+                    all.push(version.clone());
+                }
+            }
+            for version in all {
+                versions.push(VersionSupport {
+                    tool: tool.to_owned(),
+                    client_supports: client.contains(&version),
+                    server_accepts: accepted.map(|accepted| accepted.contains(&version)),
+                    version,
+                });
+            }
+        }
+
+        Self {
+            network: network.to_owned(),
+            resolvers: resolvers
+                .iter()
+                .map(|resolver| ResolverSupport {
+                    name: resolver.name.to_owned(),
+                    features: resolver.features.iter().map(|f| f.to_string()).collect(),
+                    scarb_versions: resolver
+                        .scarb_versions
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    cairo_versions: resolver
+                        .cairo_versions
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                })
+                .collect(),
+            server,
+            versions,
+        }
+    }
+
+    pub fn format(&self) -> String {
+        let mut lines = vec!["Resolvers of this client:".to_owned()];
+        for resolver in &self.resolvers {
+            let features = if resolver.features.is_empty() {
+                "none".to_owned()
+            } else {
+                resolver.features.join(", ")
+            };
+            lines.push(format!(
+                "  {} (features: {}): Scarb {}, Cairo {}",
+                resolver.name,
+                features,
+                resolver.scarb_versions.join(", "),
+                resolver.cairo_versions.join(", ")
+            ));
+        }
+        if self.server.is_none() {
+            lines.push(format!(
+                "The {} backend doesn't list the versions it accepts, or couldn't be reached.",
+                self.network
+            ));
+        }
+
+        let mut table = Table::new();
+        table.set_header(vec![
+            "Tool".to_owned(),
+            "Version".to_owned(),
+            "Client supports".to_owned(),
+            format!("{} accepts", self.network),
+        ]);
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        for version in &self.versions {
+            table.add_row(vec![
+                version.tool.clone(),
+                version.version.clone(),
+                yes_no(version.client_supports).to_owned(),
+                version.server_accepts.map_or("unknown", yes_no).to_owned(),
+            ]);
+        }
+        lines.push(table.to_string());
+        lines.join("\n")
+    }
+}
+
+pub fn run_supported_versions(args: SupportedVersionsArgs) -> anyhow::Result<()> {
+    let network = args.network.select()?;
+    let supported = SupportedVersions::new(
+        &network.to_string(),
+        &registered_resolvers(),
+        backend_capabilities(&network, &args.api_key.resolve())?,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&supported)?);
+    } else {
+        println!("{}", supported.format());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+
+    const RESOLVER: ResolverInfo = ResolverInfo {
+        name: "voyager-resolver-cairo",
+        scarb_versions: &[SupportedScarbVersions::V2_8_4],
+        cairo_versions: &[SupportedCairoVersions::V2_8_4],
+        features: &[],
+    };
+
+    #[test]
+    fn test_client_support_and_server_acceptance_are_told_apart() {
+        let server = BackendCapabilities {
+            scarb_versions: vec![],
+            cairo_versions: vec!["2.8.4".to_owned(), "2.9.1".to_owned()],
+            dependency_fingerprints: false,
+            metadata_corrections: false,
+            form_dialect: None,
+        };
+        let supported = SupportedVersions::new("sepolia", &[RESOLVER], Some(server));
+        let support = |tool: &str, version: &str| {
+            supported
+                .versions
+                .iter()
+                .find(|support| support.tool == tool && support.version == version)
+                .map(|support| (support.client_supports, support.server_accepts))
+        };
+        assert_eq!(support("scarb", "2.8.4"), Some((true, Some(false))));
+        assert_eq!(support("cairo", "2.8.4"), Some((true, Some(true))));
+        assert_eq!(support("cairo", "2.9.1"), Some((false, Some(true))));
+        assert_eq!(supported.versions.len(), 3);
+
+        let json = serde_json::to_value(&supported).unwrap();
+        assert_eq!(json["resolvers"][0]["name"], "voyager-resolver-cairo");
+        assert_eq!(json["resolvers"][0]["features"], serde_json::json!([]));
+        assert_eq!(json["versions"][0]["server_accepts"], false);
+    }
+
+    #[test]
+    fn test_unknown_server_acceptance() {
+        let supported = SupportedVersions::new("custom", &[RESOLVER], None);
+        assert!(supported
+            .versions
+            .iter()
+            .all(|support| support.client_supports && support.server_accepts.is_none()));
+
+        let text = supported.format();
+        assert!(text.contains("voyager-resolver-cairo (features: none): Scarb 2.8.4, Cairo 2.8.4"));
+        assert!(text.contains("The custom backend doesn't list the versions it accepts"));
+        assert!(text.contains("unknown"));
+        assert_eq!(
+            serde_json::to_value(&supported).unwrap()["server"],
+            serde_json::Value::Null
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use dyn_compiler::dyn_compiler::{CairoVersion, SyntaxMarker, SYNTAX_MARKERS};
+
+use crate::sources::SourceSet;
+
+/**
+ * A construct found in the sources that the selected compiler can't parse.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxFinding {
+    pub file: String,
+    pub line: usize,
+    pub marker: &'static SyntaxMarker,
+}
+
+pub(crate) fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/**
+ * Whether `token` appears in `line` on its own, not as the end of a longer
+ * identifier, e.g. `for ` in `wait_for `.
+ */
+fn contains_token(line: &str, token: &str) -> bool {
+    let starts_with_identifier = token.starts_with(is_identifier_char);
+    line.match_indices(token).any(|(index, _)| {
+        !starts_with_identifier
+            || !line[..index]
+                .chars()
+                .next_back()
+                .is_some_and(is_identifier_char)
+    })
+}
+
+/**
+ * The lines of `source` without their line comments, with their number
+ * counted from 1.
+ */
+pub(crate) fn code_lines(source: &str) -> impl Iterator<Item = (usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.split("//").next().unwrap_or_default()))
+}
+
+/**
+ * Find the markers of features introduced after `compiler`, ignoring line
+ * comments and the markers listed in `allowed`.
+ */
+pub fn scan_source(
+    file: &str,
+    source: &str,
+    compiler: CairoVersion,
+    allowed: &[String],
+) -> Vec<SyntaxFinding> {
+    let markers: Vec<&'static SyntaxMarker> = SYNTAX_MARKERS
+        .iter()
+        .filter(|marker| marker.introduced_in > compiler)
+        .filter(|marker| !allowed.iter().any(|id| id == marker.id))
+        .collect();
+
+    let mut findings = vec![];
+    for (line, code) in code_lines(source) {
+        for marker in markers.iter() {
+            if marker
+                .tokens
+                .iter()
+                .any(|token| contains_token(code, token))
+            {
+                findings.push(SyntaxFinding {
+                    file: file.to_owned(),
+                    line,
+                    marker,
+                });
+            }
+        }
+    }
+    findings
+}
+
+pub fn scan_files(
+    files: &SourceSet,
+    compiler: CairoVersion,
+    allowed: &[String],
+) -> Vec<SyntaxFinding> {
+    let mut findings = vec![];
+    for (name, contents) in files.iter().filter(|(name, _)| name.ends_with(".cairo")) {
+        let source = String::from_utf8_lossy(contents);
+        findings.extend(scan_source(name, &source, compiler, allowed));
+    }
+    findings
+}
+
+/**
+ * The oldest compiler able to parse every finding.
+ */
+pub fn required_version(findings: &[SyntaxFinding]) -> Option<CairoVersion> {
+    findings
+        .iter()
+        .map(|finding| finding.marker.introduced_in)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: [(&str, CairoVersion); 4] = [
+        (
+            include_str!("../tests/test_data/syntax/for_loop.cairo"),
+            (2, 6, 0),
+        ),
+        (
+            include_str!("../tests/test_data/syntax/crate_visibility.cairo"),
+            (2, 7, 0),
+        ),
+        (
+            include_str!("../tests/test_data/syntax/storage_node.cairo"),
+            (2, 8, 0),
+        ),
+        (
+            include_str!("../tests/test_data/syntax/closures.cairo"),
+            (2, 9, 0),
+        ),
+    ];
+
+    #[test]
+    fn test_version_boundaries() {
+        for (source, introduced_in) in FIXTURES {
+            let (major, minor, _) = introduced_in;
+            let findings = scan_source("lib.cairo", source, (major, minor - 1, 9), &[]);
+            assert_eq!(
+                required_version(&findings),
+                Some(introduced_in),
+                "{}",
+                source
+            );
+            assert!(scan_source("lib.cairo", source, introduced_in, &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_findings_can_be_allowed() {
+        let (source, _) = FIXTURES[3];
+        let findings = scan_source("lib.cairo", source, (2, 8, 4), &[]);
+        assert_eq!(findings[0].marker.id, "closures");
+        assert_eq!(
+            (findings[0].file.as_str(), findings[0].line),
+            ("lib.cairo", 1)
+        );
+        assert!(scan_source("lib.cairo", source, (2, 8, 4), &["closures".to_owned()]).is_empty());
+    }
+
+    #[test]
+    fn test_comments_and_identifiers_are_not_markers() {
+        let source = "// loop for each item\nfn wait_for (x: felt252) {}\n";
+        assert!(scan_source("lib.cairo", source, (2, 0, 0), &[]).is_empty());
+    }
+}
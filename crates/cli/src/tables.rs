@@ -0,0 +1,88 @@
+use std::time::SystemTime;
+
+use comfy_table::Table;
+
+use crate::display::sanitize;
+use crate::preflight::{Declaration, Preflight};
+use crate::verified_metadata::MetadataChange;
+
+/**
+ * The class about to be marked verified, as shown before dispatching.
+ */
+pub fn format_preflight(preflight: &Preflight, now: SystemTime) -> String {
+    let mut table = Table::new();
+    table.add_row(vec!["Class hash", preflight.class_hash.as_str()]);
+    table.add_row(vec!["Network", preflight.network.as_str()]);
+    table.add_row(vec!["Name", &*sanitize(&preflight.name)]);
+    let declared = match preflight.declaration {
+        Some(Declaration { block_number, .. }) => {
+            let block = block_number.map(|block| format!("block {}", block));
+            let age = preflight
+                .declared_days_ago(now)
+                .map(|days| format!("{} days ago", days));
+            block.into_iter().chain(age).collect::<Vec<_>>().join(", ")
+        }
+        None => "unknown".to_owned(),
+    };
+    table.add_row(vec!["Declared", declared.as_str()]);
+    if let Some(expected) = &preflight.expected_hash {
+        table.add_row(vec!["Expected hash", expected.as_str()]);
+    }
+    if let Some(verified_name) = &preflight.verified_name {
+        table.add_row(vec!["Verified as", &*sanitize(verified_name)]);
+    }
+    table.to_string()
+}
+
+pub fn format_metadata_diff(changes: &[MetadataChange]) -> String {
+    let mut table = Table::new();
+    table.set_header(vec!["Field", "Currently verified", "Resubmitted"]);
+    for change in changes {
+        table.add_row(vec![
+            change.field.as_str(),
+            &*sanitize(change.before.as_deref().unwrap_or("")),
+            &*sanitize(&change.after),
+        ]);
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const NOW: u64 = 1_760_000_000;
+
+    #[test]
+    fn test_format_preflight() {
+        let now = UNIX_EPOCH + Duration::from_secs(NOW);
+        let preflight = Preflight {
+            class_hash: "0x1234".to_owned(),
+            network: "Mainnet".to_owned(),
+            name: "MyToken".to_owned(),
+            declaration: Some(Declaration {
+                block_number: Some(812_345),
+                timestamp: Some(NOW - 3 * 24 * 60 * 60),
+            }),
+            expected_hash: None,
+            verified_name: None,
+        };
+
+        let block = format_preflight(&preflight, now);
+        assert!(block.contains("block 812345, 3 days ago"), "{}", block);
+        assert!(!block.contains("Verified as"), "{}", block);
+    }
+
+    #[test]
+    fn test_format_metadata_diff() {
+        let diff = format_metadata_diff(&[MetadataChange {
+            field: "name".to_owned(),
+            before: Some("MyToken".to_owned()),
+            after: "My\u{200b}Token".to_owned(),
+        }]);
+        assert!(diff.contains("MyToken"));
+        assert!(diff.contains("My\\u{200b}Token"));
+        assert!(!diff.contains('\u{200b}'));
+    }
+}
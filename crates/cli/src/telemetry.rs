@@ -0,0 +1,530 @@
+use std::{env, fs, sync::Mutex, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Subcommand;
+use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::error_code;
+use crate::ip_version;
+use crate::project_state::write_atomic;
+use crate::user_dirs::{Environment, Overrides, UserDirs};
+
+/// Whether the user opted in, in the state directory.
+const CONSENT_FILE: &str = "telemetry.json";
+/// Events not sent yet, one JSON object per line, next to the consent.
+const QUEUE_FILE: &str = "telemetry-queue.jsonl";
+
+/// Endpoint the batches are sent to, unless overridden with `TELEMETRY_URL_VAR`.
+pub const DEFAULT_TELEMETRY_URL: &str = "https://api.voyager.online/beta/cli-telemetry";
+pub const TELEMETRY_URL_VAR: &str = "VOYAGER_TELEMETRY_URL";
+/// Set to anything but `0`, disables telemetry whatever the consent.
+pub const DO_NOT_TRACK_VAR: &str = "DO_NOT_TRACK";
+
+/// Events sent together, fewer stay queued for a later run.
+pub const TELEMETRY_BATCH_SIZE: usize = 10;
+/// Queued events kept while sending fails, the oldest are dropped beyond.
+const MAX_QUEUED_EVENTS: usize = 100;
+/// Sending a batch is given up on after this, the events stay queued.
+pub const TELEMETRY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bounds of the duration buckets, the last one is open.
+const DURATION_BUCKETS: [(Duration, &str); 4] = [
+    (Duration::from_secs(10), "<10s"),
+    (Duration::from_secs(60), "10s-1m"),
+    (Duration::from_secs(5 * 60), "1m-5m"),
+    (Duration::from_secs(30 * 60), "5m-30m"),
+];
+const LONGEST_DURATION_BUCKET: &str = ">30m";
+
+static TOOLCHAIN: Mutex<Option<(SupportedScarbVersions, SupportedCairoVersions)>> =
+    Mutex::new(None);
+
+/**
+ * Remember the versions the project was resolved with, for the event of the
+ * run. Runs that don't resolve a project send none.
+ */
+pub fn record_toolchain(scarb: SupportedScarbVersions, cairo: SupportedCairoVersions) {
+    if let Ok(mut toolchain) = TOOLCHAIN.lock() {
+        *toolchain = Some((scarb, cairo));
+    }
+}
+
+pub fn recorded_toolchain() -> Option<(SupportedScarbVersions, SupportedCairoVersions)> {
+    TOOLCHAIN.lock().ok().and_then(|toolchain| *toolchain)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeClass {
+    Success,
+    Failure,
+}
+
+/**
+ * A finished run, as known once it returned.
+ */
+pub struct RunSummary<'a> {
+    /// Name of the subcommand, `verify` for the verification itself.
+    pub command: &'static str,
+    /// As given with --network, if at all.
+    pub network: Option<&'a str>,
+    pub result: &'a anyhow::Result<()>,
+    pub duration: Duration,
+    pub toolchain: Option<(SupportedScarbVersions, SupportedCairoVersions)>,
+}
+
+/**
+ * What is sent of a run. Every field is taken from a closed set of values,
+ * see `TelemetryEvent::new`, so class hashes, names, paths, urls, keys and
+ * error messages have nowhere to go.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryEvent {
+    pub command: String,
+    /// `mainnet`, `sepolia`, `local`, `custom` for any other, or `unspecified`.
+    pub network: String,
+    pub outcome: OutcomeClass,
+    /// Stable code of the error, see `explain`. Uncoded errors send none.
+    pub error_code: Option<String>,
+    /// One of `DURATION_BUCKETS`, never the exact duration.
+    pub duration: String,
+    pub client_version: String,
+    pub scarb_version: Option<String>,
+    pub cairo_version: Option<String>,
+}
+
+impl TelemetryEvent {
+    pub fn new(run: &RunSummary) -> Self {
+        Self {
+            command: run.command.to_owned(),
+            network: network_class(run.network).to_owned(),
+            outcome: match run.result {
+                Ok(()) => OutcomeClass::Success,
+                Err(_) => OutcomeClass::Failure,
+            },
+            error_code: run
+                .result
+                .as_ref()
+                .err()
+                .and_then(error_code)
+                .map(str::to_owned),
+            duration: duration_bucket(run.duration).to_owned(),
+            client_version: env!("CARGO_PKG_VERSION").to_owned(),
+            scarb_version: run.toolchain.map(|(scarb, _)| scarb.to_string()),
+            cairo_version: run.toolchain.map(|(_, cairo)| cairo.to_string()),
+        }
+    }
+}
+
+/**
+ * Custom networks, urls included, are all reported alike: their endpoints
+ * may name a private instance.
+ */
+fn network_class(network: Option<&str>) -> &'static str {
+    match network.map(str::to_ascii_lowercase).as_deref() {
+        None => "unspecified",
+        Some("mainnet") => "mainnet",
+        Some("sepolia") => "sepolia",
+        Some("local") => "local",
+        Some(_) => "custom",
+    }
+}
+
+fn duration_bucket(duration: Duration) -> &'static str {
+    DURATION_BUCKETS
+        .iter()
+        .find(|(bound, _)| duration < *bound)
+        .map_or(LONGEST_DURATION_BUCKET, |(_, bucket)| *bucket)
+}
+
+/**
+ * Body of a request to the telemetry endpoint.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelemetryBatch<'a> {
+    pub events: &'a [TelemetryEvent],
+}
+
+/**
+ * Why telemetry is off whatever the consent: `DO_NOT_TRACK`, or a frozen run
+ * which makes no request besides the verification.
+ */
+pub fn disabled_by(do_not_track: Option<&str>, frozen: bool) -> Option<String> {
+    if do_not_track.is_some_and(|value| !value.is_empty() && value != "0") {
+        Some(format!("{} is set", DO_NOT_TRACK_VAR))
+    } else if frozen {
+        Some("--frozen is set".to_owned())
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Consent {
+    enabled: bool,
+}
+
+/**
+ * The consent and the queued events, kept in the state directory. Telemetry
+ * is off until `telemetry enable` records the consent.
+ */
+pub struct TelemetryStore {
+    dir: Utf8PathBuf,
+}
+
+impl TelemetryStore {
+    pub fn new(state_dir: &Utf8Path) -> Self {
+        Self {
+            dir: state_dir.to_owned(),
+        }
+    }
+
+    fn consent_file(&self) -> Utf8PathBuf {
+        self.dir.join(CONSENT_FILE)
+    }
+
+    fn queue_file(&self) -> Utf8PathBuf {
+        self.dir.join(QUEUE_FILE)
+    }
+
+    /// An unreadable consent counts as none.
+    pub fn is_enabled(&self) -> bool {
+        fs::read_to_string(self.consent_file())
+            .ok()
+            .and_then(|consent| serde_json::from_str::<Consent>(&consent).ok())
+            .is_some_and(|consent| consent.enabled)
+    }
+
+    /**
+     * Record the choice of the user. Opting out also drops the events that
+     * weren't sent yet.
+     */
+    pub fn set_enabled(&self, enabled: bool) -> Result<()> {
+        let path = self.consent_file();
+        write_atomic(&path, &serde_json::to_vec(&Consent { enabled })?)
+            .with_context(|| format!("Unable to write {}", path))?;
+        if !enabled {
+            self.write_queue(&[])?;
+        }
+        Ok(())
+    }
+
+    /// Events that can't be read, e.g. of another version, are skipped.
+    pub fn queued(&self) -> Vec<TelemetryEvent> {
+        fs::read_to_string(self.queue_file())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    fn write_queue(&self, events: &[TelemetryEvent]) -> Result<()> {
+        let mut contents = String::new();
+        for event in events {
+            contents.push_str(&serde_json::to_string(event)?);
+            contents.push('\n');
+        }
+        write_atomic(&self.queue_file(), contents.as_bytes())
+    }
+
+    pub fn enqueue(&self, event: TelemetryEvent) -> Result<Vec<TelemetryEvent>> {
+        let mut events = self.queued();
+        events.push(event);
+        let overflow = events.len().saturating_sub(MAX_QUEUED_EVENTS);
+        events.drain(..overflow);
+        self.write_queue(&events)?;
+        Ok(events)
+    }
+
+    /**
+     * Send the queued events once there are enough of them, with `send`. They
+     * stay queued when it fails, to be sent with a later batch.
+     */
+    pub fn flush(&self, send: impl FnOnce(&[TelemetryEvent]) -> Result<()>) -> Result<bool> {
+        let events = self.queued();
+        if events.len() < TELEMETRY_BATCH_SIZE {
+            return Ok(false);
+        }
+        send(&events)?;
+        self.write_queue(&[])?;
+        Ok(true)
+    }
+}
+
+/**
+ * Post `events` to `url`, giving up after `TELEMETRY_TIMEOUT`.
+ */
+pub fn send_batch(url: &str, events: &[TelemetryEvent]) -> Result<()> {
+    let client = ip_version::client_builder()
+        .timeout(TELEMETRY_TIMEOUT)
+        .build()?;
+    let response = client.post(url).json(&TelemetryBatch { events }).send()?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "The telemetry endpoint answered {}",
+            response.status()
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TelemetryCommand {
+    /// Send anonymous usage events: command, network, outcome, duration and versions
+    Enable,
+    /// Stop sending events, and drop those not sent yet
+    Disable,
+    /// Show whether telemetry is on, where it is sent and how many events are queued
+    Status,
+    /// Print exactly what the next batch would send
+    Preview,
+}
+
+/**
+ * What telemetry needs of the arguments, taken before `run` consumes them.
+ */
+pub struct TelemetryContext {
+    pub command: &'static str,
+    pub network: Option<String>,
+    pub frozen: bool,
+    pub overrides: Overrides,
+}
+
+impl TelemetryContext {
+    /**
+     * Queue the event of the run if the user opted in, and send the queue once
+     * it makes a batch. Nothing is printed and nothing can fail the run.
+     */
+    pub fn record(&self, result: &Result<()>, duration: Duration) {
+        let do_not_track = env::var(DO_NOT_TRACK_VAR).ok();
+        if self.command == "telemetry"
+            || disabled_by(do_not_track.as_deref(), self.frozen).is_some()
+        {
+            return;
+        }
+        let store = match UserDirs::resolve(&Environment::current(), &self.overrides) {
+            Ok(user_dirs) => TelemetryStore::new(&user_dirs.state_dir),
+            Err(_) => return,
+        };
+        if !store.is_enabled() {
+            return;
+        }
+        let event = TelemetryEvent::new(&RunSummary {
+            command: self.command,
+            network: self.network.as_deref(),
+            result,
+            duration,
+            toolchain: recorded_toolchain(),
+        });
+        if store.enqueue(event).is_ok() {
+            let url = telemetry_url();
+            let _ = store.flush(|events| send_batch(&url, events));
+        }
+    }
+}
+
+fn telemetry_url() -> String {
+    env::var(TELEMETRY_URL_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_TELEMETRY_URL.to_owned())
+}
+
+pub fn run_telemetry(user_dirs: &UserDirs, command: TelemetryCommand) -> Result<()> {
+    let store = TelemetryStore::new(&user_dirs.state_dir);
+    let disabled = disabled_by(env::var(DO_NOT_TRACK_VAR).ok().as_deref(), false);
+    match command {
+        TelemetryCommand::Enable => {
+            store.set_enabled(true)?;
+            println!(
+                "Telemetry enabled. Each run records its command, its network (self-hosted ones as custom), whether it succeeded and its error code, how long it took in broad buckets, and the client, Scarb and Cairo versions. Class hashes, names, paths, urls and keys are never recorded."
+            );
+            println!(
+                "Events are sent to {} in batches of {}, `telemetry preview` shows them.",
+                telemetry_url(),
+                TELEMETRY_BATCH_SIZE
+            );
+            if let Some(reason) = disabled {
+                println!("Nothing is recorded while {}.", reason);
+            }
+        }
+        TelemetryCommand::Disable => {
+            store.set_enabled(false)?;
+            println!("Telemetry disabled, the events not sent yet were dropped.");
+        }
+        TelemetryCommand::Status => {
+            let state = match (store.is_enabled(), disabled) {
+                (true, None) => "enabled".to_owned(),
+                (true, Some(reason)) => format!("enabled, but off while {}", reason),
+                (false, _) => "disabled".to_owned(),
+            };
+            println!("Telemetry: {}", state);
+            println!("Endpoint: {}", telemetry_url());
+            println!(
+                "Queued events: {}, sent by batches of {}",
+                store.queued().len(),
+                TELEMETRY_BATCH_SIZE
+            );
+        }
+        TelemetryCommand::Preview => {
+            let events = store.queued();
+            println!("POST {}", telemetry_url());
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&TelemetryBatch { events: &events })?
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::VerifierError;
+    use crate::mock_server::{MockResponse, MockServer};
+
+    fn temp_dir(name: &str) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!(
+            "voyager-telemetry-{}-{}",
+            name,
+            std::process::id()
+        )))
+        .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn event(result: &anyhow::Result<()>) -> TelemetryEvent {
+        TelemetryEvent::new(&RunSummary {
+            command: "verify",
+            network: Some("sepolia"),
+            result,
+            duration: Duration::from_secs(42),
+            toolchain: Some((
+                SupportedScarbVersions::V2_8_4,
+                SupportedCairoVersions::V2_8_4,
+            )),
+        })
+    }
+
+    #[test]
+    fn test_forbidden_fields_cannot_appear() {
+        let class_hash = "0x044dc2b3239382230d8b1e943df23b96f52eebcac93efe6e8bde92f9a2f1da18";
+        let api_key = "sk-live-secret";
+        let path = "/home/alice/projects/my_token/src/lib.cairo";
+        let result: anyhow::Result<()> = Err(anyhow::Error::from(VerifierError::JobNotFound)
+            .context(format!(
+                "Class {} named MyToken at {} rejected with key {}",
+                class_hash, path, api_key
+            )));
+        let events = [
+            event(&result),
+            TelemetryEvent::new(&RunSummary {
+                command: "status",
+                network: Some("https://verifier.mycorp.dev"),
+                result: &result,
+                duration: Duration::from_secs(3600),
+                toolchain: None,
+            }),
+        ];
+        let body = serde_json::to_value(TelemetryBatch { events: &events }).unwrap();
+        let text = body.to_string();
+        for forbidden in [class_hash, "MyToken", path, "alice", api_key, "mycorp"] {
+            assert!(!text.contains(forbidden), "{} was sent", forbidden);
+        }
+
+        // Only these fields can be sent
+        let mut keys: Vec<&str> = body["events"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "cairo_version",
+                "client_version",
+                "command",
+                "duration",
+                "error_code",
+                "network",
+                "outcome",
+                "scarb_version",
+            ]
+        );
+        assert_eq!(body["events"][0]["error_code"], "VER-009");
+        assert_eq!(body["events"][0]["duration"], "10s-1m");
+        assert_eq!(body["events"][1]["network"], "custom");
+        assert_eq!(body["events"][1]["duration"], ">30m");
+
+        // Nor can queued events carry more
+        let mut tampered = serde_json::to_value(&events[0]).unwrap();
+        tampered["class_hash"] = serde_json::json!(class_hash);
+        assert!(serde_json::from_value::<TelemetryEvent>(tampered).is_err());
+    }
+
+    #[test]
+    fn test_disabled_by_do_not_track_and_frozen() {
+        assert_eq!(disabled_by(None, false), None);
+        assert_eq!(disabled_by(Some("0"), false), None);
+        assert_eq!(disabled_by(Some(""), false), None);
+        assert!(disabled_by(Some("1"), false)
+            .unwrap()
+            .contains(DO_NOT_TRACK_VAR));
+        assert!(disabled_by(None, true).unwrap().contains("--frozen"));
+    }
+
+    #[test]
+    fn test_off_by_default_and_opt_out_drops_the_queue() {
+        let dir = temp_dir("consent");
+        let store = TelemetryStore::new(&dir);
+        assert!(!store.is_enabled());
+
+        store.set_enabled(true).unwrap();
+        assert!(store.is_enabled());
+        store.enqueue(event(&Ok(()))).unwrap();
+        assert_eq!(store.queued().len(), 1);
+
+        store.set_enabled(false).unwrap();
+        assert!(!store.is_enabled());
+        assert!(store.queued().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_events_are_sent_in_batches() {
+        let dir = temp_dir("batches");
+        let store = TelemetryStore::new(&dir);
+        for _ in 0..MAX_QUEUED_EVENTS + 5 {
+            store.enqueue(event(&Ok(()))).unwrap();
+        }
+        assert_eq!(store.queued().len(), MAX_QUEUED_EVENTS);
+
+        // A failure keeps them queued
+        let server = MockServer::start(vec![
+            MockResponse::new(503, "{}"),
+            MockResponse::new(200, "{}"),
+        ]);
+        let url = format!("{}/cli-telemetry", server.url);
+        assert!(store.flush(|events| send_batch(&url, events)).is_err());
+        assert_eq!(store.queued().len(), MAX_QUEUED_EVENTS);
+        assert!(store.flush(|events| send_batch(&url, events)).unwrap());
+        assert!(store.queued().is_empty());
+        let requests = server.finish();
+        let sent: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(sent["events"].as_array().unwrap().len(), MAX_QUEUED_EVENTS);
+
+        // Too few events to be worth a request
+        store.enqueue(event(&Ok(()))).unwrap();
+        assert!(!store
+            .flush(|_| panic!("sent before the batch is full"))
+            .unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
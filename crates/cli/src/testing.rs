@@ -0,0 +1,379 @@
+// Only the tests of this crate use the fake, builds with the `testing`
+// feature expose it without using it.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::{collections::VecDeque, sync::Mutex, thread::sleep, time::Duration};
+
+use anyhow::anyhow;
+use serde_json::json;
+
+use crate::api::{PollUpdate, VerificationJob, VerifyJobStatus};
+use crate::class_hash::ClassHash;
+use crate::client::{DispatchRequest, VerifierClient};
+use crate::errors::{Error, Result, VerifierError};
+use crate::polling::PollingConfig;
+
+/// Job id of the jobs dispatched by the ready-made scenarios.
+pub const FAKE_JOB_ID: &str = "fake-job";
+
+/**
+ * One step of a scripted scenario, consumed in order by `dispatch` and `poll`.
+ */
+#[derive(Debug)]
+pub enum Step {
+    /// `dispatch` returns this job id.
+    Dispatched(String),
+    /// `poll` observes this status, with an optional description.
+    Status(VerifyJobStatus, Option<String>),
+    /// The next `dispatch` or `poll` fails with this error.
+    Error(Error),
+    /// Wait before the next step.
+    Latency(Duration),
+}
+
+/**
+ * Every status of a job. The match below stops compiling when a status is
+ * added, so that the scenarios get updated along with it.
+ */
+pub fn all_statuses() -> [VerifyJobStatus; 5] {
+    let statuses = [
+        VerifyJobStatus::Submitted,
+        VerifyJobStatus::Compiled,
+        VerifyJobStatus::CompileFailed,
+        VerifyJobStatus::Fail,
+        VerifyJobStatus::Success,
+    ];
+    for status in &statuses {
+        match status {
+            VerifyJobStatus::Submitted
+            | VerifyJobStatus::Compiled
+            | VerifyJobStatus::CompileFailed
+            | VerifyJobStatus::Fail
+            | VerifyJobStatus::Success => (),
+        }
+    }
+    statuses
+}
+
+/// Status code of `status` in the job status response.
+fn status_code(status: VerifyJobStatus) -> u8 {
+    match status {
+        VerifyJobStatus::Submitted => 0,
+        VerifyJobStatus::Compiled => 1,
+        VerifyJobStatus::CompileFailed => 2,
+        VerifyJobStatus::Fail => 3,
+        VerifyJobStatus::Success => 4,
+    }
+}
+
+/**
+ * A job as the backend returns it, for the given status.
+ */
+pub fn scripted_job(
+    job_id: &str,
+    status: VerifyJobStatus,
+    description: Option<&str>,
+) -> VerificationJob {
+    serde_json::from_value(json!({
+        "job_id": job_id,
+        "status": status_code(status),
+        "status_description": description,
+        "class_hash": "0x0",
+    }))
+    .expect("scripted jobs are valid job status responses")
+}
+
+/**
+ * A `VerifierClient` replaying a scripted scenario instead of talking to the
+ * backend. Polling behaves like the real client: it returns on `Success`,
+ * fails on `Fail` and `CompileFailed`, and times out once the scenario has no
+ * status left or after as many pending statuses as intervals fit in the
+ * timeout. No time passes between the statuses but the scripted latency.
+ */
+#[derive(Debug, Default)]
+pub struct FakeVerifier {
+    steps: Mutex<VecDeque<Step>>,
+    dispatched: Mutex<Vec<Vec<String>>>,
+    dispatched_plugins: Mutex<Vec<Vec<String>>>,
+    latency: Duration,
+    class_verified: Option<bool>,
+}
+
+impl FakeVerifier {
+    pub fn new(steps: impl IntoIterator<Item = Step>) -> Self {
+        Self {
+            steps: Mutex::new(steps.into_iter().collect()),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * A job dispatched and polled until it reaches `status`, going through the
+     * statuses the backend reports on the way. Pending statuses never finish
+     * and end in a timeout.
+     */
+    pub fn ending_with(status: VerifyJobStatus, description: Option<&str>) -> Self {
+        let description = description.map(str::to_owned);
+        let statuses = match status {
+            VerifyJobStatus::Submitted => vec![(VerifyJobStatus::Submitted, description)],
+            VerifyJobStatus::Compiled => vec![
+                (VerifyJobStatus::Submitted, None),
+                (VerifyJobStatus::Compiled, description),
+            ],
+            VerifyJobStatus::CompileFailed => vec![
+                (VerifyJobStatus::Submitted, None),
+                (VerifyJobStatus::CompileFailed, description),
+            ],
+            VerifyJobStatus::Fail | VerifyJobStatus::Success => vec![
+                (VerifyJobStatus::Submitted, None),
+                (VerifyJobStatus::Compiled, None),
+                (status, description),
+            ],
+        };
+        Self::new(
+            std::iter::once(Step::Dispatched(FAKE_JOB_ID.to_owned())).chain(
+                statuses
+                    .into_iter()
+                    .map(|(status, description)| Step::Status(status, description)),
+            ),
+        )
+    }
+
+    pub fn success() -> Self {
+        Self::ending_with(VerifyJobStatus::Success, None)
+    }
+
+    pub fn compile_failed(description: &str) -> Self {
+        Self::ending_with(VerifyJobStatus::CompileFailed, Some(description))
+    }
+
+    pub fn failed(description: &str) -> Self {
+        Self::ending_with(VerifyJobStatus::Fail, Some(description))
+    }
+
+    /// A job that stays compiled until polling gives up.
+    pub fn timeout() -> Self {
+        Self::ending_with(VerifyJobStatus::Compiled, None)
+    }
+
+    /// A dispatch rejected by the rate limiter.
+    pub fn rate_limited() -> Self {
+        Self::new([Step::Error(
+            VerifierError::DispatchFailed {
+                status: 429,
+                message: "Too Many Requests".to_owned(),
+            }
+            .into(),
+        )])
+    }
+
+    /// Append the steps of `next`, e.g. for the attempt after a resubmission.
+    pub fn then(self, next: FakeVerifier) -> Self {
+        self.steps
+            .lock()
+            .unwrap()
+            .extend(next.steps.into_inner().unwrap());
+        self
+    }
+
+    /// A job whose record is garbage-collected before it is polled.
+    pub fn job_gone() -> Self {
+        Self::new([
+            Step::Dispatched(FAKE_JOB_ID.to_owned()),
+            Step::Error(VerifierError::JobNotFound.into()),
+        ])
+    }
+
+    /// What the class says about its verification, unknown by default.
+    pub fn with_class_verified(mut self, verified: bool) -> Self {
+        self.class_verified = Some(verified);
+        self
+    }
+
+    /// Wait `latency` before answering every dispatch and poll.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Aliases of every dispatched job, in order.
+    pub fn dispatched(&self) -> Vec<Vec<String>> {
+        self.dispatched.lock().unwrap().clone()
+    }
+
+    /// Names of the compiler plugins of every dispatched job, in order.
+    pub fn dispatched_plugins(&self) -> Vec<Vec<String>> {
+        self.dispatched_plugins.lock().unwrap().clone()
+    }
+
+    /// Whether the whole scenario was played.
+    pub fn is_finished(&self) -> bool {
+        self.steps.lock().unwrap().is_empty()
+    }
+
+    /**
+     * The next step that isn't a latency, after waiting for it.
+     */
+    fn next_step(&self) -> Option<Step> {
+        sleep(self.latency);
+        loop {
+            let step = self.steps.lock().unwrap().pop_front()?;
+            match step {
+                Step::Latency(latency) => sleep(latency),
+                step => return Some(step),
+            }
+        }
+    }
+}
+
+impl VerifierClient for FakeVerifier {
+    fn dispatch(&self, request: &DispatchRequest) -> Result<String> {
+        match self.next_step() {
+            Some(Step::Dispatched(job_id)) => {
+                self.dispatched
+                    .lock()
+                    .unwrap()
+                    .push(request.aliases.to_vec());
+                self.dispatched_plugins.lock().unwrap().push(
+                    request
+                        .metadata
+                        .plugins
+                        .iter()
+                        .map(|plugin| plugin.name.clone())
+                        .collect(),
+                );
+                Ok(job_id)
+            }
+            Some(Step::Error(e)) => Err(e),
+            step => Err(anyhow!("Unexpected dispatch, the scenario expected {:?}", step).into()),
+        }
+    }
+
+    fn poll(
+        &self,
+        job_id: &str,
+        polling: &PollingConfig,
+        on_update: &mut dyn FnMut(PollUpdate),
+    ) -> Result<VerificationJob> {
+        let mut retries = 0;
+        let timeout = |retries: u32| VerifierError::Timeout {
+            job_id: job_id.to_owned(),
+            waited_secs: (polling.interval * retries).as_secs(),
+        };
+        loop {
+            let (status, description) = match self.next_step() {
+                Some(Step::Status(status, description)) => (status, description),
+                Some(Step::Error(e)) => return Err(e),
+                // Played out, the job never finishes
+                None => return Err(timeout(retries).into()),
+                Some(step) => {
+                    return Err(anyhow!("Unexpected poll, the scenario expected {:?}", step).into())
+                }
+            };
+            let job = scripted_job(job_id, status, description.as_deref());
+            on_update(PollUpdate::Job(&job));
+            match status {
+                VerifyJobStatus::Success => return Ok(job),
+                VerifyJobStatus::Fail => {
+                    return Err(VerifierError::VerificationFailed(
+                        description.unwrap_or("unknown failure".to_owned()),
+                    )
+                    .into())
+                }
+                VerifyJobStatus::CompileFailed => {
+                    return Err(VerifierError::CompilationFailed(
+                        description.unwrap_or("unknown failure".to_owned()),
+                    )
+                    .into())
+                }
+                VerifyJobStatus::Submitted | VerifyJobStatus::Compiled => (),
+            }
+            if polling
+                .max_retries()
+                .is_some_and(|max_retries| retries >= max_retries)
+            {
+                return Err(timeout(retries).into());
+            }
+            retries += 1;
+        }
+    }
+
+    fn class_verified(&self, _class_hash: &ClassHash) -> Result<Option<bool>> {
+        Ok(self.class_verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Room for `retries` polls after the first one.
+    fn polling(retries: u32) -> PollingConfig {
+        PollingConfig {
+            timeout: Some(Duration::from_secs(5) * retries),
+            ..PollingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_scripted_jobs_round_trip_every_status() {
+        for status in all_statuses() {
+            let job = scripted_job("job", status, Some("description"));
+            assert_eq!(job.status(), status);
+            assert_eq!(job.status_description(), Some("description"));
+        }
+    }
+
+    #[test]
+    fn test_scenarios_end_like_the_real_client() {
+        let mut observed = vec![];
+        let fake = FakeVerifier::new([
+            Step::Status(VerifyJobStatus::Submitted, None),
+            Step::Latency(Duration::from_millis(1)),
+            Step::Status(VerifyJobStatus::Success, None),
+        ]);
+        let job = fake
+            .poll(FAKE_JOB_ID, &polling(10), &mut |update| {
+                if let PollUpdate::Job(job) = update {
+                    observed.push(job.status())
+                }
+            })
+            .unwrap();
+        assert_eq!(job.job_id(), FAKE_JOB_ID);
+        assert_eq!(
+            observed,
+            vec![VerifyJobStatus::Submitted, VerifyJobStatus::Success]
+        );
+
+        let expected_codes = [
+            (VerifyJobStatus::Submitted, "VER-013"),
+            (VerifyJobStatus::Compiled, "VER-013"),
+            (VerifyJobStatus::CompileFailed, "VER-012"),
+            (VerifyJobStatus::Fail, "VER-011"),
+        ];
+        for (status, code) in expected_codes {
+            let fake = FakeVerifier::ending_with(status, None);
+            assert!(matches!(fake.next_step(), Some(Step::Dispatched(_))));
+            let error = fake
+                .poll(FAKE_JOB_ID, &polling(10), &mut |_| {})
+                .unwrap_err();
+            assert_eq!(error.code(), Some(code), "{}", status);
+            assert!(fake.is_finished());
+        }
+    }
+
+    #[test]
+    fn test_polling_gives_up_at_the_timeout() {
+        let fake = FakeVerifier::new([
+            Step::Status(VerifyJobStatus::Submitted, None),
+            Step::Status(VerifyJobStatus::Compiled, None),
+            Step::Status(VerifyJobStatus::Success, None),
+        ]);
+        let error = fake
+            .poll(FAKE_JOB_ID, &polling(1), &mut |_| {})
+            .unwrap_err();
+        assert_eq!(error.code(), Some("VER-013"));
+        assert!(error.to_string().contains(FAKE_JOB_ID), "{}", error);
+        assert!(!fake.is_finished());
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use comfy_table::Table;
+
+use crate::humanize::format_duration;
+
+/// Walking taking more than this share of the recorded time triggers a hint.
+const WALK_HINT_RATIO: f64 = 0.5;
+const WALK_HINT_MIN: Duration = Duration::from_secs(1);
+const SLOWEST_OPERATIONS: usize = 10;
+
+/**
+ * Timings collected while the CLI runs. Recording is a no-op until `enable` is
+ * called, so the instrumentation costs a lock and a branch when disabled.
+ */
+#[derive(Debug, Default)]
+struct Profile {
+    detailed: bool,
+    phases: Vec<(String, Duration)>,
+    directory_walks: HashMap<String, Duration>,
+    operations: Vec<(String, Duration)>,
+}
+
+static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+
+fn with_profile(f: impl FnOnce(&mut Profile)) {
+    if let Ok(mut profile) = PROFILE.lock() {
+        if let Some(profile) = profile.as_mut() {
+            f(profile);
+        }
+    }
+}
+
+/**
+ * Start recording. `detailed` additionally records per directory walk times and
+ * individual operations.
+ */
+pub fn enable(detailed: bool) {
+    if let Ok(mut profile) = PROFILE.lock() {
+        *profile = Some(Profile {
+            detailed,
+            ..Default::default()
+        });
+    }
+}
+
+pub fn is_detailed() -> bool {
+    PROFILE
+        .lock()
+        .map(|profile| profile.as_ref().is_some_and(|profile| profile.detailed))
+        .unwrap_or(false)
+}
+
+pub fn record_phase(name: &str, duration: Duration) {
+    with_profile(|profile| profile.phases.push((name.to_owned(), duration)));
+}
+
+/**
+ * Run `f` as a named phase of the command.
+ */
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record_phase(name, started.elapsed());
+    result
+}
+
+pub fn record_directory_walk(directory: &str, duration: Duration) {
+    with_profile(|profile| {
+        if profile.detailed {
+            *profile
+                .directory_walks
+                .entry(directory.to_owned())
+                .or_default() += duration;
+        }
+    });
+}
+
+pub fn record_operation(name: &str, duration: Duration) {
+    with_profile(|profile| {
+        if profile.detailed {
+            profile.operations.push((name.to_owned(), duration));
+        }
+    });
+}
+
+fn walk_hint(walking: Duration, total: Duration) -> Option<String> {
+    if walking < WALK_HINT_MIN || walking.as_secs_f64() < total.as_secs_f64() * WALK_HINT_RATIO {
+        return None;
+    }
+    Some(format!(
+        "Walking the project took {} of {}. Consider excluding large directories that aren't part of the package, such as build outputs or vendored dependencies.",
+        format_duration(walking),
+        format_duration(total)
+    ))
+}
+
+impl Profile {
+    fn format(&self) -> String {
+        let mut output = String::new();
+        let total: Duration = self.phases.iter().map(|(_, duration)| *duration).sum();
+
+        let mut phases = Table::new();
+        phases.set_header(vec!["Phase", "Duration"]);
+        for (name, duration) in self.phases.iter() {
+            phases.add_row(vec![name.clone(), format_duration(*duration)]);
+        }
+        phases.add_row(vec!["total".to_owned(), format_duration(total)]);
+        output.push_str(&phases.to_string());
+
+        if self.detailed {
+            let mut walks: Vec<_> = self.directory_walks.iter().collect();
+            walks.sort_by_key(|(_, duration)| Reverse(**duration));
+            let mut directories = Table::new();
+            directories.set_header(vec!["Directory", "Walk time"]);
+            for (directory, duration) in walks.iter().take(SLOWEST_OPERATIONS) {
+                directories.add_row(vec![directory.to_string(), format_duration(**duration)]);
+            }
+            output.push_str(&format!("\n{}", directories));
+
+            let mut operations = self.operations.clone();
+            operations.sort_by_key(|(_, duration)| Reverse(*duration));
+            let mut slowest = Table::new();
+            slowest.set_header(vec!["Slowest operations", "Duration"]);
+            for (name, duration) in operations.iter().take(SLOWEST_OPERATIONS) {
+                slowest.add_row(vec![name.clone(), format_duration(*duration)]);
+            }
+            output.push_str(&format!("\n{}", slowest));
+
+            let walking = self.directory_walks.values().sum();
+            if let Some(hint) = walk_hint(walking, total) {
+                output.push_str(&format!("\n{}", hint));
+            }
+        }
+        output
+    }
+}
+
+/**
+ * Print the timings recorded so far, if recording is enabled.
+ */
+pub fn print_report() {
+    with_profile(|profile| println!("{}", profile.format()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_hint_threshold() {
+        assert!(walk_hint(Duration::from_secs(20), Duration::from_secs(30)).is_some());
+        assert!(walk_hint(Duration::from_secs(5), Duration::from_secs(30)).is_none());
+        // Fast projects never get the hint
+        assert!(walk_hint(Duration::from_millis(200), Duration::from_millis(300)).is_none());
+    }
+
+    #[test]
+    fn test_profile_format() {
+        let mut profile = Profile {
+            detailed: true,
+            ..Default::default()
+        };
+        profile
+            .phases
+            .push(("collection".to_owned(), Duration::from_secs(25)));
+        profile
+            .phases
+            .push(("compile".to_owned(), Duration::from_secs(5)));
+        profile
+            .directory_walks
+            .insert("my_project/src".to_owned(), Duration::from_secs(20));
+        profile.operations.push((
+            "read my_project/src/lib.cairo".to_owned(),
+            Duration::from_secs(2),
+        ));
+
+        let report = profile.format();
+        assert!(report.contains("collection"));
+        assert!(report.contains("my_project/src"));
+        assert!(report.contains("read my_project/src/lib.cairo"));
+        assert!(report.contains("Consider excluding"));
+    }
+}
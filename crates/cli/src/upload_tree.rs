@@ -0,0 +1,375 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+
+use crate::api::ProjectMetadataInfo;
+use crate::errors::VerifierError;
+use crate::sources::SourceSet;
+
+/// Only the dependencies are resolved by the backend, dev dependencies are
+/// uploaded as written by the user and never built.
+const DEPENDENCY_TABLE: &str = "dependencies";
+
+const MANIFEST_NAME: &str = "scarb.toml";
+
+/**
+ * The uploaded file names seen as the only filesystem the backend has: files
+ * are the keys, directories are their `/` separated prefixes, and the root is
+ * the empty path.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct UploadTree {
+    files: BTreeSet<String>,
+    directories: BTreeSet<String>,
+}
+
+impl UploadTree {
+    pub fn new<S: AsRef<str>>(keys: impl IntoIterator<Item = S>) -> Self {
+        let mut tree = Self::default();
+        tree.directories.insert(String::new());
+        for key in keys {
+            let key = key.as_ref();
+            let mut end = 0;
+            while let Some(separator) = key[end..].find('/') {
+                end += separator;
+                tree.directories.insert(key[..end].to_owned());
+                end += 1;
+            }
+            tree.files.insert(key.to_owned());
+        }
+        tree
+    }
+
+    pub fn is_file(&self, path: &str) -> bool {
+        self.files.contains(path)
+    }
+
+    pub fn is_dir(&self, path: &str) -> bool {
+        self.directories.contains(path)
+    }
+
+    /**
+     * Lexically resolve `relative` against the directory `base`. `None` when
+     * the path climbs above the root or is absolute, the backend would then
+     * look outside of the upload.
+     */
+    pub fn resolve(base: &str, relative: &str) -> Option<String> {
+        if relative.starts_with('/') || relative.contains('\\') {
+            return None;
+        }
+        let mut components: Vec<&str> = base.split('/').filter(|c| !c.is_empty()).collect();
+        for component in relative.split('/') {
+            match component {
+                "" | "." => (),
+                ".." => {
+                    components.pop()?;
+                }
+                component => components.push(component),
+            }
+        }
+        Some(components.join("/"))
+    }
+
+    /**
+     * The deepest existing directory on the way to `path`, shown to the user
+     * to locate where the path went astray.
+     */
+    pub fn nearest_ancestor(&self, path: &str) -> String {
+        let mut ancestor = path;
+        loop {
+            if self.is_dir(ancestor) {
+                return display_path(ancestor);
+            }
+            match ancestor.rfind('/') {
+                Some(separator) => ancestor = &ancestor[..separator],
+                None => return display_path(""),
+            }
+        }
+    }
+
+    fn outside(&self, field: &str, path: &str, resolved: Option<&str>) -> VerifierError {
+        VerifierError::PathOutsideUpload {
+            field: field.to_owned(),
+            path: path.to_owned(),
+            nearest: self.nearest_ancestor(resolved.unwrap_or_default()),
+        }
+    }
+
+    /**
+     * Check that the project directory, the contract file, and the path
+     * dependencies of the uploaded manifests, given as `(key, contents)`,
+     * all resolve inside of the upload.
+     */
+    pub fn validate(
+        &self,
+        project_metadata: &ProjectMetadataInfo,
+        manifests: &[(String, String)],
+    ) -> Result<()> {
+        let project_dir = &project_metadata.project_dir_path;
+        let resolved = Self::resolve("", project_dir);
+        if !resolved.as_deref().is_some_and(|path| self.is_dir(path)) {
+            return Err(self
+                .outside("project_dir_path", project_dir, resolved.as_deref())
+                .into());
+        }
+
+        let contract_file = &project_metadata.contract_file;
+        let resolved = Self::resolve("", contract_file);
+        if !resolved.as_deref().is_some_and(|path| self.is_file(path)) {
+            return Err(self
+                .outside("contract_file", contract_file, resolved.as_deref())
+                .into());
+        }
+
+        for (key, contents) in manifests {
+            let manifest_dir = key.rsplit_once('/').map_or("", |(dir, _)| dir);
+            for dependency in manifest_path_dependencies(key, contents)? {
+                let manifest = Self::resolve(manifest_dir, &dependency)
+                    .map(|dir| Self::resolve(&dir, "Scarb.toml").unwrap_or_default());
+                if !manifest.as_deref().is_some_and(|path| self.is_file(path)) {
+                    let field = format!("dependency path in {}", key);
+                    return Err(self
+                        .outside(&field, &dependency, manifest.as_deref())
+                        .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        "the upload root".to_owned()
+    } else {
+        path.to_owned()
+    }
+}
+
+fn manifest_path_dependencies(key: &str, contents: &str) -> Result<Vec<String>> {
+    let manifest: toml::Value = contents
+        .parse()
+        .with_context(|| format!("Unable to parse uploaded manifest {}", key))?;
+    Ok(manifest
+        .get(DEPENDENCY_TABLE)
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, dependency)| dependency.get("path").and_then(toml::Value::as_str))
+        .map(str::to_owned)
+        .collect())
+}
+
+/**
+ * Final check before uploading: every path the backend will follow must be
+ * one of the uploaded files. Otherwise it resolves them against its own
+ * filesystem and fails with a confusing error.
+ */
+pub fn validate_upload_paths(
+    files: &SourceSet,
+    project_metadata: &ProjectMetadataInfo,
+) -> Result<()> {
+    let tree = UploadTree::new(files.names());
+    let manifests = files
+        .iter()
+        .filter(|(name, _)| {
+            name.rsplit('/')
+                .next()
+                .is_some_and(|name| name.eq_ignore_ascii_case(MANIFEST_NAME))
+        })
+        .map(|(name, contents)| {
+            (
+                name.to_owned(),
+                String::from_utf8_lossy(contents).into_owned(),
+            )
+        })
+        .collect::<Vec<(String, String)>>();
+    tree.validate(project_metadata, &manifests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+
+    fn metadata(project_dir_path: &str, contract_file: &str) -> ProjectMetadataInfo {
+        ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: project_dir_path.to_owned(),
+            contract_file: contract_file.to_owned(),
+            target: Default::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        }
+    }
+
+    fn manifest(dependencies: &[(&str, &str)]) -> String {
+        let mut contents = "[package]\nname = \"pkg\"\n\n[dependencies]\n".to_owned();
+        for (name, path) in dependencies {
+            contents.push_str(&format!("{} = {{ path = \"{}\" }}\n", name, path));
+        }
+        contents
+    }
+
+    fn error_message(result: Result<()>) -> String {
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn test_resolve_stays_in_the_upload() {
+        assert_eq!(UploadTree::resolve("a/b", "../c").as_deref(), Some("a/c"));
+        assert_eq!(UploadTree::resolve("a", "./b/"), Some("a/b".to_owned()));
+        assert_eq!(UploadTree::resolve("a", "../.."), None);
+        assert_eq!(UploadTree::resolve("", "/etc"), None);
+    }
+
+    #[test]
+    fn test_project_dir_above_the_upload_is_rejected() {
+        let tree = UploadTree::new(["token/Scarb.toml", "token/src/lib.cairo"]);
+        assert!(tree
+            .validate(&metadata("token", "token/src/lib.cairo"), &[])
+            .is_ok());
+
+        let message = error_message(tree.validate(&metadata("..", "token/src/lib.cairo"), &[]));
+        assert!(message.starts_with("VER-024"), "{}", message);
+        assert!(message.contains("project_dir_path .."), "{}", message);
+        assert!(message.contains("the upload root"), "{}", message);
+
+        let message = error_message(tree.validate(&metadata("token", "token/src/main.cairo"), &[]));
+        assert!(
+            message.contains("nearest uploaded directory is token/src"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_dependency_outside_the_upload_is_rejected() {
+        let tree = UploadTree::new([
+            "app/Scarb.toml",
+            "app/src/lib.cairo",
+            "libs/math/Scarb.toml",
+        ]);
+        let project = metadata("app", "app/src/lib.cairo");
+        let good = manifest(&[("math", "../libs/math")]);
+        assert!(tree
+            .validate(&project, &[("app/Scarb.toml".to_owned(), good)])
+            .is_ok());
+
+        let missing = manifest(&[("utils", "../libs/utils")]);
+        let message =
+            error_message(tree.validate(&project, &[("app/Scarb.toml".to_owned(), missing)]));
+        assert!(message.contains("dependency path in app/Scarb.toml ../libs/utils"));
+        assert!(
+            message.contains("nearest uploaded directory is libs"),
+            "{}",
+            message
+        );
+    }
+
+    /// Small deterministic generator, so that failures are reproducible.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) as usize) % bound
+        }
+    }
+
+    /**
+     * Generate workspaces of up to four members nested at random depths,
+     * upload a random subset of them as if remapped under a random prefix,
+     * and check that the validator accepts exactly the uploads containing
+     * the package and everything it depends on.
+     */
+    #[test]
+    fn test_fuzz_workspace_and_remap_combinations() {
+        const DIRECTORIES: [&str; 4] = ["", "crates", "crates/core", "libs"];
+        let mut rng = Lcg(0x5eed);
+        for iteration in 0..500 {
+            let members: Vec<String> = (0..1 + rng.next(4))
+                .map(|i| {
+                    let directory = DIRECTORIES[rng.next(DIRECTORIES.len())];
+                    match directory {
+                        "" => format!("member{}", i),
+                        directory => format!("{}/member{}", directory, i),
+                    }
+                })
+                .collect::<BTreeSet<String>>()
+                .into_iter()
+                .collect();
+            let prefix = ["", "ws", "ws/nested"][rng.next(3)];
+            let key = |path: &str| match prefix {
+                "" => path.to_owned(),
+                prefix => format!("{}/{}", prefix, path),
+            };
+            let depth = |path: &str| path.split('/').count();
+
+            let package = &members[0];
+            let uploaded: Vec<&String> = members
+                .iter()
+                .filter(|member| *member == package || rng.next(3) > 0)
+                .collect();
+            let dependencies: Vec<&String> =
+                members[1..].iter().filter(|_| rng.next(2) == 0).collect();
+            // Remapping may cut the package off from its dependencies
+            let climbs_extra = rng.next(5) == 0;
+
+            let mut keys = vec![];
+            let mut manifests = vec![];
+            for member in uploaded.iter() {
+                keys.push(key(&format!("{}/src/lib.cairo", member)));
+                keys.push(key(&format!("{}/Scarb.toml", member)));
+                let member_dependencies: Vec<(String, String)> = if *member == package {
+                    dependencies
+                        .iter()
+                        .map(|dependency| {
+                            let mut ups = depth(package);
+                            if climbs_extra {
+                                ups += depth(prefix) + 1;
+                            }
+                            (
+                                dependency.replace('/', "_"),
+                                format!("{}{}", "../".repeat(ups), dependency),
+                            )
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                };
+                let borrowed: Vec<(&str, &str)> = member_dependencies
+                    .iter()
+                    .map(|(name, path)| (name.as_str(), path.as_str()))
+                    .collect();
+                manifests.push((key(&format!("{}/Scarb.toml", member)), manifest(&borrowed)));
+            }
+
+            let tree = UploadTree::new(&keys);
+            let project = metadata(&key(package), &key(&format!("{}/src/lib.cairo", package)));
+            let result = tree.validate(&project, &manifests);
+
+            let dependencies_uploaded = dependencies.iter().all(|d| uploaded.contains(d));
+            let expected_ok = dependencies.is_empty() || (dependencies_uploaded && !climbs_extra);
+            assert_eq!(
+                result.is_ok(),
+                expected_ok,
+                "iteration {}: members {:?}, prefix {:?}, uploaded {:?}, dependencies {:?}, climbs extra {}: {:?}",
+                iteration,
+                members,
+                prefix,
+                uploaded,
+                dependencies,
+                climbs_extra,
+                result
+            );
+            if let Err(e) = result {
+                assert!(e.to_string().starts_with("VER-024"));
+            }
+        }
+    }
+}
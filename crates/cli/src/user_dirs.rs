@@ -0,0 +1,403 @@
+use std::{collections::HashMap, env, fs};
+
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::payload::{sha256_hex, DISPATCH_CACHE_FILE};
+use crate::project_state::{LEGACY_STATE_DIR, LOCK_FILE};
+
+const APP_NAME: &str = "voyager";
+/// Environment variables in dotenv syntax, loaded after the project's `.env`.
+pub const CONFIG_FILE: &str = "config.env";
+/// Rules of each network, next to the config file, see `policy.rs`.
+pub const POLICY_FILE: &str = "policy.toml";
+const PROJECTS_DIR: &str = "projects";
+const PAYLOADS_DIR: &str = "payloads";
+/// Ledger of the temporary staging directories, see `staging.rs`.
+const STAGING_DIR: &str = "staging";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    MacOs,
+    Windows,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        match env::consts::OS {
+            "macos" => Platform::MacOs,
+            "windows" => Platform::Windows,
+            // Other unixes follow the XDG conventions as well
+            _ => Platform::Linux,
+        }
+    }
+}
+
+/**
+ * What directory resolution depends on, injected so that every platform can
+ * be tested from any host.
+ */
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub platform: Platform,
+    pub vars: HashMap<String, String>,
+}
+
+impl Environment {
+    pub fn current() -> Self {
+        Self {
+            platform: Platform::current(),
+            vars: env::vars().collect(),
+        }
+    }
+
+    /// Unset and empty variables are treated alike.
+    fn var(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// XDG base directories must be absolute, relative ones are ignored.
+    fn xdg_var(&self, name: &str) -> Option<Utf8PathBuf> {
+        self.var(name)
+            .map(Utf8PathBuf::from)
+            .filter(|path| path.is_absolute())
+    }
+
+    fn home(&self) -> Result<Utf8PathBuf> {
+        let name = match self.platform {
+            Platform::Windows => "USERPROFILE",
+            Platform::Linux | Platform::MacOs => "HOME",
+        };
+        self.var(name).map(Utf8PathBuf::from).ok_or_else(|| {
+            anyhow!(
+                "Unable to locate the home directory, {} is not set. Pass --config and --cache-dir instead.",
+                name
+            )
+        })
+    }
+}
+
+/**
+ * Explicit locations, taking precedence over `VOYAGER_CONFIG` and
+ * `VOYAGER_CACHE_DIR`, which take precedence over the platform conventions.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub config: Option<Utf8PathBuf>,
+    pub cache_dir: Option<Utf8PathBuf>,
+}
+
+/**
+ * User-level locations of the verifier, following the platform conventions:
+ * XDG base directories on Linux, `~/Library` on macOS, and `AppData` on Windows.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDirs {
+    pub config_file: Utf8PathBuf,
+    /// Whether the config file was given explicitly, and so must exist.
+    pub config_required: bool,
+    pub cache_dir: Utf8PathBuf,
+    pub state_dir: Utf8PathBuf,
+}
+
+impl UserDirs {
+    pub fn resolve(environment: &Environment, overrides: &Overrides) -> Result<Self> {
+        let explicit_config = overrides
+            .config
+            .clone()
+            .or_else(|| environment.var("VOYAGER_CONFIG").map(Utf8PathBuf::from));
+        let explicit_cache_dir = overrides
+            .cache_dir
+            .clone()
+            .or_else(|| environment.var("VOYAGER_CACHE_DIR").map(Utf8PathBuf::from));
+
+        // The home directory is only needed for what isn't given explicitly
+        let home = || environment.home();
+        let (config_dir, cache_dir, state_dir) = match environment.platform {
+            Platform::Linux => {
+                let base = |var: &str, default: &str| -> Result<Utf8PathBuf> {
+                    match environment.xdg_var(var) {
+                        Some(dir) => Ok(dir),
+                        None => Ok(home()?.join(default)),
+                    }
+                };
+                (
+                    base("XDG_CONFIG_HOME", ".config"),
+                    base("XDG_CACHE_HOME", ".cache"),
+                    base("XDG_STATE_HOME", ".local/state"),
+                )
+            }
+            Platform::MacOs => {
+                let library = |dir: &str| home().map(|home| home.join("Library").join(dir));
+                (
+                    library("Application Support"),
+                    library("Caches"),
+                    library("Application Support"),
+                )
+            }
+            Platform::Windows => {
+                let app_data = |var: &str, default: &str| match environment.var(var) {
+                    Some(dir) => Ok(Utf8PathBuf::from(dir)),
+                    None => home().map(|home| home.join("AppData").join(default)),
+                };
+                (
+                    app_data("APPDATA", "Roaming").map(|dir| dir.join(APP_NAME).join("config")),
+                    app_data("LOCALAPPDATA", "Local").map(|dir| dir.join(APP_NAME).join("cache")),
+                    app_data("LOCALAPPDATA", "Local").map(|dir| dir.join(APP_NAME).join("state")),
+                )
+            }
+        };
+        let app_dir = |dir: Result<Utf8PathBuf>| match environment.platform {
+            Platform::Windows => dir,
+            Platform::Linux | Platform::MacOs => dir.map(|dir| dir.join(APP_NAME)),
+        };
+
+        let cache_dir = match explicit_cache_dir {
+            Some(cache_dir) => cache_dir,
+            None => app_dir(cache_dir)?,
+        };
+        Ok(Self {
+            config_required: explicit_config.is_some(),
+            config_file: match explicit_config {
+                Some(config) => config,
+                None => app_dir(config_dir)?.join(CONFIG_FILE),
+            },
+            // Without a home directory, state is kept next to the explicit cache
+            state_dir: app_dir(state_dir).unwrap_or_else(|_| cache_dir.join("state")),
+            cache_dir,
+        })
+    }
+
+    /**
+     * Cache directory of one project, named after the project directory and a
+     * hash of its absolute path so that projects sharing a name don't collide.
+     */
+    pub fn project_cache_dir(&self, project_dir: &Utf8Path) -> Utf8PathBuf {
+        let absolute = project_dir
+            .canonicalize_utf8()
+            .unwrap_or_else(|_| project_dir.to_owned());
+        let name = absolute.file_name().unwrap_or("project");
+        let hash = sha256_hex(absolute.as_str().as_bytes());
+        self.cache_dir
+            .join(PROJECTS_DIR)
+            .join(format!("{}-{}", name, &hash[..16]))
+    }
+
+    /**
+     * Policy file of the user, next to the config file.
+     */
+    pub fn policy_file(&self) -> Utf8PathBuf {
+        self.config_file.with_file_name(POLICY_FILE)
+    }
+
+    /**
+     * Directory of the payload cache, shared by all projects.
+     */
+    pub fn payload_cache_dir(&self) -> Utf8PathBuf {
+        self.cache_dir.join(PAYLOADS_DIR)
+    }
+
+    /**
+     * Directory of the ledger of the staging directories, shared by all projects.
+     */
+    pub fn staging_dir(&self) -> Utf8PathBuf {
+        self.cache_dir.join(STAGING_DIR)
+    }
+
+    /**
+     * Load the user-level config file into the environment. Variables that are
+     * already set, including those from the project's `.env`, are kept.
+     */
+    pub fn load_config(&self) -> Result<()> {
+        if !self.config_required && !self.config_file.is_file() {
+            return Ok(());
+        }
+        dotenv::from_path(&self.config_file)
+            .with_context(|| format!("Unable to load the config file {}", self.config_file))
+    }
+}
+
+/**
+ * Move the dispatch cache of versions that kept it in `.voyager` inside of the
+ * project. Returns the previous location when something was moved, which only
+ * happens once per project.
+ */
+pub fn migrate_legacy_state(
+    project_dir: &Utf8Path,
+    project_cache_dir: &Utf8Path,
+) -> Result<Option<Utf8PathBuf>> {
+    let legacy_dir = project_dir.join(LEGACY_STATE_DIR);
+    let legacy_cache = legacy_dir.join(DISPATCH_CACHE_FILE);
+    let cache = project_cache_dir.join(DISPATCH_CACHE_FILE);
+    if !legacy_cache.is_file() || cache.exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(project_cache_dir)
+        .with_context(|| format!("Unable to create the cache directory {}", project_cache_dir))?;
+    // Renaming fails across filesystems, the cache usually isn't on the same one
+    if fs::rename(&legacy_cache, &cache).is_err() {
+        fs::copy(&legacy_cache, &cache)
+            .with_context(|| format!("Unable to move {} to {}", legacy_cache, cache))?;
+        fs::remove_file(&legacy_cache)?;
+    }
+    let _ = fs::remove_file(legacy_dir.join(LOCK_FILE));
+    // Only succeeds when nothing else was kept in there
+    let _ = fs::remove_dir(&legacy_dir);
+    Ok(Some(legacy_cache))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(platform: Platform, vars: &[(&str, &str)]) -> Environment {
+        Environment {
+            platform,
+            vars: vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn resolve(platform: Platform, vars: &[(&str, &str)]) -> UserDirs {
+        UserDirs::resolve(&environment(platform, vars), &Overrides::default()).unwrap()
+    }
+
+    #[test]
+    fn test_linux_follows_xdg() {
+        let dirs = resolve(Platform::Linux, &[("HOME", "/home/alice")]);
+        assert_eq!(dirs.config_file, "/home/alice/.config/voyager/config.env");
+        assert_eq!(
+            dirs.policy_file(),
+            "/home/alice/.config/voyager/policy.toml"
+        );
+        assert_eq!(dirs.cache_dir, "/home/alice/.cache/voyager");
+        assert_eq!(dirs.state_dir, "/home/alice/.local/state/voyager");
+        assert!(!dirs.config_required);
+
+        let dirs = resolve(
+            Platform::Linux,
+            &[
+                ("HOME", "/home/alice"),
+                ("XDG_CONFIG_HOME", "/etc/xdg"),
+                ("XDG_CACHE_HOME", "relative/cache"),
+                ("XDG_STATE_HOME", ""),
+            ],
+        );
+        assert_eq!(dirs.config_file, "/etc/xdg/voyager/config.env");
+        assert_eq!(dirs.cache_dir, "/home/alice/.cache/voyager");
+        assert_eq!(dirs.state_dir, "/home/alice/.local/state/voyager");
+    }
+
+    #[test]
+    fn test_macos_uses_library() {
+        let dirs = resolve(
+            Platform::MacOs,
+            &[("HOME", "/Users/alice"), ("XDG_CACHE_HOME", "/tmp/xdg")],
+        );
+        assert_eq!(
+            dirs.config_file,
+            "/Users/alice/Library/Application Support/voyager/config.env"
+        );
+        assert_eq!(dirs.cache_dir, "/Users/alice/Library/Caches/voyager");
+        assert_eq!(
+            dirs.state_dir,
+            "/Users/alice/Library/Application Support/voyager"
+        );
+    }
+
+    #[test]
+    fn test_windows_uses_app_data() {
+        let app_data = Utf8PathBuf::from(r"C:\Users\alice\AppData\Roaming");
+        let local_app_data = Utf8PathBuf::from(r"C:\Users\alice\AppData\Local");
+        let dirs = resolve(
+            Platform::Windows,
+            &[
+                ("APPDATA", app_data.as_str()),
+                ("LOCALAPPDATA", local_app_data.as_str()),
+            ],
+        );
+        assert_eq!(
+            dirs.config_file,
+            app_data.join("voyager").join("config").join(CONFIG_FILE)
+        );
+        assert_eq!(dirs.cache_dir, local_app_data.join("voyager").join("cache"));
+        assert_eq!(dirs.state_dir, local_app_data.join("voyager").join("state"));
+
+        // Falls back to the profile when the AppData variables are missing
+        let profile = Utf8PathBuf::from(r"C:\Users\alice");
+        let dirs = resolve(Platform::Windows, &[("USERPROFILE", profile.as_str())]);
+        assert_eq!(
+            dirs.cache_dir,
+            profile
+                .join("AppData")
+                .join("Local")
+                .join("voyager")
+                .join("cache")
+        );
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let vars = [
+            ("HOME", "/home/alice"),
+            ("VOYAGER_CONFIG", "/etc/voyager.env"),
+            ("VOYAGER_CACHE_DIR", "/var/cache/voyager"),
+        ];
+        let dirs = resolve(Platform::Linux, &vars);
+        assert_eq!(dirs.config_file, "/etc/voyager.env");
+        assert_eq!(dirs.cache_dir, "/var/cache/voyager");
+        assert!(dirs.config_required);
+
+        let overrides = Overrides {
+            config: Some("ci.env".into()),
+            cache_dir: Some("/tmp/cache".into()),
+        };
+        let dirs = UserDirs::resolve(&environment(Platform::Linux, &vars), &overrides).unwrap();
+        assert_eq!(dirs.config_file, "ci.env");
+        assert_eq!(dirs.cache_dir, "/tmp/cache");
+    }
+
+    #[test]
+    fn test_missing_home_is_reported() {
+        let error = UserDirs::resolve(&environment(Platform::Linux, &[]), &Overrides::default())
+            .unwrap_err();
+        assert!(error.to_string().contains("HOME is not set"));
+
+        let overrides = Overrides {
+            config: Some("/etc/voyager.env".into()),
+            cache_dir: Some("/var/cache/voyager".into()),
+        };
+        let dirs = UserDirs::resolve(&environment(Platform::Linux, &[]), &overrides).unwrap();
+        assert_eq!(dirs.state_dir, "/var/cache/voyager/state");
+    }
+
+    #[test]
+    fn test_legacy_state_is_migrated_once() {
+        let root = Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("voyager-migration-{}", std::process::id())),
+        )
+        .unwrap();
+        let _ = fs::remove_dir_all(&root);
+        let project = root.join("project");
+        let legacy_dir = project.join(LEGACY_STATE_DIR);
+        fs::create_dir_all(&legacy_dir).unwrap();
+        fs::write(legacy_dir.join(DISPATCH_CACHE_FILE), r#"{"entries": []}"#).unwrap();
+        fs::write(legacy_dir.join(LOCK_FILE), "").unwrap();
+
+        let cache_dir = root.join("cache");
+        let moved = migrate_legacy_state(&project, &cache_dir).unwrap();
+        assert_eq!(moved, Some(legacy_dir.join(DISPATCH_CACHE_FILE)));
+        assert!(cache_dir.join(DISPATCH_CACHE_FILE).is_file());
+        assert!(!legacy_dir.exists());
+
+        assert_eq!(migrate_legacy_state(&project, &cache_dir).unwrap(), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
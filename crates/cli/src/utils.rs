@@ -1,9 +1,17 @@
+use anyhow::{Context, Result};
+use camino::Utf8PathBuf;
 use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
-use std::process::Command;
+use semver::{Version, VersionReq};
+use std::{fs, process::Command, str::FromStr};
+
+use crate::errors::VerifierError;
 
 const SCARB_VERSION_OUTPUT_LINES: usize = 3;
 
-pub fn detect_local_tools() -> (SupportedScarbVersions, SupportedCairoVersions) {
+/**
+ * Scarb and Cairo versions reported by the local `scarb --version`, as printed.
+ */
+pub fn detect_local_versions() -> (String, String) {
     let versioning = Command::new("scarb").arg("--version").output().expect(
         "
             Unable to detect local Scarb installation. 
@@ -27,19 +35,210 @@ pub fn detect_local_tools() -> (SupportedScarbVersions, SupportedCairoVersions)
         .split(" ")
         .collect::<Vec<&str>>()[1];
 
-    let scarb_version = match scarb_version {
-        "2.8.4" => SupportedScarbVersions::V2_8_4,
-        _ => panic!("Unsupported Scarb version: {}", scarb_version),
-    };
+    (scarb_version.to_owned(), cairo_version.to_owned())
+}
+
+/**
+ * The local Scarb toolchain, failing with `UnsupportedToolVersion` when this
+ * client can't verify with it.
+ */
+pub fn detect_local_tools() -> Result<(SupportedScarbVersions, SupportedCairoVersions)> {
+    let (scarb_version, cairo_version) = detect_local_versions();
+    Ok((
+        parse_tool_version("Scarb", &scarb_version, SupportedScarbVersions::ALL)?,
+        parse_tool_version("Cairo", &cairo_version, SupportedCairoVersions::ALL)?,
+    ))
+}
 
-    let cairo_version = match cairo_version {
-        "2.8.4" => SupportedCairoVersions::V2_8_4,
-        _ => {
-            println!("Unsupported Cairo version {}. We thus do not guarantee compatibility and compilation might fail as a result.", cairo_version);
-            // Use latest Scarb version as default.
-            SupportedCairoVersions::V2_8_4
+/**
+ * The toolchain to verify the package in `project_dir` with: the local one,
+ * unless the `cairo-version` its Scarb.toml requires excludes it, in which
+ * case the newest supported release satisfying the requirement.
+ */
+pub fn detect_project_tools(
+    project_dir: &Utf8PathBuf,
+) -> Result<(SupportedScarbVersions, SupportedCairoVersions)> {
+    let requirement = cairo_version_requirement(project_dir)?;
+    select_tools(requirement.as_ref(), detect_local_tools())
+}
+
+fn parse_tool_version<T: FromStr + ToString>(tool: &str, version: &str, all: &[T]) -> Result<T> {
+    version.parse().map_err(|_| {
+        VerifierError::UnsupportedToolVersion {
+            tool: tool.to_owned(),
+            version: version.to_owned(),
+            supported: all.iter().map(ToString::to_string).collect(),
+        }
+        .into()
+    })
+}
+
+/**
+ * The `cairo-version` of the package in `project_dir`, `None` when it has
+ * none or inherits it from the workspace.
+ */
+fn cairo_version_requirement(project_dir: &Utf8PathBuf) -> Result<Option<VersionReq>> {
+    let manifest_path = project_dir.join("Scarb.toml");
+    let contents = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path))?;
+    let manifest: toml::Value = contents.parse()?;
+    manifest
+        .get("package")
+        .and_then(|package| package.get("cairo-version"))
+        .and_then(toml::Value::as_str)
+        .map(|requirement| {
+            VersionReq::parse(requirement).with_context(|| {
+                format!(
+                    "Invalid cairo-version \"{}\" in {}",
+                    requirement, manifest_path
+                )
+            })
+        })
+        .transpose()
+}
+
+fn satisfies(requirement: &VersionReq, cairo: SupportedCairoVersions) -> bool {
+    let (major, minor, patch) = cairo.version();
+    requirement.matches(&Version::new(major.into(), minor.into(), patch.into()))
+}
+
+fn select_tools(
+    requirement: Option<&VersionReq>,
+    local: Result<(SupportedScarbVersions, SupportedCairoVersions)>,
+) -> Result<(SupportedScarbVersions, SupportedCairoVersions)> {
+    let Some(requirement) = requirement else {
+        return local;
+    };
+    if let Ok((scarb, cairo)) = &local {
+        if satisfies(requirement, *cairo) {
+            return Ok((*scarb, *cairo));
+        }
+    }
+    let Some(scarb) = SupportedScarbVersions::ALL
+        .iter()
+        .rev()
+        .find(|scarb| satisfies(requirement, scarb.cairo_version()))
+    else {
+        return Err(VerifierError::UnsupportedToolVersion {
+            tool: "Cairo".to_owned(),
+            version: format!("{} (the cairo-version of Scarb.toml)", requirement),
+            supported: SupportedCairoVersions::ALL
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
         }
+        .into());
     };
+    let local = match local {
+        Ok((_, cairo)) => format!("The local Cairo {}", cairo.to_string()),
+        Err(_) => "The local toolchain".to_owned(),
+    };
+    println!(
+        "{} doesn't satisfy the cairo-version {} of Scarb.toml, verifying with Scarb {} and Cairo {}.",
+        local,
+        requirement,
+        scarb.to_string(),
+        scarb.cairo_version().to_string()
+    );
+    Ok((*scarb, scarb.cairo_version()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+
+    fn requirement(requirement: &str) -> VersionReq {
+        VersionReq::parse(requirement).unwrap()
+    }
+
+    #[test]
+    fn test_local_toolchain_is_kept_when_it_satisfies_the_manifest() {
+        let local = (
+            SupportedScarbVersions::V2_6_4,
+            SupportedCairoVersions::V2_6_3,
+        );
+        assert_eq!(select_tools(None, Ok(local)).unwrap(), local);
+        assert_eq!(
+            select_tools(Some(&requirement("2.6.0")), Ok(local)).unwrap(),
+            local
+        );
+    }
+
+    #[test]
+    fn test_newest_release_satisfying_the_manifest_is_picked() {
+        let local = Ok((
+            SupportedScarbVersions::V2_8_4,
+            SupportedCairoVersions::V2_8_4,
+        ));
+        assert_eq!(
+            select_tools(Some(&requirement("~2.6")), local).unwrap(),
+            (
+                SupportedScarbVersions::V2_6_5,
+                SupportedCairoVersions::V2_6_4
+            )
+        );
 
-    (scarb_version, cairo_version)
+        let unsupported_local = parse_tool_version("Scarb", "2.9.1", SupportedScarbVersions::ALL)
+            .map(|scarb: SupportedScarbVersions| (scarb, scarb.cairo_version()));
+        assert_eq!(
+            select_tools(Some(&requirement("=2.5.2")), unsupported_local).unwrap(),
+            (
+                SupportedScarbVersions::V2_5_2,
+                SupportedCairoVersions::V2_5_2
+            )
+        );
+    }
+
+    #[test]
+    fn test_unsupported_versions_name_the_supported_ones() {
+        let error = parse_tool_version("Scarb", "2.9.1", SupportedScarbVersions::ALL).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-048"));
+        assert!(error.to_string().contains("Scarb 2.9.1 isn't supported"));
+        assert!(error.to_string().contains("2.5.0, 2.5.1"));
+
+        let local = Ok((
+            SupportedScarbVersions::V2_8_4,
+            SupportedCairoVersions::V2_8_4,
+        ));
+        let error = select_tools(Some(&requirement(">=2.9")), local).unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-048"));
+        assert!(error
+            .to_string()
+            .contains(">=2.9 (the cairo-version of Scarb.toml)"));
+        assert!(error.to_string().contains("2.8.4"));
+    }
+
+    #[test]
+    fn test_cairo_version_requirement_of_the_manifest() {
+        let dir = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("voyager-cairo-version-{}", std::process::id())),
+        )
+        .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("Scarb.toml"),
+            "[package]\nname = \"token\"\nversion = \"0.1.0\"\ncairo-version = \"2.6.3\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            cairo_version_requirement(&dir).unwrap(),
+            Some(requirement("^2.6.3"))
+        );
+
+        fs::write(
+            dir.join("Scarb.toml"),
+            "[package]\nname = \"token\"\nversion = \"0.1.0\"\n\n[package.cairo-version]\nworkspace = true\n",
+        )
+        .unwrap();
+        assert_eq!(cairo_version_requirement(&dir).unwrap(), None);
+
+        fs::write(
+            dir.join("Scarb.toml"),
+            "[package]\nname = \"token\"\nversion = \"0.1.0\"\ncairo-version = \"two\"\n",
+        )
+        .unwrap();
+        assert!(cairo_version_requirement(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
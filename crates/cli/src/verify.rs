@@ -1,31 +1,65 @@
-use std::{env::current_dir, str::FromStr};
+use std::{
+    env::current_dir,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{arg, Args};
+use console::{style, Emoji};
 
 use dyn_compiler::dyn_compiler::SupportedCairoVersions;
 
 use crate::{
     api::{
-        dispatch_class_verification_job, poll_verification_status, FileInfo, Network,
-        ProjectMetadataInfo,
+        class_code_url, JobMetadata, Network, PollUpdate, ProjectMetadataInfo, VerificationJob,
+        VerifyJobStatus,
+    },
+    cancel::CancelToken,
+    class_hash::ClassHash,
+    client::{ApiClient, DispatchRequest, VerifierClient},
+    dialect::Dialect,
+    display::sanitize,
+    errors::{self, error_code, VerifierError},
+    events::{Event, EventStream},
+    form::{form_metadata_fields, supports_aliases, supports_plugins, FormStyle, DOC_FIELD_PREFIX},
+    hooks::{
+        run_post_hook, Hooks, CLASS_HASH_VAR, ERROR_VAR, JOB_ID_VAR, NETWORK_VAR, POST_FAILURE,
+        POST_SUCCESS, REPORT_VAR, STATUS_VAR,
     },
+    http_retry,
+    json_output::{DispatchedJob, JsonOutput},
     license::LicenseType,
+    locked::Reproducibility,
+    outcome::{OutcomeDuration, VerificationOutcome},
+    payload::{
+        compute_payload_digest, unix_now, CacheLookup, DispatchCache, DispatchCacheEntry,
+        SourceRevision,
+    },
+    payload_cache::PayloadCache,
+    polling::{PollingConfig, StatusTracker},
+    progress::PollProgress,
+    provenance::ConfigSnapshot,
+    report::{append_to_report, HashCheck, ReportEntry, StatusHistory, Submission},
     resolver::get_dynamic_compiler,
+    resubmit::{with_resubmission, TransientFailures},
+    sources::SourceSet,
+    status_file::{spawn_waiter, StatusFile},
+    timing, transfer,
+    verified_metadata::MetadataChange,
 };
 
 #[derive(Args, Debug)]
 pub struct VerifyProjectArgs {
     #[arg(
         help = "Network to verify against",
-        default_value_t=String::from("mainnet"),
+        default_value_t = Network::Mainnet,
         required = true
     )]
-    pub network: String,
+    pub network: Network,
 
     #[arg(help = "Class hash to verify", required = true)]
-    pub hash: String,
+    pub hash: ClassHash,
 
     #[arg(help = "license type", required = true)]
     pub license: LicenseType,
@@ -36,8 +70,140 @@ pub struct VerifyProjectArgs {
     #[arg(help = "Source directory", required = true)]
     pub path: Utf8PathBuf,
 
-    #[arg(long, help = "Max retries")]
-    pub max_retries: Option<u32>,
+    #[arg(long, help = "Record the verification progress in a JSON status file")]
+    pub status_file: Option<Utf8PathBuf>,
+
+    #[arg(
+        long,
+        requires = "status_file",
+        help = "Exit right after dispatching and keep polling in a background waiter"
+    )]
+    pub daemonless_wait: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "daemonless_wait",
+        help = "Exit right after dispatching, printing only the job id on stdout"
+    )]
+    pub no_wait: bool,
+
+    #[arg(
+        long,
+        help = "Always submit a new verification job, even if the payload is unchanged"
+    )]
+    pub no_cache: bool,
+
+    #[arg(
+        long,
+        help = "Submit the payload even if a job already failed with the exact same one"
+    )]
+    pub resubmit_identical: bool,
+
+    #[arg(long, help = "Append the verification result to a JSONL report file")]
+    pub report: Option<Utf8PathBuf>,
+
+    #[arg(long, help = "Never read or write the dispatch cache of the project")]
+    pub no_project_state: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FormStyle::Parts,
+        help = "How files are laid out in the form, legacy for older self-hosted backends"
+    )]
+    pub form_style: FormStyle,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Dialect::Voyager,
+        help = "Names of the form fields, for self-hosted backends that renamed them"
+    )]
+    pub dialect: Dialect,
+
+    #[arg(
+        long,
+        help = "Send the same bytes for the same sources, with a boundary derived from their digest"
+    )]
+    pub deterministic_body: bool,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Resubmit up to this many times when the job fails for a known transient backend reason"
+    )]
+    pub auto_resubmit: u32,
+
+    #[arg(
+        long = "alias",
+        value_name = "NAME",
+        help = "Additional name the class is displayed under, can be repeated"
+    )]
+    pub aliases: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Drop the aliases with a warning instead of failing when the backend doesn't support them"
+    )]
+    pub aliases_best_effort: bool,
+
+    #[arg(
+        long,
+        value_name = "HASH",
+        help = "Fail with exit code 3 unless the verified class hash matches HASH"
+    )]
+    pub expect_hash: Option<ClassHash>,
+
+    /// Whether the run is --locked or --frozen, recorded in the report.
+    #[arg(skip)]
+    pub reproducibility: Reproducibility,
+
+    /// Effective configuration of the run, recorded in the report.
+    #[arg(skip)]
+    pub config: Option<ConfigSnapshot>,
+
+    #[arg(skip)]
+    pub metadata_changes: Vec<MetadataChange>,
+
+    /// Commit and package version of the sources, recorded in the dispatch cache.
+    #[arg(skip)]
+    pub revision: SourceRevision,
+
+    /// Job already in progress for the class, polled instead of dispatching.
+    #[arg(skip)]
+    pub attach_job_id: Option<String>,
+
+    /// Post hooks of the project, empty unless they are allowed to run.
+    #[arg(skip)]
+    pub hooks: Hooks,
+
+    /// Where submitted payloads are kept, when enabled with --payload-cache.
+    #[arg(skip)]
+    pub payload_cache: Option<PayloadCache>,
+
+    /// How often and how long the job is polled.
+    #[arg(skip)]
+    pub polling: PollingConfig,
+
+    /// JSONL event stream, when enabled with --events.
+    #[arg(skip)]
+    pub events: Option<EventStream>,
+
+    /// Where the job and its result are printed, when enabled with --json.
+    #[arg(skip)]
+    pub json_output: Option<JsonOutput>,
+
+    /// Status changes shown while polling, left out with --quiet.
+    #[arg(skip)]
+    pub progress: Option<PollProgress>,
+
+    /// Cache directory of the project, holding the dispatch cache.
+    #[arg(skip)]
+    pub cache_dir: Utf8PathBuf,
+
+    /// Cancelled on Ctrl-C, see `interrupt`.
+    #[arg(skip)]
+    pub cancel: CancelToken,
 
     pub api_key: String,
 }
@@ -48,47 +214,733 @@ pub struct VerifyFileArgs {
     path: Utf8PathBuf,
 }
 
-pub fn verify_project(
+/**
+ * Submit `files` and `docs` for verification and wait for the result. The
+ * sources don't need to exist on disk, see `SourceSet`. Fails only when the
+ * verification can't start, a failed verification is an outcome with its
+ * error, see `VerificationOutcome`.
+ */
+pub fn verify_file_set(
     args: VerifyProjectArgs,
     metadata: ProjectMetadataInfo,
-    files: Vec<FileInfo>,
-) -> Result<()> {
-    let network_enum = Network::from_str(args.network.as_str())?;
-
-    let dispatch_response = dispatch_class_verification_job(
-        args.api_key.as_str(),
-        network_enum.clone(),
-        &args.hash,
-        args.license.to_long_string().as_str(),
-        &args.name,
-        metadata,
-        files,
-    );
+    files: SourceSet,
+    docs: SourceSet,
+) -> Result<VerificationOutcome> {
+    let client = ApiClient::new(
+        args.api_key.clone(),
+        args.network.clone(),
+        args.cancel.clone(),
+    )?;
+    verify_with_client(&client, args, metadata, files, docs)
+}
 
-    let job_id = match dispatch_response {
-        Ok(response) => response,
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to dispatch verification job: {}",
-                e
-            ));
+/**
+ * `verify_file_set` against any backend, see `testing::FakeVerifier`.
+ */
+pub fn verify_with_client(
+    client: &dyn VerifierClient,
+    args: VerifyProjectArgs,
+    metadata: ProjectMetadataInfo,
+    files: SourceSet,
+    docs: SourceSet,
+) -> Result<VerificationOutcome> {
+    let network_enum = args.network.clone();
+    let license = args.license.to_long_string();
+    let report_entry = ReportEntry {
+        network: network_enum.to_string(),
+        class_hash: args.hash.to_string(),
+        metadata_changes: args.metadata_changes.clone(),
+        docs: docs.names().map(str::to_owned).collect(),
+        reproducibility: args.reproducibility,
+        config: args.config.clone(),
+        ..Default::default()
+    };
+
+    let submitted = JobMetadata {
+        name: Some(args.name.clone()),
+        license: Some(license.clone()),
+        contract_file: Some(metadata.contract_file.clone()),
+        version: Some(metadata.cairo_version.to_string()),
+    };
+
+    // Documentation is part of the payload, under its own namespace so that a
+    // doc can't be mistaken for a source file of the same name
+    let mut payload_files = files.clone();
+    for (name, contents) in docs.iter() {
+        payload_files.add(format!("{}{}", DOC_FIELD_PREFIX, name), contents)?;
+    }
+    let payload = compute_payload_digest(
+        &form_metadata_fields(
+            &license,
+            &args.name,
+            &args.aliases,
+            &metadata,
+            !docs.is_empty(),
+            args.dialect,
+        ),
+        &payload_files,
+    );
+    let cache = if args.no_project_state {
+        DispatchCache::default()
+    } else {
+        DispatchCache::load(&args.cache_dir)
+    };
+    let transient_failures = TransientFailures::from_env();
+    if args.attach_job_id.is_none() && !args.resubmit_identical {
+        let previous = cache.previous_failure(
+            &network_enum.to_string(),
+            args.hash.as_str(),
+            &payload,
+            &transient_failures,
+        );
+        if let Some(entry) = previous {
+            return Err(VerifierError::IdenticalPayloadFailed {
+                job_id: entry.job_id.clone(),
+                status: entry.status.clone().unwrap_or_default(),
+                description: entry.description.clone(),
+            }
+            .into());
         }
+    }
+    let mut attached_job_id = match &args.attach_job_id {
+        Some(job_id) => Some(job_id.clone()),
+        None if args.no_cache => None,
+        None => match cache.lookup(&network_enum.to_string(), args.hash.as_str(), &payload) {
+            CacheLookup::Hit(entry) => {
+                println!(
+                    "Payload unchanged since job {} (same file paths, form fields and file contents), attaching to it instead of resubmitting.",
+                    entry.job_id
+                );
+                Some(entry.job_id.clone())
+            }
+            CacheLookup::Miss(Some((entry, changed))) if !changed.is_empty() => {
+                println!(
+                    "Payload changed since job {} ({}), submitting a new verification job.",
+                    entry.job_id,
+                    changed.join(", ")
+                );
+                None
+            }
+            CacheLookup::Miss(_) => None,
+        },
     };
 
-    // Retry for 5 minutes
-    let poll_result = poll_verification_status(
-        args.api_key.as_str(),
-        network_enum,
-        &job_id,
-        args.max_retries.unwrap_or(180),
+    // Dropped for good once the backend rejects them, see --aliases-best-effort
+    let mut sent_aliases = args.aliases.clone();
+    // Plugins are left out for good once the backend rejects them
+    let mut sent_metadata = metadata.clone();
+    // Switched for good once the backend lists the styles it accepts
+    let mut form_style = args.form_style;
+    // Of the last attempt, for the post hooks and the outcome
+    let mut last_job_id = None;
+    let mut last_status = None;
+    let mut last_terminal_status = None;
+    let mut last_remote_elapsed = None;
+    let mut last_history = vec![];
+    let mut upload = Duration::ZERO;
+    let mut warnings = vec![];
+    let verification_started = Instant::now();
+    let mut notify = |attempt: u32, error: &anyhow::Error| {
+        println!(
+            "{} {}",
+            Emoji("🔁", ""),
+            style(format!(
+                "Attempt {} failed with a transient backend error ({:#}), resubmitting ({}/{})",
+                attempt, error, attempt, args.auto_resubmit
+            ))
+            .yellow()
+        );
+    };
+    let run_attempt = |attempt: u32| {
+        let started = Instant::now();
+        last_job_id = None;
+        last_status = None;
+        last_terminal_status = None;
+        last_remote_elapsed = None;
+        last_history = vec![];
+        upload = Duration::ZERO;
+        let (job_id, submission) = match attached_job_id.take() {
+            Some(job_id) => (job_id, Submission::Attached),
+            None => {
+                let upload_started = Instant::now();
+                let dispatch =
+                    |metadata: &ProjectMetadataInfo, aliases: &[String], form_style: FormStyle| {
+                        timing::phase("upload", || {
+                            client.dispatch(&DispatchRequest {
+                                class_hash: &args.hash,
+                                license: license.as_str(),
+                                name: &args.name,
+                                aliases,
+                                metadata,
+                                files: &files,
+                                docs: &docs,
+                                form_style,
+                                dialect: args.dialect,
+                                deterministic_body: args.deterministic_body,
+                            })
+                        })
+                    };
+                let mut dispatch_response = dispatch(&sent_metadata, &sent_aliases, form_style);
+                if let Some(supported) = negotiate_form_style(&dispatch_response)
+                    .filter(|supported| *supported != form_style)
+                {
+                    warn(
+                            &mut warnings,
+                            format!(
+                                "The verification server doesn't accept the {} form style, submitting with the {} style.",
+                                form_style, supported
+                            ),
+                        );
+                    form_style = supported;
+                    dispatch_response = dispatch(&sent_metadata, &sent_aliases, form_style);
+                }
+                if !sent_metadata.plugins.is_empty() && rejects_plugins(&dispatch_response) {
+                    let names: Vec<&str> = sent_metadata
+                        .plugins
+                        .iter()
+                        .map(|plugin| plugin.name.as_str())
+                        .collect();
+                    warn(
+                            &mut warnings,
+                            format!(
+                                "The verification server doesn't accept compiler plugin information, remote builds with {} may not be supported.",
+                                names.join(", ")
+                            ),
+                        );
+                    sent_metadata.plugins.clear();
+                    dispatch_response = dispatch(&sent_metadata, &sent_aliases, form_style);
+                }
+                if args.aliases_best_effort
+                    && !sent_aliases.is_empty()
+                    && rejects_aliases(&dispatch_response)
+                {
+                    warn(
+                            &mut warnings,
+                            format!(
+                                "The verification server doesn't support aliases, submitting without {}.",
+                                sent_aliases.join(", ")
+                            ),
+                        );
+                    sent_aliases.clear();
+                    dispatch_response = dispatch(&sent_metadata, &sent_aliases, form_style);
+                }
+                upload = upload_started.elapsed();
+
+                let job_id = match dispatch_response {
+                    Ok(response) => response,
+                    Err(e) => {
+                        let error =
+                            anyhow::Error::from(e).context("Failed to dispatch verification job");
+                        if let Some(report_path) = &args.report {
+                            let entry = ReportEntry {
+                                error: Some(format!("{:#}", error)),
+                                error_code: error_code(&error).map(str::to_owned),
+                                duration_ms: started.elapsed().as_millis() as u64,
+                                attempt,
+                                aliases: sent_aliases.clone(),
+                                transfer: transfer::global().totals(),
+                                reliability: http_retry::global().stats(),
+                                ..report_entry.clone()
+                            };
+                            append_to_report(report_path, &entry);
+                        }
+                        return Err(error);
+                    }
+                };
+
+                if !args.no_project_state {
+                    let entry = DispatchCacheEntry {
+                        network: network_enum.to_string(),
+                        class_hash: args.hash.to_string(),
+                        payload: payload.clone(),
+                        job_id: job_id.clone(),
+                        status: None,
+                        description: None,
+                        revision: args.revision.clone(),
+                        dispatched_at: Some(unix_now()),
+                        finished_at: None,
+                    };
+                    if let Err(e) =
+                        DispatchCache::update(&args.cache_dir, |cache| cache.record(entry))
+                    {
+                        println!("Unable to update the dispatch cache: {}", e);
+                    }
+                }
+                if let Some(payload_cache) = &args.payload_cache {
+                    if let Err(e) = payload_cache.store(&payload, &payload_files) {
+                        println!("Unable to cache the payload: {:#}", e);
+                    }
+                }
+                (job_id, Submission::Dispatched)
+            }
+        };
+        last_job_id = Some(job_id.clone());
+        if let Some(events) = &args.events {
+            events.emit_retries(http_retry::global(), verification_started);
+            events.emit(Event::Job {
+                job_id: job_id.clone(),
+                attempt,
+                submission,
+                elapsed_ms: verification_started.elapsed().as_millis() as u64,
+            });
+        }
+        if let Some(json_output) = &args.json_output {
+            json_output.emit(&DispatchedJob {
+                job_id: job_id.clone(),
+                class_hash: args.hash.to_string(),
+                network: network_enum.to_string(),
+            });
+        }
+
+        let status = match &args.status_file {
+            Some(path) => {
+                let status = StatusFile {
+                    submission,
+                    ..StatusFile::dispatched(&job_id, &network_enum, args.hash.as_str())
+                };
+                status.write(path)?;
+                Some((path.as_path(), status))
+            }
+            None => None,
+        };
+
+        if args.daemonless_wait {
+            if let Some((path, _)) = status {
+                spawn_waiter(path, &network_enum, &args.polling, &args.api_key)?;
+                return Ok(());
+            }
+        }
+        if args.no_wait {
+            return Ok(());
+        }
+
+        let mut final_status = None;
+        let mut terminal_status = None;
+        let mut status_description = None;
+        let mut history = StatusHistory::default();
+        let mut job_metadata: Option<JobMetadata> = None;
+        let mut remote_hash = None;
+        let mut remote_elapsed = None;
+        let mut tracker = StatusTracker::default();
+        let poll_result = timing::phase("polling", || {
+            poll_with_status_file(
+                client,
+                &job_id,
+                &args.hash,
+                &args.polling,
+                status,
+                &mut |job| {
+                    history.observe(
+                        job.status().to_string(),
+                        started.elapsed().as_millis() as u64,
+                    );
+                    if let Some(events) = &args.events {
+                        events.emit_retries(http_retry::global(), verification_started);
+                        events.emit(Event::Status {
+                            job_id: job_id.clone(),
+                            status: job.status().to_string(),
+                            elapsed_ms: verification_started.elapsed().as_millis() as u64,
+                        });
+                    }
+                    if let Some(progress) = &args.progress {
+                        progress.observe(job.status(), job.status_description(), started.elapsed());
+                    }
+                    status_description = job.status_description().map(str::to_owned);
+                    tracker.observe(job.status());
+                    if job_metadata.is_none() {
+                        let echoed = job.metadata();
+                        report_echoed_metadata(&echoed, &submitted, &mut warnings);
+                        job_metadata = Some(echoed);
+                    }
+                    if !matches!(
+                        job.status(),
+                        VerifyJobStatus::Submitted | VerifyJobStatus::Compiled
+                    ) {
+                        final_status = Some(job.status().to_string());
+                        terminal_status = Some(job.status());
+                        if let Some(json_output) = &args.json_output {
+                            json_output.emit(job);
+                        }
+                    }
+                    if job.status() == VerifyJobStatus::Success {
+                        remote_hash = Some(job.class_hash().to_owned());
+                        remote_elapsed = job.remote_elapsed();
+                    }
+                },
+            )
+        });
+
+        // The job record is gone but the class it verified is
+        let poll_result = match poll_result {
+            Err(e) if is_inferred_success(&e) => {
+                println!("{} {}", Emoji("✅", ""), e.root_cause());
+                final_status = Some(VerifyJobStatus::Success.to_string());
+                terminal_status = Some(VerifyJobStatus::Success);
+                status_description = Some(INFERRED_FROM_CLASS.to_owned());
+                remote_hash = Some(args.hash.to_string());
+                Ok(())
+            }
+            result => result,
+        };
+
+        // A successful job can still be for another class than the release pinned
+        let hash_check = match (&args.expect_hash, remote_hash) {
+            (Some(expected), Some(remote)) if poll_result.is_ok() => {
+                Some(HashCheck::new(expected, &remote))
+            }
+            _ => None,
+        };
+        let poll_result = match &hash_check {
+            Some(check) if !check.matches => Err(VerifierError::HashMismatch {
+                expected: check.expected.clone(),
+                actual: check.remote.clone(),
+            }
+            .into()),
+            Some(check) => {
+                println!(
+                    "Verified class hash matches the expected {}",
+                    check.expected
+                );
+                poll_result
+            }
+            None => poll_result,
+        };
+
+        if let Some(report_path) = &args.report {
+            let entry = ReportEntry {
+                job_id: Some(job_id.clone()),
+                status: final_status.clone(),
+                status_description: status_description.clone(),
+                error: poll_result.as_ref().err().map(|e| format!("{:#}", e)),
+                error_code: poll_result
+                    .as_ref()
+                    .err()
+                    .and_then(error_code)
+                    .map(str::to_owned),
+                duration_ms: started.elapsed().as_millis() as u64,
+                job_metadata: job_metadata.unwrap_or_default(),
+                restarts: tracker.restarts(),
+                attempt,
+                aliases: sent_aliases.clone(),
+                transfer: transfer::global().totals(),
+                reliability: http_retry::global().stats(),
+                submission,
+                hash_check,
+                ..report_entry.clone()
+            }
+            .with_history(&history);
+            append_to_report(report_path, &entry);
+        }
+
+        if let Some(final_status) = final_status.filter(|_| !args.no_project_state) {
+            if let Err(e) = DispatchCache::update(&args.cache_dir, |cache| {
+                cache.set_status(&job_id, final_status, status_description)
+            }) {
+                println!("Unable to update the dispatch cache: {}", e);
+            }
+        }
+
+        last_history = history.to_vec();
+        last_status = last_history.last().map(|observed| observed.status.clone());
+        last_terminal_status = terminal_status;
+        last_remote_elapsed = remote_elapsed;
+        poll_result
+    };
+    let result = with_resubmission(
+        args.auto_resubmit,
+        &transient_failures,
+        &mut notify,
+        run_attempt,
     );
 
+    // Runs exiting before the job finishes leave the hooks out, and so does the waiter
+    if !((args.daemonless_wait || args.no_wait) && result.is_ok()) {
+        run_post_verify_hook(
+            &args,
+            &network_enum,
+            last_job_id.as_deref(),
+            last_status.as_deref(),
+            &result,
+        );
+    }
+    if let Some(events) = args.events {
+        events.emit_retries(http_retry::global(), verification_started);
+        events.emit(Event::Finished {
+            job_id: last_job_id.clone(),
+            error_code: result
+                .as_ref()
+                .err()
+                .and_then(error_code)
+                .map(str::to_owned),
+            elapsed_ms: verification_started.elapsed().as_millis() as u64,
+        });
+        events.close();
+    }
+    let outcome = VerificationOutcome {
+        status: last_terminal_status,
+        job_id: last_job_id,
+        explorer_url: class_code_url(network_enum, args.hash.as_str()),
+        class_hash: args.hash.to_string(),
+        network: network_enum.to_string(),
+        name: args.name.clone(),
+        duration: OutcomeDuration::from_history(
+            verification_started.elapsed(),
+            upload,
+            &last_history,
+        ),
+        remote_elapsed_ms: last_remote_elapsed.map(|elapsed| elapsed.as_millis() as u64),
+        history: last_history,
+        warnings,
+        payload_digest: payload.digest,
+        ..Default::default()
+    }
+    .with_result(result);
+    if let (Some(json_output), Some(summary)) = (&args.json_output, outcome.summary()) {
+        json_output.emit(&summary);
+    }
+    Ok(outcome)
+}
+
+/**
+ * Print a warning, and record it for the outcome.
+ */
+fn warn(warnings: &mut Vec<String>, message: String) {
+    println!("{} {}", Emoji("⚠️ ", ""), style(&message).yellow());
+    warnings.push(message);
+}
+
+/**
+ * Run the post hook matching the outcome of the verification, if configured.
+ */
+fn run_post_verify_hook(
+    args: &VerifyProjectArgs,
+    network: &Network,
+    job_id: Option<&str>,
+    status: Option<&str>,
+    result: &Result<()>,
+) {
+    let (hook, command) = match result {
+        Ok(_) => (POST_SUCCESS, &args.hooks.post_success),
+        Err(_) => (POST_FAILURE, &args.hooks.post_failure),
+    };
+    let Some(command) = command else {
+        return;
+    };
+    let vars = [
+        (NETWORK_VAR, network.to_string()),
+        (CLASS_HASH_VAR, args.hash.to_string()),
+        (JOB_ID_VAR, job_id.unwrap_or_default().to_owned()),
+        (STATUS_VAR, status.unwrap_or_default().to_owned()),
+        (
+            ERROR_VAR,
+            result
+                .as_ref()
+                .err()
+                .map(|e| format!("{:#}", e))
+                .unwrap_or_default(),
+        ),
+        (
+            REPORT_VAR,
+            args.report
+                .as_ref()
+                .map(|report| report.to_string())
+                .unwrap_or_default(),
+        ),
+    ];
+    run_post_hook(hook, command, &args.path, &vars);
+}
+
+/**
+ * Whether the dispatch failed only because the backend predates aliases.
+ */
+fn rejects_aliases(response: &errors::Result<String>) -> bool {
+    let Err(e) = response else {
+        return false;
+    };
+    match e.verifier_error() {
+        Some(VerifierError::UnsupportedSchemaVersion { supported, .. }) => {
+            !supports_aliases(supported)
+        }
+        _ => false,
+    }
+}
+
+/**
+ * Whether the dispatch failed because the backend predates compiler plugin
+ * information.
+ */
+fn rejects_plugins(response: &errors::Result<String>) -> bool {
+    let Err(e) = response else {
+        return false;
+    };
+    match e.verifier_error() {
+        Some(VerifierError::UnsupportedSchemaVersion { supported, .. }) => {
+            !supports_plugins(supported)
+        }
+        _ => false,
+    }
+}
+
+/**
+ * The style to retry with when the backend rejected the form style and listed
+ * the ones it accepts.
+ */
+fn negotiate_form_style(response: &errors::Result<String>) -> Option<FormStyle> {
+    let Err(e) = response else {
+        return None;
+    };
+    match e.verifier_error() {
+        Some(VerifierError::UnsupportedFormStyle { supported, .. }) => {
+            FormStyle::negotiate(supported)
+        }
+        _ => None,
+    }
+}
+
+/**
+ * Show what the backend says it is processing, and warn if it isn't what we
+ * submitted, which would indicate a job id mixup.
+ */
+fn report_echoed_metadata(
+    echoed: &JobMetadata,
+    submitted: &JobMetadata,
+    warnings: &mut Vec<String>,
+) {
+    if echoed.is_empty() {
+        return;
+    }
+    println!("Processing {}", echoed);
+    for (field, submitted, echoed) in echoed.mismatches(submitted) {
+        warn(
+            warnings,
+            format!(
+                "The job reports {} \"{}\" but \"{}\" was submitted, the job id might not belong to this verification.",
+                field,
+                sanitize(echoed),
+                sanitize(submitted)
+            ),
+        );
+    }
+}
+
+/**
+ * Poll the verification job, keeping the status file (if any) in sync with
+ * every observed state, including the final error.
+ */
+pub fn poll_with_status_file(
+    client: &dyn VerifierClient,
+    job_id: &str,
+    class_hash: &ClassHash,
+    polling: &PollingConfig,
+    status: Option<(&Utf8Path, StatusFile)>,
+    on_update: &mut dyn FnMut(&VerificationJob),
+) -> Result<()> {
+    let (status_path, mut status_file) = match status {
+        Some((path, status_file)) => (Some(path), Some(status_file)),
+        None => (None, None),
+    };
+    let mut write_error = None;
+
+    let poll_result = client.poll(job_id, polling, &mut |update| {
+        let job = match update {
+            PollUpdate::Job(job) => job,
+            PollUpdate::UnknownStatus(status) => {
+                eprintln!("Received unknown job status {}, still polling.", status);
+                return;
+            }
+            PollUpdate::Restarted(status) => {
+                eprintln!(
+                    "Job restarted by the server ({}), compilation will rerun.",
+                    status
+                );
+                return;
+            }
+        };
+        on_update(job);
+        if let (Some(path), Some(status_file)) = (status_path, status_file.as_mut()) {
+            status_file.update_from_job(job);
+            if let Err(e) = status_file.write(path) {
+                write_error = Some(e);
+            }
+        }
+    });
+
+    if let Some(e) = write_error {
+        println!("Unable to update the status file: {}", e);
+    }
+
+    let poll_result = match poll_result {
+        Err(e) if is_job_gone(&e) => Err(infer_from_class(client, job_id, class_hash, e)),
+        result => result.map_err(anyhow::Error::from),
+    };
+
     match poll_result {
         Ok(_response) => Ok(()),
-        Err(e) => Err(anyhow::anyhow!(
-            "Error while polling verification status: {}",
-            e
+        Err(e) => {
+            if let (Some(path), Some(status_file)) = (status_path, status_file.as_mut()) {
+                if is_inferred_success(&e) {
+                    status_file.infer_success(INFERRED_FROM_CLASS);
+                } else {
+                    status_file.fail(e.to_string());
+                }
+                status_file.write(path)?;
+            }
+            Err(e.context("Error while polling verification status"))
+        }
+    }
+}
+
+/// Status description of a success inferred from the class status.
+const INFERRED_FROM_CLASS: &str =
+    "Inferred from the class verification status, the job record is gone";
+
+fn is_job_gone(error: &errors::Error) -> bool {
+    matches!(error.verifier_error(), Some(VerifierError::JobNotFound))
+}
+
+/**
+ * Whether polling failed only because the job record is gone, while the class
+ * is verified. Callers report it as a success, labeled as inferred.
+ */
+pub fn is_inferred_success(error: &anyhow::Error) -> bool {
+    errors::causes(error).any(|cause| {
+        matches!(
+            cause.downcast_ref::<VerifierError>(),
+            Some(VerifierError::JobGoneClassVerified { .. })
+        )
+    })
+}
+
+/**
+ * Backends may garbage-collect finished jobs, in which case the outcome can
+ * still be inferred from whether the class ended up verified.
+ */
+fn infer_from_class(
+    client: &dyn VerifierClient,
+    job_id: &str,
+    class_hash: &ClassHash,
+    error: errors::Error,
+) -> anyhow::Error {
+    println!(
+        "Job {} not found, checking the verification status of class {} instead.",
+        sanitize(job_id),
+        class_hash
+    );
+    match client.class_verified(class_hash) {
+        Ok(Some(true)) => VerifierError::JobGoneClassVerified {
+            job_id: job_id.to_owned(),
+            class_hash: class_hash.to_string(),
+        }
+        .into(),
+        Ok(Some(false)) => anyhow::Error::from(error).context(format!(
+            "Class {} isn't verified either (inferred from the class status)",
+            class_hash
         )),
+        Ok(None) => error.into(),
+        Err(e) => {
+            println!("Unable to check the class status: {:#}", e);
+            error.into()
+        }
     }
 }
 
@@ -105,3 +957,414 @@ pub fn _verify_file(args: VerifyFileArgs, cairo_version: SupportedCairoVersions)
     let compiler = get_dynamic_compiler(cairo_version);
     compiler.compile_file(&file_dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::CairoPlugin;
+    use crate::report::StatusObservation;
+    use crate::testing::{FakeVerifier, Step, FAKE_JOB_ID};
+    use dyn_compiler::dyn_compiler::SupportedScarbVersions;
+    use std::{
+        env, fs,
+        io::{self, Write},
+        sync::{Arc, Mutex},
+    };
+
+    fn args(name: &str, auto_resubmit: u32) -> VerifyProjectArgs {
+        let dir = Utf8PathBuf::from_path_buf(env::temp_dir())
+            .unwrap()
+            .join(format!("verify-fake-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let report = dir.join("report.jsonl");
+        let _ = fs::remove_file(&report);
+        VerifyProjectArgs {
+            network: Network::Sepolia,
+            hash: "0x1234".parse().unwrap(),
+            license: LicenseType::MIT,
+            name: "MyToken".to_owned(),
+            path: dir.clone(),
+            status_file: None,
+            daemonless_wait: false,
+            no_wait: false,
+            no_cache: true,
+            resubmit_identical: false,
+            report: Some(report),
+            no_project_state: true,
+            form_style: FormStyle::Parts,
+            dialect: Dialect::Voyager,
+            deterministic_body: false,
+            auto_resubmit,
+            aliases: vec![],
+            aliases_best_effort: false,
+            expect_hash: None,
+            reproducibility: Reproducibility::Unlocked,
+            config: None,
+            metadata_changes: vec![],
+            revision: SourceRevision::default(),
+            attach_job_id: None,
+            hooks: Hooks::default(),
+            payload_cache: None,
+            polling: PollingConfig {
+                timeout: Some(Duration::from_secs(50)),
+                ..PollingConfig::default()
+            },
+            events: None,
+            json_output: None,
+            progress: None,
+            cache_dir: dir,
+            cancel: CancelToken::new(),
+            api_key: String::new(),
+        }
+    }
+
+    fn metadata() -> ProjectMetadataInfo {
+        ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: ".".to_owned(),
+            contract_file: "my_token/src/lib.cairo".to_owned(),
+            target: Default::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        }
+    }
+
+    fn verify(fake: &FakeVerifier, args: VerifyProjectArgs) -> (Result<()>, Vec<ReportEntry>) {
+        verify_metadata(fake, args, metadata())
+    }
+
+    fn verify_metadata(
+        fake: &FakeVerifier,
+        args: VerifyProjectArgs,
+        metadata: ProjectMetadataInfo,
+    ) -> (Result<()>, Vec<ReportEntry>) {
+        let report = args.report.clone().unwrap();
+        let mut files = SourceSet::new();
+        files.add("my_token/src/lib.cairo", "mod token;").unwrap();
+        let result = verify_with_client(fake, args, metadata, files, SourceSet::new())
+            .and_then(VerificationOutcome::into_result)
+            .map(|_| ());
+        let entries = fs::read_to_string(&report)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (result, entries)
+    }
+
+    /// A writer that can still be read once handed out.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn statuses(history: &[StatusObservation]) -> Vec<&str> {
+        history
+            .iter()
+            .map(|observed| observed.status.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_successful_verification_is_reported() {
+        let fake = FakeVerifier::success();
+        let (result, entries) = verify(&fake, args("success", 0));
+
+        result.unwrap();
+        assert!(fake.is_finished());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].job_id.as_deref(), Some(FAKE_JOB_ID));
+        assert_eq!(entries[0].status.as_deref(), Some("Success"));
+        assert_eq!(
+            statuses(&entries[0].status_history),
+            vec!["Submitted", "Compiled", "Success"]
+        );
+    }
+
+    #[test]
+    fn test_no_wait_returns_after_dispatching() {
+        let fake = FakeVerifier::success();
+        let args = VerifyProjectArgs {
+            no_wait: true,
+            ..args("no-wait", 0)
+        };
+        let mut files = SourceSet::new();
+        files.add("my_token/src/lib.cairo", "mod token;").unwrap();
+        let outcome = verify_with_client(&fake, args, metadata(), files, SourceSet::new()).unwrap();
+
+        assert_eq!(fake.dispatched().len(), 1);
+        assert!(!fake.is_finished());
+        assert_eq!(outcome.job_id.as_deref(), Some(FAKE_JOB_ID));
+        assert_eq!(outcome.status, None);
+        assert!(outcome.history.is_empty());
+        outcome.into_result().unwrap();
+    }
+
+    #[test]
+    fn test_outcome_matches_the_report() {
+        let fake = FakeVerifier::success();
+        let args = args("outcome", 0);
+        let report = args.report.clone().unwrap();
+        let mut files = SourceSet::new();
+        files.add("my_token/src/lib.cairo", "mod token;").unwrap();
+        let outcome = verify_with_client(&fake, args, metadata(), files, SourceSet::new()).unwrap();
+        let entry: ReportEntry =
+            serde_json::from_str(fs::read_to_string(&report).unwrap().trim()).unwrap();
+
+        assert!(outcome.is_success());
+        assert_eq!(outcome.status, Some(VerifyJobStatus::Success));
+        assert_eq!(outcome.job_id, entry.job_id);
+        assert_eq!(outcome.history, entry.status_history);
+        assert_eq!(
+            outcome.explorer_url.as_deref(),
+            Some("https://sepolia.voyager.online/class/0x1234#code")
+        );
+        assert!(!outcome.payload_digest.is_empty());
+        assert!(outcome.warnings.is_empty());
+        assert!(outcome.duration.total >= outcome.duration.upload);
+    }
+
+    #[test]
+    fn test_plugins_are_dropped_when_the_backend_predates_them() {
+        let fake = FakeVerifier::new([Step::Error(
+            VerifierError::UnsupportedSchemaVersion {
+                supported: vec![1, 2, 3, 4],
+                description: "Unsupported schema version 5".to_owned(),
+            }
+            .into(),
+        )])
+        .then(FakeVerifier::success());
+        let metadata = ProjectMetadataInfo {
+            plugins: vec![CairoPlugin {
+                name: "token_macros".to_owned(),
+                version: "0.3.1".to_owned(),
+                source: "registry+https://scarbs.xyz/".to_owned(),
+                checksum: None,
+            }],
+            ..metadata()
+        };
+        let (result, _) = verify_metadata(&fake, args("plugins", 0), metadata);
+
+        result.unwrap();
+        assert!(fake.is_finished());
+        assert_eq!(fake.dispatched_plugins(), vec![Vec::<String>::new()]);
+    }
+
+    #[test]
+    fn test_identical_payload_isnt_resubmitted_after_a_failure() {
+        let with_state = |resubmit_identical| VerifyProjectArgs {
+            no_project_state: false,
+            resubmit_identical,
+            ..args("identical", 0)
+        };
+        let first = with_state(false);
+        let _ = fs::remove_file(DispatchCache::path(&first.cache_dir));
+        let fake = FakeVerifier::compile_failed("unknown module token");
+        let (result, _) = verify(&fake, first);
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-012"));
+
+        let fake = FakeVerifier::success();
+        let (result, _) = verify(&fake, with_state(false));
+        let error = result.unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-042"));
+        assert!(error.to_string().contains(FAKE_JOB_ID), "{}", error);
+        assert!(
+            error.to_string().contains("unknown module token"),
+            "{}",
+            error
+        );
+        assert!(fake.dispatched().is_empty());
+
+        let fake = FakeVerifier::success();
+        let (result, _) = verify(&fake, with_state(true));
+        result.unwrap();
+        assert_eq!(fake.dispatched().len(), 1);
+    }
+
+    #[test]
+    fn test_json_output_prints_the_job_and_its_result() {
+        let output = SharedBuffer::default();
+        let fake = FakeVerifier::compile_failed("unknown module token");
+        let args = VerifyProjectArgs {
+            json_output: Some(JsonOutput::new(Box::new(output.clone()))),
+            ..args("json", 0)
+        };
+        let (result, _) = verify(&fake, args);
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-012"));
+
+        let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = printed
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2, "{}", printed);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "job_id": FAKE_JOB_ID,
+                "class_hash": "0x1234",
+                "network": "sepolia",
+            })
+        );
+        assert_eq!(lines[1]["job_id"], FAKE_JOB_ID);
+        assert_eq!(lines[1]["status"], "CompileFailed");
+        assert_eq!(lines[1]["status_description"], "unknown module token");
+    }
+
+    #[test]
+    fn test_progress_shows_every_status_change() {
+        let output = SharedBuffer::default();
+        let fake = FakeVerifier::success();
+        let args = VerifyProjectArgs {
+            progress: Some(PollProgress::lines(Box::new(output.clone()))),
+            ..args("progress", 0)
+        };
+        let (result, _) = verify(&fake, args);
+        result.unwrap();
+
+        let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let statuses: Vec<&str> = printed
+            .lines()
+            .map(|line| line.split(' ').nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            statuses,
+            ["Submitted", "Compiled", "Success"],
+            "{}",
+            printed
+        );
+    }
+
+    #[test]
+    fn test_json_output_ends_with_the_summary_on_success() {
+        let output = SharedBuffer::default();
+        let fake = FakeVerifier::success();
+        let args = VerifyProjectArgs {
+            json_output: Some(JsonOutput::new(Box::new(output.clone()))),
+            ..args("json-summary", 0)
+        };
+        let (result, _) = verify(&fake, args);
+        result.unwrap();
+
+        let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = printed
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3, "{}", printed);
+        assert_eq!(lines[1]["status"], "Success");
+        assert_eq!(lines[2]["name"], "MyToken");
+        assert_eq!(lines[2]["class_hash"], "0x1234");
+        assert_eq!(lines[2]["network"], "sepolia");
+        assert_eq!(
+            lines[2]["url"],
+            "https://sepolia.voyager.online/class/0x1234#code"
+        );
+        // The scripted jobs aren't timestamped
+        assert_eq!(lines[2]["remote_elapsed_ms"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_compilation_failure_is_reported() {
+        let fake = FakeVerifier::compile_failed("unknown module token");
+        let (result, entries) = verify(&fake, args("compile-failed", 1));
+
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-012"));
+        // Compilation failures are never resubmitted
+        assert_eq!(fake.dispatched().len(), 1);
+        assert_eq!(entries[0].status.as_deref(), Some("CompileFailed"));
+        assert_eq!(
+            entries[0].status_description.as_deref(),
+            Some("unknown module token")
+        );
+        assert_eq!(entries[0].error_code.as_deref(), Some("VER-012"));
+    }
+
+    #[test]
+    fn test_transient_failure_is_resubmitted() {
+        let fake = FakeVerifier::failed("Worker timeout").then(FakeVerifier::success());
+        let (result, entries) = verify(&fake, args("resubmit", 1));
+
+        result.unwrap();
+        assert!(fake.is_finished());
+        assert_eq!(fake.dispatched().len(), 2);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.attempt, entry.status.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![(1, Some("Fail")), (2, Some("Success"))]
+        );
+    }
+
+    #[test]
+    fn test_rejected_dispatch_is_reported() {
+        let fake = FakeVerifier::rate_limited();
+        let (result, entries) = verify(&fake, args("rate-limited", 0));
+
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-008"));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].job_id, None);
+        assert_eq!(entries[0].error_code.as_deref(), Some("VER-008"));
+    }
+
+    #[test]
+    fn test_expected_hash_is_checked() {
+        let fake = FakeVerifier::success();
+        let mut matching = args("expect-hash", 0);
+        matching.expect_hash = Some("0x000".parse().unwrap());
+        let (result, entries) = verify(&fake, matching);
+
+        result.unwrap();
+        assert!(entries[0].hash_check.as_ref().unwrap().matches);
+
+        let fake = FakeVerifier::success();
+        let mut mismatching = args("expect-hash-mismatch", 0);
+        mismatching.expect_hash = Some("0x1".parse().unwrap());
+        let (result, entries) = verify(&fake, mismatching);
+
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-026"));
+        assert_eq!(entries[0].status.as_deref(), Some("Success"));
+        assert_eq!(entries[0].error_code.as_deref(), Some("VER-026"));
+        assert!(!entries[0].hash_check.as_ref().unwrap().matches);
+    }
+
+    #[test]
+    fn test_gone_job_is_inferred_from_the_class() {
+        let fake = FakeVerifier::job_gone().with_class_verified(true);
+        let (result, entries) = verify(&fake, args("job-gone", 0));
+
+        result.unwrap();
+        assert_eq!(entries[0].status.as_deref(), Some("Success"));
+        assert_eq!(
+            entries[0].status_description.as_deref(),
+            Some(INFERRED_FROM_CLASS)
+        );
+
+        let fake = FakeVerifier::job_gone().with_class_verified(false);
+        let (result, entries) = verify(&fake, args("job-gone-unverified", 0));
+
+        let error = result.unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-009"));
+        assert!(format!("{:#}", error).contains("isn't verified either"));
+        assert_eq!(entries[0].status, None);
+    }
+
+    #[test]
+    fn test_pending_job_times_out() {
+        let fake = FakeVerifier::timeout();
+        let (result, entries) = verify(&fake, args("timeout", 0));
+
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-013"));
+        assert_eq!(entries[0].status, None);
+    }
+}
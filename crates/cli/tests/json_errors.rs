@@ -0,0 +1,122 @@
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn verifier(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_starknet-contract-verifier"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// The envelope is the last line of stdout, and must be valid JSON.
+fn envelope(output: &Output) -> Value {
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    let last = stdout.lines().last().expect("nothing printed on stdout");
+    serde_json::from_str(last).unwrap()
+}
+
+#[test]
+fn test_unknown_argument() {
+    let output = verifier(&["--json-errors", "--bogus"]);
+    let envelope = envelope(&output);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(envelope["code"], "VER-039");
+    assert_eq!(envelope["exit_code"], 2);
+    assert!(envelope["message"].as_str().unwrap().contains("--bogus"));
+    assert!(envelope["details"].is_array());
+}
+
+#[test]
+fn test_output_json_prints_failures_on_stderr() {
+    let output = verifier(&["--output", "json", "--bogus"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let envelope: Value = serde_json::from_str(stderr.lines().last().unwrap()).unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(envelope["code"], "VER-039");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_output_json_errors_is_json_errors() {
+    let output = verifier(&["--output", "json-errors", "--bogus"]);
+    let envelope = envelope(&output);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(envelope["code"], "VER-039");
+}
+
+#[test]
+fn test_output_formats_conflict() {
+    for args in [
+        ["--output", "json", "--json-errors"],
+        ["--output", "human", "--json"],
+        ["--json", "--json-errors", "paths"],
+    ] {
+        let output = verifier(&args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("cannot be used with"),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn test_invalid_value() {
+    let output = verifier(&["--poll-interval", "0", "--json-errors"]);
+    let envelope = envelope(&output);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(envelope["code"], "VER-039");
+    assert!(
+        envelope["message"]
+            .as_str()
+            .unwrap()
+            .contains("at least 1 second"),
+        "{}",
+        envelope
+    );
+}
+
+#[test]
+fn test_missing_config_file() {
+    let config = std::env::temp_dir().join(format!(
+        "voyager-json-errors-{}/missing.env",
+        std::process::id()
+    ));
+    let output = verifier(&[
+        "--json-errors",
+        "--config",
+        config.to_str().unwrap(),
+        "paths",
+    ]);
+    let envelope = envelope(&output);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(envelope["code"], "VER-040");
+    let details = envelope["details"].to_string();
+    assert!(details.contains("missing.env"), "{}", details);
+}
+
+#[test]
+fn test_json_prints_errors_on_stderr() {
+    let output = verifier(&["--json", "--bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let envelope: Value = serde_json::from_str(stderr.lines().last().unwrap()).unwrap();
+    assert_eq!(envelope["code"], "VER-039");
+    assert_eq!(envelope["exit_code"], 2);
+}
+
+#[test]
+fn test_human_errors_by_default() {
+    let output = verifier(&["--bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--bogus"));
+
+    // Help isn't a failure
+    let output = verifier(&["--json-errors", "--help"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--json-errors"));
+}
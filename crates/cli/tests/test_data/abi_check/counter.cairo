@@ -0,0 +1,26 @@
+#[starknet::interface]
+trait ICounter<TContractState> {
+    fn get_count(self: @TContractState) -> u128;
+    fn increment(ref self: TContractState);
+}
+
+#[starknet::contract]
+mod Counter {
+    #[storage]
+    struct Storage {
+        count: u128,
+    }
+
+    // fn transfer(ref self: ContractState) is intentionally not part of this contract.
+
+    #[abi(embed_v0)]
+    impl CounterImpl of super::ICounter<ContractState> {
+        fn get_count(self: @ContractState) -> u128 {
+            self.count.read()
+        }
+
+        fn increment(ref self: ContractState) {
+            self.count.write(self.count.read() + 1);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+#[starknet::interface]
+trait IERC20<TContractState> {
+    fn get_name(self: @TContractState) -> felt252;
+    fn get_symbol(self: @TContractState) -> felt252;
+    fn balance_of(self: @TContractState, account: ContractAddress) -> u256;
+    fn transfer(ref self: TContractState, recipient: ContractAddress, amount: u256);
+}
+
+#[starknet::contract]
+mod ERC20 {
+    use starknet::ContractAddress;
+
+    #[storage]
+    struct Storage {
+        name: felt252,
+        symbol: felt252,
+        balances: LegacyMap::<ContractAddress, u256>,
+    }
+
+    #[constructor]
+    fn constructor(ref self: ContractState, recipient: ContractAddress) {}
+
+    #[abi(embed_v0)]
+    impl ERC20Impl of super::IERC20<ContractState> {
+        fn get_name(self: @ContractState) -> felt252 {
+            self.name.read()
+        }
+
+        fn get_symbol(self: @ContractState) -> felt252 {
+            self.symbol.read()
+        }
+
+        fn balance_of(self: @ContractState, account: ContractAddress) -> u256 {
+            self.balances.read(account)
+        }
+
+        fn transfer(ref self: ContractState, recipient: ContractAddress, amount: u256) {}
+    }
+}
@@ -0,0 +1 @@
+console.log('not part of the package');
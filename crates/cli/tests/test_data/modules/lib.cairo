@@ -0,0 +1,10 @@
+// Re-exports the token, as generated by some templates
+pub mod token;
+pub mod utils {
+    pub mod math;
+    mod constants {
+        const DECIMALS: u8 = 18;
+    }
+}
+
+pub use token::erc20::presets::MyToken;
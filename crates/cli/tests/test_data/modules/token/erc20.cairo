@@ -0,0 +1,22 @@
+pub mod presets {
+    #[starknet::interface]
+    trait IMyToken<T> {
+        fn name(self: @T) -> felt252;
+    }
+
+    #[starknet::contract]
+    pub mod MyToken {
+        #[storage]
+        struct Storage {
+            // mod ignored;
+            name: felt252,
+        }
+
+        #[abi(embed_v0)]
+        impl MyTokenImpl of super::IMyToken<ContractState> {
+            fn name(self: @ContractState) -> felt252 {
+                'mod {'
+            }
+        }
+    }
+}
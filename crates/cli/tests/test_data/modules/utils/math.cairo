@@ -0,0 +1,3 @@
+pub fn double(x: u128) -> u128 {
+    x * 2
+}
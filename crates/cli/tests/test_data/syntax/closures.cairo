@@ -0,0 +1,5 @@
+fn apply<F, +Drop<F>, impl Func: core::ops::FnOnce<F, (u32,)>, +Drop<Func::Output>>(
+    f: F, value: u32,
+) -> Func::Output {
+    f(value)
+}
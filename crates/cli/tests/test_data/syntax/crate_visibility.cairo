@@ -0,0 +1,3 @@
+pub(crate) fn double(value: u32) -> u32 {
+    value * 2
+}
@@ -0,0 +1,7 @@
+fn sum(values: Array<u32>) -> u32 {
+    let mut total = 0;
+    for value in values {
+        total += value;
+    };
+    total
+}
@@ -0,0 +1,6 @@
+use starknet::storage::Map;
+
+#[starknet::storage_node]
+struct Balances {
+    amounts: Map<felt252, u256>,
+}
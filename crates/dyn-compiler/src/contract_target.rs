@@ -0,0 +1,46 @@
+/// The `[[target.starknet-contract]]` of a package, with Scarb's defaults for
+/// the settings it leaves out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarknetContractTarget {
+    /// Set when the target isn't named after the package.
+    pub name: Option<String>,
+    pub sierra: bool,
+    pub casm: bool,
+    /// Paths of contracts from dependencies built along with the package, e.g.
+    /// `openzeppelin::presets::erc20::ERC20Upgradeable`, or `dep::tokens::*` for
+    /// every contract under a module.
+    pub build_external_contracts: Vec<String>,
+}
+
+impl Default for StarknetContractTarget {
+    fn default() -> Self {
+        Self {
+            name: None,
+            sierra: true,
+            casm: false,
+            build_external_contracts: vec![],
+        }
+    }
+}
+
+impl StarknetContractTarget {
+    /// Whether the target builds like a bare `[[target.starknet-contract]]`.
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Packages the external contracts are built from, in order of appearance.
+    pub fn external_contract_packages(&self) -> Vec<String> {
+        let mut packages: Vec<String> = vec![];
+        for package in self
+            .build_external_contracts
+            .iter()
+            .filter_map(|path| path.split("::").next())
+        {
+            if !packages.iter().any(|seen| seen == package) {
+                packages.push(package.to_owned());
+            }
+        }
+        packages
+    }
+}
@@ -1,32 +1,355 @@
+use std::{fmt, str::FromStr};
+
 use anyhow::Result;
 use camino::Utf8PathBuf;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SupportedCairoVersions {
+    V2_5_0,
+    V2_5_1,
+    V2_5_2,
+    V2_5_3,
+    V2_5_4,
+    V2_6_0,
+    V2_6_1,
+    V2_6_2,
+    V2_6_3,
+    V2_6_4,
+    V2_7_0,
+    V2_7_1,
+    V2_8_0,
+    V2_8_2,
     V2_8_4,
 }
 
 impl ToString for SupportedCairoVersions {
     fn to_string(&self) -> String {
+        let (major, minor, patch) = self.version();
+        format!("{}.{}.{}", major, minor, patch)
+    }
+}
+
+impl FromStr for SupportedCairoVersions {
+    type Err = UnsupportedVersion;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        SupportedCairoVersions::ALL
+            .iter()
+            .find(|supported| supported.to_string() == version.trim())
+            .copied()
+            .ok_or_else(|| UnsupportedVersion(version.trim().to_owned()))
+    }
+}
+
+impl SupportedCairoVersions {
+    /// Every variant, oldest first.
+    pub const ALL: &'static [SupportedCairoVersions] = &[
+        SupportedCairoVersions::V2_5_0,
+        SupportedCairoVersions::V2_5_1,
+        SupportedCairoVersions::V2_5_2,
+        SupportedCairoVersions::V2_5_3,
+        SupportedCairoVersions::V2_5_4,
+        SupportedCairoVersions::V2_6_0,
+        SupportedCairoVersions::V2_6_1,
+        SupportedCairoVersions::V2_6_2,
+        SupportedCairoVersions::V2_6_3,
+        SupportedCairoVersions::V2_6_4,
+        SupportedCairoVersions::V2_7_0,
+        SupportedCairoVersions::V2_7_1,
+        SupportedCairoVersions::V2_8_0,
+        SupportedCairoVersions::V2_8_2,
+        SupportedCairoVersions::V2_8_4,
+    ];
+
+    pub fn version(&self) -> CairoVersion {
         match self {
-            SupportedCairoVersions::V2_8_4 => "2.8.4".into(),
+            SupportedCairoVersions::V2_5_0 => (2, 5, 0),
+            SupportedCairoVersions::V2_5_1 => (2, 5, 1),
+            SupportedCairoVersions::V2_5_2 => (2, 5, 2),
+            SupportedCairoVersions::V2_5_3 => (2, 5, 3),
+            SupportedCairoVersions::V2_5_4 => (2, 5, 4),
+            SupportedCairoVersions::V2_6_0 => (2, 6, 0),
+            SupportedCairoVersions::V2_6_1 => (2, 6, 1),
+            SupportedCairoVersions::V2_6_2 => (2, 6, 2),
+            SupportedCairoVersions::V2_6_3 => (2, 6, 3),
+            SupportedCairoVersions::V2_6_4 => (2, 6, 4),
+            SupportedCairoVersions::V2_7_0 => (2, 7, 0),
+            SupportedCairoVersions::V2_7_1 => (2, 7, 1),
+            SupportedCairoVersions::V2_8_0 => (2, 8, 0),
+            SupportedCairoVersions::V2_8_2 => (2, 8, 2),
+            SupportedCairoVersions::V2_8_4 => (2, 8, 4),
         }
     }
+
+    /// Version of the Sierra programs produced by this compiler.
+    pub fn sierra_version(&self) -> SierraVersion {
+        emitted_sierra_version(self.version())
+            .expect("Every supported Cairo version is in SIERRA_VERSIONS")
+    }
+}
+
+/**
+ * A version string that doesn't name any of the supported versions.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedVersion(pub String);
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported version {}", self.0)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl std::error::Error for UnsupportedVersion {}
+
+pub type CairoVersion = (u32, u32, u32);
+
+pub type SierraVersion = (u32, u32, u32);
+
+pub fn format_version((major, minor, patch): CairoVersion) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+/**
+ * The first Cairo release of each Sierra version, in order. A class declared
+ * with another Sierra version than the selected compiler emits can't verify,
+ * this tells which compiler to use instead. Keep in sync with the supported
+ * versions above when adding one.
+ */
+pub const SIERRA_VERSIONS: &[(CairoVersion, SierraVersion)] = &[
+    ((2, 0, 0), (1, 2, 0)),
+    ((2, 1, 0), (1, 3, 0)),
+    ((2, 4, 0), (1, 4, 0)),
+    ((2, 6, 0), (1, 5, 0)),
+    ((2, 7, 0), (1, 6, 0)),
+    ((2, 10, 0), (1, 7, 0)),
+];
+
+/**
+ * The Sierra version emitted by `cairo`, if it is recent enough to be known.
+ */
+pub fn emitted_sierra_version(cairo: CairoVersion) -> Option<SierraVersion> {
+    SIERRA_VERSIONS
+        .iter()
+        .rev()
+        .find(|(first_release, _)| *first_release <= cairo)
+        .map(|(_, sierra)| *sierra)
+}
+
+/**
+ * The Cairo releases emitting `sierra`, as the first of them and the first
+ * release past them, if any.
+ */
+pub fn cairo_versions_emitting(
+    sierra: SierraVersion,
+) -> Option<(CairoVersion, Option<CairoVersion>)> {
+    let index = SIERRA_VERSIONS
+        .iter()
+        .position(|(_, emitted)| *emitted == sierra)?;
+    Some((
+        SIERRA_VERSIONS[index].0,
+        SIERRA_VERSIONS
+            .get(index + 1)
+            .map(|(first_release, _)| *first_release),
+    ))
+}
+
+/**
+ * A token that only parses from a given Cairo version on. Used to warn before
+ * uploading sources that the selected compiler will fail to compile. Keep in
+ * sync with the supported versions above when adding one.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntaxMarker {
+    /// Identifier used to silence the marker when it is a false positive.
+    pub id: &'static str,
+    pub feature: &'static str,
+    pub tokens: &'static [&'static str],
+    pub introduced_in: CairoVersion,
+}
+
+pub const SYNTAX_MARKERS: &[SyntaxMarker] = &[
+    SyntaxMarker {
+        id: "for-loop",
+        feature: "for loops",
+        tokens: &["for "],
+        introduced_in: (2, 6, 0),
+    },
+    SyntaxMarker {
+        id: "crate-visibility",
+        feature: "crate visibility",
+        tokens: &["pub(crate)"],
+        introduced_in: (2, 7, 0),
+    },
+    SyntaxMarker {
+        id: "storage-node",
+        feature: "storage nodes",
+        tokens: &["#[starknet::storage_node]"],
+        introduced_in: (2, 8, 0),
+    },
+    SyntaxMarker {
+        id: "closures",
+        feature: "closures",
+        tokens: &["+Fn<", "+FnOnce<", "core::ops::Fn<", "core::ops::FnOnce<"],
+        introduced_in: (2, 9, 0),
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SupportedScarbVersions {
+    V2_5_0,
+    V2_5_1,
+    V2_5_2,
+    V2_5_3,
+    V2_5_4,
+    V2_6_0,
+    V2_6_1,
+    V2_6_2,
+    V2_6_3,
+    V2_6_4,
+    V2_6_5,
+    V2_7_0,
+    V2_7_1,
+    V2_8_0,
+    V2_8_1,
+    V2_8_2,
+    V2_8_3,
     V2_8_4,
 }
 
 impl ToString for SupportedScarbVersions {
     fn to_string(&self) -> String {
         match self {
+            SupportedScarbVersions::V2_5_0 => "2.5.0".into(),
+            SupportedScarbVersions::V2_5_1 => "2.5.1".into(),
+            SupportedScarbVersions::V2_5_2 => "2.5.2".into(),
+            SupportedScarbVersions::V2_5_3 => "2.5.3".into(),
+            SupportedScarbVersions::V2_5_4 => "2.5.4".into(),
+            SupportedScarbVersions::V2_6_0 => "2.6.0".into(),
+            SupportedScarbVersions::V2_6_1 => "2.6.1".into(),
+            SupportedScarbVersions::V2_6_2 => "2.6.2".into(),
+            SupportedScarbVersions::V2_6_3 => "2.6.3".into(),
+            SupportedScarbVersions::V2_6_4 => "2.6.4".into(),
+            SupportedScarbVersions::V2_6_5 => "2.6.5".into(),
+            SupportedScarbVersions::V2_7_0 => "2.7.0".into(),
+            SupportedScarbVersions::V2_7_1 => "2.7.1".into(),
+            SupportedScarbVersions::V2_8_0 => "2.8.0".into(),
+            SupportedScarbVersions::V2_8_1 => "2.8.1".into(),
+            SupportedScarbVersions::V2_8_2 => "2.8.2".into(),
+            SupportedScarbVersions::V2_8_3 => "2.8.3".into(),
             SupportedScarbVersions::V2_8_4 => "2.8.4".into(),
         }
     }
 }
 
+impl FromStr for SupportedScarbVersions {
+    type Err = UnsupportedVersion;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        SupportedScarbVersions::ALL
+            .iter()
+            .find(|supported| supported.to_string() == version.trim())
+            .copied()
+            .ok_or_else(|| UnsupportedVersion(version.trim().to_owned()))
+    }
+}
+
+impl SupportedScarbVersions {
+    /// Every variant, oldest first.
+    pub const ALL: &'static [SupportedScarbVersions] = &[
+        SupportedScarbVersions::V2_5_0,
+        SupportedScarbVersions::V2_5_1,
+        SupportedScarbVersions::V2_5_2,
+        SupportedScarbVersions::V2_5_3,
+        SupportedScarbVersions::V2_5_4,
+        SupportedScarbVersions::V2_6_0,
+        SupportedScarbVersions::V2_6_1,
+        SupportedScarbVersions::V2_6_2,
+        SupportedScarbVersions::V2_6_3,
+        SupportedScarbVersions::V2_6_4,
+        SupportedScarbVersions::V2_6_5,
+        SupportedScarbVersions::V2_7_0,
+        SupportedScarbVersions::V2_7_1,
+        SupportedScarbVersions::V2_8_0,
+        SupportedScarbVersions::V2_8_1,
+        SupportedScarbVersions::V2_8_2,
+        SupportedScarbVersions::V2_8_3,
+        SupportedScarbVersions::V2_8_4,
+    ];
+
+    /// The Cairo compiler bundled with this Scarb release.
+    pub fn cairo_version(&self) -> SupportedCairoVersions {
+        match self {
+            SupportedScarbVersions::V2_5_0 => SupportedCairoVersions::V2_5_0,
+            SupportedScarbVersions::V2_5_1 => SupportedCairoVersions::V2_5_1,
+            SupportedScarbVersions::V2_5_2 => SupportedCairoVersions::V2_5_2,
+            SupportedScarbVersions::V2_5_3 => SupportedCairoVersions::V2_5_3,
+            SupportedScarbVersions::V2_5_4 => SupportedCairoVersions::V2_5_4,
+            SupportedScarbVersions::V2_6_0 => SupportedCairoVersions::V2_6_0,
+            SupportedScarbVersions::V2_6_1 => SupportedCairoVersions::V2_6_1,
+            SupportedScarbVersions::V2_6_2 => SupportedCairoVersions::V2_6_2,
+            SupportedScarbVersions::V2_6_3 | SupportedScarbVersions::V2_6_4 => {
+                SupportedCairoVersions::V2_6_3
+            }
+            SupportedScarbVersions::V2_6_5 => SupportedCairoVersions::V2_6_4,
+            SupportedScarbVersions::V2_7_0 => SupportedCairoVersions::V2_7_0,
+            SupportedScarbVersions::V2_7_1 => SupportedCairoVersions::V2_7_1,
+            SupportedScarbVersions::V2_8_0 | SupportedScarbVersions::V2_8_1 => {
+                SupportedCairoVersions::V2_8_0
+            }
+            SupportedScarbVersions::V2_8_2 | SupportedScarbVersions::V2_8_3 => {
+                SupportedCairoVersions::V2_8_2
+            }
+            SupportedScarbVersions::V2_8_4 => SupportedCairoVersions::V2_8_4,
+        }
+    }
+}
+
+/**
+ * What a resolver supports, known without constructing it so that it can be
+ * queried cheaply, e.g. without any project at hand.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolverInfo {
+    pub name: &'static str,
+    pub scarb_versions: &'static [SupportedScarbVersions],
+    pub cairo_versions: &'static [SupportedCairoVersions],
+    /// Cargo features the resolver was built with.
+    pub features: &'static [&'static str],
+}
+
+/**
+ * The Scarb versions supported by any of `resolvers`, without duplicates.
+ */
+pub fn supported_scarb_versions(resolvers: &[ResolverInfo]) -> Vec<SupportedScarbVersions> {
+    let mut versions = vec![];
+    for version in resolvers
+        .iter()
+        .flat_map(|resolver| resolver.scarb_versions)
+    {
+        if !versions.contains(version) {
+            versions.push(*version);
+        }
+    }
+    versions
+}
+
+/**
+ * The Cairo versions supported by any of `resolvers`, without duplicates.
+ */
+pub fn supported_cairo_versions(resolvers: &[ResolverInfo]) -> Vec<SupportedCairoVersions> {
+    let mut versions = vec![];
+    for version in resolvers
+        .iter()
+        .flat_map(|resolver| resolver.cairo_versions)
+    {
+        if !versions.contains(version) {
+            versions.push(*version);
+        }
+    }
+    versions
+}
+
 /**
  * This trait is required to be implemented by the voyager resolvers.
  * This allows us to use multiple version of Scarb + Cairo in the same project,
@@ -39,7 +362,108 @@ pub trait DynamicCompiler {
 
     fn get_contracts_to_verify_path(&self, project_path: &Utf8PathBuf) -> Result<Vec<Utf8PathBuf>>;
 
-    fn compile_project(&self, project_path: &Utf8PathBuf) -> Result<()>;
+    /// Extract the files to verify and build the contract classes of the
+    /// package, returning the paths of the Sierra contract class artifacts.
+    fn compile_project(&self, project_path: &Utf8PathBuf) -> Result<Vec<Utf8PathBuf>>;
 
     fn compile_file(&self, file_path: &Utf8PathBuf) -> Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_supported_version_emits_a_known_sierra_version() {
+        // A new variant doesn't compile until its Sierra version is listed here
+        for version in SupportedCairoVersions::ALL {
+            let expected = match version {
+                SupportedCairoVersions::V2_5_0
+                | SupportedCairoVersions::V2_5_1
+                | SupportedCairoVersions::V2_5_2
+                | SupportedCairoVersions::V2_5_3
+                | SupportedCairoVersions::V2_5_4 => (1, 4, 0),
+                SupportedCairoVersions::V2_6_0
+                | SupportedCairoVersions::V2_6_1
+                | SupportedCairoVersions::V2_6_2
+                | SupportedCairoVersions::V2_6_3
+                | SupportedCairoVersions::V2_6_4 => (1, 5, 0),
+                SupportedCairoVersions::V2_7_0
+                | SupportedCairoVersions::V2_7_1
+                | SupportedCairoVersions::V2_8_0
+                | SupportedCairoVersions::V2_8_2
+                | SupportedCairoVersions::V2_8_4 => (1, 6, 0),
+            };
+            assert_eq!(version.sierra_version(), expected);
+        }
+    }
+
+    #[test]
+    fn test_versions_round_trip_through_strings() {
+        for version in SupportedCairoVersions::ALL {
+            assert_eq!(
+                version.to_string().parse::<SupportedCairoVersions>(),
+                Ok(*version)
+            );
+        }
+        for version in SupportedScarbVersions::ALL {
+            assert_eq!(
+                version.to_string().parse::<SupportedScarbVersions>(),
+                Ok(*version)
+            );
+        }
+        assert_eq!(
+            " 2.6.4\n".parse::<SupportedCairoVersions>(),
+            Ok(SupportedCairoVersions::V2_6_4)
+        );
+        assert_eq!(
+            "2.9.1".parse::<SupportedScarbVersions>(),
+            Err(UnsupportedVersion("2.9.1".to_owned()))
+        );
+        // Scarb releases without a Cairo release of their own
+        assert!("2.6.5".parse::<SupportedCairoVersions>().is_err());
+        assert_eq!(
+            SupportedScarbVersions::V2_6_5.cairo_version(),
+            SupportedCairoVersions::V2_6_4
+        );
+        assert!(SupportedCairoVersions::ALL
+            .windows(2)
+            .all(|pair| pair[0].version() < pair[1].version()));
+    }
+
+    #[test]
+    fn test_supported_versions_of_several_resolvers() {
+        let resolver = ResolverInfo {
+            name: "resolver",
+            scarb_versions: &[SupportedScarbVersions::V2_8_4],
+            cairo_versions: &[SupportedCairoVersions::V2_8_4],
+            features: &[],
+        };
+        let other = ResolverInfo {
+            name: "other",
+            ..resolver
+        };
+        assert_eq!(
+            supported_scarb_versions(&[resolver, other]),
+            vec![SupportedScarbVersions::V2_8_4]
+        );
+        assert_eq!(
+            supported_cairo_versions(&[resolver, other]),
+            vec![SupportedCairoVersions::V2_8_4]
+        );
+        assert!(supported_cairo_versions(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_cairo_versions_emitting() {
+        assert_eq!(emitted_sierra_version((2, 5, 0)), Some((1, 4, 0)));
+        assert_eq!(emitted_sierra_version((1, 1, 0)), None);
+        assert_eq!(
+            cairo_versions_emitting((1, 5, 0)),
+            Some(((2, 6, 0), Some((2, 7, 0))))
+        );
+        assert_eq!(cairo_versions_emitting((1, 7, 0)), Some(((2, 10, 0), None)));
+        assert_eq!(cairo_versions_emitting((1, 1, 0)), None);
+        assert!(SIERRA_VERSIONS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
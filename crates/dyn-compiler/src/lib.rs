@@ -1 +1,2 @@
+pub mod contract_target;
 pub mod dyn_compiler;
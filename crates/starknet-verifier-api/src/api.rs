@@ -0,0 +1,2852 @@
+use std::env;
+use std::fmt::Display;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::{str::FromStr, time::Duration};
+
+use anyhow::anyhow;
+use dyn_compiler::contract_target::StarknetContractTarget;
+use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+use reqwest::{header::HeaderMap, StatusCode};
+use url::Url;
+
+use crate::api_async::{
+    dispatch_class_verification_job_async, does_class_exist_async, fetch_capabilities_async,
+    fetch_class_async, fetch_in_progress_jobs_async, fetch_known_dependencies_async,
+    get_job_status_async, poll_verification_status_async, submit_correction_async,
+};
+use crate::cancel::CancelToken;
+use crate::class_hash::ClassHash;
+use crate::corrections::{CorrectionAck, CorrectionRequest};
+use crate::dependencies::{DependencyPlan, DependencyRef, VendoredDependency};
+use crate::dialect::Dialect;
+use crate::display::sanitize;
+use crate::errors::{Error, Result, VerifierError};
+use crate::form::{
+    describe_schema_version_mismatch, form_metadata_fields, FormBody, FormFeatures, FormStyle,
+    FormSubmission,
+};
+use crate::polling::{
+    Clock, PollNegotiation, PollSchedule, PollingConfig, StatusTracker, Transition,
+};
+use crate::sources::SourceSet;
+use crate::transfer::{
+    TransferLog, CAPABILITIES_ENDPOINT, CORRECTIONS_ENDPOINT, DEPENDENCIES_ENDPOINT, JOBS_ENDPOINT,
+    JOB_STATUS_ENDPOINT, VERIFY_ENDPOINT,
+};
+use crate::upload_errors::{format_rejected_files, RejectedFile};
+use crate::verified_metadata::{class_info_from_class, ClassInfo};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Sepolia,
+    Local,
+    /// A self-hosted instance, whose endpoints are validated once, see `CustomEndpoints`.
+    Custom {
+        internal: Url,
+        public: Url,
+    },
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Sepolia => write!(f, "sepolia"),
+            Network::Local => write!(f, "local"),
+            Network::Custom { .. } => write!(f, "custom"),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "sepolia" => Ok(Network::Sepolia),
+            "local" => Ok(Network::Local),
+            "custom" => CustomEndpoints::default().network(),
+            _ => Err(VerifierError::UnknownNetwork(s.to_owned()).into()),
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub enum VerifyJobStatus {
+    Submitted,
+    Compiled,
+    CompileFailed,
+    Fail,
+    Success,
+}
+
+/**
+ * A status byte this version of the CLI doesn't know, such as a state added
+ * to the backend since.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("unknown job status {0}")]
+pub struct UnknownJobStatus(pub u8);
+
+impl TryFrom<u8> for VerifyJobStatus {
+    type Error = UnknownJobStatus;
+
+    fn try_from(status: u8) -> std::result::Result<Self, Self::Error> {
+        match status {
+            0 => Some(Self::Submitted),
+            1 => Some(Self::Compiled),
+            2 => Some(Self::CompileFailed),
+            3 => Some(Self::Fail),
+            4 => Some(Self::Success),
+            _ => None,
+        }
+        .ok_or(UnknownJobStatus(status))
+    }
+}
+
+fn deserialize_status<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<VerifyJobStatus, D::Error> {
+    let status = <u8 as serde::Deserialize>::deserialize(deserializer)?;
+    VerifyJobStatus::try_from(status).map_err(serde::de::Error::custom)
+}
+
+fn serialize_status<S: serde::Serializer>(
+    status: &VerifyJobStatus,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(status)
+}
+
+impl Display for VerifyJobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyJobStatus::Submitted => write!(f, "Submitted"),
+            VerifyJobStatus::Compiled => write!(f, "Compiled"),
+            VerifyJobStatus::CompileFailed => write!(f, "CompileFailed"),
+            VerifyJobStatus::Fail => write!(f, "Fail"),
+            VerifyJobStatus::Success => write!(f, "Success"),
+        }
+    }
+}
+
+/**
+ * Endpoints of the public API, except `LegacyGetClass` which the internal API
+ * serves, see `class_url`.
+ */
+pub enum ApiEndpoints {
+    Capabilities,
+    Corrections,
+    GetClass,
+    LegacyGetClass,
+    KnownDependencies,
+    GetJobStatus,
+    ListJobs,
+    VerifyClass,
+}
+
+impl ApiEndpoints {
+    fn as_str(&self) -> String {
+        match self {
+            ApiEndpoints::Capabilities => "/class-verify/capabilities".to_owned(),
+            ApiEndpoints::Corrections => "/class-verify/{class_hash}/corrections".to_owned(),
+            ApiEndpoints::GetClass => "/classes/{class_hash}".to_owned(),
+            ApiEndpoints::LegacyGetClass => "/api/class/{class_hash}".to_owned(),
+            ApiEndpoints::KnownDependencies => "/class-verify/dependencies".to_owned(),
+            ApiEndpoints::GetJobStatus => "/class-verify/job/{job_id}".to_owned(),
+            ApiEndpoints::ListJobs => "/class-verify/{class_hash}/jobs".to_owned(),
+            ApiEndpoints::VerifyClass => "/class-verify/{class_hash}".to_owned(),
+        }
+    }
+
+    fn to_api_path(&self, param: String) -> String {
+        match self {
+            ApiEndpoints::Capabilities => self.as_str(),
+            ApiEndpoints::Corrections => self.as_str().replace("{class_hash}", param.as_str()),
+            ApiEndpoints::GetClass => self.as_str().replace("{class_hash}", param.as_str()),
+            ApiEndpoints::LegacyGetClass => self.as_str().replace("{class_hash}", param.as_str()),
+            ApiEndpoints::KnownDependencies => self.as_str(),
+            ApiEndpoints::GetJobStatus => self.as_str().replace("{job_id}", param.as_str()),
+            ApiEndpoints::ListJobs => self.as_str().replace("{class_hash}", param.as_str()),
+            ApiEndpoints::VerifyClass => self.as_str().replace("{class_hash}", param.as_str()),
+        }
+    }
+
+    /**
+     * Url of the endpoint on the API at `base`, see `join_api_path`.
+     */
+    pub fn to_api_url(&self, base: &str, param: String) -> Result<Url> {
+        join_api_path(base, &self.to_api_path(param))
+    }
+}
+
+/**
+ * `path` resolved against the API at `base`, keeping the path the API is
+ * served under, e.g. `https://host/verifier`, with or without trailing slashes.
+ */
+pub fn join_api_path(base: &str, path: &str) -> Result<Url> {
+    let mut base = Url::parse(base).map_err(|e| anyhow!("Invalid API url {}: {}", base, e))?;
+    // Without its trailing slash, the last segment of the base would be replaced
+    let base_path = format!("{}/", base.path().trim_end_matches('/'));
+    base.set_path(&base_path);
+    Ok(base.join(path.trim_start_matches('/'))?)
+}
+
+/// Endpoints of the custom network, from the environment, the project's `.env` or the user config.
+pub const CUSTOM_INTERNAL_API_VAR: &str = "CUSTOM_INTERNAL_API_ENDPOINT_URL";
+pub const CUSTOM_PUBLIC_API_VAR: &str = "CUSTOM_PUBLIC_API_ENDPOINT_URL";
+
+/**
+ * Set to `true` to look classes up on the internal API, as releases before the
+ * public class endpoint did, for instances that don't serve it yet.
+ */
+pub const LEGACY_CLASS_ENDPOINT_VAR: &str = "VOYAGER_LEGACY_CLASS_ENDPOINT";
+
+/// API key of instances requiring one, unless given with --api-key.
+pub const API_KEY_VAR: &str = "VOYAGER_API_KEY";
+
+/// How long connecting to the local stack may take before it is considered down.
+const LOCAL_STACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/**
+ * Endpoints of the custom network given with --api-url and --public-api-url.
+ * Those not given are read from the environment, the project's `.env` or the
+ * user config.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CustomEndpoints {
+    pub internal: Option<Url>,
+    pub public: Option<Url>,
+}
+
+impl CustomEndpoints {
+    pub fn is_given(&self) -> bool {
+        self.internal.is_some() || self.public.is_some()
+    }
+
+    /**
+     * The custom network, failing on the first endpoint that is invalid with
+     * every way to configure it. When only one endpoint is configured, it
+     * serves both APIs.
+     */
+    pub fn network(&self) -> Result<Network> {
+        let endpoint = |given: &Option<Url>, endpoint: &str, flag: &str, variable: &str| {
+            if let Some(url) = given {
+                return Ok(Some(url.clone()));
+            }
+            let value = env::var(variable).unwrap_or_default();
+            if value.trim().is_empty() {
+                return Ok(None);
+            }
+            parse_endpoint(&value).map(Some).map_err(|problem| {
+                Error::from(VerifierError::InvalidEndpoint {
+                    network: "custom".to_owned(),
+                    endpoint: endpoint.to_owned(),
+                    flag: flag.to_owned(),
+                    variable: variable.to_owned(),
+                    problem,
+                })
+            })
+        };
+        let internal = endpoint(
+            &self.internal,
+            "internal API",
+            "--api-url",
+            CUSTOM_INTERNAL_API_VAR,
+        )?;
+        let public = endpoint(
+            &self.public,
+            "public API",
+            "--public-api-url",
+            CUSTOM_PUBLIC_API_VAR,
+        )?;
+        match (internal, public) {
+            (Some(internal), Some(public)) => Ok(Network::Custom { internal, public }),
+            (Some(url), None) | (None, Some(url)) => Ok(Network::Custom {
+                internal: url.clone(),
+                public: url,
+            }),
+            // Either endpoint would do
+            (None, None) => Err(VerifierError::InvalidEndpoint {
+                network: "custom".to_owned(),
+                endpoint: "API".to_owned(),
+                flag: "--api-url, --public-api-url".to_owned(),
+                variable: format!("{} or {}", CUSTOM_INTERNAL_API_VAR, CUSTOM_PUBLIC_API_VAR),
+                problem: "not set".to_owned(),
+            }
+            .into()),
+        }
+    }
+
+    /**
+     * The network named `name`, the custom one with these endpoints.
+     */
+    pub fn select(&self, name: &str) -> Result<Network> {
+        if name.eq_ignore_ascii_case("custom") {
+            self.network()
+        } else {
+            Network::from_str(name)
+        }
+    }
+}
+
+pub fn get_network_api(network: Network) -> (String, String) {
+    // Paths starting with `/` are appended to the endpoints
+    let endpoint = |url: &Url| url.as_str().trim_end_matches('/').to_owned();
+    let url = match &network {
+        Network::Mainnet => "https://voyager.online".to_string(),
+        Network::Sepolia => "https://sepolia.voyager.online".to_string(),
+        Network::Local => "http://localhost:8899".to_string(),
+        Network::Custom { internal, .. } => endpoint(internal),
+    };
+
+    let public_url = match &network {
+        Network::Mainnet => "https://api.voyager.online/beta".to_string(),
+        Network::Sepolia => "https://sepolia-api.voyager.online/beta".to_string(),
+        Network::Local => "http://localhost:30380".to_string(),
+        Network::Custom { public, .. } => endpoint(public),
+    };
+
+    (url, public_url)
+}
+
+/**
+ * What is wrong with `url` as an endpoint, `None` when it can be used. Empty
+ * and relative urls would otherwise only fail once a request is built.
+ */
+fn endpoint_problem(url: &str) -> Option<String> {
+    if url.trim().is_empty() {
+        return Some("not set".to_owned());
+    }
+    match Url::parse(url) {
+        std::result::Result::Ok(parsed) if !matches!(parsed.scheme(), "http" | "https") => {
+            Some(format!("`{}`, which is not an http(s) url", url))
+        }
+        std::result::Result::Ok(parsed) if !parsed.has_host() => {
+            Some(format!("`{}`, which has no host", url))
+        }
+        std::result::Result::Ok(_) => None,
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Some(format!("`{}`, which is a relative url", url))
+        }
+        Err(e) => Some(format!("`{}`, which is not a valid url ({})", url, e)),
+    }
+}
+
+/**
+ * Parse an endpoint, failing with what is wrong with it. Used for the flags
+ * too, so that a typo is caught before anything else runs.
+ */
+pub fn parse_endpoint(url: &str) -> std::result::Result<Url, String> {
+    match endpoint_problem(url) {
+        Some(problem) => Err(problem),
+        None => Url::parse(url).map_err(|e| e.to_string()),
+    }
+}
+
+/**
+ * Whether something listens at the host and port of `url`.
+ */
+fn is_reachable(url: &Url) -> bool {
+    url.socket_addrs(|| None)
+        .map(|addresses| {
+            addresses
+                .iter()
+                .any(|address| TcpStream::connect_timeout(address, LOCAL_STACK_TIMEOUT).is_ok())
+        })
+        .unwrap_or(false)
+}
+
+/**
+ * Check the endpoints of `network` once, before any request is made to them.
+ * Custom endpoints are validated when the network is selected, see
+ * `CustomEndpoints`, which leaves the local stack having to be up.
+ */
+pub fn check_endpoints(network: &Network) -> Result<()> {
+    if let Network::Local = network {
+        let (internal, public) = get_network_api(network.clone());
+        for url in [internal, public] {
+            if !is_reachable(&Url::parse(&url)?) {
+                return Err(VerifierError::LocalStackUnreachable(url).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Page of a class on the explorer of the network, `None` when a custom
+ * network doesn't configure one.
+ */
+pub fn class_page_url(network: Network, class_hash: &str) -> Option<String> {
+    let (explorer, _) = get_network_api(network);
+    (!explorer.is_empty())
+        .then(|| format!("{}/class/{}", explorer.trim_end_matches('/'), class_hash))
+}
+
+/**
+ * Code tab of the page of a class on the explorer, where its verified sources
+ * are shown.
+ */
+pub fn class_code_url(network: Network, class_hash: &str) -> Option<String> {
+    class_page_url(network, class_hash).map(|url| format!("{}#code", url))
+}
+
+/**
+ * Url of the job status on the public API of the network, `None` when a
+ * custom network doesn't configure one.
+ */
+pub fn job_status_url(network: Network, job_id: &str) -> Option<String> {
+    let (_, public_url) = get_network_api(network);
+    if public_url.is_empty() {
+        return None;
+    }
+    ApiEndpoints::GetJobStatus
+        .to_api_url(&public_url, job_id.to_owned())
+        .ok()
+        .map(String::from)
+}
+
+/**
+ * The verification endpoint of `network`, with a placeholder for the class hash.
+ */
+pub fn verification_endpoint(network: Network) -> Result<String> {
+    let (_, public_url) = get_network_api(network);
+    // Joined without the placeholder, whose braces would be percent-encoded
+    let url = join_api_path(
+        &public_url,
+        &ApiEndpoints::VerifyClass.to_api_path(String::new()),
+    )?;
+    Ok(format!("{}{{class_hash}}", url))
+}
+
+/**
+ * Url `class_hash` is looked up at: the public API, or the internal one when
+ * `LEGACY_CLASS_ENDPOINT_VAR` is set during the transition.
+ */
+pub fn class_url(network: Network, class_hash: &ClassHash) -> Result<Url> {
+    let legacy = env::var(LEGACY_CLASS_ENDPOINT_VAR)
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false);
+    class_lookup_url(network, class_hash, legacy)
+}
+
+fn class_lookup_url(network: Network, class_hash: &ClassHash, legacy: bool) -> Result<Url> {
+    let (internal_url, public_url) = get_network_api(network);
+    if legacy {
+        ApiEndpoints::LegacyGetClass.to_api_url(&internal_url, class_hash.to_string())
+    } else {
+        ApiEndpoints::GetClass.to_api_url(&public_url, class_hash.to_string())
+    }
+}
+
+/**
+ * Url the verification form of `class_hash` is submitted to.
+ */
+pub fn verification_url(network: Network, class_hash: &ClassHash) -> Result<Url> {
+    let (_, public_url) = get_network_api(network);
+    ApiEndpoints::VerifyClass.to_api_url(&public_url, class_hash.to_string())
+}
+
+/// Consecutive 404s after which a job is considered gone.
+const JOB_NOT_FOUND_POLLS: u32 = 3;
+/// Consecutive unknown statuses after which the job can't be followed.
+const UNKNOWN_STATUS_POLLS: u32 = 3;
+
+/// Header carrying the API key, for instances requiring one.
+const API_KEY_HEADER: &str = "x-api-key";
+
+const UNSUPPORTED_SCHEMA_VERSION_CODE: &str = "unsupported_schema_version";
+const UNSUPPORTED_FORM_STYLE_CODE: &str = "unsupported_form_style";
+
+#[derive(Debug, serde::Deserialize)]
+struct ApiError {
+    error: String,
+    code: Option<String>,
+    supported_schema_versions: Option<Vec<u32>>,
+    supported_form_styles: Option<Vec<String>>,
+    files: Option<Vec<RejectedFile>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct VerificationJobDispatch {
+    job_id: String,
+}
+
+impl VerificationJobDispatch {
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct VerificationJobList {
+    jobs: Vec<VerificationJob>,
+}
+
+/**
+ * A job as the backend reports it. Printed as is with `--json`, the status
+ * as its name.
+ */
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct VerificationJob {
+    job_id: String,
+    #[serde(
+        deserialize_with = "deserialize_status",
+        serialize_with = "serialize_status"
+    )]
+    status: VerifyJobStatus,
+    status_description: Option<String>,
+    class_hash: String,
+    created_timestamp: Option<f64>,
+    updated_timestamp: Option<f64>,
+    address: Option<String>,
+    contract_file: Option<String>,
+    name: Option<String>,
+    version: Option<String>,
+    license: Option<String>,
+}
+
+impl VerificationJob {
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    pub fn status(&self) -> VerifyJobStatus {
+        self.status
+    }
+
+    /// Class hash the backend reports for the job, as received.
+    pub fn class_hash(&self) -> &str {
+        &self.class_hash
+    }
+
+    pub fn status_description(&self) -> Option<&str> {
+        self.status_description.as_deref()
+    }
+
+    pub fn contract_file(&self) -> Option<&str> {
+        self.contract_file.as_deref()
+    }
+
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    /// Unix time the job was submitted at, in seconds.
+    pub fn created_timestamp(&self) -> Option<f64> {
+        self.created_timestamp
+    }
+
+    /// Unix time of the last change of status, in seconds.
+    pub fn updated_timestamp(&self) -> Option<f64> {
+        self.updated_timestamp
+    }
+
+    /**
+     * Time the backend took from the submission of the job to its last change
+     * of status, `None` when it doesn't timestamp its jobs.
+     */
+    pub fn remote_elapsed(&self) -> Option<Duration> {
+        let (created, updated) = self.created_timestamp.zip(self.updated_timestamp)?;
+        Some(Duration::from_secs_f64((updated - created).max(0.0)))
+    }
+
+    /**
+     * The job if it succeeded, its failure otherwise. A job that didn't finish
+     * yet is a `VerifierError::JobInProgress`.
+     */
+    pub fn into_result(self) -> Result<VerificationJob> {
+        match self.status {
+            VerifyJobStatus::Success => Ok(self),
+            VerifyJobStatus::Fail => Err(VerifierError::VerificationFailed(
+                self.status_description
+                    .unwrap_or("unknown failure".to_owned()),
+            )
+            .into()),
+            VerifyJobStatus::CompileFailed => Err(VerifierError::CompilationFailed(
+                self.status_description
+                    .unwrap_or("unknown failure".to_owned()),
+            )
+            .into()),
+            VerifyJobStatus::Submitted | VerifyJobStatus::Compiled => {
+                Err(VerifierError::JobInProgress {
+                    job_id: self.job_id,
+                    status: self.status.to_string(),
+                }
+                .into())
+            }
+        }
+    }
+
+    pub fn metadata(&self) -> JobMetadata {
+        JobMetadata {
+            name: self.name.clone(),
+            license: self.license.clone(),
+            contract_file: self.contract_file.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+/**
+ * Submission metadata echoed back by the backend with the job status. Absent
+ * fields are kept as nulls when serialized so that the JSON layout is stable.
+ */
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct JobMetadata {
+    pub name: Option<String>,
+    pub license: Option<String>,
+    pub contract_file: Option<String>,
+    pub version: Option<String>,
+}
+
+impl JobMetadata {
+    fn fields(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("name", self.name.as_deref()),
+            ("license", self.license.as_deref()),
+            ("contract_file", self.contract_file.as_deref()),
+            ("version", self.version.as_deref()),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.is_none())
+    }
+
+    /**
+     * Fields echoed by the backend that differ from what was submitted, as
+     * `(field, submitted, echoed)`. A mismatch most likely means that we are
+     * polling somebody else's job.
+     */
+    pub fn mismatches<'a>(
+        &'a self,
+        submitted: &'a JobMetadata,
+    ) -> Vec<(&'static str, &'a str, &'a str)> {
+        submitted
+            .fields()
+            .into_iter()
+            .zip(self.fields())
+            .filter_map(
+                |((field, submitted), (_, echoed))| match (submitted, echoed) {
+                    (Some(submitted), Some(echoed)) if submitted != echoed => {
+                        Some((field, submitted, echoed))
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
+
+impl Display for JobMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .fields()
+            .iter()
+            .filter_map(|(field, value)| {
+                value.map(|value| format!("{}: {}", field, sanitize(value)))
+            })
+            .collect();
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+fn body_text(body: &[u8]) -> String {
+    String::from_utf8_lossy(body).into_owned()
+}
+
+/// Characters of an unparsable response quoted in its error.
+const QUOTED_BODY_CHARS: usize = 200;
+
+/**
+ * Error of a response of `endpoint` that didn't parse, quoting the start of
+ * its body, e.g. the HTML page of a proxy.
+ */
+fn unparsable_response(endpoint: &str, body: &[u8], error: serde_json::Error) -> Error {
+    let text = body_text(body);
+    let quoted = match text.char_indices().nth(QUOTED_BODY_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    };
+    anyhow::Error::from(error)
+        .context(format!(
+            "Unexpected response of the {} endpoint: {}",
+            endpoint,
+            sanitize(&quoted)
+        ))
+        .into()
+}
+
+pub fn does_class_exist(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<bool> {
+    block_on(does_class_exist_async(network, class_hash, cancel))
+}
+
+pub fn class_lookup_failed(status: StatusCode, body: &[u8]) -> Error {
+    VerifierError::ClassLookupFailed {
+        status: status.as_u16(),
+        message: body_text(body),
+    }
+    .into()
+}
+
+/**
+ * Fetch the class as the class endpoint returns it, `None` if it isn't declared.
+ * The checks made before submitting all read the one fetched class.
+ */
+pub fn fetch_class(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<Option<serde_json::Value>> {
+    block_on(fetch_class_async(network, class_hash, cancel))
+}
+
+/**
+ * ABI of a fetched class, `None` if it carries none.
+ */
+pub fn abi_from_class(class: &serde_json::Value) -> Option<serde_json::Value> {
+    class.get("abi").filter(|abi| !abi.is_null()).cloned()
+}
+
+/**
+ * Whether the class is declared and verified, with the name, license and
+ * compiler version it was verified with when the backend returns them.
+ */
+pub fn get_class_info(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<ClassInfo> {
+    let class = fetch_class(network, class_hash, cancel)?;
+    Ok(class_info_from_class(class.as_ref()))
+}
+
+/**
+ * Whether the class is verified, from the class endpoint rather than a job.
+ * Returns `None` if the class cannot be found or the backend doesn't say.
+ */
+pub fn fetch_class_verified(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<Option<bool>> {
+    let class = fetch_class(network, class_hash, cancel)?;
+    Ok(class.and_then(|class| class.get("verified").and_then(serde_json::Value::as_bool)))
+}
+
+/**
+ * Verification jobs of the class that haven't finished yet, oldest first.
+ * Backends without the jobs listing report none.
+ */
+pub fn fetch_in_progress_jobs(
+    network: Network,
+    api_key: &str,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<Vec<VerificationJob>> {
+    block_on(fetch_in_progress_jobs_async(
+        network, api_key, class_hash, cancel,
+    ))
+}
+
+/**
+ * Compiler versions a backend accepts, and the optional endpoints it serves,
+ * as listed by its capabilities endpoint.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BackendCapabilities {
+    pub scarb_versions: Vec<String>,
+    pub cairo_versions: Vec<String>,
+    /// Whether it reports the vendored dependencies it has, see `fetch_known_dependencies`.
+    #[serde(default)]
+    pub dependency_fingerprints: bool,
+    /// Whether it takes requests to correct verified metadata, see `submit_correction`.
+    #[serde(default)]
+    pub metadata_corrections: bool,
+    /// Names it expects for the fields of the form, see `dialect::select_dialect`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_dialect: Option<String>,
+}
+
+/**
+ * The compiler versions the backend of `network` accepts, `None` when it
+ * doesn't list them, i.e. an older release without the endpoint. A backend
+ * that can't be reached or rejects the key is an error, not a missing list.
+ */
+pub fn fetch_capabilities(
+    network: Network,
+    api_key: &str,
+    cancel: &CancelToken,
+) -> Result<Option<BackendCapabilities>> {
+    block_on(fetch_capabilities_async(network, api_key, cancel))
+}
+
+/**
+ * The capabilities a backend answered `status` with `body`. Releases
+ * predating the endpoint answer 404.
+ */
+pub(crate) fn capabilities_response(
+    status: StatusCode,
+    body: &[u8],
+    api_key: &str,
+) -> Result<Option<BackendCapabilities>> {
+    match status {
+        StatusCode::OK => serde_json::from_slice(body)
+            .map(Some)
+            .map_err(|e| unparsable_response(CAPABILITIES_ENDPOINT, body, e)),
+        StatusCode::NOT_FOUND => Ok(None),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(api_key_rejected(status, api_key)),
+        status => Err(anyhow!(
+            "The {} endpoint answered {}: {}",
+            CAPABILITIES_ENDPOINT,
+            status,
+            sanitize(&body_text(body))
+        )
+        .into()),
+    }
+}
+
+/**
+ * What happens to each of `dependencies` on `network`: those whose sources
+ * its backend already has are left out of the upload, and all of them are
+ * uploaded when it can't tell.
+ */
+pub fn negotiate_dependencies(
+    network: Network,
+    api_key: &str,
+    dependencies: Vec<VendoredDependency>,
+    cancel: &CancelToken,
+) -> DependencyPlan {
+    if dependencies.is_empty() {
+        return DependencyPlan::default();
+    }
+    let fingerprints: Vec<String> = dependencies
+        .iter()
+        .map(|dependency| dependency.fingerprint.clone())
+        .collect();
+    match fetch_known_dependencies(network, api_key, &fingerprints, cancel) {
+        Ok(known) => DependencyPlan::new(dependencies, known.as_deref()),
+        Err(e) => DependencyPlan::lookup_failed(dependencies, format!("{:#}", e)),
+    }
+}
+
+/**
+ * Which of `fingerprints` the backend of `network` already has the sources
+ * of, `None` when its capabilities don't list the endpoint.
+ */
+pub fn fetch_known_dependencies(
+    network: Network,
+    api_key: &str,
+    fingerprints: &[String],
+    cancel: &CancelToken,
+) -> Result<Option<Vec<String>>> {
+    block_on(fetch_known_dependencies_async(
+        network,
+        api_key,
+        fingerprints,
+        cancel,
+    ))
+}
+
+#[derive(serde::Deserialize)]
+struct KnownDependencies {
+    known: Vec<String>,
+}
+
+/**
+ * The fingerprints a backend answered `status` with `body` it knows, among
+ * those asked about.
+ */
+pub(crate) fn known_dependencies_response(
+    status: StatusCode,
+    body: &[u8],
+    fingerprints: &[String],
+    api_key: &str,
+) -> Result<Vec<String>> {
+    match status {
+        StatusCode::OK => (),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(api_key_rejected(status, api_key))
+        }
+        status => {
+            return Err(anyhow!(
+                "The {} endpoint answered {}: {}",
+                DEPENDENCIES_ENDPOINT,
+                status,
+                sanitize(&body_text(body))
+            )
+            .into())
+        }
+    }
+    let known: KnownDependencies = serde_json::from_slice(body)
+        .map_err(|e| unparsable_response(DEPENDENCIES_ENDPOINT, body, e))?;
+    // Only those asked about can be left out
+    Ok(known
+        .known
+        .into_iter()
+        .filter(|fingerprint| fingerprints.contains(fingerprint))
+        .collect())
+}
+
+/**
+ * Ask the backend of `network` to correct the metadata `request` is about,
+ * returning the id it acknowledged the request with. Only backends listing
+ * `metadata_corrections` in their capabilities serve the endpoint.
+ */
+pub fn submit_correction(
+    network: Network,
+    api_key: &str,
+    request: &CorrectionRequest,
+) -> Result<String> {
+    block_on(submit_correction_async(network, api_key, request))
+}
+
+/**
+ * The id a correction request answered `status` with `body` was acknowledged
+ * with, or why it was refused.
+ */
+pub(crate) fn correction_response(
+    status: StatusCode,
+    body: &[u8],
+    api_key: &str,
+) -> Result<String> {
+    match status {
+        StatusCode::OK | StatusCode::CREATED | StatusCode::ACCEPTED => {
+            serde_json::from_slice::<CorrectionAck>(body)
+                .map(|ack| ack.request_id)
+                .map_err(|e| unparsable_response(CORRECTIONS_ENDPOINT, body, e))
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(api_key_rejected(status, api_key)),
+        _ => Err(VerifierError::CorrectionRejected {
+            status: status.as_u16(),
+            message: serde_json::from_slice::<ApiError>(body)
+                .map(|error| error.error)
+                .unwrap_or_else(|_| body_text(body)),
+        }
+        .into()),
+    }
+}
+
+/**
+ * The jobs a listing answered `status` with `body` has in progress, oldest
+ * first. Backends without the listing answer 404.
+ */
+pub(crate) fn in_progress_jobs_response(
+    status: StatusCode,
+    body: &[u8],
+    api_key: &str,
+) -> Result<Vec<VerificationJob>> {
+    match status {
+        StatusCode::OK => (),
+        StatusCode::NOT_FOUND => return Ok(vec![]),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(api_key_rejected(status, api_key))
+        }
+        status => {
+            return Err(VerifierError::UnexpectedJobStatus {
+                status: status.as_u16(),
+                message: body_text(body),
+            }
+            .into())
+        }
+    }
+
+    let mut jobs = serde_json::from_slice::<VerificationJobList>(body)
+        .map_err(|e| unparsable_response(JOBS_ENDPOINT, body, e))?
+        .jobs;
+    jobs.retain(|job| {
+        matches!(
+            job.status(),
+            VerifyJobStatus::Submitted | VerifyJobStatus::Compiled
+        )
+    });
+    jobs.sort_by(|a, b| {
+        let created = |job: &VerificationJob| job.created_timestamp.unwrap_or_default();
+        created(a).total_cmp(&created(b))
+    });
+    Ok(jobs)
+}
+
+/**
+ * A compiler plugin the package builds with, such as a procedural macro
+ * package with a Rust component. The backend needs its exact version to
+ * rebuild the class.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CairoPlugin {
+    pub name: String,
+    pub version: String,
+    /// Scarb source id, e.g. `registry+https://scarbs.xyz/`.
+    pub source: String,
+    /// From Scarb.lock, which only records it for registry packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl CairoPlugin {
+    /// Plugins of path dependencies only exist on this machine.
+    pub fn is_local(&self) -> bool {
+        self.source.starts_with("path+")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectMetadataInfo {
+    pub cairo_version: SupportedCairoVersions,
+    pub scarb_version: SupportedScarbVersions,
+    pub project_dir_path: String,
+    pub contract_file: String,
+    pub target: StarknetContractTarget,
+    /// Compiler plugins the package builds with, from `scarb metadata`.
+    pub plugins: Vec<CairoPlugin>,
+    /// Vendored dependencies left out of the upload, see `dependencies::DependencyPlan`.
+    pub cached_dependencies: Vec<DependencyRef>,
+}
+
+/**
+ * The verification form for `files` and `docs`, along with the optional parts
+ * of the form it uses.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn build_verification_form(
+    license: &str,
+    name: &str,
+    aliases: &[String],
+    project_metadata: &ProjectMetadataInfo,
+    files: &SourceSet,
+    docs: &SourceSet,
+    form_style: FormStyle,
+    dialect: Dialect,
+    deterministic_body: bool,
+    log: &'static TransferLog,
+) -> Result<(FormBody, FormFeatures)> {
+    let features = FormFeatures {
+        docs: !docs.is_empty(),
+        aliases: !aliases.is_empty(),
+        target: !project_metadata.target.is_default(),
+        plugins: !project_metadata.plugins.is_empty(),
+        cached_dependencies: !project_metadata.cached_dependencies.is_empty(),
+    };
+    let body = FormSubmission {
+        fields: form_metadata_fields(
+            license,
+            name,
+            aliases,
+            project_metadata,
+            features.docs,
+            dialect,
+        ),
+        files: files.to_files(),
+        docs: docs.to_files(),
+    }
+    .into_body(form_style, deterministic_body, log)?;
+    Ok((body, features))
+}
+
+/**
+ * Attach the API key to a request of the verification API, if there is one.
+ * The public endpoints don't need any, requests are then sent as before.
+ */
+pub fn with_api_key(request: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+    if api_key.is_empty() {
+        request
+    } else {
+        request.header(API_KEY_HEADER, api_key)
+    }
+}
+
+pub fn api_key_rejected(status: StatusCode, api_key: &str) -> Error {
+    VerifierError::ApiKeyRejected {
+        status: status.as_u16(),
+        provided: !api_key.is_empty(),
+    }
+    .into()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn dispatch_class_verification_job(
+    api_key: &str,
+    network: Network,
+    class_hash: &ClassHash,
+    license: &str,
+    name: &str,
+    aliases: &[String],
+    project_metadata: &ProjectMetadataInfo,
+    files: &SourceSet,
+    docs: &SourceSet,
+    form_style: FormStyle,
+    dialect: Dialect,
+    deterministic_body: bool,
+    cancel: &CancelToken,
+) -> Result<String> {
+    block_on(dispatch_class_verification_job_async(
+        api_key,
+        network,
+        class_hash,
+        license,
+        name,
+        aliases,
+        project_metadata,
+        files,
+        docs,
+        form_style,
+        dialect,
+        deterministic_body,
+        cancel,
+    ))
+}
+
+/**
+ * The job id of a verification form for `class_hash` on `network` answered
+ * `status` with `body`, or why the backend refused it.
+ */
+pub fn dispatch_response(
+    status: StatusCode,
+    body: &[u8],
+    api_key: &str,
+    features: FormFeatures,
+    form_style: FormStyle,
+    class_hash: &ClassHash,
+    network: &Network,
+) -> Result<String> {
+    match status {
+        StatusCode::OK => (),
+        // The backend only verifies declared classes
+        StatusCode::NOT_FOUND => {
+            return Err(VerifierError::ClassNotFound {
+                class_hash: class_hash.to_string(),
+                network: network.to_string(),
+            }
+            .into());
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(api_key_rejected(status, api_key));
+        }
+        // Answered by the proxy in front of the backend, the body is its HTML page
+        StatusCode::PAYLOAD_TOO_LARGE => {
+            return Err(VerifierError::UploadRejectedTooLarge.into());
+        }
+        StatusCode::BAD_REQUEST => {
+            let err_response = serde_json::from_slice::<ApiError>(body)
+                .map_err(|e| unparsable_response(VERIFY_ENDPOINT, body, e))?;
+
+            if err_response.code.as_deref() == Some(UNSUPPORTED_SCHEMA_VERSION_CODE) {
+                let supported = err_response.supported_schema_versions.unwrap_or_default();
+                return Err(VerifierError::UnsupportedSchemaVersion {
+                    description: describe_schema_version_mismatch(&supported, features),
+                    supported,
+                }
+                .into());
+            }
+
+            if err_response.code.as_deref() == Some(UNSUPPORTED_FORM_STYLE_CODE) {
+                return Err(VerifierError::UnsupportedFormStyle {
+                    style: form_style.to_string(),
+                    supported: err_response.supported_form_styles.unwrap_or_default(),
+                }
+                .into());
+            }
+
+            if let Some(files) = err_response.files.filter(|files| !files.is_empty()) {
+                return Err(VerifierError::FilesRejected {
+                    error: err_response.error,
+                    files: format_rejected_files(&files),
+                }
+                .into());
+            }
+
+            // Older backends reject newer styles without saying why
+            let message = match form_style {
+                FormStyle::Legacy => err_response.error,
+                _ => format!(
+                    "{}\nIf this is a self-hosted instance running an older backend release, retry with --form-style legacy.",
+                    err_response.error
+                ),
+            };
+            return Err(VerifierError::DispatchFailed {
+                status: 400,
+                message,
+            }
+            .into());
+        }
+        unknown_status_code => {
+            return Err(VerifierError::DispatchFailed {
+                status: unknown_status_code.as_u16(),
+                message: body_text(body),
+            }
+            .into());
+        }
+    }
+
+    let data = serde_json::from_slice::<VerificationJobDispatch>(body)
+        .map_err(|e| unparsable_response(VERIFY_ENDPOINT, body, e))?;
+
+    Ok(data.job_id)
+}
+
+pub fn poll_verification_status(
+    api_key: &str,
+    network: Network,
+    job_id: &str,
+    polling: &PollingConfig,
+    cancel: &CancelToken,
+    on_update: &mut dyn FnMut(PollUpdate),
+) -> Result<VerificationJob> {
+    block_on(poll_verification_status_async(
+        api_key, network, job_id, polling, cancel, on_update,
+    ))
+}
+
+/**
+ * Fetch the state of a job once, without waiting for it to finish like
+ * `poll_verification_status` does.
+ */
+pub fn get_job_status(
+    network: Network,
+    api_key: &str,
+    job_id: &str,
+    cancel: &CancelToken,
+) -> Result<VerificationJob> {
+    block_on(get_job_status_async(network, api_key, job_id, cancel))
+}
+
+/**
+ * The job a status request answered `status` with `body`.
+ */
+pub(crate) fn job_status_response(
+    status: StatusCode,
+    body: &[u8],
+    api_key: &str,
+) -> Result<VerificationJob> {
+    match status {
+        StatusCode::OK => (),
+        StatusCode::NOT_FOUND => return Err(VerifierError::JobNotFound.into()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(api_key_rejected(status, api_key))
+        }
+        unknown_status_code => {
+            return Err(VerifierError::UnexpectedJobStatus {
+                status: unknown_status_code.as_u16(),
+                message: body_text(body),
+            }
+            .into());
+        }
+    }
+    match serde_json::from_slice::<VerificationJob>(body) {
+        std::result::Result::Ok(job) => Ok(job),
+        Err(e) => match unknown_status(body) {
+            Some(status) => Err(VerifierError::UnknownJobStatus(status).into()),
+            None => Err(unparsable_response(JOB_STATUS_ENDPOINT, body, e)),
+        },
+    }
+}
+
+/**
+ * Status byte of a job status response that failed to parse because this CLI
+ * doesn't know it, see `UnknownJobStatus`.
+ */
+fn unknown_status(body: &[u8]) -> Option<u8> {
+    let status = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()?
+        .get("status")?
+        .as_u64()?;
+    VerifyJobStatus::try_from(u8::try_from(status).ok()?)
+        .err()
+        .map(|UnknownJobStatus(status)| status)
+}
+
+/**
+ * Drive the async `request` from this thread, on a runtime of its own. The
+ * blocking requests are the async ones run this way, so that both behave the
+ * same, and a cancelled request is dropped, which aborts it.
+ */
+fn block_on<T>(request: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)?
+        .block_on(request)
+}
+
+pub fn poll_timed_out(job_id: &str, waited: Duration) -> Error {
+    VerifierError::Timeout {
+        job_id: job_id.to_owned(),
+        waited_secs: waited.as_secs(),
+    }
+    .into()
+}
+
+/**
+ * What the polling of a job reports as it goes, for callers to show. The
+ * library prints nothing itself.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum PollUpdate<'a> {
+    /// A status received, the last one once the job finished.
+    Job(&'a VerificationJob),
+    /// A status this client doesn't know, likely added to the backend since.
+    /// Polling goes on, up to a few of them in a row.
+    UnknownStatus(u8),
+    /// The job went back to an earlier status, restarted by the server, and
+    /// is reported right after with that status.
+    Restarted(VerifyJobStatus),
+}
+
+/**
+ * What the polling of a job keeps between status responses. The blocking
+ * and the async loops share it, they only differ in how they send and wait.
+ */
+#[derive(Debug, Default)]
+pub struct JobPoll {
+    pub negotiation: PollNegotiation,
+    tracker: StatusTracker,
+    not_found: u32,
+    unknown: u32,
+}
+
+impl JobPoll {
+    /// Whether `observe` needs the body of a response answered `status`.
+    pub fn reads_body(status: StatusCode) -> bool {
+        !matches!(
+            status,
+            StatusCode::NOT_MODIFIED
+                | StatusCode::NOT_FOUND
+                | StatusCode::UNAUTHORIZED
+                | StatusCode::FORBIDDEN
+        )
+    }
+
+    /**
+     * Go through a status response, returning the job once it finished.
+     * A job restarted by the server gets the restart grace of `polling`.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn observe<C: Clock + ?Sized>(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+        api_key: &str,
+        polling: &PollingConfig,
+        schedule: &mut PollSchedule<C>,
+        on_update: &mut dyn FnMut(PollUpdate),
+    ) -> Result<Option<VerificationJob>> {
+        match status {
+            // A 304 is unchanged since the last response, there is no body to parse
+            StatusCode::OK | StatusCode::NOT_MODIFIED => self.not_found = 0,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(api_key_rejected(status, api_key))
+            }
+            // The record may not be visible yet, or already garbage-collected
+            StatusCode::NOT_FOUND => {
+                self.not_found += 1;
+                if self.not_found >= JOB_NOT_FOUND_POLLS {
+                    return Err(VerifierError::JobNotFound.into());
+                }
+            }
+            unknown_status_code => {
+                return Err(VerifierError::UnexpectedJobStatus {
+                    status: unknown_status_code.as_u16(),
+                    message: body_text(body.unwrap_or_default()),
+                }
+                .into());
+            }
+        }
+
+        let Some(body) = body.filter(|_| status == StatusCode::OK) else {
+            return Ok(None);
+        };
+        self.negotiation = PollNegotiation::from_headers(headers);
+        let data = match serde_json::from_slice::<VerificationJob>(body) {
+            std::result::Result::Ok(data) => {
+                self.unknown = 0;
+                data
+            }
+            // A status added to the backend since may be followed by a known one
+            Err(e) => match unknown_status(body) {
+                Some(status) => {
+                    self.unknown += 1;
+                    if self.unknown >= UNKNOWN_STATUS_POLLS {
+                        return Err(VerifierError::UnknownJobStatus(status).into());
+                    }
+                    on_update(PollUpdate::UnknownStatus(status));
+                    return Ok(None);
+                }
+                None => return Err(unparsable_response(JOB_STATUS_ENDPOINT, body, e)),
+            },
+        };
+
+        // Go through the possible status
+        if self.tracker.observe(data.status()) == Transition::Regressed {
+            on_update(PollUpdate::Restarted(data.status()));
+            schedule.extend(polling.restart_grace);
+        }
+        on_update(PollUpdate::Job(&data));
+        if matches!(
+            data.status,
+            VerifyJobStatus::Submitted | VerifyJobStatus::Compiled
+        ) {
+            return Ok(None);
+        }
+        Ok(Some(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corrections::CorrectionField;
+    use crate::dependencies::DependencyStatus;
+    use crate::errors::error_code;
+    use crate::mock_server::{MockResponse, MockServer, RecordedRequest};
+    use crate::polling::LONG_POLL_HEADER;
+    use crate::resubmit::{with_resubmission, TransientFailures};
+    use crate::transfer;
+    use camino::Utf8PathBuf;
+    use std::{env, fs};
+
+    // The requests of `api_async`, driven like the blocking requests drive them
+
+    #[allow(clippy::too_many_arguments)]
+    fn submit_verification_form(
+        network: &Network,
+        class_hash: &ClassHash,
+        api_key: &str,
+        body: FormBody,
+        features: FormFeatures,
+        form_style: FormStyle,
+        log: &'static TransferLog,
+        cancel: &CancelToken,
+    ) -> Result<String> {
+        block_on(crate::api_async::submit_verification_form(
+            network, class_hash, api_key, body, features, form_style, log, cancel,
+        ))
+    }
+
+    fn poll_job_status(
+        url: &str,
+        api_key: &str,
+        job_id: &str,
+        polling: &PollingConfig,
+        log: &'static TransferLog,
+        cancel: &CancelToken,
+        on_update: &mut dyn FnMut(PollUpdate),
+    ) -> Result<VerificationJob> {
+        block_on(crate::api_async::poll_job_status(
+            url, api_key, job_id, polling, log, cancel, on_update,
+        ))
+    }
+
+    fn fetch_job_status(
+        url: &str,
+        api_key: &str,
+        log: &'static TransferLog,
+    ) -> Result<VerificationJob> {
+        block_on(crate::api_async::fetch_job_status(
+            url,
+            api_key,
+            log,
+            &CancelToken::new(),
+        ))
+    }
+
+    fn list_in_progress_jobs(
+        url: &str,
+        api_key: &str,
+        log: &'static TransferLog,
+    ) -> Result<Vec<VerificationJob>> {
+        block_on(crate::api_async::list_in_progress_jobs(
+            url,
+            api_key,
+            log,
+            &CancelToken::new(),
+        ))
+    }
+
+    fn request_capabilities(
+        url: &str,
+        api_key: &str,
+        log: &'static TransferLog,
+    ) -> Result<Option<BackendCapabilities>> {
+        block_on(crate::api_async::request_capabilities(
+            url,
+            api_key,
+            log,
+            &CancelToken::new(),
+        ))
+    }
+
+    fn job(status: u8) -> String {
+        format!(
+            r#"{{"job_id": "job", "status": {}, "class_hash": "0x1"}}"#,
+            status
+        )
+    }
+
+    fn polling(interval: Duration) -> PollingConfig {
+        PollingConfig {
+            interval,
+            timeout: Some(Duration::from_secs(60)),
+            restart_grace: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_job_metadata_mismatches() {
+        let submitted = JobMetadata {
+            name: Some("MyToken".to_owned()),
+            license: Some("MIT License (MIT)".to_owned()),
+            contract_file: Some("my_token/src/lib.cairo".to_owned()),
+            version: Some("2.8.4".to_owned()),
+        };
+        let echoed = JobMetadata {
+            name: Some("OtherToken".to_owned()),
+            license: None,
+            ..submitted.clone()
+        };
+
+        assert!(submitted.mismatches(&submitted).is_empty());
+        assert_eq!(
+            echoed.mismatches(&submitted),
+            vec![("name", "MyToken", "OtherToken")]
+        );
+        assert_eq!(
+            echoed.to_string(),
+            "name: OtherToken, contract_file: my_token/src/lib.cairo, version: 2.8.4"
+        );
+    }
+
+    #[test]
+    fn test_job_metadata_serializes_nulls() {
+        let json = serde_json::to_value(JobMetadata::default()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": null,
+                "license": null,
+                "contract_file": null,
+                "version": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_job_serializes_status_name() {
+        let job: VerificationJob = serde_json::from_str(
+            r#"{"job_id": "job", "status": 2, "class_hash": "0x1", "status_description": "unknown module token", "created_timestamp": 1700000000.5}"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&job).unwrap();
+        assert_eq!(json["status"], "CompileFailed");
+        assert_eq!(json["status_description"], "unknown module token");
+        assert_eq!(json["created_timestamp"], 1700000000.5);
+        assert_eq!(json["updated_timestamp"], serde_json::Value::Null);
+        assert_eq!(json["job_id"], "job");
+        assert_eq!(job.remote_elapsed(), None);
+    }
+
+    #[test]
+    fn test_remote_elapsed_spans_the_job_timestamps() {
+        let job: VerificationJob = serde_json::from_str(
+            r#"{"job_id": "job", "status": 4, "class_hash": "0x1", "created_timestamp": 1700000000.5, "updated_timestamp": 1700000042.5}"#,
+        )
+        .unwrap();
+        assert_eq!(job.remote_elapsed(), Some(Duration::from_secs(42)));
+        assert_eq!(
+            class_code_url(Network::Sepolia, "0x1").as_deref(),
+            Some("https://sepolia.voyager.online/class/0x1#code")
+        );
+    }
+
+    #[test]
+    fn test_invalid_endpoints_are_explained() {
+        assert_eq!(endpoint_problem("https://api.voyager.online/beta"), None);
+        assert_eq!(endpoint_problem("http://localhost:3030"), None);
+        assert_eq!(endpoint_problem("").as_deref(), Some("not set"));
+        assert_eq!(endpoint_problem("  ").as_deref(), Some("not set"));
+        assert_eq!(
+            endpoint_problem("/beta").as_deref(),
+            Some("`/beta`, which is a relative url")
+        );
+        assert_eq!(
+            endpoint_problem("ftp://example.com").as_deref(),
+            Some("`ftp://example.com`, which is not an http(s) url")
+        );
+        // Without its scheme, the host is taken for one
+        assert_eq!(
+            endpoint_problem("localhost:3030").as_deref(),
+            Some("`localhost:3030`, which is not an http(s) url")
+        );
+        assert_eq!(
+            endpoint_problem("http://").as_deref(),
+            Some("`http://`, which is not a valid url (empty host)")
+        );
+
+        assert_eq!(
+            parse_endpoint("http://localhost:3030/").unwrap().as_str(),
+            "http://localhost:3030/"
+        );
+        assert!(parse_endpoint("localhost:3030").is_err());
+
+        // The built-in networks are always valid
+        check_endpoints(&Network::Mainnet).unwrap();
+        check_endpoints(&Network::Sepolia).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_endpoint_lists_every_way_to_configure_it() {
+        let error = VerifierError::InvalidEndpoint {
+            network: "custom".to_owned(),
+            endpoint: "public API".to_owned(),
+            flag: "--public-api-url".to_owned(),
+            variable: CUSTOM_PUBLIC_API_VAR.to_owned(),
+            problem: endpoint_problem("").unwrap(),
+        };
+        let message = error.to_string();
+        assert!(message
+            .starts_with("VER-044: The public API endpoint of the custom network is not set."));
+        for way in [
+            "--public-api-url",
+            "--network <BASE_URL>",
+            "CUSTOM_PUBLIC_API_ENDPOINT_URL in the environment",
+            ".env file of the project",
+            "user config.env",
+        ] {
+            assert!(message.contains(way), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_local_stack_reachability() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        assert!(is_reachable(&url));
+        drop(listener);
+        assert!(!is_reachable(&url));
+    }
+
+    #[test]
+    fn test_poll_with_conditional_get() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(0)).header("etag", "\"v1\""),
+            MockResponse::new(304, ""),
+            MockResponse::new(200, &job(4)).header("etag", "\"v2\""),
+        ]);
+
+        let mut updates = 0;
+        let job = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| updates += 1,
+        )
+        .unwrap();
+        let requests = server.finish();
+
+        assert!(matches!(job.status(), VerifyJobStatus::Success));
+        // The 304 is not reported as an update
+        assert_eq!(updates, 2);
+        assert_eq!(requests[0].header("if-none-match"), None);
+        assert_eq!(requests[1].header("if-none-match"), Some("\"v1\""));
+        assert_eq!(requests[2].header("if-none-match"), Some("\"v1\""));
+        assert!(!requests[1].path.contains("wait="));
+    }
+
+    #[test]
+    fn test_poll_with_long_polling() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(0)).header(LONG_POLL_HEADER, "60"),
+            MockResponse::new(200, &job(1)).header(LONG_POLL_HEADER, "60"),
+            MockResponse::new(200, &job(4)),
+        ]);
+
+        // A long interval shows that held requests are not followed by a sleep
+        let started = std::time::Instant::now();
+        poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::from_secs(60)),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap();
+        let requests = server.finish();
+
+        assert!(started.elapsed() < Duration::from_secs(30));
+        assert!(!requests[0].path.contains("wait="));
+        assert!(requests[1].path.ends_with("?wait=30s"));
+        assert!(requests[2].path.ends_with("?wait=30s"));
+    }
+
+    #[test]
+    fn test_unknown_status_fails_to_deserialize() {
+        let error = serde_json::from_str::<VerificationJob>(&job(7)).unwrap_err();
+        assert!(
+            error.to_string().contains("unknown job status 7"),
+            "{}",
+            error
+        );
+        assert_eq!(unknown_status(job(7).as_bytes()), Some(7));
+        assert_eq!(unknown_status(job(1).as_bytes()), None);
+        assert_eq!(
+            VerifyJobStatus::try_from(4).unwrap(),
+            VerifyJobStatus::Success
+        );
+        assert_eq!(
+            VerifyJobStatus::try_from(7).unwrap_err(),
+            UnknownJobStatus(7)
+        );
+    }
+
+    #[test]
+    fn test_single_status_check() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(1)),
+            MockResponse::new(200, &job(3)),
+            MockResponse::new(404, ""),
+        ]);
+        let url = format!("{}/class-verify/job/job", server.url);
+
+        let compiled = fetch_job_status(&url, "", transfer::global()).unwrap();
+        assert_eq!(compiled.status(), VerifyJobStatus::Compiled);
+        let error = compiled.into_result().unwrap_err();
+        assert_eq!(error.code(), Some("VER-041"));
+
+        let failed = fetch_job_status(&url, "", transfer::global()).unwrap();
+        assert_eq!(failed.into_result().unwrap_err().code(), Some("VER-011"));
+
+        let error = fetch_job_status(&url, "", transfer::global()).unwrap_err();
+        assert_eq!(error.code(), Some("VER-009"));
+        // A single request, even when the job isn't found
+        assert_eq!(server.finish().len(), 3);
+    }
+
+    #[test]
+    fn test_capabilities_are_optional() {
+        let server = MockServer::start(vec![
+            MockResponse::new(
+                200,
+                r#"{"scarb_versions": ["2.8.4"], "cairo_versions": ["2.8.4", "2.9.1"]}"#,
+            ),
+            MockResponse::new(404, ""),
+            MockResponse::new(401, ""),
+            MockResponse::new(200, "<html></html>"),
+        ]);
+        let url = format!("{}/class-verify/capabilities", server.url);
+
+        let capabilities = request_capabilities(&url, "secret", transfer::global())
+            .unwrap()
+            .unwrap();
+        assert_eq!(capabilities.scarb_versions, ["2.8.4"]);
+        assert_eq!(capabilities.cairo_versions, ["2.8.4", "2.9.1"]);
+        // Backends predating the endpoint
+        assert_eq!(
+            request_capabilities(&url, "", transfer::global()).unwrap(),
+            None
+        );
+        // Failing to tell is not the same as not listing them
+        let rejected = request_capabilities(&url, "secret", transfer::global()).unwrap_err();
+        assert_eq!(rejected.code(), Some("VER-046"));
+        assert!(request_capabilities(&url, "", transfer::global()).is_err());
+        let requests = server.finish();
+        assert_eq!(requests[0].header(API_KEY_HEADER), Some("secret"));
+        assert_eq!(requests[1].header(API_KEY_HEADER), None);
+    }
+
+    #[test]
+    fn test_capabilities_are_retried() {
+        let server = MockServer::start(vec![
+            MockResponse::new(503, ""),
+            MockResponse::new(200, r#"{"scarb_versions": [], "cairo_versions": []}"#),
+        ]);
+        let url = format!("{}/class-verify/capabilities", server.url);
+
+        let capabilities = request_capabilities(&url, "", transfer::global()).unwrap();
+        assert!(capabilities.is_some());
+        assert_eq!(server.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_dependencies_known_to_the_backend_are_skipped() {
+        let dependency = |name: &str| VendoredDependency {
+            name: name.to_owned(),
+            version: "1.0.0".to_owned(),
+            directory: name.to_owned(),
+            fingerprint: format!("{}-fingerprint", name),
+            files: vec![format!("{}/Scarb.toml", name)],
+            size: 64,
+        };
+        let server = MockServer::start(vec![
+            MockResponse::new(
+                200,
+                r#"{"scarb_versions": [], "cairo_versions": [], "dependency_fingerprints": true}"#,
+            ),
+            MockResponse::new(
+                200,
+                r#"{"known": ["openzeppelin-fingerprint", "unrelated-fingerprint"]}"#,
+            ),
+        ]);
+
+        let plan = negotiate_dependencies(
+            server.network(),
+            "secret",
+            vec![dependency("alexandria"), dependency("openzeppelin")],
+            &CancelToken::new(),
+        );
+        let statuses: Vec<(&str, DependencyStatus)> = plan
+            .dependencies
+            .iter()
+            .map(|(dependency, status)| (dependency.name.as_str(), *status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("alexandria", DependencyStatus::NotCached),
+                ("openzeppelin", DependencyStatus::Cached)
+            ]
+        );
+        assert_eq!(plan.skipped(), [dependency("openzeppelin").reference()]);
+
+        let requests = server.finish();
+        assert_eq!(requests[1].path, "/class-verify/dependencies");
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(
+            body["fingerprints"],
+            serde_json::json!(["alexandria-fingerprint", "openzeppelin-fingerprint"])
+        );
+    }
+
+    #[test]
+    fn test_correction_request_is_acknowledged() {
+        let server = MockServer::start(vec![
+            MockResponse::new(202, r#"{"request_id": "corr-42"}"#),
+            MockResponse::new(
+                400,
+                r#"{"error": "A correction for this class is already under review"}"#,
+            ),
+        ]);
+        let request = CorrectionRequest {
+            class_hash: "0x1".to_owned(),
+            field: CorrectionField::Name,
+            proposed_value: "MyToken".to_owned(),
+            justification: "Verified under the name of another project".to_owned(),
+            contact: "security@example.com".to_owned(),
+        };
+
+        let request_id = submit_correction(server.network(), "key", &request).unwrap();
+        let error = submit_correction(server.network(), "key", &request).unwrap_err();
+        let requests = server.finish();
+
+        assert_eq!(request_id, "corr-42");
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::CorrectionRejected { status: 400, message })
+                if message.contains("already under review")
+        ));
+        assert_eq!(requests[0].path, "/class-verify/0x1/corrections");
+        assert_eq!(requests[0].header("x-api-key"), Some("key"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["field"], "name");
+        assert_eq!(body["proposed_value"], "MyToken");
+    }
+
+    #[test]
+    fn test_dependencies_are_vendored_without_the_capability() {
+        let dependency = VendoredDependency {
+            name: "openzeppelin".to_owned(),
+            version: "0.20.0".to_owned(),
+            directory: "openzeppelin".to_owned(),
+            fingerprint: "5d3a".to_owned(),
+            files: vec![],
+            size: 0,
+        };
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"scarb_versions": [], "cairo_versions": []}"#),
+            MockResponse::new(404, ""),
+        ]);
+
+        for _ in 0..2 {
+            let plan = negotiate_dependencies(
+                server.network(),
+                "",
+                vec![dependency.clone()],
+                &CancelToken::new(),
+            );
+            assert_eq!(plan.dependencies[0].1, DependencyStatus::Unsupported);
+            assert_eq!(plan.lookup_error, None);
+        }
+        // Only the capabilities were asked for
+        assert_eq!(server.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_dependencies_are_vendored_when_the_lookup_fails() {
+        let dependency = VendoredDependency {
+            name: "openzeppelin".to_owned(),
+            version: "0.20.0".to_owned(),
+            directory: "openzeppelin".to_owned(),
+            fingerprint: "5d3a".to_owned(),
+            files: vec![],
+            size: 0,
+        };
+        let server = MockServer::start(vec![
+            MockResponse::new(
+                200,
+                r#"{"scarb_versions": [], "cairo_versions": [], "dependency_fingerprints": true}"#,
+            ),
+            MockResponse::new(403, ""),
+        ]);
+
+        let plan = negotiate_dependencies(
+            server.network(),
+            "secret",
+            vec![dependency],
+            &CancelToken::new(),
+        );
+        let requests = server.finish();
+
+        assert_eq!(plan.dependencies[0].1, DependencyStatus::LookupFailed);
+        assert!(plan.skipped().is_empty());
+        assert!(plan.lookup_error.unwrap().contains("API key"));
+        assert_eq!(requests[1].header(API_KEY_HEADER), Some("secret"));
+    }
+
+    #[test]
+    fn test_job_status_url() {
+        assert_eq!(
+            job_status_url(Network::Sepolia, "abc").as_deref(),
+            Some("https://sepolia-api.voyager.online/beta/class-verify/job/abc")
+        );
+    }
+
+    #[test]
+    fn test_poll_timeout_names_the_job() {
+        let server = MockServer::start(vec![MockResponse::new(200, &job(0))]);
+        let error = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &PollingConfig {
+                interval: Duration::from_secs(5),
+                timeout: Some(Duration::ZERO),
+                restart_grace: Duration::ZERO,
+            },
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        server.finish();
+
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::Timeout { job_id, .. }) if job_id == "job"
+        ));
+    }
+
+    /// A token cancelled from another thread after `delay`.
+    fn cancelled_after(delay: Duration) -> CancelToken {
+        let token = CancelToken::new();
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            canceller.cancel();
+        });
+        token
+    }
+
+    fn cancelled_job(error: &Error) -> Option<Option<&str>> {
+        match error.verifier_error() {
+            Some(VerifierError::Cancelled { job_id }) => Some(job_id.as_deref()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_cancel_during_upload() {
+        // Accepts the connection but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = parse_endpoint(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let network = Network::Custom {
+            internal: url.clone(),
+            public: url,
+        };
+        let form = FormSubmission::default()
+            .into_body(FormStyle::Parts, false, transfer::global())
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let error = submit_verification_form(
+            &network,
+            &"0x1".parse().unwrap(),
+            "",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            transfer::global(),
+            &cancelled_after(Duration::from_millis(100)),
+        )
+        .unwrap_err();
+
+        assert_eq!(cancelled_job(&error), Some(None));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_cancel_between_polls() {
+        let server = MockServer::start(vec![MockResponse::new(200, &job(0))]);
+
+        let started = std::time::Instant::now();
+        let error = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            // No timeout, which would end the polling before the first wait
+            &PollingConfig {
+                timeout: None,
+                ..polling(Duration::from_secs(60))
+            },
+            transfer::global(),
+            &cancelled_after(Duration::from_millis(100)),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        server.finish();
+
+        // Dispatched, so the job may still complete and is named
+        assert_eq!(cancelled_job(&error), Some(Some("job")));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(error.to_string().contains("status job"), "{}", error);
+    }
+
+    #[test]
+    fn test_cancel_during_rate_limit_wait() {
+        let server =
+            MockServer::start(vec![MockResponse::new(429, "").header("retry-after", "60")]);
+        let form = FormSubmission::default()
+            .into_body(FormStyle::Parts, false, transfer::global())
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let error = submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            transfer::global(),
+            &cancelled_after(Duration::from_millis(100)),
+        )
+        .unwrap_err();
+        server.finish();
+
+        // Well before the 60s asked for
+        assert_eq!(cancelled_job(&error), Some(None));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_poll_tolerates_a_few_unknown_statuses() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(0)),
+            MockResponse::new(200, &job(7)),
+            MockResponse::new(200, &job(7)),
+            MockResponse::new(200, &job(4)),
+        ]);
+        let mut updates = vec![];
+        let polled = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |update| {
+                updates.push(match update {
+                    PollUpdate::Job(job) => job.status().to_string(),
+                    PollUpdate::UnknownStatus(status) => format!("unknown {}", status),
+                    PollUpdate::Restarted(status) => format!("restarted {}", status),
+                })
+            },
+        )
+        .unwrap();
+        server.finish();
+
+        assert_eq!(polled.status(), VerifyJobStatus::Success);
+        // Unknown statuses are reported apart from the jobs
+        assert_eq!(
+            updates,
+            [
+                VerifyJobStatus::Submitted.to_string(),
+                "unknown 7".to_owned(),
+                "unknown 7".to_owned(),
+                VerifyJobStatus::Success.to_string(),
+            ]
+        );
+
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(7)),
+            MockResponse::new(200, &job(8)),
+            MockResponse::new(200, &job(7)),
+        ]);
+        let error = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        server.finish();
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::UnknownJobStatus(7))
+        ));
+    }
+
+    #[test]
+    fn test_poll_reports_a_restarted_job() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(1)),
+            MockResponse::new(200, &job(0)),
+            MockResponse::new(200, &job(4)),
+        ]);
+        let mut restarts = vec![];
+        poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |update| {
+                if let PollUpdate::Restarted(status) = update {
+                    restarts.push(status);
+                }
+            },
+        )
+        .unwrap();
+        server.finish();
+        assert_eq!(restarts, [VerifyJobStatus::Submitted]);
+    }
+
+    #[test]
+    fn test_in_progress_jobs_are_listed_oldest_first() {
+        let jobs = r#"{"jobs": [
+            {"job_id": "newer", "status": 1, "class_hash": "0x1", "created_timestamp": 20.0},
+            {"job_id": "done", "status": 4, "class_hash": "0x1", "created_timestamp": 5.0},
+            {"job_id": "older", "status": 0, "class_hash": "0x1", "created_timestamp": 10.0}
+        ]}"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, jobs),
+            MockResponse::new(404, "Not Found"),
+        ]);
+
+        let url = format!("{}/class-verify/0x1/jobs", server.url);
+        let listed = list_in_progress_jobs(&url, "secret", transfer::global()).unwrap();
+        // Backends without the listing have nothing to attach to
+        let unsupported = list_in_progress_jobs(&url, "", transfer::global()).unwrap();
+        let requests = server.finish();
+        assert_eq!(requests[0].header(API_KEY_HEADER), Some("secret"));
+        assert_eq!(requests[1].header(API_KEY_HEADER), None);
+
+        let ids: Vec<&str> = listed.iter().map(VerificationJob::job_id).collect();
+        assert_eq!(ids, vec!["older", "newer"]);
+        assert!(unsupported.is_empty());
+    }
+
+    #[test]
+    fn test_job_is_gone_after_persistent_not_found() {
+        let server = MockServer::start(vec![
+            MockResponse::new(404, "Not Found"),
+            MockResponse::new(200, &job(1)),
+            MockResponse::new(404, "Not Found"),
+            MockResponse::new(404, "Not Found"),
+            MockResponse::new(404, "Not Found"),
+        ]);
+        let error = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        // A 404 in between statuses isn't enough
+        assert_eq!(server.finish().len(), 5);
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::JobNotFound)
+        ));
+    }
+
+    fn failed_job(description: &str) -> String {
+        format!(
+            r#"{{"job_id": "job", "status": 3, "status_description": "{}", "class_hash": "0x1"}}"#,
+            description
+        )
+    }
+
+    /**
+     * Submit and poll against the mock server until the job completes, at most
+     * `max_resubmissions` times more after transient failures.
+     */
+    fn verify_with_resubmission(
+        server: &MockServer,
+        max_resubmissions: u32,
+    ) -> (anyhow::Result<VerificationJob>, Vec<(u32, String)>) {
+        let mut attempts = vec![];
+        let attempt = |n: u32| {
+            let form =
+                FormSubmission::default().into_body(FormStyle::Parts, false, transfer::global())?;
+            let job_id = submit_verification_form(
+                &server.network(),
+                &"0x1".parse().unwrap(),
+                "",
+                form,
+                FormFeatures::default(),
+                FormStyle::Parts,
+                transfer::global(),
+                &CancelToken::new(),
+            )?;
+            attempts.push((n, job_id.clone()));
+            Ok(poll_job_status(
+                &format!("{}/class-verify/job/{}", server.url, job_id),
+                "",
+                &job_id,
+                &polling(Duration::ZERO),
+                transfer::global(),
+                &CancelToken::new(),
+                &mut |_| {},
+            )?)
+        };
+        let transient_failures = TransientFailures::default();
+        let result = with_resubmission(
+            max_resubmissions,
+            &transient_failures,
+            &mut |_, _| {},
+            attempt,
+        );
+        (result, attempts)
+    }
+
+    /// The multipart boundary is random, everything else must be identical.
+    fn normalized_body(request: &RecordedRequest) -> String {
+        let boundary = request
+            .header("content-type")
+            .and_then(|content_type| content_type.split("boundary=").nth(1))
+            .unwrap();
+        String::from_utf8_lossy(&request.body).replace(boundary, "BOUNDARY")
+    }
+
+    #[test]
+    fn test_in_memory_sources_are_submitted_like_files_on_disk() {
+        let mut in_memory = SourceSet::new();
+        in_memory
+            .add("my_token/Scarb.toml", "[package]\nname = \"my_token\"\n")
+            .unwrap();
+        in_memory
+            .add("my_token/src/lib.cairo", "mod token;\n")
+            .unwrap();
+        let dir = Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("voyager-in-memory-{}", std::process::id())),
+        )
+        .unwrap();
+        let on_disk = SourceSet::from_files(&in_memory.materialize(&dir).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"job_id": "job-1"}"#),
+            MockResponse::new(200, r#"{"job_id": "job-2"}"#),
+            MockResponse::new(200, r#"{"job_id": "job-3"}"#),
+            MockResponse::new(200, r#"{"job_id": "job-4"}"#),
+        ]);
+        let metadata = ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: "my_token".to_owned(),
+            contract_file: "my_token/src/lib.cairo".to_owned(),
+            target: StarknetContractTarget::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        };
+        for (sources, deterministic) in [
+            (&in_memory, false),
+            (&on_disk, false),
+            (&in_memory, true),
+            (&on_disk, true),
+        ] {
+            let (form, features) = build_verification_form(
+                "MIT License (MIT)",
+                "MyToken",
+                &[],
+                &metadata,
+                sources,
+                &SourceSet::new(),
+                FormStyle::Parts,
+                Dialect::default(),
+                deterministic,
+                transfer::global(),
+            )
+            .unwrap();
+            submit_verification_form(
+                &server.network(),
+                &"0x1".parse().unwrap(),
+                "",
+                form,
+                features,
+                FormStyle::Parts,
+                transfer::global(),
+                &CancelToken::new(),
+            )
+            .unwrap();
+        }
+        let requests = server.finish();
+
+        let submitted = normalized_body(&requests[0]);
+        assert!(submitted.contains("mod token;"));
+        assert_eq!(submitted, normalized_body(&requests[1]));
+
+        // Deterministic bodies are framed alike, and byte for byte the same
+        assert_eq!(submitted, normalized_body(&requests[2]));
+        assert_eq!(requests[2].body, requests[3].body);
+        assert_eq!(
+            requests[2].header("content-type"),
+            requests[3].header("content-type")
+        );
+    }
+
+    #[test]
+    fn test_transfer_is_counted_per_endpoint() {
+        let dispatch = r#"{"job_id": "job"}"#;
+        let server = MockServer::start(vec![
+            MockResponse::new(200, dispatch),
+            MockResponse::new(200, &job(1)),
+            MockResponse::new(200, &job(4)),
+        ]);
+        let log: &'static TransferLog = Box::leak(Box::default());
+
+        let contents = "mod contract;".to_owned();
+        let form = FormSubmission {
+            fields: vec![("name", "MyToken".to_owned())],
+            files: vec![("src/lib.cairo".to_owned(), contents.clone().into_bytes())],
+            docs: vec![],
+        }
+        .into_body(FormStyle::Parts, false, log)
+        .unwrap();
+        submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            log,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::ZERO),
+            log,
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap();
+        server.finish();
+
+        let verify = log.endpoint(VERIFY_ENDPOINT);
+        assert_eq!((verify.requests, verify.retries), (1, 0));
+        // Only part contents are counted, not the multipart framing
+        assert_eq!(verify.bytes_sent, ("MyToken".len() + contents.len()) as u64);
+        assert_eq!(verify.bytes_received, dispatch.len() as u64);
+
+        let status = log.endpoint(JOB_STATUS_ENDPOINT);
+        assert_eq!((status.requests, status.retries), (2, 1));
+        assert_eq!(status.bytes_received, (job(1).len() + job(4).len()) as u64);
+        assert_eq!(log.totals().requests, 3);
+    }
+
+    #[test]
+    fn test_api_key_is_sent_on_every_request() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"job_id": "job"}"#),
+            MockResponse::new(200, &job(4)),
+            MockResponse::new(401, r#"{"error": "Unauthorized"}"#),
+            MockResponse::new(403, r#"{"error": "Forbidden"}"#),
+        ]);
+        let form = FormSubmission {
+            fields: vec![("name", "MyToken".to_owned())],
+            files: vec![("src/lib.cairo".to_owned(), b"mod contract;".to_vec())],
+            docs: vec![],
+        }
+        .into_body(FormStyle::Parts, false, transfer::global())
+        .unwrap();
+        submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "secret",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            transfer::global(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+        let url = format!("{}/class-verify/job/job", server.url);
+        poll_job_status(
+            &url,
+            "secret",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap();
+
+        let missing = fetch_job_status(&url, "", transfer::global()).unwrap_err();
+        assert_eq!(missing.code(), Some("VER-046"));
+        assert!(missing.to_string().contains("Missing API key"));
+        let invalid = fetch_job_status(&url, "secret", transfer::global()).unwrap_err();
+        assert_eq!(invalid.code(), Some("VER-046"));
+        assert!(invalid.to_string().contains("Invalid API key"));
+
+        let requests = server.finish();
+        let keys: Vec<_> = requests
+            .iter()
+            .map(|request| request.header("x-api-key"))
+            .collect();
+        assert_eq!(keys, [Some("secret"), Some("secret"), None, Some("secret")]);
+    }
+
+    #[test]
+    fn test_undeclared_class_is_not_found() {
+        let server = MockServer::start(vec![MockResponse::new(404, "Not Found")]);
+        let form = FormSubmission::default()
+            .into_body(FormStyle::Parts, false, transfer::global())
+            .unwrap();
+        let error = submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            transfer::global(),
+            &CancelToken::new(),
+        )
+        .unwrap_err();
+        server.finish();
+
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::ClassNotFound { class_hash, network })
+                if class_hash == "0x1" && network == "custom"
+        ));
+    }
+
+    #[test]
+    fn test_oversized_upload_is_rejected_as_too_large() {
+        let server = MockServer::start(vec![MockResponse::new(
+            413,
+            "<html><body><h1>413 Request Entity Too Large</h1></body></html>",
+        )]);
+        let form = FormSubmission::default()
+            .into_body(FormStyle::Parts, false, transfer::global())
+            .unwrap();
+        let error = submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "",
+            form,
+            FormFeatures::default(),
+            FormStyle::Parts,
+            transfer::global(),
+            &CancelToken::new(),
+        )
+        .unwrap_err();
+        server.finish();
+
+        assert!(matches!(
+            error.verifier_error(),
+            Some(VerifierError::UploadRejectedTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_unparsable_dispatch_names_the_endpoint() {
+        let page = format!("<html>{}</html>", "x".repeat(500));
+        let error = dispatch_response(
+            StatusCode::OK,
+            page.as_bytes(),
+            "",
+            FormFeatures::default(),
+            FormStyle::Parts,
+            &"0x1".parse().unwrap(),
+            &Network::Mainnet,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("verify endpoint: <html>xxx"), "{}", error);
+        assert!(error.ends_with("..."), "{}", error);
+        assert!(!error.contains("</html>"), "{}", error);
+    }
+
+    #[test]
+    fn test_mismatched_form_style_is_explained() {
+        let server = MockServer::start(vec![
+            MockResponse::new(
+                400,
+                r#"{"error": "Unsupported form style", "code": "unsupported_form_style", "supported_form_styles": ["legacy"]}"#,
+            ),
+            MockResponse::new(400, r#"{"error": "No files"}"#),
+            MockResponse::new(400, r#"{"error": "No files"}"#),
+        ]);
+        let submit = |style| {
+            let form = FormSubmission::default()
+                .into_body(style, false, transfer::global())
+                .unwrap();
+            submit_verification_form(
+                &server.network(),
+                &"0x1".parse().unwrap(),
+                "",
+                form,
+                FormFeatures::default(),
+                style,
+                transfer::global(),
+                &CancelToken::new(),
+            )
+            .unwrap_err()
+        };
+
+        let error = submit(FormStyle::Archive);
+        match error.verifier_error() {
+            Some(VerifierError::UnsupportedFormStyle { style, supported }) => {
+                assert_eq!(style, "archive");
+                assert_eq!(supported, &["legacy"]);
+            }
+            _ => panic!("unexpected error {:?}", error),
+        }
+        // Older backends only answer with a bare 400
+        assert!(submit(FormStyle::Parts)
+            .to_string()
+            .contains("--form-style legacy"));
+        assert!(!submit(FormStyle::Legacy)
+            .to_string()
+            .contains("--form-style legacy"));
+        server.finish();
+    }
+
+    #[test]
+    fn test_transient_failure_then_success_is_resubmitted() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"job_id": "job-1"}"#),
+            MockResponse::new(200, &failed_job("Worker timeout")),
+            MockResponse::new(200, r#"{"job_id": "job-2"}"#),
+            MockResponse::new(200, &job(4)),
+        ]);
+
+        let (result, attempts) = verify_with_resubmission(&server, 2);
+        let requests = server.finish();
+
+        assert!(matches!(result.unwrap().status(), VerifyJobStatus::Success));
+        assert_eq!(
+            attempts,
+            vec![(1, "job-1".to_owned()), (2, "job-2".to_owned())]
+        );
+        let methods: Vec<&str> = requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, vec!["POST", "GET", "POST", "GET"]);
+    }
+
+    #[test]
+    fn test_transient_failure_forever_gives_up() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"job_id": "job-1"}"#),
+            MockResponse::new(200, &failed_job("artifact store unavailable")),
+            MockResponse::new(200, r#"{"job_id": "job-2"}"#),
+            MockResponse::new(200, &failed_job("artifact store unavailable")),
+        ]);
+
+        let (result, attempts) = verify_with_resubmission(&server, 1);
+        server.finish();
+
+        let error = result.unwrap_err();
+        assert_eq!(error_code(&error), Some("VER-011"));
+        assert_eq!(attempts.len(), 2);
+    }
+
+    #[test]
+    fn test_getting_default_voyager_endpoints() {
+        let selected_network = Network::Sepolia;
+        let actual_network_api = get_network_api(selected_network);
+
+        // Assert that the internal api is correct
+        assert_eq!(actual_network_api.0, "https://sepolia.voyager.online");
+        // Assert that the public api is correct``
+        assert_eq!(
+            actual_network_api.1,
+            "https://sepolia-api.voyager.online/beta"
+        );
+    }
+
+    #[test]
+    fn test_getting_custom_endpoints() {
+        let my_internal_api_url = "https://my-instance-internal-api.com";
+        let my_public_api_url = "https://my-instance-public-api.com/beta/";
+        let given = CustomEndpoints {
+            internal: Some(parse_endpoint(my_internal_api_url).unwrap()),
+            public: Some(parse_endpoint(my_public_api_url).unwrap()),
+        };
+        let actual_network_api = get_network_api(given.select("Custom").unwrap());
+
+        // Assert that the internal api is correct
+        assert_eq!(actual_network_api.0, my_internal_api_url);
+        // Paths are appended to the public api, without its trailing slash
+        assert_eq!(
+            actual_network_api.1,
+            "https://my-instance-public-api.com/beta"
+        );
+
+        // An endpoint that isn't given is read from the environment
+        let public_missing = CustomEndpoints {
+            public: None,
+            ..given.clone()
+        };
+        env::set_var(CUSTOM_PUBLIC_API_VAR, "https://from-env.com");
+        let (_, public) = get_network_api(public_missing.network().unwrap());
+        assert_eq!(public, "https://from-env.com");
+        env::remove_var(CUSTOM_PUBLIC_API_VAR);
+
+        // Or else the one that is configured serves both APIs
+        let (internal, public) = get_network_api(public_missing.network().unwrap());
+        assert_eq!(
+            (internal.as_str(), public.as_str()),
+            (my_internal_api_url, my_internal_api_url)
+        );
+        let internal_missing = CustomEndpoints {
+            internal: None,
+            ..given.clone()
+        };
+        let (internal, public) = get_network_api(internal_missing.network().unwrap());
+        assert_eq!(internal, "https://my-instance-public-api.com/beta");
+        assert_eq!(public, "https://my-instance-public-api.com/beta");
+
+        let error = CustomEndpoints::default().network().unwrap_err();
+        assert_eq!(error.code(), Some("VER-044"));
+        let message = error.to_string();
+        for way in [
+            "--api-url",
+            "--public-api-url",
+            CUSTOM_INTERNAL_API_VAR,
+            CUSTOM_PUBLIC_API_VAR,
+        ] {
+            assert!(message.contains(way), "{}", message);
+        }
+
+        let invalid = CustomEndpoints {
+            internal: None,
+            ..Default::default()
+        };
+        env::set_var(CUSTOM_INTERNAL_API_VAR, "not a url");
+        let error = invalid.network().unwrap_err();
+        env::remove_var(CUSTOM_INTERNAL_API_VAR);
+        assert!(error.to_string().contains("--api-url"));
+    }
+
+    #[test]
+    fn test_endpoints_resolve_against_their_api() {
+        let class_hash: ClassHash = "0x123".parse().unwrap();
+        let network = Network::Custom {
+            internal: parse_endpoint("https://internal.host").unwrap(),
+            public: parse_endpoint("https://public.host/beta").unwrap(),
+        };
+        assert_eq!(
+            class_lookup_url(network.clone(), &class_hash, false)
+                .unwrap()
+                .as_str(),
+            "https://public.host/beta/classes/0x123"
+        );
+        assert_eq!(
+            class_lookup_url(network.clone(), &class_hash, true)
+                .unwrap()
+                .as_str(),
+            "https://internal.host/api/class/0x123"
+        );
+        assert_eq!(
+            verification_url(network.clone(), &class_hash)
+                .unwrap()
+                .as_str(),
+            "https://public.host/beta/class-verify/0x123"
+        );
+        assert_eq!(
+            job_status_url(network.clone(), "job-1").unwrap(),
+            "https://public.host/beta/class-verify/job/job-1"
+        );
+        assert_eq!(
+            class_page_url(network, "0x123").unwrap(),
+            "https://internal.host/class/0x123"
+        );
+
+        assert_eq!(
+            class_lookup_url(Network::Sepolia, &class_hash, false)
+                .unwrap()
+                .as_str(),
+            "https://sepolia-api.voyager.online/beta/classes/0x123"
+        );
+        assert_eq!(
+            class_lookup_url(Network::Sepolia, &class_hash, true)
+                .unwrap()
+                .as_str(),
+            "https://sepolia.voyager.online/api/class/0x123"
+        );
+    }
+
+    #[test]
+    fn test_class_lookup_only_needs_the_public_endpoint() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"verified": false}"#),
+            MockResponse::new(404, ""),
+        ]);
+        // Nothing listens on the internal endpoint
+        let network = Network::Custom {
+            internal: parse_endpoint("http://127.0.0.1:1").unwrap(),
+            public: parse_endpoint(&server.url).unwrap(),
+        };
+
+        let cancel = CancelToken::new();
+        assert!(does_class_exist(
+            network.clone(),
+            "0x1".parse::<ClassHash>().unwrap(),
+            &cancel
+        )
+        .unwrap());
+        assert!(!does_class_exist(network, "0x2".parse::<ClassHash>().unwrap(), &cancel).unwrap());
+        let requests = server.finish();
+        assert_eq!(requests[0].path, "/classes/0x1");
+        assert_eq!(requests[1].path, "/classes/0x2");
+    }
+
+    #[test]
+    fn test_api_paths_are_joined_to_the_base_url() {
+        let class_url = |base: &str| {
+            ApiEndpoints::LegacyGetClass
+                .to_api_url(base, "0x123".to_owned())
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(class_url("https://host"), "https://host/api/class/0x123");
+        assert_eq!(class_url("https://host/"), "https://host/api/class/0x123");
+        assert_eq!(class_url("https://host//"), "https://host/api/class/0x123");
+        assert_eq!(
+            class_url("https://host/verifier"),
+            "https://host/verifier/api/class/0x123"
+        );
+        assert_eq!(
+            class_url("https://host/verifier/"),
+            "https://host/verifier/api/class/0x123"
+        );
+        assert_eq!(
+            class_url("http://localhost:8899"),
+            "http://localhost:8899/api/class/0x123"
+        );
+        assert_eq!(
+            class_url("http://localhost:8899/verifier/"),
+            "http://localhost:8899/verifier/api/class/0x123"
+        );
+
+        let job_url = ApiEndpoints::GetJobStatus
+            .to_api_url("https://host/beta/", "job-1".to_owned())
+            .unwrap();
+        assert_eq!(job_url.as_str(), "https://host/beta/class-verify/job/job-1");
+        assert!(join_api_path("not a url", "/api").is_err());
+
+        let network = Network::Custom {
+            internal: parse_endpoint("https://internal.host").unwrap(),
+            public: parse_endpoint("https://host/beta/").unwrap(),
+        };
+        assert_eq!(
+            verification_endpoint(network).unwrap(),
+            "https://host/beta/class-verify/{class_hash}"
+        );
+    }
+
+    #[test]
+    fn test_custom_endpoint_with_base_path_and_trailing_slash() {
+        let server = MockServer::start(vec![MockResponse::new(200, r#"{"job_id": "job-1"}"#)]);
+        let base = parse_endpoint(&format!("{}/verifier/", server.url)).unwrap();
+        let network = Network::Custom {
+            internal: base.clone(),
+            public: base,
+        };
+        dispatch_class_verification_job(
+            "",
+            network,
+            &"0x1".parse().unwrap(),
+            "MIT License (MIT)",
+            "MyToken",
+            &[],
+            &ProjectMetadataInfo {
+                cairo_version: SupportedCairoVersions::V2_8_4,
+                scarb_version: SupportedScarbVersions::V2_8_4,
+                project_dir_path: ".".to_owned(),
+                contract_file: "src/lib.cairo".to_owned(),
+                target: StarknetContractTarget::default(),
+                plugins: vec![],
+                cached_dependencies: vec![],
+            },
+            &SourceSet::new(),
+            &SourceSet::new(),
+            FormStyle::Parts,
+            Dialect::default(),
+            false,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        let requests = server.finish();
+        assert_eq!(requests[0].path, "/verifier/class-verify/0x1");
+    }
+}
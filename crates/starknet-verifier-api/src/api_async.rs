@@ -0,0 +1,608 @@
+// Async requests of a verification, for tools running it on a runtime. The
+// blocking requests of `api` are these, driven on a runtime of their own.
+
+use reqwest::{header::CONTENT_TYPE, Response, StatusCode};
+use url::Url;
+
+use crate::api::{
+    build_verification_form, capabilities_response, class_lookup_failed, class_url,
+    correction_response, dispatch_response, get_network_api, in_progress_jobs_response,
+    job_status_response, known_dependencies_response, poll_timed_out, verification_url,
+    with_api_key, ApiEndpoints, BackendCapabilities, JobPoll, Network, PollUpdate,
+    ProjectMetadataInfo, VerificationJob,
+};
+use crate::cancel::{self, CancelToken};
+use crate::class_hash::ClassHash;
+use crate::corrections::CorrectionRequest;
+use crate::dialect::Dialect;
+use crate::errors::Result;
+use crate::form::{multipart_form, FormBody, FormFeatures, FormStyle};
+use crate::http_retry::{self, send_with_retry_async, Resend, RetryPolicy};
+use crate::ip_version;
+use crate::polling::{PollNegotiation, PollSchedule, PollingConfig, HTTP_TIMEOUT};
+use crate::sources::SourceSet;
+use crate::transfer::{
+    self, TransferLog, CAPABILITIES_ENDPOINT, CLASS_ENDPOINT, CORRECTIONS_ENDPOINT,
+    DEPENDENCIES_ENDPOINT, JOBS_ENDPOINT, JOB_STATUS_ENDPOINT, VERIFY_ENDPOINT,
+};
+
+/**
+ * `does_class_exist` without blocking the runtime.
+ */
+pub async fn does_class_exist_async(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<bool> {
+    let url = class_url(network, &class_hash.into())?;
+    let log = transfer::global();
+    let result = get_class(url, log, cancel).await?;
+    match result.status() {
+        StatusCode::OK => Ok(true),
+        StatusCode::NOT_FOUND => Ok(false),
+        status => Err(class_lookup_failed(
+            status,
+            &log.read_body_async(CLASS_ENDPOINT, result).await?,
+        )),
+    }
+}
+
+/**
+ * `fetch_class` without blocking the runtime.
+ */
+pub async fn fetch_class_async(
+    network: Network,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<Option<serde_json::Value>> {
+    let url = class_url(network, &class_hash.into())?;
+    let log = transfer::global();
+    let result = get_class(url, log, cancel).await?;
+    match result.status() {
+        StatusCode::OK => Ok(Some(serde_json::from_slice(
+            &log.read_body_async(CLASS_ENDPOINT, result).await?,
+        )?)),
+        StatusCode::NOT_FOUND => Ok(None),
+        status => Err(class_lookup_failed(
+            status,
+            &log.read_body_async(CLASS_ENDPOINT, result).await?,
+        )),
+    }
+}
+
+/**
+ * Request the class at `url`, retrying transient failures.
+ */
+async fn get_class(
+    url: Url,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> anyhow::Result<Response> {
+    let client = ip_version::client_builder_async().build()?;
+    send_with_retry_async(
+        &RetryPolicy::from_env(),
+        cancel,
+        http_retry::global(),
+        Resend::Always,
+        CLASS_ENDPOINT,
+        |attempt| {
+            log.record_request(CLASS_ENDPOINT, attempt > 1);
+            let request = client.get(url.clone());
+            async move { anyhow::Ok(request.send().await?) }
+        },
+    )
+    .await
+}
+
+/**
+ * `fetch_in_progress_jobs` without blocking the runtime.
+ */
+pub async fn fetch_in_progress_jobs_async(
+    network: Network,
+    api_key: &str,
+    class_hash: impl Into<ClassHash>,
+    cancel: &CancelToken,
+) -> Result<Vec<VerificationJob>> {
+    let (_, public_url) = get_network_api(network);
+    let class_hash: ClassHash = class_hash.into();
+    let url = ApiEndpoints::ListJobs.to_api_url(&public_url, class_hash.as_str().to_owned())?;
+    list_in_progress_jobs(url.as_str(), api_key, transfer::global(), cancel).await
+}
+
+pub(crate) async fn list_in_progress_jobs(
+    url: &str,
+    api_key: &str,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> Result<Vec<VerificationJob>> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    log.record_request(JOBS_ENDPOINT, false);
+    let result = cancel
+        .run_async(async { Ok(with_api_key(client.get(url), api_key).send().await?) })
+        .await?;
+    let status = result.status();
+    let body = log.read_body_async(JOBS_ENDPOINT, result).await?;
+    in_progress_jobs_response(status, &body, api_key)
+}
+
+/**
+ * `fetch_capabilities` without blocking the runtime.
+ */
+pub async fn fetch_capabilities_async(
+    network: Network,
+    api_key: &str,
+    cancel: &CancelToken,
+) -> Result<Option<BackendCapabilities>> {
+    let (_, public_url) = get_network_api(network);
+    let url = ApiEndpoints::Capabilities.to_api_url(&public_url, String::new())?;
+    request_capabilities(url.as_str(), api_key, transfer::global(), cancel).await
+}
+
+pub(crate) async fn request_capabilities(
+    url: &str,
+    api_key: &str,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> Result<Option<BackendCapabilities>> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    let response = send_with_retry_async(
+        &RetryPolicy::from_env(),
+        cancel,
+        http_retry::global(),
+        Resend::Always,
+        CAPABILITIES_ENDPOINT,
+        |attempt| {
+            log.record_request(CAPABILITIES_ENDPOINT, attempt > 1);
+            let request = with_api_key(client.get(url), api_key);
+            async move { anyhow::Ok(request.send().await?) }
+        },
+    )
+    .await?;
+    let status = response.status();
+    let body = log.read_body_async(CAPABILITIES_ENDPOINT, response).await?;
+    capabilities_response(status, &body, api_key)
+}
+
+/**
+ * `fetch_known_dependencies` without blocking the runtime.
+ */
+pub async fn fetch_known_dependencies_async(
+    network: Network,
+    api_key: &str,
+    fingerprints: &[String],
+    cancel: &CancelToken,
+) -> Result<Option<Vec<String>>> {
+    let capabilities = fetch_capabilities_async(network.clone(), api_key, cancel).await?;
+    if !capabilities.is_some_and(|capabilities| capabilities.dependency_fingerprints) {
+        return Ok(None);
+    }
+    let (_, public_url) = get_network_api(network);
+    let url = ApiEndpoints::KnownDependencies.to_api_url(&public_url, String::new())?;
+    request_known_dependencies(
+        url.as_str(),
+        api_key,
+        fingerprints,
+        transfer::global(),
+        cancel,
+    )
+    .await
+    .map(Some)
+}
+
+pub(crate) async fn request_known_dependencies(
+    url: &str,
+    api_key: &str,
+    fingerprints: &[String],
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> Result<Vec<String>> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    // Only a lookup, sending it again can't do any harm
+    let response = send_with_retry_async(
+        &RetryPolicy::from_env(),
+        cancel,
+        http_retry::global(),
+        Resend::Always,
+        DEPENDENCIES_ENDPOINT,
+        |attempt| {
+            log.record_request(DEPENDENCIES_ENDPOINT, attempt > 1);
+            let request = with_api_key(client.post(url), api_key)
+                .json(&serde_json::json!({ "fingerprints": fingerprints }));
+            async move { anyhow::Ok(request.send().await?) }
+        },
+    )
+    .await?;
+    let status = response.status();
+    let body = log.read_body_async(DEPENDENCIES_ENDPOINT, response).await?;
+    known_dependencies_response(status, &body, fingerprints, api_key)
+}
+
+/**
+ * `submit_correction` without blocking the runtime.
+ */
+pub async fn submit_correction_async(
+    network: Network,
+    api_key: &str,
+    request: &CorrectionRequest,
+) -> Result<String> {
+    request.validate()?;
+    let (_, public_url) = get_network_api(network);
+    let url = ApiEndpoints::Corrections.to_api_url(&public_url, request.class_hash.clone())?;
+    request_correction(url.as_str(), api_key, request, transfer::global()).await
+}
+
+pub(crate) async fn request_correction(
+    url: &str,
+    api_key: &str,
+    request: &CorrectionRequest,
+    log: &'static TransferLog,
+) -> Result<String> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    log.record_request(CORRECTIONS_ENDPOINT, false);
+    let response = with_api_key(client.post(url), api_key)
+        .json(request)
+        .send()
+        .await?;
+    let status = response.status();
+    let body = log.read_body_async(CORRECTIONS_ENDPOINT, response).await?;
+    correction_response(status, &body, api_key)
+}
+
+/**
+ * `dispatch_class_verification_job` without blocking the runtime.
+ */
+#[allow(clippy::too_many_arguments)]
+pub async fn dispatch_class_verification_job_async(
+    api_key: &str,
+    network: Network,
+    class_hash: &ClassHash,
+    license: &str,
+    name: &str,
+    aliases: &[String],
+    project_metadata: &ProjectMetadataInfo,
+    files: &SourceSet,
+    docs: &SourceSet,
+    form_style: FormStyle,
+    dialect: Dialect,
+    deterministic_body: bool,
+    cancel: &CancelToken,
+) -> Result<String> {
+    let log = transfer::global();
+    let (body, features) = build_verification_form(
+        license,
+        name,
+        aliases,
+        project_metadata,
+        files,
+        docs,
+        form_style,
+        dialect,
+        deterministic_body,
+        log,
+    )?;
+
+    submit_verification_form(
+        &network, class_hash, api_key, body, features, form_style, log, cancel,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn submit_verification_form(
+    network: &Network,
+    class_hash: &ClassHash,
+    api_key: &str,
+    body: FormBody,
+    features: FormFeatures,
+    form_style: FormStyle,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> Result<String> {
+    let url = verification_url(network.clone(), class_hash)?;
+    let client = ip_version::client_builder_async().build()?;
+    let response = send_with_retry_async(
+        &RetryPolicy::from_env(),
+        cancel,
+        http_retry::global(),
+        Resend::Unaccepted,
+        VERIFY_ENDPOINT,
+        |attempt| {
+            log.record_request(VERIFY_ENDPOINT, attempt > 1);
+            let request = with_api_key(client.post(url.clone()), api_key);
+            let request = match &body {
+                FormBody::Multipart(parts) => {
+                    multipart_form(parts.clone(), log).map(|form| request.multipart(form))
+                }
+                FormBody::Deterministic { boundary, body } => Ok(request
+                    .header(
+                        CONTENT_TYPE,
+                        format!("multipart/form-data; boundary={}", boundary),
+                    )
+                    .body(body.clone())),
+            };
+            async move { anyhow::Ok(request?.send().await?) }
+        },
+    )
+    .await?;
+    let status = response.status();
+    let body = log.read_body_async(VERIFY_ENDPOINT, response).await?;
+    dispatch_response(
+        status, &body, api_key, features, form_style, class_hash, network,
+    )
+}
+
+/**
+ * `poll_verification_status` without blocking the runtime: the polls are
+ * spaced by sleeping on it rather than on the thread. Generic over
+ * `on_update` so that the future is `Send` when the callback is, while the
+ * blocking poll can pass any callback.
+ */
+pub async fn poll_verification_status_async<F: FnMut(PollUpdate) + ?Sized>(
+    api_key: &str,
+    network: Network,
+    job_id: &str,
+    polling: &PollingConfig,
+    cancel: &CancelToken,
+    on_update: &mut F,
+) -> Result<VerificationJob> {
+    let (_, public_url) = get_network_api(network);
+    let url = ApiEndpoints::GetJobStatus.to_api_url(&public_url, job_id.to_owned())?;
+
+    poll_job_status(
+        url.as_str(),
+        api_key,
+        job_id,
+        polling,
+        transfer::global(),
+        cancel,
+        on_update,
+    )
+    .await
+}
+
+/**
+ * Poll the job at `url` until it finishes.
+ */
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn poll_job_status<F: FnMut(PollUpdate) + ?Sized>(
+    url: &str,
+    api_key: &str,
+    job_id: &str,
+    polling: &PollingConfig,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+    on_update: &mut F,
+) -> Result<VerificationJob> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    let mut poll = JobPoll::default();
+    // Sleeping on the token ends the wait between polls once cancelled
+    let mut schedule = PollSchedule::new(cancel, polling.timeout);
+
+    loop {
+        // Waiting for maintenance doesn't count against the timeout
+        let maintenance = http_retry::global().maintenance_waited();
+        let result = request_job_status(
+            &client,
+            url,
+            api_key,
+            &poll.negotiation,
+            schedule.retries() > 0,
+            log,
+            cancel,
+        )
+        .await
+        .map_err(|error| cancel::with_job_id(error, job_id))?;
+        schedule.extend(http_retry::global().maintenance_waited() - maintenance);
+        let (status, headers) = (result.status(), result.headers().clone());
+        let body = match JobPoll::reads_body(status) {
+            true => Some(log.read_body_async(JOB_STATUS_ENDPOINT, result).await?),
+            false => None,
+        };
+        let finished = poll.observe(
+            status,
+            &headers,
+            body.as_deref(),
+            api_key,
+            polling,
+            &mut schedule,
+            &mut |update| on_update(update),
+        )?;
+        if let Some(job) = finished {
+            return job.into_result();
+        }
+
+        if !schedule
+            .next_async(poll.negotiation.delay(polling.interval), cancel)
+            .await
+        {
+            break;
+        }
+    }
+
+    Err(poll_timed_out(job_id, schedule.waited()))
+}
+
+/**
+ * `get_job_status` without blocking the runtime.
+ */
+pub async fn get_job_status_async(
+    network: Network,
+    api_key: &str,
+    job_id: &str,
+    cancel: &CancelToken,
+) -> Result<VerificationJob> {
+    let (_, public_url) = get_network_api(network);
+    let url = ApiEndpoints::GetJobStatus.to_api_url(&public_url, job_id.to_owned())?;
+    fetch_job_status(url.as_str(), api_key, transfer::global(), cancel).await
+}
+
+pub(crate) async fn fetch_job_status(
+    url: &str,
+    api_key: &str,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> Result<VerificationJob> {
+    let client = ip_version::client_builder_async()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    let negotiation = PollNegotiation::default();
+    let result =
+        request_job_status(&client, url, api_key, &negotiation, false, log, cancel).await?;
+    let status = result.status();
+    let body = log.read_body_async(JOB_STATUS_ENDPOINT, result).await?;
+    job_status_response(status, &body, api_key)
+}
+
+/**
+ * Send a status request of the job, as negotiated with the backend.
+ */
+async fn request_job_status(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: &str,
+    negotiation: &PollNegotiation,
+    retry: bool,
+    log: &'static TransferLog,
+    cancel: &CancelToken,
+) -> anyhow::Result<Response> {
+    send_with_retry_async(
+        &RetryPolicy::from_env(),
+        cancel,
+        http_retry::global(),
+        Resend::Always,
+        JOB_STATUS_ENDPOINT,
+        |attempt| {
+            let request = with_api_key(client.get(url), api_key)
+                .query(&negotiation.query())
+                .headers(negotiation.headers());
+            log.record_request(JOB_STATUS_ENDPOINT, retry || attempt > 1);
+            async move { anyhow::Ok(request.send().await?) }
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::VerifyJobStatus;
+    use crate::mock_server::{MockResponse, MockServer};
+    use std::time::Duration;
+
+    fn job(status: u8) -> String {
+        format!(
+            r#"{{"job_id": "job", "status": {}, "class_hash": "0x1"}}"#,
+            status
+        )
+    }
+
+    fn polling(interval: Duration) -> PollingConfig {
+        PollingConfig {
+            interval,
+            timeout: None,
+            restart_grace: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_poll_matches_the_blocking_one() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, &job(0)).header("etag", "\"v1\""),
+            MockResponse::new(304, ""),
+            MockResponse::new(200, &job(4)).header("etag", "\"v2\""),
+        ]);
+
+        let mut updates = 0;
+        let on_update: &mut (dyn FnMut(PollUpdate) + Send) = &mut |_| updates += 1;
+        let job = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "key",
+            "job",
+            &polling(Duration::ZERO),
+            transfer::global(),
+            &CancelToken::new(),
+            on_update,
+        )
+        .await
+        .unwrap();
+        let requests = server.finish();
+
+        assert!(matches!(job.status(), VerifyJobStatus::Success));
+        assert_eq!(updates, 2);
+        assert_eq!(requests[0].header("if-none-match"), None);
+        assert_eq!(requests[1].header("if-none-match"), Some("\"v1\""));
+        assert!(requests
+            .iter()
+            .all(|r| r.header("x-api-key") == Some("key")));
+    }
+
+    #[tokio::test]
+    async fn test_async_submission_returns_the_job_id() {
+        let server = MockServer::start(vec![MockResponse::new(200, r#"{"job_id": "job-1"}"#)]);
+        let mut files = SourceSet::new();
+        files.add("token/src/lib.cairo", "mod token;").unwrap();
+        let body = crate::form::FormSubmission {
+            fields: vec![("name", "Token".to_owned())],
+            files: files.to_files(),
+            docs: vec![],
+        }
+        .into_body(FormStyle::default(), false, transfer::global())
+        .unwrap();
+
+        let job_id = submit_verification_form(
+            &server.network(),
+            &"0x1".parse().unwrap(),
+            "",
+            body,
+            FormFeatures::default(),
+            FormStyle::default(),
+            transfer::global(),
+            &CancelToken::new(),
+        )
+        .await
+        .unwrap();
+        let requests = server.finish();
+
+        assert_eq!(job_id, "job-1");
+        assert_eq!(requests[0].method, "POST");
+    }
+
+    #[tokio::test]
+    async fn test_async_cancel_between_polls() {
+        let server = MockServer::start(vec![MockResponse::new(200, &job(0))]);
+        let cancel = CancelToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let error = poll_job_status(
+            &format!("{}/class-verify/job/job", server.url),
+            "",
+            "job",
+            &polling(Duration::from_secs(60)),
+            transfer::global(),
+            &cancel,
+            &mut |_| {},
+        )
+        .await
+        .unwrap_err();
+        server.finish();
+
+        assert!(matches!(
+            error.verifier_error(),
+            Some(crate::errors::VerifierError::Cancelled { job_id: Some(job_id) }) if job_id == "job"
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Error, Result};
+
+use crate::errors::VerifierError;
+use crate::polling::Clock;
+
+/**
+ * Cancellation of a verification, shared by every clone of the token across
+ * threads. Once cancelled, the requests in flight are aborted, sleeps
+ * between retries and polls end early, and the requests fail with
+ * `VerifierError::Cancelled`.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<CancelState>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    /// Only held to wake the sleepers of `wait` without missing a cancel.
+    lock: Mutex<()>,
+    changed: Condvar,
+    notify: tokio::sync::Notify,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        let _guard = self
+            .state
+            .lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.state.changed.notify_all();
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Sleep for `duration`, or until cancelled. Returns false when cancelled.
+     */
+    pub fn wait(&self, duration: Duration) -> bool {
+        let guard = self
+            .state
+            .lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (_guard, _) = self
+            .state
+            .changed
+            .wait_timeout_while(guard, duration, |_| !self.is_cancelled())
+            .unwrap_or_else(PoisonError::into_inner);
+        !self.is_cancelled()
+    }
+
+    /**
+     * Fail with `Cancelled` once cancelled, naming `job_id` when the job was
+     * already dispatched.
+     */
+    pub fn check(&self, job_id: Option<&str>) -> Result<()> {
+        match self.is_cancelled() {
+            true => Err(VerifierError::Cancelled {
+                job_id: job_id.map(str::to_owned),
+            }
+            .into()),
+            false => Ok(()),
+        }
+    }
+
+    /**
+     * Resolves once cancelled.
+     */
+    pub async fn cancelled(&self) {
+        loop {
+            let mut notified = std::pin::pin!(self.state.notify.notified());
+            // Registered before the check, so a cancel in between still wakes it
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /**
+     * `wait` for async callers, sleeping on the runtime. Returns false when
+     * cancelled.
+     */
+    pub async fn wait_async(&self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => (),
+            _ = self.cancelled() => (),
+        }
+        !self.is_cancelled()
+    }
+
+    /**
+     * Run the async `request` until it completes or the token is cancelled,
+     * which drops the request and so aborts it. The blocking requests are
+     * async ones too, see `api::block_on`, so this aborts them as well.
+     */
+    pub async fn run_async<T>(
+        &self,
+        request: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        self.check(None)?;
+        tokio::select! {
+            result = request => result,
+            _ = self.cancelled() => Err(VerifierError::Cancelled { job_id: None }.into()),
+        }
+    }
+}
+
+/// Sleeps end early once cancelled, so that retries and polls stop waiting.
+impl Clock for CancelToken {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.wait(duration);
+    }
+}
+
+/**
+ * `error`, naming `job_id` if it is a cancellation that doesn't name a job
+ * yet, for the requests that follow the dispatch.
+ */
+pub fn with_job_id(error: Error, job_id: &str) -> Error {
+    match error.downcast_ref::<VerifierError>() {
+        Some(VerifierError::Cancelled { job_id: None }) => VerifierError::Cancelled {
+            job_id: Some(job_id.to_owned()),
+        }
+        .into(),
+        _ => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+    use std::thread;
+
+    fn cancel_after(token: &CancelToken, delay: Duration) -> thread::JoinHandle<()> {
+        let token = token.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            token.cancel();
+        })
+    }
+
+    #[test]
+    fn test_cancel_ends_a_wait_early() {
+        let token = CancelToken::new();
+        let canceller = cancel_after(&token, Duration::from_millis(50));
+
+        let started = Instant::now();
+        assert!(!token.wait(Duration::from_secs(60)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        canceller.join().unwrap();
+
+        // Cancelled for good
+        assert!(!token.wait(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_a_request_in_flight() {
+        let token = CancelToken::new();
+        let canceller = cancel_after(&token, Duration::from_millis(50));
+
+        let started = Instant::now();
+        let result = token
+            .run_async(async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            })
+            .await;
+        canceller.join().unwrap();
+
+        assert_eq!(error_code(&result.unwrap_err()), Some("VER-049"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_cancellation_names_the_job_once_dispatched() {
+        let token = CancelToken::new();
+        assert!(token.check(Some("job")).is_ok());
+        token.cancel();
+
+        let error = with_job_id(token.check(None).unwrap_err(), "job");
+        assert!(matches!(
+            error.downcast_ref::<VerifierError>(),
+            Some(VerifierError::Cancelled { job_id: Some(job_id) }) if job_id == "job"
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Error};
+
+use crate::validation::is_class_hash_valid;
+
+/**
+ * A validated class hash, normalized to its lower-case `0x` prefixed form
+ * without leading zeros so that `0x0ABC` and `0xabc` compare equal.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassHash(String);
+
+impl ClassHash {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn normalize(hash: &str) -> String {
+    let digits = hash[2..].trim_start_matches('0').to_lowercase();
+    if digits.is_empty() {
+        "0x0".to_owned()
+    } else {
+        format!("0x{}", digits)
+    }
+}
+
+impl FromStr for ClassHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !is_class_hash_valid(s) {
+            return Err(anyhow!(
+                "{} is not a valid class hash, expected 0x followed by the hexadecimal digits of a Starknet field element, below 2^251 + 17 * 2^192 + 1, e.g. 0x044dc2b3...",
+                s
+            ));
+        }
+        Ok(Self(normalize(s)))
+    }
+}
+
+impl Display for ClassHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&ClassHash> for ClassHash {
+    fn from(hash: &ClassHash) -> Self {
+        hash.clone()
+    }
+}
+
+#[cfg(feature = "starknet-types")]
+mod starknet_types {
+    use starknet_types_core::felt::Felt;
+
+    use super::ClassHash;
+
+    impl From<Felt> for ClassHash {
+        fn from(felt: Felt) -> Self {
+            Self(felt.to_hex_string())
+        }
+    }
+
+    impl From<&Felt> for ClassHash {
+        fn from(felt: &Felt) -> Self {
+            Self(felt.to_hex_string())
+        }
+    }
+
+    /**
+     * A class hash only fits in a felt if it is below the field prime.
+     */
+    impl TryFrom<&ClassHash> for Felt {
+        type Error = anyhow::Error;
+
+        fn try_from(hash: &ClassHash) -> Result<Self, Self::Error> {
+            Felt::from_hex(hash.as_str())
+                .map_err(|e| anyhow::anyhow!("{} is not a valid felt: {}", hash, e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_hash_normalization() {
+        let padded = ClassHash::from_str("0x0000ABC").unwrap();
+        let short = ClassHash::from_str("0xabc").unwrap();
+
+        assert_eq!(padded, short);
+        assert_eq!(padded.to_string(), "0xabc");
+        assert_eq!(ClassHash::from_str("0x000").unwrap().as_str(), "0x0");
+    }
+
+    #[test]
+    fn test_invalid_class_hash() {
+        assert!(ClassHash::from_str("abc").is_err());
+        assert!(ClassHash::from_str("0xnothex").is_err());
+        assert!(ClassHash::from_str("0x").is_err());
+        assert!(ClassHash::from_str(&format!("0x{}", "f".repeat(64))).is_err());
+
+        let error = ClassHash::from_str("abc").unwrap_err().to_string();
+        assert!(error.contains("below 2^251 + 17 * 2^192 + 1"));
+    }
+
+    #[cfg(feature = "starknet-types")]
+    #[test]
+    fn test_felt_round_trip() {
+        use starknet_types_core::felt::Felt;
+
+        let hash = ClassHash::from_str(
+            "0x044dc2b3239382230d8b1e943df23b96f52eebcac93efe6e8bde92f9a2f1da18",
+        )
+        .unwrap();
+        let felt = Felt::try_from(&hash).unwrap();
+
+        assert_eq!(ClassHash::from(felt), hash);
+        assert_eq!(ClassHash::from(Felt::ZERO).as_str(), "0x0");
+    }
+}
@@ -0,0 +1,122 @@
+use crate::api::{
+    check_endpoints, dispatch_class_verification_job, fetch_class_verified,
+    poll_verification_status, Network, PollUpdate, ProjectMetadataInfo, VerificationJob,
+};
+use crate::cancel::CancelToken;
+use crate::class_hash::ClassHash;
+use crate::dialect::Dialect;
+use crate::errors::Result;
+use crate::form::FormStyle;
+use crate::polling::PollingConfig;
+use crate::sources::SourceSet;
+
+/**
+ * Everything submitted with a verification job.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct DispatchRequest<'a> {
+    pub class_hash: &'a ClassHash,
+    pub license: &'a str,
+    pub name: &'a str,
+    pub aliases: &'a [String],
+    pub metadata: &'a ProjectMetadataInfo,
+    pub files: &'a SourceSet,
+    pub docs: &'a SourceSet,
+    pub form_style: FormStyle,
+    /// Names of the metadata fields, see `dialect::select_dialect`.
+    pub dialect: Dialect,
+    /// Render the body ourselves, see `FormSubmission::into_body`.
+    pub deterministic_body: bool,
+}
+
+/**
+ * What a verification needs from the backend. `ApiClient` talks to the
+ * explorer, `testing::FakeVerifier` replays scripted scenarios instead.
+ */
+pub trait VerifierClient {
+    /**
+     * Submit a verification job, returning its id.
+     */
+    fn dispatch(&self, request: &DispatchRequest) -> Result<String>;
+
+    /**
+     * Poll the job until it finishes, calling `on_update` with every status
+     * received and every notice, see `PollUpdate`. A failed job is an error,
+     * see `VerifierError`.
+     */
+    fn poll(
+        &self,
+        job_id: &str,
+        polling: &PollingConfig,
+        on_update: &mut dyn FnMut(PollUpdate),
+    ) -> Result<VerificationJob>;
+
+    /**
+     * Whether the class is verified according to the class itself, for jobs
+     * whose record is gone. `None` when the backend doesn't say.
+     */
+    fn class_verified(&self, class_hash: &ClassHash) -> Result<Option<bool>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    pub api_key: String,
+    pub network: Network,
+    /// Ends the dispatch and the polls early, see `CancelToken`.
+    pub cancel: CancelToken,
+}
+
+impl ApiClient {
+    /**
+     * A client for `network`, whose endpoints are checked once here rather
+     * than failing the first request, see `check_endpoints`.
+     */
+    pub fn new(api_key: String, network: Network, cancel: CancelToken) -> Result<Self> {
+        check_endpoints(&network)?;
+        Ok(Self {
+            api_key,
+            network,
+            cancel,
+        })
+    }
+}
+
+impl VerifierClient for ApiClient {
+    fn dispatch(&self, request: &DispatchRequest) -> Result<String> {
+        dispatch_class_verification_job(
+            &self.api_key,
+            self.network.clone(),
+            request.class_hash,
+            request.license,
+            request.name,
+            request.aliases,
+            request.metadata,
+            request.files,
+            request.docs,
+            request.form_style,
+            request.dialect,
+            request.deterministic_body,
+            &self.cancel,
+        )
+    }
+
+    fn poll(
+        &self,
+        job_id: &str,
+        polling: &PollingConfig,
+        on_update: &mut dyn FnMut(PollUpdate),
+    ) -> Result<VerificationJob> {
+        poll_verification_status(
+            &self.api_key,
+            self.network.clone(),
+            job_id,
+            polling,
+            &self.cancel,
+            on_update,
+        )
+    }
+
+    fn class_verified(&self, class_hash: &ClassHash) -> Result<Option<bool>> {
+        fetch_class_verified(self.network.clone(), class_hash, &self.cancel)
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::VerifierError;
+use crate::validation::validate_class_name;
+
+/**
+ * Field of the verified metadata of a class that a correction request is
+ * about, those the submitter of a verification chooses.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionField {
+    Name,
+    License,
+}
+
+impl CorrectionField {
+    pub const ALL: &'static [CorrectionField] = &[CorrectionField::Name, CorrectionField::License];
+}
+
+impl std::fmt::Display for CorrectionField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CorrectionField::Name => "name",
+            CorrectionField::License => "license",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * A request to correct the metadata a class was verified with, e.g. a
+ * misleading name, as sent to the corrections endpoint of the backend.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorrectionRequest {
+    pub class_hash: String,
+    pub field: CorrectionField,
+    /// As it would be submitted with a verification, e.g. the long form of a
+    /// license.
+    pub proposed_value: String,
+    pub justification: String,
+    /// How the backend operators can reach the requester.
+    pub contact: String,
+}
+
+/**
+ * The backend took the request, to be reviewed by its operators.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CorrectionAck {
+    pub request_id: String,
+}
+
+impl CorrectionRequest {
+    /**
+     * The proposed value follows the rules of the field in a verification,
+     * and the request says why and who asks.
+     */
+    pub fn validate(&self) -> Result<()> {
+        let invalid =
+            |reason: String| -> Result<()> { Err(VerifierError::InvalidCorrection(reason).into()) };
+        match self.field {
+            CorrectionField::Name => {
+                if let Err(reason) = validate_class_name(&self.proposed_value) {
+                    return invalid(format!("Invalid proposed name: {}", reason));
+                }
+            }
+            CorrectionField::License => {
+                if self.proposed_value.trim().is_empty() {
+                    return invalid("The proposed license is empty".to_owned());
+                }
+            }
+        }
+        if self.justification.trim().is_empty() {
+            return invalid("The justification of the correction is empty".to_owned());
+        }
+        if self.contact.trim().is_empty() {
+            return invalid("The contact of the correction is empty".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+    use crate::validation::MAX_CLASS_NAME_LENGTH;
+
+    fn request(field: CorrectionField, proposed_value: &str) -> CorrectionRequest {
+        CorrectionRequest {
+            class_hash: "0x1".to_owned(),
+            field,
+            proposed_value: proposed_value.to_owned(),
+            justification: "Verified under the name of another project".to_owned(),
+            contact: "security@example.com".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_correction_follows_the_metadata_rules() {
+        assert!(request(CorrectionField::Name, "MyToken").validate().is_ok());
+        assert!(request(CorrectionField::License, "MIT License (MIT)")
+            .validate()
+            .is_ok());
+
+        let too_long = request(
+            CorrectionField::Name,
+            &"a".repeat(MAX_CLASS_NAME_LENGTH + 1),
+        );
+        assert_eq!(
+            error_code(&too_long.validate().unwrap_err()),
+            Some("VER-062")
+        );
+        assert!(request(CorrectionField::Name, " ").validate().is_err());
+        assert!(request(CorrectionField::License, "").validate().is_err());
+
+        let anonymous = CorrectionRequest {
+            contact: " ".to_owned(),
+            ..request(CorrectionField::Name, "MyToken")
+        };
+        assert!(anonymous.validate().is_err());
+        let unjustified = CorrectionRequest {
+            justification: String::new(),
+            ..request(CorrectionField::Name, "MyToken")
+        };
+        assert!(unjustified.validate().is_err());
+
+        let json = serde_json::to_value(request(CorrectionField::Name, "MyToken")).unwrap();
+        assert_eq!(json["field"], "name");
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::sources::SourceSet;
+
+/**
+ * A dependency vendored in the upload, i.e. a top-level directory of the
+ * upload other than the package's own with a Scarb.toml of its own, see the
+ * layout of the resolver.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendoredDependency {
+    pub name: String,
+    pub version: String,
+    /// Top-level directory of the upload holding its sources.
+    pub directory: String,
+    /// See `fingerprint`.
+    pub fingerprint: String,
+    /// Paths of its files in the upload.
+    pub files: Vec<String>,
+    /// Total size of its files, in bytes.
+    pub size: u64,
+}
+
+impl VendoredDependency {
+    pub fn reference(&self) -> DependencyRef {
+        DependencyRef {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            directory: self.directory.clone(),
+            fingerprint: self.fingerprint.clone(),
+        }
+    }
+}
+
+/**
+ * A dependency left out of the upload, as listed in the form for the backend
+ * to restore its sources under `directory`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyRef {
+    pub name: String,
+    pub version: String,
+    pub directory: String,
+    pub fingerprint: String,
+}
+
+/**
+ * Hex SHA-256 digest of the name, the version and the files of a dependency,
+ * with the paths relative to its directory so that it doesn't depend on where
+ * the upload puts it.
+ */
+pub fn fingerprint<'a>(
+    name: &str,
+    version: &str,
+    files: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+) -> String {
+    let mut files: Vec<(&str, &[u8])> = files.into_iter().collect();
+    files.sort();
+    let mut hasher = Sha256::new();
+    for value in [name.as_bytes(), version.as_bytes()] {
+        hasher.update(value);
+        hasher.update([0]);
+    }
+    for (path, contents) in files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(contents.len().to_string());
+        hasher.update([0]);
+        hasher.update(contents);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/**
+ * The dependencies vendored in `sources`, in the order of their directory.
+ * `package_dir` is the directory of the package being verified, which is
+ * always uploaded. Directories without a Scarb.toml naming the package and
+ * its version, such as workspace members inheriting it, are left alone.
+ */
+pub fn vendored_dependencies(sources: &SourceSet, package_dir: &str) -> Vec<VendoredDependency> {
+    let mut directories: BTreeMap<&str, Vec<(&str, &[u8])>> = BTreeMap::new();
+    for (path, contents) in sources.iter() {
+        if let Some((directory, relative)) = path.split_once('/') {
+            if directory != package_dir {
+                directories
+                    .entry(directory)
+                    .or_default()
+                    .push((relative, contents));
+            }
+        }
+    }
+
+    let mut dependencies = vec![];
+    for (directory, files) in directories {
+        let Some((name, version)) = files
+            .iter()
+            .find(|(path, _)| *path == "Scarb.toml")
+            .and_then(|(_, manifest)| package_of(manifest))
+        else {
+            continue;
+        };
+        dependencies.push(VendoredDependency {
+            fingerprint: fingerprint(&name, &version, files.iter().copied()),
+            name,
+            version,
+            directory: directory.to_owned(),
+            files: files
+                .iter()
+                .map(|(path, _)| format!("{}/{}", directory, path))
+                .collect(),
+            size: files
+                .iter()
+                .map(|(_, contents)| contents.len() as u64)
+                .sum(),
+        });
+    }
+    dependencies
+}
+
+/// Name and version of the package of a Scarb.toml.
+fn package_of(manifest: &[u8]) -> Option<(String, String)> {
+    let manifest = std::str::from_utf8(manifest)
+        .ok()?
+        .parse::<toml::Value>()
+        .ok()?;
+    let package = manifest.get("package")?;
+    let field = |field: &str| package.get(field)?.as_str().map(str::to_owned);
+    Some((field("name")?, field("version")?))
+}
+
+/**
+ * Whether a vendored dependency is left out of the upload, and why.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    /// The backend already has its sources, they are only referenced.
+    Cached,
+    /// The backend doesn't have its sources.
+    NotCached,
+    /// The backend can't tell which sources it has, e.g. an older release.
+    Unsupported,
+    /// Asking the backend which sources it has failed.
+    LookupFailed,
+    /// Left in the upload by choice, e.g. with --vendor-all-dependencies.
+    Disabled,
+}
+
+impl DependencyStatus {
+    pub fn is_skipped(&self) -> bool {
+        *self == DependencyStatus::Cached
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DependencyStatus::Cached => "skipped, the backend already has it",
+            DependencyStatus::NotCached => "uploaded, the backend doesn't have it",
+            DependencyStatus::Unsupported => {
+                "uploaded, the backend doesn't report the dependencies it has"
+            }
+            DependencyStatus::LookupFailed => {
+                "uploaded, the backend couldn't be asked whether it has it"
+            }
+            DependencyStatus::Disabled => "uploaded, dependency caching is disabled",
+        }
+    }
+}
+
+/**
+ * The vendored dependencies of an upload with what happens to each, from the
+ * fingerprints the backend reports having.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyPlan {
+    pub dependencies: Vec<(VendoredDependency, DependencyStatus)>,
+    /// Why the backend couldn't be asked which sources it has, to warn about.
+    pub lookup_error: Option<String>,
+}
+
+impl DependencyPlan {
+    /**
+     * `known` are the fingerprints the backend has, `None` when it can't tell,
+     * in which case every dependency is uploaded.
+     */
+    pub fn new(dependencies: Vec<VendoredDependency>, known: Option<&[String]>) -> Self {
+        let dependencies = dependencies
+            .into_iter()
+            .map(|dependency| {
+                let status = match known {
+                    None => DependencyStatus::Unsupported,
+                    Some(known) if known.contains(&dependency.fingerprint) => {
+                        DependencyStatus::Cached
+                    }
+                    Some(_) => DependencyStatus::NotCached,
+                };
+                (dependency, status)
+            })
+            .collect();
+        Self {
+            dependencies,
+            lookup_error: None,
+        }
+    }
+
+    /**
+     * Every dependency uploaded, asking the backend having failed with `error`.
+     */
+    pub fn lookup_failed(dependencies: Vec<VendoredDependency>, error: String) -> Self {
+        Self {
+            dependencies: dependencies
+                .into_iter()
+                .map(|dependency| (dependency, DependencyStatus::LookupFailed))
+                .collect(),
+            lookup_error: Some(error),
+        }
+    }
+
+    /**
+     * Every dependency uploaded, without asking the backend.
+     */
+    pub fn disabled(dependencies: Vec<VendoredDependency>) -> Self {
+        Self {
+            dependencies: dependencies
+                .into_iter()
+                .map(|dependency| (dependency, DependencyStatus::Disabled))
+                .collect(),
+            lookup_error: None,
+        }
+    }
+
+    /**
+     * The dependencies left out of the upload, for the form.
+     */
+    pub fn skipped(&self) -> Vec<DependencyRef> {
+        self.dependencies
+            .iter()
+            .filter(|(_, status)| status.is_skipped())
+            .map(|(dependency, _)| dependency.reference())
+            .collect()
+    }
+
+    /**
+     * Remove the files of the skipped dependencies from `sources`.
+     */
+    pub fn strip(&self, sources: &mut SourceSet) {
+        let skipped: Vec<&String> = self
+            .dependencies
+            .iter()
+            .filter(|(_, status)| status.is_skipped())
+            .flat_map(|(dependency, _)| dependency.files.iter())
+            .collect();
+        sources.retain(|path| !skipped.iter().any(|file| file.as_str() == path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, version: &str) -> String {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"{}\"\n",
+            name, version
+        )
+    }
+
+    fn sources() -> SourceSet {
+        let mut sources = SourceSet::new();
+        for (path, contents) in [
+            ("token/Scarb.toml", manifest("token", "0.1.0")),
+            ("token/src/lib.cairo", "mod erc20;".to_owned()),
+            (
+                "openzeppelin/Scarb.toml",
+                manifest("openzeppelin", "0.20.0"),
+            ),
+            ("openzeppelin/src/lib.cairo", "mod token;".to_owned()),
+            ("alexandria/Scarb.toml", manifest("alexandria", "0.3.0")),
+            ("alexandria/src/lib.cairo", "mod math;".to_owned()),
+            ("LICENSE", "MIT".to_owned()),
+            (
+                "member/Scarb.toml",
+                "[package]\nname = \"member\"\nversion.workspace = true\n".to_owned(),
+            ),
+        ] {
+            sources.add(path, contents).unwrap();
+        }
+        sources
+    }
+
+    #[test]
+    fn test_vendored_dependencies_exclude_the_package() {
+        let dependencies = vendored_dependencies(&sources(), "token");
+        let names: Vec<(&str, &str)> = dependencies
+            .iter()
+            .map(|dependency| (dependency.name.as_str(), dependency.version.as_str()))
+            .collect();
+        assert_eq!(names, [("alexandria", "0.3.0"), ("openzeppelin", "0.20.0")]);
+        assert_eq!(
+            dependencies[1].files,
+            ["openzeppelin/Scarb.toml", "openzeppelin/src/lib.cairo"]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_directory_and_order() {
+        let files = [
+            ("Scarb.toml", b"a".as_slice()),
+            ("src/lib.cairo", b"b".as_slice()),
+        ];
+        let reversed = [files[1], files[0]];
+        assert_eq!(
+            fingerprint("oz", "1.0.0", files),
+            fingerprint("oz", "1.0.0", reversed)
+        );
+        assert_ne!(
+            fingerprint("oz", "1.0.0", files),
+            fingerprint("oz", "1.0.1", files)
+        );
+        assert_ne!(
+            fingerprint("oz", "1.0.0", files),
+            fingerprint(
+                "oz",
+                "1.0.0",
+                [files[0], ("src/lib.cairo", b"c".as_slice())]
+            )
+        );
+    }
+
+    #[test]
+    fn test_only_cached_dependencies_are_stripped() {
+        let mut sources = sources();
+        let dependencies = vendored_dependencies(&sources, "token");
+        let known = vec![
+            dependencies[1].fingerprint.clone(),
+            "0xunrelated".to_owned(),
+        ];
+        let plan = DependencyPlan::new(dependencies, Some(known.as_slice()));
+
+        assert_eq!(plan.skipped().len(), 1);
+        assert_eq!(plan.skipped()[0].name, "openzeppelin");
+        plan.strip(&mut sources);
+        assert_eq!(
+            sources.names().collect::<Vec<_>>(),
+            [
+                "token/Scarb.toml",
+                "token/src/lib.cairo",
+                "alexandria/Scarb.toml",
+                "alexandria/src/lib.cairo",
+                "LICENSE",
+                "member/Scarb.toml"
+            ]
+        );
+
+        let (alexandria, _) = plan.dependencies[0].clone();
+        let unsupported = DependencyPlan::new(vec![alexandria], None);
+        assert!(unsupported.skipped().is_empty());
+        assert_eq!(unsupported.dependencies[0].1, DependencyStatus::Unsupported);
+    }
+}
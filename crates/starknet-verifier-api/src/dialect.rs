@@ -0,0 +1,257 @@
+use anyhow::Result;
+
+use crate::errors::VerifierError;
+use crate::form::{
+    ALIAS_FIELD, CACHED_DEPENDENCIES_FIELD, EXTERNAL_CONTRACT_FIELD, PLUGINS_FIELD,
+    SCHEMA_VERSION_FIELD,
+};
+
+/// Variable of the environment or the user config selecting the dialect.
+pub const DIALECT_VAR: &str = "VOYAGER_FORM_DIALECT";
+
+/**
+ * Metadata field of the verification form, whatever a backend calls it. Files
+ * and documentation keep their prefixes in every dialect.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormField {
+    SchemaVersion,
+    CompilerVersion,
+    ScarbVersion,
+    License,
+    Name,
+    ContractFile,
+    ProjectDirPath,
+    Alias,
+    TargetName,
+    TargetSierra,
+    TargetCasm,
+    ExternalContract,
+    Plugins,
+    CachedDependencies,
+}
+
+impl FormField {
+    /**
+     * Every field. The match below stops compiling when a field is added, so
+     * that the dialect tables get updated along with it.
+     */
+    pub fn all() -> [FormField; 14] {
+        let fields = [
+            FormField::SchemaVersion,
+            FormField::CompilerVersion,
+            FormField::ScarbVersion,
+            FormField::License,
+            FormField::Name,
+            FormField::ContractFile,
+            FormField::ProjectDirPath,
+            FormField::Alias,
+            FormField::TargetName,
+            FormField::TargetSierra,
+            FormField::TargetCasm,
+            FormField::ExternalContract,
+            FormField::Plugins,
+            FormField::CachedDependencies,
+        ];
+        for field in &fields {
+            match field {
+                FormField::SchemaVersion
+                | FormField::CompilerVersion
+                | FormField::ScarbVersion
+                | FormField::License
+                | FormField::Name
+                | FormField::ContractFile
+                | FormField::ProjectDirPath
+                | FormField::Alias
+                | FormField::TargetName
+                | FormField::TargetSierra
+                | FormField::TargetCasm
+                | FormField::ExternalContract
+                | FormField::Plugins
+                | FormField::CachedDependencies => (),
+            }
+        }
+        fields
+    }
+}
+
+/// Field names of the Voyager backend.
+const VOYAGER_FIELDS: [(FormField, &str); 14] = [
+    (FormField::SchemaVersion, SCHEMA_VERSION_FIELD),
+    (FormField::CompilerVersion, "compiler_version"),
+    (FormField::ScarbVersion, "scarb_version"),
+    (FormField::License, "license"),
+    (FormField::Name, "name"),
+    (FormField::ContractFile, "contract_file"),
+    (FormField::ProjectDirPath, "project_dir_path"),
+    (FormField::Alias, ALIAS_FIELD),
+    (FormField::TargetName, "target_name"),
+    (FormField::TargetSierra, "target_sierra"),
+    (FormField::TargetCasm, "target_casm"),
+    (FormField::ExternalContract, EXTERNAL_CONTRACT_FIELD),
+    (FormField::Plugins, PLUGINS_FIELD),
+    (FormField::CachedDependencies, CACHED_DEPENDENCIES_FIELD),
+];
+
+/// Field names of the forks that call the compiler version `cairo_version`.
+const CAIRO_VERSION_FIELDS: [(FormField, &str); 14] = [
+    (FormField::SchemaVersion, SCHEMA_VERSION_FIELD),
+    (FormField::CompilerVersion, "cairo_version"),
+    (FormField::ScarbVersion, "scarb_version"),
+    (FormField::License, "license"),
+    (FormField::Name, "name"),
+    (FormField::ContractFile, "contract_file"),
+    (FormField::ProjectDirPath, "project_dir_path"),
+    (FormField::Alias, ALIAS_FIELD),
+    (FormField::TargetName, "target_name"),
+    (FormField::TargetSierra, "target_sierra"),
+    (FormField::TargetCasm, "target_casm"),
+    (FormField::ExternalContract, EXTERNAL_CONTRACT_FIELD),
+    (FormField::Plugins, PLUGINS_FIELD),
+    (FormField::CachedDependencies, CACHED_DEPENDENCIES_FIELD),
+];
+
+/**
+ * The names a backend expects for the metadata fields of the form. Self-hosted
+ * forks renamed some of them, the default is the Voyager backend.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Dialect {
+    #[default]
+    Voyager,
+    /// `cairo_version` instead of `compiler_version`.
+    CairoVersion,
+}
+
+impl Dialect {
+    pub const ALL: &'static [Dialect] = &[Dialect::Voyager, Dialect::CairoVersion];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dialect::Voyager => "voyager",
+            Dialect::CairoVersion => "cairo-version",
+        }
+    }
+
+    fn fields(self) -> &'static [(FormField, &'static str)] {
+        match self {
+            Dialect::Voyager => &VOYAGER_FIELDS,
+            Dialect::CairoVersion => &CAIRO_VERSION_FIELDS,
+        }
+    }
+
+    /// Name of `field` on the wire.
+    pub fn field_name(self, field: FormField) -> &'static str {
+        self.fields()
+            .iter()
+            .find(|(mapped, _)| *mapped == field)
+            .map(|(_, name)| *name)
+            .expect("every dialect maps every field")
+    }
+
+    pub fn names() -> Vec<String> {
+        Dialect::ALL.iter().map(ToString::to_string).collect()
+    }
+
+    /**
+     * The dialect called `name`, from `origin`, or a `VerifierError::UnknownDialect`
+     * listing the known ones.
+     */
+    pub fn from_name(name: &str, origin: &str) -> Result<Dialect> {
+        Dialect::ALL
+            .iter()
+            .copied()
+            .find(|dialect| dialect.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                VerifierError::UnknownDialect {
+                    name: name.to_owned(),
+                    origin: origin.to_owned(),
+                    available: Dialect::names(),
+                }
+                .into()
+            })
+    }
+}
+
+impl std::fmt::Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/**
+ * The dialect to submit with: `--dialect`, or else the `DIALECT_VAR` setting,
+ * or else the one the backend advertises in its capabilities, or else the
+ * default. Unknown names fail rather than falling back, the submission would
+ * be rejected or misread.
+ */
+pub fn select_dialect(
+    flag: Option<Dialect>,
+    setting: Option<&str>,
+    advertised: Option<&str>,
+) -> Result<Dialect> {
+    if let Some(dialect) = flag {
+        return Ok(dialect);
+    }
+    if let Some(name) = setting {
+        return Dialect::from_name(name, DIALECT_VAR);
+    }
+    match advertised {
+        Some(name) => Dialect::from_name(name, "the capabilities of the backend"),
+        None => Ok(Dialect::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_every_dialect_maps_every_field_once() {
+        for dialect in Dialect::ALL {
+            for field in FormField::all() {
+                let mappings = dialect
+                    .fields()
+                    .iter()
+                    .filter(|(mapped, _)| *mapped == field)
+                    .count();
+                assert_eq!(mappings, 1, "{:?} in the {} dialect", field, dialect);
+            }
+            let names: HashSet<&str> = dialect.fields().iter().map(|(_, name)| *name).collect();
+            assert_eq!(names.len(), FormField::all().len(), "{} dialect", dialect);
+        }
+        assert_eq!(
+            Dialect::CairoVersion.field_name(FormField::CompilerVersion),
+            "cairo_version"
+        );
+    }
+
+    #[test]
+    fn test_dialect_is_selected_by_precedence() {
+        assert_eq!(select_dialect(None, None, None).unwrap(), Dialect::Voyager);
+        assert_eq!(
+            select_dialect(None, None, Some("cairo-version")).unwrap(),
+            Dialect::CairoVersion
+        );
+        assert_eq!(
+            select_dialect(None, Some("voyager"), Some("cairo-version")).unwrap(),
+            Dialect::Voyager
+        );
+        assert_eq!(
+            select_dialect(Some(Dialect::CairoVersion), Some("fork"), None).unwrap(),
+            Dialect::CairoVersion
+        );
+
+        let unknown = select_dialect(None, Some("fork"), None).unwrap_err();
+        assert_eq!(error_code(&unknown), Some("VER-058"));
+        assert!(
+            unknown.to_string().contains("voyager, cairo-version"),
+            "{}",
+            unknown
+        );
+        assert!(unknown.to_string().contains(DIALECT_VAR), "{}", unknown);
+    }
+}
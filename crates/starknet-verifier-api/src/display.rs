@@ -0,0 +1,40 @@
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// Characters reordering the text around them, so that e.g. a file name can
+/// be made to look like another one.
+const BIDI_CONTROLS: &[char] = &[
+    '\u{061c}', '\u{200e}', '\u{200f}', '\u{202a}', '\u{202b}', '\u{202c}', '\u{202d}', '\u{202e}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// Characters without width, which can hide the difference between two names.
+/// The joiners are left alone, some scripts and emoji need them.
+const INVISIBLE: &[char] = &['\u{200b}', '\u{2060}', '\u{feff}'];
+
+fn needs_escaping(c: char) -> bool {
+    c.is_control() || BIDI_CONTROLS.contains(&c) || INVISIBLE.contains(&c)
+}
+
+/**
+ * A user-controlled string, such as a file or class name, as it is printed on
+ * the terminal. Control, bidi and invisible characters are shown escaped, e.g.
+ * `\u{202e}`, so that they can neither garble the output nor spoof another
+ * name, and the rest is normalized to NFC. This is for display only, the
+ * uploaded names and contents are never sanitized.
+ */
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if is_nfc(text) && !text.chars().any(needs_escaping) {
+        return Cow::Borrowed(text);
+    }
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.nfc() {
+        if needs_escaping(c) {
+            sanitized.extend(c.escape_default());
+        } else {
+            sanitized.push(c);
+        }
+    }
+    Cow::Owned(sanitized)
+}
@@ -0,0 +1,1354 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::display::sanitize;
+use crate::humanize::{format_duration, format_size};
+
+/**
+ * Registry of the codes given to user-facing errors, so that an error can be
+ * looked up even after its message was reworded. Codes are never removed or
+ * reused: a code that is no longer emitted is marked as deprecated instead.
+ * Front-ends register the codes of their own errors, e.g. invalid arguments,
+ * in the same sequence.
+ */
+pub struct ErrorCodeEntry {
+    pub code: &'static str,
+    pub summary: &'static str,
+    pub explanation: &'static str,
+    pub deprecated: bool,
+}
+
+pub const ERROR_CODES: &[ErrorCodeEntry] = &[
+    ErrorCodeEntry {
+        code: "VER-001",
+        summary: "Unknown network",
+        explanation: "The network must be one of mainnet, sepolia, local or custom, or the base url of a self-hosted instance.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-002",
+        summary: "Class lookup failed",
+        explanation: "The explorer answered the class lookup with an unexpected status. Check that the network endpoints are correct and reachable.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-003",
+        summary: "Class already verified",
+        explanation: "The class is already verified. Pass --force to resubmit it, e.g. to change its name or license.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-004",
+        summary: "Metadata change not confirmed",
+        explanation: "Resubmitting would change the name or license displayed for the class. Pass --yes to confirm in non-interactive environments.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-005",
+        summary: "Metadata change aborted",
+        explanation: "The change of the verified name or license was declined, nothing was submitted.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-006",
+        summary: "Unsupported form schema version",
+        explanation: "The verification server and this client don't agree on the layout of the submitted form. Upgrade whichever side is older.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-007",
+        summary: "Files rejected",
+        explanation: "The server rejected some of the submitted files, each of them is listed with a hint on how to fix it.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-008",
+        summary: "Dispatch failed",
+        explanation: "The server refused the verification job. The message returned by the server is included.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-009",
+        summary: "Job not found",
+        explanation: "The server doesn't know the verification job. The job id may be wrong, or the job may have expired.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-010",
+        summary: "Unexpected job status response",
+        explanation: "The server answered a job status request with an unexpected status code.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-011",
+        summary: "Verification failed",
+        explanation: "The sources compiled but don't produce the declared class. Check that the compiler version, the contract file and the sources match the deployment.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-012",
+        summary: "Compilation failed",
+        explanation: "The server couldn't compile the submitted sources. Make sure the project builds locally with the same Scarb and Cairo versions.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-013",
+        summary: "Verification timed out",
+        explanation: "The job didn't finish within --poll-timeout. It may still complete, check it again later or poll for longer.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-014",
+        summary: "No contract to verify",
+        explanation: "No contract was found in the package. Mark the contract to verify in the [tool.voyager] section of Scarb.toml.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-015",
+        summary: "Several contracts to verify",
+        explanation: "Only one contract can be verified at a time, keep a single one in the [tool.voyager] section of Scarb.toml.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-016",
+        summary: "Project state locked",
+        explanation: "Another verifier instance is updating the dispatch cache of the project. Wait for it to finish, or pass --no-project-state.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-017",
+        summary: "Unsupported file name",
+        explanation: "File names can't contain control characters, double quotes or backslashes, which would break the headers of the multipart form. Rename the file.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-018",
+        summary: "Collection root too narrow",
+        explanation: "The directory given with --collection-root must contain the package and all of its path dependencies.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-019",
+        summary: "Unknown git revision",
+        explanation: "The revision given with --git-ref doesn't exist in the repository of the project.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-020",
+        summary: "Documentation file not markdown",
+        explanation: "Only markdown files (.md or .markdown) can be uploaded with --readme and --doc.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-021",
+        summary: "Documentation file too large",
+        explanation: "Documentation files are limited to 256 KiB each. Split the file, or leave it out with --no-readme.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-022",
+        summary: "Compiler version too old",
+        explanation: "The sources use language features introduced after the selected Cairo version, so the remote compilation would fail. Select a newer compiler, or pass --allow-syntax <id> for each false positive.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-023",
+        summary: "Invalid alias",
+        explanation: "Aliases follow the same rules as the class name: they can't be empty or longer than 50 characters.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-024",
+        summary: "Path outside of the upload",
+        explanation: "The project directory, the contract file and the path dependencies of the uploaded manifests must point into the uploaded files, the backend sees nothing else. Check --collection-root and the path dependencies of the package.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-026",
+        summary: "Class hash mismatch",
+        explanation: "The class hash reported by the backend differs from the one given with --expect-hash. The verifier exits with code 3.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-027",
+        summary: "Unsupported form style",
+        explanation: "The verification server doesn't accept files laid out with the selected --form-style. Rerun with --form-style legacy when the server doesn't list the styles it supports.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-028",
+        summary: "Job record gone, class verified",
+        explanation: "The backend no longer knows the job, but the class is verified. The success is inferred from the class status, so the job details are unavailable.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-029",
+        summary: "Run not reproducible",
+        explanation: "--locked forbids a missing or outdated Scarb.lock and unpinned compiler versions, --frozen also forbids network access other than the verification endpoints. The error names what to pin and how.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-030",
+        summary: "Sources reference the build machine",
+        explanation: "--scan-paths found absolute paths or home directories in the uploaded files, which explorers would publish. Under --strict publishing them has to be confirmed, or acknowledged with --yes.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-032",
+        summary: "Local compiler plugins",
+        explanation: "Compiler plugins that are path dependencies only exist on this machine, so the server can't build with them. Publish them to a registry or a git repository, this is only a warning without --strict.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-033",
+        summary: "Class hash not confirmed",
+        explanation: "Runs without a terminal can't confirm which class will be marked verified. Check the preflight record, emitted with --events, and pass --yes.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-034",
+        summary: "Verification aborted at the class hash confirmation",
+        explanation: "The class hash was not confirmed, nothing was submitted.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-036",
+        summary: "Uploaded file names collide",
+        explanation: "Two resolved files would be uploaded under the same name, ignoring case. Rename one of them.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-037",
+        summary: "RPC chain not confirmed",
+        explanation: "The chain id of --rpc-url isn't the chain of --network, or the one given with --chain-id, so the checks may be about another class. Point --rpc-url at the network, or pass --yes if the mismatch is expected.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-038",
+        summary: "Unknown job status",
+        explanation: "The verification API keeps reporting a job status this version doesn't know. The job may still complete, check it on the explorer and update the verifier.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-041",
+        summary: "Job still in progress",
+        explanation: "`status <JOB_ID>` found the job unfinished and exits with code 4. Check again later, or pass --wait to poll it until it finishes.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-042",
+        summary: "Identical payload already failed",
+        explanation: "A previous job failed with exactly the same payload for the same class and network, and would fail the same way again. Fix the reported failure, or pass --resubmit-identical if something outside of the payload changed.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-043",
+        summary: "Unsafe archive entry",
+        explanation: "An entry of the --stdin-tar stream has an absolute path or goes up with `..`, so nothing is extracted. Create the archive with relative paths, e.g. `tar -cf - -C <root> .`.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-044",
+        summary: "Invalid endpoint",
+        explanation: "An endpoint of the selected network is empty, relative or not an http(s) url, as with an unconfigured custom network. Pass --api-url and --public-api-url, or the base url of the instance with --network.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-045",
+        summary: "Local stack unreachable",
+        explanation: "Nothing answers at the endpoints of the local network. Start the verification stack first, see RUNNING_LOCALLY.md.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-046",
+        summary: "Missing or invalid API key",
+        explanation: "The verification API answered 401 or 403, the API key is missing or refused. Pass it with --api-key or VOYAGER_API_KEY.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-047",
+        summary: "Request failed after retries",
+        explanation: "A request kept failing with connection errors, timeouts, 429 or 5xx responses until its attempts, see VOYAGER_HTTP_ATTEMPTS, ran out. Check the status of the instance, or retry later.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-048",
+        summary: "Unsupported Scarb or Cairo version",
+        explanation: "The local Scarb or Cairo version, or the cairo-version of Scarb.toml, isn't one this client can verify with. Run `supported-versions` to list the supported ones.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-049",
+        summary: "Verification cancelled",
+        explanation: "The verification was cancelled, e.g. with Ctrl-C, and the request in flight aborted. A job already dispatched may still complete, check it with the status command.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-050",
+        summary: "Class not found on the network",
+        explanation: "The backend doesn't know the class hash on the network it was submitted to. Check that the class was declared on --network.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-051",
+        summary: "Backend down for maintenance",
+        explanation: "The backend is down for a scheduled maintenance. With --on-maintenance wait the run waits for its announced end, with fail it stops right away.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-052",
+        summary: "Contract file outside of the source root",
+        explanation: "The contract path of [tool.voyager] resolves outside of the source root of the package, where the compiler never looks. Set it relative to the source root.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-053",
+        summary: "Contract file only re-exports the contract",
+        explanation: "The contract file only declares the module of the contract, which is defined in another file. Point [tool.voyager] at the file defining the #[starknet::contract] module, only a warning without --strict.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-054",
+        summary: "Contract module not found",
+        explanation: "No #[starknet::contract] module was found in the contract file or the modules it declares. Check its path in [tool.voyager], only a warning without --strict.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-055",
+        summary: "Upload too large",
+        explanation: "The files to upload are over the upload limit, 10 MiB by default. Exclude the largest in .voyagerignore, or raise --max-upload-size if the backend accepts more.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-056",
+        summary: "Upload rejected as too large",
+        explanation: "The backend answered 413, its upload limit is lower than the one checked locally. Exclude large files in .voyagerignore, and lower --max-upload-size to catch this before uploading.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-057",
+        summary: "Correction request rejected",
+        explanation: "The backend refused the request to correct the metadata of a verified class, the message says why.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-058",
+        summary: "Unknown form dialect",
+        explanation: "The form dialect from --dialect, VOYAGER_FORM_DIALECT or the capabilities of the backend isn't one this client knows. Pick one of the dialects listed, or upgrade the client.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-059",
+        summary: "Starknet RPC unreachable",
+        explanation: "The Starknet JSON-RPC node of --rpc-url or STARKNET_RPC_URL couldn't be reached or didn't answer with JSON-RPC. This says nothing about whether the class is declared.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-060",
+        summary: "Sources don't compile to the class",
+        explanation: "None of the classes the sources compile to locally has the class hash being verified, so nothing was uploaded. Check out the sources the class was declared from, or pass --skip-hash-check.",
+        deprecated: false,
+    },
+    ErrorCodeEntry {
+        code: "VER-062",
+        summary: "Invalid correction request",
+        explanation: "The proposed value, the justification or the contact of a correction request is invalid or empty, the message says which.",
+        deprecated: false,
+    },
+];
+
+pub fn find_error_code(code: &str) -> Option<&'static ErrorCodeEntry> {
+    ERROR_CODES
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+/**
+ * Errors reported to the user. Their message always starts with their code.
+ */
+#[derive(Debug, thiserror::Error)]
+pub enum VerifierError {
+    UnknownNetwork(String),
+    ClassLookupFailed {
+        status: u16,
+        message: String,
+    },
+    AlreadyVerified {
+        class_hash: String,
+        name: String,
+    },
+    MetadataChangeNotConfirmed,
+    MetadataChangeAborted,
+    UnsupportedSchemaVersion {
+        description: String,
+        supported: Vec<u32>,
+    },
+    FilesRejected {
+        error: String,
+        files: String,
+    },
+    DispatchFailed {
+        status: u16,
+        message: String,
+    },
+    JobNotFound,
+    UnexpectedJobStatus {
+        status: u16,
+        message: String,
+    },
+    VerificationFailed(String),
+    CompilationFailed(String),
+    Timeout {
+        job_id: String,
+        waited_secs: u64,
+    },
+    NoContracts,
+    SeveralContracts,
+    StateLocked {
+        path: String,
+        timeout_secs: u64,
+    },
+    UnsupportedFileName {
+        file_name: String,
+        character: char,
+    },
+    CollectionRootTooNarrow {
+        root: String,
+        outside: String,
+    },
+    UnknownGitRevision(String),
+    DocumentNotMarkdown(String),
+    DocumentTooLarge {
+        path: String,
+        size: u64,
+        limit: u64,
+    },
+    CompilerTooOld {
+        selected: String,
+        required: String,
+        findings: String,
+    },
+    InvalidAlias {
+        alias: String,
+        reason: String,
+    },
+    PathOutsideUpload {
+        field: String,
+        path: String,
+        nearest: String,
+    },
+    HashMismatch {
+        expected: String,
+        actual: String,
+    },
+    UnsupportedFormStyle {
+        style: String,
+        supported: Vec<String>,
+    },
+    JobGoneClassVerified {
+        job_id: String,
+        class_hash: String,
+    },
+    NotReproducible {
+        flag: String,
+        what: String,
+        hint: String,
+    },
+    EnvironmentPaths {
+        findings: String,
+    },
+    LocalPlugin {
+        plugins: String,
+    },
+    ClassNotConfirmed {
+        class_hash: String,
+    },
+    ClassAborted,
+    UploadNameCollision {
+        first: String,
+        second: String,
+    },
+    ChainMismatchNotConfirmed {
+        chain_id: String,
+        network: String,
+    },
+    UnknownJobStatus(u8),
+    JobInProgress {
+        job_id: String,
+        status: String,
+    },
+    IdenticalPayloadFailed {
+        job_id: String,
+        status: String,
+        description: Option<String>,
+    },
+    UnsafeArchiveEntry(String),
+    InvalidEndpoint {
+        network: String,
+        endpoint: String,
+        flag: String,
+        variable: String,
+        problem: String,
+    },
+    LocalStackUnreachable(String),
+    ApiKeyRejected {
+        status: u16,
+        provided: bool,
+    },
+    RetriesExhausted {
+        endpoint: String,
+        attempts: u32,
+        cause: String,
+    },
+    UnsupportedToolVersion {
+        tool: String,
+        version: String,
+        supported: Vec<String>,
+    },
+    Cancelled {
+        job_id: Option<String>,
+    },
+    ClassNotFound {
+        class_hash: String,
+        network: String,
+    },
+    Maintenance {
+        endpoint: String,
+        message: String,
+        /// The announced `Retry-After`, when there is one.
+        retry_after_secs: Option<u64>,
+    },
+    ContractFileOutsideSourceRoot {
+        contract_file: String,
+        source_root: String,
+    },
+    ContractFileReexport {
+        contract_file: String,
+        contract: String,
+        defined_in: String,
+        /// The path to set in [tool.voyager], relative to the source root.
+        suggestion: String,
+    },
+    ContractModuleNotFound {
+        contract_file: String,
+        contract: String,
+    },
+    UploadTooLarge {
+        size: u64,
+        limit: u64,
+        /// The largest files, one per line, largest first, already sanitized.
+        largest: String,
+    },
+    UploadRejectedTooLarge,
+    CorrectionRejected {
+        status: u16,
+        message: String,
+    },
+    UnknownDialect {
+        name: String,
+        /// Where the name came from, e.g. `--dialect`.
+        origin: String,
+        available: Vec<String>,
+    },
+    RpcUnreachable {
+        url: String,
+        cause: String,
+    },
+    LocalHashMismatch {
+        given: String,
+        /// Each hash the sources compile to, with the artifact it comes from.
+        local: Vec<String>,
+    },
+    InvalidCorrection(String),
+}
+
+impl VerifierError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifierError::UnknownNetwork(_) => "VER-001",
+            VerifierError::ClassLookupFailed { .. } => "VER-002",
+            VerifierError::AlreadyVerified { .. } => "VER-003",
+            VerifierError::MetadataChangeNotConfirmed => "VER-004",
+            VerifierError::MetadataChangeAborted => "VER-005",
+            VerifierError::UnsupportedSchemaVersion { .. } => "VER-006",
+            VerifierError::FilesRejected { .. } => "VER-007",
+            VerifierError::DispatchFailed { .. } => "VER-008",
+            VerifierError::JobNotFound => "VER-009",
+            VerifierError::UnexpectedJobStatus { .. } => "VER-010",
+            VerifierError::VerificationFailed(_) => "VER-011",
+            VerifierError::CompilationFailed(_) => "VER-012",
+            VerifierError::Timeout { .. } => "VER-013",
+            VerifierError::NoContracts => "VER-014",
+            VerifierError::SeveralContracts => "VER-015",
+            VerifierError::StateLocked { .. } => "VER-016",
+            VerifierError::UnsupportedFileName { .. } => "VER-017",
+            VerifierError::CollectionRootTooNarrow { .. } => "VER-018",
+            VerifierError::UnknownGitRevision(_) => "VER-019",
+            VerifierError::DocumentNotMarkdown(_) => "VER-020",
+            VerifierError::DocumentTooLarge { .. } => "VER-021",
+            VerifierError::CompilerTooOld { .. } => "VER-022",
+            VerifierError::InvalidAlias { .. } => "VER-023",
+            VerifierError::PathOutsideUpload { .. } => "VER-024",
+            VerifierError::HashMismatch { .. } => "VER-026",
+            VerifierError::UnsupportedFormStyle { .. } => "VER-027",
+            VerifierError::JobGoneClassVerified { .. } => "VER-028",
+            VerifierError::NotReproducible { .. } => "VER-029",
+            VerifierError::EnvironmentPaths { .. } => "VER-030",
+            VerifierError::LocalPlugin { .. } => "VER-032",
+            VerifierError::ClassNotConfirmed { .. } => "VER-033",
+            VerifierError::ClassAborted => "VER-034",
+            VerifierError::UploadNameCollision { .. } => "VER-036",
+            VerifierError::ChainMismatchNotConfirmed { .. } => "VER-037",
+            VerifierError::UnknownJobStatus(_) => "VER-038",
+            VerifierError::JobInProgress { .. } => "VER-041",
+            VerifierError::IdenticalPayloadFailed { .. } => "VER-042",
+            VerifierError::UnsafeArchiveEntry(_) => "VER-043",
+            VerifierError::InvalidEndpoint { .. } => "VER-044",
+            VerifierError::LocalStackUnreachable(_) => "VER-045",
+            VerifierError::ApiKeyRejected { .. } => "VER-046",
+            VerifierError::RetriesExhausted { .. } => "VER-047",
+            VerifierError::UnsupportedToolVersion { .. } => "VER-048",
+            VerifierError::Cancelled { .. } => "VER-049",
+            VerifierError::ClassNotFound { .. } => "VER-050",
+            VerifierError::Maintenance { .. } => "VER-051",
+            VerifierError::ContractFileOutsideSourceRoot { .. } => "VER-052",
+            VerifierError::ContractFileReexport { .. } => "VER-053",
+            VerifierError::ContractModuleNotFound { .. } => "VER-054",
+            VerifierError::UploadTooLarge { .. } => "VER-055",
+            VerifierError::UploadRejectedTooLarge => "VER-056",
+            VerifierError::CorrectionRejected { .. } => "VER-057",
+            VerifierError::UnknownDialect { .. } => "VER-058",
+            VerifierError::RpcUnreachable { .. } => "VER-059",
+            VerifierError::LocalHashMismatch { .. } => "VER-060",
+            VerifierError::InvalidCorrection(_) => "VER-062",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            VerifierError::UnknownNetwork(network) => format!("Unknown network: {}", network),
+            VerifierError::ClassLookupFailed { status, message } => format!(
+                "Unexpected status code {} when trying to get class with error {}",
+                status, message
+            ),
+            VerifierError::AlreadyVerified { class_hash, name } => format!(
+                "Class {} is already verified as {}, use --force to resubmit it",
+                class_hash, name
+            ),
+            VerifierError::MetadataChangeNotConfirmed => {
+                "Refusing to change the verified metadata without confirmation, use --yes to proceed"
+                    .to_owned()
+            }
+            VerifierError::MetadataChangeAborted => {
+                "Aborted, the verified metadata is unchanged".to_owned()
+            }
+            VerifierError::UnsupportedSchemaVersion { description, .. } => description.clone(),
+            VerifierError::FilesRejected { error, files } => format!(
+                "Verification job rejected with status 400: {}\nThe following files were rejected:\n{}",
+                error, files
+            ),
+            VerifierError::DispatchFailed { status, message } => format!(
+                "Verification job rejected with status {}: {}",
+                status, message
+            ),
+            VerifierError::JobNotFound => "Job not found".to_owned(),
+            VerifierError::UnexpectedJobStatus { status, message } => format!(
+                "Unexpected status code: {}, with error message: {}",
+                status, message
+            ),
+            VerifierError::VerificationFailed(description) => {
+                format!("Failed to verify: {:?}", description)
+            }
+            VerifierError::CompilationFailed(description) => {
+                format!("Compilation failed: {:?}", description)
+            }
+            VerifierError::Timeout {
+                job_id,
+                waited_secs,
+            } => format!(
+                "Timeout: verification job {} didn't finish after waiting {}. It may still complete, check it again later or raise --poll-timeout.",
+                job_id,
+                format_duration(Duration::from_secs(*waited_secs))
+            ),
+            VerifierError::NoContracts => "No contracts to verify".to_owned(),
+            VerifierError::SeveralContracts => {
+                "Only one contract can be verified at a time".to_owned()
+            }
+            VerifierError::StateLocked { path, timeout_secs } => format!(
+                "Another verifier instance holds the lock on {}, gave up after {}s",
+                path, timeout_secs
+            ),
+            VerifierError::UnsupportedFileName {
+                file_name,
+                character,
+            } => format!(
+                "Unsupported character '{}' in file name \"{}\", please rename the file",
+                character.escape_default(),
+                sanitize(file_name)
+            ),
+            VerifierError::CollectionRootTooNarrow { root, outside } => format!(
+                "The collection root {} doesn't contain {}",
+                root, outside
+            ),
+            VerifierError::UnknownGitRevision(git_ref) => {
+                format!("Unknown git revision {}", git_ref)
+            }
+            VerifierError::DocumentNotMarkdown(path) => {
+                format!(
+                    "Documentation file {} is not a markdown file",
+                    sanitize(path)
+                )
+            }
+            VerifierError::DocumentTooLarge { path, size, limit } => format!(
+                "Documentation file {} is {} bytes, the limit is {} bytes",
+                sanitize(path),
+                size,
+                limit
+            ),
+            VerifierError::CompilerTooOld {
+                selected,
+                required,
+                findings,
+            } => format!(
+                "The sources likely need Cairo {} or newer, but Cairo {} is selected:\n{}",
+                required, selected, findings
+            ),
+            VerifierError::InvalidAlias { alias, reason } => {
+                format!("Invalid alias \"{}\": {}", sanitize(alias), reason)
+            }
+            VerifierError::PathOutsideUpload {
+                field,
+                path,
+                nearest,
+            } => format!(
+                "{} {} is not part of the uploaded files, the nearest uploaded directory is {}",
+                sanitize(field),
+                sanitize(path),
+                sanitize(nearest)
+            ),
+            VerifierError::HashMismatch { expected, actual } => format!(
+                "The verified class hash {} doesn't match the expected {}",
+                sanitize(actual),
+                expected
+            ),
+            VerifierError::UnsupportedFormStyle { style, supported } if supported.is_empty() => {
+                format!(
+                    "The verification server doesn't accept the {} form style, retry with --form-style legacy",
+                    style
+                )
+            }
+            VerifierError::UnsupportedFormStyle { style, supported } => format!(
+                "The verification server doesn't accept the {} form style, it supports {}",
+                style,
+                sanitize(&supported.join(", "))
+            ),
+            VerifierError::JobGoneClassVerified { job_id, class_hash } => format!(
+                "Job {} no longer exists, but class {} is verified (inferred from the class status)",
+                sanitize(job_id),
+                class_hash
+            ),
+            VerifierError::NotReproducible { flag, what, hint } => {
+                format!("{} forbids {}: {}", flag, sanitize(what), hint)
+            }
+            VerifierError::EnvironmentPaths { findings } => format!(
+                "The sources reference paths of the build machine, which would be published:\n{}",
+                findings
+            ),
+            VerifierError::LocalPlugin { plugins } => format!(
+                "The compiler plugins {} are path dependencies, which the verification server can't fetch. Publish them to a registry or a git repository.",
+                sanitize(plugins)
+            ),
+            VerifierError::ClassNotConfirmed { class_hash } => format!(
+                "Refusing to mark class {} verified without confirmation, use --yes to proceed",
+                class_hash
+            ),
+            VerifierError::ClassAborted => "Aborted, nothing was submitted".to_owned(),
+            VerifierError::UploadNameCollision { first, second } => format!(
+                "The resolved files {} and {} would be uploaded under the same name",
+                sanitize(first),
+                sanitize(second)
+            ),
+            VerifierError::ChainMismatchNotConfirmed { chain_id, network } => format!(
+                "Refusing to check the class against chain {} and submit it to {} without confirmation, use --yes to proceed",
+                sanitize(chain_id),
+                network
+            ),
+            VerifierError::UnknownJobStatus(status) => format!(
+                "The verification API keeps reporting the unknown job status {}, this version of the verifier can't follow the job",
+                status
+            ),
+            VerifierError::JobInProgress { job_id, status } => format!(
+                "Verification job {} is still in progress ({})",
+                sanitize(job_id),
+                status
+            ),
+            VerifierError::IdenticalPayloadFailed {
+                job_id,
+                status,
+                description,
+            } => format!(
+                "Job {} already ended in {} with this exact payload: {}. Nothing changed since, submitting it again would fail the same way. Fix the failure, or pass --resubmit-identical to submit it anyway",
+                sanitize(job_id),
+                status,
+                sanitize(description.as_deref().unwrap_or("no description"))
+            ),
+            VerifierError::UnsafeArchiveEntry(path) => format!(
+                "The source archive has an entry outside of its root: {}",
+                sanitize(path)
+            ),
+            VerifierError::InvalidEndpoint {
+                network,
+                endpoint,
+                flag,
+                variable,
+                problem,
+            } => format!(
+                "The {} endpoint of the {} network is {}. Configure it with {} or --network <BASE_URL>, or with {} in the environment, the .env file of the project or the user config.env",
+                endpoint, network, sanitize(problem), flag, variable
+            ),
+            VerifierError::ApiKeyRejected {
+                status,
+                provided: false,
+            } => format!(
+                "Missing API key, the verification API answered {}. Pass it with --api-key or VOYAGER_API_KEY",
+                status
+            ),
+            VerifierError::ApiKeyRejected {
+                status,
+                provided: true,
+            } => format!(
+                "Invalid API key, the verification API answered {}. Check the value of --api-key or VOYAGER_API_KEY",
+                status
+            ),
+            VerifierError::RetriesExhausted {
+                endpoint,
+                attempts,
+                cause,
+            } => format!(
+                "Request to the {} endpoint failed after {} attempts: {}",
+                endpoint,
+                attempts,
+                sanitize(cause)
+            ),
+            VerifierError::LocalStackUnreachable(url) => format!(
+                "Nothing answers at {}, is the local verification stack running? See RUNNING_LOCALLY.md to start it",
+                url
+            ),
+            VerifierError::UnsupportedToolVersion {
+                tool,
+                version,
+                supported,
+            } => format!(
+                "{} {} isn't supported, the supported versions are {}",
+                tool,
+                sanitize(version),
+                supported.join(", ")
+            ),
+            VerifierError::Cancelled { job_id: None } => {
+                "Cancelled before the job was dispatched".to_owned()
+            }
+            VerifierError::Cancelled {
+                job_id: Some(job_id),
+            } => format!(
+                "Cancelled while waiting for verification job {}. It may still complete, check it with `status {}`.",
+                job_id, job_id
+            ),
+            VerifierError::ClassNotFound {
+                class_hash,
+                network,
+            } => format!(
+                "Class {} isn't declared on {}, check the class hash and --network",
+                class_hash, network
+            ),
+            VerifierError::Maintenance {
+                endpoint,
+                message,
+                retry_after_secs,
+            } => format!(
+                "The {} endpoint is down for maintenance: {}{}. Retry later, or pass --on-maintenance wait",
+                endpoint,
+                sanitize(message),
+                retry_after_secs
+                    .map(|secs| format!(
+                        ", announced to last {}",
+                        format_duration(Duration::from_secs(secs))
+                    ))
+                    .unwrap_or_default()
+            ),
+            VerifierError::ContractFileOutsideSourceRoot {
+                contract_file,
+                source_root,
+            } => format!(
+                "The contract file {} is outside of the source root {} of the package, set its path in [tool.voyager] relative to it",
+                sanitize(contract_file),
+                sanitize(source_root)
+            ),
+            VerifierError::ContractFileReexport {
+                contract_file,
+                contract,
+                defined_in,
+                suggestion,
+            } => format!(
+                "The contract file {} only re-exports the contract module {}, defined in {}. Set path = \"{}\" in [tool.voyager]",
+                sanitize(contract_file),
+                contract,
+                sanitize(defined_in),
+                sanitize(suggestion)
+            ),
+            VerifierError::ContractModuleNotFound {
+                contract_file,
+                contract,
+            } => format!(
+                "No #[starknet::contract] module for {} in the contract file {} or the modules it declares, check its path in [tool.voyager]",
+                contract,
+                sanitize(contract_file)
+            ),
+            VerifierError::UploadTooLarge {
+                size,
+                limit,
+                largest,
+            } => format!(
+                "The upload is {}, over the limit of {}. The largest files are:\n{}",
+                format_size(*size),
+                format_size(*limit),
+                largest
+            ),
+            VerifierError::UploadRejectedTooLarge => {
+                "The backend rejected the upload as too large, its limit is lower than --max-upload-size"
+                    .to_owned()
+            }
+            VerifierError::CorrectionRejected { status, message } => format!(
+                "The backend rejected the correction request with status {}: {}",
+                status,
+                sanitize(message)
+            ),
+            VerifierError::UnknownDialect {
+                name,
+                origin,
+                available,
+            } => format!(
+                "Unknown form dialect \"{}\" from {}, available: {}",
+                sanitize(name),
+                origin,
+                available.join(", ")
+            ),
+            VerifierError::RpcUnreachable { url, cause } => format!(
+                "Unable to look the class up through the Starknet RPC at {}: {}",
+                sanitize(url),
+                sanitize(cause)
+            ),
+            VerifierError::LocalHashMismatch { given, local } if local.is_empty() => format!(
+                "The sources compiled to no contract class, so none can be {}. Nothing was uploaded",
+                given
+            ),
+            VerifierError::LocalHashMismatch { given, local } => format!(
+                "The class being verified is {} but the sources compile to {}. Was it declared from other sources? Nothing was uploaded, use --skip-hash-check to submit anyway",
+                given,
+                local.iter().map(|hash| sanitize(hash)).collect::<Vec<_>>().join(", ")
+            ),
+            VerifierError::InvalidCorrection(reason) => {
+                format!("Invalid correction request: {}", reason)
+            }
+        }
+    }
+}
+
+impl Display for VerifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+/**
+ * Failure of a request of the API.
+ */
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A failure the backend or the inputs caused, with its code.
+    #[error(transparent)]
+    Verifier(VerifierError),
+    /// A request that never got an answer, even after retries.
+    #[error(transparent)]
+    Http(reqwest::Error),
+    /// Anything else, e.g. an answer that couldn't be parsed.
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /**
+     * The coded error of this failure, if any, for callers matching on what
+     * failed.
+     */
+    pub fn verifier_error(&self) -> Option<&VerifierError> {
+        match self {
+            Error::Verifier(error) => Some(error),
+            Error::Http(_) => None,
+            Error::Other(error) => verifier_error(error),
+        }
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        self.verifier_error().map(VerifierError::code)
+    }
+}
+
+impl From<VerifierError> for Error {
+    fn from(error: VerifierError) -> Self {
+        Error::Verifier(error)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Http(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Other(error.into())
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(error: url::ParseError) -> Self {
+        Error::Other(error.into())
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Unwraps the errors that have a variant of their own, so that matching on
+    /// the variant doesn't depend on how the error was propagated.
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<VerifierError>() {
+            Ok(error) => return Error::Verifier(error),
+            Err(error) => error,
+        };
+        match error.downcast::<reqwest::Error>() {
+            Ok(error) => Error::Http(error),
+            Err(error) => Error::Other(error),
+        }
+    }
+}
+
+/**
+ * The errors of the chain of `error`, with the ones wrapped in an `Error` in
+ * place of their wrapper. Its variants are transparent, so walking the chain
+ * alone would skip them.
+ */
+pub fn causes(error: &anyhow::Error) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+    error
+        .chain()
+        .map(|cause| -> &(dyn std::error::Error + 'static) {
+            match cause.downcast_ref::<Error>() {
+                Some(Error::Verifier(error)) => error,
+                Some(Error::Http(error)) => error,
+                Some(Error::Other(error)) => &**error,
+                None => cause,
+            }
+        })
+}
+
+/**
+ * The first coded error in the chain of `error`, for callers matching on what
+ * failed. Requests that never got an answer fail with a `reqwest::Error`
+ * instead.
+ */
+pub fn verifier_error(error: &anyhow::Error) -> Option<&VerifierError> {
+    causes(error).find_map(|cause| cause.downcast_ref::<VerifierError>())
+}
+
+/**
+ * Code of the first coded error in the chain of `error`, if any.
+ */
+pub fn error_code(error: &anyhow::Error) -> Option<&'static str> {
+    verifier_error(error).map(VerifierError::code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Every code ever assigned here. Codes may be deprecated but never removed from the registry.
+    /// VER-025, VER-031, VER-035, VER-039, VER-040 and VER-061 are assigned by the CLI.
+    const ASSIGNED_CODES: &[&str] = &[
+        "VER-001", "VER-002", "VER-003", "VER-004", "VER-005", "VER-006", "VER-007", "VER-008",
+        "VER-009", "VER-010", "VER-011", "VER-012", "VER-013", "VER-014", "VER-015", "VER-016",
+        "VER-017", "VER-018", "VER-019", "VER-020", "VER-021", "VER-022", "VER-023", "VER-024",
+        "VER-026", "VER-027", "VER-028", "VER-029", "VER-030", "VER-032", "VER-033", "VER-034",
+        "VER-036", "VER-037", "VER-038", "VER-041", "VER-042", "VER-043", "VER-044", "VER-045",
+        "VER-046", "VER-047", "VER-048", "VER-049", "VER-050", "VER-051", "VER-052", "VER-053",
+        "VER-054", "VER-055", "VER-056", "VER-057", "VER-058", "VER-059", "VER-060", "VER-062",
+    ];
+
+    fn one_of_each_variant() -> Vec<VerifierError> {
+        vec![
+            VerifierError::UnknownNetwork("goerli".to_owned()),
+            VerifierError::ClassLookupFailed {
+                status: 500,
+                message: "".to_owned(),
+            },
+            VerifierError::AlreadyVerified {
+                class_hash: "0x1".to_owned(),
+                name: "MyToken".to_owned(),
+            },
+            VerifierError::MetadataChangeNotConfirmed,
+            VerifierError::MetadataChangeAborted,
+            VerifierError::UnsupportedSchemaVersion {
+                description: "".to_owned(),
+                supported: vec![1],
+            },
+            VerifierError::FilesRejected {
+                error: "".to_owned(),
+                files: "".to_owned(),
+            },
+            VerifierError::DispatchFailed {
+                status: 400,
+                message: "".to_owned(),
+            },
+            VerifierError::JobNotFound,
+            VerifierError::UnexpectedJobStatus {
+                status: 500,
+                message: "".to_owned(),
+            },
+            VerifierError::VerificationFailed("".to_owned()),
+            VerifierError::CompilationFailed("".to_owned()),
+            VerifierError::Timeout {
+                job_id: "".to_owned(),
+                waited_secs: 0,
+            },
+            VerifierError::NoContracts,
+            VerifierError::SeveralContracts,
+            VerifierError::StateLocked {
+                path: "".to_owned(),
+                timeout_secs: 30,
+            },
+            VerifierError::UnsupportedFileName {
+                file_name: "".to_owned(),
+                character: '"',
+            },
+            VerifierError::CollectionRootTooNarrow {
+                root: "".to_owned(),
+                outside: "".to_owned(),
+            },
+            VerifierError::UnknownGitRevision("v1".to_owned()),
+            VerifierError::DocumentNotMarkdown("notes.txt".to_owned()),
+            VerifierError::DocumentTooLarge {
+                path: "README.md".to_owned(),
+                size: 1,
+                limit: 0,
+            },
+            VerifierError::CompilerTooOld {
+                selected: "2.8.4".to_owned(),
+                required: "2.9.0".to_owned(),
+                findings: "".to_owned(),
+            },
+            VerifierError::InvalidAlias {
+                alias: "".to_owned(),
+                reason: "".to_owned(),
+            },
+            VerifierError::PathOutsideUpload {
+                field: "project_dir_path".to_owned(),
+                path: "..".to_owned(),
+                nearest: "".to_owned(),
+            },
+            VerifierError::HashMismatch {
+                expected: "0x1".to_owned(),
+                actual: "0x2".to_owned(),
+            },
+            VerifierError::UnsupportedFormStyle {
+                style: "parts".to_owned(),
+                supported: vec!["legacy".to_owned()],
+            },
+            VerifierError::JobGoneClassVerified {
+                job_id: "job".to_owned(),
+                class_hash: "0x1".to_owned(),
+            },
+            VerifierError::NotReproducible {
+                flag: "--locked".to_owned(),
+                what: "a prompt".to_owned(),
+                hint: "pass --network".to_owned(),
+            },
+            VerifierError::EnvironmentPaths {
+                findings: "".to_owned(),
+            },
+            VerifierError::LocalPlugin {
+                plugins: "local_macros".to_owned(),
+            },
+            VerifierError::ClassNotConfirmed {
+                class_hash: "0x1".to_owned(),
+            },
+            VerifierError::ClassAborted,
+            VerifierError::UploadNameCollision {
+                first: "utils/src/lib.cairo".to_owned(),
+                second: "Utils/src/lib.cairo".to_owned(),
+            },
+            VerifierError::ChainMismatchNotConfirmed {
+                chain_id: "SN_SEPOLIA".to_owned(),
+                network: "mainnet".to_owned(),
+            },
+            VerifierError::UnknownJobStatus(7),
+            VerifierError::JobInProgress {
+                job_id: "a1b2".to_owned(),
+                status: "Compiled".to_owned(),
+            },
+            VerifierError::IdenticalPayloadFailed {
+                job_id: "a1b2".to_owned(),
+                status: "CompileFailed".to_owned(),
+                description: None,
+            },
+            VerifierError::UnsafeArchiveEntry("../Scarb.toml".to_owned()),
+            VerifierError::InvalidEndpoint {
+                network: "custom".to_owned(),
+                endpoint: "public API".to_owned(),
+                flag: "--public-api-url".to_owned(),
+                variable: "CUSTOM_PUBLIC_API_ENDPOINT_URL".to_owned(),
+                problem: "not set".to_owned(),
+            },
+            VerifierError::LocalStackUnreachable("http://localhost:8899".to_owned()),
+            VerifierError::ApiKeyRejected {
+                status: 401,
+                provided: false,
+            },
+            VerifierError::RetriesExhausted {
+                endpoint: "job-status".to_owned(),
+                attempts: 4,
+                cause: "the backend answered 503 Service Unavailable".to_owned(),
+            },
+            VerifierError::UnsupportedToolVersion {
+                tool: "Scarb".to_owned(),
+                version: "2.9.1".to_owned(),
+                supported: vec!["2.8.4".to_owned()],
+            },
+            VerifierError::Cancelled {
+                job_id: Some("a1b2".to_owned()),
+            },
+            VerifierError::ClassNotFound {
+                class_hash: "0x1".to_owned(),
+                network: "sepolia".to_owned(),
+            },
+            VerifierError::Maintenance {
+                endpoint: "verify".to_owned(),
+                message: "Database upgrade".to_owned(),
+                retry_after_secs: Some(1800),
+            },
+            VerifierError::ContractFileOutsideSourceRoot {
+                contract_file: "token/src/../contracts/erc20.cairo".to_owned(),
+                source_root: "token/src".to_owned(),
+            },
+            VerifierError::ContractFileReexport {
+                contract_file: "token/src/lib.cairo".to_owned(),
+                contract: "MyToken".to_owned(),
+                defined_in: "token/src/token/erc20.cairo".to_owned(),
+                suggestion: "token/erc20.cairo".to_owned(),
+            },
+            VerifierError::ContractModuleNotFound {
+                contract_file: "token/src/utils.cairo".to_owned(),
+                contract: "MyToken".to_owned(),
+            },
+            VerifierError::UploadTooLarge {
+                size: 2,
+                limit: 1,
+                largest: "token/src/lib.cairo".to_owned(),
+            },
+            VerifierError::UploadRejectedTooLarge,
+            VerifierError::CorrectionRejected {
+                status: 400,
+                message: "".to_owned(),
+            },
+            VerifierError::UnknownDialect {
+                name: "fork".to_owned(),
+                origin: "--dialect".to_owned(),
+                available: vec!["voyager".to_owned()],
+            },
+            VerifierError::RpcUnreachable {
+                url: "http://localhost:5050/rpc".to_owned(),
+                cause: "connection refused".to_owned(),
+            },
+            VerifierError::LocalHashMismatch {
+                given: "0x1".to_owned(),
+                local: vec![],
+            },
+            VerifierError::InvalidCorrection("The contact of the correction is empty".to_owned()),
+        ]
+    }
+
+    #[test]
+    fn test_registry_codes_are_unique_and_never_removed() {
+        let registered: Vec<&str> = ERROR_CODES.iter().map(|entry| entry.code).collect();
+        let unique: HashSet<&str> = registered.iter().copied().collect();
+        assert_eq!(unique.len(), registered.len(), "Duplicate error code");
+
+        for code in ASSIGNED_CODES {
+            assert!(unique.contains(code), "Error code {} was removed", code);
+        }
+    }
+
+    #[test]
+    fn test_every_variant_has_exactly_one_active_code() {
+        let variants = one_of_each_variant();
+        let codes: Vec<&str> = variants.iter().map(VerifierError::code).collect();
+        let unique: HashSet<&str> = codes.iter().copied().collect();
+        assert_eq!(unique.len(), codes.len(), "Two variants share a code");
+
+        for code in codes.iter() {
+            let entry = find_error_code(code).expect("Variant code missing from the registry");
+            assert!(!entry.deprecated, "Variant uses deprecated code {}", code);
+        }
+        for entry in ERROR_CODES.iter().filter(|entry| !entry.deprecated) {
+            assert!(
+                unique.contains(entry.code),
+                "No variant emits active code {}",
+                entry.code
+            );
+        }
+    }
+
+    #[test]
+    fn test_messages_start_with_their_code() {
+        for error in one_of_each_variant() {
+            assert!(error
+                .to_string()
+                .starts_with(&format!("{}: ", error.code())));
+        }
+
+        let error = anyhow::Error::new(VerifierError::JobNotFound).context("Polling failed");
+        assert_eq!(error_code(&error), Some("VER-009"));
+        assert_eq!(error_code(&anyhow::anyhow!("uncoded")), None);
+    }
+
+    #[test]
+    fn test_timeout_says_which_job_and_how_long() {
+        let message = VerifierError::Timeout {
+            job_id: "a1b2".to_owned(),
+            waited_secs: 367,
+        }
+        .to_string();
+        assert!(message.contains("a1b2"), "{}", message);
+        assert!(message.contains("6m 07s"), "{}", message);
+    }
+
+    #[test]
+    fn test_codes_survive_propagation() {
+        let wrapped: Error = anyhow::Error::from(VerifierError::JobNotFound)
+            .context("Error while polling verification status")
+            .into();
+        assert_eq!(wrapped.code(), Some("VER-009"));
+
+        let unwrapped: Error = anyhow::Error::from(VerifierError::JobNotFound).into();
+        assert!(matches!(
+            unwrapped,
+            Error::Verifier(VerifierError::JobNotFound)
+        ));
+
+        let propagated = anyhow::Error::from(unwrapped).context("Failed to verify");
+        assert_eq!(error_code(&propagated), Some("VER-009"));
+    }
+}
@@ -0,0 +1,1576 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, Context, Result};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::multipart;
+use sha2::{Digest, Sha256};
+
+use crate::api::ProjectMetadataInfo;
+use crate::dialect::{Dialect, FormField};
+use crate::display::sanitize;
+use crate::errors::VerifierError;
+use crate::humanize::format_size;
+use crate::transfer::{TransferLog, VERIFY_ENDPOINT};
+
+/**
+ * Version of the multipart form layout sent to the verification backend.
+ * Bump this whenever a field is added, removed, or changes meaning so the
+ * backend knows which layout it received.
+ */
+pub const FORM_SCHEMA_VERSION: u32 = 1;
+
+/**
+ * Layout adding documentation files under `docs__<name>`. It is only sent when
+ * documentation is attached, so that backends that don't display it reject the
+ * submission instead of silently dropping the files.
+ */
+pub const DOCS_FORM_SCHEMA_VERSION: u32 = 2;
+
+/**
+ * Layout adding alternative names of the class, one `aliases[]` field each.
+ * Like documentation, it is only sent when aliases are given.
+ */
+pub const ALIASES_FORM_SCHEMA_VERSION: u32 = 3;
+
+/**
+ * Layout adding the settings of a `[[target.starknet-contract]]` that doesn't
+ * build like the default one, so that the backend builds the same artifacts.
+ * Only sent for such targets.
+ */
+pub const TARGET_FORM_SCHEMA_VERSION: u32 = 4;
+
+/**
+ * Layout adding the compiler plugins the package builds with, as a JSON array
+ * of name, version, source and checksum under `plugins`. Only sent when the
+ * package uses plugins.
+ */
+pub const PLUGINS_FORM_SCHEMA_VERSION: u32 = 5;
+
+/**
+ * Layout adding the vendored dependencies left out of the upload because the
+ * backend already has their sources, as a JSON array of name, version,
+ * directory and fingerprint under `cached_dependencies`. Only sent when some
+ * were left out, see `dependencies::DependencyPlan`.
+ */
+pub const CACHED_DEPENDENCIES_FORM_SCHEMA_VERSION: u32 = 6;
+
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+pub const FILE_FIELD_PREFIX: &str = "files__";
+pub const DOC_FIELD_PREFIX: &str = "docs__";
+pub const ALIAS_FIELD: &str = "aliases[]";
+pub const EXTERNAL_CONTRACT_FIELD: &str = "build_external_contracts[]";
+pub const ARCHIVE_FIELD: &str = "files_archive";
+pub const PLUGINS_FIELD: &str = "plugins";
+pub const CACHED_DEPENDENCIES_FIELD: &str = "cached_dependencies";
+
+/// Upload limit of the verification backend, files and documentation together.
+pub const DEFAULT_MAX_UPLOAD_SIZE: u64 = 10 * 1024 * 1024;
+
+/// How many of the largest files an `UploadTooLarge` error lists.
+const LARGEST_FILES_LISTED: usize = 5;
+
+/**
+ * Optional parts of the form, each requiring a newer layout.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormFeatures {
+    pub docs: bool,
+    pub aliases: bool,
+    pub target: bool,
+    pub plugins: bool,
+    pub cached_dependencies: bool,
+}
+
+impl FormFeatures {
+    /// Layouts are cumulative, the newest one needed is sent.
+    pub fn schema_version(&self) -> u32 {
+        if self.cached_dependencies {
+            CACHED_DEPENDENCIES_FORM_SCHEMA_VERSION
+        } else if self.plugins {
+            PLUGINS_FORM_SCHEMA_VERSION
+        } else if self.target {
+            TARGET_FORM_SCHEMA_VERSION
+        } else if self.aliases {
+            ALIASES_FORM_SCHEMA_VERSION
+        } else if self.docs {
+            DOCS_FORM_SCHEMA_VERSION
+        } else {
+            FORM_SCHEMA_VERSION
+        }
+    }
+}
+
+pub fn supports_aliases(supported_versions: &[u32]) -> bool {
+    supported_versions
+        .iter()
+        .any(|version| *version >= ALIASES_FORM_SCHEMA_VERSION)
+}
+
+pub fn supports_plugins(supported_versions: &[u32]) -> bool {
+    supported_versions
+        .iter()
+        .any(|version| *version >= PLUGINS_FORM_SCHEMA_VERSION)
+}
+
+/**
+ * The metadata fields of the verification form, in the order they are sent:
+ * the fields of the base layout in a fixed sequence, then those of the newer
+ * layouts in the order they were introduced. Files are appended after these,
+ * see `FormSubmission::parts`. Fields are named as `dialect` names them.
+ */
+pub fn form_metadata_fields(
+    license: &str,
+    name: &str,
+    aliases: &[String],
+    project_metadata: &ProjectMetadataInfo,
+    with_docs: bool,
+    dialect: Dialect,
+) -> Vec<(&'static str, String)> {
+    let target = &project_metadata.target;
+    let features = FormFeatures {
+        docs: with_docs,
+        aliases: !aliases.is_empty(),
+        target: !target.is_default(),
+        plugins: !project_metadata.plugins.is_empty(),
+        cached_dependencies: !project_metadata.cached_dependencies.is_empty(),
+    };
+    let wire = |field: FormField| dialect.field_name(field);
+    let mut fields = vec![
+        (
+            wire(FormField::SchemaVersion),
+            features.schema_version().to_string(),
+        ),
+        (
+            wire(FormField::CompilerVersion),
+            project_metadata.cairo_version.to_string(),
+        ),
+        (
+            wire(FormField::ScarbVersion),
+            project_metadata.scarb_version.to_string(),
+        ),
+        (wire(FormField::License), license.to_string()),
+        (wire(FormField::Name), name.to_string()),
+        (
+            wire(FormField::ContractFile),
+            project_metadata.contract_file.clone(),
+        ),
+        (
+            wire(FormField::ProjectDirPath),
+            project_metadata.project_dir_path.clone(),
+        ),
+    ];
+    fields.extend(
+        aliases
+            .iter()
+            .map(|alias| (wire(FormField::Alias), alias.clone())),
+    );
+    if features.target {
+        if let Some(name) = &target.name {
+            fields.push((wire(FormField::TargetName), name.clone()));
+        }
+        fields.push((wire(FormField::TargetSierra), target.sierra.to_string()));
+        fields.push((wire(FormField::TargetCasm), target.casm.to_string()));
+        fields.extend(
+            target
+                .build_external_contracts
+                .iter()
+                .map(|contract| (wire(FormField::ExternalContract), contract.clone())),
+        );
+    }
+    if features.plugins {
+        fields.push((
+            wire(FormField::Plugins),
+            serde_json::to_string(&project_metadata.plugins).unwrap_or_default(),
+        ));
+    }
+    if features.cached_dependencies {
+        fields.push((
+            wire(FormField::CachedDependencies),
+            serde_json::to_string(&project_metadata.cached_dependencies).unwrap_or_default(),
+        ));
+    }
+    fields
+}
+
+/**
+ * Characters left as-is in file field names. The backend splits the decoded
+ * name on `/`, so path separators are never encoded.
+ */
+const FILE_NAME_SAFE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/**
+ * How file names are written into form field names.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldNameEncoding {
+    /// Percent-encode everything but unreserved characters and `/`.
+    #[default]
+    Percent,
+    /// Send names verbatim, for older backends that don't decode field names.
+    /// Names with spaces, `%` or non-ASCII characters may be misparsed.
+    Legacy,
+}
+
+/**
+ * How the files are laid out in the form. Self-hosted instances pinned to
+ * older backend releases only understand the legacy style.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum FormStyle {
+    /// A text field per file, `files__<path>` with the path verbatim.
+    Legacy,
+    /// A file part per file, `files__<path>` with the path percent-encoded,
+    /// sent byte for byte with its file name and content type.
+    #[default]
+    Parts,
+    /// A single tar archive of the files under `files/` and the docs under
+    /// `docs/`, in the `files_archive` part.
+    Archive,
+}
+
+impl FormStyle {
+    pub fn name(self) -> &'static str {
+        match self {
+            FormStyle::Legacy => "legacy",
+            FormStyle::Parts => "parts",
+            FormStyle::Archive => "archive",
+        }
+    }
+
+    pub fn field_name_encoding(self) -> FieldNameEncoding {
+        match self {
+            FormStyle::Legacy => FieldNameEncoding::Legacy,
+            FormStyle::Parts | FormStyle::Archive => FieldNameEncoding::Percent,
+        }
+    }
+
+    /**
+     * The preferred style among those a backend lists as supported, ignoring
+     * the styles this client doesn't know.
+     */
+    pub fn negotiate(supported: &[String]) -> Option<FormStyle> {
+        [FormStyle::Parts, FormStyle::Archive, FormStyle::Legacy]
+            .into_iter()
+            .find(|style| {
+                supported
+                    .iter()
+                    .any(|name| name.trim().eq_ignore_ascii_case(style.name()))
+            })
+    }
+}
+
+impl std::fmt::Display for FormStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn field_name(prefix: &str, file_name: &str, encoding: FieldNameEncoding) -> String {
+    match encoding {
+        FieldNameEncoding::Percent => format!(
+            "{}{}",
+            prefix,
+            utf8_percent_encode(file_name, FILE_NAME_SAFE)
+        ),
+        FieldNameEncoding::Legacy => format!("{}{}", prefix, file_name),
+    }
+}
+
+pub fn file_field_name(file_name: &str, encoding: FieldNameEncoding) -> String {
+    field_name(FILE_FIELD_PREFIX, file_name, encoding)
+}
+
+pub fn doc_field_name(doc_name: &str, encoding: FieldNameEncoding) -> String {
+    field_name(DOC_FIELD_PREFIX, doc_name, encoding)
+}
+
+/**
+ * File names that can't be sent whatever the encoding: control characters and
+ * `"` break the part headers of older backends, and `\` would be ambiguous with
+ * the `/` path separator. These are rejected when collecting the project files.
+ */
+pub fn validate_file_name(file_name: &str) -> Result<()> {
+    match file_name
+        .chars()
+        .find(|c| c.is_control() || *c == '"' || *c == '\\')
+    {
+        Some(character) => Err(VerifierError::UnsupportedFileName {
+            file_name: file_name.to_owned(),
+            character,
+        }
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/**
+ * The headers of `part` can be written as they are: reqwest and
+ * `deterministic_body` copy the names into the part headers verbatim, where a
+ * NUL, CR or LF either panics or frames a broken body, and a `"` ends the name
+ * early. Names are also ASCII unless the legacy encoding sends them raw, file
+ * names always being percent-encoded, see `part_file_name`.
+ */
+pub fn validate_part_headers(part: &FormPart, encoding: FieldNameEncoding) -> Result<()> {
+    let names = std::iter::once((part.name.as_str(), encoding == FieldNameEncoding::Legacy)).chain(
+        part.file
+            .as_ref()
+            .map(|(file_name, _)| (file_name.as_str(), false)),
+    );
+    for (name, raw) in names {
+        let illegal = name
+            .chars()
+            .find(|c| c.is_control() || *c == '"' || (!raw && !c.is_ascii()));
+        if let Some(character) = illegal {
+            return Err(VerifierError::UnsupportedFileName {
+                file_name: name.to_owned(),
+                character,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/**
+ * The name the file at `path` is uploaded under: its path relative to `root`,
+ * the directory the resolver lays the packages out in, with `/` separators
+ * whatever the platform, see `validate_file_name`.
+ */
+pub fn upload_file_name(root: &Path, path: &Path) -> Result<String> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|_| anyhow!("{} is outside of {}", path.display(), root.display()))?;
+    let components = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name
+                .to_str()
+                .ok_or_else(|| anyhow!("{} is not valid UTF-8", path.display())),
+            _ => Err(anyhow!(
+                "{} is not a plain relative path",
+                relative.display()
+            )),
+        })
+        .collect::<Result<Vec<&str>>>()?;
+    let name = components.join("/");
+    validate_file_name(&name)?;
+    Ok(name)
+}
+
+/**
+ * The `files__` fields are flat, each package of the upload is told apart by
+ * the top-level directory the resolver lays it out in. Two names that only
+ * differ by case would still land on the same file on case-insensitive
+ * filesystems, so they are rejected too.
+ */
+pub fn validate_unique_file_names<S: AsRef<str>>(file_names: &[S]) -> Result<()> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for file_name in file_names.iter().map(AsRef::as_ref) {
+        if let Some(first) = seen.insert(file_name.to_lowercase(), file_name) {
+            return Err(VerifierError::UploadNameCollision {
+                first: first.to_owned(),
+                second: file_name.to_owned(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Total size of the files to upload, given by name and size, checked against
+ * `limit` before the form is built rather than answered by an opaque 413.
+ * Over it, the error lists the largest files, which are the ones worth
+ * excluding.
+ */
+pub fn check_upload_size<'a>(
+    files: impl IntoIterator<Item = (&'a str, u64)>,
+    limit: u64,
+) -> Result<u64> {
+    let mut files: Vec<(&str, u64)> = files.into_iter().collect();
+    let size = files.iter().map(|(_, size)| size).sum();
+    if size <= limit {
+        return Ok(size);
+    }
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let largest = files
+        .iter()
+        .take(LARGEST_FILES_LISTED)
+        .map(|(name, size)| format!("  {} ({})", sanitize(name), format_size(*size)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(VerifierError::UploadTooLarge {
+        size,
+        limit,
+        largest,
+    }
+    .into())
+}
+
+/**
+ * Content type of the file part of `path`, from its extension.
+ */
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("cairo") => "text/x-cairo",
+        Some("toml") => "application/toml",
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/**
+ * File name of the file part of `path`, its last component encoded like the
+ * field names so that the part headers stay ASCII.
+ */
+fn part_file_name(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    utf8_percent_encode(name, FILE_NAME_SAFE).to_string()
+}
+
+/**
+ * Tar archive of the files under `files/` and the docs under `docs/`. Entries
+ * have fixed metadata, so that the same sources always give the same archive.
+ */
+fn archive(files: &[(String, Vec<u8>)], docs: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(vec![]);
+    let entries = files
+        .iter()
+        .map(|(name, content)| (format!("files/{}", name), content))
+        .chain(
+            docs.iter()
+                .map(|(name, content)| (format!("docs/{}", name), content)),
+        );
+    for (path, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        builder.append_data(&mut header, path, content.as_slice())?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+/**
+ * A part of the verification form, see `FormSubmission::parts`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormPart {
+    pub name: String,
+    /// File name and content type, sent for files but not for fields.
+    pub file: Option<(String, &'static str)>,
+    pub content: Vec<u8>,
+}
+
+/**
+ * The verification form as sent: built by reqwest with a random boundary, or
+ * with `--deterministic-body` rendered by us, see `deterministic_body`.
+ */
+pub enum FormBody {
+    /// The parts, built into a new form each time the body is sent.
+    Multipart(Vec<FormPart>),
+    Deterministic {
+        boundary: String,
+        body: Vec<u8>,
+    },
+}
+
+/**
+ * Everything submitted with a verification job, whatever the form style.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormSubmission {
+    pub fields: Vec<(&'static str, String)>,
+    /// Paths and contents, sent as they are, see `FormSubmission::parts`.
+    pub files: Vec<(String, Vec<u8>)>,
+    pub docs: Vec<(String, Vec<u8>)>,
+}
+
+impl FormSubmission {
+    /**
+     * The parts of the form, in the order they are sent: the metadata fields
+     * in their given order, see `form_metadata_fields`, then the files sorted
+     * by field name, then the documentation files sorted by field name. The
+     * archive style sends its entries in the same order. Backends hash the raw
+     * body to deduplicate submissions, changing this order is a format change.
+     * The legacy style sends text fields, which older backends decode, so its
+     * files must be valid UTF-8.
+     */
+    pub fn parts(mut self, style: FormStyle) -> Result<Vec<FormPart>> {
+        // Before anything is built, names may come from a broken generator or a
+        // replayed payload rather than from the collection of the project
+        for (name, _) in self.files.iter().chain(&self.docs) {
+            validate_file_name(name)?;
+        }
+        let encoding = style.field_name_encoding();
+        let mut parts: Vec<FormPart> = self
+            .fields
+            .into_iter()
+            .map(|(field, value)| FormPart {
+                name: field.to_owned(),
+                file: None,
+                content: value.into_bytes(),
+            })
+            .collect();
+        if style == FormStyle::Archive {
+            self.files.sort();
+            self.docs.sort();
+            parts.push(FormPart {
+                name: ARCHIVE_FIELD.to_owned(),
+                file: Some(("sources.tar".to_owned(), "application/x-tar")),
+                content: archive(&self.files, &self.docs)?,
+            });
+        } else {
+            let sorted = |files: Vec<(String, Vec<u8>)>,
+                          field_name: fn(&str, FieldNameEncoding) -> String|
+             -> Result<Vec<FormPart>> {
+                let mut files: Vec<FormPart> = files
+                    .into_iter()
+                    .map(|(name, content)| {
+                        let file = match style {
+                            FormStyle::Legacy => {
+                                std::str::from_utf8(&content)
+                                    .with_context(|| format!("{} is not valid UTF-8", name))?;
+                                None
+                            }
+                            _ => Some((part_file_name(&name), content_type(&name))),
+                        };
+                        Ok(FormPart {
+                            name: field_name(&name, encoding),
+                            file,
+                            content,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                files.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(files)
+            };
+            parts.extend(sorted(self.files, file_field_name)?);
+            parts.extend(sorted(self.docs, doc_field_name)?);
+        }
+        for part in &parts {
+            validate_part_headers(part, encoding)?;
+        }
+        Ok(parts)
+    }
+
+    /**
+     * Build the multipart form sent to the backend. Field names are encoded by
+     * us, the form itself must not encode them a second time.
+     */
+    pub fn into_form(self, style: FormStyle, log: &'static TransferLog) -> Result<multipart::Form> {
+        multipart_form(self.parts(style)?, log)
+    }
+
+    /**
+     * The form to send, deterministic or not. The parts of a deterministic
+     * body are counted in `log` as soon as it is built, it is sent whole.
+     */
+    pub fn into_body(
+        self,
+        style: FormStyle,
+        deterministic: bool,
+        log: &'static TransferLog,
+    ) -> Result<FormBody> {
+        if !deterministic {
+            return Ok(FormBody::Multipart(self.parts(style)?));
+        }
+        let parts = self.parts(style)?;
+        let sent = parts.iter().map(|part| part.content.len() as u64).sum();
+        log.record_sent(VERIFY_ENDPOINT, sent);
+        let (boundary, body) = deterministic_body(&parts);
+        Ok(FormBody::Deterministic { boundary, body })
+    }
+}
+
+/**
+ * The multipart form of `parts`. Parts are counted in `log` as the form is
+ * built, the async client has no reader to meter them with.
+ */
+pub fn multipart_form(parts: Vec<FormPart>, log: &'static TransferLog) -> Result<multipart::Form> {
+    let mut body = multipart::Form::new().percent_encode_noop();
+    for part in parts {
+        // Already encoded, the legacy encoding is the one accepting them all
+        validate_part_headers(&part, FieldNameEncoding::Legacy)?;
+        log.record_sent(VERIFY_ENDPOINT, part.content.len() as u64);
+        let mut bytes = multipart::Part::bytes(part.content);
+        if let Some((file_name, content_type)) = part.file {
+            bytes = bytes.file_name(file_name).mime_str(content_type)?;
+        }
+        body = body.part(part.name, bytes);
+    }
+    Ok(body)
+}
+
+/**
+ * Boundary derived from the digest of `parts`, so that the same parts always
+ * give the same body. Content can't contain it short of a hash collision.
+ */
+pub fn deterministic_boundary(parts: &[FormPart]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        let (file_name, content_type) = part.file.clone().unwrap_or_default();
+        for value in [
+            part.name.as_bytes(),
+            file_name.as_bytes(),
+            content_type.as_bytes(),
+        ] {
+            hasher.update(value);
+            hasher.update([0]);
+        }
+        hasher.update(part.content.len().to_string());
+        hasher.update([0]);
+        hasher.update(&part.content);
+    }
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("voyager-{}", &digest[..32])
+}
+
+/**
+ * The multipart body of `parts` with a `deterministic_boundary`, framed the
+ * way reqwest frames its forms. Returns the boundary along with the body.
+ */
+pub fn deterministic_body(parts: &[FormPart]) -> (String, Vec<u8>) {
+    let boundary = deterministic_boundary(parts);
+    let mut body = vec![];
+    for part in parts {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                boundary, part.name
+            )
+            .as_bytes(),
+        );
+        if let Some((file_name, content_type)) = &part.file {
+            body.extend_from_slice(
+                format!(
+                    "; filename=\"{}\"\r\nContent-Type: {}",
+                    file_name, content_type
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(b"\r\n\r\n");
+        body.extend_from_slice(&part.content);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    (boundary, body)
+}
+
+/**
+ * Explain an "unsupported schema version" rejection by telling the user
+ * which side needs upgrading.
+ */
+pub fn describe_schema_version_mismatch(
+    supported_versions: &[u32],
+    features: FormFeatures,
+) -> String {
+    let sent_version = features.schema_version();
+    if supported_versions.contains(&FORM_SCHEMA_VERSION) {
+        let max_supported = supported_versions.iter().max().copied().unwrap_or_default();
+        let mut unsupported = vec![];
+        if features.docs && max_supported < DOCS_FORM_SCHEMA_VERSION {
+            unsupported.push("documentation files (rerun without --doc and with --no-readme)");
+        }
+        if features.aliases && max_supported < ALIASES_FORM_SCHEMA_VERSION {
+            unsupported.push("aliases (rerun without --alias, or with --aliases-best-effort)");
+        }
+        if features.target && max_supported < TARGET_FORM_SCHEMA_VERSION {
+            unsupported.push(
+                "the settings of [[target.starknet-contract]] (target name, sierra, casm or build-external-contracts)",
+            );
+        }
+        if features.plugins && max_supported < PLUGINS_FORM_SCHEMA_VERSION {
+            unsupported.push("compiler plugin information");
+        }
+        if features.cached_dependencies && max_supported < CACHED_DEPENDENCIES_FORM_SCHEMA_VERSION {
+            unsupported.push("cached dependencies (rerun with --vendor-all-dependencies)");
+        }
+        if !unsupported.is_empty() {
+            return format!(
+                "The verification server doesn't accept {}.",
+                unsupported.join(" nor ")
+            );
+        }
+    }
+    match supported_versions.iter().max() {
+        Some(max_supported) if *max_supported < FORM_SCHEMA_VERSION => format!(
+            "The verification server only supports form schema versions up to {}, but this client sends version {}. The server is too old for this client, please use an older release of the verifier or ask the instance operator to upgrade.",
+            max_supported, sent_version
+        ),
+        Some(max_supported) => format!(
+            "The verification server expects form schema version {} but this client sends version {}. Please upgrade the verifier.",
+            max_supported, sent_version
+        ),
+        None => format!(
+            "The verification server rejected form schema version {}. Please upgrade the verifier.",
+            sent_version
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::CairoPlugin;
+    use crate::dependencies::DependencyRef;
+    use crate::mock_server::{MockResponse, MockServer};
+    use crate::transfer::global;
+    use dyn_compiler::contract_target::StarknetContractTarget;
+    use dyn_compiler::dyn_compiler::{SupportedCairoVersions, SupportedScarbVersions};
+
+    fn metadata() -> ProjectMetadataInfo {
+        ProjectMetadataInfo {
+            cairo_version: SupportedCairoVersions::V2_8_4,
+            scarb_version: SupportedScarbVersions::V2_8_4,
+            project_dir_path: "my_project".to_owned(),
+            contract_file: "my_project/src/main.cairo".to_owned(),
+            target: StarknetContractTarget::default(),
+            plugins: vec![],
+            cached_dependencies: vec![],
+        }
+    }
+
+    #[test]
+    fn test_form_fields_schema_v1_snapshot() {
+        assert_eq!(FORM_SCHEMA_VERSION, 1);
+        for dialect in Dialect::ALL {
+            let fields = form_metadata_fields(
+                "MIT License (MIT)",
+                "MyContract",
+                &[],
+                &metadata(),
+                false,
+                *dialect,
+            );
+
+            assert_eq!(
+                fields,
+                vec![
+                    ("schema_version", "1".to_owned()),
+                    (
+                        dialect.field_name(FormField::CompilerVersion),
+                        "2.8.4".to_owned()
+                    ),
+                    ("scarb_version", "2.8.4".to_owned()),
+                    ("license", "MIT License (MIT)".to_owned()),
+                    ("name", "MyContract".to_owned()),
+                    ("contract_file", "my_project/src/main.cairo".to_owned()),
+                    ("project_dir_path", "my_project".to_owned()),
+                ],
+                "{} dialect",
+                dialect
+            );
+        }
+        assert_eq!(
+            form_metadata_fields(
+                "MIT License (MIT)",
+                "MyContract",
+                &[],
+                &metadata(),
+                false,
+                Dialect::CairoVersion
+            )[1],
+            ("cairo_version", "2.8.4".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_file_field_name() {
+        assert_eq!(
+            file_field_name("my_project/src/lib.cairo", FieldNameEncoding::Percent),
+            "files__my_project/src/lib.cairo"
+        );
+        assert_eq!(
+            file_field_name("my project/100%.cairo", FieldNameEncoding::Percent),
+            "files__my%20project/100%25.cairo"
+        );
+        assert_eq!(
+            file_field_name("my project/100%.cairo", FieldNameEncoding::Legacy),
+            "files__my project/100%.cairo"
+        );
+    }
+
+    #[test]
+    fn test_validate_file_name() {
+        assert!(validate_file_name("my project/src/ünïcode 100%.cairo").is_ok());
+        assert!(validate_file_name("my_project/src/\"quoted\".cairo").is_err());
+        assert!(validate_file_name("my_project\\src\\lib.cairo").is_err());
+        assert!(validate_file_name("my_project/src/lib\n.cairo").is_err());
+    }
+
+    /**
+     * Names mixing characters part headers can't carry with ordinary ones,
+     * from a fixed seed so that a failure reproduces.
+     */
+    fn adversarial_names(count: usize) -> Vec<String> {
+        const ALPHABET: &[char] = &[
+            'a', '_', ' ', '%', 'ü', '合', '\0', '\r', '\n', '\t', '"', '\\', '\u{7f}', '\u{85}',
+            '\u{2028}', '\u{202e}',
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+        (0..count)
+            .map(|_| {
+                let len = 1 + next() % 12;
+                let name: String = (0..len)
+                    .map(|_| ALPHABET[next() % ALPHABET.len()])
+                    .collect();
+                format!("src/{}.cairo", name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_adversarial_names_fail_gracefully() {
+        for name in adversarial_names(500) {
+            let rejected = validate_file_name(&name).is_err();
+            for style in [FormStyle::Legacy, FormStyle::Parts, FormStyle::Archive] {
+                let submission = FormSubmission {
+                    files: vec![(name.clone(), b"mod token;".to_vec())],
+                    docs: vec![(name.clone(), b"# Token".to_vec())],
+                    ..Default::default()
+                };
+                match submission.parts(style) {
+                    Ok(parts) => {
+                        assert!(!rejected, "{:?} was sent with the {} style", name, style);
+                        deterministic_body(&parts);
+                        multipart_form(parts, global()).unwrap();
+                    }
+                    Err(e) => {
+                        assert!(rejected, "{:?} with the {} style: {:#}", name, style, e);
+                        assert_eq!(crate::errors::error_code(&e), Some("VER-017"));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_part_headers_are_checked_before_building_the_form() {
+        let part = |name: &str, file_name: &str| FormPart {
+            name: name.to_owned(),
+            file: Some((file_name.to_owned(), "text/plain")),
+            content: vec![],
+        };
+        let error = multipart_form(vec![part("files__src/lib\0.cairo", "lib.cairo")], global())
+            .unwrap_err();
+        assert!(error.to_string().contains("lib\\u{0}.cairo"), "{}", error);
+        assert!(multipart_form(vec![part("files__lib.cairo", "lib\r\n.cairo")], global()).is_err());
+
+        // Raw names are only sent by the legacy encoding, file names never are
+        let raw = part("files__合约.cairo", "%E5%90%88%E7%BA%A6.cairo");
+        assert!(validate_part_headers(&raw, FieldNameEncoding::Legacy).is_ok());
+        assert!(validate_part_headers(&raw, FieldNameEncoding::Percent).is_err());
+        let raw_file_name = part("files__lib.cairo", "合约.cairo");
+        assert!(validate_part_headers(&raw_file_name, FieldNameEncoding::Legacy).is_err());
+    }
+
+    #[test]
+    fn test_upload_size_lists_the_largest_files() {
+        let files = [
+            ("token/src/lib.cairo", 300),
+            ("token/assets/logo.png", 6000),
+            ("token/src/erc20.cairo", 700),
+            ("token/README.md", 3000),
+        ];
+        assert_eq!(check_upload_size(files, 10_000).unwrap(), 10_000);
+
+        let error = check_upload_size(files, 5000).unwrap_err();
+        match error.downcast_ref::<VerifierError>() {
+            Some(VerifierError::UploadTooLarge {
+                size,
+                limit,
+                largest,
+            }) => {
+                assert_eq!((*size, *limit), (10_000, 5000));
+                assert_eq!(
+                    largest.lines().collect::<Vec<_>>(),
+                    vec![
+                        "  token/assets/logo.png (5.9 KiB)",
+                        "  token/README.md (2.9 KiB)",
+                        "  token/src/erc20.cairo (700 B)",
+                        "  token/src/lib.cairo (300 B)",
+                    ]
+                );
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_upload_file_names_keep_the_module_directories() {
+        let root = Path::new("voyager-verify");
+        let paths = [
+            "token/src/interfaces/erc20.cairo",
+            "token/src/tokens/erc20.cairo",
+            "token/src/lib.cairo",
+            "token/Scarb.toml",
+        ];
+        let names: Vec<String> = paths
+            .iter()
+            .map(|path| upload_file_name(root, &root.join(path)).unwrap())
+            .collect();
+        assert_eq!(names, paths);
+        validate_unique_file_names(&names).unwrap();
+
+        // Both erc20.cairo would clobber each other under their file name only
+        let error =
+            validate_unique_file_names(&["token/erc20.cairo", "token/erc20.cairo"]).unwrap_err();
+        assert!(error.to_string().contains("token/erc20.cairo"));
+
+        assert!(upload_file_name(root, Path::new("elsewhere/lib.cairo")).is_err());
+        #[cfg(windows)]
+        assert_eq!(
+            upload_file_name(
+                Path::new("C:\\voyager-verify"),
+                Path::new("C:\\voyager-verify\\token\\src\\lib.cairo")
+            )
+            .unwrap(),
+            "token/src/lib.cairo"
+        );
+    }
+
+    #[test]
+    fn test_same_named_packages_have_distinct_file_names() {
+        assert!(validate_unique_file_names(&[
+            "token/src/lib.cairo",
+            "utils/src/lib.cairo",
+            "utils-2/src/lib.cairo",
+        ])
+        .is_ok());
+
+        let message = format!(
+            "{:#}",
+            validate_unique_file_names(&["utils/src/lib.cairo", "Utils/src/lib.cairo"])
+                .unwrap_err()
+        );
+        assert!(message.starts_with("VER-036"), "{}", message);
+        assert!(message.contains("utils/src/lib.cairo and Utils/src/lib.cairo"));
+    }
+
+    fn send_form(url: &str, form: multipart::Form) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(
+                reqwest::Client::new()
+                    .post(format!("{}/", url))
+                    .multipart(form)
+                    .send(),
+            )
+            .unwrap();
+    }
+
+    /**
+     * Send the form to a local server and return the field names it received,
+     * decoded the way the backend decodes them.
+     */
+    fn received_field_names(form: multipart::Form, decode: bool) -> Vec<String> {
+        let server = MockServer::start(vec![MockResponse::new(200, "")]);
+        send_form(&server.url, form);
+        let request = server.finish().remove(0);
+        assert_eq!(request.method, "POST");
+        let body = String::from_utf8(request.body).unwrap();
+
+        body.split("form-data; name=\"")
+            .skip(1)
+            .map(|part| part.split('"').next().unwrap())
+            .map(|name| match decode {
+                true => percent_encoding::percent_decode_str(name)
+                    .decode_utf8()
+                    .unwrap()
+                    .into_owned(),
+                false => name.to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_file_names_round_trip() {
+        let file_names = [
+            "my_project/src/lib.cairo",
+            "my project/src/with space.cairo",
+            "my_project/src/ünïcode_合约.cairo",
+            "my_project/src/100%_done.cairo",
+        ];
+        let files = || {
+            file_names
+                .iter()
+                .map(|name| (name.to_string(), b"fn main() {}".to_vec()))
+                .collect::<Vec<_>>()
+        };
+
+        let expected = |order: [usize; 4]| -> Vec<String> {
+            order
+                .iter()
+                .map(|index| format!("{}{}", FILE_FIELD_PREFIX, file_names[*index]))
+                .collect()
+        };
+
+        // Sorted by the encoded name, as sent
+        let received = received_field_names(
+            FormSubmission {
+                files: files(),
+                ..Default::default()
+            }
+            .into_form(FormStyle::Parts, global())
+            .unwrap(),
+            true,
+        );
+        assert_eq!(received, expected([1, 2, 3, 0]));
+
+        // Legacy backends read the raw name
+        let received = received_field_names(
+            FormSubmission {
+                files: files(),
+                ..Default::default()
+            }
+            .into_form(FormStyle::Legacy, global())
+            .unwrap(),
+            false,
+        );
+        assert_eq!(received, expected([1, 3, 0, 2]));
+    }
+
+    #[test]
+    fn test_schema_mismatch_server_too_old() {
+        let message = describe_schema_version_mismatch(&[0], FormFeatures::default());
+        assert!(message.contains("server is too old"));
+    }
+
+    #[test]
+    fn test_schema_mismatch_client_too_old() {
+        let message =
+            describe_schema_version_mismatch(&[FORM_SCHEMA_VERSION + 1], FormFeatures::default());
+        assert!(message.contains("upgrade the verifier"));
+    }
+
+    #[test]
+    fn test_docs_are_gated_by_schema_version() {
+        let fields = form_metadata_fields(
+            "MIT License (MIT)",
+            "MyContract",
+            &[],
+            &metadata(),
+            true,
+            Dialect::Voyager,
+        );
+        assert_eq!(
+            fields[0],
+            ("schema_version", DOCS_FORM_SCHEMA_VERSION.to_string())
+        );
+
+        let docs = FormFeatures {
+            docs: true,
+            ..Default::default()
+        };
+        let message = describe_schema_version_mismatch(&[FORM_SCHEMA_VERSION], docs);
+        assert!(message.contains("--no-readme"));
+    }
+
+    #[test]
+    fn test_form_fields_with_aliases_snapshot() {
+        let aliases = vec!["MyToken Pro".to_owned(), "MyToken Lite".to_owned()];
+        for dialect in Dialect::ALL {
+            let fields = form_metadata_fields(
+                "MIT License (MIT)",
+                "MyContract",
+                &aliases,
+                &metadata(),
+                false,
+                *dialect,
+            );
+
+            assert_eq!(
+                fields,
+                vec![
+                    ("schema_version", "3".to_owned()),
+                    (
+                        dialect.field_name(FormField::CompilerVersion),
+                        "2.8.4".to_owned()
+                    ),
+                    ("scarb_version", "2.8.4".to_owned()),
+                    ("license", "MIT License (MIT)".to_owned()),
+                    ("name", "MyContract".to_owned()),
+                    ("contract_file", "my_project/src/main.cairo".to_owned()),
+                    ("project_dir_path", "my_project".to_owned()),
+                    ("aliases[]", "MyToken Pro".to_owned()),
+                    ("aliases[]", "MyToken Lite".to_owned()),
+                ],
+                "{} dialect",
+                dialect
+            );
+
+            // The field name is sent as-is, only file field names are encoded
+            let received = received_field_names(
+                FormSubmission {
+                    fields,
+                    ..Default::default()
+                }
+                .into_form(FormStyle::Parts, global())
+                .unwrap(),
+                false,
+            );
+            assert_eq!(received[1], dialect.field_name(FormField::CompilerVersion));
+            assert_eq!(&received[7..], ["aliases[]", "aliases[]"]);
+        }
+
+        let aliases_only = FormFeatures {
+            aliases: true,
+            ..Default::default()
+        };
+        assert!(!supports_aliases(&[
+            FORM_SCHEMA_VERSION,
+            DOCS_FORM_SCHEMA_VERSION
+        ]));
+        assert!(
+            describe_schema_version_mismatch(&[FORM_SCHEMA_VERSION], aliases_only)
+                .contains("--aliases-best-effort")
+        );
+    }
+
+    #[test]
+    fn test_form_fields_with_target_snapshot() {
+        let with_target = ProjectMetadataInfo {
+            target: StarknetContractTarget {
+                name: Some("my_vault".to_owned()),
+                casm: true,
+                build_external_contracts: vec!["external_token::token::Token".to_owned()],
+                ..Default::default()
+            },
+            ..metadata()
+        };
+        for dialect in Dialect::ALL {
+            let fields = form_metadata_fields(
+                "MIT License (MIT)",
+                "MyVault",
+                &[],
+                &with_target,
+                false,
+                *dialect,
+            );
+
+            assert_eq!(
+                fields,
+                vec![
+                    ("schema_version", "4".to_owned()),
+                    (
+                        dialect.field_name(FormField::CompilerVersion),
+                        "2.8.4".to_owned()
+                    ),
+                    ("scarb_version", "2.8.4".to_owned()),
+                    ("license", "MIT License (MIT)".to_owned()),
+                    ("name", "MyVault".to_owned()),
+                    ("contract_file", "my_project/src/main.cairo".to_owned()),
+                    ("project_dir_path", "my_project".to_owned()),
+                    ("target_name", "my_vault".to_owned()),
+                    ("target_sierra", "true".to_owned()),
+                    ("target_casm", "true".to_owned()),
+                    (
+                        "build_external_contracts[]",
+                        "external_token::token::Token".to_owned()
+                    ),
+                ],
+                "{} dialect",
+                dialect
+            );
+        }
+
+        // A default target keeps the form unchanged
+        assert_eq!(
+            form_metadata_fields(
+                "MIT License (MIT)",
+                "MyVault",
+                &[],
+                &metadata(),
+                false,
+                Dialect::Voyager
+            )[0],
+            ("schema_version", FORM_SCHEMA_VERSION.to_string())
+        );
+
+        let target_only = FormFeatures {
+            target: true,
+            ..Default::default()
+        };
+        assert!(describe_schema_version_mismatch(
+            &[FORM_SCHEMA_VERSION, ALIASES_FORM_SCHEMA_VERSION],
+            target_only
+        )
+        .contains("build-external-contracts"));
+    }
+
+    #[test]
+    fn test_plugins_are_sent_as_json() {
+        let metadata = ProjectMetadataInfo {
+            plugins: vec![CairoPlugin {
+                name: "token_macros".to_owned(),
+                version: "0.3.1".to_owned(),
+                source: "registry+https://scarbs.xyz/".to_owned(),
+                checksum: Some("sha256:5d3a".to_owned()),
+            }],
+            ..metadata()
+        };
+        let fields = form_metadata_fields(
+            "MIT License (MIT)",
+            "MyToken",
+            &[],
+            &metadata,
+            false,
+            Dialect::Voyager,
+        );
+
+        assert_eq!(
+            fields[0],
+            ("schema_version", PLUGINS_FORM_SCHEMA_VERSION.to_string())
+        );
+        assert_eq!(
+            fields.last().unwrap(),
+            &(
+                "plugins",
+                r#"[{"name":"token_macros","version":"0.3.1","source":"registry+https://scarbs.xyz/","checksum":"sha256:5d3a"}]"#
+                    .to_owned()
+            )
+        );
+        assert!(!supports_plugins(&[
+            FORM_SCHEMA_VERSION,
+            TARGET_FORM_SCHEMA_VERSION
+        ]));
+        let plugins_only = FormFeatures {
+            plugins: true,
+            ..Default::default()
+        };
+        assert!(describe_schema_version_mismatch(
+            &[FORM_SCHEMA_VERSION, TARGET_FORM_SCHEMA_VERSION],
+            plugins_only
+        )
+        .contains("compiler plugin"));
+    }
+
+    #[test]
+    fn test_cached_dependencies_are_sent_as_json() {
+        let metadata = ProjectMetadataInfo {
+            cached_dependencies: vec![DependencyRef {
+                name: "openzeppelin".to_owned(),
+                version: "0.20.0".to_owned(),
+                directory: "openzeppelin".to_owned(),
+                fingerprint: "5d3a".to_owned(),
+            }],
+            ..metadata()
+        };
+        let fields = form_metadata_fields(
+            "MIT License (MIT)",
+            "MyToken",
+            &[],
+            &metadata,
+            false,
+            Dialect::Voyager,
+        );
+
+        assert_eq!(
+            fields[0],
+            (
+                "schema_version",
+                CACHED_DEPENDENCIES_FORM_SCHEMA_VERSION.to_string()
+            )
+        );
+        assert_eq!(
+            fields.last().unwrap(),
+            &(
+                "cached_dependencies",
+                r#"[{"name":"openzeppelin","version":"0.20.0","directory":"openzeppelin","fingerprint":"5d3a"}]"#
+                    .to_owned()
+            )
+        );
+        let cached_only = FormFeatures {
+            cached_dependencies: true,
+            ..Default::default()
+        };
+        assert!(describe_schema_version_mismatch(
+            &[FORM_SCHEMA_VERSION, PLUGINS_FORM_SCHEMA_VERSION],
+            cached_only
+        )
+        .contains("--vendor-all-dependencies"));
+    }
+
+    #[test]
+    fn test_docs_are_sent_under_their_own_namespace() {
+        let received = received_field_names(
+            FormSubmission {
+                fields: vec![],
+                files: vec![("my_project/src/lib.cairo".to_owned(), vec![])],
+                docs: vec![("README.md".to_owned(), b"# MyContract".to_vec())],
+            }
+            .into_form(FormStyle::Parts, global())
+            .unwrap(),
+            true,
+        );
+        assert_eq!(
+            received,
+            vec!["files__my_project/src/lib.cairo", "docs__README.md"]
+        );
+    }
+
+    /// The form as received, with the random boundary replaced.
+    fn received_body(form: multipart::Form) -> Vec<u8> {
+        let server = MockServer::start(vec![MockResponse::new(200, "")]);
+        send_form(&server.url, form);
+        let request = server.finish().remove(0);
+        let boundary = request
+            .header("content-type")
+            .and_then(|content_type| content_type.split("boundary=").nth(1))
+            .unwrap()
+            .to_owned();
+        String::from_utf8(request.body)
+            .unwrap()
+            .replace(&boundary, "BOUNDARY")
+            .into_bytes()
+    }
+
+    fn submission() -> FormSubmission {
+        FormSubmission {
+            fields: vec![("name", "MyContract".to_owned())],
+            files: vec![("my project/lib.cairo".to_owned(), b"mod token;".to_vec())],
+            docs: vec![("README.md".to_owned(), b"# MyContract".to_vec())],
+        }
+    }
+
+    #[test]
+    fn test_legacy_style_snapshot() {
+        let body = received_body(submission().into_form(FormStyle::Legacy, global()).unwrap());
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             MyContract\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"files__my project/lib.cairo\"\r\n\r\n\
+             mod token;\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"docs__README.md\"\r\n\r\n\
+             # MyContract\r\n\
+             --BOUNDARY--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parts_style_snapshot() {
+        let body = received_body(submission().into_form(FormStyle::Parts, global()).unwrap());
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             MyContract\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"files__my%20project/lib.cairo\"; filename=\"lib.cairo\"\r\n\
+             Content-Type: text/x-cairo\r\n\r\n\
+             mod token;\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"docs__README.md\"; filename=\"README.md\"\r\n\
+             Content-Type: text/markdown\r\n\r\n\
+             # MyContract\r\n\
+             --BOUNDARY--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_archive_style_snapshot() {
+        let body = received_body(
+            submission()
+                .into_form(FormStyle::Archive, global())
+                .unwrap(),
+        );
+        let headers = "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             MyContract\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"files_archive\"; filename=\"sources.tar\"\r\n\
+             Content-Type: application/x-tar\r\n\r\n";
+        let trailer = "\r\n--BOUNDARY--\r\n";
+        assert!(body.starts_with(headers.as_bytes()));
+        assert!(body.ends_with(trailer.as_bytes()));
+
+        let archive = &body[headers.len()..body.len() - trailer.len()];
+        let submission = submission();
+        assert_eq!(
+            archive,
+            super::archive(&submission.files, &submission.docs).unwrap()
+        );
+        let entries: Vec<(String, String)> = tar::Archive::new(archive)
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                let mut content = String::new();
+                std::io::Read::read_to_string(&mut entry, &mut content).unwrap();
+                (path, content)
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "files/my project/lib.cairo".to_owned(),
+                    "mod token;".to_owned()
+                ),
+                ("docs/README.md".to_owned(), "# MyContract".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_files_are_sent_byte_for_byte() {
+        // A BOM and an ISO-8859-1 comment, as in some vendored dependencies
+        let content = b"\xef\xbb\xbf// caf\xe9\nmod token;\n".to_vec();
+        let submission = FormSubmission {
+            files: vec![("vendored/src/lib.cairo".to_owned(), content.clone())],
+            ..Default::default()
+        };
+
+        let parts = submission.clone().parts(FormStyle::Parts).unwrap();
+        assert_eq!(
+            parts[0].file,
+            Some(("lib.cairo".to_owned(), "text/x-cairo"))
+        );
+        let server = MockServer::start(vec![MockResponse::new(200, "")]);
+        send_form(
+            &server.url,
+            submission
+                .clone()
+                .into_form(FormStyle::Parts, global())
+                .unwrap(),
+        );
+        let body = server.finish().remove(0).body;
+        assert!(body.windows(content.len()).any(|window| window == content));
+
+        // Older backends decode the text fields of the legacy style
+        let error = submission.parts(FormStyle::Legacy).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("vendored/src/lib.cairo is not valid UTF-8"));
+    }
+
+    #[test]
+    fn test_deterministic_body_is_framed_like_reqwest() {
+        let mut shuffled = submission();
+        shuffled
+            .files
+            .insert(0, ("my project/z.cairo".to_owned(), b"mod z;".to_vec()));
+        for style in [FormStyle::Legacy, FormStyle::Parts, FormStyle::Archive] {
+            let parts = shuffled.clone().parts(style).unwrap();
+            let (boundary, body) = deterministic_body(&parts);
+            let body = String::from_utf8_lossy(&body).replace(&boundary, "BOUNDARY");
+            let sent = received_body(shuffled.clone().into_form(style, global()).unwrap());
+            assert_eq!(body.as_bytes(), sent, "{}", style);
+        }
+
+        // The order of the sources doesn't change the body, their contents do
+        let mut reordered = shuffled.clone();
+        reordered.files.reverse();
+        let body = |submission: FormSubmission| {
+            deterministic_body(&submission.parts(FormStyle::Parts).unwrap())
+        };
+        assert_eq!(body(shuffled.clone()), body(reordered));
+        let mut changed = shuffled.clone();
+        changed.files[0].1 = b"mod y;".to_vec();
+        assert_ne!(body(shuffled).0, body(changed).0);
+    }
+
+    /// Digest of the deterministic body of the fixture below. Any change to
+    /// the wire format changes it and must update it on purpose, as backends
+    /// deduplicate submissions by hashing that body.
+    const GOLDEN_BODY_DIGEST: &str =
+        "4b73e2175d77d8bc26c1ec7c1277a2eeeae69ac92717b182ec88af52b988d26e";
+
+    #[test]
+    fn test_deterministic_body_golden_digest() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test_data/monorepo");
+        let read = |name: &str| std::fs::read(fixture.join(name)).unwrap();
+        // Out of order on purpose, files are sent sorted
+        let files = [
+            "chain/shared/src/lib.cairo",
+            "chain/contracts/src/lib.cairo",
+            "chain/shared/Scarb.toml",
+            "chain/contracts/Scarb.toml",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), read(name)))
+        .collect();
+        let submission = FormSubmission {
+            fields: form_metadata_fields(
+                "MIT License (MIT)",
+                "MyContract",
+                &[],
+                &metadata(),
+                true,
+                Dialect::Voyager,
+            ),
+            files,
+            docs: vec![("README.md".to_owned(), read("web/README.md"))],
+        };
+
+        let parts = submission.parts(FormStyle::Parts).unwrap();
+        let names: Vec<&str> = parts.iter().map(|part| part.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "schema_version",
+                "compiler_version",
+                "scarb_version",
+                "license",
+                "name",
+                "contract_file",
+                "project_dir_path",
+                "files__chain/contracts/Scarb.toml",
+                "files__chain/contracts/src/lib.cairo",
+                "files__chain/shared/Scarb.toml",
+                "files__chain/shared/src/lib.cairo",
+                "docs__README.md",
+            ]
+        );
+        let (boundary, body) = deterministic_body(&parts);
+        assert_eq!(boundary, "voyager-5fce46822d93fc7e908ec8bce579c78e");
+        let digest: String = Sha256::digest(&body)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert_eq!(digest, GOLDEN_BODY_DIGEST);
+    }
+
+    #[test]
+    fn test_form_style_negotiation() {
+        let supported = |styles: &[&str]| {
+            FormStyle::negotiate(&styles.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(supported(&["legacy"]), Some(FormStyle::Legacy));
+        assert_eq!(supported(&["archive", "legacy"]), Some(FormStyle::Archive));
+        assert_eq!(supported(&["legacy", "parts"]), Some(FormStyle::Parts));
+        assert_eq!(supported(&["zip"]), None);
+        assert_eq!(FormStyle::Archive.to_string(), "archive");
+    }
+}
@@ -0,0 +1,987 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    env,
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use reqwest::{
+    blocking::Response,
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::display::sanitize;
+use crate::errors::VerifierError;
+use crate::humanize::format_duration;
+use crate::polling::{format_wall_clock, Clock};
+
+/// Attempts of a request, the first one included, unless `HTTP_ATTEMPTS_VAR` is set.
+pub const DEFAULT_HTTP_ATTEMPTS: u32 = 4;
+pub const HTTP_ATTEMPTS_VAR: &str = "VOYAGER_HTTP_ATTEMPTS";
+/// Delay before the first retry, doubled for each of the next ones.
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Longest `Retry-After` honored, backends asking for more are retried sooner.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+/// Header with which the backend tells a 503 of a maintenance window from an
+/// outage, its value is the message of the maintenance.
+pub const MAINTENANCE_HEADER: &str = "x-maintenance-message";
+
+/**
+ * What a request does when the backend is down for maintenance, see
+ * `--on-maintenance`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OnMaintenance {
+    /// Wait for the announced end of the maintenance, without spending
+    /// attempts or counting against the poll timeout.
+    Wait,
+    /// Fail right away with `Maintenance`.
+    Fail,
+}
+
+impl OnMaintenance {
+    /**
+     * Fail in CI, where nobody is there to tell a stuck run from a waiting
+     * one, wait otherwise. `ci` is the value of the `CI` variable.
+     */
+    pub fn default_for(ci: Option<&str>) -> Self {
+        if ci.is_some_and(|ci| !ci.is_empty() && ci != "false" && ci != "0") {
+            OnMaintenance::Fail
+        } else {
+            OnMaintenance::Wait
+        }
+    }
+}
+
+static ON_MAINTENANCE: Mutex<Option<OnMaintenance>> = Mutex::new(None);
+
+/**
+ * Set what the requests of the process do on maintenance, from
+ * `--on-maintenance`.
+ */
+pub fn set_on_maintenance(on_maintenance: OnMaintenance) {
+    if let Ok(mut current) = ON_MAINTENANCE.lock() {
+        *current = Some(on_maintenance);
+    }
+}
+
+/**
+ * What the requests of the process do on maintenance, as set or by default
+ * for the environment.
+ */
+pub fn on_maintenance() -> OnMaintenance {
+    ON_MAINTENANCE
+        .lock()
+        .ok()
+        .and_then(|current| *current)
+        .unwrap_or_else(|| OnMaintenance::default_for(env::var("CI").ok().as_deref()))
+}
+
+/**
+ * How often and how long apart a request failing transiently is sent again.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, at least one.
+    pub attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub on_maintenance: OnMaintenance,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_HTTP_ATTEMPTS,
+            base_delay: BASE_BACKOFF,
+            max_delay: MAX_BACKOFF,
+            on_maintenance: OnMaintenance::Fail,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let attempts = env::var(HTTP_ATTEMPTS_VAR)
+            .ok()
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .unwrap_or(DEFAULT_HTTP_ATTEMPTS);
+        Self {
+            attempts: attempts.max(1),
+            on_maintenance: on_maintenance(),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Delay before the retry number `retry`, counted from 1: exponential,
+     * bounded by `max_delay`, and with its upper half spread by `jitter`
+     * (between 0 and 1) so that clients failing together don't retry together.
+     */
+    pub fn backoff(&self, retry: u32, jitter: f64) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        exponential / 2 + exponential.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/**
+ * Which failures a request may be sent again after.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resend {
+    /// Reads, sent again after any transient failure.
+    Always,
+    /// Submissions, only sent again when the backend can't have accepted them:
+    /// the connection failed, or it answered 429 or 5xx. A timed out one may
+    /// have been, resending it could create a second job.
+    Unaccepted,
+}
+
+/**
+ * Why a request was sent again.
+ */
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryCause {
+    /// 429, the client sends too many requests.
+    RateLimited,
+    /// 502, 503 or 504, usually from a gateway in front of the backend.
+    Gateway,
+    /// Any other 5xx.
+    ServerError,
+    Connection,
+    Timeout,
+}
+
+impl RetryCause {
+    fn of_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => RetryCause::RateLimited,
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => RetryCause::Gateway,
+            _ => RetryCause::ServerError,
+        }
+    }
+
+    fn describe(&self, count: u64) -> String {
+        let (one, many) = match self {
+            RetryCause::RateLimited => ("rate limit", "rate limits"),
+            RetryCause::Gateway => ("gateway error", "gateway errors"),
+            RetryCause::ServerError => ("server error", "server errors"),
+            RetryCause::Connection => ("connection error", "connection errors"),
+            RetryCause::Timeout => ("timeout", "timeouts"),
+        };
+        format!("{} {}", count, if count == 1 { one } else { many })
+    }
+}
+
+/**
+ * A request sent again after a transient failure.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryRecord {
+    pub endpoint: String,
+    /// The attempt that failed, counted from 1.
+    pub attempt: u32,
+    pub cause: RetryCause,
+    pub wait: Duration,
+    /// Whether the wait is the `Retry-After` asked for by the backend.
+    pub retry_after: bool,
+    pub at: Instant,
+}
+
+/**
+ * A wait for the backend to come back from maintenance.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceRecord {
+    pub endpoint: String,
+    /// The maintenance message of the backend.
+    pub message: String,
+    /// How long the backend announced the maintenance to last, if it did.
+    pub announced: Option<Duration>,
+    pub waited: Duration,
+    pub at: Instant,
+}
+
+/**
+ * The retries of a run, for the report.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ReliabilityStats {
+    pub retries: u64,
+    /// Retries per cause, causes that didn't occur are left out.
+    pub causes: BTreeMap<RetryCause, u64>,
+    /// Retries waiting for as long as the `Retry-After` of the backend.
+    pub retry_after_honored: u64,
+    pub waited_ms: u64,
+    /// Maintenance windows waited for, apart from the retries.
+    #[serde(default)]
+    pub maintenance_windows: u64,
+    #[serde(default)]
+    pub maintenance_waited_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct RetryState {
+    records: Vec<RetryRecord>,
+    /// Records already returned by `unreported`.
+    reported: usize,
+    maintenance: Vec<MaintenanceRecord>,
+}
+
+/**
+ * Retries for the lifetime of the process, see `global`. Like the transfer
+ * log, it is shared by every request, whichever command sends it.
+ */
+#[derive(Debug, Default)]
+pub struct RetryLog {
+    state: Mutex<RetryState>,
+}
+
+static GLOBAL: RetryLog = RetryLog {
+    state: Mutex::new(RetryState {
+        records: Vec::new(),
+        reported: 0,
+        maintenance: Vec::new(),
+    }),
+};
+
+pub fn global() -> &'static RetryLog {
+    &GLOBAL
+}
+
+impl RetryLog {
+    pub fn record(&self, record: RetryRecord) {
+        if let Ok(mut state) = self.state.lock() {
+            state.records.push(record);
+        }
+    }
+
+    /**
+     * Record a wait for maintenance, returning whether it starts a new
+     * window, i.e. the first one or with another message, to announce it
+     * only once.
+     */
+    pub fn record_maintenance(&self, record: MaintenanceRecord) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        let new_window =
+            state.maintenance.last().map(|last| &last.message) != Some(&record.message);
+        state.maintenance.push(record);
+        new_window
+    }
+
+    /**
+     * How long the run waited for maintenance so far, which deadlines leave
+     * out.
+     */
+    pub fn maintenance_waited(&self) -> Duration {
+        self.state
+            .lock()
+            .map(|state| state.maintenance.iter().map(|record| record.waited).sum())
+            .unwrap_or_default()
+    }
+
+    /**
+     * The retries since the previous call, for the event stream.
+     */
+    pub fn unreported(&self) -> Vec<RetryRecord> {
+        let Ok(mut state) = self.state.lock() else {
+            return vec![];
+        };
+        let unreported = state.records[state.reported..].to_vec();
+        state.reported = state.records.len();
+        unreported
+    }
+
+    pub fn stats(&self) -> ReliabilityStats {
+        let mut stats = ReliabilityStats::default();
+        let Ok(state) = self.state.lock() else {
+            return stats;
+        };
+        for record in &state.records {
+            stats.retries += 1;
+            *stats.causes.entry(record.cause).or_default() += 1;
+            stats.retry_after_honored += record.retry_after as u64;
+            stats.waited_ms += record.wait.as_millis() as u64;
+        }
+        let mut previous: Option<&str> = None;
+        for record in &state.maintenance {
+            if previous != Some(record.message.as_str()) {
+                stats.maintenance_windows += 1;
+            }
+            stats.maintenance_waited_ms += record.waited.as_millis() as u64;
+            previous = Some(&record.message);
+        }
+        stats
+    }
+
+    /**
+     * One line for the end of the run, `None` when nothing was retried or
+     * waited for.
+     */
+    pub fn summary(&self) -> Option<String> {
+        let stats = self.stats();
+        let mut parts = vec![];
+        if stats.retries > 0 {
+            let causes: Vec<String> = stats
+                .causes
+                .iter()
+                .map(|(cause, count)| cause.describe(*count))
+                .collect();
+            parts.push(format!(
+                "Retried {} {} ({}), waiting {} in total",
+                stats.retries,
+                if stats.retries == 1 {
+                    "request"
+                } else {
+                    "requests"
+                },
+                causes.join(", "),
+                format_duration(Duration::from_millis(stats.waited_ms))
+            ));
+        }
+        if stats.maintenance_windows > 0 {
+            parts.push(format!(
+                "Waited {} for {} maintenance {} of the backend",
+                format_duration(Duration::from_millis(stats.maintenance_waited_ms)),
+                stats.maintenance_windows,
+                if stats.maintenance_windows == 1 {
+                    "window"
+                } else {
+                    "windows"
+                }
+            ));
+        }
+        (!parts.is_empty()).then(|| parts.join(". "))
+    }
+}
+
+/**
+ * The parts of a response retries look at, of the blocking and the async
+ * client alike.
+ */
+pub trait RetryResponse {
+    fn status(&self) -> StatusCode;
+    fn headers(&self) -> &HeaderMap;
+}
+
+impl RetryResponse for Response {
+    fn status(&self) -> StatusCode {
+        Response::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        Response::headers(self)
+    }
+}
+
+impl RetryResponse for reqwest::Response {
+    fn status(&self) -> StatusCode {
+        reqwest::Response::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        reqwest::Response::headers(self)
+    }
+}
+
+/// Random number between 0 and 1, without a dependency for it.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// The `Retry-After` of the backend as is.
+fn announced_retry_after(response: &impl RetryResponse) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// The `Retry-After` of the backend, bounded for retries.
+fn retry_after(response: &impl RetryResponse) -> Option<Duration> {
+    announced_retry_after(response).map(|delay| delay.min(MAX_RETRY_AFTER))
+}
+
+/**
+ * The message and announced length of the maintenance `outcome` is an answer
+ * of, `None` when it isn't: a 503 with a `MAINTENANCE_HEADER`.
+ */
+fn maintenance(outcome: &Result<impl RetryResponse>) -> Option<(String, Option<Duration>)> {
+    let response = outcome.as_ref().ok()?;
+    if response.status() != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let message = response.headers().get(MAINTENANCE_HEADER)?;
+    let message = String::from_utf8_lossy(message.as_bytes())
+        .trim()
+        .to_owned();
+    Some((message, announced_retry_after(response)))
+}
+
+/**
+ * Why `outcome` is worth sending again, `None` when it is final.
+ */
+fn transient(outcome: &Result<impl RetryResponse>, resend: Resend) -> Option<(RetryCause, String)> {
+    match outcome {
+        Ok(response) => {
+            let status = response.status();
+            (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()).then(|| {
+                (
+                    RetryCause::of_status(status),
+                    format!("the backend answered {}", status),
+                )
+            })
+        }
+        Err(e) => {
+            let reqwest_error = e
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<reqwest::Error>())?;
+            if reqwest_error.is_connect() {
+                Some((RetryCause::Connection, format!("{:#}", e)))
+            } else if resend == Resend::Always && reqwest_error.is_timeout() {
+                Some((RetryCause::Timeout, format!("{:#}", e)))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/**
+ * What follows an attempt: its outcome is final, or the request is sent again
+ * after the delay, as another attempt or, after maintenance, as the same one.
+ */
+enum Attempted<R> {
+    Done(Result<R>),
+    Retry(Duration),
+    Wait(Duration),
+}
+
+/**
+ * Decide what follows attempt number `attempt`, recording the retry in
+ * `retries` if there is one. Shared by the blocking and the async loops,
+ * which only differ in how they wait.
+ */
+fn after_attempt<R: RetryResponse>(
+    outcome: Result<R>,
+    attempt: u32,
+    policy: &RetryPolicy,
+    retries: &RetryLog,
+    resend: Resend,
+    endpoint: &str,
+    now: Instant,
+) -> Attempted<R> {
+    if let Some((message, announced)) = maintenance(&outcome) {
+        return after_maintenance(message, announced, policy, retries, endpoint, now);
+    }
+    let Some((retry_cause, cause)) = transient(&outcome, resend) else {
+        return Attempted::Done(outcome);
+    };
+    if attempt >= policy.attempts {
+        if attempt == 1 {
+            return Attempted::Done(outcome);
+        }
+        return Attempted::Done(Err(VerifierError::RetriesExhausted {
+            endpoint: endpoint.to_owned(),
+            attempts: attempt,
+            cause,
+        }
+        .into()));
+    }
+    let asked = match &outcome {
+        Ok(response) => retry_after(response),
+        Err(_) => None,
+    };
+    let delay = asked.unwrap_or_else(|| policy.backoff(attempt, jitter()));
+    retries.record(RetryRecord {
+        endpoint: endpoint.to_owned(),
+        attempt,
+        cause: retry_cause,
+        wait: delay,
+        retry_after: asked.is_some(),
+        at: now,
+    });
+    println!(
+        "Request to the {} endpoint failed, {}. Retrying in {}s ({}/{}).",
+        endpoint,
+        cause,
+        delay.as_secs_f32(),
+        attempt + 1,
+        policy.attempts
+    );
+    Attempted::Retry(delay)
+}
+
+/**
+ * Fail with `Maintenance`, or wait for as long as the backend announced, or
+ * `max_delay` when it didn't, announcing the window the first time.
+ */
+fn after_maintenance<R>(
+    message: String,
+    announced: Option<Duration>,
+    policy: &RetryPolicy,
+    retries: &RetryLog,
+    endpoint: &str,
+    now: Instant,
+) -> Attempted<R> {
+    if policy.on_maintenance == OnMaintenance::Fail {
+        return Attempted::Done(Err(VerifierError::Maintenance {
+            endpoint: endpoint.to_owned(),
+            message,
+            retry_after_secs: announced.map(|announced| announced.as_secs()),
+        }
+        .into()));
+    }
+    let wait = announced.unwrap_or(policy.max_delay);
+    let new_window = retries.record_maintenance(MaintenanceRecord {
+        endpoint: endpoint.to_owned(),
+        message: message.clone(),
+        announced,
+        waited: wait,
+        at: now,
+    });
+    if new_window {
+        let window = match announced {
+            Some(announced) => format!(
+                "announced to end around {} (in {})",
+                format_wall_clock(SystemTime::now() + announced),
+                format_duration(announced)
+            ),
+            None => "without an announced end".to_owned(),
+        };
+        println!(
+            "The backend is down for maintenance, {}: {}. Waiting for it, pass --on-maintenance fail to stop instead.",
+            window,
+            sanitize(&message)
+        );
+    }
+    Attempted::Wait(wait)
+}
+
+/**
+ * Send a request built by `send`, which gets the number of the send counted
+ * from 1, until it succeeds, fails for good, or `policy` runs out of
+ * attempts. A 429 or 5xx answer of the last attempt fails with
+ * `RetriesExhausted`, naming `endpoint` and the attempts, unless there was
+ * only one. Each retry is recorded in `retries`. Waits for maintenance, see
+ * `OnMaintenance`, are sends of their own but don't spend attempts.
+ */
+pub fn send_with_retry(
+    policy: &RetryPolicy,
+    clock: &dyn Clock,
+    retries: &RetryLog,
+    resend: Resend,
+    endpoint: &str,
+    mut send: impl FnMut(u32) -> Result<Response>,
+) -> Result<Response> {
+    let (mut attempt, mut sent) = (1, 1);
+    loop {
+        let outcome = send(sent);
+        match after_attempt(
+            outcome,
+            attempt,
+            policy,
+            retries,
+            resend,
+            endpoint,
+            clock.now(),
+        ) {
+            Attempted::Done(outcome) => return outcome,
+            Attempted::Retry(delay) => {
+                clock.sleep(delay);
+                attempt += 1;
+            }
+            Attempted::Wait(delay) => clock.sleep(delay),
+        }
+        sent += 1;
+    }
+}
+
+/**
+ * `send_with_retry` for the async client, sleeping on the runtime between
+ * attempts. Once `cancel` is cancelled, the attempt in flight is dropped
+ * and the wait for the next one ends.
+ */
+pub async fn send_with_retry_async<F, Fut>(
+    policy: &RetryPolicy,
+    cancel: &crate::cancel::CancelToken,
+    retries: &RetryLog,
+    resend: Resend,
+    endpoint: &str,
+    mut send: F,
+) -> Result<reqwest::Response>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: std::future::Future<Output = Result<reqwest::Response>>,
+{
+    let (mut attempt, mut sent) = (1, 1);
+    loop {
+        let outcome = cancel.run_async(send(sent)).await;
+        match after_attempt(
+            outcome,
+            attempt,
+            policy,
+            retries,
+            resend,
+            endpoint,
+            Instant::now(),
+        ) {
+            Attempted::Done(outcome) => return outcome,
+            Attempted::Retry(delay) => {
+                cancel.wait_async(delay).await;
+                attempt += 1;
+            }
+            Attempted::Wait(delay) => {
+                cancel.wait_async(delay).await;
+            }
+        }
+        sent += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::error_code;
+    use crate::mock_server::{MockResponse, MockServer};
+    use crate::polling::SystemClock;
+    use reqwest::blocking::Client;
+    use std::{
+        cell::RefCell,
+        net::TcpListener,
+        time::{Instant, SystemTime},
+    };
+
+    #[derive(Default)]
+    struct RecordingClock {
+        sleeps: RefCell<Vec<Duration>>,
+    }
+
+    impl Clock for RecordingClock {
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn wall(&self) -> SystemTime {
+            SystemTime::now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.sleeps.borrow_mut().push(duration);
+        }
+    }
+
+    fn policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            ..Default::default()
+        }
+    }
+
+    fn get(url: &str) -> impl FnMut(u32) -> Result<Response> + '_ {
+        move |_| Ok(Client::new().get(url).send()?)
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_bounded_and_jittered() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3, 1.0), Duration::from_secs(4));
+        assert_eq!(policy.backoff(30, 1.0), MAX_BACKOFF);
+        for _ in 0..100 {
+            let delay = policy.backoff(2, jitter());
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+        }
+    }
+
+    #[test]
+    fn test_server_errors_are_retried_and_retry_after_honored() {
+        let server = MockServer::start(vec![
+            MockResponse::new(503, "{}"),
+            MockResponse::new(429, "{}").header("retry-after", "7"),
+            MockResponse::new(200, "{}"),
+        ]);
+        let clock = RecordingClock::default();
+        let url = format!("{}/class-verify/job/job", server.url);
+        let response = send_with_retry(
+            &policy(4),
+            &clock,
+            &RetryLog::default(),
+            Resend::Always,
+            "job-status",
+            get(&url),
+        )
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.finish().len(), 3);
+        let sleeps = clock.sleeps.borrow();
+        assert!(sleeps[0] >= Duration::from_millis(500) && sleeps[0] <= Duration::from_secs(1));
+        assert_eq!(sleeps[1], Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_retries_are_counted_by_cause() {
+        let server = MockServer::start(vec![
+            MockResponse::new(502, "{}"),
+            MockResponse::new(429, "{}").header("retry-after", "3"),
+            MockResponse::new(500, "{}"),
+            MockResponse::new(503, "{}"),
+            MockResponse::new(200, "{}"),
+        ]);
+        let retries = RetryLog::default();
+        let url = format!("{}/class-verify/job/job", server.url);
+        send_with_retry(
+            &policy(5),
+            &RecordingClock::default(),
+            &retries,
+            Resend::Always,
+            "job-status",
+            get(&url),
+        )
+        .unwrap();
+        server.finish();
+
+        let records = retries.unreported();
+        let causes: Vec<(u32, RetryCause)> = records
+            .iter()
+            .map(|record| (record.attempt, record.cause))
+            .collect();
+        assert_eq!(
+            causes,
+            [
+                (1, RetryCause::Gateway),
+                (2, RetryCause::RateLimited),
+                (3, RetryCause::ServerError),
+                (4, RetryCause::Gateway),
+            ]
+        );
+        assert!(records[1].retry_after && records[1].wait == Duration::from_secs(3));
+        assert!(retries.unreported().is_empty());
+
+        let stats = retries.stats();
+        assert_eq!(stats.retries, 4);
+        assert_eq!(
+            stats.causes,
+            BTreeMap::from([
+                (RetryCause::RateLimited, 1),
+                (RetryCause::Gateway, 2),
+                (RetryCause::ServerError, 1),
+            ])
+        );
+        assert_eq!(stats.retry_after_honored, 1);
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["causes"]["rate_limited"], 1);
+        assert_eq!(json["causes"]["gateway"], 2);
+
+        let summary = retries.summary().unwrap();
+        assert!(summary.starts_with(
+            "Retried 4 requests (1 rate limit, 2 gateway errors, 1 server error), waiting "
+        ));
+        assert_eq!(RetryLog::default().summary(), None);
+    }
+
+    #[test]
+    fn test_exhausted_retries_name_the_attempts() {
+        let server = MockServer::start(vec![
+            MockResponse::new(500, "{}"),
+            MockResponse::new(502, "{}"),
+            MockResponse::new(503, "{}"),
+        ]);
+        let url = format!("{}/class-verify/job/job", server.url);
+        let error = send_with_retry(
+            &policy(3),
+            &RecordingClock::default(),
+            &RetryLog::default(),
+            Resend::Always,
+            "job-status",
+            get(&url),
+        )
+        .unwrap_err();
+        server.finish();
+
+        assert_eq!(error_code(&error), Some("VER-047"));
+        assert!(error.to_string().contains("after 3 attempts"));
+        assert!(error.to_string().contains("503"));
+
+        // Without retries, the answer is left to the caller as before
+        let server = MockServer::start(vec![MockResponse::new(500, "{}")]);
+        let url = format!("{}/class-verify/job/job", server.url);
+        let response = send_with_retry(
+            &policy(1),
+            &SystemClock,
+            &RetryLog::default(),
+            Resend::Always,
+            "job-status",
+            get(&url),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        server.finish();
+    }
+
+    #[test]
+    fn test_connection_errors_are_retried() {
+        // Nothing listens on the port once the listener is dropped
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{}/class-verify/0x1", address);
+        let clock = RecordingClock::default();
+        let retries = RetryLog::default();
+        let mut attempts = 0;
+        let error = send_with_retry(
+            &policy(3),
+            &clock,
+            &retries,
+            Resend::Unaccepted,
+            "verify",
+            |_| {
+                attempts += 1;
+                Ok(Client::new().post(&url).send()?)
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(attempts, 3);
+        assert_eq!(clock.sleeps.borrow().len(), 2);
+        assert_eq!(
+            retries.stats().causes,
+            BTreeMap::from([(RetryCause::Connection, 2)])
+        );
+        assert!(format!("{:#}", error).contains("after 3 attempts"));
+    }
+
+    fn maintenance(retry_after: &str) -> MockResponse {
+        MockResponse::new(503, "{}")
+            .header(MAINTENANCE_HEADER, "Database upgrade")
+            .header("retry-after", retry_after)
+    }
+
+    #[test]
+    fn test_maintenance_is_waited_for_without_spending_attempts() {
+        let server = MockServer::start(vec![
+            maintenance("600"),
+            maintenance("300"),
+            MockResponse::new(200, "{}"),
+        ]);
+        let clock = RecordingClock::default();
+        let retries = RetryLog::default();
+        let url = format!("{}/class-verify/job/job", server.url);
+        let mut sends = vec![];
+        let response = send_with_retry(
+            &RetryPolicy {
+                on_maintenance: OnMaintenance::Wait,
+                ..policy(1)
+            },
+            &clock,
+            &retries,
+            Resend::Unaccepted,
+            "verify",
+            |sent| {
+                sends.push(sent);
+                Ok(Client::new().post(&url).send()?)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.finish().len(), 3);
+        assert_eq!(sends, [1, 2, 3]);
+        // Waited as long as announced, well past the bound of retries
+        assert_eq!(
+            *clock.sleeps.borrow(),
+            [Duration::from_secs(600), Duration::from_secs(300)]
+        );
+        assert_eq!(retries.maintenance_waited(), Duration::from_secs(900));
+        assert!(retries.unreported().is_empty());
+
+        let stats = retries.stats();
+        assert_eq!((stats.retries, stats.maintenance_windows), (0, 1));
+        assert_eq!(stats.maintenance_waited_ms, 900_000);
+        assert_eq!(
+            retries.summary().unwrap(),
+            "Waited 15m 00s for 1 maintenance window of the backend"
+        );
+    }
+
+    #[test]
+    fn test_maintenance_fails_right_away_when_asked() {
+        let server = MockServer::start(vec![maintenance("1800")]);
+        let url = format!("{}/class-verify/job/job", server.url);
+        let retries = RetryLog::default();
+        let error = send_with_retry(
+            &RetryPolicy {
+                on_maintenance: OnMaintenance::Fail,
+                ..policy(4)
+            },
+            &RecordingClock::default(),
+            &retries,
+            Resend::Always,
+            "job-status",
+            get(&url),
+        )
+        .unwrap_err();
+
+        assert_eq!(server.finish().len(), 1);
+        assert_eq!(error_code(&error), Some("VER-051"));
+        assert!(error.to_string().contains("Database upgrade"));
+        assert_eq!(retries.summary(), None);
+
+        assert_eq!(
+            OnMaintenance::default_for(Some("true")),
+            OnMaintenance::Fail
+        );
+        assert_eq!(
+            OnMaintenance::default_for(Some("false")),
+            OnMaintenance::Wait
+        );
+        assert_eq!(OnMaintenance::default_for(None), OnMaintenance::Wait);
+    }
+
+    #[test]
+    fn test_submissions_are_never_resent_once_answered() {
+        let server = MockServer::start(vec![
+            MockResponse::new(503, "{}"),
+            MockResponse::new(400, r#"{"error": "No files"}"#),
+        ]);
+        let url = format!("{}/class-verify/0x1", server.url);
+        let response = send_with_retry(
+            &policy(4),
+            &RecordingClock::default(),
+            &RetryLog::default(),
+            Resend::Unaccepted,
+            "verify",
+            |_| Ok(Client::new().post(&url).body("form").send()?),
+        )
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(server.finish().len(), 2);
+    }
+}
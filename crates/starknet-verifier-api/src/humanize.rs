@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+/**
+ * Human readable duration: `820ms`, `7.2s`, `6m 07s` or `1h 02m`.
+ */
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1_000 {
+        return format!("{}ms", millis);
+    }
+    // Rounded first, so that 59.96s doesn't print as 60.0s
+    let tenths = (millis + 50) / 100;
+    if tenths < 600 {
+        return format!("{}.{}s", tenths / 10, tenths % 10);
+    }
+    let secs = (millis + 500) / 1_000;
+    if secs < 60 * 60 {
+        return format!("{}m {:02}s", secs / 60, secs % 60);
+    }
+    let mins = secs / 60;
+    format!("{}h {:02}m", mins / 60, mins % 60)
+}
+
+/**
+ * Count with thousands separators, e.g. `48,213`.
+ */
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/**
+ * Human readable size, e.g. `1.5 MiB`.
+ */
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use reqwest::blocking::{Client, ClientBuilder};
+use url::Url;
+
+/// How long a connection of `probe` may take before the family is considered broken.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A family this much slower than the other is considered broken.
+const CLEARLY_SLOWER: u32 = 5;
+
+/// Below this, a family is fast enough whatever the other does.
+const FAST_ENOUGH: Duration = Duration::from_millis(500);
+
+/**
+ * Address family the requests connect over. With `auto`, the addresses of
+ * both are tried, the second family shortly after the first one stalls, as
+ * the HTTP client does by default.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum IpVersion {
+    #[default]
+    Auto,
+    /// IPv4 only.
+    #[cfg_attr(feature = "clap", value(name = "4"))]
+    V4,
+    /// IPv6 only.
+    #[cfg_attr(feature = "clap", value(name = "6"))]
+    V6,
+}
+
+impl IpVersion {
+    pub fn name(self) -> &'static str {
+        match self {
+            IpVersion::Auto => "auto",
+            IpVersion::V4 => "4",
+            IpVersion::V6 => "6",
+        }
+    }
+
+    /**
+     * The unspecified local address of the family. The client only connects
+     * to the addresses of the family of its local address.
+     */
+    pub fn local_address(self) -> Option<IpAddr> {
+        match self {
+            IpVersion::Auto => None,
+            IpVersion::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpVersion::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+
+    pub fn allows(self, address: &SocketAddr) -> bool {
+        match self {
+            IpVersion::Auto => true,
+            IpVersion::V4 => address.is_ipv4(),
+            IpVersion::V6 => address.is_ipv6(),
+        }
+    }
+}
+
+impl std::fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Name of the family of `address`, as shown by --debug-http and `doctor`.
+pub fn family(address: &SocketAddr) -> &'static str {
+    if address.is_ipv4() {
+        "IPv4"
+    } else {
+        "IPv6"
+    }
+}
+
+static IP_VERSION: Mutex<IpVersion> = Mutex::new(IpVersion::Auto);
+
+/**
+ * Set the family the requests of the process connect over, from `--ip-version`.
+ */
+pub fn set_ip_version(version: IpVersion) {
+    if let Ok(mut current) = IP_VERSION.lock() {
+        *current = version;
+    }
+}
+
+pub fn ip_version() -> IpVersion {
+    IP_VERSION
+        .lock()
+        .map(|current| *current)
+        .unwrap_or_default()
+}
+
+/**
+ * Builder of the clients of the requests, connecting over the family set with
+ * `set_ip_version`.
+ */
+pub fn client_builder() -> ClientBuilder {
+    Client::builder().local_address(ip_version().local_address())
+}
+
+/**
+ * `client_builder` for the async client.
+ */
+pub fn client_builder_async() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().local_address(ip_version().local_address())
+}
+
+/**
+ * How connecting to the first address of a family went.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FamilyProbe {
+    pub address: SocketAddr,
+    pub result: Result<Duration, String>,
+}
+
+/**
+ * Connecting over each family to the host of an endpoint, `None` for a family
+ * the host has no address of.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointProbe {
+    pub endpoint: String,
+    pub ipv4: Option<FamilyProbe>,
+    pub ipv6: Option<FamilyProbe>,
+}
+
+fn connect(address: SocketAddr, timeout: Duration) -> FamilyProbe {
+    let started = Instant::now();
+    FamilyProbe {
+        address,
+        result: TcpStream::connect_timeout(&address, timeout)
+            .map(|_| started.elapsed())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/**
+ * Time a TCP connection to the first address of each family of the host of
+ * `url`, giving up on a family after `timeout`.
+ */
+pub fn probe(url: &Url, timeout: Duration) -> EndpointProbe {
+    let addresses = url.socket_addrs(|| None).unwrap_or_default();
+    let first = |version: IpVersion| {
+        addresses
+            .iter()
+            .find(|address| version.allows(address))
+            .map(|address| connect(*address, timeout))
+    };
+    EndpointProbe {
+        endpoint: url.to_string(),
+        ipv4: first(IpVersion::V4),
+        ipv6: first(IpVersion::V6),
+    }
+}
+
+/**
+ * The family to set when one is clearly broken for every endpoint, failing or
+ * much slower than the other where the other works. `None` when both work,
+ * or when no family works any better.
+ */
+pub fn recommend(probes: &[EndpointProbe]) -> Option<IpVersion> {
+    let broken = |probe: &EndpointProbe, family: IpVersion| {
+        let (own, other) = match family {
+            IpVersion::V4 => (&probe.ipv4, &probe.ipv6),
+            _ => (&probe.ipv6, &probe.ipv4),
+        };
+        let Some(Ok(other)) = other.as_ref().map(|other| &other.result) else {
+            return false;
+        };
+        match own.as_ref().map(|own| &own.result) {
+            Some(Err(_)) => true,
+            Some(Ok(own)) => *own > FAST_ENOUGH && *own > *other * CLEARLY_SLOWER,
+            None => false,
+        }
+    };
+    if probes.is_empty() {
+        return None;
+    }
+    if probes.iter().all(|probe| broken(probe, IpVersion::V6)) {
+        Some(IpVersion::V4)
+    } else if probes.iter().all(|probe| broken(probe, IpVersion::V4)) {
+        Some(IpVersion::V6)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn family_probe(address: &str, result: Result<u64, &str>) -> Option<FamilyProbe> {
+        Some(FamilyProbe {
+            address: address.parse().unwrap(),
+            result: result.map(Duration::from_millis).map_err(str::to_owned),
+        })
+    }
+
+    fn endpoint(ipv4: Result<u64, &str>, ipv6: Option<Result<u64, &str>>) -> EndpointProbe {
+        EndpointProbe {
+            endpoint: "https://api.voyager.online/beta".to_owned(),
+            ipv4: family_probe("1.2.3.4:443", ipv4),
+            ipv6: ipv6.and_then(|result| family_probe("[2001:db8::1]:443", result)),
+        }
+    }
+
+    #[test]
+    fn test_broken_family_is_recommended_against() {
+        let timed_out = Err("connection timed out");
+        assert_eq!(
+            recommend(&[
+                endpoint(Ok(40), Some(timed_out)),
+                endpoint(Ok(60), Some(timed_out))
+            ]),
+            Some(IpVersion::V4)
+        );
+        assert_eq!(
+            recommend(&[endpoint(timed_out, Some(Ok(40)))]),
+            Some(IpVersion::V6)
+        );
+        // Much slower and past the point where it matters
+        assert_eq!(
+            recommend(&[endpoint(Ok(40), Some(Ok(3000)))]),
+            Some(IpVersion::V4)
+        );
+        assert_eq!(recommend(&[endpoint(Ok(40), Some(Ok(300)))]), None);
+    }
+
+    #[test]
+    fn test_nothing_is_recommended_unless_every_endpoint_agrees() {
+        let timed_out = Err("connection timed out");
+        assert_eq!(recommend(&[]), None);
+        assert_eq!(recommend(&[endpoint(Ok(40), Some(Ok(45)))]), None);
+        // Hosts without IPv6 addresses don't tell anything about IPv6
+        assert_eq!(recommend(&[endpoint(Ok(40), None)]), None);
+        assert_eq!(
+            recommend(&[endpoint(Ok(40), Some(timed_out)), endpoint(Ok(40), None)]),
+            None
+        );
+        // Nothing works any better
+        assert_eq!(recommend(&[endpoint(timed_out, Some(timed_out))]), None);
+    }
+
+    #[test]
+    fn test_probe_connects_over_the_families_of_the_host() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+
+        let probe = probe(&url, PROBE_TIMEOUT);
+        assert!(probe.ipv4.unwrap().result.is_ok());
+        assert_eq!(probe.ipv6, None);
+    }
+
+    #[test]
+    fn test_local_address_selects_the_family() {
+        assert_eq!(IpVersion::Auto.local_address(), None);
+        assert!(IpVersion::V4.local_address().unwrap().is_ipv4());
+        assert!(IpVersion::V6.local_address().unwrap().is_ipv6());
+        assert_eq!(IpVersion::V4.to_string(), "4");
+        assert_eq!(IpVersion::V6.to_string(), "6");
+    }
+}
@@ -0,0 +1,35 @@
+//! Requests of the verification API of Voyager, used by the
+//! `starknet-contract-verifier` CLI. Requests fail with an `Error`, whose
+//! `Verifier` variant holds a coded `errors::VerifierError` for the failures
+//! the backend or the inputs caused, see `Error::verifier_error`.
+
+pub mod api;
+#[cfg(feature = "async")]
+pub mod api_async;
+#[cfg(not(feature = "async"))]
+mod api_async;
+pub mod cancel;
+pub mod class_hash;
+pub mod client;
+pub mod corrections;
+pub mod dependencies;
+pub mod dialect;
+mod display;
+pub mod errors;
+pub mod form;
+pub mod http_retry;
+mod humanize;
+pub mod ip_version;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_server;
+pub mod polling;
+pub mod preflight;
+pub mod resubmit;
+pub mod sierra;
+pub mod sources;
+pub mod transfer;
+pub mod upload_errors;
+pub mod validation;
+pub mod verified_metadata;
+
+pub use errors::{Error, Result};
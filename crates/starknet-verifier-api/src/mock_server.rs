@@ -0,0 +1,134 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread::JoinHandle,
+};
+
+use crate::api::{parse_endpoint, Network};
+
+/**
+ * Minimal HTTP server for tests, answering each request with the next of the
+ * given responses and recording what it received. Every response closes the
+ * connection so that each request is read from a fresh one.
+ */
+pub struct MockServer {
+    pub url: String,
+    handle: JoinHandle<Vec<RecordedRequest>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.to_owned(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_owned();
+                let path = parts.next().unwrap_or_default().to_owned();
+
+                let mut headers = vec![];
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.push((name.trim().to_owned(), value.trim().to_owned()));
+                    }
+                }
+                let request = RecordedRequest {
+                    method,
+                    path,
+                    body: vec![],
+                    headers,
+                };
+                let content_length = request
+                    .header("content-length")
+                    .map(|length| length.parse().unwrap())
+                    .unwrap_or(0);
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let mut raw = format!("HTTP/1.1 {} Mock\r\n", response.status);
+                for (name, value) in response.headers.iter() {
+                    raw.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                raw.push_str(&format!(
+                    "content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    response.body.len(),
+                    response.body
+                ));
+                reader.get_mut().write_all(raw.as_bytes()).unwrap();
+
+                requests.push(RecordedRequest { body, ..request });
+            }
+            requests
+        });
+
+        Self { url, handle }
+    }
+
+    /**
+     * A custom network whose endpoints are this server.
+     */
+    pub fn network(&self) -> Network {
+        let url = parse_endpoint(&self.url).unwrap();
+        Network::Custom {
+            internal: url.clone(),
+            public: url,
+        }
+    }
+
+    /**
+     * Wait for every response to be served and return the recorded requests.
+     */
+    pub fn finish(self) -> Vec<RecordedRequest> {
+        self.handle.join().unwrap()
+    }
+}
@@ -0,0 +1,578 @@
+use std::{
+    env, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_NONE_MATCH};
+
+use crate::api::VerifyJobStatus;
+
+/// Header through which the backend advertises long polling, with the longest
+/// wait in seconds it accepts.
+pub const LONG_POLL_HEADER: &str = "x-long-poll-max-wait";
+
+/// Timeout of every status request, including held ones.
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Longest a single long-poll request may be held, kept below `HTTP_TIMEOUT`
+/// so that a held request never times out on our side.
+pub const MAX_LONG_POLL_WAIT: Duration = Duration::from_secs(30);
+
+/// Interval between polls, unless the backend supports long polling.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Intervals `USE_POLLING_MAX_RETRIES` bounds the polling to, without a timeout.
+const LEGACY_MAX_RETRIES: u32 = 180;
+
+/// A wait this many times longer than requested is taken for a system suspend.
+const RESUME_GAP_FACTOR: u32 = 10;
+
+/// Shortest wait taken for a system suspend, so that a slow wake up on a
+/// loaded machine isn't.
+const MIN_RESUME_GAP: Duration = Duration::from_secs(60);
+
+/**
+ * What the backend advertised in its last status response. Without either,
+ * we fall back to polling at a fixed interval.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollNegotiation {
+    /// Sent back as `If-None-Match`, an unchanged state is answered with a 304.
+    pub etag: Option<String>,
+    /// Sent as the `wait` query parameter, the backend holds the request until
+    /// the state changes or the wait expires.
+    pub long_poll_wait: Option<Duration>,
+}
+
+impl PollNegotiation {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        Self::negotiate(header(ETAG.as_str()), header(LONG_POLL_HEADER))
+    }
+
+    pub fn negotiate(etag: Option<&str>, long_poll_max_wait: Option<&str>) -> Self {
+        Self {
+            etag: etag.map(str::to_owned),
+            long_poll_wait: long_poll_max_wait
+                .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| Duration::from_secs(seconds).min(MAX_LONG_POLL_WAIT)),
+        }
+    }
+
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        match self.long_poll_wait {
+            Some(wait) => vec![("wait", format!("{}s", wait.as_secs()))],
+            None => vec![],
+        }
+    }
+
+    /// `If-None-Match` with the etag of the last response, when it had one.
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self
+            .etag
+            .as_deref()
+            .and_then(|etag| HeaderValue::from_str(etag).ok())
+        {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        headers
+    }
+
+    /**
+     * A held request already waited for a change, so there is no need to sleep
+     * before the next one.
+     */
+    pub fn delay(&self, interval: Duration) -> Duration {
+        match self.long_poll_wait {
+            Some(_) => Duration::ZERO,
+            None => interval,
+        }
+    }
+}
+
+/**
+ * Source of time of the polling, replaced in tests.
+ */
+pub trait Clock {
+    /// Monotonic time, for every interval and deadline.
+    fn now(&self) -> Instant;
+    /// Wall-clock time, only for display and suspend detection.
+    fn wall(&self) -> SystemTime;
+    fn sleep(&self, duration: Duration);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/**
+ * Time of day of `time` in UTC, for the logs.
+ */
+pub fn format_wall_clock(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{:02}:{:02}:{:02} UTC",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/**
+ * How often and for how long a job is polled, see `--poll-interval` and
+ * `--poll-timeout`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollingConfig {
+    pub interval: Duration,
+    /// Polls until the job finishes when `None`.
+    pub timeout: Option<Duration>,
+    /// Extra time granted each time the backend restarts the job.
+    pub restart_grace: Duration,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_POLL_INTERVAL,
+            timeout: None,
+            restart_grace: Duration::ZERO,
+        }
+    }
+}
+
+impl PollingConfig {
+    /**
+     * Configuration of the flags, with the environment as a fallback: without
+     * a timeout, `USE_POLLING_MAX_RETRIES=true` still bounds the polling to
+     * 180 intervals, and `POLLING_RESTART_GRACE_RETRIES` grants that many
+     * intervals after each restart of the job.
+     */
+    pub fn from_flags(interval: Option<Duration>, timeout: Option<Duration>) -> Self {
+        let interval = interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+        let legacy_timeout = env::var("USE_POLLING_MAX_RETRIES")
+            .is_ok_and(|value| value.to_lowercase() == "true")
+            .then(|| interval * LEGACY_MAX_RETRIES);
+        let restart_grace = env::var("POLLING_RESTART_GRACE_RETRIES")
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(0);
+        Self {
+            interval,
+            timeout: timeout.or(legacy_timeout),
+            restart_grace: interval * restart_grace,
+        }
+    }
+
+    /**
+     * Polls after the first one that fit in the timeout, for verifiers that
+     * don't keep time.
+     */
+    pub fn max_retries(&self) -> Option<u32> {
+        self.timeout.map(|timeout| {
+            (timeout.as_millis() / self.interval.as_millis().max(1)).min(u32::MAX as u128) as u32
+        })
+    }
+}
+
+/**
+ * Parse a number of seconds given on the command line.
+ */
+pub fn parse_seconds(value: &str) -> Result<Duration, String> {
+    value
+        .parse::<u64>()
+        .map(Duration::from_secs)
+        .map_err(|_| format!("expected a number of seconds, got '{}'", value))
+}
+
+/**
+ * Parse `--poll-interval`, a zero interval would hammer the backend.
+ */
+pub fn parse_poll_interval(value: &str) -> Result<Duration, String> {
+    let interval = parse_seconds(value)?;
+    if interval.is_zero() {
+        return Err("the poll interval must be at least 1 second".to_owned());
+    }
+    Ok(interval)
+}
+
+/**
+ * Keeps the polls of a job within its timeout. A wait far longer than
+ * requested means that the system was suspended: the job is checked again
+ * right away and the time spent suspended isn't counted.
+ */
+pub struct PollSchedule<'a, C: Clock + ?Sized> {
+    clock: &'a C,
+    started: Instant,
+    deadline: Option<Instant>,
+    retries: u32,
+}
+
+impl<'a, C: Clock + ?Sized> PollSchedule<'a, C> {
+    pub fn new(clock: &'a C, timeout: Option<Duration>) -> Self {
+        let started = clock.now();
+        Self {
+            clock,
+            started,
+            deadline: timeout.map(|timeout| started + timeout),
+            retries: 0,
+        }
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// How long the job has been polled, for the timeout error.
+    pub fn waited(&self) -> Duration {
+        self.clock.now().duration_since(self.started)
+    }
+
+    /// Allow polling for `time` longer, e.g. after a restart of the job.
+    pub fn extend(&mut self, time: Duration) {
+        self.deadline = self.deadline.map(|deadline| deadline + time);
+    }
+
+    /**
+     * Wait `delay` before the next poll, or return false when the next poll
+     * would be past the timeout.
+     */
+    pub fn next(&mut self, delay: Duration) -> bool {
+        let Some(started) = self.start_wait(delay) else {
+            return false;
+        };
+        self.clock.sleep(delay);
+        self.end_wait(started, delay);
+        true
+    }
+
+    /**
+     * `next` for async callers, which sleep on the runtime rather than
+     * blocking its thread, and stop sleeping once `cancel` is cancelled. The
+     * clock still tells the time.
+     */
+    pub async fn next_async(
+        &mut self,
+        delay: Duration,
+        cancel: &crate::cancel::CancelToken,
+    ) -> bool {
+        let Some(started) = self.start_wait(delay) else {
+            return false;
+        };
+        cancel.wait_async(delay).await;
+        self.end_wait(started, delay);
+        true
+    }
+
+    /**
+     * When the wait for the next poll starts, or `None` when the next poll
+     * would be past the timeout.
+     */
+    fn start_wait(&mut self, delay: Duration) -> Option<(Instant, SystemTime)> {
+        let started = self.clock.now();
+        if self
+            .deadline
+            .is_some_and(|deadline| started + delay > deadline)
+        {
+            return None;
+        }
+        self.retries += 1;
+        Some((started, self.clock.wall()))
+    }
+
+    /**
+     * Give back the time suspended if the wait for `delay` took far longer.
+     * The monotonic clock may stop while suspended, so the wall clock is
+     * checked too, a wall clock set forward merely costs an extra poll.
+     */
+    fn end_wait(&mut self, (started, wall_started): (Instant, SystemTime), delay: Duration) {
+        let slept = self.clock.now().duration_since(started);
+        let waited = slept.max(
+            self.clock
+                .wall()
+                .duration_since(wall_started)
+                .unwrap_or_default(),
+        );
+        if waited > (delay * RESUME_GAP_FACTOR).max(MIN_RESUME_GAP) {
+            println!(
+                "Resumed at {} after {}s without polling, the system was likely suspended. Checking the job again.",
+                format_wall_clock(self.clock.wall()),
+                waited.as_secs()
+            );
+            // Only what the monotonic clock counted of the suspend is given back
+            self.extend(slept.saturating_sub(delay));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Initial,
+    Unchanged,
+    Advanced,
+    /// The job went back to an earlier state, i.e. it was restarted by the backend.
+    Regressed,
+}
+
+fn progress(status: VerifyJobStatus) -> u8 {
+    match status {
+        VerifyJobStatus::Submitted => 0,
+        VerifyJobStatus::Compiled => 1,
+        VerifyJobStatus::CompileFailed | VerifyJobStatus::Fail | VerifyJobStatus::Success => 2,
+    }
+}
+
+/**
+ * Classifies the successive statuses of a job and counts its restarts.
+ */
+#[derive(Debug, Default)]
+pub struct StatusTracker {
+    last: Option<VerifyJobStatus>,
+    restarts: u32,
+}
+
+impl StatusTracker {
+    pub fn observe(&mut self, status: VerifyJobStatus) -> Transition {
+        let transition = match self.last {
+            None => Transition::Initial,
+            Some(last) if last == status => Transition::Unchanged,
+            Some(last) if progress(status) < progress(last) => Transition::Regressed,
+            Some(_) => Transition::Advanced,
+        };
+        if transition == Transition::Regressed {
+            self.restarts += 1;
+        }
+        self.last = Some(status);
+        transition
+    }
+
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn test_status_tracker_transitions() {
+        let mut tracker = StatusTracker::default();
+        let transitions: Vec<Transition> = [
+            VerifyJobStatus::Submitted,
+            VerifyJobStatus::Submitted,
+            VerifyJobStatus::Compiled,
+            VerifyJobStatus::Compiled,
+            VerifyJobStatus::Success,
+        ]
+        .into_iter()
+        .map(|status| tracker.observe(status))
+        .collect();
+
+        assert_eq!(
+            transitions,
+            vec![
+                Transition::Initial,
+                Transition::Unchanged,
+                Transition::Advanced,
+                Transition::Unchanged,
+                Transition::Advanced,
+            ]
+        );
+        assert_eq!(tracker.restarts(), 0);
+    }
+
+    #[test]
+    fn test_status_tracker_counts_restarts() {
+        let mut tracker = StatusTracker::default();
+        tracker.observe(VerifyJobStatus::Submitted);
+        tracker.observe(VerifyJobStatus::Compiled);
+        assert_eq!(
+            tracker.observe(VerifyJobStatus::Submitted),
+            Transition::Regressed
+        );
+        // Repeating the regressed status is not another restart
+        assert_eq!(
+            tracker.observe(VerifyJobStatus::Submitted),
+            Transition::Unchanged
+        );
+        tracker.observe(VerifyJobStatus::Compiled);
+        assert_eq!(
+            tracker.observe(VerifyJobStatus::Submitted),
+            Transition::Regressed
+        );
+        assert_eq!(tracker.restarts(), 2);
+    }
+
+    #[test]
+    fn test_negotiation_fallback() {
+        let negotiation = PollNegotiation::negotiate(None, None);
+        assert_eq!(negotiation, PollNegotiation::default());
+        assert!(negotiation.query().is_empty());
+        assert_eq!(
+            negotiation.delay(Duration::from_secs(5)),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_long_poll_wait_is_capped() {
+        let negotiation = PollNegotiation::negotiate(Some("\"v1\""), Some("120"));
+        assert_eq!(negotiation.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(negotiation.long_poll_wait, Some(MAX_LONG_POLL_WAIT));
+        assert!(MAX_LONG_POLL_WAIT < HTTP_TIMEOUT);
+        assert_eq!(negotiation.query(), vec![("wait", "30s".to_owned())]);
+        assert_eq!(negotiation.delay(Duration::from_secs(5)), Duration::ZERO);
+
+        assert_eq!(
+            PollNegotiation::negotiate(None, Some("garbage")).long_poll_wait,
+            None
+        );
+    }
+
+    /**
+     * Time advancing only when slept, with suspends of the given lengths on
+     * the first sleeps. `monotonic_stops` models platforms where the
+     * monotonic clock doesn't advance while suspended.
+     */
+    struct FakeClock {
+        start: Instant,
+        elapsed: Cell<Duration>,
+        wall_elapsed: Cell<Duration>,
+        suspends: RefCell<Vec<Duration>>,
+        monotonic_stops: bool,
+    }
+
+    impl FakeClock {
+        fn new(suspends: Vec<Duration>, monotonic_stops: bool) -> Self {
+            Self {
+                start: Instant::now(),
+                elapsed: Cell::default(),
+                wall_elapsed: Cell::default(),
+                suspends: RefCell::new(suspends),
+                monotonic_stops,
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed.get()
+        }
+
+        fn wall(&self) -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000) + self.wall_elapsed.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            let mut suspends = self.suspends.borrow_mut();
+            let suspended = match suspends.is_empty() {
+                true => Duration::ZERO,
+                false => suspends.remove(0),
+            };
+            let monotonic = match self.monotonic_stops {
+                true => duration,
+                false => duration + suspended,
+            };
+            self.elapsed.set(self.elapsed.get() + monotonic);
+            self.wall_elapsed
+                .set(self.wall_elapsed.get() + duration + suspended);
+        }
+    }
+
+    fn polls(clock: &FakeClock, timeout: Duration) -> u32 {
+        let mut schedule = PollSchedule::new(clock, Some(timeout));
+        let mut polls = 1;
+        while schedule.next(Duration::from_secs(5)) {
+            polls += 1;
+        }
+        polls
+    }
+
+    #[test]
+    fn test_schedule_stops_at_the_timeout() {
+        let clock = FakeClock::new(vec![], false);
+        assert_eq!(polls(&clock, Duration::from_secs(15)), 4);
+        assert_eq!(clock.now() - clock.start, Duration::from_secs(15));
+
+        // A slow machine isn't suspended, the slow wait counts
+        let clock = FakeClock::new(vec![Duration::from_secs(30)], false);
+        assert_eq!(polls(&clock, Duration::from_secs(15)), 2);
+    }
+
+    #[test]
+    fn test_suspend_isnt_counted_against_the_timeout() {
+        for monotonic_stops in [false, true] {
+            let clock = FakeClock::new(vec![Duration::from_secs(3600)], monotonic_stops);
+            assert_eq!(
+                polls(&clock, Duration::from_secs(15)),
+                4,
+                "monotonic stops: {}",
+                monotonic_stops
+            );
+        }
+    }
+
+    #[test]
+    fn test_unbounded_schedule_and_grace() {
+        let clock = FakeClock::new(vec![], false);
+        let mut schedule = PollSchedule::new(&clock, None);
+        assert!((0..100).all(|_| schedule.next(Duration::ZERO)));
+
+        let clock = FakeClock::new(vec![], false);
+        let mut schedule = PollSchedule::new(&clock, Some(Duration::ZERO));
+        schedule.extend(Duration::from_secs(5));
+        assert!(schedule.next(Duration::from_secs(5)));
+        assert!(!schedule.next(Duration::from_secs(5)));
+        assert_eq!(schedule.retries(), 1);
+        assert_eq!(schedule.waited(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_poll_interval_must_be_positive() {
+        assert_eq!(parse_poll_interval("10"), Ok(Duration::from_secs(10)));
+        assert!(parse_poll_interval("0")
+            .unwrap_err()
+            .contains("at least 1 second"));
+        assert!(parse_poll_interval("5s").is_err());
+        assert_eq!(parse_seconds("0"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_max_retries_fit_in_the_timeout() {
+        let config = PollingConfig {
+            interval: Duration::from_secs(5),
+            timeout: Some(Duration::from_secs(12)),
+            restart_grace: Duration::ZERO,
+        };
+        assert_eq!(config.max_retries(), Some(2));
+        assert_eq!(PollingConfig::default().max_retries(), None);
+    }
+
+    #[test]
+    fn test_format_wall_clock() {
+        assert_eq!(
+            format_wall_clock(UNIX_EPOCH + Duration::from_secs(86_400 + 3_723)),
+            "01:02:03 UTC"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::display::sanitize;
+
+/// Classes declared longer ago than this are unusual targets for a first verification.
+pub const STALE_DECLARATION_DAYS: u64 = 365;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/**
+ * When the class was declared, as far as the class endpoint says.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Declaration {
+    pub block_number: Option<u64>,
+    /// Unix time of the declaring block, in seconds.
+    pub timestamp: Option<u64>,
+}
+
+/**
+ * Extract the declaring block from a class returned by the class endpoint.
+ * Returns `None` when the backend doesn't expose it.
+ */
+pub fn declaration_from_class(class: &Value) -> Option<Declaration> {
+    let field = |keys: [&str; 2]| {
+        keys.iter()
+            .find_map(|key| class.get(key).and_then(Value::as_u64))
+    };
+    let declaration = Declaration {
+        block_number: field(["blockNumber", "block_number"]),
+        timestamp: field(["timestamp", "declared_at"]),
+    };
+    (declaration != Declaration::default()).then_some(declaration)
+}
+
+/**
+ * What is about to be marked verified, shown before dispatching so that the
+ * sources don't end up on the wrong class.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Preflight {
+    pub class_hash: String,
+    pub network: String,
+    pub name: String,
+    pub declaration: Option<Declaration>,
+    /// Pinned with --expect-hash.
+    pub expected_hash: Option<String>,
+    /// Name the class is already verified under.
+    pub verified_name: Option<String>,
+}
+
+fn days_between(timestamp: u64, now: SystemTime) -> u64 {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+    now.saturating_sub(timestamp) / SECONDS_PER_DAY
+}
+
+impl Preflight {
+    /// Days since the class was declared, when the backend gave its timestamp.
+    pub fn declared_days_ago(&self, now: SystemTime) -> Option<u64> {
+        let timestamp = self.declaration?.timestamp?;
+        Some(days_between(timestamp, now))
+    }
+
+    /**
+     * Reasons to double check the class hash, worth more than the usual prompt.
+     */
+    pub fn escalations(&self, now: SystemTime) -> Vec<String> {
+        let mut escalations = vec![];
+        if let Some(days) = self.declared_days_ago(now) {
+            if days > STALE_DECLARATION_DAYS {
+                escalations.push(format!(
+                    "The class was declared {} days ago, make sure it is the class these sources were built into.",
+                    days
+                ));
+            }
+        }
+        if let Some(verified_name) = self
+            .verified_name
+            .as_ref()
+            .filter(|verified_name| **verified_name != self.name)
+        {
+            escalations.push(format!(
+                "The class is already verified as {}, not {}.",
+                sanitize(verified_name),
+                sanitize(&self.name)
+            ));
+        }
+        if let Some(expected) = self
+            .expected_hash
+            .as_ref()
+            .filter(|expected| **expected != self.class_hash)
+        {
+            escalations.push(format!(
+                "--expect-hash pins {}, not the class being verified.",
+                expected
+            ));
+        }
+        escalations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    const NOW: u64 = 1_760_000_000;
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(NOW)
+    }
+
+    fn preflight() -> Preflight {
+        Preflight {
+            class_hash: "0x1234".to_owned(),
+            network: "Mainnet".to_owned(),
+            name: "MyToken".to_owned(),
+            declaration: Some(Declaration {
+                block_number: Some(812_345),
+                timestamp: Some(NOW - 3 * SECONDS_PER_DAY),
+            }),
+            expected_hash: None,
+            verified_name: None,
+        }
+    }
+
+    #[test]
+    fn test_declaration_from_class() {
+        assert_eq!(
+            declaration_from_class(&json!({"blockNumber": 812345, "timestamp": 1700000000})),
+            Some(Declaration {
+                block_number: Some(812_345),
+                timestamp: Some(1_700_000_000),
+            })
+        );
+        assert_eq!(
+            declaration_from_class(&json!({"block_number": 7})),
+            Some(Declaration {
+                block_number: Some(7),
+                timestamp: None,
+            })
+        );
+        assert_eq!(declaration_from_class(&json!({"abi": []})), None);
+    }
+
+    #[test]
+    fn test_recent_unverified_class_is_not_escalated() {
+        let preflight = preflight();
+        assert!(preflight.escalations(now()).is_empty());
+        assert_eq!(preflight.declared_days_ago(now()), Some(3));
+    }
+
+    #[test]
+    fn test_suspicious_targets_are_escalated() {
+        let suspicious = Preflight {
+            declaration: Some(Declaration {
+                block_number: None,
+                timestamp: Some(NOW - 400 * SECONDS_PER_DAY),
+            }),
+            verified_name: Some("OtherToken".to_owned()),
+            expected_hash: Some("0x5678".to_owned()),
+            ..preflight()
+        };
+        let escalations = suspicious.escalations(now());
+        assert_eq!(escalations.len(), 3);
+        assert!(escalations[0].contains("400 days ago"));
+        assert!(escalations[1].contains("OtherToken"));
+
+        let same_name = Preflight {
+            verified_name: Some("MyToken".to_owned()),
+            ..preflight()
+        };
+        assert!(same_name.escalations(now()).is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use std::env;
+
+use anyhow::Result;
+
+use crate::errors::{verifier_error, VerifierError};
+
+/// Failure descriptions after which resubmitting the identical payload is
+/// known to succeed.
+pub const DEFAULT_TRANSIENT_FAILURES: [&str; 2] = ["worker timeout", "artifact store unavailable"];
+
+/**
+ * Patterns identifying transient backend failures, matched case-insensitively
+ * against the failure description. `VOYAGER_TRANSIENT_FAILURES` replaces the
+ * defaults with a comma separated list.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransientFailures {
+    patterns: Vec<String>,
+}
+
+impl Default for TransientFailures {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRANSIENT_FAILURES)
+    }
+}
+
+impl TransientFailures {
+    pub fn new<S: AsRef<str>>(patterns: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        match env::var("VOYAGER_TRANSIENT_FAILURES") {
+            Ok(patterns) => Self::new(patterns.split(',')),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /**
+     * Whether the job failed for a transient reason. Only failures reported
+     * by the backend for a completed job qualify, compilation failures and
+     * request errors never do.
+     */
+    pub fn matches(&self, error: &anyhow::Error) -> bool {
+        match verifier_error(error) {
+            Some(VerifierError::VerificationFailed(description)) => {
+                self.matches_description(description)
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * Whether a failure description, e.g. of a previous job, is transient.
+     */
+    pub fn matches_description(&self, description: &str) -> bool {
+        let description = description.to_lowercase();
+        self.patterns
+            .iter()
+            .any(|pattern| description.contains(pattern.as_str()))
+    }
+}
+
+/**
+ * Run `attempt` (given its 1-based number) and run it again after each
+ * transient failure, at most `max_resubmissions` times. `on_resubmit` is
+ * given the number and error of each attempt about to be run again.
+ */
+pub fn with_resubmission<T>(
+    max_resubmissions: u32,
+    transient_failures: &TransientFailures,
+    on_resubmit: &mut dyn FnMut(u32, &anyhow::Error),
+    mut attempt: impl FnMut(u32) -> Result<T>,
+) -> Result<T> {
+    let mut number = 1;
+    loop {
+        match attempt(number) {
+            Err(e) if number <= max_resubmissions && transient_failures.matches(&e) => {
+                on_resubmit(number, &e);
+                number += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn failure(description: &str) -> anyhow::Error {
+        anyhow::Error::new(VerifierError::VerificationFailed(description.to_owned()))
+            .context("Error while polling verification status")
+    }
+
+    #[test]
+    fn test_transient_failure_patterns() {
+        let transient = TransientFailures::default();
+        assert!(transient.matches(&failure("Worker timeout after 600s")));
+        assert!(transient.matches(&failure("artifact store unavailable")));
+        assert!(!transient.matches(&failure("Class hash mismatch")));
+        assert!(!transient.matches(&anyhow!("worker timeout")));
+        assert!(
+            !transient.matches(&anyhow::Error::new(VerifierError::CompilationFailed(
+                "worker timeout".to_owned()
+            )))
+        );
+
+        let configured = TransientFailures::new("queue full, ".split(','));
+        assert!(configured.matches(&failure("Queue full")));
+        assert!(!configured.matches(&failure("worker timeout")));
+    }
+
+    #[test]
+    fn test_resubmission_is_opt_in() {
+        let mut attempts = vec![];
+        let result: Result<()> =
+            with_resubmission(0, &TransientFailures::default(), &mut |_, _| {}, |n| {
+                attempts.push(n);
+                Err(failure("worker timeout"))
+            });
+        assert!(result.is_err());
+        assert_eq!(attempts, vec![1]);
+    }
+}
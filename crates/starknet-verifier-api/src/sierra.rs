@@ -0,0 +1,89 @@
+use dyn_compiler::dyn_compiler::{
+    cairo_versions_emitting, format_version, SierraVersion, SupportedCairoVersions,
+};
+use serde_json::Value;
+
+/**
+ * Sierra version of a class returned by the class endpoint, encoded in the
+ * first three felts of its `sierra_program`. Returns `None` when the backend
+ * doesn't return the program.
+ */
+pub fn sierra_version_from_class(class: &Value) -> Option<SierraVersion> {
+    let program = class.get("sierra_program")?.as_array()?;
+    let felt = |index: usize| -> Option<u32> {
+        let felt = program.get(index)?.as_str()?;
+        u32::from_str_radix(felt.strip_prefix("0x")?, 16).ok()
+    };
+    Some((felt(0)?, felt(1)?, felt(2)?))
+}
+
+/**
+ * Explain why the class hash can't match when the class was declared with
+ * another Sierra version than `compiler` emits, and which compilers to use
+ * instead. Returns `None` when the versions match.
+ */
+pub fn diagnose_sierra_mismatch(
+    declared: SierraVersion,
+    compiler: SupportedCairoVersions,
+) -> Option<String> {
+    let emitted = compiler.sierra_version();
+    if declared == emitted {
+        return None;
+    }
+    let suggestion = match cairo_versions_emitting(declared) {
+        Some((first, Some(next))) => format!(
+            "verify with a Cairo version from {} and before {} instead",
+            format_version(first),
+            format_version(next)
+        ),
+        Some((first, None)) => format!(
+            "verify with Cairo {} or newer instead",
+            format_version(first)
+        ),
+        None if declared > emitted => "verify with a newer Cairo version instead".to_owned(),
+        None => "verify with an older Cairo version instead".to_owned(),
+    };
+    Some(format!(
+        "The class was declared with Sierra {}, but Cairo {} emits Sierra {}, so the class hash won't match: {}.",
+        format_version(declared),
+        compiler.to_string(),
+        format_version(emitted),
+        suggestion
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sierra_version_from_class() {
+        let class = json!({"sierra_program": ["0x1", "0x6", "0x0", "0x2", "0x8", "0x4", "0xa"]});
+        assert_eq!(sierra_version_from_class(&class), Some((1, 6, 0)));
+
+        assert_eq!(sierra_version_from_class(&json!({"abi": []})), None);
+        assert_eq!(
+            sierra_version_from_class(&json!({"sierra_program": ["0x1"]})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diagnose_sierra_mismatch() {
+        let compiler = SupportedCairoVersions::V2_8_4;
+        assert_eq!(diagnose_sierra_mismatch((1, 6, 0), compiler), None);
+
+        let older = diagnose_sierra_mismatch((1, 4, 0), compiler).unwrap();
+        assert!(older.starts_with(
+            "The class was declared with Sierra 1.4.0, but Cairo 2.8.4 emits Sierra 1.6.0"
+        ));
+        assert!(older.ends_with("from 2.4.0 and before 2.6.0 instead."));
+
+        let newer = diagnose_sierra_mismatch((1, 7, 0), compiler).unwrap();
+        assert!(newer.ends_with("verify with Cairo 2.10.0 or newer instead."));
+
+        let unknown = diagnose_sierra_mismatch((1, 9, 0), compiler).unwrap();
+        assert!(unknown.ends_with("verify with a newer Cairo version instead."));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8Path;
+
+use crate::api::FileInfo;
+use crate::form::validate_file_name;
+
+/**
+ * Files submitted for verification, held in memory as their path relative to
+ * the root of the upload and their contents. Sources collected from disk are
+ * read into one with `from_files`, generated sources can be added directly,
+ * and both go through the same validation from there on.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceSet {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl SourceSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Add a file under `path`, which follows the same rules as collected
+     * files, see `validate_file_name`.
+     */
+    pub fn add(&mut self, path: impl Into<String>, contents: impl Into<Vec<u8>>) -> Result<()> {
+        let path = path.into();
+        validate_file_name(&path)?;
+        if self.contents(&path).is_some() {
+            return Err(anyhow!("{} is part of the sources more than once", path));
+        }
+        self.files.push((path, contents.into()));
+        Ok(())
+    }
+
+    pub fn from_files(files: &[FileInfo]) -> Result<Self> {
+        Self::from_files_with(files, &mut |_, _| {})
+    }
+
+    /**
+     * `from_files`, telling `on_read` how long reading each file took.
+     */
+    pub fn from_files_with(
+        files: &[FileInfo],
+        on_read: &mut dyn FnMut(&FileInfo, Duration),
+    ) -> Result<Self> {
+        let mut sources = Self::new();
+        for file in files.iter() {
+            let read_started = Instant::now();
+            let contents = fs::read(&file.path)
+                .with_context(|| format!("Unable to read {}", file.path.display()))?;
+            on_read(file, read_started.elapsed());
+            sources.add(file.name.clone(), contents)?;
+        }
+        Ok(sources)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files
+            .iter()
+            .map(|(path, contents)| (path.as_str(), contents.as_slice()))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(path, _)| path)
+    }
+
+    /**
+     * Keep only the files whose path `keep` accepts.
+     */
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.files.retain(|(path, _)| keep(path));
+    }
+
+    pub fn contents(&self, path: &str) -> Option<&[u8]> {
+        self.iter()
+            .find(|(name, _)| *name == path)
+            .map(|(_, contents)| contents)
+    }
+
+    /**
+     * The files as sent in the form, byte for byte.
+     */
+    pub fn to_files(&self) -> Vec<(String, Vec<u8>)> {
+        self.iter()
+            .map(|(path, contents)| (path.to_owned(), contents.to_vec()))
+            .collect()
+    }
+
+    /**
+     * Write the files under `dir`, for tools that need them on disk such as a
+     * local compilation before submitting.
+     */
+    #[allow(dead_code)]
+    pub fn materialize(&self, dir: &Utf8Path) -> Result<Vec<FileInfo>> {
+        self.iter()
+            .map(|(path, contents)| {
+                let destination = dir.join(path);
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Unable to create {}", parent))?;
+                }
+                fs::write(&destination, contents)
+                    .with_context(|| format!("Unable to write {}", destination))?;
+                Ok(FileInfo {
+                    name: path.to_owned(),
+                    path: destination.into_std_path_buf(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use std::env;
+
+    #[test]
+    fn test_materialized_sources_read_back_identically() {
+        let mut sources = SourceSet::new();
+        sources
+            .add("my_token/Scarb.toml", "[package]\nname = \"my_token\"\n")
+            .unwrap();
+        sources
+            .add("my_token/src/lib.cairo", "mod token;\n")
+            .unwrap();
+
+        let dir = Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("voyager-sources-{}", std::process::id())),
+        )
+        .unwrap();
+        let files = sources.materialize(&dir).unwrap();
+        assert_eq!(SourceSet::from_files(&files).unwrap(), sources);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sources_are_validated() {
+        let mut sources = SourceSet::new();
+        sources.add("src/lib.cairo", "").unwrap();
+        assert!(sources.add("src/lib.cairo", "").is_err());
+        assert!(sources.add("src\\lib.cairo", "").is_err());
+
+        // Kept byte for byte, only the legacy form style needs UTF-8
+        sources.add("src/binary.cairo", vec![0xff, 0xfe]).unwrap();
+        assert_eq!(sources.to_files()[1].1, [0xff, 0xfe]);
+    }
+
+    #[test]
+    fn test_missing_file_is_named() {
+        let missing = env::temp_dir().join(format!("voyager-missing-{}", std::process::id()));
+        let error = SourceSet::from_files(&[FileInfo {
+            name: "my_token/src/lib.cairo".to_owned(),
+            path: missing.clone(),
+        }])
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&format!("Unable to read {}", missing.display())));
+    }
+}
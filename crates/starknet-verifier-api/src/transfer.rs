@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Result;
+use reqwest::blocking::Response;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::humanize::{format_count, format_size};
+use crate::ip_version::family;
+
+pub const CLASS_ENDPOINT: &str = "class";
+pub const VERIFY_ENDPOINT: &str = "verify";
+pub const JOB_STATUS_ENDPOINT: &str = "job-status";
+pub const JOBS_ENDPOINT: &str = "jobs";
+pub const DISCOVERY_ENDPOINT: &str = "discovery";
+pub const CAPABILITIES_ENDPOINT: &str = "capabilities";
+pub const DEPENDENCIES_ENDPOINT: &str = "dependencies";
+pub const CORRECTIONS_ENDPOINT: &str = "corrections";
+pub const RPC_ENDPOINT: &str = "rpc";
+
+/**
+ * Traffic exchanged with one endpoint. Bytes are body bytes: request bodies
+ * are counted as the transport reads them, response bodies as we read them.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TransferStats {
+    pub requests: u64,
+    /// Requests repeating an earlier one, i.e. status polls after the first.
+    pub retries: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl AddAssign for TransferStats {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.retries += other.retries;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+}
+
+/**
+ * Traffic per endpoint for the lifetime of the process, see `global`.
+ */
+#[derive(Debug, Default)]
+pub struct TransferLog {
+    endpoints: Mutex<BTreeMap<&'static str, TransferStats>>,
+    /// Print each response with the address it came from, from --debug-http.
+    debug: AtomicBool,
+}
+
+static GLOBAL: TransferLog = TransferLog {
+    endpoints: Mutex::new(BTreeMap::new()),
+    debug: AtomicBool::new(false),
+};
+
+pub fn global() -> &'static TransferLog {
+    &GLOBAL
+}
+
+impl TransferLog {
+    fn update(&self, endpoint: &'static str, f: impl FnOnce(&mut TransferStats)) {
+        if let Ok(mut endpoints) = self.endpoints.lock() {
+            f(endpoints.entry(endpoint).or_default());
+        }
+    }
+
+    pub fn record_request(&self, endpoint: &'static str, retry: bool) {
+        self.update(endpoint, |stats| {
+            stats.requests += 1;
+            stats.retries += retry as u64;
+        });
+    }
+
+    pub fn record_sent(&self, endpoint: &'static str, bytes: u64) {
+        self.update(endpoint, |stats| stats.bytes_sent += bytes);
+    }
+
+    pub fn set_debug(&self, debug: bool) {
+        self.debug.store(debug, Ordering::Relaxed);
+    }
+
+    fn debug_response(&self, endpoint: &str, status: u16, remote: Option<SocketAddr>) {
+        if self.debug.load(Ordering::Relaxed) {
+            eprintln!("{}", describe_response(endpoint, status, remote));
+        }
+    }
+
+    /**
+     * Read the whole response body, counting it as received.
+     */
+    pub fn read_body(&self, endpoint: &'static str, response: Response) -> Result<Vec<u8>> {
+        self.debug_response(endpoint, response.status().as_u16(), response.remote_addr());
+        let body = response.bytes()?.to_vec();
+        self.update(endpoint, |stats| stats.bytes_received += body.len() as u64);
+        Ok(body)
+    }
+
+    /**
+     * `read_body` for a response of the async client.
+     */
+    pub async fn read_body_async(
+        &self,
+        endpoint: &'static str,
+        response: reqwest::Response,
+    ) -> Result<Vec<u8>> {
+        self.debug_response(endpoint, response.status().as_u16(), response.remote_addr());
+        let body = response.bytes().await?.to_vec();
+        self.update(endpoint, |stats| stats.bytes_received += body.len() as u64);
+        Ok(body)
+    }
+
+    #[cfg(test)]
+    pub fn endpoint(&self, endpoint: &str) -> TransferStats {
+        self.endpoints
+            .lock()
+            .ok()
+            .and_then(|endpoints| endpoints.get(endpoint).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn totals(&self) -> TransferStats {
+        let mut totals = TransferStats::default();
+        if let Ok(endpoints) = self.endpoints.lock() {
+            for stats in endpoints.values() {
+                totals += *stats;
+            }
+        }
+        totals
+    }
+
+    pub fn summary(&self) -> String {
+        let totals = self.totals();
+        format!(
+            "Network: {} requests ({} retries), {} sent, {} received",
+            format_count(totals.requests),
+            format_count(totals.retries),
+            format_size(totals.bytes_sent),
+            format_size(totals.bytes_received)
+        )
+    }
+}
+
+/**
+ * Line of --debug-http for a response, with the address family the connection
+ * ended up using.
+ */
+pub fn describe_response(endpoint: &str, status: u16, remote: Option<SocketAddr>) -> String {
+    match remote {
+        Some(remote) => format!(
+            "http: {} {} from {} over {}",
+            endpoint,
+            status,
+            remote,
+            family(&remote)
+        ),
+        None => format!("http: {} {}", endpoint, status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals_sum_the_endpoints() {
+        let log: &'static TransferLog = Box::leak(Box::default());
+        log.record_sent("verify", 60);
+        assert_eq!(log.endpoint("verify").bytes_sent, 60);
+
+        log.record_request("verify", false);
+        log.record_request("job-status", true);
+        let totals = log.totals();
+        assert_eq!((totals.requests, totals.retries), (2, 1));
+        assert!(log
+            .summary()
+            .starts_with("Network: 2 requests (1 retries), 60 B sent"));
+    }
+
+    #[test]
+    fn test_debug_line_names_the_address_family() {
+        assert_eq!(
+            describe_response("class", 200, Some("[2001:db8::1]:443".parse().unwrap())),
+            "http: class 200 from [2001:db8::1]:443 over IPv6"
+        );
+        assert_eq!(
+            describe_response("verify", 503, Some("1.2.3.4:443".parse().unwrap())),
+            "http: verify 503 from 1.2.3.4:443 over IPv4"
+        );
+        assert_eq!(describe_response("verify", 200, None), "http: verify 200");
+    }
+}
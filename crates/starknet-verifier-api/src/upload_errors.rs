@@ -0,0 +1,104 @@
+use crate::display::sanitize;
+
+/**
+ * A single file rejected by the backend, as listed in the body of a 400 response.
+ */
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RejectedFile {
+    #[serde(alias = "name", alias = "path")]
+    pub file: String,
+    pub reason: String,
+}
+
+/**
+ * Map the reason given by the backend to something the user can act upon locally.
+ */
+pub fn remediation_hint(reason: &str) -> &'static str {
+    let reason = reason.to_lowercase();
+    if reason.contains("large") || reason.contains("size") {
+        "Shrink the file or exclude it from the verified sources"
+    } else if reason.contains("extension") || reason.contains("type") {
+        "Exclude the file, only Cairo sources and Scarb manifests are needed"
+    } else if reason.contains("path") || reason.contains("name") {
+        "Rename the file or move it inside the package directory"
+    } else {
+        "Exclude the file from the verified sources"
+    }
+}
+
+/**
+ * One line per rejected file, with its reason and a hint.
+ */
+pub fn format_rejected_files(files: &[RejectedFile]) -> String {
+    files
+        .iter()
+        .map(|file| {
+            format!(
+                "  {}: {}. {}",
+                sanitize(&file.file),
+                sanitize(&file.reason),
+                remediation_hint(&file.reason)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejected_files_deserialization() {
+        let files: Vec<RejectedFile> = serde_json::from_str(
+            r#"[
+                {"file": "my_project/target/big.json", "reason": "file too large"},
+                {"name": "my_project/src/lib.rs", "reason": "invalid_extension"},
+                {"path": "../etc/passwd", "reason": "path rejected"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[1].file, "my_project/src/lib.rs");
+        assert_eq!(files[2].file, "../etc/passwd");
+    }
+
+    #[test]
+    fn test_remediation_hints() {
+        assert!(remediation_hint("File too large").starts_with("Shrink"));
+        assert!(remediation_hint("invalid_extension").contains("only Cairo sources"));
+        assert!(remediation_hint("path rejected").starts_with("Rename"));
+        assert!(remediation_hint("checksum mismatch").starts_with("Exclude"));
+    }
+
+    #[test]
+    fn test_format_rejected_files_lists_every_file() {
+        let listed = format_rejected_files(&[
+            RejectedFile {
+                file: "a.cairo".to_owned(),
+                reason: "file too large".to_owned(),
+            },
+            RejectedFile {
+                file: "b.txt".to_owned(),
+                reason: "bad extension".to_owned(),
+            },
+        ]);
+
+        assert_eq!(
+            listed,
+            "  a.cairo: file too large. Shrink the file or exclude it from the verified sources\n  b.txt: bad extension. Exclude the file, only Cairo sources and Scarb manifests are needed"
+        );
+    }
+
+    #[test]
+    fn test_rejected_file_names_are_sanitized() {
+        let listed = format_rejected_files(&[RejectedFile {
+            file: "src/evil\u{202e}oriac.txt".to_owned(),
+            reason: "bad extension".to_owned(),
+        }]);
+
+        assert!(listed.contains("src/evil\\u{202e}oriac.txt"));
+        assert!(!listed.contains('\u{202e}'));
+    }
+}
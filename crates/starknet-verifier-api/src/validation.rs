@@ -0,0 +1,90 @@
+use regex::Regex;
+
+const CLASS_HASH_PATTERN: &str = r"^0x[a-fA-F0-9]+$";
+/// The Starknet field prime, 2^251 + 17 * 2^192 + 1, field elements are below it.
+const FELT_PRIME_HEX: &str = "800000000000011000000000000000000000000000000000000000000000001";
+pub const MAX_CLASS_NAME_LENGTH: usize = 50;
+
+pub fn is_class_hash_valid(hash: &str) -> bool {
+    let re = Regex::new(CLASS_HASH_PATTERN).unwrap();
+
+    if !re.is_match(hash) {
+        return false;
+    }
+    // Hex digits of the same length compare like the numbers they write
+    let digits = hash[2..].trim_start_matches('0').to_lowercase();
+    (digits.len(), digits.as_str()) < (FELT_PRIME_HEX.len(), FELT_PRIME_HEX)
+}
+
+/**
+ * Rules shared by the class name and its aliases.
+ */
+pub fn validate_class_name(name: &str) -> Result<(), &'static str> {
+    if name.trim().is_empty() {
+        Err("Given name is empty")
+    } else if name.len() > MAX_CLASS_NAME_LENGTH {
+        Err("Given name is too long")
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_name_validation() {
+        assert!(validate_class_name("MyToken").is_ok());
+        assert!(validate_class_name("  ").is_err());
+        assert!(validate_class_name(&"a".repeat(MAX_CLASS_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_valid_class_hash_normalized() {
+        let valid_hash = "0x044dc2b3239382230d8b1e943df23b96f52eebcac93efe6e8bde92f9a2f1da18";
+        assert!(is_class_hash_valid(valid_hash));
+    }
+
+    #[test]
+    fn test_valid_class_hash_without_leading_zeros() {
+        let valid_hash = "0x44dc2b3239382230d8b1e943df23b96f52eebcac93efe6e8bde92f9a2f1da18";
+        assert!(is_class_hash_valid(valid_hash));
+    }
+
+    #[test]
+    fn test_invalid_class_hash_pattern() {
+        let invalid_hash = "0xGHIJKLMNOPQRSTUVWXYZ";
+        assert!(!is_class_hash_valid(invalid_hash));
+    }
+
+    #[test]
+    fn test_invalid_class_hash_above_felt_range() {
+        let invalid_hash = "0x844dc2b3239382230d8b1e943df23b96f52eebcac93efe6e8bde92f9a2f1da18";
+        assert!(!is_class_hash_valid(invalid_hash));
+        assert!(!is_class_hash_valid(&format!("0x{}", "f".repeat(63))));
+        // Zero-padded, but in range once normalized
+        assert!(is_class_hash_valid(&format!("0x{}", "0".repeat(65))));
+        assert!(is_class_hash_valid(&format!("0x000{}", "f".repeat(62))));
+    }
+
+    #[test]
+    fn test_class_hash_below_the_field_prime() {
+        let prime = format!("0x{}", FELT_PRIME_HEX);
+        let below = "0x800000000000011000000000000000000000000000000000000000000000000";
+        assert!(is_class_hash_valid(below));
+        assert!(!is_class_hash_valid(&prime));
+        assert!(!is_class_hash_valid(
+            &prime.to_uppercase().replace("0X", "0x")
+        ));
+        assert!(!is_class_hash_valid(
+            "0x800000000000011000000000000000000000000000000000000000000000002"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_class_hash_no_prefix() {
+        let invalid_hash = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+        assert!(!is_class_hash_valid(invalid_hash));
+    }
+}
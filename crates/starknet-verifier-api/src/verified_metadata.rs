@@ -0,0 +1,207 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/**
+ * Metadata currently displayed by the explorer for an already verified class.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedMetadata {
+    pub name: Option<String>,
+    pub license: Option<String>,
+}
+
+/**
+ * Extract the verified metadata from a class returned by the class endpoint.
+ * Returns `None` when the class isn't verified, or when the backend doesn't
+ * expose whether it is, in which case the metadata check is skipped.
+ */
+pub fn verified_metadata_from_class(class: &Value) -> Option<VerifiedMetadata> {
+    if !class.get("verified")?.as_bool()? {
+        return None;
+    }
+    let field = |key: &str| class.get(key).and_then(Value::as_str).map(str::to_owned);
+    Some(VerifiedMetadata {
+        name: field("name"),
+        license: field("license"),
+    })
+}
+
+/**
+ * Where a class stands, as reported by the class endpoint.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassStatus {
+    Verified,
+    NotVerified,
+    NotDeclared,
+    /// The class is declared but the backend doesn't say whether it is verified.
+    Unknown,
+}
+
+impl std::fmt::Display for ClassStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            ClassStatus::Verified => "verified",
+            ClassStatus::NotVerified => "not verified",
+            ClassStatus::NotDeclared => "not declared",
+            ClassStatus::Unknown => "declared, verification status unknown",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/**
+ * A class as the class endpoint describes it, see `api::get_class_info`. The
+ * metadata is only set for verified classes, and only when the backend
+ * returns it.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassInfo {
+    pub status: ClassStatus,
+    pub name: Option<String>,
+    pub license: Option<String>,
+    /// Cairo version the class was verified with.
+    pub compiler_version: Option<String>,
+}
+
+/**
+ * Describe the class returned by the class endpoint, `None` when it wasn't
+ * found.
+ */
+pub fn class_info_from_class(class: Option<&Value>) -> ClassInfo {
+    let Some(class) = class else {
+        return ClassInfo {
+            status: ClassStatus::NotDeclared,
+            name: None,
+            license: None,
+            compiler_version: None,
+        };
+    };
+    let status = match class.get("verified").and_then(Value::as_bool) {
+        Some(true) => ClassStatus::Verified,
+        Some(false) => ClassStatus::NotVerified,
+        None => ClassStatus::Unknown,
+    };
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| class.get(key).and_then(Value::as_str))
+            .filter(|_| status == ClassStatus::Verified)
+            .map(str::to_owned)
+    };
+    ClassInfo {
+        status,
+        name: field(&["name"]),
+        license: field(&["license"]),
+        compiler_version: field(&["compilerVersion", "compiler_version"]),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MetadataChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: String,
+}
+
+/**
+ * Compare the stored metadata with what is about to be submitted. Fields the
+ * backend didn't return are not reported as changed.
+ */
+pub fn diff_metadata(
+    existing: &VerifiedMetadata,
+    name: &str,
+    license: &str,
+) -> Vec<MetadataChange> {
+    [
+        ("name", &existing.name, name),
+        ("license", &existing.license, license),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| before.as_deref().is_some_and(|before| before != *after))
+    .map(|(field, before, after)| MetadataChange {
+        field: field.to_owned(),
+        before: before.clone(),
+        after: after.to_owned(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_verified_metadata_from_class() {
+        let class = json!({"verified": true, "name": "MyToken", "license": "MIT"});
+        assert_eq!(
+            verified_metadata_from_class(&class),
+            Some(VerifiedMetadata {
+                name: Some("MyToken".to_owned()),
+                license: Some("MIT".to_owned()),
+            })
+        );
+
+        assert_eq!(
+            verified_metadata_from_class(&json!({"verified": false})),
+            None
+        );
+        // Backends without verification info skip the check
+        assert_eq!(verified_metadata_from_class(&json!({"abi": []})), None);
+    }
+
+    #[test]
+    fn test_class_info_from_class() {
+        let class = json!({
+            "verified": true,
+            "name": "MyToken",
+            "license": "MIT",
+            "compilerVersion": "2.8.4",
+        });
+        assert_eq!(
+            class_info_from_class(Some(&class)),
+            ClassInfo {
+                status: ClassStatus::Verified,
+                name: Some("MyToken".to_owned()),
+                license: Some("MIT".to_owned()),
+                compiler_version: Some("2.8.4".to_owned()),
+            }
+        );
+
+        // The name of an unverified class is the one it was declared with, if any
+        let unverified = class_info_from_class(Some(&json!({"verified": false, "name": "x"})));
+        assert_eq!(unverified.status, ClassStatus::NotVerified);
+        assert_eq!(unverified.name, None);
+        assert_eq!(
+            class_info_from_class(Some(&json!({"abi": []}))).status,
+            ClassStatus::Unknown
+        );
+        assert_eq!(class_info_from_class(None).status, ClassStatus::NotDeclared);
+        assert_eq!(
+            serde_json::to_value(ClassStatus::NotDeclared).unwrap(),
+            "not_declared"
+        );
+    }
+
+    #[test]
+    fn test_diff_metadata() {
+        let existing = VerifiedMetadata {
+            name: Some("MyToken".to_owned()),
+            license: None,
+        };
+
+        assert!(diff_metadata(&existing, "MyToken", "MIT").is_empty());
+
+        let changes = diff_metadata(&existing, "MyTokenV2", "MIT");
+        assert_eq!(
+            changes,
+            vec![MetadataChange {
+                field: "name".to_owned(),
+                before: Some("MyToken".to_owned()),
+                after: "MyTokenV2".to_owned(),
+            }]
+        );
+    }
+}
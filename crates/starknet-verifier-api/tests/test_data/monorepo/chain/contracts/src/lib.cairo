@@ -0,0 +1,7 @@
+#[starknet::contract]
+mod counter {
+    #[storage]
+    struct Storage {
+        value: u128,
+    }
+}
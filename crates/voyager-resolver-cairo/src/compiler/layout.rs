@@ -0,0 +1,277 @@
+//! Layout of the packages in the reduced project.
+//!
+//! Every package copied to the output directory (the main package, the other
+//! workspace members and the vendored dependencies) gets its own top-level
+//! directory, and the uploaded file names are prefixed with it. Package names
+//! are only unique per source, so the directory can't simply be the name:
+//! a workspace member and a git dependency may both be called `utils`, and
+//! both have a `src/lib.cairo`.
+//!
+//! Directories are assigned in two passes over the packages ordered by role
+//! (the main package, then the workspace members, then the dependencies), then
+//! by name, source and manifest path, which doesn't depend on the order Scarb
+//! lists them in:
+//!
+//! 1. The first package of each name gets the name itself, so the main package
+//!    always keeps its name, and so does every package without a namesake.
+//! 2. Each remaining package gets `<name>-<n>`, with the smallest `n` from 2
+//!    that no package took yet.
+//!
+//! Directories are compared ignoring ASCII case, so that the layout is the
+//! same on case-insensitive filesystems.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use scarb_metadata::{Metadata, PackageMetadata};
+
+/// Why a package is part of the reduced project, the first ones keep their name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PackageRole {
+    Main,
+    Member,
+    Dependency,
+}
+
+/// A package to lay out, identified by its manifest path.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LayoutPackage {
+    pub role: PackageRole,
+    pub name: String,
+    pub source: String,
+    pub manifest_path: String,
+}
+
+/// The top-level directory of each package, by manifest path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageLayout {
+    directories: BTreeMap<String, String>,
+}
+
+impl PackageLayout {
+    pub fn new(packages: &[LayoutPackage]) -> Self {
+        let mut packages = packages.to_vec();
+        packages.sort();
+
+        let mut directories = BTreeMap::new();
+        let mut taken = HashSet::new();
+        let mut namesakes = vec![];
+        for package in packages {
+            if taken.insert(package.name.to_ascii_lowercase()) {
+                directories.insert(package.manifest_path, package.name);
+            } else {
+                namesakes.push(package);
+            }
+        }
+        for package in namesakes {
+            let directory = (2..)
+                .map(|n| format!("{}-{}", package.name, n))
+                .find(|directory| !taken.contains(&directory.to_ascii_lowercase()))
+                .expect("suffixes are unbounded");
+            taken.insert(directory.to_ascii_lowercase());
+            directories.insert(package.manifest_path, directory);
+        }
+        Self { directories }
+    }
+
+    /// Lay out `packages`, out of the packages of `metadata`, `main` being the
+    /// manifest path of the package to verify.
+    pub fn from_metadata(metadata: &Metadata, packages: &[PackageMetadata], main: &str) -> Self {
+        let packages = packages
+            .iter()
+            .map(|package| LayoutPackage {
+                role: if package.manifest_path.as_str() == main {
+                    PackageRole::Main
+                } else if metadata.workspace.members.contains(&package.id) {
+                    PackageRole::Member
+                } else {
+                    PackageRole::Dependency
+                },
+                name: package.name.clone(),
+                source: package.source.repr.clone(),
+                manifest_path: package.manifest_path.to_string(),
+            })
+            .collect::<Vec<_>>();
+        Self::new(&packages)
+    }
+
+    /// Directory of the package with this manifest path.
+    pub fn directory(&self, manifest_path: &str) -> Option<&str> {
+        self.directories.get(manifest_path).map(String::as_str)
+    }
+
+    /// Directory of each dependency of `package` among `packages`, by the name
+    /// it is declared under. Dependencies sharing a name are told apart by
+    /// their source.
+    pub fn dependency_directories(
+        &self,
+        package: &PackageMetadata,
+        packages: &[PackageMetadata],
+    ) -> HashMap<String, String> {
+        package
+            .dependencies
+            .iter()
+            .filter_map(|dependency| {
+                let candidates = packages
+                    .iter()
+                    .filter(|candidate| candidate.name == dependency.name)
+                    .collect::<Vec<_>>();
+                let resolved = candidates
+                    .iter()
+                    .find(|candidate| candidate.source == dependency.source)
+                    .or(candidates.first())?;
+                let directory = self.directory(resolved.manifest_path.as_str())?;
+                Some((dependency.name.clone(), directory.to_owned()))
+            })
+            .collect()
+    }
+}
+
+/// Directory of a crate of the compilation unit, given the directory of each
+/// crate by name. Crates left out of the layout keep their name.
+pub fn crate_directory<'a>(
+    directories: &'a HashMap<String, String>,
+    crate_name: &'a str,
+) -> &'a str {
+    directories
+        .get(crate_name)
+        .map(String::as_str)
+        .unwrap_or(crate_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(role: PackageRole, name: &str, source: &str, manifest_path: &str) -> LayoutPackage {
+        LayoutPackage {
+            role,
+            name: name.to_owned(),
+            source: source.to_owned(),
+            manifest_path: manifest_path.to_owned(),
+        }
+    }
+
+    fn directories(layout: &PackageLayout) -> Vec<(&str, &str)> {
+        layout
+            .directories
+            .iter()
+            .map(|(manifest_path, directory)| (manifest_path.as_str(), directory.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn should_keep_the_names_of_packages_without_namesakes() {
+        let layout = PackageLayout::new(&[
+            package(
+                PackageRole::Main,
+                "token",
+                "path+file:///ws/token",
+                "/ws/token/Scarb.toml",
+            ),
+            package(
+                PackageRole::Dependency,
+                "math",
+                "path+file:///ws/math",
+                "/ws/math/Scarb.toml",
+            ),
+        ]);
+
+        assert_eq!(layout.directory("/ws/token/Scarb.toml"), Some("token"));
+        assert_eq!(layout.directory("/ws/math/Scarb.toml"), Some("math"));
+    }
+
+    #[test]
+    fn should_suffix_same_named_packages_from_different_sources() {
+        let packages = [
+            package(
+                PackageRole::Dependency,
+                "utils",
+                "git+https://github.com/org/utils.git",
+                "/git/utils/Scarb.toml",
+            ),
+            package(
+                PackageRole::Main,
+                "token",
+                "path+file:///ws/token",
+                "/ws/token/Scarb.toml",
+            ),
+            package(
+                PackageRole::Member,
+                "utils",
+                "path+file:///ws/utils",
+                "/ws/utils/Scarb.toml",
+            ),
+            package(
+                PackageRole::Dependency,
+                "Utils",
+                "path+file:///vendor/utils",
+                "/vendor/utils/Scarb.toml",
+            ),
+        ];
+        let layout = PackageLayout::new(&packages);
+
+        // The workspace member keeps the name, then the dependencies in order
+        assert_eq!(
+            directories(&layout),
+            vec![
+                ("/git/utils/Scarb.toml", "utils-3"),
+                ("/vendor/utils/Scarb.toml", "Utils-2"),
+                ("/ws/token/Scarb.toml", "token"),
+                ("/ws/utils/Scarb.toml", "utils"),
+            ]
+        );
+
+        let mut reversed = packages.to_vec();
+        reversed.reverse();
+        assert_eq!(PackageLayout::new(&reversed), layout);
+    }
+
+    #[test]
+    fn should_not_take_the_name_of_another_package() {
+        let layout = PackageLayout::new(&[
+            package(
+                PackageRole::Main,
+                "a",
+                "path+file:///ws/a",
+                "/ws/a/Scarb.toml",
+            ),
+            package(
+                PackageRole::Dependency,
+                "a",
+                "git+https://a.git",
+                "/git/a/Scarb.toml",
+            ),
+            package(
+                PackageRole::Dependency,
+                "a-2",
+                "path+file:///ws/a-2",
+                "/ws/a-2/Scarb.toml",
+            ),
+        ]);
+
+        assert_eq!(layout.directory("/ws/a/Scarb.toml"), Some("a"));
+        assert_eq!(layout.directory("/ws/a-2/Scarb.toml"), Some("a-2"));
+        assert_eq!(layout.directory("/git/a/Scarb.toml"), Some("a-3"));
+    }
+
+    #[test]
+    fn should_keep_the_main_package_name_over_a_dependency() {
+        let layout = PackageLayout::new(&[
+            package(
+                PackageRole::Dependency,
+                "token",
+                "git+https://a.git",
+                "/git/token/Scarb.toml",
+            ),
+            package(
+                PackageRole::Main,
+                "token",
+                "path+file:///ws/token",
+                "/ws/token/Scarb.toml",
+            ),
+        ]);
+
+        assert_eq!(layout.directory("/ws/token/Scarb.toml"), Some("token"));
+        assert_eq!(layout.directory("/git/token/Scarb.toml"), Some("token-2"));
+    }
+}
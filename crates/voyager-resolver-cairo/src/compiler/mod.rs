@@ -16,6 +16,7 @@ use cairo_lang_diagnostics::ToOption;
 use cairo_lang_utils::Upcast;
 use petgraph::Graph;
 
+use crate::compiler::layout::PackageLayout;
 use crate::compiler::queries::collect_crate_module_files;
 use crate::model::{CairoAttachmentModule, CairoCrate, CairoModule, ModulePath};
 
@@ -25,8 +26,8 @@ use crate::utils::{
 };
 
 use crate::compiler::scarb_utils::{
-    generate_scarb_updated_files, get_contracts_to_verify, read_scarb_metadata,
-    update_crate_roots_from_metadata,
+    external_contract_modules, generate_scarb_updated_files, get_contracts_to_verify,
+    read_scarb_metadata, read_starknet_contract_target, update_crate_roots_from_metadata,
 };
 use crate::graph::{
     create_graph, get_required_module_for_contracts, EdgeWeight, _display_graphviz,
@@ -37,6 +38,7 @@ use scarb::flock::Filesystem;
 
 pub struct VoyagerGenerator;
 
+pub mod layout;
 pub mod queries;
 pub mod scarb_utils;
 
@@ -155,11 +157,26 @@ impl Compiler for VoyagerGenerator {
         }
 
         // Collect the CairoModule corresponding to the file paths of the contracts.
-        let modules_to_verify = project_modules
+        let mut modules_to_verify = project_modules
             .iter()
             .filter(|m| contracts_to_verify.contains(&m.relative_filepath))
             .collect::<Vec<_>>();
 
+        // Contracts of dependencies built along with the package must be uploaded too,
+        // otherwise the remote build doesn't produce the same artifacts.
+        let package_name = unit.main_component().package.id.name.to_string();
+        let target = read_starknet_contract_target(
+            &std::fs::read_to_string(&manifest_path)?,
+            &package_name,
+        )?
+        .unwrap_or_default();
+        for module in external_contract_modules(&project_modules, &target.build_external_contracts)
+        {
+            if !modules_to_verify.contains(&module) {
+                modules_to_verify.push(module);
+            }
+        }
+
         let external_packages = get_external_nonlocal_packages(metadata.clone());
 
         let (required_modules_paths, attachment_modules_data) =
@@ -175,6 +192,44 @@ impl Compiler for VoyagerGenerator {
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect::<HashMap<ModulePath, CairoAttachmentModule>>();
 
+        // Get the Cairo Modules corresponding to the required modules paths.
+        let required_modules = project_modules
+            .iter()
+            .filter(|m| {
+                let base_package_name = &m.path.0.split("::").collect::<Vec<&str>>()[0].trim();
+                required_modules_paths.contains(&m.path)
+                    && !external_packages.contains(&base_package_name.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        // Every package gets its own directory in the output, see `layout`.
+        let required_packages = required_modules
+            .iter()
+            .map(|m| m.path.get_crate())
+            .collect::<Vec<_>>();
+        let layout_packages = metadata
+            .packages
+            .iter()
+            .filter(|package| required_packages.contains(&package.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        let layout = PackageLayout::from_metadata(
+            &metadata,
+            &layout_packages,
+            manifest_path.to_str().unwrap_or_default(),
+        );
+        let crate_directories = unit
+            .components
+            .iter()
+            .filter_map(|component| {
+                let directory = layout.directory(component.package.manifest_path().as_str())?;
+                Some((
+                    component.cairo_package_name().to_string(),
+                    directory.to_owned(),
+                ))
+            })
+            .collect::<HashMap<String, String>>();
+
         let target_dir = Utf8PathBuf::from(
             manifest_path
                 .parent()
@@ -197,27 +252,23 @@ impl Compiler for VoyagerGenerator {
         // treat target dir as a Filesystem
         let target_dir = Filesystem::new(target_dir);
 
-        create_attachment_files(&attachment_modules_data, &target_dir)
+        create_attachment_files(&attachment_modules_data, &target_dir, &crate_directories)
             .with_context(|| "Failed to create attachment files")?;
 
-        // Get the Cairo Modules corresponding to the required modules paths.
-        let required_modules = project_modules
-            .iter()
-            .filter(|m| {
-                let base_package_name = &m.path.0.split("::").collect::<Vec<&str>>()[0].trim();
-                required_modules_paths.contains(&m.path)
-                    && !external_packages.contains(&base_package_name.to_string())
-            })
-            .collect::<Vec<_>>();
         // Copy these modules in the target directory.
         // Copy readme files and license files over too
-        copy_required_files(&required_modules, &target_dir, ws)?;
+        copy_required_files(&required_modules, &target_dir, ws, &crate_directories)?;
 
         // Generate each of the Scarb manifest files for the output directory.
         // The dependencies are updated to include the required modules as local dependencies.
-        generate_scarb_updated_files(metadata, &target_dir, required_modules, external_packages)?;
+        generate_scarb_updated_files(
+            metadata,
+            &target_dir,
+            required_modules,
+            external_packages,
+            &layout,
+        )?;
 
-        let package_name = unit.main_component().package.id.name.to_string();
         let generated_crate_dir = target_dir.path_existent().unwrap().join(package_name);
 
         // Problem with this step is that sometimes the build happens faster than the Scarb.toml is actually created and detected.
@@ -3,7 +3,7 @@ use itertools::Itertools;
 use scarb::flock::Filesystem;
 use scarb_metadata::Metadata;
 use serde::Deserialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -15,7 +15,13 @@ use cairo_lang_filesystem::ids::{CrateLongId, Directory};
 use cairo_lang_semantic::db::SemanticGroup;
 use scarb::core::Package;
 
-use crate::model::CairoModule;
+use crate::compiler::layout::PackageLayout;
+use crate::model::{CairoModule, ModulePath};
+
+pub use dyn_compiler::contract_target::StarknetContractTarget;
+
+/// Entry of `[tool.voyager]` holding the hooks run by the verifier CLI.
+pub const HOOKS_KEY: &str = "hooks";
 
 #[derive(Debug, Deserialize)]
 struct ScarbTomlRawPackageData {
@@ -29,6 +35,84 @@ struct ScarbTomlRawData {
     package: ScarbTomlRawPackageData,
 }
 
+/// Kind of the Scarb target building Starknet contracts.
+pub const STARKNET_CONTRACT_TARGET: &str = "starknet-contract";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawStarknetContractTarget {
+    name: Option<String>,
+    sierra: Option<bool>,
+    casm: Option<bool>,
+    #[serde(default)]
+    build_external_contracts: Vec<String>,
+}
+
+/// Reads the starknet-contract target of a manifest, `None` when the package
+/// has none. Scarb accepts the target as a table or as an array of tables.
+pub fn read_starknet_contract_target(
+    scarb_toml_content: &str,
+    package_name: &str,
+) -> Result<Option<StarknetContractTarget>> {
+    let manifest: toml::Value = toml::from_str(scarb_toml_content)?;
+    let Some(target) = manifest
+        .get("target")
+        .and_then(|targets| targets.get(STARKNET_CONTRACT_TARGET))
+    else {
+        return Ok(None);
+    };
+    let target = match target {
+        toml::Value::Array(targets) => match targets.as_slice() {
+            [] => return Ok(None),
+            [target] => target.clone(),
+            _ => {
+                return Err(anyhow!(
+                    "only one [[target.{}]] is supported",
+                    STARKNET_CONTRACT_TARGET
+                ))
+            }
+        },
+        target => target.clone(),
+    };
+    let raw: RawStarknetContractTarget = target
+        .try_into()
+        .with_context(|| format!("invalid [[target.{}]]", STARKNET_CONTRACT_TARGET))?;
+    let defaults = StarknetContractTarget::default();
+    Ok(Some(StarknetContractTarget {
+        name: raw.name.filter(|name| name != package_name),
+        sierra: raw.sierra.unwrap_or(defaults.sierra),
+        casm: raw.casm.unwrap_or(defaults.casm),
+        build_external_contracts: raw.build_external_contracts,
+    }))
+}
+
+/// The file modules declaring the contracts of `build-external-contracts`, so
+/// that they are part of the upload. A contract is declared either in a file
+/// module of its own path or inside its parent module, e.g. `dep::erc20::ERC20`
+/// in `dep::erc20`, and a glob covers every module under its prefix.
+pub fn external_contract_modules<'a>(
+    modules: &'a [CairoModule],
+    external_contracts: &[String],
+) -> Vec<&'a CairoModule> {
+    modules
+        .iter()
+        .filter(|module| {
+            let module_path = module.path.get_path();
+            external_contracts
+                .iter()
+                .any(|contract| match contract.strip_suffix("::*") {
+                    Some(prefix) => {
+                        module_path == prefix || module_path.starts_with(&format!("{prefix}::"))
+                    }
+                    None => {
+                        module_path == contract
+                            || ModulePath::new(contract).get_parent_path().get_path() == module_path
+                    }
+                })
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct AdditionalScarbManifestMetadata {
     pub name: String,
@@ -159,6 +243,7 @@ pub fn get_table_mut<'a>(doc: &'a mut Document, path: &[&str]) -> Result<&'a mut
 /// * `scarb_metadata` - A `scarb_metadata::Metadata` object containing information about Scarb packages.
 /// * `target_dir` - A `Filesystem` object representing the target directory where updated Scarb.toml files will be generated.
 /// * `required_modules` - A `Vec` of `CairoModule` objects representing the modules required by the compiler.
+/// * `layout` - The directory of each package in `target_dir`, see `layout`.
 ///
 /// # Errors
 ///
@@ -171,6 +256,7 @@ pub fn generate_scarb_updated_files(
     target_dir: &Filesystem,
     required_modules: Vec<&CairoModule>,
     external_packages: Vec<String>,
+    layout: &PackageLayout,
 ) -> Result<()> {
     let mut metadata = scarb_metadata.clone();
     let required_packages = required_modules
@@ -185,14 +271,18 @@ pub fn generate_scarb_updated_files(
         .packages
         .retain(|package| required_packages.contains(&package.name));
 
-    for package in metadata.packages.clone() {
-        let manifest_path = package.manifest_path;
-        let target_path = target_dir.path_existent()?.join(package.name);
+    for package in metadata.packages.iter() {
+        let manifest_path = &package.manifest_path;
+        let directory = layout
+            .directory(manifest_path.as_str())
+            .unwrap_or(&package.name);
+        let target_path = target_dir.path_existent()?.join(directory);
         generate_updated_scarb_toml(
             manifest_path.clone().into_std_path_buf(),
             target_path.as_std_path(),
             &required_packages,
             &external_packages,
+            &layout.dependency_directories(package, &metadata.packages),
         )?;
     }
 
@@ -207,6 +297,8 @@ pub fn generate_scarb_updated_files(
  * * `manifest_path` - A `PathBuf` of the Scarb.toml file to be updated.
  * * `target_path` - A `Path` to the target directory for the updated Scarb.toml file.
  * * `required_packages` - A `Vec` of `String`s representing the names of the packages required by the compiler.
+ * * `dependency_directories` - The directory in the output of each dependency, by name. Dependencies
+ *   left out point to the directory named after them.
  *
  * # Errors
  *
@@ -223,6 +315,7 @@ pub fn generate_updated_scarb_toml(
     target_path: &Path,
     required_packages: &[String],
     external_packages: &[String],
+    dependency_directories: &HashMap<String, String>,
 ) -> Result<()> {
     let manifest_path = fs::canonicalize(manifest_path)?;
     let original_raw_manifest = fs::read_to_string(&manifest_path)?;
@@ -263,7 +356,14 @@ pub fn generate_updated_scarb_toml(
         }
 
         let mut new_table = InlineTable::new();
-        new_table.insert("path", Value::String(Formatted::new(format!("../{}", k))));
+        let directory = dependency_directories
+            .get(*k)
+            .map(String::as_str)
+            .unwrap_or(*k);
+        new_table.insert(
+            "path",
+            Value::String(Formatted::new(format!("../{}", directory))),
+        );
         tab.as_table_like_mut()
             .unwrap()
             .insert(k, Item::Value(Value::InlineTable(new_table)));
@@ -282,7 +382,7 @@ pub fn generate_updated_scarb_toml(
 }
 
 /// This function retrieves the relative path of the contracts that need to be verified from a
-/// package's tool metadata.
+/// package's tool metadata. The `hooks` entry configures the verifier and is not a contract.
 ///
 /// # Arguments
 ///
@@ -306,8 +406,9 @@ pub fn get_contracts_to_verify(package: &Package) -> Result<Vec<PathBuf>> {
     let table_values = verify_metadata
         .as_table()
         .ok_or_else(|| anyhow!("verify metadata is not a table"))?
-        .values()
-        .map(|v| PathBuf::from(v.get("path").unwrap().as_str().unwrap()))
+        .iter()
+        .filter(|(name, _)| name.as_str() != HOOKS_KEY)
+        .map(|(_, v)| PathBuf::from(v.get("path").unwrap().as_str().unwrap()))
         .collect::<Vec<_>>();
 
     Ok(table_values)
@@ -364,4 +465,87 @@ mod tests {
         assert_eq!(data.license_file, "");
         assert_eq!(data.readme, "README.md");
     }
+
+    #[test]
+    fn should_read_the_starknet_contract_target() {
+        let scarb_toml_content = r#"
+        [package]
+        name = "vault"
+        version = "0.1.0"
+
+        [[target.starknet-contract]]
+        name = "my_vault"
+        casm = true
+        build-external-contracts = ["external_token::token::Token", "external_token::tokens::*"]
+        "#;
+
+        let target = read_starknet_contract_target(scarb_toml_content, "vault")
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.name.as_deref(), Some("my_vault"));
+        assert!(target.sierra && target.casm);
+        assert_eq!(target.external_contract_packages(), vec!["external_token"]);
+        assert!(!target.is_default());
+
+        // A target named after the package is the default name
+        let scarb_toml_content = r#"
+        [package]
+        name = "vault"
+
+        [target.starknet-contract]
+        name = "vault"
+        "#;
+        let target = read_starknet_contract_target(scarb_toml_content, "vault")
+            .unwrap()
+            .unwrap();
+        assert!(target.is_default());
+
+        let scarb_toml_content = r#"
+        [package]
+        name = "vault"
+        "#;
+        assert_eq!(
+            read_starknet_contract_target(scarb_toml_content, "vault").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn should_find_the_modules_of_external_contracts() {
+        let module = |path: &str| CairoModule {
+            dir: PathBuf::new(),
+            main_file: PathBuf::new(),
+            path: ModulePath::new(path),
+            filepath: PathBuf::new(),
+            relative_filepath: PathBuf::new(),
+            submodules: vec![],
+            imports: HashSet::new(),
+        };
+        let modules = vec![
+            module("external_token"),
+            module("external_token::token"),
+            module("external_token::tokens"),
+            module("external_token::tokens::wrapped"),
+            module("external_token::utils"),
+        ];
+
+        let found = |contracts: &[&str]| {
+            external_contract_modules(
+                &modules,
+                &contracts.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            )
+            .iter()
+            .map(|module| module.path.get_path().clone())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            found(&["external_token::token::Token"]),
+            vec!["external_token::token"]
+        );
+        assert_eq!(
+            found(&["external_token::tokens::*"]),
+            vec!["external_token::tokens", "external_token::tokens::wrapped"]
+        );
+        assert!(found(&["other::token::Token"]).is_empty());
+    }
 }
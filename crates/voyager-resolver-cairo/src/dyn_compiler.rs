@@ -2,7 +2,9 @@ use std::env;
 
 use anyhow::Result;
 use camino::Utf8PathBuf;
-use dyn_compiler::dyn_compiler::{DynamicCompiler, SupportedCairoVersions, SupportedScarbVersions};
+use dyn_compiler::dyn_compiler::{
+    DynamicCompiler, ResolverInfo, SupportedCairoVersions, SupportedScarbVersions,
+};
 use itertools::Itertools;
 use scarb::{
     compiler::CompilerRepository,
@@ -17,12 +19,23 @@ use crate::{
 
 pub struct VoyagerGeneratorWrapper;
 
+/// What `VoyagerGeneratorWrapper` supports, see `ResolverInfo`: the versions
+/// it is built with, cairo-lang 2.8.4 and the Scarb of the same release. The
+/// wider `ALL` lists are only for detecting a project's versions.
+pub const RESOLVER_INFO: ResolverInfo = ResolverInfo {
+    name: "voyager-resolver-cairo",
+    scarb_versions: &[SupportedScarbVersions::V2_8_4],
+    cairo_versions: &[SupportedCairoVersions::V2_8_4],
+    // The resolver has no optional features so far
+    features: &[],
+};
+
 impl DynamicCompiler for VoyagerGeneratorWrapper {
     fn get_supported_scarb_versions(&self) -> Vec<SupportedScarbVersions> {
-        vec![SupportedScarbVersions::V2_8_4]
+        RESOLVER_INFO.scarb_versions.to_vec()
     }
     fn get_supported_cairo_versions(&self) -> Vec<SupportedCairoVersions> {
-        vec![SupportedCairoVersions::V2_8_4]
+        RESOLVER_INFO.cairo_versions.to_vec()
     }
 
     fn get_contracts_to_verify_path(&self, project_path: &Utf8PathBuf) -> Result<Vec<Utf8PathBuf>> {
@@ -51,13 +64,13 @@ impl DynamicCompiler for VoyagerGeneratorWrapper {
             .collect_vec())
     }
 
-    fn compile_project(&self, project_path: &Utf8PathBuf) -> Result<()> {
+    fn compile_project(&self, project_path: &Utf8PathBuf) -> Result<Vec<Utf8PathBuf>> {
         let manifest_path = project_path.join("Scarb.toml");
 
         let mut compilers = CompilerRepository::empty();
         compilers.add(Box::new(VoyagerGenerator)).unwrap();
 
-        let config = Config::builder(manifest_path)
+        let config = Config::builder(manifest_path.clone())
             .ui_verbosity(scarb_ui::Verbosity::Verbose)
             .log_filter_directive(env::var_os("SCARB_LOG"))
             .compilers(compilers)
@@ -66,17 +79,9 @@ impl DynamicCompiler for VoyagerGeneratorWrapper {
 
         let ws = ops::read_workspace(config.manifest_path(), &config).unwrap();
         let package_ids = ws.members().map(|p| p.id).collect();
-        let compile_opts = ops::CompileOpts {
-            include_target_kinds: vec![TargetKind::STARKNET_CONTRACT],
-            exclude_target_kinds: Vec::new(),
-            include_target_names: Vec::new(),
-            features: ops::FeaturesOpts {
-                features: ops::FeaturesSelector::AllFeatures,
-                no_default_features: true,
-            },
-        };
-
-        ops::compile(package_ids, compile_opts, &ws)
+        ops::compile(package_ids, contract_compile_opts(), &ws)?;
+
+        build_contract_classes(&manifest_path)
     }
 
     fn compile_file(&self, file_path: &Utf8PathBuf) -> Result<()> {
@@ -86,3 +91,43 @@ impl DynamicCompiler for VoyagerGeneratorWrapper {
         run_starknet_compile(file_path.as_str())
     }
 }
+
+fn contract_compile_opts() -> ops::CompileOpts {
+    ops::CompileOpts {
+        include_target_kinds: vec![TargetKind::STARKNET_CONTRACT],
+        exclude_target_kinds: Vec::new(),
+        include_target_names: Vec::new(),
+        features: ops::FeaturesOpts {
+            features: ops::FeaturesSelector::AllFeatures,
+            no_default_features: true,
+        },
+    }
+}
+
+/// Suffix of the Sierra contract classes Scarb writes in the target directory.
+const CONTRACT_CLASS_SUFFIX: &str = ".contract_class.json";
+
+/// Build the package like `scarb build` does, so that the classes the sources
+/// compile to can be compared with the declared one, and list the contract
+/// classes written in the target directory of the profile.
+fn build_contract_classes(manifest_path: &Utf8PathBuf) -> Result<Vec<Utf8PathBuf>> {
+    let config = Config::builder(manifest_path.clone())
+        .log_filter_directive(env::var_os("SCARB_LOG"))
+        .compilers(CompilerRepository::std())
+        .build()?;
+
+    let ws = ops::read_workspace(config.manifest_path(), &config)?;
+    let package_ids = ws.members().map(|p| p.id).collect();
+    ops::compile(package_ids, contract_compile_opts(), &ws)?;
+
+    let profile_dir = ws
+        .target_dir()
+        .path_unchecked()
+        .join(config.profile().as_str());
+    let mut artifacts = std::fs::read_dir(&profile_dir)?
+        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.ok()?.path()).ok())
+        .filter(|path| path.as_str().ends_with(CONTRACT_CLASS_SUFFIX))
+        .collect_vec();
+    artifacts.sort();
+    Ok(artifacts)
+}
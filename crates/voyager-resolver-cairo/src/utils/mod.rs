@@ -1,5 +1,6 @@
 use anyhow::Result;
 
+use crate::compiler::layout::crate_directory;
 use crate::compiler::scarb_utils::read_additional_scarb_manifest_metadata;
 use crate::model::{CairoAttachmentModule, CairoImport, CairoModule, ModulePath};
 
@@ -84,9 +85,11 @@ pub fn get_import_remaps(modules_to_verify: Vec<&CairoModule>) -> Vec<CairoImpor
 ///
 /// * `declaration_modules` - A `HashMap<String, HashSet<String>>` containing the parent modules and the forward declarations of child modules.
 /// * `target_dir` - The directory in which to generate the .cairo files.
+/// * `crate_directories` - The directory of each crate in `target_dir`, see `layout`.
 pub fn create_attachment_files(
     attachment_modules: &HashMap<ModulePath, CairoAttachmentModule>,
     target_dir: &Filesystem,
+    crate_directories: &HashMap<String, String>,
 ) -> Result<()> {
     for (parent_module, attachment_module) in attachment_modules {
         let child_modules = &attachment_module.children;
@@ -94,7 +97,9 @@ pub fn create_attachment_files(
         let path_split = parent_module.0.split("::");
         let crate_name = parent_module.get_crate();
         let target_path = target_dir.path_existent()?;
-        let source_path = target_path.join(crate_name).join("src");
+        let source_path = target_path
+            .join(crate_directory(crate_directories, &crate_name))
+            .join("src");
         filename = match path_split.clone().count() {
             1 => "lib.cairo".to_string(),
             _ => {
@@ -132,6 +137,7 @@ pub fn create_attachment_files(
 /// * `required_modules` - A vector containing references to the CairoModule instances that need to be copied.
 /// * `target_dir` - A reference to the target directory where the files should be copied.
 /// * `ws` - A reference to the Workspace.
+/// * `crate_directories` - The directory of each crate in `target_dir`, see `layout`.
 ///
 /// # Errors
 ///
@@ -141,6 +147,7 @@ pub fn copy_required_files(
     required_modules: &Vec<&CairoModule>,
     target_dir: &Filesystem,
     ws: &Workspace,
+    crate_directories: &HashMap<String, String>,
 ) -> Result<()> {
     let root_path = ws.root();
     let mut root_parts = root_path.components().peekable();
@@ -156,6 +163,7 @@ pub fn copy_required_files(
     // Copy each required module's .cairo file to the target directory
     for module in required_modules {
         let crate_name = module.path.get_crate();
+        let crate_dir = crate_directory(crate_directories, &crate_name);
         let filepath = Path::new(&module.filepath);
         let mut filepath_parts = filepath.components().peekable();
 
@@ -170,7 +178,7 @@ pub fn copy_required_files(
         let root_dir = module.get_root_dir()?;
         let filepath_relative = filepath.strip_prefix(root_dir.clone())?;
         let dest_path = Path::new(target_path)
-            .join(crate_name.clone())
+            .join(crate_dir)
             .join(filepath_relative);
 
         let manifest_path = root_dir.clone().join("Scarb.toml");
@@ -190,7 +198,7 @@ pub fn copy_required_files(
         // Attempt to copy the readme and license files to the target directory
         let root_dir_clone = root_dir.clone();
         let base_source_root_path = Path::new(&root_dir_clone);
-        let base_dest_root_path = Path::new(target_path).join(crate_name);
+        let base_dest_root_path = Path::new(target_path).join(crate_dir);
 
         let readme_source_path = base_source_root_path.join(&additional_metadata.readme);
         let readme_dest_path = base_dest_root_path.join(&additional_metadata.readme);
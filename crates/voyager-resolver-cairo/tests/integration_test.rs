@@ -7,7 +7,9 @@ use scarb::ops;
 use scarb_ui::Verbosity;
 use std::path::PathBuf;
 
-use voyager_resolver_cairo::compiler::scarb_utils::get_contracts_to_verify;
+use voyager_resolver_cairo::compiler::scarb_utils::{
+    get_contracts_to_verify, read_starknet_contract_target,
+};
 use voyager_resolver_cairo::compiler::VoyagerGenerator;
 use voyager_resolver_cairo::utils::run_scarb_build;
 
@@ -273,3 +275,56 @@ fn test_project_with_external_import_resolved() -> Result<()> {
     run_scarb_build(reduced_project_path.to_str().unwrap()).unwrap();
     Ok(())
 }
+
+#[test]
+fn test_project_with_external_contract_target() -> Result<()> {
+    let source_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_data")
+        .join("external_contract_target");
+    let mut compilers: CompilerRepository = CompilerRepository::empty();
+    compilers.add(Box::new(VoyagerGenerator)).unwrap();
+
+    let manifest_path = source_dir.join("Scarb.toml");
+
+    let config = Config::builder(manifest_path.to_str().unwrap())
+        .ui_verbosity(Verbosity::Verbose)
+        .log_filter_directive(env::var_os("SCARB_LOG"))
+        .compilers(compilers)
+        .build()
+        .unwrap();
+
+    let ws = ops::read_workspace(config.manifest_path(), &config).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+    let package_ids = ws.members().map(|p| p.id).collect();
+    let compile_opts = ops::CompileOpts {
+        include_target_kinds: vec![TargetKind::STARKNET_CONTRACT],
+        exclude_target_kinds: Vec::new(),
+        include_target_names: Vec::new(),
+        features: ops::FeaturesOpts {
+            features: ops::FeaturesSelector::AllFeatures,
+            no_default_features: true,
+        },
+    };
+
+    ops::compile(package_ids, compile_opts, &ws).unwrap();
+
+    // The external contract isn't used by the verified contract, it is only
+    // uploaded because the target builds it
+    let output_dir = source_dir.join("voyager-verify");
+    assert!(output_dir.join("external_token/src/token.cairo").exists());
+
+    let reduced_project_path = output_dir.join("external_contract_target");
+    let manifest = std::fs::read_to_string(reduced_project_path.join("Scarb.toml"))?;
+    let target = read_starknet_contract_target(&manifest, "external_contract_target")?
+        .ok_or_else(|| anyhow!("the generated manifest lost the starknet-contract target"))?;
+    assert_eq!(target.name.as_deref(), Some("vault"));
+    assert_eq!(
+        target.build_external_contracts,
+        vec!["external_token::token::Token"]
+    );
+
+    run_scarb_build(reduced_project_path.to_str().unwrap()).unwrap();
+    Ok(())
+}
@@ -0,0 +1,2 @@
+mod vault;
+pub use external_token::token::Token;
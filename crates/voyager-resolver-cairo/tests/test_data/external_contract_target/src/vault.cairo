@@ -0,0 +1,15 @@
+#[starknet::contract]
+mod Vault {
+    use starknet::ContractAddress;
+    use starknet::storage::StoragePointerReadAccess;
+
+    #[storage]
+    struct Storage {
+        token: ContractAddress,
+    }
+
+    #[external(v0)]
+    fn token(self: @ContractState) -> ContractAddress {
+        self.token.read()
+    }
+}
@@ -0,0 +1,14 @@
+#[starknet::contract]
+pub mod Token {
+    use starknet::storage::StoragePointerReadAccess;
+
+    #[storage]
+    struct Storage {
+        supply: felt252,
+    }
+
+    #[external(v0)]
+    fn total_supply(self: @ContractState) -> felt252 {
+        self.supply.read()
+    }
+}